@@ -0,0 +1,208 @@
+//! Parser and encoder throughput.
+//!
+//! Plain `std::time` harness so it runs without extra dependencies:
+//!
+//! ```bash
+//! cargo bench --bench throughput
+//! cargo bench --bench throughput --no-default-features
+//! ```
+//!
+//! Measures parse throughput of a mixed packet stream, the same stream
+//! with garbage making up half the bytes, and encode latency for every
+//! packet kind. To check a change for regressions, record a baseline on
+//! the old code and compare against it on the new:
+//!
+//! ```bash
+//! git stash && cargo bench --bench throughput -- --save-baseline main && git stash pop
+//! cargo bench --bench throughput -- --baseline main
+//! ```
+//!
+//! Baselines are kept in `target/bench-baselines/<name>.txt`. Short encode
+//! timings still move by 10-20% between runs, so look for changes that
+//! repeat across runs and across related packet kinds.
+
+use std::collections::BTreeMap;
+use std::hint::black_box;
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+
+use liban::test_support::{Rng, random_packet};
+use liban::wire::{self, EncodedFrame, PacketId};
+use liban::{AnppParser, Packet, PacketKind, Request};
+
+const ITERATIONS: usize = 2_000;
+const ENCODE_ITERATIONS: usize = 20_000;
+/// Encode timings keep the fastest of this many rounds, which is far less
+/// sensitive to scheduling and frequency scaling than the mean
+const ROUNDS: usize = 5;
+
+/// SystemState at 100 Hz interleaved with Status and a few small packets
+fn mixed_stream() -> Vec<u8> {
+    let mut stream = Vec::new();
+    for i in 0..100u8 {
+        stream.extend(wire::encode_frame(PacketId::new(20), &[i; 100]).unwrap());
+        stream.extend(wire::encode_frame(PacketId::new(23), &[0, 0, 0x07, 0]).unwrap());
+        if i % 10 == 0 {
+            stream.extend(wire::encode_frame(PacketId::new(21), &[i; 8]).unwrap());
+            stream.extend(wire::encode_frame(PacketId::new(1), &[20, 23]).unwrap());
+        }
+    }
+    stream
+}
+
+/// The mixed stream with a run of random bytes as long as each frame in
+/// front of it, so half the input is garbage the parser has to resync over
+fn garbage_stream() -> Vec<u8> {
+    let mut rng = Rng::new(0x5eed);
+    let clean = mixed_stream();
+    let mut parser = AnppParser::new();
+    let mut stream = Vec::with_capacity(clean.len() * 2);
+    let mut next = parser.consume(&clean);
+    while let Some(packet) = next {
+        let frame = packet.to_bytes().unwrap_or_else(|_| {
+            wire::encode_frame(PacketId::new(packet.packet_id()), &wire::encode_payload(&packet).unwrap()).unwrap()
+        });
+        let mut garbage = vec![0; frame.len()];
+        rng.fill(&mut garbage);
+        stream.extend(garbage);
+        stream.extend(frame);
+        next = parser.consume(&[]);
+    }
+    stream
+}
+
+/// Results by name, in the unit each benchmark reports
+#[derive(Default)]
+struct Results(BTreeMap<String, f64>);
+
+impl Results {
+    /// Throughput benchmarks, higher is better
+    fn throughput(&mut self, name: &str, elapsed: Duration, bytes: usize, items: usize) {
+        let secs = elapsed.as_secs_f64();
+        let mb_per_sec = bytes as f64 / secs / 1e6;
+        println!("{:<36} {:>10.1} MB/s {:>12.0} packets/s", name, mb_per_sec, items as f64 / secs);
+        self.0.insert(format!("{name} MB/s"), mb_per_sec);
+    }
+
+    /// Latency benchmarks, lower is better
+    fn latency(&mut self, name: &str, elapsed: Duration, items: usize) {
+        let nanos = elapsed.as_nanos() as f64 / items as f64;
+        println!("{:<36} {:>10.0} ns/packet", name, nanos);
+        self.0.insert(format!("{name} ns"), nanos);
+    }
+
+    fn save(&self, path: &PathBuf) {
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        let lines: String = self.0.iter().map(|(name, value)| format!("{value}\t{name}\n")).collect();
+        std::fs::write(path, lines).unwrap();
+        println!("\nSaved baseline {}", path.display());
+    }
+
+    fn compare(&self, path: &PathBuf) {
+        let Ok(saved) = std::fs::read_to_string(path) else {
+            eprintln!("\nNo baseline at {}", path.display());
+            return;
+        };
+        println!("\nChange against {}", path.display());
+        for line in saved.lines() {
+            let Some((value, name)) = line.split_once('\t') else { continue };
+            let (Ok(before), Some(now)) = (value.parse::<f64>(), self.0.get(name)) else { continue };
+            // Positive is always an improvement
+            let change = if name.ends_with(" ns") { before / now - 1.0 } else { now / before - 1.0 };
+            println!("{:<44} {:>+8.1}%", name, change * 100.0);
+        }
+    }
+}
+
+fn parse_all(stream: &[u8]) -> usize {
+    let mut parser = AnppParser::new();
+    let mut packets = 0;
+    let mut next = parser.consume(black_box(stream));
+    while let Some(packet) = next {
+        black_box(packet);
+        packets += 1;
+        next = parser.consume(&[]);
+    }
+    packets
+}
+
+fn bench_parse(results: &mut Results, name: &str, stream: &[u8]) {
+    let mut packets = 0;
+    let start = Instant::now();
+    for _ in 0..ITERATIONS {
+        packets += parse_all(stream);
+    }
+    results.throughput(name, start.elapsed(), stream.len() * ITERATIONS, packets);
+}
+
+fn bench_encode(results: &mut Results) {
+    let packet = Packet::Request(Request::multiple([PacketKind::SystemState, PacketKind::Status]));
+    let mut bytes = 0;
+    let start = Instant::now();
+    for _ in 0..ENCODE_ITERATIONS * 10 {
+        bytes += black_box(&packet).to_bytes().unwrap().len();
+    }
+    results.throughput("encode request", start.elapsed(), bytes, ENCODE_ITERATIONS * 10);
+
+    let elapsed = fastest(|| {
+        for _ in 0..ENCODE_ITERATIONS {
+            black_box(EncodedFrame::encode(black_box(&packet)).unwrap());
+        }
+    });
+    results.latency("encode request, split frame", elapsed, ENCODE_ITERATIONS);
+}
+
+/// Payload serialization plus framing for one random instance of each kind
+fn bench_encode_kinds(results: &mut Results) {
+    let mut rng = Rng::new(1);
+    for kind in PacketKind::all().filter(|kind| *kind != PacketKind::Unsupported) {
+        let packet = random_packet(kind, &mut rng);
+        let id = PacketId::new(kind.packet_id());
+        let elapsed = fastest(|| {
+            for _ in 0..ENCODE_ITERATIONS {
+                let payload = wire::encode_payload(black_box(&packet)).unwrap();
+                black_box(wire::encode_frame(id, &payload).unwrap());
+            }
+        });
+        results.latency(&format!("encode {kind:?}"), elapsed, ENCODE_ITERATIONS);
+    }
+}
+
+fn fastest(mut round: impl FnMut()) -> Duration {
+    (0..ROUNDS)
+        .map(|_| {
+            let start = Instant::now();
+            round();
+            start.elapsed()
+        })
+        .min()
+        .unwrap()
+}
+
+fn baseline_path(name: &str) -> PathBuf {
+    let target = std::env::var_os("CARGO_TARGET_DIR").map_or_else(|| PathBuf::from("target"), PathBuf::from);
+    target.join("bench-baselines").join(format!("{name}.txt"))
+}
+
+fn main() {
+    // cargo passes --bench; anything not recognised is ignored
+    let args: Vec<String> = std::env::args().collect();
+    let option = |flag: &str| args.iter().position(|arg| arg == flag).and_then(|i| args.get(i + 1)).map(|name| baseline_path(name));
+
+    let clean = mixed_stream();
+    let garbage = garbage_stream();
+    assert_eq!(parse_all(&garbage), parse_all(&clean), "garbage stream lost packets");
+
+    let mut results = Results::default();
+    bench_parse(&mut results, "parse mixed stream", &clean);
+    bench_parse(&mut results, "parse 50% garbage", &garbage);
+    bench_encode(&mut results);
+    bench_encode_kinds(&mut results);
+
+    if let Some(path) = option("--save-baseline") {
+        results.save(&path);
+    }
+    if let Some(path) = option("--baseline") {
+        results.compare(&path);
+    }
+}
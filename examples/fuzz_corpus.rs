@@ -0,0 +1,29 @@
+//! Write the seed corpus for the `parse_frames` fuzz target.
+//!
+//! ```bash
+//! cargo run --example fuzz_corpus --features fuzzing -- fuzz/corpus/parse_frames
+//! ```
+
+use std::fs;
+use std::path::PathBuf;
+
+use clap::Parser;
+
+#[derive(Parser)]
+struct Args {
+    /// Corpus directory
+    #[arg(default_value = "fuzz/corpus/parse_frames")]
+    dir: PathBuf,
+}
+
+fn main() -> std::io::Result<()> {
+    let args = Args::parse();
+    fs::create_dir_all(&args.dir)?;
+
+    let seeds = liban::fuzzing::seed_frames();
+    for (kind, frame) in &seeds {
+        fs::write(args.dir.join(format!("{:03}-{:?}", kind.packet_id(), kind)), frame)?;
+    }
+    println!("wrote {} seeds to {}", seeds.len(), args.dir.display());
+    Ok(())
+}
@@ -64,9 +64,9 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
                     println!("   Down:  {:.2} m/s", p.velocity_down);
 
                     println!("\nORIENTATION:");
-                    println!("   Roll:    {:.2}°", p.roll.to_degrees());
-                    println!("   Pitch:   {:.2}°", p.pitch.to_degrees());
-                    println!("   Heading: {:.2}°", p.heading.to_degrees());
+                    println!("   Roll:    {:.2}°", p.roll.to_degrees().get());
+                    println!("   Pitch:   {:.2}°", p.pitch.to_degrees().get());
+                    println!("   Heading: {:.2}°", p.heading.to_degrees().get());
 
                     println!("\nGNSS STATUS:");
                     println!("   Fix Type: {:?}", p.filter_status.gnss_fix_type());
@@ -176,9 +176,9 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
                     println!("EulerOrientationStdDev");
                     println!("━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━");
                     println!("ORIENTATION UNCERTAINTY:");
-                    println!("   Roll:    {:.4}°", p.roll_std_dev.to_degrees());
-                    println!("   Pitch:   {:.4}°", p.pitch_std_dev.to_degrees());
-                    println!("   Heading: {:.4}°", p.heading_std_dev.to_degrees());
+                    println!("   Roll:    {:.4}°", p.roll_std_dev.to_degrees().get());
+                    println!("   Pitch:   {:.4}°", p.pitch_std_dev.to_degrees().get());
+                    println!("   Heading: {:.4}°", p.heading_std_dev.to_degrees().get());
                 }
 
                 liban::Packet::Heave(p) => {
@@ -196,8 +196,8 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
                     println!("EXTERNAL TIME: {}s + {}μs", p.unix_time_seconds, p.microseconds);
                 }
 
-                liban::Packet::Unsupported(_) => {
-                    println!("Unsupported packet type");
+                liban::Packet::Unsupported { id, .. } => {
+                    println!("Unsupported packet type {}", id);
                 }
 
                 _ => {
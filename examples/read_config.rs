@@ -18,6 +18,7 @@ struct Args {
     port: u16,
 }
 
+#[allow(clippy::collapsible_match)]
 fn main() -> Result<(), Box<dyn std::error::Error>> {
     let args = Args::parse();
 
@@ -45,9 +46,7 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
 
     println!("Requesting configurations...");
     for (name, packet_kind) in &configs_to_request {
-        let request = Request {
-            requested_packet: *packet_kind,
-        };
+        let request = Request::single(*packet_kind);
         let packet = Packet::Request(request);
         let bytes = packet.to_bytes()?;
         stream.write_all(&bytes)?;
@@ -159,26 +158,10 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
                         println!("━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━");
                         println!("  Permanent:   {}", ip_config.permanent);
                         println!("  DHCP Mode:   {}", ip_config.dhcp_mode);
-                        println!("  IP Address:  {}.{}.{}.{}",
-                                 (ip_config.ip_address >> 24) & 0xFF,
-                                 (ip_config.ip_address >> 16) & 0xFF,
-                                 (ip_config.ip_address >> 8) & 0xFF,
-                                 ip_config.ip_address & 0xFF);
-                        println!("  Netmask:     {}.{}.{}.{}",
-                                 (ip_config.ip_netmask >> 24) & 0xFF,
-                                 (ip_config.ip_netmask >> 16) & 0xFF,
-                                 (ip_config.ip_netmask >> 8) & 0xFF,
-                                 ip_config.ip_netmask & 0xFF);
-                        println!("  Gateway:     {}.{}.{}.{}",
-                                 (ip_config.ip_gateway >> 24) & 0xFF,
-                                 (ip_config.ip_gateway >> 16) & 0xFF,
-                                 (ip_config.ip_gateway >> 8) & 0xFF,
-                                 ip_config.ip_gateway & 0xFF);
-                        println!("  DNS Server:  {}.{}.{}.{}",
-                                 (ip_config.dns_server >> 24) & 0xFF,
-                                 (ip_config.dns_server >> 16) & 0xFF,
-                                 (ip_config.dns_server >> 8) & 0xFF,
-                                 ip_config.dns_server & 0xFF);
+                        println!("  IP Address:  {}", ip_config.ip_address);
+                        println!("  Netmask:     {}", ip_config.ip_netmask);
+                        println!("  Gateway:     {}", ip_config.ip_gateway);
+                        println!("  DNS Server:  {}", ip_config.dns_server);
                         println!();
                     }
                 }
@@ -204,7 +187,7 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
                         println!("  Clear Existing: {}", periods.clear_existing);
                         println!("  Configured Packets:");
                         for period in &periods.packet_periods {
-                            println!("    {:?}: {:?}", period.packet_type, period.period);
+                            println!("    {:?}: every {} timer periods", period.packet_type, period.divisor);
                         }
                         println!();
                     }
@@ -247,13 +230,8 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
                         println!("━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━");
                         for (i, dataport) in dataports.dataports.iter().enumerate() {
                             println!("  Dataport {}:", i);
-                            println!("    IP:   {}.{}.{}.{}",
-                                     (dataport.ip_address >> 24) & 0xFF,
-                                     (dataport.ip_address >> 16) & 0xFF,
-                                     (dataport.ip_address >> 8) & 0xFF,
-                                     dataport.ip_address & 0xFF);
-                            println!("    Port: {}", dataport.port);
-                            println!("    Mode: {:?}", dataport.mode);
+                            println!("    Address: {}", dataport.address);
+                            println!("    Mode:    {:?}", dataport.mode);
                         }
                         println!();
                     }
@@ -2,7 +2,6 @@ use liban::{Packet, PacketsPeriod, PacketPeriod, FilterOptions, VehicleType, Req
 use clap::Parser as ClapParser;
 use std::io::Write;
 use std::net::TcpStream;
-use std::time::Duration;
 
 #[derive(ClapParser)]
 #[command(name = "send_config")]
@@ -26,7 +25,7 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
     let mut stream = TcpStream::connect(format!("{}:{}", args.ip, args.port))?;
     println!("Connected!\n");
 
-    // Example 1: Configure packet periods using Duration
+    // Example 1: Configure packet periods in packet timer periods (1 ms by default)
     println!("1. Configuring packet periods...");
     let packets_period = PacketsPeriod {
         permanent: false,
@@ -34,15 +33,15 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
         packet_periods: vec![
             PacketPeriod {
                 packet_type: liban::PacketKind::SystemState,
-                period: Duration::from_millis(100),  // 100ms = 10Hz
+                divisor: 100,  // 100ms = 10Hz
             },
             PacketPeriod {
                 packet_type: liban::PacketKind::Status,
-                period: Duration::from_millis(1000),  // 1s = 1Hz
+                divisor: 1000,  // 1s = 1Hz
             },
             PacketPeriod {
                 packet_type: liban::PacketKind::Satellites,
-                period: Duration::from_millis(5000),  // 5s
+                divisor: 5000,  // 5s
             },
         ],
     };
@@ -58,15 +57,11 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
 
     // Example 2: Configure filter options
     println!("2. Configuring filter options...");
-    let filter_opts = FilterOptions {
-        permanent: false,
-        vehicle_type: VehicleType::Boat,
-        internal_gnss_enabled: true,
-        atmospheric_altitude_enabled: true,
-        velocity_heading_enabled: true,
-        reversing_detection_enabled: false,
-        motion_analysis_enabled: true,
-    };
+    let filter_opts = FilterOptions::builder()
+        .vehicle_type(VehicleType::Boat)
+        .velocity_heading(true)
+        .motion_analysis(true)
+        .build()?;
 
     let packet = Packet::FilterOptions(filter_opts);
     let bytes = packet.to_bytes()?;
@@ -80,9 +75,7 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
 
     // Example 3: Request device information
     println!("3. Requesting device information...");
-    let request = Request {
-        requested_packet: PacketKind::DeviceInformation,
-    };
+    let request = Request::single(PacketKind::DeviceInformation);
 
     let packet = Packet::Request(request);
     let bytes = packet.to_bytes()?;
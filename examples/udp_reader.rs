@@ -37,7 +37,7 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
                                 s.latitude.to_degrees(),
                                 s.longitude.to_degrees(),
                                 s.height,
-                                s.heading.to_degrees());
+                                s.heading.to_degrees().get());
                     }
                     Packet::UnixTime(t) => {
                         println!("#{packet_count} [{src}] UnixTime: {}s + {}us",
@@ -55,8 +55,8 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
                         println!("#{packet_count} [{src}] Ack: {:?} -> {:?}",
                                 ack.acknowledged_packet, ack.result);
                     }
-                    Packet::Unsupported(_) => {
-                        println!("#{packet_count} [{src}] Unsupported");
+                    Packet::Unsupported { id, .. } => {
+                        println!("#{packet_count} [{src}] Unsupported ID {id}");
                     }
                     other => {
                         println!("#{packet_count} [{src}] {:?}", other);
@@ -0,0 +1,530 @@
+use std::time::{Duration, Instant};
+
+#[cfg(feature = "serde")]
+use serde::{Serialize, Deserialize};
+
+use crate::clock::Clock;
+use crate::packet::Packet;
+use crate::packet::state::{FilterStatus, GnssFixType, Status, SystemState, SystemStatus};
+use crate::units::Length;
+
+/// How serious an alarm is, ordered from least to most severe
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum Severity {
+    Info,
+    Warning,
+    Critical,
+}
+
+macro_rules! define_alarms {
+    (
+        system { $( $sys:ident => $sys_fn:ident, $sys_sev:ident, $sys_code:literal ),+ $(,)? }
+        filter { $( $flt:ident => $flt_check:expr, $flt_sev:ident, $flt_code:literal ),+ $(,)? }
+    ) => {
+        /// Identifies a single alarm condition reported by the device
+        #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+        #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+        #[cfg_attr(feature = "defmt", derive(defmt::Format))]
+        pub enum AlarmId {
+            $( $sys, )+
+            $( $flt, )+
+        }
+
+        impl AlarmId {
+            /// Every alarm, in declaration order
+            pub const ALL: &'static [AlarmId] = &[ $( AlarmId::$sys, )+ $( AlarmId::$flt, )+ ];
+
+            /// Severity of this alarm
+            pub fn severity(&self) -> Severity {
+                match self {
+                    $( AlarmId::$sys => Severity::$sys_sev, )+
+                    $( AlarmId::$flt => Severity::$flt_sev, )+
+                }
+            }
+
+            /// Stable snake_case identifier for logs and alerting
+            pub fn code(&self) -> &'static str {
+                match self {
+                    $( AlarmId::$sys => $sys_code, )+
+                    $( AlarmId::$flt => $flt_code, )+
+                }
+            }
+
+            /// Whether this alarm is raised by the given status words
+            pub fn is_active(&self, system: &SystemStatus, filter: &FilterStatus) -> bool {
+                match self {
+                    $( AlarmId::$sys => system.$sys_fn(), )+
+                    $( AlarmId::$flt => ($flt_check)(filter), )+
+                }
+            }
+        }
+    };
+}
+
+define_alarms! {
+    system {
+        SystemFailure => system_failure, Critical, "system_failure",
+        AccelerometerSensorFailure => accelerometer_sensor_failure, Critical, "accelerometer_sensor_failure",
+        GyroscopeSensorFailure => gyroscope_sensor_failure, Critical, "gyroscope_sensor_failure",
+        MagnetometerSensorFailure => magnetometer_sensor_failure, Warning, "magnetometer_sensor_failure",
+        PressureSensorFailure => pressure_sensor_failure, Warning, "pressure_sensor_failure",
+        GnssFailure => gnss_failure, Warning, "gnss_failure",
+        AccelerometerOverRange => accelerometer_over_range, Warning, "accelerometer_over_range",
+        GyroscopeOverRange => gyroscope_over_range, Warning, "gyroscope_over_range",
+        MagnetometerOverRange => magnetometer_over_range, Warning, "magnetometer_over_range",
+        PressureOverRange => pressure_over_range, Warning, "pressure_over_range",
+        MinimumTemperature => minimum_temperature_alarm, Warning, "minimum_temperature",
+        MaximumTemperature => maximum_temperature_alarm, Warning, "maximum_temperature",
+        InternalDataLoggingError => internal_data_logging_error, Warning, "internal_data_logging_error",
+        HighVoltage => high_voltage_alarm, Warning, "high_voltage",
+        GnssAntennaDisconnected => gnss_antenna_disconnected, Warning, "gnss_antenna_disconnected",
+        DataOutputOverflow => data_output_overflow_alarm, Warning, "data_output_overflow",
+    }
+    filter {
+        OrientationFilterNotInitialised => |f: &FilterStatus| !f.orientation_filter_initialised(), Critical, "orientation_filter_not_initialised",
+        NavigationFilterNotInitialised => |f: &FilterStatus| !f.navigation_filter_initialised(), Warning, "navigation_filter_not_initialised",
+        HeadingNotInitialised => |f: &FilterStatus| !f.heading_initialised(), Warning, "heading_not_initialised",
+        NoGnssFix => |f: &FilterStatus| f.gnss_fix_type() == GnssFixType::NoFix, Warning, "no_gnss_fix",
+        Event1 => |f: &FilterStatus| f.event1_flag(), Info, "event1",
+        Event2 => |f: &FilterStatus| f.event2_flag(), Info, "event2",
+    }
+}
+
+/// All alarms raised by a pair of status words
+pub fn active_alarms(system: &SystemStatus, filter: &FilterStatus) -> Vec<AlarmId> {
+    AlarmId::ALL.iter()
+        .copied()
+        .filter(|alarm| alarm.is_active(system, filter))
+        .collect()
+}
+
+/// One active alarm in a [`HealthReport`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct HealthFinding {
+    pub alarm: AlarmId,
+    pub severity: Severity,
+    /// Stable identifier, see [`AlarmId::code`]
+    pub code: &'static str,
+}
+
+impl From<AlarmId> for HealthFinding {
+    fn from(alarm: AlarmId) -> Self {
+        Self { alarm, severity: alarm.severity(), code: alarm.code() }
+    }
+}
+
+/// Active alarms of a pair of status words, most severe first
+///
+/// # Examples
+///
+/// ```
+/// use liban::alarms::{HealthReport, Severity};
+/// use liban::{FilterStatus, Status, SystemStatus};
+///
+/// let status = Status {
+///     system_status: SystemStatus::from(1 << 11),
+///     filter_status: FilterStatus::from(0x0027),
+///     extension: None,
+/// };
+/// let report = status.health();
+/// assert_eq!(report.severity(), Some(Severity::Warning));
+/// assert_eq!(report.findings()[0].code, "maximum_temperature");
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct HealthReport {
+    findings: Vec<HealthFinding>,
+}
+
+impl HealthReport {
+    pub fn from_status_words(system: &SystemStatus, filter: &FilterStatus) -> Self {
+        let mut findings: Vec<HealthFinding> = active_alarms(system, filter).into_iter().map(Into::into).collect();
+        findings.sort_by_key(|f| std::cmp::Reverse(f.severity));
+        Self { findings }
+    }
+
+    /// Findings ordered from most to least severe, then in
+    /// [`AlarmId::ALL`] order
+    pub fn findings(&self) -> &[HealthFinding] {
+        &self.findings
+    }
+
+    /// Most severe finding, `None` when nothing is raised
+    pub fn severity(&self) -> Option<Severity> {
+        self.findings.first().map(|f| f.severity)
+    }
+
+    /// Nothing at warning level or above
+    pub fn is_healthy(&self) -> bool {
+        self.severity().is_none_or(|severity| severity < Severity::Warning)
+    }
+
+    /// Findings at `severity` or above
+    pub fn at_least(&self, severity: Severity) -> impl Iterator<Item = &HealthFinding> {
+        self.findings.iter().take_while(move |f| f.severity >= severity)
+    }
+}
+
+impl From<&Status> for HealthReport {
+    fn from(status: &Status) -> Self {
+        Self::from_status_words(&status.system_status, &status.filter_status)
+    }
+}
+
+impl From<&SystemState> for HealthReport {
+    fn from(state: &SystemState) -> Self {
+        Self::from_status_words(&state.system_status, &state.filter_status)
+    }
+}
+
+/// What an [`AlarmRule`] watches for
+#[derive(Debug, Clone, PartialEq)]
+pub enum AlarmCondition {
+    /// A device alarm or flag is active
+    Flag(AlarmId),
+    /// Combined latitude and longitude standard deviation above a limit
+    HorizontalStdDevAbove(Length),
+    /// Height standard deviation above a limit
+    HeightStdDevAbove(Length),
+    /// GNSS fix is no fix or 2D
+    FixBelow3D,
+}
+
+impl AlarmCondition {
+    /// `None` when the packet does not carry what the condition needs
+    fn evaluate(&self, system: &SystemStatus, filter: &FilterStatus, state: Option<&SystemState>) -> Option<bool> {
+        match self {
+            AlarmCondition::Flag(alarm) => Some(alarm.is_active(system, filter)),
+            AlarmCondition::HorizontalStdDevAbove(limit) => {
+                state.map(|s| s.horizontal_position_std().meters() > limit.meters())
+            }
+            AlarmCondition::HeightStdDevAbove(limit) => state.map(|s| s.height_std_dev as f64 > limit.meters()),
+            AlarmCondition::FixBelow3D => {
+                Some(matches!(filter.gnss_fix_type(), GnssFixType::NoFix | GnssFixType::Fix2D))
+            }
+        }
+    }
+}
+
+/// Named condition watched by an [`AlarmWatcher`]
+#[derive(Debug, Clone, PartialEq)]
+pub struct AlarmRule {
+    pub name: String,
+    pub condition: AlarmCondition,
+    pub severity: Severity,
+    /// How long the condition must hold before the alarm is raised
+    pub raise_after: Duration,
+}
+
+impl AlarmRule {
+    /// Raised as soon as the condition holds. Flags keep their own
+    /// severity, other conditions are warnings.
+    pub fn new(name: impl Into<String>, condition: AlarmCondition) -> Self {
+        let severity = match &condition {
+            AlarmCondition::Flag(alarm) => alarm.severity(),
+            _ => Severity::Warning,
+        };
+        Self { name: name.into(), condition, severity, raise_after: Duration::ZERO }
+    }
+
+    pub fn with_severity(mut self, severity: Severity) -> Self {
+        self.severity = severity;
+        self
+    }
+
+    pub fn with_raise_after(mut self, raise_after: Duration) -> Self {
+        self.raise_after = raise_after;
+        self
+    }
+}
+
+/// Edge of a watched alarm
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AlarmEvent {
+    AlarmRaised {
+        rule: String,
+        severity: Severity,
+        /// When the condition started holding
+        since: Instant,
+        at: Instant,
+    },
+    AlarmCleared {
+        rule: String,
+        /// How long the alarm was raised
+        raised_for: Duration,
+        at: Instant,
+    },
+}
+
+#[derive(Debug, Clone)]
+struct Watch {
+    rule: AlarmRule,
+    holding_since: Option<Instant>,
+    raised_at: Option<Instant>,
+}
+
+/// Turns successive Status and SystemState packets into raised and
+/// cleared events for a set of rules
+///
+/// A condition that a packet cannot evaluate, such as a standard deviation
+/// limit on a Status packet, keeps its previous state.
+///
+/// # Examples
+///
+/// ```
+/// use std::time::Duration;
+/// use liban::alarms::{AlarmCondition, AlarmId, AlarmRule, AlarmWatcher};
+///
+/// let watcher = AlarmWatcher::new()
+///     .with_rule(AlarmRule::new("overheat", AlarmCondition::Flag(AlarmId::MaximumTemperature)))
+///     .with_rule(AlarmRule::new("gnss", AlarmCondition::FixBelow3D).with_raise_after(Duration::from_secs(5)));
+/// // for each packet: for event in watcher.handle(&packet, Instant::now()) { ... }
+/// assert!(!watcher.is_raised("gnss"));
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct AlarmWatcher {
+    watches: Vec<Watch>,
+}
+
+impl AlarmWatcher {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_rule(mut self, rule: AlarmRule) -> Self {
+        self.add_rule(rule);
+        self
+    }
+
+    /// Add a rule, replacing any rule with the same name
+    pub fn add_rule(&mut self, rule: AlarmRule) {
+        self.watches.retain(|w| w.rule.name != rule.name);
+        self.watches.push(Watch { rule, holding_since: None, raised_at: None });
+    }
+
+    /// Feed any packet; packets other than Status and SystemState are
+    /// ignored
+    pub fn handle(&mut self, packet: &Packet, clock: impl Clock) -> Vec<AlarmEvent> {
+        let now = clock.now();
+        match packet {
+            Packet::Status(status) => self.update_status(status, now),
+            Packet::SystemState(state) => self.update_state(state, now),
+            _ => Vec::new(),
+        }
+    }
+
+    pub fn update_status(&mut self, status: &Status, clock: impl Clock) -> Vec<AlarmEvent> {
+        let now = clock.now();
+        self.evaluate(&status.system_status, &status.filter_status, None, now)
+    }
+
+    pub fn update_state(&mut self, state: &SystemState, clock: impl Clock) -> Vec<AlarmEvent> {
+        let now = clock.now();
+        self.evaluate(&state.system_status, &state.filter_status, Some(state), now)
+    }
+
+    fn evaluate(
+        &mut self,
+        system: &SystemStatus,
+        filter: &FilterStatus,
+        state: Option<&SystemState>,
+        now: Instant,
+    ) -> Vec<AlarmEvent> {
+        let mut events = Vec::new();
+        for watch in &mut self.watches {
+            match watch.rule.condition.evaluate(system, filter, state) {
+                Some(true) => {
+                    let since = *watch.holding_since.get_or_insert(now);
+                    if watch.raised_at.is_none() && now.saturating_duration_since(since) >= watch.rule.raise_after {
+                        watch.raised_at = Some(now);
+                        events.push(AlarmEvent::AlarmRaised {
+                            rule: watch.rule.name.clone(),
+                            severity: watch.rule.severity,
+                            since,
+                            at: now,
+                        });
+                    }
+                }
+                Some(false) => {
+                    watch.holding_since = None;
+                    if let Some(raised_at) = watch.raised_at.take() {
+                        events.push(AlarmEvent::AlarmCleared {
+                            rule: watch.rule.name.clone(),
+                            raised_for: now.saturating_duration_since(raised_at),
+                            at: now,
+                        });
+                    }
+                }
+                None => {}
+            }
+        }
+        events
+    }
+
+    pub fn is_raised(&self, name: &str) -> bool {
+        self.watches.iter().any(|w| w.rule.name == name && w.raised_at.is_some())
+    }
+
+    /// Rules currently raised, in the order they were added
+    pub fn raised(&self) -> impl Iterator<Item = &AlarmRule> {
+        self.watches.iter().filter(|w| w.raised_at.is_some()).map(|w| &w.rule)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clock::ManualClock;
+    use crate::test_support::system_state;
+
+    // Orientation + navigation + heading initialised, 3D fix
+    const HEALTHY_FILTER: u16 = 0b0000_0000_0010_0111;
+
+    #[test]
+    fn test_healthy_status_has_no_alarms() {
+        let alarms = active_alarms(&SystemStatus::from(0), &FilterStatus::from(HEALTHY_FILTER));
+        assert!(alarms.is_empty());
+    }
+
+    #[test]
+    fn test_system_and_filter_alarms() {
+        let system = SystemStatus::from((1 << 0) | (1 << 14));
+        let filter = FilterStatus::from(0b0000_0000_0000_0001);
+        let alarms = active_alarms(&system, &filter);
+        assert_eq!(alarms, vec![
+            AlarmId::SystemFailure,
+            AlarmId::GnssAntennaDisconnected,
+            AlarmId::NavigationFilterNotInitialised,
+            AlarmId::HeadingNotInitialised,
+            AlarmId::NoGnssFix,
+        ]);
+        assert_eq!(alarms.iter().map(|a| a.severity()).max(), Some(Severity::Critical));
+    }
+
+    #[test]
+    fn test_health_report_ranks_findings() {
+        let system = SystemStatus::from((1 << 10) | (1 << 0));
+        let filter = FilterStatus::from(HEALTHY_FILTER | (1 << 8));
+        let report = HealthReport::from_status_words(&system, &filter);
+        let codes: Vec<_> = report.findings().iter().map(|f| f.code).collect();
+        assert_eq!(codes, ["system_failure", "minimum_temperature", "event2"]);
+        assert_eq!(report.severity(), Some(Severity::Critical));
+        assert!(!report.is_healthy());
+        assert_eq!(report.at_least(Severity::Warning).count(), 2);
+
+        let events = HealthReport::from_status_words(&SystemStatus::from(0), &FilterStatus::from(HEALTHY_FILTER | (1 << 7)));
+        assert_eq!(events.severity(), Some(Severity::Info));
+        assert!(events.is_healthy());
+        assert!(HealthReport::from_status_words(&SystemStatus::from(0), &FilterStatus::from(HEALTHY_FILTER)).findings().is_empty());
+    }
+
+    fn status(system: u16, filter: u16) -> Status {
+        Status { system_status: SystemStatus::from(system), filter_status: FilterStatus::from(filter), extension: None }
+    }
+
+    #[test]
+    fn test_watcher_flag_edges() {
+        let start = Instant::now();
+        let mut watcher = AlarmWatcher::new()
+            .with_rule(AlarmRule::new("overheat", AlarmCondition::Flag(AlarmId::MaximumTemperature)));
+
+        assert!(watcher.update_status(&status(0, HEALTHY_FILTER), start).is_empty());
+        let raised = watcher.handle(&Packet::Status(status(1 << 11, HEALTHY_FILTER)), start + Duration::from_secs(1));
+        assert_eq!(raised, [AlarmEvent::AlarmRaised {
+            rule: "overheat".into(),
+            severity: Severity::Warning,
+            since: start + Duration::from_secs(1),
+            at: start + Duration::from_secs(1),
+        }]);
+        // Level-held conditions do not repeat
+        assert!(watcher.update_status(&status(1 << 11, HEALTHY_FILTER), start + Duration::from_secs(2)).is_empty());
+        assert!(watcher.is_raised("overheat"));
+        assert_eq!(watcher.raised().count(), 1);
+
+        let cleared = watcher.update_status(&status(0, HEALTHY_FILTER), start + Duration::from_secs(4));
+        assert_eq!(cleared, [AlarmEvent::AlarmCleared {
+            rule: "overheat".into(),
+            raised_for: Duration::from_secs(3),
+            at: start + Duration::from_secs(4),
+        }]);
+        assert!(!watcher.is_raised("overheat"));
+    }
+
+    #[test]
+    fn test_watcher_replay_clock() {
+        let clock = ManualClock::new();
+        let mut watcher = AlarmWatcher::new()
+            .with_rule(AlarmRule::new("fix", AlarmCondition::FixBelow3D).with_raise_after(Duration::from_secs(2)));
+        let no_fix = status(0, HEALTHY_FILTER & !0x0070);
+        let recorded = std::time::UNIX_EPOCH + Duration::from_secs(1_700_000_000);
+
+        for (offset, raises) in [(0, false), (1500, false), (2000, true), (2500, false)] {
+            clock.follow(recorded + Duration::from_millis(offset));
+            assert_eq!(watcher.update_status(&no_fix, &clock).len(), raises as usize, "{offset}");
+        }
+    }
+
+    #[test]
+    fn test_watcher_fix_loss_hold_time() {
+        let start = Instant::now();
+        let at = |s| start + Duration::from_secs(s);
+        let mut watcher = AlarmWatcher::new()
+            .with_rule(AlarmRule::new("fix", AlarmCondition::FixBelow3D).with_raise_after(Duration::from_secs(5)));
+        let no_fix = HEALTHY_FILTER & !0x0070;
+
+        assert!(watcher.update_status(&status(0, no_fix), at(0)).is_empty());
+        assert!(watcher.update_status(&status(0, HEALTHY_FILTER), at(3)).is_empty());
+        assert!(watcher.update_status(&status(0, no_fix), at(4)).is_empty());
+        assert!(watcher.update_status(&status(0, no_fix), at(8)).is_empty());
+        let raised = watcher.update_status(&status(0, no_fix), at(9));
+        assert!(matches!(&raised[..], [AlarmEvent::AlarmRaised { since, .. }] if *since == at(4)));
+    }
+
+    #[test]
+    fn test_watcher_std_dev_needs_system_state() {
+        let start = Instant::now();
+        let mut watcher = AlarmWatcher::new()
+            .with_rule(AlarmRule::new("position", AlarmCondition::HorizontalStdDevAbove(Length::from_meters(2.0)))
+                .with_severity(Severity::Critical))
+            .with_rule(AlarmRule::new("height", AlarmCondition::HeightStdDevAbove(Length::from_meters(5.0))));
+        let mut state = SystemState {
+            filter_status: FilterStatus::from(HEALTHY_FILTER),
+            latitude_std_dev: 1.5,
+            longitude_std_dev: 1.5,
+            height_std_dev: 1.0,
+            ..system_state()
+        };
+
+        let raised = watcher.update_state(&state, start);
+        assert!(matches!(&raised[..], [AlarmEvent::AlarmRaised { rule, severity: Severity::Critical, .. }] if rule == "position"));
+        // Status packets leave standard deviation rules alone
+        assert!(watcher.update_status(&status(0, HEALTHY_FILTER), start).is_empty());
+        assert!(watcher.is_raised("position"));
+
+        state.latitude_std_dev = 0.5;
+        let cleared = watcher.update_state(&state, start + Duration::from_secs(1));
+        assert!(matches!(&cleared[..], [AlarmEvent::AlarmCleared { rule, .. }] if rule == "position"));
+    }
+
+    #[test]
+    fn test_alarm_codes_are_unique() {
+        let mut codes: Vec<_> = AlarmId::ALL.iter().map(AlarmId::code).collect();
+        codes.sort();
+        codes.dedup();
+        assert_eq!(codes.len(), AlarmId::ALL.len());
+    }
+
+    #[test]
+    fn test_every_system_status_bit_maps_to_an_alarm() {
+        for bit in 0..16 {
+            let system = SystemStatus::from(1 << bit);
+            let alarms = active_alarms(&system, &FilterStatus::from(HEALTHY_FILTER));
+            assert_eq!(alarms.len(), 1, "bit {} should raise exactly one alarm", bit);
+        }
+    }
+}
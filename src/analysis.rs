@@ -0,0 +1,284 @@
+//! Navigation solution quality.
+//!
+//! [`NavQualityMonitor`] combines SystemState, Satellites and Euler
+//! orientation standard deviation packets into a coarse [`NavQuality`]
+//! level, tracks how long the solution spends at each level and measures
+//! dead-reckoning gaps, the stretches without a usable GNSS fix.
+
+use std::time::{Duration, Instant};
+
+use crate::packet::Packet;
+use crate::packet::state::{EulerOrientationStdDev, GnssFixType, Satellites, SystemState};
+use crate::units::{Angle, Length};
+
+/// Coarse solution quality, ordered from worst to best
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum NavQuality {
+    /// Filter not initialised or uncertainty above the limits
+    Degraded,
+    /// Navigating without a usable GNSS fix
+    DeadReckoning,
+    /// 3D, SBAS, differential, PPP or RTK float fix
+    Fix3D,
+    /// RTK fixed solution within the RTK uncertainty limit
+    Rtk,
+}
+
+impl NavQuality {
+    pub const ALL: [NavQuality; 4] = [NavQuality::Degraded, NavQuality::DeadReckoning, NavQuality::Fix3D, NavQuality::Rtk];
+
+    fn index(self) -> usize {
+        self as usize
+    }
+}
+
+/// Limits used by [`NavQualityMonitor`]
+#[derive(Debug, Clone, PartialEq)]
+pub struct QualityThresholds {
+    /// Largest horizontal standard deviation still reported as [`NavQuality::Rtk`]
+    pub rtk_horizontal_std_dev: Length,
+    /// Horizontal standard deviation above which the solution is degraded
+    pub max_horizontal_std_dev: Length,
+    /// Heading standard deviation above which the solution is degraded
+    pub max_heading_std_dev: Angle,
+    /// Fewer satellites than this and a fix is not counted as usable
+    pub min_satellites: u8,
+    /// HDOP above this and a fix is not counted as usable
+    pub max_hdop: f32,
+}
+
+impl Default for QualityThresholds {
+    fn default() -> Self {
+        Self {
+            rtk_horizontal_std_dev: Length::from_meters(0.1),
+            max_horizontal_std_dev: Length::from_meters(10.0),
+            max_heading_std_dev: Angle::from_degrees(5.0),
+            min_satellites: 4,
+            max_hdop: 5.0,
+        }
+    }
+}
+
+/// Change of quality level
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct QualityChange {
+    /// Previous level, `None` for the first classified state
+    pub from: Option<NavQuality>,
+    pub to: NavQuality,
+    pub at: Instant,
+}
+
+/// Classifies the navigation solution and accounts time at each level
+#[derive(Debug, Clone, Default)]
+pub struct NavQualityMonitor {
+    thresholds: QualityThresholds,
+    satellites: Option<Satellites>,
+    orientation_std_dev: Option<EulerOrientationStdDev>,
+    current: Option<NavQuality>,
+    last_update: Option<Instant>,
+    time_in: [Duration; 4],
+    gap_start: Option<Instant>,
+    longest_gap: Duration,
+}
+
+impl NavQualityMonitor {
+    pub fn new(thresholds: QualityThresholds) -> Self {
+        Self { thresholds, ..Default::default() }
+    }
+
+    pub fn thresholds(&self) -> &QualityThresholds {
+        &self.thresholds
+    }
+
+    /// Feed any packet. SystemState packets are classified, Satellites and
+    /// EulerOrientationStdDev are kept for later classifications and other
+    /// packets are ignored.
+    pub fn handle(&mut self, packet: &Packet, now: Instant) -> Option<QualityChange> {
+        match packet {
+            Packet::SystemState(state) => self.update(state, now),
+            Packet::Satellites(satellites) => {
+                self.satellites = Some(satellites.clone());
+                None
+            }
+            Packet::EulerOrientationStdDev(std_dev) => {
+                self.orientation_std_dev = Some(std_dev.clone());
+                None
+            }
+            _ => None,
+        }
+    }
+
+    /// Classify a state and account the time since the previous one to the
+    /// previous level. Returns the change if the level moved.
+    pub fn update(&mut self, state: &SystemState, now: Instant) -> Option<QualityChange> {
+        let quality = self.classify(state);
+        if let (Some(previous), Some(last)) = (self.current, self.last_update) {
+            self.time_in[previous.index()] += now.saturating_duration_since(last);
+        }
+        self.last_update = Some(now);
+
+        if quality == NavQuality::DeadReckoning {
+            self.gap_start.get_or_insert(now);
+        } else if let Some(start) = self.gap_start.take() {
+            self.longest_gap = self.longest_gap.max(now.saturating_duration_since(start));
+        }
+
+        let from = self.current.replace(quality);
+        (from != Some(quality)).then_some(QualityChange { from, to: quality, at: now })
+    }
+
+    /// Quality of a state given the latest Satellites and orientation
+    /// standard deviation seen
+    pub fn classify(&self, state: &SystemState) -> NavQuality {
+        let t = &self.thresholds;
+        if !state.filter_status.navigation_filter_initialised() {
+            return NavQuality::Degraded;
+        }
+        let horizontal = state.horizontal_position_std().meters();
+        if !horizontal.is_finite() || horizontal > t.max_horizontal_std_dev.meters() {
+            return NavQuality::Degraded;
+        }
+        if let Some(std_dev) = &self.orientation_std_dev
+            && f64::from(std_dev.heading_std_dev) > t.max_heading_std_dev.radians()
+        {
+            return NavQuality::Degraded;
+        }
+
+        let usable = self.satellites.as_ref().is_none_or(|s| {
+            let count = s.gps_satellites as u32 + s.glonass_satellites as u32 + s.beidou_satellites as u32
+                + s.galileo_satellites as u32 + s.sbas_satellites as u32;
+            count >= t.min_satellites as u32 && s.hdop <= t.max_hdop
+        });
+        match state.filter_status.gnss_fix_type() {
+            _ if !usable => NavQuality::DeadReckoning,
+            GnssFixType::NoFix | GnssFixType::Fix2D => NavQuality::DeadReckoning,
+            GnssFixType::RtkFixed if horizontal <= t.rtk_horizontal_std_dev.meters() => NavQuality::Rtk,
+            _ => NavQuality::Fix3D,
+        }
+    }
+
+    /// Level of the latest state
+    pub fn quality(&self) -> Option<NavQuality> {
+        self.current
+    }
+
+    /// Time accounted to a level, up to the latest state
+    pub fn time_in(&self, quality: NavQuality) -> Duration {
+        self.time_in[quality.index()]
+    }
+
+    /// Length of the current dead-reckoning gap, if in one
+    pub fn dead_reckoning_gap(&self, now: Instant) -> Option<Duration> {
+        self.gap_start.map(|start| now.saturating_duration_since(start))
+    }
+
+    /// Longest completed dead-reckoning gap
+    pub fn longest_dead_reckoning_gap(&self) -> Duration {
+        self.longest_gap
+    }
+
+    /// Clear time accounting and gap history, keeping the current level
+    pub fn reset_statistics(&mut self) {
+        self.time_in = [Duration::ZERO; 4];
+        self.longest_gap = Duration::ZERO;
+        if self.gap_start.is_some() {
+            self.gap_start = self.last_update;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::packet::state::FilterStatus;
+    use crate::test_support::system_state;
+    use crate::units::Radians;
+
+    const NAV_INITIALISED: u16 = 0b0000_0000_0000_0011;
+
+    fn state(fix: GnssFixType, std_dev: f32) -> SystemState {
+        SystemState {
+            filter_status: FilterStatus::from(NAV_INITIALISED | ((fix as u16) << 4)),
+            latitude_std_dev: std_dev,
+            longitude_std_dev: std_dev,
+            height_std_dev: std_dev,
+            ..system_state()
+        }
+    }
+
+    fn satellites(count: u8, hdop: f32) -> Satellites {
+        Satellites {
+            hdop,
+            vdop: hdop,
+            gps_satellites: count,
+            glonass_satellites: 0,
+            beidou_satellites: 0,
+            galileo_satellites: 0,
+            sbas_satellites: 0,
+        }
+    }
+
+    #[test]
+    fn test_classification() {
+        let mut monitor = NavQualityMonitor::default();
+        assert_eq!(monitor.classify(&state(GnssFixType::RtkFixed, 0.02)), NavQuality::Rtk);
+        assert_eq!(monitor.classify(&state(GnssFixType::RtkFixed, 0.5)), NavQuality::Fix3D);
+        assert_eq!(monitor.classify(&state(GnssFixType::RtkFloat, 0.02)), NavQuality::Fix3D);
+        assert_eq!(monitor.classify(&state(GnssFixType::Fix2D, 1.0)), NavQuality::DeadReckoning);
+        assert_eq!(monitor.classify(&state(GnssFixType::Fix3D, 20.0)), NavQuality::Degraded);
+
+        let mut uninitialised = state(GnssFixType::Fix3D, 1.0);
+        uninitialised.filter_status = FilterStatus::from(0x0031);
+        assert_eq!(monitor.classify(&uninitialised), NavQuality::Degraded);
+
+        let now = Instant::now();
+        monitor.handle(&Packet::Satellites(satellites(3, 1.0)), now);
+        assert_eq!(monitor.classify(&state(GnssFixType::Fix3D, 1.0)), NavQuality::DeadReckoning);
+        monitor.handle(&Packet::Satellites(satellites(12, 0.8)), now);
+        assert_eq!(monitor.classify(&state(GnssFixType::Fix3D, 1.0)), NavQuality::Fix3D);
+
+        let heading = EulerOrientationStdDev { roll_std_dev: Radians(0.01), pitch_std_dev: Radians(0.01), heading_std_dev: Radians(0.2) };
+        monitor.handle(&Packet::EulerOrientationStdDev(heading), now);
+        assert_eq!(monitor.classify(&state(GnssFixType::Fix3D, 1.0)), NavQuality::Degraded);
+    }
+
+    #[test]
+    fn test_custom_thresholds() {
+        let monitor = NavQualityMonitor::new(QualityThresholds {
+            rtk_horizontal_std_dev: Length::from_meters(1.0),
+            ..Default::default()
+        });
+        assert_eq!(monitor.classify(&state(GnssFixType::RtkFixed, 0.5)), NavQuality::Rtk);
+    }
+
+    #[test]
+    fn test_time_in_state_and_gaps() {
+        let start = Instant::now();
+        let at = |s: u64| start + Duration::from_secs(s);
+        let mut monitor = NavQualityMonitor::default();
+
+        let change = monitor.update(&state(GnssFixType::Fix3D, 1.0), at(0)).unwrap();
+        assert_eq!((change.from, change.to), (None, NavQuality::Fix3D));
+        assert!(monitor.update(&state(GnssFixType::Fix3D, 1.0), at(10)).is_none());
+
+        let change = monitor.update(&state(GnssFixType::NoFix, 2.0), at(20)).unwrap();
+        assert_eq!((change.from, change.to), (Some(NavQuality::Fix3D), NavQuality::DeadReckoning));
+        assert_eq!(monitor.dead_reckoning_gap(at(25)), Some(Duration::from_secs(5)));
+        monitor.update(&state(GnssFixType::NoFix, 3.0), at(30));
+
+        monitor.update(&state(GnssFixType::RtkFixed, 0.05), at(50));
+        assert_eq!(monitor.quality(), Some(NavQuality::Rtk));
+        assert_eq!(monitor.dead_reckoning_gap(at(50)), None);
+        assert_eq!(monitor.longest_dead_reckoning_gap(), Duration::from_secs(30));
+        monitor.update(&state(GnssFixType::RtkFixed, 0.05), at(55));
+
+        assert_eq!(monitor.time_in(NavQuality::Fix3D), Duration::from_secs(20));
+        assert_eq!(monitor.time_in(NavQuality::DeadReckoning), Duration::from_secs(30));
+        assert_eq!(monitor.time_in(NavQuality::Rtk), Duration::from_secs(5));
+        assert_eq!(monitor.time_in(NavQuality::Degraded), Duration::ZERO);
+
+        monitor.reset_statistics();
+        assert_eq!(monitor.time_in(NavQuality::Rtk), Duration::ZERO);
+        assert_eq!(monitor.quality(), Some(NavQuality::Rtk));
+    }
+}
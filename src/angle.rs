@@ -0,0 +1,136 @@
+//! Angle wrapping for orientation fields.
+//!
+//! The device reports roll in (-π, π] and heading in [0, 2π), so headings
+//! either side of north are nearly 2π apart as raw numbers. Differencing or
+//! averaging them directly gives a turn the long way round; these helpers
+//! work on the shorter arc instead. They take bare radians in `f32` or
+//! `f64`, [`Radians`] or [`Angle`], and wrap in that type's own precision.
+//!
+//! ```
+//! use liban::angle::{normalize_2pi, shortest_angular_distance};
+//! use liban::units::{Angle, Radians};
+//!
+//! let previous = Angle::from_degrees(359.0);
+//! let current = Angle::from_degrees(1.0);
+//! assert!((shortest_angular_distance(previous, current).degrees() - 2.0).abs() < 1e-9);
+//! assert!((normalize_2pi(Radians(-90f32.to_radians())).to_degrees().get() - 270.0).abs() < 1e-3);
+//! assert!((normalize_2pi(-90f32.to_radians()).to_degrees() - 270.0).abs() < 1e-3);
+//! ```
+
+use std::ops::Sub;
+
+use crate::units::{Angle, Radians};
+
+/// An angle in radians the helpers can wrap
+pub trait WrapAngle: Copy + Sub<Output = Self> {
+    /// Wrap into (-π, π]
+    fn normalize_pi(self) -> Self;
+    /// Wrap into [0, 2π)
+    fn normalize_2pi(self) -> Self;
+}
+
+macro_rules! wrap_float {
+    ($float:ident) => {
+        impl WrapAngle for $float {
+            fn normalize_pi(self) -> Self {
+                let wrapped = self.normalize_2pi();
+                if wrapped > std::$float::consts::PI { wrapped - std::$float::consts::TAU } else { wrapped }
+            }
+
+            fn normalize_2pi(self) -> Self {
+                let wrapped = self.rem_euclid(std::$float::consts::TAU);
+                // rem_euclid rounds tiny negative angles up to exactly 2π
+                if wrapped >= std::$float::consts::TAU { 0.0 } else { wrapped }
+            }
+        }
+    };
+}
+
+wrap_float!(f32);
+wrap_float!(f64);
+
+impl WrapAngle for Radians {
+    fn normalize_pi(self) -> Self {
+        Radians(self.get().normalize_pi())
+    }
+
+    fn normalize_2pi(self) -> Self {
+        Radians(self.get().normalize_2pi())
+    }
+}
+
+impl WrapAngle for Angle {
+    fn normalize_pi(self) -> Self {
+        Angle::from_radians(self.radians().normalize_pi())
+    }
+
+    fn normalize_2pi(self) -> Self {
+        Angle::from_radians(self.radians().normalize_2pi())
+    }
+}
+
+/// Wrap an angle into (-π, π]
+pub fn normalize_pi<A: WrapAngle>(angle: A) -> A {
+    angle.normalize_pi()
+}
+
+/// Wrap an angle into [0, 2π)
+pub fn normalize_2pi<A: WrapAngle>(angle: A) -> A {
+    angle.normalize_2pi()
+}
+
+/// Signed turn from `a` to `b` along the shorter arc, in (-π, π].
+/// Positive when `b` is clockwise of `a` for headings.
+pub fn shortest_angular_distance<A: WrapAngle>(a: A, b: A) -> A {
+    (b - a).normalize_pi()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::f32::consts::{PI, TAU};
+
+    fn close(a: f32, b: f32) -> bool {
+        (a - b).abs() < 1e-5
+    }
+
+    #[test]
+    fn test_normalize() {
+        assert!(close(normalize_pi(3.0 * PI / 2.0), -PI / 2.0));
+        assert!(close(normalize_pi(-3.0 * PI / 2.0), PI / 2.0));
+        assert_eq!(normalize_pi(PI), PI);
+        assert_eq!(normalize_pi(-PI), PI);
+        assert_eq!(normalize_pi(0.25), 0.25);
+        assert!(close(normalize_pi(10.0 * TAU + 0.5), 0.5));
+
+        assert!(close(normalize_2pi(-PI / 2.0), 3.0 * PI / 2.0));
+        assert_eq!(normalize_2pi(TAU), 0.0);
+        assert_eq!(normalize_2pi(-1e-9f32), 0.0);
+        assert!(normalize_2pi(f32::NAN).is_nan());
+    }
+
+    #[test]
+    fn test_shortest_angular_distance() {
+        let (a, b) = (350f32.to_radians(), 10f32.to_radians());
+        assert!(close(shortest_angular_distance(a, b), 20f32.to_radians()));
+        assert!(close(shortest_angular_distance(b, a), -20f32.to_radians()));
+        assert!(close(shortest_angular_distance(-3.0, 3.0), 6.0 - TAU));
+        assert_eq!(shortest_angular_distance(1.0, 1.0), 0.0);
+    }
+
+    #[test]
+    fn test_typed_angles() {
+        use std::f64::consts::{PI, TAU};
+        assert_eq!(normalize_pi(Radians(-PI as f32)), Radians(PI as f32));
+        assert_eq!(normalize_2pi(Radians(-1e-9)), Radians(0.0));
+        assert_eq!(normalize_pi(Angle::from_radians(-PI)), Angle::from_radians(PI));
+        assert_eq!(normalize_2pi(Angle::from_radians(-1e-18)), Angle::ZERO);
+        assert!((normalize_2pi(Angle::from_radians(-PI / 2.0)).radians() - 1.5 * PI).abs() < 1e-12);
+        assert!(normalize_2pi(Angle::from_radians(TAU)).radians() < TAU);
+
+        let turn = shortest_angular_distance(Angle::from_degrees(350.0), Angle::from_degrees(10.0));
+        assert!((turn.degrees() - 20.0).abs() < 1e-9);
+        let turn = shortest_angular_distance(Radians(10f32.to_radians()), Radians(350f32.to_radians()));
+        assert!((turn.to_degrees().get() + 20.0).abs() < 1e-3);
+    }
+}
@@ -0,0 +1,283 @@
+//! Command line companion for Advanced Navigation devices over TCP.
+//!
+//! Built only on liban's public API; enable with `--features cli`.
+
+use clap::{Parser as ClapParser, Subcommand};
+use liban::client::{Client, ClientEvent, RetryPolicy};
+use liban::provision::{ApplyOptions, ConfigSet};
+use liban::{AnppParser, Packet, PacketKind};
+use std::fs::File;
+use std::io::{self, BufWriter, Read, Write};
+use std::net::TcpStream;
+use std::path::PathBuf;
+use std::sync::mpsc::Receiver;
+use std::time::{Duration, Instant};
+
+type Result<T> = std::result::Result<T, Box<dyn std::error::Error>>;
+
+#[derive(ClapParser)]
+#[command(name = "liban-cli")]
+#[command(about = "Inspect, configure and record ANPP devices", long_about = None)]
+struct Args {
+    /// IP address of the device
+    #[arg(short, long, default_value = "192.168.42.42", global = true)]
+    ip: String,
+
+    /// Port number
+    #[arg(short, long, default_value = "16718", global = true)]
+    port: u16,
+
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Show device information and status
+    Info,
+    /// Print every packet of one kind as it arrives
+    Watch {
+        /// Packet name (e.g. SystemState) or numeric ID
+        packet: String,
+        /// Stop after this many packets
+        #[arg(short = 'n', long)]
+        count: Option<usize>,
+        /// Print JSON instead of text
+        #[arg(long)]
+        json: bool,
+    },
+    /// Read every configuration packet and print it as a JSON profile
+    GetConfig {
+        /// Write the profile to a file instead of stdout
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+    },
+    /// Write the configuration packets in a JSON profile, link settings last
+    SetConfig {
+        /// Profile written by get-config
+        #[arg(short, long)]
+        file: PathBuf,
+        /// Override every packet's permanent flag
+        #[arg(long)]
+        permanent: Option<bool>,
+        /// Validate the profile without writing it
+        #[arg(long)]
+        dry_run: bool,
+    },
+    /// Save the raw byte stream from the device
+    Record {
+        #[arg(short, long)]
+        output: PathBuf,
+        /// Stop after this many seconds
+        #[arg(short, long)]
+        duration: Option<f64>,
+    },
+    /// Decode and print a recording made with record
+    Replay {
+        file: PathBuf,
+        /// Print JSON instead of text
+        #[arg(long)]
+        json: bool,
+    },
+}
+
+/// A TCP link driven through [`Client`]
+struct Device {
+    stream: TcpStream,
+    client: Client,
+    events: Receiver<ClientEvent>,
+}
+
+impl Device {
+    fn connect(args: &Args) -> Result<Self> {
+        let stream = TcpStream::connect((args.ip.as_str(), args.port))?;
+        stream.set_read_timeout(Some(Duration::from_millis(20)))?;
+        let mut client = Client::new()
+            .with_retry(RetryPolicy { max_attempts: 3, timeout: Duration::from_millis(500) });
+        let events = client.subscribe();
+        client.connected(Instant::now());
+        Ok(Self { stream, client, events })
+    }
+
+    /// Send what is due, then decode whatever arrives within the read timeout
+    fn pump(&mut self) -> Result<Vec<Packet>> {
+        let out = self.client.poll_transmit(Instant::now());
+        if !out.is_empty() {
+            self.stream.write_all(&out)?;
+        }
+        let mut buffer = [0u8; 4096];
+        match self.stream.read(&mut buffer) {
+            Ok(0) => Err("connection closed by device".into()),
+            Ok(n) => Ok(self.client.handle(&buffer[..n], Instant::now())),
+            Err(e) if matches!(e.kind(), io::ErrorKind::WouldBlock | io::ErrorKind::TimedOut) => Ok(Vec::new()),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// Pump until every request and write has been answered or given up
+    fn settle(&mut self) -> Result<Vec<Packet>> {
+        let mut packets = Vec::new();
+        while self.client.pending() > 0 {
+            packets.extend(self.pump()?);
+        }
+        Ok(packets)
+    }
+}
+
+fn parse_kind(name: &str) -> Result<PacketKind> {
+    let kind = match name.parse::<u8>() {
+        Ok(id) => PacketKind::from(id),
+        Err(_) => PacketKind::all()
+            .find(|kind| kind.name().eq_ignore_ascii_case(name))
+            .unwrap_or(PacketKind::Unsupported),
+    };
+    if kind == PacketKind::Unsupported {
+        return Err(format!("unknown packet {:?}", name).into());
+    }
+    Ok(kind)
+}
+
+fn print_packet(packet: &Packet, json: bool) -> Result<()> {
+    if json {
+        println!("{}", serde_json::to_string(packet)?);
+    } else {
+        println!("{}", packet);
+    }
+    Ok(())
+}
+
+fn info(args: &Args) -> Result<()> {
+    let mut device = Device::connect(args)?;
+    device.client.request(PacketKind::DeviceInformation)?;
+    device.client.request(PacketKind::Status)?;
+    for packet in device.settle()? {
+        match &packet {
+            Packet::DeviceInformation(info) => {
+                println!("{}", packet);
+                println!("Serial number {:08X}-{:08X}-{:08X}", info.serial_number_1, info.serial_number_2, info.serial_number_3);
+            }
+            Packet::Status(_) => println!("{}", packet),
+            _ => {}
+        }
+    }
+    if let Ok(ClientEvent::RequestFailed(kind)) = device.events.try_recv() {
+        return Err(format!("device did not answer the {:?} request", kind).into());
+    }
+    Ok(())
+}
+
+fn watch(args: &Args, kind: PacketKind, count: Option<usize>, json: bool) -> Result<()> {
+    let mut device = Device::connect(args)?;
+    let mut seen = 0;
+    loop {
+        for packet in device.pump()? {
+            if PacketKind::from(packet.packet_id()) == kind && !matches!(packet, Packet::Unsupported { .. }) {
+                print_packet(&packet, json)?;
+                seen += 1;
+                if count.is_some_and(|count| seen >= count) {
+                    return Ok(());
+                }
+            }
+        }
+    }
+}
+
+fn get_config(args: &Args, output: Option<PathBuf>) -> Result<()> {
+    let mut device = Device::connect(args)?;
+    device.client.read_full_config();
+    device.settle()?;
+    let Some(profile) = device.events.try_iter().find_map(|event| match event {
+        ClientEvent::ConfigRead(set) => Some(set),
+        _ => None,
+    }) else {
+        return Err("configuration read did not finish".into());
+    };
+    for kind in profile.missing() {
+        eprintln!("No answer for {:?}", kind);
+    }
+
+    let json = serde_json::to_string_pretty(&profile)?;
+    match output {
+        Some(path) => std::fs::write(&path, json + "\n")?,
+        None => println!("{}", json),
+    }
+    eprintln!("Read {} configuration packets", profile.len());
+    Ok(())
+}
+
+fn set_config(args: &Args, file: PathBuf, permanent: Option<bool>, dry_run: bool) -> Result<()> {
+    let profile: ConfigSet = serde_json::from_str(&std::fs::read_to_string(&file)?)?;
+    let mut options = ApplyOptions::new();
+    options.permanent = permanent;
+    if dry_run {
+        profile.validate(None)?;
+        eprintln!("{} configuration packets are valid", profile.len());
+        return Ok(());
+    }
+
+    let mut device = Device::connect(args)?;
+    device.client.apply_full_config(&profile, &options)?;
+    device.settle()?;
+    let Some(report) = device.events.try_iter().find_map(|event| match event {
+        ClientEvent::ConfigApplied(report) => Some(report),
+        _ => None,
+    }) else {
+        return Err("configuration apply did not finish".into());
+    };
+    for (kind, outcome) in report.failures() {
+        eprintln!("{:?}: {:?}", kind, outcome);
+    }
+    if !report.is_success() {
+        return Err(format!("{} of {} writes failed", report.failures().count(), profile.len()).into());
+    }
+    eprintln!("Wrote {} configuration packets", profile.len());
+    Ok(())
+}
+
+fn record(args: &Args, output: PathBuf, duration: Option<f64>) -> Result<()> {
+    let mut stream = TcpStream::connect((args.ip.as_str(), args.port))?;
+    stream.set_read_timeout(Some(Duration::from_millis(200)))?;
+    let deadline = duration.map(|seconds| Instant::now() + Duration::from_secs_f64(seconds));
+    let mut file = BufWriter::new(File::create(&output)?);
+    let mut buffer = [0u8; 4096];
+    let mut total = 0;
+    while deadline.is_none_or(|deadline| Instant::now() < deadline) {
+        match stream.read(&mut buffer) {
+            Ok(0) => break,
+            Ok(n) => {
+                file.write_all(&buffer[..n])?;
+                total += n;
+            }
+            Err(e) if matches!(e.kind(), io::ErrorKind::WouldBlock | io::ErrorKind::TimedOut) => {}
+            Err(e) => return Err(e.into()),
+        }
+    }
+    file.flush()?;
+    eprintln!("Recorded {} bytes to {}", total, output.display());
+    Ok(())
+}
+
+fn replay(file: PathBuf, json: bool) -> Result<()> {
+    let bytes = std::fs::read(&file)?;
+    let mut parser = AnppParser::new();
+    let mut next = parser.consume(&bytes);
+    while let Some(packet) = next {
+        print_packet(&packet, json)?;
+        next = parser.consume(&[]);
+    }
+    let stats = parser.stats();
+    eprintln!("{} packets, {} CRC errors, {} bytes discarded", stats.packets, stats.crc_errors, stats.bytes_discarded);
+    Ok(())
+}
+
+fn main() -> Result<()> {
+    let args = Args::parse();
+    match &args.command {
+        Command::Info => info(&args),
+        Command::Watch { packet, count, json } => watch(&args, parse_kind(packet)?, *count, *json),
+        Command::GetConfig { output } => get_config(&args, output.clone()),
+        Command::SetConfig { file, permanent, dry_run } => set_config(&args, file.clone(), *permanent, *dry_run),
+        Command::Record { output, duration } => record(&args, output.clone(), *duration),
+        Command::Replay { file, json } => replay(file.clone(), *json),
+    }
+}
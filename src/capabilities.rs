@@ -0,0 +1,127 @@
+//! What a connected device can do.
+//!
+//! [`DeviceCapabilities`] is derived from the device's own identification
+//! packets, Device Information and Subcomponent Information: the packet
+//! set, the fastest output rate, the ports it can be configured on and its
+//! internal subcomponents.
+//! [`Client::handshake`](crate::client::Client::handshake) requests the
+//! packets and keeps the result, so requests and writes the device could
+//! never answer fail straight away with [`AnError::UnsupportedByDevice`]
+//! instead of timing out.
+
+#[cfg(feature = "serde")]
+use serde::{Serialize, Deserialize};
+
+use crate::error::AnError;
+use crate::packet::config::PacketTimerPeriod;
+use crate::packet::system::{DeviceInformation, DeviceModel, Subcomponent, Version};
+use crate::packet::PacketKind;
+
+/// Number of IP dataports on devices configured over Ethernet
+pub const IP_DATAPORTS: u8 = 4;
+
+/// A port the device sends and receives ANPP on
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum DevicePort {
+    /// Primary serial port, set by Baud Rates
+    PrimarySerial,
+    /// Auxiliary serial port on the GPIO pins, set by GPIO Configuration
+    AuxiliarySerial,
+    /// TCP or UDP dataport, by index, set by IP Dataports Configuration
+    IpDataport(u8),
+}
+
+/// Packet set, output rate and ports of one device
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct DeviceCapabilities {
+    pub device: DeviceInformation,
+    pub model: DeviceModel,
+    /// Every packet kind the device can send or accept, by ID
+    pub packets: Vec<PacketKind>,
+    /// Fastest output rate in Hz, at the shortest packet timer period
+    pub max_rate: f64,
+    pub ports: Vec<DevicePort>,
+    /// From Subcomponent Information; empty if the device did not answer
+    pub subcomponents: Vec<Subcomponent>,
+}
+
+impl DeviceCapabilities {
+    pub fn from_device_information(device: &DeviceInformation) -> Self {
+        let model = device.model();
+        let mut ports = vec![DevicePort::PrimarySerial, DevicePort::AuxiliarySerial];
+        if model.has_ethernet() {
+            ports.extend((0..IP_DATAPORTS).map(DevicePort::IpDataport));
+        }
+        Self {
+            device: device.clone(),
+            model,
+            packets: PacketKind::all().filter(|kind| model.supports(*kind)).collect(),
+            max_rate: 1.0 / PacketTimerPeriod::MIN_PERIOD.as_secs_f64(),
+            ports,
+            subcomponents: Vec::new(),
+        }
+    }
+
+    pub fn with_subcomponents(mut self, subcomponents: Vec<Subcomponent>) -> Self {
+        self.subcomponents = subcomponents;
+        self
+    }
+
+    pub fn firmware(&self) -> Version {
+        self.device.software()
+    }
+
+    pub fn supports(&self, kind: PacketKind) -> bool {
+        self.packets.contains(&kind)
+    }
+
+    /// Fastest rate packets of `kind` can be output at, or `None` if the
+    /// device has no such packet
+    pub fn max_rate(&self, kind: PacketKind) -> Option<f64> {
+        self.supports(kind).then_some(self.max_rate)
+    }
+
+    /// Fail with [`AnError::UnsupportedByDevice`] unless the device has
+    /// packets of `kind`
+    pub fn check(&self, kind: PacketKind) -> Result<(), AnError> {
+        if self.supports(kind) { Ok(()) } else { Err(AnError::UnsupportedByDevice { id: kind.packet_id() }) }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::packet::system::DeviceType;
+
+    fn device(device_type: DeviceType) -> DeviceInformation {
+        DeviceInformation {
+            software_version: 7040,
+            device_type,
+            hardware_revision: 1000,
+            serial_number_1: 1,
+            serial_number_2: 2,
+            serial_number_3: 3,
+        }
+    }
+
+    #[test]
+    fn test_capabilities_follow_model() {
+        let boreas = DeviceCapabilities::from_device_information(&device(DeviceType::BoreasD90));
+        assert_eq!(boreas.model, DeviceModel::BoreasD90);
+        assert_eq!(boreas.firmware(), Version::new(7, 0, 40));
+        assert!(boreas.supports(PacketKind::DualAntennaConfiguration));
+        assert!(boreas.check(PacketKind::NorthSeekingStatus).is_ok());
+        assert_eq!(boreas.max_rate(PacketKind::SystemState), Some(1000.0));
+        assert!(boreas.ports.contains(&DevicePort::IpDataport(3)));
+
+        let orientus = DeviceCapabilities::from_device_information(&device(DeviceType::Orientus));
+        assert_eq!(orientus.max_rate(PacketKind::RawGnss), None);
+        assert!(matches!(orientus.check(PacketKind::RawGnss), Err(AnError::UnsupportedByDevice { id: 29 })));
+        assert_eq!(orientus.ports, vec![DevicePort::PrimarySerial, DevicePort::AuxiliarySerial]);
+        assert!(!orientus.supports(PacketKind::Unsupported));
+    }
+}
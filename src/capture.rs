@@ -0,0 +1,442 @@
+//! Raw byte capture with file rotation.
+//!
+//! [`CaptureWriter`] appends the bytes received from a device to numbered
+//! `.anpp` files, starting a new file once the current one reaches a size or
+//! age limit and optionally deleting the oldest. Each capture file has a
+//! `.meta` sidecar of `key=value` lines recording when it started, how many
+//! bytes it holds and, once known, the device's serial number.
+//!
+//! [`Tee`] wraps any [`Read`] transport so everything read through it is
+//! captured on the way to the parser:
+//!
+//! ```no_run
+//! use std::net::TcpStream;
+//! use liban::capture::{CaptureConfig, CaptureWriter, Tee};
+//! use liban::reader::AnppReader;
+//!
+//! let stream = TcpStream::connect("192.168.42.42:16718").unwrap();
+//! let writer = CaptureWriter::new(CaptureConfig::new("captures").with_keep(24)).unwrap();
+//! for packet in AnppReader::new(Tee::new(stream, writer)) {
+//!     println!("{}", packet.unwrap());
+//! }
+//! ```
+//!
+//! Files roll over between writes, so a stream written a frame at a time
+//! gives files that each start and end on a frame boundary and can go
+//! straight into the golden corpus. Files cut from a raw socket stream may
+//! start and end with a partial frame, which the parser skips.
+
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, BufWriter, Read, Write};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use crate::packet::system::DeviceInformation;
+
+/// Default size at which a new file is started
+pub const DEFAULT_MAX_BYTES: u64 = 64 * 1024 * 1024;
+
+/// Default age at which a new file is started
+pub const DEFAULT_MAX_AGE: Duration = Duration::from_secs(3600);
+
+/// Where and how captures are written
+#[derive(Debug, Clone, PartialEq)]
+pub struct CaptureConfig {
+    pub dir: PathBuf,
+    /// File name prefix, followed by the start time and a sequence number
+    pub prefix: String,
+    pub max_bytes: Option<u64>,
+    pub max_age: Option<Duration>,
+    /// Capture files with this prefix to keep in `dir`, including those
+    /// from earlier runs; older ones are deleted on rollover
+    pub keep: Option<usize>,
+}
+
+impl CaptureConfig {
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self {
+            dir: dir.into(),
+            prefix: "capture".to_string(),
+            max_bytes: Some(DEFAULT_MAX_BYTES),
+            max_age: Some(DEFAULT_MAX_AGE),
+            keep: None,
+        }
+    }
+
+    pub fn with_prefix(mut self, prefix: impl Into<String>) -> Self {
+        self.prefix = prefix.into();
+        self
+    }
+
+    /// Size limit per file, or `None` for no limit
+    pub fn with_max_bytes(mut self, max_bytes: Option<u64>) -> Self {
+        self.max_bytes = max_bytes;
+        self
+    }
+
+    /// Age limit per file, or `None` for no limit
+    pub fn with_max_age(mut self, max_age: Option<Duration>) -> Self {
+        self.max_age = max_age;
+        self
+    }
+
+    pub fn with_keep(mut self, keep: usize) -> Self {
+        self.keep = Some(keep);
+        self
+    }
+}
+
+/// Contents of a capture's `.meta` sidecar
+#[derive(Debug, Clone, PartialEq)]
+pub struct CaptureMetadata {
+    pub started: SystemTime,
+    pub bytes: u64,
+    /// Serial number as printed by the device, e.g. `00000001-00000000-00000000`
+    pub serial: Option<String>,
+    pub device_type: Option<String>,
+}
+
+impl CaptureMetadata {
+    fn render(&self) -> String {
+        let started = self.started.duration_since(UNIX_EPOCH).unwrap_or_default();
+        let mut text = format!("started_unix={}.{:06}\nbytes={}\n", started.as_secs(), started.subsec_micros(), self.bytes);
+        if let Some(serial) = &self.serial {
+            text += &format!("serial={}\n", serial);
+        }
+        if let Some(device_type) = &self.device_type {
+            text += &format!("device_type={}\n", device_type);
+        }
+        text
+    }
+
+    /// Read a `.meta` sidecar written by [`CaptureWriter`]
+    pub fn load(path: impl AsRef<Path>) -> io::Result<Self> {
+        let invalid = |what: &str| io::Error::new(io::ErrorKind::InvalidData, format!("bad capture metadata: {}", what));
+        let mut metadata = Self { started: UNIX_EPOCH, bytes: 0, serial: None, device_type: None };
+        for line in fs::read_to_string(path)?.lines() {
+            let Some((key, value)) = line.split_once('=') else { continue };
+            match key {
+                "started_unix" => {
+                    let seconds: f64 = value.parse().map_err(|_| invalid("started_unix"))?;
+                    metadata.started = UNIX_EPOCH + Duration::try_from_secs_f64(seconds).map_err(|_| invalid("started_unix"))?;
+                }
+                "bytes" => metadata.bytes = value.parse().map_err(|_| invalid("bytes"))?,
+                "serial" => metadata.serial = Some(value.to_string()),
+                "device_type" => metadata.device_type = Some(value.to_string()),
+                _ => {}
+            }
+        }
+        Ok(metadata)
+    }
+}
+
+#[derive(Debug)]
+struct OpenFile {
+    path: PathBuf,
+    file: BufWriter<File>,
+    metadata: CaptureMetadata,
+}
+
+/// Writes received bytes to rotating capture files
+#[derive(Debug)]
+pub struct CaptureWriter {
+    config: CaptureConfig,
+    current: Option<OpenFile>,
+    files: Vec<PathBuf>,
+    sequence: u32,
+    serial: Option<String>,
+    device_type: Option<String>,
+}
+
+impl CaptureWriter {
+    /// Create the capture directory if needed. No file is opened until the
+    /// first write.
+    pub fn new(config: CaptureConfig) -> io::Result<Self> {
+        fs::create_dir_all(&config.dir)?;
+        Ok(Self { config, current: None, files: Vec::new(), sequence: 0, serial: None, device_type: None })
+    }
+
+    /// Record the device in the metadata of the current and later files
+    pub fn set_device(&mut self, info: &DeviceInformation) -> io::Result<()> {
+        self.serial = Some(format!("{:08X}-{:08X}-{:08X}", info.serial_number_1, info.serial_number_2, info.serial_number_3));
+        self.device_type = Some(format!("{:?}", info.device_type));
+        if let Some(current) = &mut self.current {
+            current.metadata.serial = self.serial.clone();
+            current.metadata.device_type = self.device_type.clone();
+            fs::write(current.path.with_extension("meta"), current.metadata.render())?;
+        }
+        Ok(())
+    }
+
+    /// Append bytes received at `now`, rolling over first if the current
+    /// file is full or too old
+    pub fn write(&mut self, bytes: &[u8], now: SystemTime) -> io::Result<()> {
+        if bytes.is_empty() {
+            return Ok(());
+        }
+        if let Some(current) = &self.current {
+            let full = self.config.max_bytes
+                .is_some_and(|max| current.metadata.bytes > 0 && current.metadata.bytes + bytes.len() as u64 > max);
+            let old = self.config.max_age
+                .is_some_and(|max| now.duration_since(current.metadata.started).unwrap_or_default() >= max);
+            if full || old {
+                self.close_current()?;
+            }
+        }
+        if self.current.is_none() {
+            self.open(now)?;
+        }
+        let current = self.current.as_mut().expect("capture file was just opened");
+        current.file.write_all(bytes)?;
+        current.metadata.bytes += bytes.len() as u64;
+        Ok(())
+    }
+
+    /// Flush the current file and bring its metadata up to date
+    pub fn flush(&mut self) -> io::Result<()> {
+        if let Some(current) = &mut self.current {
+            current.file.flush()?;
+            fs::write(current.path.with_extension("meta"), current.metadata.render())?;
+        }
+        Ok(())
+    }
+
+    /// Capture files still on disk, oldest first
+    pub fn files(&self) -> &[PathBuf] {
+        &self.files
+    }
+
+    /// Close the current file and return every capture file still on disk
+    pub fn finish(mut self) -> io::Result<Vec<PathBuf>> {
+        self.close_current()?;
+        Ok(std::mem::take(&mut self.files))
+    }
+
+    fn open(&mut self, now: SystemTime) -> io::Result<()> {
+        let started = now.duration_since(UNIX_EPOCH).unwrap_or_default().as_secs();
+        // Never append to a capture left by another writer or an earlier run
+        let (path, file) = loop {
+            let path = self.config.dir.join(format!("{}-{}-{:04}.anpp", self.config.prefix, started, self.sequence));
+            self.sequence += 1;
+            match OpenOptions::new().write(true).create_new(true).open(&path) {
+                Ok(file) => break (path, file),
+                Err(e) if e.kind() == io::ErrorKind::AlreadyExists => continue,
+                Err(e) => return Err(e),
+            }
+        };
+        let metadata = CaptureMetadata {
+            started: now,
+            bytes: 0,
+            serial: self.serial.clone(),
+            device_type: self.device_type.clone(),
+        };
+        fs::write(path.with_extension("meta"), metadata.render())?;
+        self.files.push(path.clone());
+        self.current = Some(OpenFile { path, file: BufWriter::new(file), metadata });
+
+        if let Some(keep) = self.config.keep {
+            self.enforce_keep(keep.max(1))?;
+        }
+        Ok(())
+    }
+
+    /// Start time and sequence number of a capture file with our prefix
+    fn capture_key(&self, path: &Path) -> Option<(u64, u32)> {
+        let name = path.file_name()?.to_str()?;
+        let rest = name.strip_prefix(&self.config.prefix)?.strip_prefix('-')?.strip_suffix(".anpp")?;
+        let (started, sequence) = rest.split_once('-')?;
+        Some((started.parse().ok()?, sequence.parse().ok()?))
+    }
+
+    /// Delete the oldest capture files in the directory beyond `keep`
+    fn enforce_keep(&mut self, keep: usize) -> io::Result<()> {
+        let mut captures = Vec::new();
+        for entry in fs::read_dir(&self.config.dir)? {
+            let path = entry?.path();
+            if let Some(key) = self.capture_key(&path) {
+                captures.push((key, path));
+            }
+        }
+        captures.sort();
+        let excess = captures.len().saturating_sub(keep);
+        for (_, oldest) in captures.into_iter().take(excess) {
+            if self.current.as_ref().is_some_and(|current| current.path == oldest) {
+                continue;
+            }
+            fs::remove_file(&oldest)?;
+            let _ = fs::remove_file(oldest.with_extension("meta"));
+            self.files.retain(|path| *path != oldest);
+        }
+        Ok(())
+    }
+
+    fn close_current(&mut self) -> io::Result<()> {
+        if let Some(mut current) = self.current.take() {
+            current.file.flush()?;
+            fs::write(current.path.with_extension("meta"), current.metadata.render())?;
+        }
+        Ok(())
+    }
+}
+
+impl Drop for CaptureWriter {
+    fn drop(&mut self) {
+        let _ = self.close_current();
+    }
+}
+
+/// Reader that captures every byte read through it
+#[derive(Debug)]
+pub struct Tee<R: Read> {
+    inner: R,
+    writer: CaptureWriter,
+}
+
+impl<R: Read> Tee<R> {
+    pub fn new(inner: R, writer: CaptureWriter) -> Self {
+        Self { inner, writer }
+    }
+
+    pub fn writer_mut(&mut self) -> &mut CaptureWriter {
+        &mut self.writer
+    }
+
+    pub fn into_parts(self) -> (R, CaptureWriter) {
+        (self.inner, self.writer)
+    }
+}
+
+impl<R: Read> Read for Tee<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let n = self.inner.read(buf)?;
+        self.writer.write(&buf[..n], SystemTime::now())?;
+        Ok(n)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::packet::system::DeviceType;
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("liban-capture-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        dir
+    }
+
+    #[test]
+    fn test_size_rollover_and_retention() {
+        let dir = temp_dir("size");
+        let config = CaptureConfig::new(&dir).with_max_bytes(Some(10)).with_max_age(None).with_keep(2);
+        let mut writer = CaptureWriter::new(config).unwrap();
+        let start = UNIX_EPOCH + Duration::from_secs(1_700_000_000);
+        for chunk in [&[1u8; 6][..], &[2; 4], &[3; 6], &[4; 20], &[5; 1]] {
+            writer.write(chunk, start).unwrap();
+        }
+        let files = writer.finish().unwrap();
+
+        // [1;6][2;4] | [3;6] | [4;20] | [5;1], keeping the last two
+        assert_eq!(files.len(), 2);
+        assert_eq!(fs::read(&files[0]).unwrap(), vec![4; 20]);
+        assert_eq!(fs::read(&files[1]).unwrap(), vec![5]);
+        assert!(files[1].to_string_lossy().ends_with("capture-1700000000-0003.anpp"));
+        assert_eq!(fs::read_dir(&dir).unwrap().count(), 4);
+        assert_eq!(CaptureMetadata::load(files[0].with_extension("meta")).unwrap().bytes, 20);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_age_rollover_and_metadata() {
+        let dir = temp_dir("age");
+        let config = CaptureConfig::new(&dir).with_prefix("boreas").with_max_age(Some(Duration::from_secs(60)));
+        let mut writer = CaptureWriter::new(config).unwrap();
+        let start = UNIX_EPOCH + Duration::from_millis(1_700_000_000_250);
+        writer.write(b"abc", start).unwrap();
+        writer.set_device(&DeviceInformation {
+            software_version: 7000,
+            device_type: DeviceType::BoreasD90,
+            hardware_revision: 1,
+            serial_number_1: 1,
+            serial_number_2: 2,
+            serial_number_3: 3,
+        }).unwrap();
+        writer.write(b"def", start + Duration::from_secs(59)).unwrap();
+        writer.write(b"ghi", start + Duration::from_secs(60)).unwrap();
+        let files = writer.finish().unwrap();
+
+        assert_eq!(files.len(), 2);
+        assert_eq!(fs::read(&files[0]).unwrap(), b"abcdef");
+        let first = CaptureMetadata::load(files[0].with_extension("meta")).unwrap();
+        assert_eq!(first.started, start);
+        assert_eq!(first.bytes, 6);
+        assert_eq!(first.serial.as_deref(), Some("00000001-00000002-00000003"));
+        assert_eq!(first.device_type.as_deref(), Some("BoreasD90"));
+        let second = CaptureMetadata::load(files[1].with_extension("meta")).unwrap();
+        assert_eq!(second.serial, first.serial);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_tee_captures_what_it_reads() {
+        let dir = temp_dir("tee");
+        let writer = CaptureWriter::new(CaptureConfig::new(&dir)).unwrap();
+        let mut tee = Tee::new(&b"0123456789"[..], writer);
+        let mut buffer = [0u8; 4];
+        let mut read = Vec::new();
+        loop {
+            let n = tee.read(&mut buffer).unwrap();
+            if n == 0 {
+                break;
+            }
+            read.extend_from_slice(&buffer[..n]);
+        }
+        let (_, writer) = tee.into_parts();
+        let files = writer.finish().unwrap();
+        assert_eq!(fs::read(&files[0]).unwrap(), read);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_rollover_never_reuses_an_existing_file() {
+        let dir = temp_dir("collision");
+        fs::create_dir_all(&dir).unwrap();
+        let existing = dir.join("capture-1700000000-0000.anpp");
+        fs::write(&existing, b"earlier run").unwrap();
+
+        let mut writer = CaptureWriter::new(CaptureConfig::new(&dir)).unwrap();
+        writer.write(b"new", UNIX_EPOCH + Duration::from_secs(1_700_000_000)).unwrap();
+        let files = writer.finish().unwrap();
+
+        assert_eq!(fs::read(&existing).unwrap(), b"earlier run");
+        assert!(files[0].to_string_lossy().ends_with("capture-1700000000-0001.anpp"));
+        assert_eq!(fs::read(&files[0]).unwrap(), b"new");
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_retention_counts_earlier_runs() {
+        let dir = temp_dir("scan");
+        fs::create_dir_all(&dir).unwrap();
+        for name in ["capture-1600000000-0000", "capture-1600000000-0001"] {
+            fs::write(dir.join(name).with_extension("anpp"), b"old").unwrap();
+            fs::write(dir.join(name).with_extension("meta"), b"bytes=3\n").unwrap();
+        }
+        fs::write(dir.join("other-1500000000-0000.anpp"), b"not ours").unwrap();
+
+        let config = CaptureConfig::new(&dir).with_max_bytes(Some(4)).with_max_age(None).with_keep(2);
+        let mut writer = CaptureWriter::new(config).unwrap();
+        writer.write(b"abcd", UNIX_EPOCH + Duration::from_secs(1_700_000_000)).unwrap();
+        assert!(!dir.join("capture-1600000000-0000.anpp").exists());
+        assert!(dir.join("capture-1600000000-0001.anpp").exists());
+        writer.write(b"efgh", UNIX_EPOCH + Duration::from_secs(1_700_000_001)).unwrap();
+        let files = writer.finish().unwrap();
+
+        assert_eq!(files.len(), 2);
+        assert!(!dir.join("capture-1600000000-0001.anpp").exists());
+        assert!(!dir.join("capture-1600000000-0001.meta").exists());
+        assert!(dir.join("other-1500000000-0000.anpp").exists());
+        assert_eq!(fs::read_dir(&dir).unwrap().count(), 5);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
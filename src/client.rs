@@ -0,0 +1,1562 @@
+//! Resilient single-device client.
+//!
+//! [`Client`] layers the connection policy every long-running service needs
+//! on top of the parser: requests and configuration writes are retried
+//! until answered, reconnects back off exponentially, a watchdog pings
+//! Status (23) and drops a link that stops answering, and connection
+//! changes and device reboots are published as [`ClientEvent`]s on
+//! channels. After [`Client::handshake`] the client knows the device's
+//! [`DeviceCapabilities`] and refuses requests and writes it could never
+//! answer. With [`Client::with_flow_control`], writes of rate limited kinds
+//! such as external aiding go out no faster than their [`FlowControl`]
+//! allows.
+//!
+//! Like the rest of the crate it is sans-io. The caller opens and closes
+//! the transport when told to, writes the bytes from
+//! [`Client::poll_transmit`], feeds received bytes to [`Client::handle`]
+//! and supplies the current time.
+
+use std::fmt;
+use std::sync::Arc;
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::time::{Duration, Instant};
+
+use crate::capabilities::DeviceCapabilities;
+use crate::clock::Clock;
+use crate::error::AnError;
+use crate::flow::FlowControl;
+use crate::packet::system::{AcknowledgeResult, DeviceInformation, IpConfiguration, Ipv4Subnet, Request, Subcomponent};
+use crate::packet::{Packet, PacketKind};
+use crate::parser::{AnppParser, ParserStats};
+use crate::packet::config::{FilterIssue, InstallationAlignment};
+use crate::packet::state::AntennaOffsetEstimation;
+use crate::ops::{AntennaOffsetCalibration, AntennaOffsetFailure, AntennaOffsetState};
+use crate::provision::{ApplyOptions, ApplyReport, ConfigApply, ConfigRead, ConfigSet, WriteOutcome};
+use crate::telemetry::DeviceUptime;
+
+/// How often unanswered requests and writes are sent again
+#[derive(Debug, Clone, PartialEq)]
+pub struct RetryPolicy {
+    /// Sends before giving up, including the first
+    pub max_attempts: u32,
+    /// Wait for an answer before sending again
+    pub timeout: Duration,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self { max_attempts: 3, timeout: Duration::from_secs(1) }
+    }
+}
+
+/// Whether writes may set the `permanent` flag.
+///
+/// Permanent writes wear the device's flash and change how it boots, so
+/// fleet tooling can refuse them outright or ask an operator first.
+#[derive(Clone, Default)]
+pub enum WritePolicy {
+    #[default]
+    AllowPermanent,
+    /// Refuse every permanent write with [`AnError::PermanentWriteDenied`]
+    DenyPermanent,
+    /// Ask the callback about each permanent write; `false` refuses it
+    PromptViaCallback(Arc<dyn Fn(&Packet) -> bool + Send + Sync>),
+}
+
+impl WritePolicy {
+    /// Check a write about to be queued
+    pub fn check(&self, packet: &Packet) -> Result<(), AnError> {
+        let allowed = !packet.is_permanent() || match self {
+            WritePolicy::AllowPermanent => true,
+            WritePolicy::DenyPermanent => false,
+            WritePolicy::PromptViaCallback(prompt) => prompt(packet),
+        };
+        if allowed { Ok(()) } else { Err(AnError::PermanentWriteDenied { id: packet.packet_id() }) }
+    }
+}
+
+impl fmt::Debug for WritePolicy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            WritePolicy::AllowPermanent => f.write_str("AllowPermanent"),
+            WritePolicy::DenyPermanent => f.write_str("DenyPermanent"),
+            WritePolicy::PromptViaCallback(_) => f.write_str("PromptViaCallback(..)"),
+        }
+    }
+}
+
+/// Token required by [`Client::set_ip_configuration`], issued only for an
+/// IP configuration that keeps the device reachable from the host's subnet.
+///
+/// Its `Display` form previews the change, so it doubles as a dry run.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ConfirmNetworkChange {
+    configuration: IpConfiguration,
+    current: Ipv4Subnet,
+}
+
+impl ConfirmNetworkChange {
+    /// Check `configuration` with
+    /// [`IpConfiguration::validate_reachable_from`] against `current`, the
+    /// subnet the host reaches the device on
+    pub fn new(configuration: &IpConfiguration, current: Ipv4Subnet) -> Result<Self, AnError> {
+        configuration.validate_reachable_from(current)?;
+        Ok(Self { configuration: configuration.clone(), current })
+    }
+
+    pub fn configuration(&self) -> &IpConfiguration {
+        &self.configuration
+    }
+}
+
+impl fmt::Display for ConfirmNetworkChange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let ip = &self.configuration;
+        if ip.dhcp_enabled() {
+            write!(f, "Switch the device to DHCP")?;
+        } else {
+            write!(f, "Move the device to {} on {}", ip.ip_address, ip.subnet())?;
+            if !ip.ip_gateway.is_unspecified() {
+                write!(f, " via gateway {}", ip.ip_gateway)?;
+            }
+        }
+        write!(f, ", reached from {}", self.current)?;
+        if ip.permanent {
+            write!(f, " (permanent)")?;
+        }
+        Ok(())
+    }
+}
+
+/// Exponential delay between reconnect attempts
+#[derive(Debug, Clone, PartialEq)]
+pub struct Backoff {
+    /// Delay after the first failure
+    pub initial: Duration,
+    /// Upper bound on the delay
+    pub max: Duration,
+    /// Growth factor per further failure
+    pub multiplier: f64,
+}
+
+impl Default for Backoff {
+    fn default() -> Self {
+        Self {
+            initial: Duration::from_millis(250),
+            max: Duration::from_secs(30),
+            multiplier: 2.0,
+        }
+    }
+}
+
+impl Backoff {
+    /// Delay before reconnect attempt `attempt`, counting from zero
+    pub fn delay(&self, attempt: u32) -> Duration {
+        let factor = self.multiplier.max(1.0).powi(attempt.min(i32::MAX as u32) as i32);
+        let delay = self.initial.as_secs_f64() * factor;
+        if delay.is_finite() && delay < self.max.as_secs_f64() {
+            Duration::from_secs_f64(delay)
+        } else {
+            self.max
+        }
+    }
+}
+
+/// Something that happened to the link or the device
+#[derive(Debug, Clone, PartialEq)]
+pub enum ClientEvent {
+    Connected,
+    /// The transport was lost or the watchdog gave up on the device
+    Disconnected,
+    /// Running Time fell behind the host clock, so the device restarted
+    DeviceRebooted,
+    /// A request or write was not answered after every retry
+    RequestFailed(PacketKind),
+    /// The device acknowledged a write with a failure
+    WriteRejected(PacketKind, AcknowledgeResult),
+    /// [`Client::read_full_config`] finished; kinds that went unanswered
+    /// are in [`ConfigSet::missing`]
+    ConfigRead(ConfigSet),
+    /// [`Client::apply_full_config`] finished
+    ConfigApplied(ApplyReport),
+    /// The antenna offset estimate reached the calibration's standard
+    /// deviation limit; [`Client::confirm_antenna_offset`] stores it
+    AntennaOffsetConverged(AntennaOffsetEstimation),
+    /// The estimated offset was written into InstallationAlignment
+    AntennaOffsetCalibrated(InstallationAlignment),
+    AntennaOffsetFailed(AntennaOffsetFailure),
+    /// Device Information and Subcomponent Information answered
+    /// [`Client::handshake`], or the latter went unanswered
+    Handshake(DeviceCapabilities),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Awaiting {
+    /// Any packet of this kind answers a request
+    Response(PacketKind),
+    /// An acknowledgement of this kind answers a write
+    Acknowledge(PacketKind),
+}
+
+#[derive(Debug, Clone)]
+struct Transaction {
+    frame: Vec<u8>,
+    awaiting: Awaiting,
+    attempts: u32,
+    sent_at: Option<Instant>,
+    /// Open from the first send until the transaction is answered or fails
+    #[cfg(feature = "tracing")]
+    span: tracing::Span,
+}
+
+impl Transaction {
+    fn new(frame: Vec<u8>, awaiting: Awaiting) -> Self {
+        Self {
+            #[cfg(feature = "tracing")]
+            span: tracing::debug_span!("anpp_transaction", ?awaiting),
+            frame,
+            awaiting,
+            attempts: 0,
+            sent_at: None,
+        }
+    }
+}
+
+/// Answers collected by a running [`Client::handshake`]
+#[derive(Debug, Clone, Default)]
+struct Handshake {
+    device: Option<DeviceInformation>,
+    /// Empty once the request has given up
+    subcomponents: Option<Vec<Subcomponent>>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Link {
+    Connected { last_status: Instant, last_ping: Option<Instant> },
+    Disconnected { attempt: u32, retry_at: Option<Instant> },
+}
+
+/// Retrying, self-monitoring client for one device
+///
+/// # Examples
+///
+/// ```
+/// use std::time::Instant;
+/// use liban::client::{Client, ClientEvent};
+/// use liban::PacketKind;
+///
+/// let mut client = Client::new();
+/// let events = client.subscribe();
+///
+/// let now = Instant::now();
+/// if client.poll_reconnect(now) {
+///     // open the transport, then:
+///     client.connected(now);
+/// }
+/// client.handshake();
+/// client.request(PacketKind::Status).unwrap();
+/// let bytes = client.poll_transmit(now); // write to the device
+/// assert!(!bytes.is_empty());
+/// // for each read: client.handle(&buf[..n], Instant::now());
+///
+/// assert_eq!(events.try_recv(), Ok(ClientEvent::Connected));
+/// ```
+pub struct Client {
+    parser: AnppParser,
+    retry: RetryPolicy,
+    backoff: Backoff,
+    write_policy: WritePolicy,
+    flow: FlowControl,
+    watchdog_interval: Duration,
+    watchdog_timeout: Duration,
+    link: Link,
+    pending: Vec<Transaction>,
+    uptime: DeviceUptime,
+    config_read: Option<ConfigRead>,
+    config_apply: Option<ConfigApply>,
+    antenna_offset: Option<AntennaOffsetCalibration>,
+    capabilities: Option<DeviceCapabilities>,
+    handshake: Option<Handshake>,
+    subscribers: Vec<Sender<ClientEvent>>,
+}
+
+impl Default for Client {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Client {
+    pub fn new() -> Self {
+        Self {
+            parser: AnppParser::new(),
+            retry: RetryPolicy::default(),
+            backoff: Backoff::default(),
+            write_policy: WritePolicy::default(),
+            flow: FlowControl::new(),
+            watchdog_interval: Duration::from_secs(1),
+            watchdog_timeout: Duration::from_secs(3),
+            link: Link::Disconnected { attempt: 0, retry_at: None },
+            pending: Vec::new(),
+            uptime: DeviceUptime::new(),
+            config_read: None,
+            config_apply: None,
+            antenna_offset: None,
+            capabilities: None,
+            handshake: None,
+            subscribers: Vec::new(),
+        }
+    }
+
+    pub fn with_retry(mut self, retry: RetryPolicy) -> Self {
+        self.retry = retry;
+        self
+    }
+
+    pub fn with_backoff(mut self, backoff: Backoff) -> Self {
+        self.backoff = backoff;
+        self
+    }
+
+    pub fn with_write_policy(mut self, policy: WritePolicy) -> Self {
+        self.write_policy = policy;
+        self
+    }
+
+    /// Rate limit writes per packet kind. A write waits in the queue until
+    /// its kind has a token, and [`send`](Self::send) refuses a write with
+    /// [`AnError::Backpressure`] while [`FlowLimit::max_queued`] of its kind
+    /// are already waiting. A rejected write empties its kind's bucket.
+    ///
+    /// [`FlowLimit::max_queued`]: crate::flow::FlowLimit::max_queued
+    pub fn with_flow_control(mut self, flow: FlowControl) -> Self {
+        self.flow = flow;
+        self
+    }
+
+    /// Ping Status every `interval` and drop the link when no Status has
+    /// arrived for `timeout`
+    pub fn with_watchdog(mut self, interval: Duration, timeout: Duration) -> Self {
+        self.watchdog_interval = interval;
+        self.watchdog_timeout = timeout;
+        self
+    }
+
+    /// Channel receiving every event from now on
+    pub fn subscribe(&mut self) -> Receiver<ClientEvent> {
+        let (sender, receiver) = mpsc::channel();
+        self.subscribers.push(sender);
+        receiver
+    }
+
+    pub fn is_connected(&self) -> bool {
+        matches!(self.link, Link::Connected { .. })
+    }
+
+    /// Whether the caller should try to open the transport now
+    pub fn poll_reconnect(&self, clock: impl Clock) -> bool {
+        let now = clock.now();
+        match self.link {
+            Link::Connected { .. } => false,
+            Link::Disconnected { retry_at, .. } => retry_at.is_none_or(|at| now >= at),
+        }
+    }
+
+    /// The transport was opened
+    pub fn connected(&mut self, clock: impl Clock) {
+        let now = clock.now();
+        if self.is_connected() {
+            return;
+        }
+        self.parser.clear();
+        #[cfg(feature = "tracing")]
+        if let Link::Disconnected { attempt, .. } = self.link {
+            tracing::info!(attempt, pending = self.pending.len(), "device link connected");
+        }
+        self.link = Link::Connected { last_status: now, last_ping: None };
+        self.emit(ClientEvent::Connected);
+    }
+
+    /// Opening the transport failed; the next attempt is backed off
+    pub fn connect_failed(&mut self, clock: impl Clock) {
+        let now = clock.now();
+        if let Link::Disconnected { attempt, .. } = self.link {
+            #[cfg(feature = "tracing")]
+            tracing::warn!(attempt, retry_in = ?self.backoff.delay(attempt), "device link connect failed");
+            self.link = Link::Disconnected {
+                attempt: attempt.saturating_add(1),
+                retry_at: Some(now + self.backoff.delay(attempt)),
+            };
+        }
+    }
+
+    /// The transport was lost. Unanswered requests are sent again after
+    /// reconnecting.
+    pub fn disconnected(&mut self, clock: impl Clock) {
+        let now = clock.now();
+        if !self.is_connected() {
+            return;
+        }
+        self.parser.clear();
+        for transaction in &mut self.pending {
+            transaction.sent_at = None;
+        }
+        #[cfg(feature = "tracing")]
+        tracing::warn!(pending = self.pending.len(), retry_in = ?self.backoff.delay(0), "device link disconnected");
+        self.link = Link::Disconnected { attempt: 1, retry_at: Some(now + self.backoff.delay(0)) };
+        self.emit(ClientEvent::Disconnected);
+    }
+
+    /// Identify the device: request Device Information and Subcomponent
+    /// Information and derive its [`DeviceCapabilities`], published as
+    /// [`ClientEvent::Handshake`]. Firmware without Subcomponent
+    /// Information only delays the event until that request gives up. From
+    /// then on requests and writes of packets the device lacks fail with
+    /// [`AnError::UnsupportedByDevice`]. Repeat it after reconnecting if the
+    /// link may now reach a different device.
+    pub fn handshake(&mut self) {
+        self.handshake = Some(Handshake::default());
+        for kind in [PacketKind::DeviceInformation, PacketKind::SubcomponentInformation] {
+            let frame = Packet::Request(Request::single(kind))
+                .to_bytes()
+                .expect("single packet request always encodes");
+            self.pending.push(Transaction::new(frame, Awaiting::Response(kind)));
+        }
+    }
+
+    /// Capabilities from the last Device Information received
+    pub fn capabilities(&self) -> Option<&DeviceCapabilities> {
+        self.capabilities.as_ref()
+    }
+
+    /// Ask the device for a packet, retrying until one of that kind arrives.
+    /// Write only packets are never sent by the device, so requesting one
+    /// logs a warning and the request eventually gives up. Fails if the
+    /// handshake found the device lacks the packet.
+    pub fn request(&mut self, kind: PacketKind) -> Result<(), AnError> {
+        self.check_supported(kind)?;
+        if !kind.direction().is_readable() {
+            #[cfg(feature = "tracing")]
+            tracing::warn!("Requested write only packet {:?}, the device will not answer", kind);
+        }
+        let frame = Packet::Request(Request::single(kind))
+            .to_bytes()
+            .expect("single packet request always encodes");
+        self.pending.push(Transaction::new(frame, Awaiting::Response(kind)));
+        Ok(())
+    }
+
+    fn check_supported(&self, kind: PacketKind) -> Result<(), AnError> {
+        self.capabilities.as_ref().map_or(Ok(()), |capabilities| capabilities.check(kind))
+    }
+
+    /// Write a packet, retrying until the device acknowledges it. Read only
+    /// packets are refused with [`AnError::NotWritable`] rather than sent
+    /// for the device to reject, as are packets the handshake found the
+    /// device lacks, and permanent writes are checked against the
+    /// [`WritePolicy`]. IP configuration goes through
+    /// [`set_ip_configuration`](Self::set_ip_configuration) instead.
+    pub fn send(&mut self, packet: &Packet) -> Result<(), AnError> {
+        if let Packet::IpConfiguration(_) = packet {
+            return Err(AnError::ValidationFailed(
+                "IP configuration must be written with set_ip_configuration".to_string()));
+        }
+        self.write(packet)
+    }
+
+    /// Write the IP configuration the token was issued for. The device
+    /// moves to the new address once it acknowledges, so expect the link
+    /// to drop.
+    pub fn set_ip_configuration(&mut self, confirm_network_change: ConfirmNetworkChange) -> Result<(), AnError> {
+        self.write(&Packet::IpConfiguration(confirm_network_change.configuration))
+    }
+
+    /// Whether [`send`](Self::send) would refuse a packet of `kind` because
+    /// its flow control queue is full
+    pub fn backpressure(&self, kind: PacketKind) -> bool {
+        self.flow.limit(kind).is_some_and(|limit| self.waiting(kind) >= limit.max_queued)
+    }
+
+    /// Writes of `kind` not sent yet
+    fn waiting(&self, kind: PacketKind) -> usize {
+        self.pending.iter().filter(|t| t.awaiting == Awaiting::Acknowledge(kind) && t.sent_at.is_none()).count()
+    }
+
+    fn write(&mut self, packet: &Packet) -> Result<(), AnError> {
+        let frame = packet.to_bytes()?;
+        let kind = PacketKind::from(packet.packet_id());
+        self.check_supported(kind)?;
+        self.write_policy.check(packet)?;
+        self.queue_write(kind, frame)
+    }
+
+    /// Queue an encoded write behind its kind's flow control limit
+    fn queue_write(&mut self, kind: PacketKind, frame: Vec<u8>) -> Result<(), AnError> {
+        if self.backpressure(kind) {
+            return Err(AnError::Backpressure { id: kind.packet_id() });
+        }
+        self.pending.push(Transaction::new(frame, Awaiting::Acknowledge(kind)));
+        Ok(())
+    }
+
+    /// An IP configuration in a set needs the token issued for it, and must
+    /// still keep the device reachable from the token's subnet
+    fn check_network_change(set: &ConfigSet, options: &ApplyOptions) -> Result<(), AnError> {
+        let Some(Packet::IpConfiguration(ip)) = set.get(PacketKind::IpConfiguration) else {
+            return Ok(());
+        };
+        let Some(confirm) = &options.network_change else {
+            return Err(AnError::ValidationFailed(
+                "IP configuration in a set needs a ConfirmNetworkChange in the apply options".to_string()));
+        };
+        // The permanent override is the caller's to make
+        let issued = IpConfiguration { permanent: ip.permanent, ..confirm.configuration.clone() };
+        if issued != *ip {
+            return Err(AnError::ValidationFailed(
+                "ConfirmNetworkChange was issued for a different IP configuration".to_string()));
+        }
+        ip.validate_reachable_from(confirm.current)
+    }
+
+    /// Request every configuration packet. [`ClientEvent::ConfigRead`]
+    /// carries the set once each kind has been answered or given up on;
+    /// kinds the handshake found the device lacks are given up on at once.
+    pub fn read_full_config(&mut self) {
+        let mut read = ConfigRead::new();
+        for kind in read.outstanding().to_vec() {
+            if self.request(kind).is_err() {
+                read.failed(kind);
+            }
+        }
+        self.config_read = Some(read);
+        self.advance_config();
+    }
+
+    /// Validate a configuration set and, unless it is a dry run, write it
+    /// in stages (see [`provision`](crate::provision)).
+    /// [`ClientEvent::ConfigApplied`] reports each write once the last
+    /// stage is answered.
+    ///
+    /// Fails without writing anything if a packet does not validate or is
+    /// not supported by the device, the set holds an IP configuration
+    /// without its [`ConfirmNetworkChange`] in the options, the
+    /// [`WritePolicy`] refuses a permanent write or an apply is already
+    /// running; filter option warnings are returned. A dry run only prompts
+    /// the policy callback for real writes. Staged writes are subject to
+    /// flow control like [`send`](Self::send), and one refused for
+    /// backpressure is reported as [`WriteOutcome::Backpressure`].
+    pub fn apply_full_config(&mut self, set: &ConfigSet, options: &ApplyOptions) -> Result<Vec<FilterIssue>, AnError> {
+        let warnings = set.validate(options.device)?;
+        Self::check_network_change(set, options)?;
+        set.iter().try_for_each(|packet| self.check_supported(PacketKind::from(packet.packet_id())))?;
+        let apply = ConfigApply::new(set, options);
+        if options.dry_run {
+            if matches!(self.write_policy, WritePolicy::DenyPermanent) {
+                apply.packets().try_for_each(|packet| self.write_policy.check(packet))?;
+            }
+            return Ok(warnings);
+        }
+        if self.config_apply.is_some() {
+            return Err(AnError::ValidationFailed("a configuration apply is already running".to_string()));
+        }
+        apply.packets().try_for_each(|packet| self.write_policy.check(packet))?;
+        self.config_apply = Some(apply);
+        self.advance_config();
+        Ok(warnings)
+    }
+
+    /// Start a GNSS antenna offset calibration (see
+    /// [`AntennaOffsetCalibration`]). The estimate is polled with the
+    /// watchdog ping; [`ClientEvent::AntennaOffsetConverged`] reports when it
+    /// is good enough to [`confirm`](Self::confirm_antenna_offset).
+    ///
+    /// Fails if a calibration is already running, or if the calibration
+    /// stores permanently and the [`WritePolicy`] denies permanent writes.
+    pub fn calibrate_antenna_offset(&mut self, mut calibration: AntennaOffsetCalibration) -> Result<(), AnError> {
+        if self.antenna_offset.as_ref().is_some_and(|running| !running.is_finished()) {
+            return Err(AnError::ValidationFailed("an antenna offset calibration is already running".to_string()));
+        }
+        if calibration.permanent() && matches!(self.write_policy, WritePolicy::DenyPermanent) {
+            return Err(AnError::PermanentWriteDenied { id: PacketKind::InstallationAlignment.packet_id() });
+        }
+        let start = calibration.start();
+        self.write(&start)?;
+        self.antenna_offset = Some(calibration);
+        Ok(())
+    }
+
+    /// Store the converged antenna offset estimate in InstallationAlignment.
+    /// [`ClientEvent::AntennaOffsetCalibrated`] reports the written packet.
+    pub fn confirm_antenna_offset(&mut self) -> Result<(), AnError> {
+        let request = self.antenna_offset.as_mut().and_then(AntennaOffsetCalibration::confirm)
+            .ok_or_else(|| AnError::ValidationFailed("no converged antenna offset to confirm".to_string()))?;
+        self.send_calibration(request);
+        Ok(())
+    }
+
+    /// Abort the antenna offset calibration and stop estimation
+    pub fn cancel_antenna_offset(&mut self) {
+        if let Some(calibration) = self.antenna_offset.as_mut().filter(|running| !running.is_finished()) {
+            let stop = calibration.cancel();
+            self.send_calibration(stop);
+            self.emit(ClientEvent::AntennaOffsetFailed(AntennaOffsetFailure::Cancelled));
+        }
+    }
+
+    /// The current or last antenna offset calibration
+    pub fn antenna_offset_calibration(&self) -> Option<&AntennaOffsetCalibration> {
+        self.antenna_offset.as_ref()
+    }
+
+    /// Requests and writes still waiting for an answer
+    pub fn pending(&self) -> usize {
+        self.pending.len()
+    }
+
+    /// Decoder counters for the device link
+    pub fn parser_stats(&self) -> &ParserStats {
+        self.parser.stats()
+    }
+
+    /// Device uptime from the Running Time answers to watchdog pings
+    pub fn uptime(&self) -> &DeviceUptime {
+        &self.uptime
+    }
+
+    /// Bytes to write to the device now: first sends, retries and watchdog
+    /// pings. Also runs the watchdog, so call it regularly.
+    pub fn poll_transmit(&mut self, clock: impl Clock) -> Vec<u8> {
+        let now = clock.now();
+        let Link::Connected { last_status, last_ping } = self.link else {
+            return Vec::new();
+        };
+
+        if now.saturating_duration_since(last_status) > self.watchdog_timeout {
+            #[cfg(feature = "tracing")]
+            tracing::debug!("No Status for {:?}, dropping link", now.saturating_duration_since(last_status));
+            self.disconnected(now);
+            return Vec::new();
+        }
+
+        let mut out = Vec::new();
+        let mut failed = Vec::new();
+        let retry = &self.retry;
+        let flow = &mut self.flow;
+        self.pending.retain_mut(|transaction| {
+            let due = transaction.sent_at.is_none_or(|sent| now.saturating_duration_since(sent) >= retry.timeout);
+            if !due {
+                return true;
+            }
+            if transaction.attempts >= retry.max_attempts {
+                #[cfg(feature = "tracing")]
+                transaction.span.in_scope(|| tracing::warn!(attempts = transaction.attempts, "no answer, giving up"));
+                failed.push(transaction.awaiting);
+                return false;
+            }
+            if let Awaiting::Acknowledge(kind) = transaction.awaiting
+                && flow.try_acquire(kind, now).is_err()
+            {
+                return true;
+            }
+            transaction.attempts += 1;
+            #[cfg(feature = "tracing")]
+            transaction.span.in_scope(|| tracing::debug!(attempt = transaction.attempts, "sent"));
+            transaction.sent_at = Some(now);
+            out.extend_from_slice(&transaction.frame);
+            true
+        });
+        for awaiting in failed {
+            match awaiting {
+                Awaiting::Response(kind) => {
+                    if let Some(read) = &mut self.config_read {
+                        read.failed(kind);
+                    }
+                    match (kind, &mut self.handshake) {
+                        (PacketKind::DeviceInformation, handshake) => *handshake = None,
+                        (PacketKind::SubcomponentInformation, Some(handshake)) => {
+                            handshake.subcomponents.get_or_insert_with(Vec::new);
+                        }
+                        _ => {}
+                    }
+                    self.emit(ClientEvent::RequestFailed(kind));
+                }
+                Awaiting::Acknowledge(kind) => {
+                    if let Some(apply) = &mut self.config_apply {
+                        apply.answered(kind, WriteOutcome::NoAnswer);
+                    }
+                    self.emit(ClientEvent::RequestFailed(kind));
+                }
+            }
+        }
+        self.advance_config();
+        self.advance_handshake();
+
+        if last_ping.is_none_or(|ping| now.saturating_duration_since(ping) >= self.watchdog_interval) {
+            let mut kinds = vec![PacketKind::Status, PacketKind::RunningTime];
+            if self.antenna_offset.as_ref().is_some_and(|calibration| calibration.status_request().is_some()) {
+                kinds.push(PacketKind::AntennaOffsetEstimation);
+            }
+            let ping = Packet::Request(Request::multiple(kinds));
+            out.extend(ping.to_bytes().expect("request always encodes"));
+            self.link = Link::Connected { last_status, last_ping: Some(now) };
+        }
+        out
+    }
+
+    /// When [`poll_transmit`](Self::poll_transmit) or
+    /// [`poll_reconnect`](Self::poll_reconnect) next has something to do
+    pub fn next_deadline(&self) -> Option<Instant> {
+        match self.link {
+            Link::Disconnected { retry_at, .. } => retry_at,
+            Link::Connected { last_status, last_ping } => {
+                let ping = last_ping.map_or(last_status, |ping| ping + self.watchdog_interval);
+                // A write whose rate limit never frees a token has no deadline
+                let retries = self.pending.iter().filter_map(|t| {
+                    let due = t.sent_at.map_or(last_status, |sent| sent + self.retry.timeout);
+                    match t.awaiting {
+                        Awaiting::Acknowledge(kind) => self.flow.ready_at(kind, due).map(|ready| due.max(ready)),
+                        Awaiting::Response(_) => Some(due),
+                    }
+                });
+                retries.chain([ping, last_status + self.watchdog_timeout]).min()
+            }
+        }
+    }
+
+    /// Feed bytes received from the device and return the decoded packets
+    pub fn handle(&mut self, input: &[u8], clock: impl Clock) -> Vec<Packet> {
+        let now = clock.now();
+        let mut decoded = Vec::new();
+        let mut next = self.parser.consume(input);
+        while let Some(packet) = next {
+            decoded.push(packet);
+            next = self.parser.consume(&[]);
+        }
+        for packet in &decoded {
+            self.record(packet, now);
+        }
+        decoded
+    }
+
+    fn record(&mut self, packet: &Packet, now: Instant) {
+        self.advance_calibration(packet);
+        match packet {
+            Packet::Status(_) => {
+                if let Link::Connected { last_ping, .. } = self.link {
+                    self.link = Link::Connected { last_status: now, last_ping };
+                }
+            }
+            Packet::DeviceInformation(device) => {
+                self.capabilities = Some(DeviceCapabilities::from_device_information(device));
+                if let Some(handshake) = &mut self.handshake {
+                    handshake.device = Some(device.clone());
+                }
+                self.advance_handshake();
+            }
+            Packet::SubcomponentInformation(info) => {
+                if let Some(handshake) = &mut self.handshake {
+                    handshake.subcomponents = Some(info.subcomponents.clone());
+                }
+                self.advance_handshake();
+            }
+            Packet::RunningTime(running) => {
+                let reboot = self.uptime.update(running, now);
+                if reboot.is_some() {
+                    self.emit(ClientEvent::DeviceRebooted);
+                }
+            }
+            Packet::Acknowledge(ack) => {
+                // Prefer the write whose payload CRC the device echoed, so
+                // several writes of one kind in flight resolve in the right
+                // order; devices that echo no usable CRC fall back to the oldest.
+                // Writes flow control still holds back cannot have been acknowledged.
+                let awaiting = Awaiting::Acknowledge(ack.acknowledged_packet);
+                let in_flight = |t: &Transaction| t.awaiting == awaiting && t.sent_at.is_some();
+                let index = self.pending.iter().position(|t| in_flight(t) && ack.matches(&t.frame))
+                    .or_else(|| self.pending.iter().position(in_flight));
+                if let Some(index) = index {
+                    let _transaction = self.pending.remove(index);
+                    #[cfg(feature = "tracing")]
+                    _transaction.span.in_scope(|| tracing::debug!(result = ?ack.result, "acknowledged"));
+                    if !ack.is_success() {
+                        self.flow.rejected(ack.acknowledged_packet, now);
+                        self.emit(ClientEvent::WriteRejected(ack.acknowledged_packet, ack.result));
+                    }
+                    if let Some(apply) = &mut self.config_apply {
+                        let outcome = if ack.is_success() { WriteOutcome::Acknowledged } else { WriteOutcome::Rejected(ack.result) };
+                        apply.answered(ack.acknowledged_packet, outcome);
+                    }
+                    self.advance_config();
+                }
+                return;
+            }
+            _ => {}
+        }
+
+        let awaiting = Awaiting::Response(PacketKind::from(packet.packet_id()));
+        self.pending.retain(|t| {
+            if t.awaiting != awaiting {
+                return true;
+            }
+            #[cfg(feature = "tracing")]
+            t.span.in_scope(|| tracing::debug!(attempts = t.attempts, "answered"));
+            false
+        });
+        if let Some(read) = &mut self.config_read {
+            read.received(packet);
+        }
+        self.advance_config();
+    }
+
+    /// Queue the next apply stage and publish finished reads and applies
+    fn advance_config(&mut self) {
+        if let Some(mut apply) = self.config_apply.take() {
+            loop {
+                let stage = apply.next_stage();
+                let mut refused = 0;
+                for packet in &stage {
+                    let kind = PacketKind::from(packet.packet_id());
+                    let frame = packet.to_bytes().expect("validated before the apply started");
+                    if self.queue_write(kind, frame).is_err() {
+                        apply.answered(kind, WriteOutcome::Backpressure);
+                        refused += 1;
+                    }
+                }
+                // A stage refused outright is answered, so move on at once
+                if stage.is_empty() || refused < stage.len() {
+                    break;
+                }
+            }
+            match apply.finished() {
+                Some(report) => self.emit(ClientEvent::ConfigApplied(report)),
+                None => self.config_apply = Some(apply),
+            }
+        }
+        if let Some(set) = self.config_read.as_mut().and_then(ConfigRead::finished) {
+            self.config_read = None;
+            self.emit(ClientEvent::ConfigRead(set));
+        }
+    }
+
+    /// Publish the handshake once both answers are in
+    fn advance_handshake(&mut self) {
+        let Some(Handshake { device: Some(device), subcomponents: Some(subcomponents) }) = &self.handshake else {
+            return;
+        };
+        let capabilities = DeviceCapabilities::from_device_information(device).with_subcomponents(subcomponents.clone());
+        self.capabilities = Some(capabilities.clone());
+        self.handshake = None;
+        self.emit(ClientEvent::Handshake(capabilities));
+    }
+
+    /// Feed the antenna offset calibration, send what it asks for and
+    /// publish its progress
+    fn advance_calibration(&mut self, packet: &Packet) {
+        let Some(calibration) = self.antenna_offset.as_mut().filter(|running| !running.is_finished()) else {
+            return;
+        };
+        let was_converged = matches!(calibration.state(), AntennaOffsetState::Converged(_));
+        if let Some(reply) = calibration.handle(packet) {
+            self.send_calibration(reply);
+        }
+        let Some(state) = self.antenna_offset.as_ref().map(|calibration| calibration.state().clone()) else {
+            return;
+        };
+        match state {
+            AntennaOffsetState::Converged(estimate) if !was_converged => self.emit(ClientEvent::AntennaOffsetConverged(estimate)),
+            AntennaOffsetState::Complete(alignment) => self.emit(ClientEvent::AntennaOffsetCalibrated(alignment)),
+            AntennaOffsetState::Failed(failure) => self.emit(ClientEvent::AntennaOffsetFailed(failure)),
+            _ => {}
+        }
+    }
+
+    /// Queue a packet from the antenna offset calibration. Requests and
+    /// writes the client refuses end the calibration.
+    fn send_calibration(&mut self, packet: Packet) {
+        let sent = match packet {
+            Packet::Request(request) => request.requested_packets.iter().try_for_each(|kind| self.request(*kind)),
+            packet => self.write(&packet),
+        };
+        if let Err(_error) = sent {
+            #[cfg(feature = "tracing")]
+            tracing::warn!("Antenna offset calibration refused: {}", _error);
+            if let Some(calibration) = &mut self.antenna_offset {
+                let stop = calibration.save_denied();
+                let _ = self.write(&stop);
+            }
+        }
+    }
+
+    fn emit(&mut self, event: ClientEvent) {
+        self.subscribers.retain(|subscriber| subscriber.send(event.clone()).is_ok());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clock::ManualClock;
+    use crate::packet::state::{FilterStatus, RunningTime, Status, SystemStatus};
+    use crate::packet::system::Acknowledge;
+    use crate::protocol::AnppProtocol;
+    use crate::packet::config::{FilterOptions, PacketTimerPeriod, VehicleType};
+    use crate::sim::{MockBoreas, Trajectory};
+    use crate::units::{Angle, Length};
+    use crate::wire;
+
+    fn frame(packet: &Packet) -> Vec<u8> {
+        let payload = wire::encode_payload(packet).unwrap();
+        wire::encode_frame(wire::PacketId::new(packet.packet_id()), &payload).unwrap()
+    }
+
+    fn status_packet() -> Packet {
+        Packet::Status(Status { system_status: SystemStatus::default(), filter_status: FilterStatus::default(), extension: None })
+    }
+
+    fn running_time(seconds: u32) -> Vec<u8> {
+        frame(&Packet::RunningTime(RunningTime { seconds, microseconds: 0 }))
+    }
+
+    fn connected(now: Instant) -> (Client, Receiver<ClientEvent>) {
+        let mut client = Client::new()
+            .with_retry(RetryPolicy { max_attempts: 2, timeout: Duration::from_millis(100) })
+            .with_watchdog(Duration::from_millis(500), Duration::from_secs(2));
+        let events = client.subscribe();
+        assert!(client.poll_reconnect(now));
+        client.connected(now);
+        assert_eq!(events.try_recv(), Ok(ClientEvent::Connected));
+        (client, events)
+    }
+
+    #[test]
+    fn test_backoff_grows_to_max() {
+        let backoff = Backoff { initial: Duration::from_millis(100), max: Duration::from_secs(1), multiplier: 2.0 };
+        assert_eq!(backoff.delay(0), Duration::from_millis(100));
+        assert_eq!(backoff.delay(3), Duration::from_millis(800));
+        assert_eq!(backoff.delay(4), Duration::from_secs(1));
+        assert_eq!(backoff.delay(u32::MAX), Duration::from_secs(1));
+    }
+
+    #[test]
+    fn test_request_retried_until_answered() {
+        let start = Instant::now();
+        let (mut client, events) = connected(start);
+        client.request(PacketKind::DeviceInformation).unwrap();
+
+        // First poll sends the request and the watchdog ping
+        assert!(!client.poll_transmit(start).is_empty());
+        assert!(client.poll_transmit(start + Duration::from_millis(50)).is_empty());
+        assert!(!client.poll_transmit(start + Duration::from_millis(100)).is_empty());
+
+        // Out of attempts
+        assert!(client.poll_transmit(start + Duration::from_millis(200)).is_empty());
+        assert_eq!(client.pending(), 0);
+        assert_eq!(events.try_recv(), Ok(ClientEvent::RequestFailed(PacketKind::DeviceInformation)));
+
+        client.request(PacketKind::Status).unwrap();
+        client.poll_transmit(start + Duration::from_millis(300));
+        let packets = client.handle(&frame(&status_packet()), start + Duration::from_millis(310));
+        assert_eq!(packets.len(), 1);
+        assert_eq!(client.pending(), 0);
+    }
+
+    #[test]
+    fn test_manual_clock_drives_retries() {
+        let clock = ManualClock::new();
+        let (mut client, events) = connected(clock.now());
+        client.request(PacketKind::DeviceInformation).unwrap();
+
+        assert!(!client.poll_transmit(&clock).is_empty());
+        clock.advance(Duration::from_millis(99));
+        assert!(client.poll_transmit(&clock).is_empty());
+        clock.advance(Duration::from_millis(1));
+        assert!(!client.poll_transmit(&clock).is_empty());
+        clock.advance(Duration::from_millis(100));
+        client.poll_transmit(&clock);
+        assert_eq!(events.try_recv(), Ok(ClientEvent::RequestFailed(PacketKind::DeviceInformation)));
+    }
+
+    #[test]
+    fn test_write_waits_for_acknowledge() {
+        let start = Instant::now();
+        let (mut client, events) = connected(start);
+        let period = PacketTimerPeriod::from_rate_hz(1000.0).unwrap();
+        client.send(&Packet::PacketTimerPeriod(period)).unwrap();
+        // Status is read only, so it is refused before reaching the queue
+        assert!(matches!(client.send(&status_packet()), Err(AnError::NotWritable { id: 23 })));
+        assert_eq!(client.pending(), 1);
+        client.poll_transmit(start);
+
+        let ack = Packet::Acknowledge(Acknowledge {
+            acknowledged_packet: PacketKind::PacketTimerPeriod,
+            packet_crc: 0,
+            result: AcknowledgeResult::ValuesOutOfRange,
+        });
+        client.handle(&frame(&ack), start);
+        assert_eq!(client.pending(), 0);
+        assert_eq!(events.try_recv(),
+            Ok(ClientEvent::WriteRejected(PacketKind::PacketTimerPeriod, AcknowledgeResult::ValuesOutOfRange)));
+    }
+
+    fn sent(client: &mut Client, now: Instant) -> Vec<Packet> {
+        let bytes = client.poll_transmit(now);
+        let mut parser = AnppParser::new();
+        let mut packets = Vec::new();
+        let mut next = parser.consume(&bytes);
+        while let Some(packet) = next {
+            packets.push(packet);
+            next = parser.consume(&[]);
+        }
+        packets
+    }
+
+    fn acknowledge(kind: PacketKind) -> Vec<u8> {
+        frame(&Packet::Acknowledge(Acknowledge { acknowledged_packet: kind, packet_crc: 0, result: AcknowledgeResult::Success }))
+    }
+
+    #[test]
+    fn test_antenna_offset_calibration() {
+        use crate::packet::config::OffsetVector;
+
+        let start = Instant::now();
+        let (mut client, events) = connected(start);
+        client.calibrate_antenna_offset(AntennaOffsetCalibration::new()).unwrap();
+        assert!(client.calibrate_antenna_offset(AntennaOffsetCalibration::new()).is_err());
+        assert!(client.confirm_antenna_offset().is_err());
+
+        // The start command goes out, and the watchdog ping polls the estimate
+        let packets = sent(&mut client, start);
+        assert_eq!(packets[0], Packet::AntennaOffsetEstimation(AntennaOffsetEstimation::start()));
+        assert_eq!(packets[1], Packet::Request(Request::multiple([
+            PacketKind::Status, PacketKind::RunningTime, PacketKind::AntennaOffsetEstimation,
+        ])));
+        client.handle(&acknowledge(PacketKind::AntennaOffsetEstimation), start);
+
+        let estimate = AntennaOffsetEstimation {
+            active: true,
+            converged: true,
+            offset: OffsetVector { x: 0.5, y: 0.0, z: -1.2 },
+            offset_std_dev: OffsetVector { x: 0.01, y: 0.01, z: 0.015 },
+        };
+        client.handle(&frame(&Packet::AntennaOffsetEstimation(estimate.clone())), start);
+        assert_eq!(events.try_recv(), Ok(ClientEvent::AntennaOffsetConverged(estimate.clone())));
+        client.handle(&frame(&Packet::AntennaOffsetEstimation(estimate.clone())), start);
+        assert!(events.try_recv().is_err());
+
+        client.confirm_antenna_offset().unwrap();
+        assert_eq!(sent(&mut client, start), vec![Packet::Request(Request::single(PacketKind::InstallationAlignment))]);
+        let current = InstallationAlignment::builder().build().unwrap();
+        client.handle(&frame(&Packet::InstallationAlignment(current)), start);
+        let Packet::InstallationAlignment(written) = sent(&mut client, start).remove(0) else {
+            panic!("expected an InstallationAlignment write");
+        };
+        assert_eq!(written.gnss_antenna_offset, estimate.offset);
+
+        client.handle(&acknowledge(PacketKind::InstallationAlignment), start);
+        assert_eq!(events.try_recv(), Ok(ClientEvent::AntennaOffsetCalibrated(written)));
+        assert_eq!(sent(&mut client, start), vec![Packet::AntennaOffsetEstimation(AntennaOffsetEstimation::stop())]);
+        assert!(client.antenna_offset_calibration().unwrap().is_finished());
+
+        let mut denied = Client::new().with_write_policy(WritePolicy::DenyPermanent);
+        assert!(matches!(
+            denied.calibrate_antenna_offset(AntennaOffsetCalibration::new()),
+            Err(AnError::PermanentWriteDenied { id: 185 })
+        ));
+        denied.calibrate_antenna_offset(AntennaOffsetCalibration::new().with_permanent(false)).unwrap();
+        denied.cancel_antenna_offset();
+        assert!(matches!(
+            denied.antenna_offset_calibration().unwrap().state(),
+            AntennaOffsetState::Failed(AntennaOffsetFailure::Cancelled)
+        ));
+    }
+
+    #[test]
+    fn test_acknowledge_correlated_by_crc() {
+        let start = Instant::now();
+        let (mut client, _events) = connected(start);
+        let first = Packet::PacketTimerPeriod(PacketTimerPeriod::from_rate_hz(1000.0).unwrap());
+        let second = Packet::PacketTimerPeriod(PacketTimerPeriod::from_rate_hz(500.0).unwrap());
+        client.send(&first).unwrap();
+        client.send(&second).unwrap();
+        client.poll_transmit(start);
+
+        let second_frame = second.to_bytes().unwrap();
+        let ack = Packet::Acknowledge(Acknowledge {
+            acknowledged_packet: PacketKind::PacketTimerPeriod,
+            packet_crc: AnppProtocol::calculate_crc16(&second_frame[5..]),
+            result: AcknowledgeResult::Success,
+        });
+        client.handle(&frame(&ack), start);
+        assert_eq!(client.pending(), 1);
+
+        // The first write is the one still waiting, so it is what gets retried
+        let retry = client.poll_transmit(start + Duration::from_millis(100));
+        let first_frame = first.to_bytes().unwrap();
+        assert!(retry.windows(first_frame.len()).any(|w| w == first_frame));
+        assert!(!retry.windows(second_frame.len()).any(|w| w == second_frame));
+    }
+
+    #[test]
+    fn test_write_policy_guards_permanent_writes() {
+        let now = Instant::now();
+        let (client, _events) = connected(now);
+        let mut client = client.with_write_policy(WritePolicy::DenyPermanent);
+        let mut timer = Packet::PacketTimerPeriod(PacketTimerPeriod::from_rate_hz(1000.0).unwrap());
+        client.send(&timer).unwrap();
+        assert!(timer.set_permanent(true));
+        assert!(matches!(client.send(&timer), Err(AnError::PermanentWriteDenied { id: 180 })));
+        assert_eq!(client.pending(), 1);
+
+        // The apply path checks after the permanent override
+        let set = ConfigSet::try_from(vec![timer.clone()]).unwrap();
+        assert!(client.apply_full_config(&set, &ApplyOptions::new().dry_run()).is_err());
+        assert!(client.apply_full_config(&set, &ApplyOptions::new().with_permanent(false)).is_ok());
+        assert_eq!(client.pending(), 2);
+
+        let prompted = Arc::new(std::sync::atomic::AtomicUsize::new(0));
+        let counter = prompted.clone();
+        let mut client = Client::new().with_write_policy(WritePolicy::PromptViaCallback(Arc::new(move |packet| {
+            counter.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+            packet.packet_id() == 180
+        })));
+        client.apply_full_config(&set, &ApplyOptions::new().dry_run()).unwrap();
+        client.send(&timer).unwrap();
+        let mut options = Packet::FilterOptions(FilterOptions {
+            permanent: true,
+            vehicle_type: VehicleType::Car,
+            internal_gnss_enabled: true,
+            atmospheric_altitude_enabled: false,
+            velocity_heading_enabled: false,
+            reversing_detection_enabled: false,
+            motion_analysis_enabled: false,
+        });
+        assert!(client.send(&options).is_err());
+        options.set_permanent(false);
+        client.send(&options).unwrap();
+        // Only the two permanent sends asked
+        assert_eq!(prompted.load(std::sync::atomic::Ordering::Relaxed), 2);
+    }
+
+    #[test]
+    fn test_ip_configuration_needs_confirmation() {
+        use std::net::Ipv4Addr;
+        let now = Instant::now();
+        let (mut client, _events) = connected(now);
+        let ip = IpConfiguration {
+            permanent: false,
+            dhcp_mode: 0,
+            ip_address: Ipv4Addr::new(192, 168, 42, 50),
+            ip_netmask: Ipv4Addr::new(255, 255, 255, 0),
+            ip_gateway: Ipv4Addr::new(192, 168, 42, 1),
+            dns_server: Ipv4Addr::UNSPECIFIED,
+            boreas_serial_number_part_1: 0,
+            boreas_serial_number_part_2: 0,
+            boreas_serial_number_part_3: 0,
+        };
+        assert!(client.send(&Packet::IpConfiguration(ip.clone())).is_err());
+
+        let current = Ipv4Subnet::with_prefix(Ipv4Addr::new(192, 168, 42, 42), 24);
+        let elsewhere = IpConfiguration { ip_address: Ipv4Addr::new(10, 0, 0, 5), ..ip.clone() };
+        assert!(ConfirmNetworkChange::new(&elsewhere, current).is_err());
+
+        let confirm = ConfirmNetworkChange::new(&ip, current).unwrap();
+        assert_eq!(confirm.to_string(),
+            "Move the device to 192.168.42.50 on 192.168.42.0/24 via gateway 192.168.42.1, reached from 192.168.42.0/24");
+        client.set_ip_configuration(confirm.clone()).unwrap();
+        assert_eq!(client.pending(), 1);
+
+        // A set holding the change is refused without the token, or with
+        // one issued for another configuration
+        let set = ConfigSet::try_from(vec![Packet::IpConfiguration(ip.clone())]).unwrap();
+        assert!(client.apply_full_config(&set, &ApplyOptions::new()).is_err());
+        assert!(client.apply_full_config(&set, &ApplyOptions::new().dry_run()).is_err());
+        let other = IpConfiguration { ip_address: Ipv4Addr::new(192, 168, 42, 60), ..ip.clone() };
+        let wrong = ConfirmNetworkChange::new(&other, current).unwrap();
+        assert!(client.apply_full_config(&set, &ApplyOptions::new().with_network_change(wrong)).is_err());
+        assert_eq!(client.pending(), 1);
+        let options = ApplyOptions::new().with_permanent(true).with_network_change(confirm);
+        client.apply_full_config(&set, &options).unwrap();
+        assert_eq!(client.pending(), 2);
+    }
+
+    #[test]
+    fn test_config_apply_respects_flow_control() {
+        use crate::flow::{FlowControl, FlowLimit};
+        let now = Instant::now();
+        let (client, events) = connected(now);
+        let mut client = client.with_flow_control(FlowControl::new().with_limit(PacketKind::PacketTimerPeriod, FlowLimit::new(10.0, 1).unwrap()));
+        let timer = Packet::PacketTimerPeriod(PacketTimerPeriod::from_rate_hz(1000.0).unwrap());
+        client.send(&timer).unwrap();
+        assert!(client.backpressure(PacketKind::PacketTimerPeriod));
+
+        let set = ConfigSet::try_from(vec![timer]).unwrap();
+        client.apply_full_config(&set, &ApplyOptions::new()).unwrap();
+        assert_eq!(client.pending(), 1);
+        let report = events.try_iter().find_map(|event| match event {
+            ClientEvent::ConfigApplied(report) => Some(report),
+            _ => None,
+        }).expect("apply finished at once");
+        assert_eq!(report.outcomes, vec![(PacketKind::PacketTimerPeriod, WriteOutcome::Backpressure)]);
+    }
+
+    /// Exchange bytes with the simulator until a configuration read or
+    /// apply finishes
+    fn drive_config(client: &mut Client, device: &mut MockBoreas, events: &Receiver<ClientEvent>, now: &mut Instant) -> ClientEvent {
+        for _ in 0..100 {
+            let reply = device.feed(&client.poll_transmit(*now));
+            client.handle(&reply, *now);
+            if let Some(event) = events.try_iter()
+                .find(|event| matches!(event, ClientEvent::ConfigRead(_) | ClientEvent::ConfigApplied(_)))
+            {
+                return event;
+            }
+            *now += Duration::from_millis(50);
+        }
+        panic!("configuration operation did not finish");
+    }
+
+    #[test]
+    fn test_full_config_read_and_apply() {
+        let mut now = Instant::now();
+        let (mut client, events) = connected(now);
+        let mut device = MockBoreas::new(Trajectory::Static {
+            latitude: Angle::from_degrees(-33.87),
+            longitude: Angle::from_degrees(151.21),
+            height: Length::from_meters(10.0),
+        });
+
+        client.read_full_config();
+        let ClientEvent::ConfigRead(mut set) = drive_config(&mut client, &mut device, &events, &mut now) else {
+            unreachable!()
+        };
+        // The simulator only knows its factory configuration
+        assert_eq!(set.iter().map(Packet::packet_id).collect::<Vec<_>>(), vec![180, 182, 186]);
+        assert!(set.missing().contains(&PacketKind::IpConfiguration));
+
+        let Some(Packet::FilterOptions(options)) = set.get(PacketKind::FilterOptions).cloned() else {
+            unreachable!()
+        };
+        set.insert(Packet::FilterOptions(FilterOptions { vehicle_type: VehicleType::Car, ..options })).unwrap();
+
+        // A dry run queues nothing
+        client.apply_full_config(&set, &ApplyOptions::new().dry_run()).unwrap();
+        assert_eq!(client.pending(), 0);
+
+        client.apply_full_config(&set, &ApplyOptions::new().with_permanent(false)).unwrap();
+        assert!(client.apply_full_config(&set, &ApplyOptions::new()).is_err());
+        let ClientEvent::ConfigApplied(report) = drive_config(&mut client, &mut device, &events, &mut now) else {
+            unreachable!()
+        };
+        assert!(report.is_success(), "{:?}", report);
+        // Baud rates go in the stage after the settings
+        assert_eq!(report.outcomes.last().unwrap().0, PacketKind::BaudRates);
+        assert!(matches!(device.configuration(PacketKind::FilterOptions),
+            Some(Packet::FilterOptions(FilterOptions { vehicle_type: VehicleType::Car, permanent: false, .. }))));
+
+        // A rejected setting stops the link changes
+        device.reject(PacketKind::FilterOptions, AcknowledgeResult::ValuesOutOfRange);
+        client.apply_full_config(&set, &ApplyOptions::new()).unwrap();
+        let ClientEvent::ConfigApplied(report) = drive_config(&mut client, &mut device, &events, &mut now) else {
+            unreachable!()
+        };
+        assert!(report.outcomes.contains(&(PacketKind::FilterOptions, WriteOutcome::Rejected(AcknowledgeResult::ValuesOutOfRange))));
+        assert!(report.outcomes.contains(&(PacketKind::BaudRates, WriteOutcome::Skipped)));
+    }
+
+    #[test]
+    fn test_watchdog_drops_silent_link_and_backs_off() {
+        let start = Instant::now();
+        let (mut client, events) = connected(start);
+        client.request(PacketKind::DeviceInformation).unwrap();
+        client.poll_transmit(start);
+
+        // Status keeps the link alive
+        client.handle(&frame(&status_packet()), start + Duration::from_millis(1500));
+        client.poll_transmit(start + Duration::from_secs(3));
+        assert!(client.is_connected());
+
+        client.poll_transmit(start + Duration::from_millis(3600));
+        assert!(!client.is_connected());
+        assert_eq!(events.try_recv(), Ok(ClientEvent::Disconnected));
+
+        let lost = start + Duration::from_millis(3600);
+        assert!(!client.poll_reconnect(lost));
+        assert_eq!(client.next_deadline(), Some(lost + Duration::from_millis(250)));
+        client.connect_failed(lost + Duration::from_millis(250));
+        assert_eq!(client.next_deadline(), Some(lost + Duration::from_millis(750)));
+
+        // The unanswered request is sent again on the new link
+        client.connected(lost + Duration::from_millis(750));
+        assert_eq!(client.pending(), 1);
+        assert!(!client.poll_transmit(lost + Duration::from_millis(750)).is_empty());
+    }
+
+    #[test]
+    fn test_handshake_refuses_unsupported_packets() {
+        use crate::capabilities::DevicePort;
+        use crate::packet::config::{DualAntennaConfiguration, AutomaticOffsetOrientation};
+        use crate::packet::system::{DeviceInformation, DeviceType, SubcomponentInformation};
+
+        let now = Instant::now();
+        let (mut client, events) = connected(now);
+        client.handshake();
+        assert_eq!(sent(&mut client, now)[..2], [
+            Packet::Request(Request::single(PacketKind::DeviceInformation)),
+            Packet::Request(Request::single(PacketKind::SubcomponentInformation)),
+        ]);
+        assert!(client.request(PacketKind::RawGnss).is_ok(), "nothing is refused before the handshake");
+
+        let device = DeviceInformation {
+            software_version: 5010,
+            device_type: DeviceType::Orientus,
+            hardware_revision: 1000,
+            serial_number_1: 1,
+            serial_number_2: 2,
+            serial_number_3: 3,
+        };
+        let subcomponent = Subcomponent {
+            software_version: 2105,
+            device_id: 1,
+            hardware_revision: 110,
+            serial_number_1: 4,
+            serial_number_2: 5,
+            serial_number_3: 6,
+        };
+        client.handle(&frame(&Packet::DeviceInformation(device)), now);
+        assert!(events.try_recv().is_err(), "handshake waits for Subcomponent Information");
+        let subcomponents = SubcomponentInformation { subcomponents: vec![subcomponent.clone()] };
+        client.handle(&frame(&Packet::SubcomponentInformation(subcomponents)), now);
+        let Ok(ClientEvent::Handshake(capabilities)) = events.try_recv() else {
+            panic!("expected the handshake event");
+        };
+        assert_eq!(Some(&capabilities), client.capabilities());
+        assert_eq!(capabilities.subcomponents, vec![subcomponent]);
+        assert_eq!(capabilities.ports, vec![DevicePort::PrimarySerial, DevicePort::AuxiliarySerial]);
+
+        assert!(matches!(client.request(PacketKind::Satellites), Err(AnError::UnsupportedByDevice { id: 30 })));
+        let dual = Packet::DualAntennaConfiguration(DualAntennaConfiguration::automatic(AutomaticOffsetOrientation::PrimaryFrontSecondaryRear));
+        assert!(matches!(client.send(&dual), Err(AnError::UnsupportedByDevice { id: 196 })));
+        assert!(matches!(
+            client.calibrate_antenna_offset(AntennaOffsetCalibration::new()),
+            Err(AnError::UnsupportedByDevice { id: 62 })
+        ));
+
+        // Configuration the device lacks is not asked for
+        let pending = client.pending();
+        client.read_full_config();
+        let requested = client.pending() - pending;
+        assert_eq!(requested, ConfigSet::kinds().filter(|kind| capabilities.supports(*kind)).count());
+        assert!(requested < ConfigSet::kinds().count());
+    }
+
+    #[test]
+    fn test_handshake_without_subcomponent_information() {
+        use crate::packet::system::{DeviceInformation, DeviceModel, DeviceType};
+
+        let mut now = Instant::now();
+        let (mut client, events) = connected(now);
+        client.handshake();
+        let device = DeviceInformation {
+            software_version: 5010,
+            device_type: DeviceType::BoreasD90,
+            hardware_revision: 1000,
+            serial_number_1: 1,
+            serial_number_2: 2,
+            serial_number_3: 3,
+        };
+        client.poll_transmit(now);
+        client.handle(&frame(&Packet::DeviceInformation(device)), now);
+        for _ in 0..3 {
+            now += Duration::from_millis(100);
+            client.handle(&frame(&status_packet()), now);
+            client.poll_transmit(now);
+        }
+        assert_eq!(events.try_recv(), Ok(ClientEvent::RequestFailed(PacketKind::SubcomponentInformation)));
+        let Ok(ClientEvent::Handshake(capabilities)) = events.try_recv() else {
+            panic!("expected the handshake event");
+        };
+        assert_eq!(capabilities.model, DeviceModel::BoreasD90);
+        assert!(capabilities.subcomponents.is_empty());
+    }
+
+    #[test]
+    fn test_detects_reboot() {
+        let start = Instant::now();
+        let (mut client, events) = connected(start);
+        client.handle(&running_time(100), start);
+        client.handle(&running_time(101), start);
+        assert!(events.try_recv().is_err());
+
+        client.handle(&running_time(2), start);
+        assert_eq!(events.try_recv(), Ok(ClientEvent::DeviceRebooted));
+    }
+
+    #[cfg(feature = "tracing")]
+    #[test]
+    fn test_transaction_spans_and_link_events() {
+        use std::sync::{Arc, Mutex};
+        use tracing::field::{Field, Visit};
+        use tracing::span::{Attributes, Id, Record};
+        use tracing::{Event, Metadata};
+
+        /// Records span names and "span: message" for each event
+        #[derive(Clone, Default)]
+        struct Recorder {
+            spans: Arc<Mutex<Vec<&'static str>>>,
+            events: Arc<Mutex<Vec<String>>>,
+            current: Arc<Mutex<Vec<u64>>>,
+        }
+
+        struct Message(String);
+
+        impl Visit for Message {
+            fn record_debug(&mut self, field: &Field, value: &dyn std::fmt::Debug) {
+                if field.name() == "message" {
+                    self.0 = format!("{:?}", value);
+                }
+            }
+        }
+
+        impl tracing::Subscriber for Recorder {
+            fn enabled(&self, _: &Metadata<'_>) -> bool { true }
+            fn new_span(&self, span: &Attributes<'_>) -> Id {
+                let mut spans = self.spans.lock().unwrap();
+                spans.push(span.metadata().name());
+                Id::from_u64(spans.len() as u64)
+            }
+            fn record(&self, _: &Id, _: &Record<'_>) {}
+            fn record_follows_from(&self, _: &Id, _: &Id) {}
+            fn event(&self, event: &Event<'_>) {
+                let mut message = Message(String::new());
+                event.record(&mut message);
+                let span = self.current.lock().unwrap().last().map_or("-", |_| "anpp_transaction");
+                self.events.lock().unwrap().push(format!("{}: {}", span, message.0));
+            }
+            fn enter(&self, span: &Id) { self.current.lock().unwrap().push(span.into_u64()); }
+            fn exit(&self, _: &Id) { self.current.lock().unwrap().pop(); }
+        }
+
+        let recorder = Recorder::default();
+        tracing::subscriber::with_default(recorder.clone(), || {
+            let start = Instant::now();
+            let (mut client, _events) = connected(start);
+            client.request(PacketKind::Status).unwrap();
+            client.poll_transmit(start);
+            client.handle(&frame(&status_packet()), start);
+            client.disconnected(start);
+        });
+
+        assert_eq!(*recorder.spans.lock().unwrap(), vec!["anpp_transaction"]);
+        let events = recorder.events.lock().unwrap();
+        assert!(events.contains(&"anpp_transaction: sent".to_string()));
+        assert!(events.contains(&"anpp_transaction: answered".to_string()));
+        assert!(events.contains(&"-: decoded packet".to_string()));
+        assert!(events.contains(&"-: device link disconnected".to_string()));
+    }
+
+    #[test]
+    fn test_flow_control_paces_aiding() {
+        use crate::flow::{FlowControl, FlowLimit};
+        use crate::packet::state::ExternalHeading;
+        use crate::units::Radians;
+
+        let start = Instant::now();
+        let (client, _events) = connected(start);
+        let mut client = client.with_flow_control(FlowControl::new().with_limit(PacketKind::ExternalHeading, FlowLimit::new(10.0, 1).unwrap()));
+        let heading = |degrees: f32| Packet::ExternalHeading(ExternalHeading {
+            heading: Radians(degrees.to_radians()),
+            standard_deviation: Radians(0.01),
+        });
+
+        client.send(&heading(1.0)).unwrap();
+        assert!(client.backpressure(PacketKind::ExternalHeading));
+        assert!(matches!(client.send(&heading(2.0)), Err(AnError::Backpressure { id: 48 })));
+        assert!(sent(&mut client, start).contains(&heading(1.0)));
+        client.handle(&acknowledge(PacketKind::ExternalHeading), start);
+
+        // The next one waits for a token, and other writes are not held up
+        client.send(&heading(3.0)).unwrap();
+        client.send(&Packet::PacketTimerPeriod(PacketTimerPeriod::from_rate_hz(1000.0).unwrap())).unwrap();
+        let at = start + Duration::from_millis(20);
+        let packets = sent(&mut client, at);
+        assert!(!packets.contains(&heading(3.0)));
+        assert!(packets.iter().any(|packet| matches!(packet, Packet::PacketTimerPeriod(_))));
+        assert!(client.next_deadline().unwrap() <= start + Duration::from_millis(100));
+        assert!(sent(&mut client, start + Duration::from_millis(100)).contains(&heading(3.0)));
+
+        // A rejection holds the next write back a full interval
+        let nak = Packet::Acknowledge(Acknowledge {
+            acknowledged_packet: PacketKind::ExternalHeading,
+            packet_crc: 0,
+            result: AcknowledgeResult::SystemNotReady,
+        });
+        let rejected_at = start + Duration::from_millis(250);
+        client.handle(&frame(&nak), rejected_at);
+        client.send(&heading(4.0)).unwrap();
+        assert!(!sent(&mut client, rejected_at + Duration::from_millis(50)).contains(&heading(4.0)));
+        assert!(sent(&mut client, rejected_at + Duration::from_millis(100)).contains(&heading(4.0)));
+    }
+
+    #[test]
+    fn test_flow_control_with_a_vanishing_rate() {
+        use crate::flow::{FlowControl, FlowLimit};
+
+        let start = Instant::now();
+        let (client, _events) = connected(start);
+        let limit = FlowLimit::new(1e-300, 1).unwrap();
+        let mut client = client.with_flow_control(FlowControl::new().with_limit(PacketKind::PacketTimerPeriod, limit));
+        let timer = Packet::PacketTimerPeriod(PacketTimerPeriod::from_rate_hz(1000.0).unwrap());
+        client.send(&timer).unwrap();
+        assert!(sent(&mut client, start).contains(&timer));
+        client.handle(&acknowledge(PacketKind::PacketTimerPeriod), start);
+
+        // The next token is further off than an Instant reaches
+        client.send(&timer).unwrap();
+        assert!(client.next_deadline().is_some());
+        assert!(!sent(&mut client, start + Duration::from_secs(1)).contains(&timer));
+        client.handle(&frame(&Packet::Acknowledge(Acknowledge {
+            acknowledged_packet: PacketKind::PacketTimerPeriod,
+            packet_crc: 0,
+            result: AcknowledgeResult::SystemNotReady,
+        })), start + Duration::from_secs(2));
+        assert!(client.next_deadline().is_some());
+    }
+
+    #[test]
+    fn test_acknowledge_skips_writes_held_by_flow_control() {
+        use crate::flow::{FlowControl, FlowLimit};
+        use crate::packet::state::ExternalHeading;
+        use crate::units::Radians;
+
+        let start = Instant::now();
+        let (client, events) = connected(start);
+        let mut client = client.with_flow_control(FlowControl::new().with_limit(PacketKind::ExternalHeading, FlowLimit::new(10.0, 1).unwrap()));
+        let heading = |degrees: f32| Packet::ExternalHeading(ExternalHeading {
+            heading: Radians(degrees.to_radians()),
+            standard_deviation: Radians(0.01),
+        });
+        let ack_for = |packet: &Packet| Packet::Acknowledge(Acknowledge {
+            acknowledged_packet: PacketKind::ExternalHeading,
+            packet_crc: AnppProtocol::calculate_crc16(&packet.to_bytes().unwrap()[5..]),
+            result: AcknowledgeResult::Success,
+        });
+
+        client.send(&heading(1.0)).unwrap();
+        assert!(sent(&mut client, start).contains(&heading(1.0)));
+        client.send(&heading(2.0)).unwrap();
+        assert!(!sent(&mut client, start).contains(&heading(2.0)));
+
+        // Even an echo of the held write's CRC answers the one on the wire
+        client.handle(&frame(&ack_for(&heading(2.0))), start);
+        assert_eq!(client.pending(), 1);
+        // With nothing on the wire, a stray acknowledge resolves nothing
+        client.handle(&acknowledge(PacketKind::ExternalHeading), start);
+        assert_eq!(client.pending(), 1);
+
+        let at = start + Duration::from_millis(100);
+        assert!(sent(&mut client, at).contains(&heading(2.0)));
+        client.handle(&frame(&ack_for(&heading(2.0))), at);
+        assert_eq!(client.pending(), 0);
+        assert!(events.try_iter().all(|event| !matches!(event, ClientEvent::WriteRejected(..))));
+    }
+}
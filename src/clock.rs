@@ -0,0 +1,154 @@
+//! Time sources for the time-dependent state machines.
+//!
+//! [`Client`](crate::client::Client), [`AlarmWatcher`](crate::alarms::AlarmWatcher)
+//! and [`RateMonitor`](crate::rate::RateMonitor) take their time as an
+//! `impl Clock` argument. An [`Instant`] is a clock stopped at that instant,
+//! so passing `Instant::now()` keeps working; [`SystemClock`] reads the
+//! monotonic clock and [`ManualClock`] is moved by hand, by tests or by log
+//! replay following recorded timestamps.
+
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant, SystemTime};
+
+/// Source of the current monotonic time
+pub trait Clock {
+    fn now(&self) -> Instant;
+}
+
+impl Clock for Instant {
+    fn now(&self) -> Instant {
+        *self
+    }
+}
+
+impl<C: Clock + ?Sized> Clock for &C {
+    fn now(&self) -> Instant {
+        (**self).now()
+    }
+}
+
+/// The operating system's monotonic clock
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> Instant {
+        Instant::now()
+    }
+}
+
+#[derive(Debug)]
+struct ManualState {
+    now: Instant,
+    /// Recorded time and clock reading of the first replayed timestamp
+    anchor: Option<(SystemTime, Instant)>,
+}
+
+/// Clock that only moves when told to
+///
+/// Clones share the same time, so a test can keep one handle and give
+/// another to the code under test. The clock never runs backwards: moves
+/// to an earlier time are ignored.
+///
+/// # Examples
+///
+/// ```
+/// use std::time::Duration;
+/// use liban::clock::{Clock, ManualClock};
+///
+/// let clock = ManualClock::new();
+/// let start = clock.now();
+/// clock.advance(Duration::from_millis(250));
+/// assert_eq!(clock.now() - start, Duration::from_millis(250));
+/// ```
+#[derive(Debug, Clone)]
+pub struct ManualClock {
+    state: Arc<Mutex<ManualState>>,
+}
+
+impl Default for ManualClock {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ManualClock {
+    /// Starts at the current monotonic time
+    pub fn new() -> Self {
+        Self::starting_at(Instant::now())
+    }
+
+    pub fn starting_at(start: Instant) -> Self {
+        Self { state: Arc::new(Mutex::new(ManualState { now: start, anchor: None })) }
+    }
+
+    pub fn advance(&self, by: Duration) {
+        let mut state = self.state.lock().unwrap();
+        state.now += by;
+    }
+
+    /// Move to `to`, if it is not earlier than the current time
+    pub fn set(&self, to: Instant) {
+        let mut state = self.state.lock().unwrap();
+        state.now = state.now.max(to);
+    }
+
+    /// Follow recorded wall clock timestamps during replay. The first call
+    /// pins the recorded time to the current clock reading; later calls move
+    /// the clock by the recorded time elapsed since then.
+    pub fn follow(&self, recorded: SystemTime) {
+        let mut state = self.state.lock().unwrap();
+        let now = state.now;
+        let (base, start) = *state.anchor.get_or_insert((recorded, now));
+        if let Ok(elapsed) = recorded.duration_since(base) {
+            state.now = now.max(start + elapsed);
+        }
+    }
+}
+
+impl Clock for ManualClock {
+    fn now(&self) -> Instant {
+        self.state.lock().unwrap().now
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::UNIX_EPOCH;
+
+    #[test]
+    fn test_manual_clock_is_shared_and_monotonic() {
+        let start = Instant::now();
+        let clock = ManualClock::starting_at(start);
+        let handle = clock.clone();
+        handle.advance(Duration::from_secs(2));
+        assert_eq!(clock.now(), start + Duration::from_secs(2));
+        clock.set(start);
+        assert_eq!(handle.now(), start + Duration::from_secs(2));
+        clock.set(start + Duration::from_secs(5));
+        assert_eq!(Clock::now(&&handle), start + Duration::from_secs(5));
+        assert_eq!(start.now(), start);
+    }
+
+    #[test]
+    fn test_follow_recorded_time() {
+        let start = Instant::now();
+        let clock = ManualClock::starting_at(start);
+        let recorded = UNIX_EPOCH + Duration::from_secs(1_700_000_000);
+        clock.follow(recorded);
+        assert_eq!(clock.now(), start);
+        clock.follow(recorded + Duration::from_millis(1500));
+        assert_eq!(clock.now(), start + Duration::from_millis(1500));
+        // Out of order records do not move the clock back
+        clock.follow(recorded + Duration::from_millis(500));
+        clock.follow(recorded - Duration::from_secs(1));
+        assert_eq!(clock.now(), start + Duration::from_millis(1500));
+    }
+
+    #[test]
+    fn test_system_clock_advances() {
+        let before = Instant::now();
+        assert!(SystemClock.now() >= before);
+    }
+}
@@ -0,0 +1,264 @@
+//! Network discovery of Advanced Navigation devices.
+//!
+//! Discovery is plain ANPP: a broadcast Request packet asking for Device
+//! Information (ID 3) and IP Configuration (ID 11). Every device listening on the port
+//! answers with both packets, which [`DiscoveryCollector`] pairs up by sender.
+//!
+//! `scan` (feature `udp`) runs the exchange on a blocking std socket and
+//! `scan_async` (feature `tokio`) on a tokio socket.
+
+use std::collections::HashMap;
+use std::net::{IpAddr, SocketAddr};
+
+use crate::packet::{Packet, PacketId, PacketKind};
+use crate::packet::system::{DeviceInformation, DeviceType, IpConfiguration};
+use crate::parser::AnppParser;
+use crate::protocol::AnppProtocol;
+
+/// A device that answered a discovery request
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DiscoveredDevice {
+    pub device_id: DeviceType,
+    /// Serial number formatted as `XXXXXXXX-XXXXXXXX-XXXXXXXX`
+    pub serial: String,
+    /// Configured address if IP Configuration was received, otherwise the
+    /// address the response came from
+    pub ip: IpAddr,
+    /// Raw software version from Device Information
+    pub firmware: u32,
+}
+
+/// Build the discovery datagram
+pub fn discovery_request() -> Vec<u8> {
+    let ids = [PacketKind::DeviceInformation.packet_id(), PacketKind::IpConfiguration.packet_id()];
+    AnppProtocol::get_packet_bytes(PacketId::new(1), &ids)
+        .expect("two byte payload always fits")
+}
+
+#[derive(Default)]
+struct Responses {
+    information: Option<DeviceInformation>,
+    ip_configuration: Option<IpConfiguration>,
+}
+
+/// Collects discovery responses and turns them into [`DiscoveredDevice`]s
+#[derive(Default)]
+pub struct DiscoveryCollector {
+    responses: HashMap<SocketAddr, Responses>,
+}
+
+impl DiscoveryCollector {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Feed one received datagram
+    pub fn handle_datagram(&mut self, source: SocketAddr, datagram: &[u8]) {
+        let mut parser = AnppParser::new();
+        let mut input = datagram;
+        while let Some(packet) = parser.consume(input) {
+            input = &[];
+            self.handle_packet(source, packet);
+        }
+    }
+
+    /// Feed an already decoded packet
+    pub fn handle_packet(&mut self, source: SocketAddr, packet: Packet) {
+        match packet {
+            Packet::DeviceInformation(info) => {
+                self.responses.entry(source).or_default().information = Some(info);
+            }
+            Packet::IpConfiguration(ip) => {
+                self.responses.entry(source).or_default().ip_configuration = Some(ip);
+            }
+            _ => {}
+        }
+    }
+
+    /// Devices that have answered with Device Information, ordered by address
+    pub fn devices(&self) -> Vec<DiscoveredDevice> {
+        let mut devices: Vec<_> = self.responses.iter()
+            .filter_map(|(source, responses)| {
+                let info = responses.information.as_ref()?;
+                let ip = match &responses.ip_configuration {
+                    Some(ip) if !ip.ip_address.is_unspecified() => IpAddr::V4(ip.ip_address),
+                    _ => source.ip(),
+                };
+                Some(DiscoveredDevice {
+                    device_id: info.device_type,
+                    serial: format!("{:08X}-{:08X}-{:08X}",
+                                    info.serial_number_1, info.serial_number_2, info.serial_number_3),
+                    ip,
+                    firmware: info.software_version,
+                })
+            })
+            .collect();
+        devices.sort_by_key(|d| d.ip);
+        devices
+    }
+}
+
+/// Broadcast a discovery request and collect answers until `timeout` elapses
+#[cfg(feature = "udp")]
+pub fn scan(
+    broadcast: std::net::SocketAddrV4,
+    timeout: std::time::Duration,
+) -> std::io::Result<Vec<DiscoveredDevice>> {
+    use std::io::ErrorKind;
+    use std::net::{Ipv4Addr, UdpSocket};
+    use std::time::Instant;
+
+    let socket = UdpSocket::bind((Ipv4Addr::UNSPECIFIED, 0))?;
+    socket.set_broadcast(true)?;
+    socket.send_to(&discovery_request(), broadcast)?;
+
+    let deadline = Instant::now() + timeout;
+    let mut collector = DiscoveryCollector::new();
+    let mut buf = [0u8; 2048];
+
+    loop {
+        let remaining = deadline.saturating_duration_since(Instant::now());
+        if remaining.is_zero() {
+            break;
+        }
+        socket.set_read_timeout(Some(remaining))?;
+        match socket.recv_from(&mut buf) {
+            Ok((n, source)) => collector.handle_datagram(source, &buf[..n]),
+            Err(e) if matches!(e.kind(), ErrorKind::WouldBlock | ErrorKind::TimedOut) => break,
+            Err(e) => return Err(e),
+        }
+    }
+
+    Ok(collector.devices())
+}
+
+/// [`scan`] on tokio, without blocking the executor
+#[cfg(feature = "tokio")]
+pub async fn scan_async(
+    broadcast: std::net::SocketAddrV4,
+    timeout: std::time::Duration,
+) -> std::io::Result<Vec<DiscoveredDevice>> {
+    use std::net::Ipv4Addr;
+    use tokio::net::UdpSocket;
+    use tokio::time::{timeout_at, Instant};
+
+    let socket = UdpSocket::bind((Ipv4Addr::UNSPECIFIED, 0)).await?;
+    socket.set_broadcast(true)?;
+    socket.send_to(&discovery_request(), broadcast).await?;
+
+    let deadline = Instant::now() + timeout;
+    let mut collector = DiscoveryCollector::new();
+    let mut buf = [0u8; 2048];
+
+    while let Ok(received) = timeout_at(deadline, socket.recv_from(&mut buf)).await {
+        let (n, source) = received?;
+        collector.handle_datagram(source, &buf[..n]);
+    }
+
+    Ok(collector.devices())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::packet::system::Request;
+    use std::net::Ipv4Addr;
+
+    fn frame(packet: Packet) -> Vec<u8> {
+        let data = packet.payload_bytes().unwrap();
+        AnppProtocol::get_packet_bytes(PacketId::new(packet.packet_id()), &data).unwrap()
+    }
+
+    fn information(serial: u32) -> Packet {
+        Packet::DeviceInformation(DeviceInformation {
+            software_version: 0x0007_0001,
+            device_type: DeviceType::BoreasD90,
+            hardware_revision: 2,
+            serial_number_1: serial,
+            serial_number_2: 0,
+            serial_number_3: 1,
+        })
+    }
+
+    #[test]
+    fn test_discovery_request_contains_both_requests() {
+        let mut parser = AnppParser::new();
+        let request = parser.consume(&discovery_request()).unwrap();
+        assert_eq!(request, Packet::Request(Request::multiple([
+            PacketKind::DeviceInformation,
+            PacketKind::IpConfiguration,
+        ])));
+    }
+
+    #[test]
+    fn test_collector_pairs_responses_by_sender() {
+        let a: SocketAddr = "10.0.0.5:16718".parse().unwrap();
+        let b: SocketAddr = "10.0.0.6:16718".parse().unwrap();
+
+        let mut datagram = frame(information(0xAB));
+        datagram.extend(frame(Packet::IpConfiguration(IpConfiguration {
+            permanent: false,
+            dhcp_mode: 0,
+            ip_address: Ipv4Addr::new(192, 168, 1, 50),
+            ip_netmask: Ipv4Addr::UNSPECIFIED,
+            ip_gateway: Ipv4Addr::UNSPECIFIED,
+            dns_server: Ipv4Addr::UNSPECIFIED,
+            boreas_serial_number_part_1: 0,
+            boreas_serial_number_part_2: 0,
+            boreas_serial_number_part_3: 0,
+        })));
+
+        let mut collector = DiscoveryCollector::new();
+        collector.handle_datagram(a, &datagram);
+        collector.handle_datagram(b, &frame(information(0xCD)));
+
+        let devices = collector.devices();
+        assert_eq!(devices.len(), 2);
+        assert_eq!(devices[0].ip, IpAddr::V4(Ipv4Addr::new(10, 0, 0, 6)));
+        assert_eq!(devices[0].serial, "000000CD-00000000-00000001");
+        assert_eq!(devices[1].ip, IpAddr::V4(Ipv4Addr::new(192, 168, 1, 50)));
+        assert_eq!(devices[1].device_id, DeviceType::BoreasD90);
+        assert_eq!(devices[1].firmware, 0x0007_0001);
+    }
+
+    #[test]
+    fn test_ip_configuration_alone_is_not_a_device() {
+        let mut collector = DiscoveryCollector::new();
+        collector.handle_packet("10.0.0.5:1".parse().unwrap(), Packet::IpConfiguration(IpConfiguration {
+            permanent: false,
+            dhcp_mode: 1,
+            ip_address: Ipv4Addr::UNSPECIFIED,
+            ip_netmask: Ipv4Addr::UNSPECIFIED,
+            ip_gateway: Ipv4Addr::UNSPECIFIED,
+            dns_server: Ipv4Addr::UNSPECIFIED,
+            boreas_serial_number_part_1: 0,
+            boreas_serial_number_part_2: 0,
+            boreas_serial_number_part_3: 0,
+        }));
+        assert!(collector.devices().is_empty());
+    }
+
+    #[cfg(feature = "tokio")]
+    #[tokio::test]
+    async fn test_scan_async_collects_answers() {
+        use std::time::Duration;
+        use tokio::net::UdpSocket;
+
+        let device = UdpSocket::bind((Ipv4Addr::LOCALHOST, 0)).await.unwrap();
+        let SocketAddr::V4(address) = device.local_addr().unwrap() else {
+            unreachable!()
+        };
+        let answer = tokio::spawn(async move {
+            let mut buf = [0u8; 64];
+            let (n, host) = device.recv_from(&mut buf).await.unwrap();
+            assert_eq!(&buf[..n], discovery_request().as_slice());
+            device.send_to(&frame(information(0xAB)), host).await.unwrap();
+        });
+
+        let devices = scan_async(address, Duration::from_millis(200)).await.unwrap();
+        answer.await.unwrap();
+        assert_eq!(devices.len(), 1);
+        assert_eq!(devices[0].serial, "000000AB-00000000-00000001");
+        assert_eq!(devices[0].ip, IpAddr::V4(Ipv4Addr::LOCALHOST));
+    }
+}
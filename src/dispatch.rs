@@ -0,0 +1,170 @@
+//! Typed callbacks for decoded packets.
+//!
+//! Register a handler per packet type with [`Dispatcher::on`] and feed every
+//! decoded [`Packet`] to [`Dispatcher::dispatch`]. Each handler receives the
+//! packet's own type, so consumers no longer match on [`Packet`] themselves.
+//!
+//! ```
+//! use liban::dispatch::Dispatcher;
+//! use liban::{AnppParser, Packet, SystemState, UnixTime};
+//!
+//! let mut dispatcher = Dispatcher::new();
+//! dispatcher
+//!     .on::<SystemState>(|state| println!("at {:.6}", state.latitude_angle().degrees()))
+//!     .on::<UnixTime>(|time| println!("{}", time.unix_time_seconds));
+//!
+//! let mut parser = AnppParser::new();
+//! # let bytes: Vec<u8> = Vec::new();
+//! while let Some(packet) = parser.consume(&bytes) {
+//!     dispatcher.dispatch(&packet);
+//! }
+//! ```
+
+use std::collections::HashMap;
+
+use crate::packet::{HasPacketId, Packet, PacketKind};
+
+type Handler = Box<dyn FnMut(&Packet)>;
+
+/// Routes decoded packets to handlers registered per packet type
+#[derive(Default)]
+pub struct Dispatcher {
+    handlers: HashMap<PacketKind, Vec<Handler>>,
+    any: Vec<Handler>,
+    unhandled: Option<Handler>,
+}
+
+impl std::fmt::Debug for Dispatcher {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Dispatcher")
+            .field("kinds", &self.handlers.keys().collect::<Vec<_>>())
+            .field("any", &self.any.len())
+            .field("unhandled", &self.unhandled.is_some())
+            .finish()
+    }
+}
+
+impl Dispatcher {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Call `handler` for every packet of type `T`. Handlers for the same
+    /// type run in registration order.
+    pub fn on<T: HasPacketId + 'static>(&mut self, mut handler: impl FnMut(&T) + 'static) -> &mut Self {
+        let kind = PacketKind::from(T::PACKET_ID.as_u8());
+        self.handlers.entry(kind).or_default().push(Box::new(move |packet| {
+            if let Some(packet) = T::from_packet(packet) {
+                handler(packet);
+            }
+        }));
+        self
+    }
+
+    /// Call `handler` for every packet, after the typed handlers
+    pub fn on_any(&mut self, handler: impl FnMut(&Packet) + 'static) -> &mut Self {
+        self.any.push(Box::new(handler));
+        self
+    }
+
+    /// Call `handler` for packets no typed handler is registered for,
+    /// including [`Packet::Unsupported`]. Replaces any previous handler.
+    pub fn on_unhandled(&mut self, handler: impl FnMut(&Packet) + 'static) -> &mut Self {
+        self.unhandled = Some(Box::new(handler));
+        self
+    }
+
+    /// Whether a typed handler is registered for `kind`
+    pub fn handles(&self, kind: PacketKind) -> bool {
+        self.handlers.contains_key(&kind)
+    }
+
+    /// Run the handlers for a packet. Returns whether a typed handler ran.
+    pub fn dispatch(&mut self, packet: &Packet) -> bool {
+        let typed = match packet {
+            Packet::Unsupported { .. } => None,
+            _ => self.handlers.get_mut(&PacketKind::from(packet.packet_id())),
+        };
+        let handled = match typed {
+            Some(handlers) => {
+                handlers.iter_mut().for_each(|handler| handler(packet));
+                true
+            }
+            None => {
+                if let Some(handler) = &mut self.unhandled {
+                    handler(packet);
+                }
+                false
+            }
+        };
+        self.any.iter_mut().for_each(|handler| handler(packet));
+        handled
+    }
+
+    /// Dispatch every packet from an iterator, e.g. an
+    /// [`AnppReader`](crate::reader::AnppReader) with errors filtered out
+    pub fn dispatch_all<'a>(&mut self, packets: impl IntoIterator<Item = &'a Packet>) {
+        for packet in packets {
+            self.dispatch(packet);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Payload;
+    use std::cell::RefCell;
+    use std::rc::Rc;
+    use crate::packet::state::{RunningTime, UnixTime};
+
+    fn time(seconds: u32) -> Packet {
+        Packet::UnixTime(UnixTime { unix_time_seconds: seconds, microseconds: 0 })
+    }
+
+    #[test]
+    fn test_typed_handlers() {
+        let seen = Rc::new(RefCell::new(Vec::new()));
+        let mut dispatcher = Dispatcher::new();
+        let first = seen.clone();
+        let second = seen.clone();
+        dispatcher
+            .on::<UnixTime>(move |t| first.borrow_mut().push(("first", t.unix_time_seconds)))
+            .on::<UnixTime>(move |t| second.borrow_mut().push(("second", t.unix_time_seconds)));
+
+        assert!(dispatcher.dispatch(&time(7)));
+        assert!(!dispatcher.dispatch(&Packet::RunningTime(RunningTime { seconds: 1, microseconds: 0 })));
+        assert_eq!(*seen.borrow(), vec![("first", 7), ("second", 7)]);
+        assert!(dispatcher.handles(PacketKind::UnixTime));
+        assert!(!dispatcher.handles(PacketKind::RunningTime));
+    }
+
+    #[test]
+    fn test_any_and_unhandled() {
+        let any = Rc::new(RefCell::new(0));
+        let unhandled = Rc::new(RefCell::new(Vec::new()));
+        let mut dispatcher = Dispatcher::new();
+        let any_count = any.clone();
+        let unhandled_ids = unhandled.clone();
+        dispatcher
+            .on::<UnixTime>(|_| {})
+            .on_any(move |_| *any_count.borrow_mut() += 1)
+            .on_unhandled(move |p| unhandled_ids.borrow_mut().push(p.packet_id()));
+
+        let packets = [
+            time(1),
+            Packet::RunningTime(RunningTime { seconds: 1, microseconds: 0 }),
+            Packet::Unsupported { id: 250, payload: Payload::new() },
+        ];
+        dispatcher.dispatch_all(&packets);
+        assert_eq!(*any.borrow(), 3);
+        assert_eq!(*unhandled.borrow(), vec![49, 250]);
+    }
+
+    #[test]
+    fn test_unsupported_with_known_id_is_not_typed() {
+        let mut dispatcher = Dispatcher::new();
+        dispatcher.on::<UnixTime>(|_| panic!("unsupported packets carry no UnixTime"));
+        assert!(!dispatcher.dispatch(&Packet::Unsupported { id: 21, payload: Payload::from_slice(&[0; 8]).unwrap() }));
+    }
+}
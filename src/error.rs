@@ -1,5 +1,7 @@
 use thiserror::Error;
 
+use crate::packet::PacketKind;
+
 pub type Result<T> = std::result::Result<T, AnError>;
 
 #[derive(Error, Debug)]
@@ -13,11 +15,53 @@ pub enum AnError {
     #[error("Invalid packet format: {0}")]
     InvalidPacket(String),
 
-    #[error("Invalid packet checksum")]
-    InvalidChecksum,
+    /// Payload CRC does not match the header; `expected` is the CRC the
+    /// header carries and `actual` the CRC of the received payload
+    #[error("Invalid packet checksum: expected 0x{expected:04X}, got 0x{actual:04X}")]
+    CrcMismatch { expected: u16, actual: u16 },
+
+    /// Header LRC does not match the rest of the header
+    #[error("Invalid packet format: Header LRC mismatch: expected 0x{expected:02X}, got 0x{actual:02X}")]
+    LrcMismatch { expected: u8, actual: u8 },
+
+    /// Frame or payload length differs from what the header or the packet
+    /// definition calls for
+    #[error("Invalid packet length for packet {id}: expected {expected}, got {got}")]
+    LengthMismatch { id: u8, expected: usize, got: usize },
+
+    /// Input ended before a complete frame
+    #[error("Invalid packet format: Packet too short, {needed} more bytes needed")]
+    Truncated { needed: usize },
+
+    /// A different packet arrived than the one the caller waited for
+    #[error("Unexpected packet: expected {expected:?}, got {got:?}")]
+    UnexpectedPacket { expected: PacketKind, got: PacketKind },
+
+    /// Payload bytes could not be decoded into the packet structure
+    #[error("Invalid packet format: Failed to deserialize {packet}: {source}")]
+    Decode { packet: &'static str, #[source] source: binrw::Error },
+
+    /// Packet structure could not be encoded
+    #[error("Invalid packet format: Failed to serialize {packet}: {source}")]
+    Encode { packet: &'static str, #[source] source: binrw::Error },
+
+    /// Read-only and unsupported packets cannot be framed for sending
+    #[error("Invalid packet format: Cannot send read-only or unsupported packet types (packet {id})")]
+    NotWritable { id: u8 },
+
+    /// The client's write policy refused a write with the permanent flag set
+    #[error("Permanent write of packet {id} denied by write policy")]
+    PermanentWriteDenied { id: u8 },
+
+    /// The connected device's capabilities rule out the packet, so it was
+    /// not sent
+    #[error("Packet {id} is not supported by the connected device")]
+    UnsupportedByDevice { id: u8 },
 
-    #[error("Invalid packet length: expected {expected}, got {actual}")]
-    InvalidLength { expected: usize, actual: usize },
+    /// The packet's kind already has as many writes waiting for the
+    /// client's flow control as it allows
+    #[error("Packet {id} refused: too many writes waiting for the rate limit")]
+    Backpressure { id: u8 },
 
     #[error("Device error: {0}")]
     Device(String),
@@ -34,3 +78,73 @@ pub enum AnError {
     #[error("Packet data too long: {0} bytes (max 255)")]
     PacketTooLong(usize),
 }
+
+impl AnError {
+    /// Stable identifier for the kind of error, for metrics and log
+    /// filtering. Unlike the message it never changes between releases.
+    pub fn code(&self) -> &'static str {
+        match self {
+            AnError::Network(_) => "network",
+            AnError::Timeout => "timeout",
+            AnError::InvalidPacket(_) => "invalid_packet",
+            AnError::CrcMismatch { .. } => "crc_mismatch",
+            AnError::LrcMismatch { .. } => "lrc_mismatch",
+            AnError::LengthMismatch { .. } => "length_mismatch",
+            AnError::Truncated { .. } => "truncated",
+            AnError::UnexpectedPacket { .. } => "unexpected_packet",
+            AnError::Decode { .. } => "decode",
+            AnError::Encode { .. } => "encode",
+            AnError::NotWritable { .. } => "not_writable",
+            AnError::PermanentWriteDenied { .. } => "permanent_write_denied",
+            AnError::UnsupportedByDevice { .. } => "unsupported_by_device",
+            AnError::Backpressure { .. } => "backpressure",
+            AnError::Device(_) => "device",
+            AnError::ValidationFailed(_) => "validation_failed",
+            AnError::NotConnected => "not_connected",
+            AnError::UnsupportedPacketId(_) => "unsupported_packet_id",
+            AnError::PacketTooLong(_) => "packet_too_long",
+        }
+    }
+
+    /// Whether the bytes were damaged in transit (checksum, framing or
+    /// truncation) rather than malformed at the source
+    pub fn is_corruption(&self) -> bool {
+        matches!(self,
+            AnError::CrcMismatch { .. } | AnError::LrcMismatch { .. } |
+            AnError::LengthMismatch { .. } | AnError::Truncated { .. })
+    }
+}
+
+/// The error code and message, as `Display` shows it
+#[cfg(feature = "defmt")]
+impl defmt::Format for AnError {
+    fn format(&self, f: defmt::Formatter) {
+        defmt::write!(f, "{=str}: {}", self.code(), defmt::Display2Format(self))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::error::Error;
+
+    #[test]
+    fn test_display_keeps_existing_prefixes() {
+        let crc = AnError::CrcMismatch { expected: 0x1234, actual: 0xABCD };
+        assert_eq!(crc.to_string(), "Invalid packet checksum: expected 0x1234, got 0xABCD");
+        let length = AnError::LengthMismatch { id: 20, expected: 100, got: 96 };
+        assert_eq!(length.to_string(), "Invalid packet length for packet 20: expected 100, got 96");
+        assert!(AnError::Truncated { needed: 3 }.to_string().starts_with("Invalid packet format: "));
+    }
+
+    #[test]
+    fn test_decode_error_has_source() {
+        let source = binrw::Error::AssertFail { pos: 4, message: "bad".to_string() };
+        let error = AnError::Decode { packet: "SystemState", source };
+        assert!(error.to_string().starts_with("Invalid packet format: Failed to deserialize SystemState: "));
+        assert!(error.source().is_some());
+        assert_eq!(error.code(), "decode");
+        assert!(!error.is_corruption());
+        assert!(AnError::CrcMismatch { expected: 0, actual: 1 }.is_corruption());
+    }
+}
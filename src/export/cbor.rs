@@ -0,0 +1,27 @@
+//! CBOR (RFC 8949) for serde types, on top of `ciborium`.
+//!
+//! Structs are maps keyed by field name, unit enum variants are text and
+//! other variants are a one-entry map from variant name to value, so the
+//! output reads naturally in any generic CBOR decoder.
+
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+use crate::error::AnError;
+
+/// Encode a value as CBOR
+pub fn to_vec<T: Serialize + ?Sized>(value: &T) -> Result<Vec<u8>, AnError> {
+    let mut out = Vec::new();
+    ciborium::into_writer(value, &mut out).map_err(|e| AnError::InvalidPacket(format!("CBOR: {}", e)))?;
+    Ok(out)
+}
+
+/// Decode a value from CBOR, rejecting trailing bytes
+pub fn from_slice<T: DeserializeOwned>(bytes: &[u8]) -> Result<T, AnError> {
+    let mut input = bytes;
+    let value = ciborium::from_reader(&mut input).map_err(|e| AnError::InvalidPacket(format!("CBOR: {}", e)))?;
+    if !input.is_empty() {
+        return Err(AnError::InvalidPacket(format!("CBOR: {} trailing bytes", input.len())));
+    }
+    Ok(value)
+}
@@ -0,0 +1,150 @@
+//! Compact self-describing telemetry for message buses.
+//!
+//! [`encode_envelope`] wraps a decoded packet in a CBOR envelope so it can
+//! be forwarded to processes that know nothing about ANPP framing. The
+//! envelope is a three element CBOR array:
+//!
+//! ```text
+//! [schema_version, packet_id, { "<PacketName>": { "<field>": value, ... } }]
+//! ```
+//!
+//! Any CBOR library can read it, e.g. `cbor2.loads(data)` in Python. The
+//! schema version changes whenever a packet's serde form changes
+//! incompatibly, and [`decode_envelope`] checks it before decoding the
+//! packet so that mismatched producers fail with a clear error.
+
+pub mod cbor;
+
+use serde::de::IgnoredAny;
+use serde::Deserialize;
+
+use crate::error::AnError;
+use crate::packet::Packet;
+
+/// Schema version written in every envelope
+pub const SCHEMA_VERSION: u16 = 1;
+
+/// A decoded envelope
+#[derive(Debug, Clone, PartialEq)]
+pub struct Envelope {
+    pub schema_version: u16,
+    pub packet_id: u8,
+    pub packet: Packet,
+}
+
+#[derive(Deserialize)]
+struct Header(u16, IgnoredAny, IgnoredAny);
+
+/// Encode a packet with the current schema version header
+pub fn encode_envelope(packet: &Packet) -> Result<Vec<u8>, AnError> {
+    cbor::to_vec(&(SCHEMA_VERSION, packet.packet_id(), packet))
+}
+
+/// Decode an envelope written by [`encode_envelope`]
+pub fn decode_envelope(bytes: &[u8]) -> Result<Envelope, AnError> {
+    let Header(schema_version, _, _) = cbor::from_slice(bytes)?;
+    if schema_version != SCHEMA_VERSION {
+        return Err(AnError::InvalidPacket(format!(
+            "unsupported telemetry schema version {} (expected {})", schema_version, SCHEMA_VERSION)));
+    }
+
+    let (schema_version, packet_id, packet): (u16, u8, Packet) = cbor::from_slice(bytes)?;
+    if packet.packet_id() != packet_id {
+        return Err(AnError::InvalidPacket(format!(
+            "envelope packet ID {} does not match {} packet", packet_id, packet.packet_id())));
+    }
+    Ok(Envelope { schema_version, packet_id, packet })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Payload;
+    use std::collections::BTreeMap;
+    use std::net::Ipv4Addr;
+    use crate::packet::PacketKind;
+    use crate::packet::config::{FilterOptions, IpDataportsConfiguration, PacketTimerPeriod, UserData, VehicleType};
+    use crate::packet::state::{FilterStatus, Status, SystemStatus, UnixTime};
+    use crate::packet::system::{IpConfiguration, Request};
+
+    fn round_trip(packet: Packet) {
+        let bytes = encode_envelope(&packet).unwrap();
+        let envelope = decode_envelope(&bytes).unwrap();
+        assert_eq!(envelope.schema_version, SCHEMA_VERSION);
+        assert_eq!(envelope.packet_id, packet.packet_id());
+        assert_eq!(envelope.packet, packet);
+    }
+
+    #[test]
+    fn test_round_trips_packets() {
+        round_trip(Packet::UnixTime(UnixTime { unix_time_seconds: 1_700_000_000, microseconds: 12 }));
+        round_trip(Packet::Status(Status {
+            system_status: SystemStatus::default(),
+            filter_status: FilterStatus::default(),
+            extension: None,
+        }));
+        round_trip(Packet::PacketTimerPeriod(PacketTimerPeriod::from_rate_hz(500.0).unwrap()));
+        round_trip(Packet::UserData(UserData { data: [7; 64] }));
+        round_trip(Packet::Unsupported { id: 250, payload: Payload::from_slice(&[1, 2, 3]).unwrap() });
+
+        let ip = IpConfiguration {
+            permanent: false,
+            dhcp_mode: 0,
+            ip_address: Ipv4Addr::new(192, 168, 1, 20),
+            ip_netmask: Ipv4Addr::new(255, 255, 255, 0),
+            ip_gateway: Ipv4Addr::new(192, 168, 1, 1),
+            dns_server: Ipv4Addr::UNSPECIFIED,
+            boreas_serial_number_part_1: 1,
+            boreas_serial_number_part_2: 2,
+            boreas_serial_number_part_3: 3,
+        };
+        round_trip(Packet::IpConfiguration(ip));
+
+        let dataports = IpDataportsConfiguration::builder()
+            .udp_client(1, Ipv4Addr::new(10, 0, 0, 2), 16718)
+            .build()
+            .unwrap();
+        round_trip(Packet::IpDataportsConfiguration(dataports));
+        round_trip(Packet::FilterOptions(FilterOptions::builder().vehicle_type(VehicleType::Boat).build().unwrap()));
+        round_trip(Packet::Request(Request::multiple([PacketKind::SystemState, PacketKind::Status])));
+    }
+
+    #[test]
+    fn test_readable_without_packet_types() {
+        let bytes = encode_envelope(&Packet::UnixTime(UnixTime { unix_time_seconds: 5, microseconds: 6 })).unwrap();
+        // The leading bytes are the array head and the version
+        assert_eq!(&bytes[..3], &[0x83, 0x01, 0x15]);
+
+        type Fields = BTreeMap<String, u32>;
+        let (version, id, body): (u16, u8, BTreeMap<String, Fields>) = cbor::from_slice(&bytes).unwrap();
+        assert_eq!((version, id), (SCHEMA_VERSION, 21));
+        assert_eq!(body["UnixTime"]["unix_time_seconds"], 5);
+        assert_eq!(body["UnixTime"]["microseconds"], 6);
+    }
+
+    #[test]
+    fn test_rejects_other_versions_and_garbage() {
+        let mut bytes = encode_envelope(&Packet::Unsupported { id: 250, payload: Payload::new() }).unwrap();
+        bytes[1] = 0x02;
+        let error = decode_envelope(&bytes).unwrap_err();
+        assert!(error.to_string().contains("schema version 2"));
+
+        assert!(decode_envelope(&[]).is_err());
+        assert!(decode_envelope(&[0x9f; 200]).is_err());
+        assert!(decode_envelope(&[0x83, 0x01, 0x15, 0xa1, 0x7b, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff]).is_err());
+    }
+
+    #[test]
+    fn test_cbor_scalars() {
+        assert_eq!(cbor::to_vec(&-500i32).unwrap(), vec![0x39, 0x01, 0xf3]);
+        assert_eq!(cbor::from_slice::<i32>(&[0x39, 0x01, 0xf3]).unwrap(), -500);
+        // Floats take the shortest lossless width
+        assert_eq!(cbor::to_vec(&Some(1.5f32)).unwrap(), vec![0xf9, 0x3e, 0x00]);
+        assert_eq!(cbor::to_vec(&0.1f32).unwrap(), vec![0xfa, 0x3d, 0xcc, 0xcc, 0xcd]);
+        assert_eq!(cbor::from_slice::<Option<f32>>(&[0xf6]).unwrap(), None);
+        // Half precision 1.5 and an indefinite array from another encoder
+        assert_eq!(cbor::from_slice::<f32>(&[0xf9, 0x3e, 0x00]).unwrap(), 1.5);
+        assert_eq!(cbor::from_slice::<Vec<u8>>(&[0x9f, 0x01, 0x02, 0xff]).unwrap(), vec![1, 2]);
+        assert!(cbor::from_slice::<u8>(&[0x01, 0x02]).is_err());
+    }
+}
@@ -0,0 +1,403 @@
+//! Several devices at once.
+//!
+//! [`Fleet`] keeps one parser per link (a connection, serial port or UDP
+//! source, identified by whatever key the caller uses) and learns which
+//! device is on each link from its Device Information. Decoded packets are
+//! tagged with the device serial, forwarded to per-device channels and
+//! folded into a fleet-wide health summary.
+//!
+//! Everything is sans-io: the caller sends the bytes [`Fleet::connect`]
+//! returns, feeds received bytes to [`Fleet::handle`] and supplies the
+//! current time.
+
+use std::collections::HashMap;
+use std::fmt;
+use std::hash::Hash;
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::time::{Duration, Instant};
+
+use crate::alarms::{active_alarms, AlarmId, Severity};
+use crate::packet::state::{FilterStatus, SystemStatus};
+use crate::packet::system::{DeviceInformation, DeviceType, Request};
+use crate::packet::{Packet, PacketKind};
+use crate::parser::AnppParser;
+
+/// Packets kept per link while waiting for Device Information
+pub const MAX_PENDING: usize = 256;
+
+/// Default time without packets after which a device counts as stale
+pub const DEFAULT_STALE_AFTER: Duration = Duration::from_secs(2);
+
+/// Device identity from the three serial number words of Device Information
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct DeviceSerial(pub [u32; 3]);
+
+impl From<&DeviceInformation> for DeviceSerial {
+    fn from(info: &DeviceInformation) -> Self {
+        Self([info.serial_number_1, info.serial_number_2, info.serial_number_3])
+    }
+}
+
+/// Same `XXXXXXXX-XXXXXXXX-XXXXXXXX` form as discovery
+impl fmt::Display for DeviceSerial {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:08X}-{:08X}-{:08X}", self.0[0], self.0[1], self.0[2])
+    }
+}
+
+/// Health of one device
+#[derive(Debug, Clone, PartialEq)]
+pub struct DeviceHealth {
+    pub serial: DeviceSerial,
+    pub device_type: DeviceType,
+    pub packets_received: u64,
+    pub last_seen: Instant,
+    /// No packet within the stale timeout
+    pub stale: bool,
+    /// Alarms raised by the latest status words, empty until one arrives
+    pub alarms: Vec<AlarmId>,
+}
+
+impl DeviceHealth {
+    /// Most severe alarm; a stale device counts as critical
+    pub fn worst(&self) -> Option<Severity> {
+        if self.stale {
+            return Some(Severity::Critical);
+        }
+        self.alarms.iter().map(AlarmId::severity).max()
+    }
+}
+
+/// Health of every known device, ordered by serial
+#[derive(Debug, Clone, PartialEq)]
+pub struct FleetHealth {
+    pub devices: Vec<DeviceHealth>,
+}
+
+impl FleetHealth {
+    /// Most severe condition across the fleet
+    pub fn worst(&self) -> Option<Severity> {
+        self.devices.iter().filter_map(DeviceHealth::worst).max()
+    }
+
+    /// No device is stale or raising a warning or worse
+    pub fn all_healthy(&self) -> bool {
+        self.worst().is_none_or(|severity| severity < Severity::Warning)
+    }
+}
+
+struct Link {
+    parser: AnppParser,
+    serial: Option<DeviceSerial>,
+    pending: Vec<Packet>,
+}
+
+struct Device<L> {
+    information: DeviceInformation,
+    link: L,
+    packets_received: u64,
+    last_seen: Instant,
+    status: Option<(SystemStatus, FilterStatus)>,
+}
+
+/// Parsers, identities and health for a set of devices
+pub struct Fleet<L> {
+    links: HashMap<L, Link>,
+    devices: HashMap<DeviceSerial, Device<L>>,
+    subscriptions: HashMap<DeviceSerial, Sender<Packet>>,
+    stale_after: Duration,
+}
+
+impl<L: Clone + Eq + Hash> Default for Fleet<L> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<L: Clone + Eq + Hash> Fleet<L> {
+    pub fn new() -> Self {
+        Self {
+            links: HashMap::new(),
+            devices: HashMap::new(),
+            subscriptions: HashMap::new(),
+            stale_after: DEFAULT_STALE_AFTER,
+        }
+    }
+
+    /// Time without packets after which a device is reported stale
+    pub fn with_stale_after(mut self, stale_after: Duration) -> Self {
+        self.stale_after = stale_after;
+        self
+    }
+
+    /// Register a link and return the Device Information request to send
+    /// on it. Reconnecting an existing link resets its parser.
+    pub fn connect(&mut self, link: L) -> Vec<u8> {
+        self.links.insert(link, Link { parser: AnppParser::new(), serial: None, pending: Vec::new() });
+        Packet::Request(Request::single(PacketKind::DeviceInformation))
+            .to_bytes()
+            .expect("single packet request always encodes")
+    }
+
+    /// Forget a link. Its device stays known and goes stale.
+    pub fn disconnect(&mut self, link: &L) {
+        self.links.remove(link);
+    }
+
+    /// Feed bytes received on `link` and return the decoded packets tagged
+    /// with their device. Packets that arrive before the device has
+    /// identified itself are held and returned once it has.
+    pub fn handle(&mut self, link: &L, input: &[u8], now: Instant) -> Vec<(DeviceSerial, Packet)> {
+        let Some(state) = self.links.get_mut(link) else {
+            return Vec::new();
+        };
+
+        let mut decoded = Vec::new();
+        let mut next = state.parser.consume(input);
+        while let Some(packet) = next {
+            decoded.push(packet);
+            next = state.parser.consume(&[]);
+        }
+
+        let mut routed = Vec::new();
+        for packet in decoded {
+            if let Packet::DeviceInformation(info) = &packet {
+                let serial = DeviceSerial::from(info);
+                self.identify(link, serial, info.clone(), now);
+                let state = self.links.get_mut(link).expect("link checked above");
+                let pending = std::mem::take(&mut state.pending);
+                for held in pending {
+                    routed.push((serial, held));
+                }
+            }
+
+            let state = self.links.get_mut(link).expect("link checked above");
+            match state.serial {
+                Some(serial) => routed.push((serial, packet)),
+                None => {
+                    if state.pending.len() == MAX_PENDING {
+                        state.pending.remove(0);
+                    }
+                    state.pending.push(packet);
+                }
+            }
+        }
+
+        for (serial, packet) in &routed {
+            self.record(serial, packet, now);
+        }
+        routed
+    }
+
+    /// Channel receiving every packet from the device with `serial`,
+    /// including devices not seen yet. Replaces any earlier subscription.
+    pub fn subscribe(&mut self, serial: DeviceSerial) -> Receiver<Packet> {
+        let (sender, receiver) = mpsc::channel();
+        self.subscriptions.insert(serial, sender);
+        receiver
+    }
+
+    /// Serials of every device identified so far
+    pub fn devices(&self) -> impl Iterator<Item = (&DeviceSerial, &DeviceInformation)> {
+        self.devices.iter().map(|(serial, device)| (serial, &device.information))
+    }
+
+    /// Link the device was last seen on, for sending to it
+    pub fn link_of(&self, serial: &DeviceSerial) -> Option<&L> {
+        self.devices.get(serial).map(|device| &device.link).filter(|link| self.links.contains_key(*link))
+    }
+
+    /// Device currently identified on `link`
+    pub fn serial_on(&self, link: &L) -> Option<DeviceSerial> {
+        self.links.get(link)?.serial
+    }
+
+    /// Health summary at `now`
+    pub fn health(&self, now: Instant) -> FleetHealth {
+        let mut devices: Vec<DeviceHealth> = self.devices.iter()
+            .map(|(serial, device)| DeviceHealth {
+                serial: *serial,
+                device_type: device.information.device_type,
+                packets_received: device.packets_received,
+                last_seen: device.last_seen,
+                stale: now.saturating_duration_since(device.last_seen) > self.stale_after
+                    || !self.links.contains_key(&device.link),
+                alarms: device.status
+                    .map(|(system, filter)| active_alarms(&system, &filter))
+                    .unwrap_or_default(),
+            })
+            .collect();
+        devices.sort_by_key(|d| d.serial);
+        FleetHealth { devices }
+    }
+
+    fn identify(&mut self, link: &L, serial: DeviceSerial, information: DeviceInformation, now: Instant) {
+        // A device that shows up on a new link has moved; the old link no
+        // longer speaks for it
+        for (other, state) in self.links.iter_mut() {
+            if other != link && state.serial == Some(serial) {
+                state.serial = None;
+            }
+        }
+        if let Some(state) = self.links.get_mut(link) {
+            state.serial = Some(serial);
+        }
+
+        let device = self.devices.entry(serial).or_insert_with(|| Device {
+            information: information.clone(),
+            link: link.clone(),
+            packets_received: 0,
+            last_seen: now,
+            status: None,
+        });
+        device.information = information;
+        device.link = link.clone();
+    }
+
+    fn record(&mut self, serial: &DeviceSerial, packet: &Packet, now: Instant) {
+        let Some(device) = self.devices.get_mut(serial) else {
+            return;
+        };
+        device.packets_received += 1;
+        device.last_seen = now;
+        match packet {
+            Packet::SystemState(state) => device.status = Some((state.system_status, state.filter_status)),
+            Packet::Status(status) => device.status = Some((status.system_status, status.filter_status)),
+            _ => {}
+        }
+        if let Some(sender) = self.subscriptions.get(serial)
+            && sender.send(packet.clone()).is_err()
+        {
+            self.subscriptions.remove(serial);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::packet::config::{PacketPeriod, PacketsPeriod};
+    use crate::packet::state::SystemState;
+    use crate::sim::{MockBoreas, Trajectory};
+    use crate::units::{Angle, Length};
+
+    fn device(serial: u32) -> MockBoreas {
+        let trajectory = Trajectory::Static {
+            latitude: Angle::from_degrees(-33.87),
+            longitude: Angle::from_degrees(151.21),
+            height: Length::from_meters(10.0),
+        };
+        MockBoreas::new(trajectory).with_device_information(DeviceInformation {
+            software_version: 7_000,
+            device_type: DeviceType::BoreasD90,
+            hardware_revision: 1,
+            serial_number_1: serial,
+            serial_number_2: 0,
+            serial_number_3: 0,
+        })
+    }
+
+    fn subscribe_state() -> Vec<u8> {
+        Packet::PacketsPeriod(PacketsPeriod {
+            permanent: false,
+            clear_existing: true,
+            packet_periods: vec![PacketPeriod::from_packet::<SystemState>(100)],
+        })
+        .to_bytes()
+        .unwrap()
+    }
+
+    #[test]
+    fn test_routes_packets_by_serial() {
+        let now = Instant::now();
+        let mut fleet = Fleet::new();
+        let mut units = [device(1), device(2)];
+
+        let channel = fleet.subscribe(DeviceSerial([2, 0, 0]));
+        for (link, unit) in units.iter_mut().enumerate() {
+            let request = fleet.connect(link);
+            let reply = unit.feed(&request);
+            let routed = fleet.handle(&link, &reply, now);
+            assert!(matches!(&routed[..], [(_, Packet::DeviceInformation(_))]));
+            unit.feed(&subscribe_state());
+        }
+        assert_eq!(fleet.serial_on(&1), Some(DeviceSerial([2, 0, 0])));
+        assert_eq!(fleet.link_of(&DeviceSerial([1, 0, 0])), Some(&0));
+
+        for (link, unit) in units.iter_mut().enumerate() {
+            let stream = unit.advance(Duration::from_millis(500));
+            let routed = fleet.handle(&link, &stream, now);
+            assert_eq!(routed.len(), 5);
+            assert!(routed.iter().all(|(serial, _)| serial.0[0] == link as u32 + 1));
+        }
+
+        let received: Vec<Packet> = channel.try_iter().collect();
+        assert_eq!(received.len(), 6);
+        assert!(matches!(received[0], Packet::DeviceInformation(_)));
+        assert_eq!(DeviceSerial([2, 0, 0]).to_string(), "00000002-00000000-00000000");
+    }
+
+    #[test]
+    fn test_packets_before_identification_are_held() {
+        let now = Instant::now();
+        let mut fleet = Fleet::new();
+        let mut unit = device(9);
+        fleet.connect("a");
+        unit.feed(&subscribe_state());
+
+        assert!(fleet.handle(&"a", &unit.advance(Duration::from_millis(300)), now).is_empty());
+        let reply = unit.feed(&Packet::Request(Request::single(PacketKind::DeviceInformation)).to_bytes().unwrap());
+        let routed = fleet.handle(&"a", &reply, now);
+        assert_eq!(routed.len(), 4);
+        assert!(matches!(routed[0].1, Packet::SystemState(_)));
+        assert!(matches!(routed[3].1, Packet::DeviceInformation(_)));
+    }
+
+    #[test]
+    fn test_health_aggregation() {
+        let start = Instant::now();
+        let mut fleet = Fleet::new().with_stale_after(Duration::from_secs(1));
+        let mut units = [device(1), device(2)];
+        for (link, unit) in units.iter_mut().enumerate() {
+            let reply = unit.feed(&fleet.connect(link));
+            fleet.handle(&link, &reply, start);
+            unit.feed(&subscribe_state());
+            let stream = unit.advance(Duration::from_millis(100));
+            fleet.handle(&link, &stream, start);
+        }
+
+        let health = fleet.health(start);
+        assert_eq!(health.devices.len(), 2);
+        assert!(health.devices.iter().all(|d| d.alarms.is_empty()));
+        assert!(health.all_healthy());
+
+        // Unit 2 keeps streaming, unit 1 goes quiet
+        let later = start + Duration::from_secs(3);
+        let stream = units[1].advance(Duration::from_millis(100));
+        fleet.handle(&1, &stream, later);
+        let health = fleet.health(later);
+        assert!(health.devices[0].stale);
+        assert!(!health.devices[1].stale);
+        assert_eq!(health.worst(), Some(Severity::Critical));
+        assert!(!health.all_healthy());
+
+        fleet.disconnect(&1);
+        assert!(fleet.health(later).devices[1].stale);
+        assert_eq!(fleet.link_of(&DeviceSerial([2, 0, 0])), None);
+    }
+
+    #[test]
+    fn test_device_moving_links() {
+        let now = Instant::now();
+        let mut fleet = Fleet::new();
+        let mut unit = device(5);
+        let reply = unit.feed(&fleet.connect(0));
+        fleet.handle(&0, &reply, now);
+
+        let reply = unit.feed(&fleet.connect(1));
+        fleet.handle(&1, &reply, now);
+        assert_eq!(fleet.serial_on(&0), None);
+        assert_eq!(fleet.link_of(&DeviceSerial([5, 0, 0])), Some(&1));
+        assert_eq!(fleet.devices().count(), 1);
+    }
+}
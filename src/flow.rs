@@ -0,0 +1,250 @@
+//! Outbound rate limiting per packet kind.
+//!
+//! A device under load refuses external aiding sent faster than its
+//! filter can take it. [`FlowControl`] holds a token bucket per limited
+//! packet kind: each packet sent takes a token, tokens refill at the
+//! configured rate up to the burst size, and a packet without a token has
+//! to wait. A rejected write empties the bucket, so the next packet of that
+//! kind waits a full interval before going out.
+//!
+//! The limiter works on its own, or inside [`Client`] through
+//! [`Client::with_flow_control`], which holds writes back until their kind
+//! has a token and refuses new writes with [`AnError::Backpressure`] once
+//! [`FlowLimit::max_queued`] are already waiting.
+//!
+//! ```
+//! use std::time::{Duration, Instant};
+//! use liban::flow::{FlowControl, FlowLimit};
+//! use liban::PacketKind;
+//!
+//! let mut flow = FlowControl::new().with_limit(PacketKind::ExternalPosition, FlowLimit::new(10.0, 1).unwrap());
+//! let start = Instant::now();
+//! assert!(flow.try_acquire(PacketKind::ExternalPosition, start).is_ok());
+//! let throttled = flow.try_acquire(PacketKind::ExternalPosition, start).unwrap_err();
+//! assert_eq!(throttled.retry_after, Duration::from_millis(100));
+//! assert!(flow.try_acquire(PacketKind::ExternalPosition, start + Duration::from_millis(100)).is_ok());
+//! // Kinds without a limit always go
+//! assert!(flow.try_acquire(PacketKind::FilterOptions, start).is_ok());
+//! ```
+//!
+//! [`Client`]: crate::client::Client
+//! [`Client::with_flow_control`]: crate::client::Client::with_flow_control
+//! [`AnError::Backpressure`]: crate::error::AnError::Backpressure
+
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+use crate::clock::Clock;
+use crate::error::AnError;
+use crate::packet::PacketKind;
+
+/// External aiding packets, the ones a busy device refuses first
+pub const AIDING_KINDS: [PacketKind; 8] = [
+    PacketKind::ExternalPositionVelocity,
+    PacketKind::ExternalPosition,
+    PacketKind::ExternalVelocity,
+    PacketKind::ExternalBodyVelocity,
+    PacketKind::ExternalHeading,
+    PacketKind::ExternalTime,
+    PacketKind::ExternalDepth,
+    PacketKind::ExternalAirData,
+];
+
+/// Rate limit for one packet kind
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FlowLimit {
+    /// Sustained packets per second
+    pub rate_hz: f64,
+    /// Packets that may go back to back after a quiet spell
+    pub burst: u32,
+    /// Packets a [`Client`](crate::client::Client) holds back for this kind
+    /// before refusing more
+    pub max_queued: usize,
+}
+
+impl FlowLimit {
+    /// Limit to `rate_hz` with bursts of `burst`, holding back one packet.
+    /// Fails unless the rate is finite and positive.
+    pub fn new(rate_hz: f64, burst: u32) -> Result<Self, AnError> {
+        if !(rate_hz.is_finite() && rate_hz > 0.0) {
+            return Err(AnError::ValidationFailed(format!("flow limit rate {} Hz is not positive", rate_hz)));
+        }
+        Ok(Self { rate_hz, burst: burst.max(1), max_queued: 1 })
+    }
+
+    pub fn with_max_queued(mut self, max_queued: usize) -> Self {
+        self.max_queued = max_queued;
+        self
+    }
+
+    /// A rate set directly on the field to zero or less waits forever
+    #[cfg(feature = "tracing")]
+    fn interval(&self) -> Duration {
+        Duration::try_from_secs_f64(1.0 / self.rate_hz).unwrap_or(Duration::MAX)
+    }
+}
+
+/// A packet that has to wait for its kind's rate limit
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Throttled {
+    pub kind: PacketKind,
+    /// Time until a token is available
+    pub retry_after: Duration,
+}
+
+#[derive(Debug, Clone)]
+struct Bucket {
+    limit: FlowLimit,
+    tokens: f64,
+    updated: Option<Instant>,
+}
+
+impl Bucket {
+    fn refill(&mut self, now: Instant) {
+        if let Some(updated) = self.updated {
+            let elapsed = now.saturating_duration_since(updated).as_secs_f64();
+            self.tokens = (self.tokens + elapsed * self.limit.rate_hz).min(self.limit.burst as f64);
+        }
+        self.updated = Some(now);
+    }
+
+    /// Time until a whole token is available, zero if one is
+    fn wait(&self) -> Duration {
+        if self.tokens >= 1.0 {
+            Duration::ZERO
+        } else {
+            Duration::try_from_secs_f64((1.0 - self.tokens) / self.limit.rate_hz).unwrap_or(Duration::MAX)
+        }
+    }
+}
+
+/// Token buckets for the packet kinds that are rate limited
+#[derive(Debug, Clone, Default)]
+pub struct FlowControl {
+    buckets: HashMap<PacketKind, Bucket>,
+}
+
+impl FlowControl {
+    /// No kind is limited
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Every external aiding packet limited to `rate_hz`, without bursts.
+    /// Fails for the rates [`FlowLimit::new`] refuses.
+    pub fn for_aiding(rate_hz: f64) -> Result<Self, AnError> {
+        let limit = FlowLimit::new(rate_hz, 1)?;
+        Ok(AIDING_KINDS.iter().fold(Self::new(), |flow, kind| flow.with_limit(*kind, limit)))
+    }
+
+    /// Limit `kind`, replacing any earlier limit. The bucket starts full.
+    pub fn with_limit(mut self, kind: PacketKind, limit: FlowLimit) -> Self {
+        self.buckets.insert(kind, Bucket { limit, tokens: limit.burst as f64, updated: None });
+        self
+    }
+
+    pub fn limit(&self, kind: PacketKind) -> Option<&FlowLimit> {
+        self.buckets.get(&kind).map(|bucket| &bucket.limit)
+    }
+
+    /// Take a token for one packet of `kind`, or say how long to wait.
+    /// Kinds without a limit always succeed.
+    pub fn try_acquire(&mut self, kind: PacketKind, clock: impl Clock) -> Result<(), Throttled> {
+        let Some(bucket) = self.buckets.get_mut(&kind) else {
+            return Ok(());
+        };
+        bucket.refill(clock.now());
+        if bucket.tokens >= 1.0 {
+            bucket.tokens -= 1.0;
+            Ok(())
+        } else {
+            Err(Throttled { kind, retry_after: bucket.wait() })
+        }
+    }
+
+    /// When a packet of `kind` can next be sent without waiting, `None` if
+    /// the wait runs past what an `Instant` can hold
+    pub fn ready_at(&self, kind: PacketKind, clock: impl Clock) -> Option<Instant> {
+        let now = clock.now();
+        let Some(bucket) = self.buckets.get(&kind) else {
+            return Some(now);
+        };
+        let mut bucket = bucket.clone();
+        bucket.refill(now);
+        now.checked_add(bucket.wait())
+    }
+
+    /// The device refused a packet of `kind`: empty the bucket so the next
+    /// one waits a full interval
+    pub fn rejected(&mut self, kind: PacketKind, clock: impl Clock) {
+        if let Some(bucket) = self.buckets.get_mut(&kind) {
+            bucket.tokens = 0.0;
+            bucket.updated = Some(clock.now());
+            #[cfg(feature = "tracing")]
+            tracing::debug!("{:?} rejected, next send in {:?}", kind, bucket.limit.interval());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bucket_refills_up_to_burst() {
+        let kind = PacketKind::ExternalPositionVelocity;
+        let mut flow = FlowControl::new().with_limit(kind, FlowLimit::new(50.0, 3).unwrap());
+        let start = Instant::now();
+        for _ in 0..3 {
+            flow.try_acquire(kind, start).unwrap();
+        }
+        assert_eq!(flow.try_acquire(kind, start), Err(Throttled { kind, retry_after: Duration::from_millis(20) }));
+        assert_eq!(flow.ready_at(kind, start), Some(start + Duration::from_millis(20)));
+
+        // A long quiet spell refills only the burst
+        let later = start + Duration::from_secs(10);
+        for _ in 0..3 {
+            flow.try_acquire(kind, later).unwrap();
+        }
+        assert!(flow.try_acquire(kind, later).is_err());
+    }
+
+    #[test]
+    fn test_rejection_empties_bucket() {
+        let mut flow = FlowControl::for_aiding(10.0).unwrap();
+        let start = Instant::now();
+        assert_eq!(flow.limit(PacketKind::ExternalHeading), Some(&FlowLimit::new(10.0, 1).unwrap()));
+        assert!(flow.limit(PacketKind::PacketTimerPeriod).is_none());
+
+        flow.rejected(PacketKind::ExternalHeading, start);
+        assert!(flow.try_acquire(PacketKind::ExternalHeading, start + Duration::from_millis(50)).is_err());
+        assert!(flow.try_acquire(PacketKind::ExternalHeading, start + Duration::from_millis(100)).is_ok());
+        // Other kinds keep their own tokens
+        assert!(flow.try_acquire(PacketKind::ExternalDepth, start).is_ok());
+    }
+
+    #[test]
+    fn test_rejects_invalid_rates() {
+        for rate in [0.0, -1.0, f64::NAN, f64::INFINITY] {
+            assert!(FlowLimit::new(rate, 1).is_err(), "{rate}");
+            assert!(FlowControl::for_aiding(rate).is_err(), "{rate}");
+        }
+        assert!(FlowLimit::new(1e-300, 1).is_ok());
+    }
+
+    #[test]
+    fn test_extreme_rates_never_panic() {
+        let kind = PacketKind::ExternalHeading;
+        let start = Instant::now();
+        // The fields are public, so a limit can skip the check in new
+        let zero = FlowLimit { rate_hz: 0.0, ..FlowLimit::new(1.0, 1).unwrap() };
+        for limit in [zero, FlowLimit::new(1e-300, 1).unwrap()] {
+            let mut flow = FlowControl::new().with_limit(kind, limit);
+            flow.try_acquire(kind, start).unwrap();
+            let throttled = flow.try_acquire(kind, start).unwrap_err();
+            assert_eq!(throttled.retry_after, Duration::MAX);
+            assert_eq!(flow.ready_at(kind, start), None);
+            flow.rejected(kind, start);
+        }
+    }
+}
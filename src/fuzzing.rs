@@ -0,0 +1,87 @@
+//! Fuzzing support.
+//!
+//! [`check_frames`] is the body of the `fuzz/` cargo-fuzz target: it feeds
+//! arbitrary bytes through [`AnppParser`] and checks that every decoded
+//! packet re-encodes to a payload that decodes to the same bytes again.
+//! [`seed_frames`] builds one valid frame per fixed-length packet kind from
+//! the length table so a fuzzer starts from inputs that reach the payload
+//! decoders.
+
+use crate::packet::PacketKind;
+use crate::parser::AnppParser;
+use crate::wire::{self, PacketId};
+
+/// Payload length used for variable-length packets in the seed corpus
+const VARIABLE_SEED_LEN: usize = 8;
+
+/// One valid frame per known packet kind, with an all-zero payload. The
+/// frames pass the header and CRC checks; the payloads need not be valid
+/// for every kind (a zero baud rate is not).
+pub fn seed_frames() -> Vec<(PacketKind, Vec<u8>)> {
+    PacketKind::all()
+        .filter_map(|kind| {
+            let payload = vec![0; kind.byte_length().unwrap_or(VARIABLE_SEED_LEN)];
+            let frame = wire::encode_frame(PacketId::new(kind.packet_id()), &payload).ok()?;
+            Some((kind, frame))
+        })
+        .collect()
+}
+
+/// Parse `data` as a byte stream and check every packet round-trips.
+///
+/// Panics on a round-trip mismatch; decode errors are expected and ignored.
+/// The comparison is on re-encoded bytes rather than on the packets because
+/// NaN floats never compare equal.
+pub fn check_frames(data: &[u8]) {
+    let mut parser = AnppParser::new();
+    let mut next = parser.consume(data);
+    while let Some(packet) = next {
+        if let Ok(payload) = wire::encode_payload(&packet) {
+            let decoded = wire::decode_payload(packet.packet_id(), &payload)
+                .unwrap_or_else(|e| panic!("re-encoded {:?} failed to decode: {}", packet, e));
+            let again = wire::encode_payload(&decoded).unwrap();
+            assert_eq!(payload, again, "{:?} did not round-trip", packet);
+        }
+        next = parser.consume(&[]);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_seed_frames_are_valid_frames() {
+        let seeds = seed_frames();
+        assert!(seeds.iter().any(|(kind, _)| *kind == PacketKind::SystemState));
+
+        for (kind, frame) in &seeds {
+            let (header, payload) = wire::decode_frame(frame).unwrap();
+            assert_eq!(PacketKind::from(header.packet_id.id), *kind);
+            assert_eq!(payload.len(), kind.byte_length().unwrap_or(VARIABLE_SEED_LEN));
+        }
+    }
+
+    #[test]
+    fn test_check_frames_on_mutated_seeds() {
+        let mut state = 0x2545_f491_4f6c_dd1du64;
+        let mut stream = Vec::new();
+        for (_, frame) in seed_frames() {
+            stream.extend(&frame);
+        }
+        check_frames(&stream);
+
+        // Flip payload bytes and re-frame so the CRC stays valid and the
+        // mutations reach the decoders
+        for (kind, frame) in seed_frames() {
+            let mut payload = frame[wire::HEADER_LEN..].to_vec();
+            for byte in payload.iter_mut() {
+                state ^= state << 13;
+                state ^= state >> 7;
+                state ^= state << 17;
+                *byte = state as u8;
+            }
+            check_frames(&wire::encode_frame(PacketId::new(kind.packet_id()), &payload).unwrap());
+        }
+    }
+}
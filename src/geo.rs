@@ -0,0 +1,432 @@
+//! Geofencing on the navigation stream.
+//!
+//! [`Geofence`] holds named [`Zone`]s, circles or polygons in latitude and
+//! longitude, and turns a stream of [`SystemState`] updates into
+//! [`GeofenceEvent`]s. A zone is entered once the vehicle is inside it by
+//! at least the hysteresis margin and left once it is outside by the same
+//! margin, so position noise along a boundary does not produce a burst of
+//! events. Time comes from the packets themselves.
+//!
+//! [`distance_haversine`] and [`bearing`] relate two fixes on a sphere, and
+//! [`SystemState::offset_from`] gives the north, east, down offset of one
+//! fix from another on the WGS84 local tangent plane, from which
+//! [`NedOffset::along_cross_track`] splits off guidance errors.
+
+use std::time::{Duration, SystemTime};
+
+use crate::angle::{normalize_2pi, shortest_angular_distance};
+use crate::error::AnError;
+use crate::packet::state::SystemState;
+use crate::sim::trajectory::EARTH_RADIUS;
+use crate::units::{Angle, Length, Ned};
+
+/// Default margin either side of a boundary before a crossing counts
+pub const DEFAULT_HYSTERESIS: Length = Length::from_meters(5.0);
+
+#[derive(Debug, Clone, PartialEq)]
+enum Shape {
+    Circle { latitude: Angle, longitude: Angle, radius: Length },
+    Polygon(Vec<(Angle, Angle)>),
+}
+
+/// Area on the Earth's surface
+#[derive(Debug, Clone, PartialEq)]
+pub struct Zone {
+    shape: Shape,
+}
+
+/// Great circle distance between two points
+fn haversine(lat1: f64, lon1: f64, lat2: f64, lon2: f64) -> f64 {
+    let a = ((lat2 - lat1) / 2.0).sin().powi(2) + lat1.cos() * lat2.cos() * ((lon2 - lon1) / 2.0).sin().powi(2);
+    2.0 * EARTH_RADIUS.meters() * a.sqrt().min(1.0).asin()
+}
+
+/// WGS84 semi-major axis in meters
+const WGS84_A: f64 = 6_378_137.0;
+/// WGS84 first eccentricity squared
+const WGS84_E2: f64 = 6.694_379_990_14e-3;
+
+/// Great circle distance between two fixes on a spherical Earth, ignoring
+/// height
+pub fn distance_haversine(a: &SystemState, b: &SystemState) -> Length {
+    Length::from_meters(haversine(a.latitude, a.longitude, b.latitude, b.longitude))
+}
+
+/// Initial great circle bearing from `a` to `b`, clockwise from true north
+/// in [0, 2π)
+pub fn bearing(a: &SystemState, b: &SystemState) -> Angle {
+    let d_lon = b.longitude - a.longitude;
+    let y = d_lon.sin() * b.latitude.cos();
+    let x = a.latitude.cos() * b.latitude.sin() - a.latitude.sin() * b.latitude.cos() * d_lon.cos();
+    normalize_2pi(Angle::from_radians(y.atan2(x)))
+}
+
+/// Offset of a point from an origin in the origin's local tangent plane
+pub type NedOffset = Ned<Length>;
+
+impl NedOffset {
+    /// Split the horizontal offset into the distance along a track with
+    /// `course` (clockwise from true north) and the distance right of it
+    pub fn along_cross_track(&self, course: Angle) -> (Length, Length) {
+        let (sin, cos) = course.radians().sin_cos();
+        let (north, east) = (self.north.meters(), self.east.meters());
+        (Length::from_meters(north * cos + east * sin), Length::from_meters(east * cos - north * sin))
+    }
+}
+
+fn ecef(latitude: f64, longitude: f64, height: f64) -> [f64; 3] {
+    let n = WGS84_A / (1.0 - WGS84_E2 * latitude.sin().powi(2)).sqrt();
+    [
+        (n + height) * latitude.cos() * longitude.cos(),
+        (n + height) * latitude.cos() * longitude.sin(),
+        (n * (1.0 - WGS84_E2) + height) * latitude.sin(),
+    ]
+}
+
+impl SystemState {
+    /// Position relative to `origin` as north, east, down in the origin's
+    /// local tangent plane on the WGS84 ellipsoid
+    pub fn offset_from(&self, origin: &SystemState) -> NedOffset {
+        let o = ecef(origin.latitude, origin.longitude, origin.height);
+        let p = ecef(self.latitude, self.longitude, self.height);
+        let [dx, dy, dz] = [p[0] - o[0], p[1] - o[1], p[2] - o[2]];
+        let (sin_lat, cos_lat) = origin.latitude.sin_cos();
+        let (sin_lon, cos_lon) = origin.longitude.sin_cos();
+        Ned::new(
+            -sin_lat * cos_lon * dx - sin_lat * sin_lon * dy + cos_lat * dz,
+            -sin_lon * dx + cos_lon * dy,
+            -cos_lat * cos_lon * dx - cos_lat * sin_lon * dy - sin_lat * dz,
+        ).map(Length::from_meters)
+    }
+}
+
+/// Distance from the origin to segment `a`-`b`
+fn distance_to_segment(a: (f64, f64), b: (f64, f64)) -> f64 {
+    let (dx, dy) = (b.0 - a.0, b.1 - a.1);
+    let length_sq = dx * dx + dy * dy;
+    let t = if length_sq > 0.0 { (-(a.0 * dx + a.1 * dy) / length_sq).clamp(0.0, 1.0) } else { 0.0 };
+    (a.0 + t * dx).hypot(a.1 + t * dy)
+}
+
+impl Zone {
+    /// Circle of `radius` around a centre point
+    pub fn circle(latitude: Angle, longitude: Angle, radius: Length) -> Self {
+        Self { shape: Shape::Circle { latitude, longitude, radius } }
+    }
+
+    /// Polygon through `(latitude, longitude)` vertices in order. The last
+    /// vertex joins back to the first. Edges are straight lines on a local
+    /// flat-Earth projection, which is accurate for zones up to tens of
+    /// kilometres across.
+    pub fn polygon(vertices: Vec<(Angle, Angle)>) -> Result<Self, AnError> {
+        if vertices.len() < 3 {
+            return Err(AnError::ValidationFailed(format!(
+                "geofence polygon needs at least 3 vertices, got {}", vertices.len())));
+        }
+        Ok(Self { shape: Shape::Polygon(vertices) })
+    }
+
+    /// Distance from the boundary, negative inside the zone
+    pub fn signed_distance(&self, latitude: Angle, longitude: Angle) -> Length {
+        let (lat, lon) = (latitude.radians(), longitude.radians());
+        let meters = match &self.shape {
+            Shape::Circle { latitude, longitude, radius } => {
+                haversine(lat, lon, latitude.radians(), longitude.radians()) - radius.meters()
+            }
+            Shape::Polygon(vertices) => {
+                // East/north offsets of each vertex from the query point
+                let r = EARTH_RADIUS.meters();
+                let points: Vec<(f64, f64)> = vertices.iter()
+                    .map(|(v_lat, v_lon)| {
+                        let d_lon = shortest_angular_distance(lon, v_lon.radians());
+                        (d_lon * r * lat.cos(), (v_lat.radians() - lat) * r)
+                    })
+                    .collect();
+
+                let mut inside = false;
+                let mut distance = f64::INFINITY;
+                for (i, &a) in points.iter().enumerate() {
+                    let b = points[(i + 1) % points.len()];
+                    // Ray cast along +east from the origin
+                    if (a.1 > 0.0) != (b.1 > 0.0) && a.0 + (b.0 - a.0) * -a.1 / (b.1 - a.1) > 0.0 {
+                        inside = !inside;
+                    }
+                    distance = distance.min(distance_to_segment(a, b));
+                }
+                if inside { -distance } else { distance }
+            }
+        };
+        Length::from_meters(meters)
+    }
+
+    pub fn contains(&self, latitude: Angle, longitude: Angle) -> bool {
+        self.signed_distance(latitude, longitude).meters() <= 0.0
+    }
+}
+
+/// What happened at a zone
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GeofenceEventKind {
+    Enter,
+    Exit,
+    /// Still inside after the dwell time, reported once per visit
+    Dwell,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct GeofenceEvent {
+    pub zone: String,
+    pub kind: GeofenceEventKind,
+    /// Time of validity of the state that triggered the event
+    pub time: SystemTime,
+}
+
+#[derive(Debug, Clone)]
+struct Fence {
+    name: String,
+    zone: Zone,
+    inside: Option<bool>,
+    entered_at: Option<SystemTime>,
+    dwell_reported: bool,
+}
+
+/// Enter, exit and dwell detection for a set of zones
+///
+/// # Examples
+///
+/// ```
+/// use liban::geo::{Geofence, GeofenceEventKind, Zone};
+/// use liban::units::{Angle, Length};
+///
+/// let harbour = Zone::circle(Angle::from_degrees(-33.86), Angle::from_degrees(151.21), Length::from_meters(500.0));
+/// let mut fence = Geofence::new().with_zone("harbour", harbour);
+/// // for each SystemState: for event in fence.update(&state) { ... }
+/// assert_eq!(fence.is_inside("harbour"), None);
+/// ```
+#[derive(Debug, Clone)]
+pub struct Geofence {
+    fences: Vec<Fence>,
+    hysteresis: Length,
+    dwell_after: Option<Duration>,
+}
+
+impl Default for Geofence {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Geofence {
+    pub fn new() -> Self {
+        Self { fences: Vec::new(), hysteresis: DEFAULT_HYSTERESIS, dwell_after: None }
+    }
+
+    /// Margin either side of a boundary before a crossing counts
+    pub fn with_hysteresis(mut self, hysteresis: Length) -> Self {
+        self.hysteresis = hysteresis;
+        self
+    }
+
+    /// Report [`GeofenceEventKind::Dwell`] after staying inside this long
+    pub fn with_dwell(mut self, dwell_after: Duration) -> Self {
+        self.dwell_after = Some(dwell_after);
+        self
+    }
+
+    pub fn with_zone(mut self, name: impl Into<String>, zone: Zone) -> Self {
+        self.add_zone(name, zone);
+        self
+    }
+
+    /// Add a zone, replacing any zone with the same name
+    pub fn add_zone(&mut self, name: impl Into<String>, zone: Zone) {
+        let name = name.into();
+        self.fences.retain(|f| f.name != name);
+        self.fences.push(Fence { name, zone, inside: None, entered_at: None, dwell_reported: false });
+    }
+
+    /// Whether the vehicle is inside a zone, `None` for unknown zones and
+    /// before the first position clear of the hysteresis band
+    pub fn is_inside(&self, name: &str) -> Option<bool> {
+        self.fences.iter().find(|f| f.name == name).and_then(|f| f.inside)
+    }
+
+    /// Evaluate a state. States from a navigation filter that has not
+    /// initialised are ignored.
+    pub fn update(&mut self, state: &SystemState) -> Vec<GeofenceEvent> {
+        if !state.filter_status.navigation_filter_initialised() {
+            return Vec::new();
+        }
+        let time = state.timestamp();
+        let margin = self.hysteresis.meters().abs();
+
+        let mut events = Vec::new();
+        for fence in &mut self.fences {
+            let distance = fence.zone.signed_distance(state.latitude_angle(), state.longitude_angle()).meters();
+            let now_inside = if distance <= -margin {
+                Some(true)
+            } else if distance >= margin {
+                Some(false)
+            } else {
+                fence.inside
+            };
+
+            let mut emit = |kind| events.push(GeofenceEvent { zone: fence.name.clone(), kind, time });
+            match (fence.inside, now_inside) {
+                (Some(false) | None, Some(true)) => {
+                    fence.entered_at = Some(time);
+                    fence.dwell_reported = false;
+                    emit(GeofenceEventKind::Enter);
+                }
+                (Some(true), Some(false)) => {
+                    fence.entered_at = None;
+                    emit(GeofenceEventKind::Exit);
+                }
+                _ => {}
+            }
+            fence.inside = now_inside;
+
+            if let (Some(dwell_after), Some(entered_at)) = (self.dwell_after, fence.entered_at)
+                && !fence.dwell_reported
+                && time.duration_since(entered_at).unwrap_or(Duration::ZERO) >= dwell_after
+            {
+                fence.dwell_reported = true;
+                emit(GeofenceEventKind::Dwell);
+            }
+        }
+        events
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::packet::state::FilterStatus;
+    use crate::test_support::system_state;
+
+    fn state(north_m: f64, east_m: f64, seconds: u32) -> SystemState {
+        let r = EARTH_RADIUS.meters();
+        let latitude = -0.5 + north_m / r;
+        SystemState {
+            filter_status: FilterStatus::from(0x0003),
+            unix_time_seconds: seconds,
+            latitude,
+            longitude: 2.6 + east_m / (r * (-0.5f64).cos()),
+            ..system_state()
+        }
+    }
+
+    fn offset(north_m: f64, east_m: f64) -> (Angle, Angle) {
+        let s = state(north_m, east_m, 0);
+        (s.latitude_angle(), s.longitude_angle())
+    }
+
+    fn kinds(events: &[GeofenceEvent]) -> Vec<GeofenceEventKind> {
+        events.iter().map(|e| e.kind).collect()
+    }
+
+    #[test]
+    fn test_distance_and_bearing() {
+        let origin = state(0.0, 0.0, 0);
+        let target = state(300.0, 400.0, 0);
+        assert!((distance_haversine(&origin, &target).meters() - 500.0).abs() < 0.01);
+        assert!((bearing(&origin, &target).degrees() - 53.130).abs() < 0.01);
+        assert!((bearing(&target, &origin).degrees() - 233.130).abs() < 0.01);
+        assert_eq!(distance_haversine(&origin, &origin), Length::ZERO);
+    }
+
+    #[test]
+    fn test_offset_from() {
+        let origin = state(0.0, 0.0, 0);
+        let mut target = state(300.0, -400.0, 0);
+        target.height = 20.0;
+        let offset = target.offset_from(&origin);
+        // Spherical test fixes against the ellipsoid: within half a percent
+        assert!((offset.north.meters() - 300.0).abs() < 1.5, "{offset:?}");
+        assert!((offset.east.meters() + 400.0).abs() < 2.0, "{offset:?}");
+        assert!((offset.down.meters() + 20.0).abs() < 0.1, "{offset:?}");
+        assert!(origin.offset_from(&origin).north.meters().abs() < 1e-9);
+
+        // Heading due east, the target lies behind and to the left
+        let (along, cross) = offset.along_cross_track(Angle::from_degrees(90.0));
+        assert_eq!((along, cross), (offset.east, -offset.north));
+    }
+
+    #[test]
+    fn test_circle_distance() {
+        let (lat, lon) = offset(0.0, 0.0);
+        let zone = Zone::circle(lat, lon, Length::from_meters(100.0));
+        let (lat, lon) = offset(30.0, 40.0);
+        assert!((zone.signed_distance(lat, lon).meters() + 50.0).abs() < 0.01);
+        assert!(zone.contains(lat, lon));
+        let (lat, lon) = offset(0.0, -150.0);
+        assert!(!zone.contains(lat, lon));
+    }
+
+    #[test]
+    fn test_polygon_distance() {
+        // 200 m square with a notch cut into its east side
+        let zone = Zone::polygon(vec![
+            offset(-100.0, -100.0), offset(100.0, -100.0), offset(100.0, 100.0),
+            offset(10.0, 100.0), offset(0.0, 0.0), offset(-10.0, 100.0), offset(-100.0, 100.0),
+        ]).unwrap();
+        let (lat, lon) = offset(0.0, -90.0);
+        assert!((zone.signed_distance(lat, lon).meters() + 10.0).abs() < 0.01);
+        let (lat, lon) = offset(0.0, 50.0);
+        assert!(!zone.contains(lat, lon));
+        let (lat, lon) = offset(50.0, 50.0);
+        assert!(zone.contains(lat, lon));
+        let (lat, lon) = offset(150.0, 0.0);
+        assert!((zone.signed_distance(lat, lon).meters() - 50.0).abs() < 0.01);
+
+        assert!(Zone::polygon(vec![offset(0.0, 0.0), offset(1.0, 1.0)]).is_err());
+    }
+
+    #[test]
+    fn test_enter_exit_with_hysteresis() {
+        let (lat, lon) = offset(0.0, 0.0);
+        let mut fence = Geofence::new()
+            .with_hysteresis(Length::from_meters(5.0))
+            .with_zone("pen", Zone::circle(lat, lon, Length::from_meters(100.0)));
+
+        assert!(fence.update(&state(0.0, 200.0, 0)).is_empty());
+        assert_eq!(fence.is_inside("pen"), Some(false));
+
+        // Jitter around the boundary does not trigger anything
+        assert!(fence.update(&state(0.0, 98.0, 1)).is_empty());
+        assert!(fence.update(&state(0.0, 103.0, 2)).is_empty());
+
+        let events = fence.update(&state(0.0, 90.0, 3));
+        assert_eq!(kinds(&events), vec![GeofenceEventKind::Enter]);
+        assert_eq!(events[0].zone, "pen");
+        assert!(fence.update(&state(0.0, 102.0, 4)).is_empty());
+        assert_eq!(kinds(&fence.update(&state(0.0, 110.0, 5))), vec![GeofenceEventKind::Exit]);
+    }
+
+    #[test]
+    fn test_dwell_once_per_visit() {
+        let (lat, lon) = offset(0.0, 0.0);
+        let mut fence = Geofence::new()
+            .with_dwell(Duration::from_secs(10))
+            .with_zone("dock", Zone::circle(lat, lon, Length::from_meters(50.0)));
+
+        // Starting inside counts as entering
+        assert_eq!(kinds(&fence.update(&state(0.0, 0.0, 100))), vec![GeofenceEventKind::Enter]);
+        assert!(fence.update(&state(0.0, 0.0, 105)).is_empty());
+        assert_eq!(kinds(&fence.update(&state(0.0, 0.0, 110))), vec![GeofenceEventKind::Dwell]);
+        assert!(fence.update(&state(0.0, 0.0, 120)).is_empty());
+
+        assert_eq!(kinds(&fence.update(&state(0.0, 100.0, 121))), vec![GeofenceEventKind::Exit]);
+        assert_eq!(kinds(&fence.update(&state(0.0, 0.0, 122))), vec![GeofenceEventKind::Enter]);
+    }
+
+    #[test]
+    fn test_ignores_uninitialised_filter() {
+        let (lat, lon) = offset(0.0, 0.0);
+        let mut fence = Geofence::new().with_zone("dock", Zone::circle(lat, lon, Length::from_meters(50.0)));
+        let mut s = state(0.0, 0.0, 0);
+        s.filter_status = FilterStatus::default();
+        assert!(fence.update(&s).is_empty());
+        assert_eq!(fence.is_inside("dock"), None);
+    }
+}
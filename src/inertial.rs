@@ -0,0 +1,288 @@
+//! Delta-angle and delta-velocity integration of raw IMU data.
+//!
+//! External navigation filters usually want integrated increments rather
+//! than rate samples. [`DeltaIntegrator`] integrates the body rates and
+//! specific force of [`RawSensors`] packets over intervals the caller
+//! chooses, with coning compensation on the angle and rotation plus
+//! sculling compensation on the velocity (Savage's recursive two-sample
+//! form on trapezoidal sample increments). Gravity is not removed: the
+//! velocity increment is the integral of specific force in the body frame
+//! at the start of the interval.
+
+use std::time::{Duration, Instant};
+
+use crate::packet::Packet;
+use crate::packet::state::RawSensors;
+use crate::units::{Angle, Velocity};
+
+type Vector = [f64; 3];
+
+fn add(a: Vector, b: Vector) -> Vector {
+    [a[0] + b[0], a[1] + b[1], a[2] + b[2]]
+}
+
+fn scale(a: Vector, s: f64) -> Vector {
+    [a[0] * s, a[1] * s, a[2] * s]
+}
+
+fn cross(a: Vector, b: Vector) -> Vector {
+    [a[1] * b[2] - a[2] * b[1], a[2] * b[0] - a[0] * b[2], a[0] * b[1] - a[1] * b[0]]
+}
+
+/// Integrated motion over one interval
+#[derive(Debug, Clone, PartialEq)]
+pub struct DeltaIncrement {
+    /// Time of the first sample of the interval
+    pub start: Instant,
+    /// Time of the last sample of the interval
+    pub end: Instant,
+    /// Rotation vector of the body from `start` to `end`, coning compensated
+    pub delta_theta: [Angle; 3],
+    /// Specific force integral in the body frame at `start`, rotation and
+    /// sculling compensated
+    pub delta_velocity: [Velocity; 3],
+    /// Sample increments that went into the interval
+    pub samples: usize,
+}
+
+impl DeltaIncrement {
+    pub fn duration(&self) -> Duration {
+        self.end - self.start
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+struct Accumulator {
+    /// Uncompensated angle and velocity sums
+    alpha: Vector,
+    upsilon: Vector,
+    coning: Vector,
+    sculling: Vector,
+    /// Previous minor increments
+    last_alpha: Vector,
+    last_upsilon: Vector,
+    samples: usize,
+}
+
+impl Accumulator {
+    fn add(&mut self, d_alpha: Vector, d_upsilon: Vector) {
+        let alpha_term = add(self.alpha, scale(self.last_alpha, 1.0 / 6.0));
+        let upsilon_term = add(self.upsilon, scale(self.last_upsilon, 1.0 / 6.0));
+        self.coning = add(self.coning, scale(cross(alpha_term, d_alpha), 0.5));
+        self.sculling = add(self.sculling, scale(add(cross(alpha_term, d_upsilon), cross(upsilon_term, d_alpha)), 0.5));
+        self.alpha = add(self.alpha, d_alpha);
+        self.upsilon = add(self.upsilon, d_upsilon);
+        self.last_alpha = d_alpha;
+        self.last_upsilon = d_upsilon;
+        self.samples += 1;
+    }
+
+    fn delta_theta(&self) -> Vector {
+        add(self.alpha, self.coning)
+    }
+
+    fn delta_velocity(&self) -> Vector {
+        add(add(self.upsilon, scale(cross(self.alpha, self.upsilon), 0.5)), self.sculling)
+    }
+}
+
+/// Integrates RawSensors samples into delta-theta and delta-velocity
+///
+/// Intervals end on sample boundaries: either when [`take`](Self::take) is
+/// called, or at the first sample at least the configured interval after
+/// the interval's start.
+///
+/// # Examples
+///
+/// ```
+/// use std::time::Duration;
+/// use liban::inertial::DeltaIntegrator;
+///
+/// let integrator = DeltaIntegrator::new().with_interval(Duration::from_millis(10));
+/// // for each packet: if let Some(increment) = integrator.handle(&packet, Instant::now()) { ... }
+/// assert_eq!(integrator.interval(), Some(Duration::from_millis(10)));
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct DeltaIntegrator {
+    interval: Option<Duration>,
+    max_sample_gap: Option<Duration>,
+    previous: Option<(Instant, Vector, Vector)>,
+    start: Option<Instant>,
+    accumulator: Accumulator,
+}
+
+impl DeltaIntegrator {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Close an interval automatically once it spans at least `interval`
+    pub fn with_interval(mut self, interval: Duration) -> Self {
+        self.interval = Some(interval);
+        self
+    }
+
+    /// Samples further apart than this are not integrated across; the
+    /// interval in progress is discarded and restarts at the later sample
+    pub fn with_max_sample_gap(mut self, max_sample_gap: Duration) -> Self {
+        self.max_sample_gap = Some(max_sample_gap);
+        self
+    }
+
+    pub fn interval(&self) -> Option<Duration> {
+        self.interval
+    }
+
+    /// Feed any packet; packets other than RawSensors are ignored
+    pub fn handle(&mut self, packet: &Packet, at: Instant) -> Option<DeltaIncrement> {
+        match packet {
+            Packet::RawSensors(sensors) => self.push(sensors, at),
+            _ => None,
+        }
+    }
+
+    /// Integrate a sample taken at `at`. Returns the finished interval when
+    /// an automatic interval is configured and this sample completes it.
+    /// Samples not after the previous one are ignored.
+    pub fn push(&mut self, sensors: &RawSensors, at: Instant) -> Option<DeltaIncrement> {
+        let rate = [sensors.gyroscope_x, sensors.gyroscope_y, sensors.gyroscope_z].map(f64::from);
+        let force = [sensors.accelerometer_x, sensors.accelerometer_y, sensors.accelerometer_z].map(f64::from);
+
+        match self.previous {
+            Some((previous_at, _, _)) if at <= previous_at => return None,
+            Some((previous_at, previous_rate, previous_force))
+                if self.max_sample_gap.is_none_or(|max| at - previous_at <= max) =>
+            {
+                let dt = (at - previous_at).as_secs_f64();
+                self.accumulator.add(
+                    scale(add(previous_rate, rate), 0.5 * dt),
+                    scale(add(previous_force, force), 0.5 * dt),
+                );
+            }
+            _ => {
+                self.accumulator = Accumulator::default();
+                self.start = Some(at);
+            }
+        }
+        self.previous = Some((at, rate, force));
+
+        let start = self.start?;
+        match self.interval {
+            Some(interval) if at - start >= interval => self.take(),
+            _ => None,
+        }
+    }
+
+    /// Close the interval in progress at the latest sample and start the
+    /// next one there. `None` if no sample increment has been integrated.
+    pub fn take(&mut self) -> Option<DeltaIncrement> {
+        let (end, _, _) = self.previous?;
+        let start = self.start.replace(end)?;
+        let accumulator = std::mem::take(&mut self.accumulator);
+        if accumulator.samples == 0 {
+            return None;
+        }
+        Some(DeltaIncrement {
+            start,
+            end,
+            delta_theta: accumulator.delta_theta().map(Angle::from_radians),
+            delta_velocity: accumulator.delta_velocity().map(Velocity::from_meters_per_second),
+            samples: accumulator.samples,
+        })
+    }
+
+    /// Drop the interval in progress and the previous sample
+    pub fn reset(&mut self) {
+        self.previous = None;
+        self.start = None;
+        self.accumulator = Accumulator::default();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::f64::consts::TAU;
+
+    fn sensors(rate: Vector, force: Vector) -> RawSensors {
+        RawSensors {
+            accelerometer_x: force[0] as f32,
+            accelerometer_y: force[1] as f32,
+            accelerometer_z: force[2] as f32,
+            gyroscope_x: rate[0] as f32,
+            gyroscope_y: rate[1] as f32,
+            gyroscope_z: rate[2] as f32,
+            imu_temperature: 25.0,
+            pressure: 101_325.0,
+            pressure_temperature: 25.0,
+        }
+    }
+
+    /// Feed `motion(t) -> (rate, force)` at `hz` for `seconds` and take one increment
+    fn integrate(hz: u32, seconds: f64, motion: impl Fn(f64) -> (Vector, Vector)) -> DeltaIncrement {
+        let start = Instant::now();
+        let mut integrator = DeltaIntegrator::new();
+        let steps = (seconds * hz as f64).round() as u32;
+        for k in 0..=steps {
+            let t = k as f64 / hz as f64;
+            let (rate, force) = motion(t);
+            assert!(integrator.push(&sensors(rate, force), start + Duration::from_secs_f64(t)).is_none());
+        }
+        integrator.take().unwrap()
+    }
+
+    #[test]
+    fn test_constant_rotation_and_sculling() {
+        // Yawing at 1 rad/s under constant forward specific force
+        let (w, f, seconds) = (1.0, 2.0, 0.05);
+        let increment = integrate(1000, seconds, |_| ([0.0, 0.0, w], [f, 0.0, 0.0]));
+        assert_eq!(increment.samples, 50);
+        assert!((increment.duration().as_secs_f64() - seconds).abs() < 1e-6);
+        assert!((increment.delta_theta[2].radians() - w * seconds).abs() < 1e-6);
+
+        // Exact: f/w (sin wT, 1 - cos wT) in the start frame, the residual
+        // being the third order term f w² T³ / 6
+        let dv = increment.delta_velocity.map(|v| v.meters_per_second());
+        assert!((dv[0] - f / w * (w * seconds).sin()).abs() < 1e-4, "{dv:?}");
+        assert!((dv[1] - f / w * (1.0 - (w * seconds).cos())).abs() < 1e-5, "{dv:?}");
+    }
+
+    #[test]
+    fn test_coning_compensation() {
+        // Coning at half-angle a and frequency W drifts the body about z by
+        // a²W/2 per unit time although the z rate is zero
+        let (a, w) = (0.02, TAU * 5.0);
+        let increment = integrate(400, 1.0, |t| ([a * w * (w * t).cos(), a * w * (w * t).sin(), 0.0], [0.0; 3]));
+        let expected = 0.5 * a * a * w;
+        assert!((increment.delta_theta[2].radians() - expected).abs() < 0.02 * expected, "{increment:?}");
+    }
+
+    #[test]
+    fn test_intervals() {
+        let start = Instant::now();
+        let ms = |n| start + Duration::from_millis(n);
+        let still = sensors([0.0; 3], [0.0, 0.0, -9.8]);
+        let mut integrator = DeltaIntegrator::new()
+            .with_interval(Duration::from_millis(20))
+            .with_max_sample_gap(Duration::from_millis(50));
+
+        assert_eq!(integrator.take(), None);
+        assert!(integrator.push(&still, ms(0)).is_none());
+        assert!(integrator.push(&still, ms(10)).is_none());
+        let first = integrator.push(&still, ms(20)).unwrap();
+        assert_eq!((first.start, first.end, first.samples), (ms(0), ms(20), 2));
+        assert!((first.delta_velocity[2].meters_per_second() + 0.196).abs() < 1e-6);
+
+        // Out of order samples are ignored, a long gap restarts the interval
+        assert!(integrator.push(&still, ms(15)).is_none());
+        assert!(integrator.push(&still, ms(30)).is_none());
+        assert!(integrator.push(&still, ms(200)).is_none());
+        assert!(integrator.push(&still, ms(210)).is_none());
+        let next = integrator.take().unwrap();
+        assert_eq!((next.start, next.end, next.samples), (ms(200), ms(210), 1));
+
+        integrator.reset();
+        assert!(integrator.handle(&Packet::RawSensors(still), ms(300)).is_none());
+        assert_eq!(integrator.take(), None);
+    }
+}
@@ -3,44 +3,95 @@
 //! A sans-io Rust library for working with Advanced Navigation devices using the
 //! Advanced Navigation Packet Protocol (ANPP).
 
+pub mod alarms;
+pub mod analysis;
+pub mod angle;
+pub mod capabilities;
+#[cfg(feature = "capture")]
+pub mod capture;
+pub mod client;
+pub mod clock;
+pub mod discovery;
+pub mod dispatch;
 pub mod error;
+#[cfg(feature = "serde")]
+pub mod export;
+pub mod fleet;
+pub mod flow;
+#[cfg(feature = "fuzzing")]
+pub mod fuzzing;
+pub mod geo;
+pub mod inertial;
+pub mod marine;
+#[cfg(feature = "mavlink")]
+pub mod mavlink;
+#[cfg(feature = "metrics")]
+pub mod metrics;
+pub mod ops;
 pub mod packet;
 pub mod parser;
+pub mod policy;
+pub mod profile;
 pub mod protocol;
+pub mod provision;
+pub mod rate;
 pub mod reader;
+pub mod resample;
+pub mod schedule;
+pub mod sim;
+pub mod telemetry;
+pub mod time_utils;
+#[cfg(any(test, feature = "test-utils"))]
+pub mod test_support;
+pub mod transport;
+pub mod units;
+pub mod wire;
+pub mod writer;
 
 pub use error::{AnError, Result};
-pub use packet::{Packet, PacketKind, HasPacketId};
-pub use parser::{AnppParser, parse_datagram, DatagramError};
+pub use client::WritePolicy;
+pub use packet::{Packet, PacketKind, PacketDirection, PacketInfo, HasPacketId, Payload};
+pub use parser::{AnppParser, ParserStats, ResyncPolicy, ReceivedPacket, parse_datagram, parse_datagram_with_profile, DatagramError};
+pub use profile::{ProtocolProfile, Quirk};
 
 // Re-export all public types from packet modules
 pub use packet::system::{
     Acknowledge, AcknowledgeResult, Request, BootMode, DeviceInformation, DeviceType,
-    RestoreFactorySettings, Reset, IpConfiguration,
+    DeviceModel, DeviceFamily, Version, RestoreFactorySettings, Reset, SerialPortPassthrough, PassthroughRoute, PassthroughBuffer,
+    FileTransferRequest, FileTransferAcknowledge, FileTransferData, FileTransferEncoding, FileTransferMetadata,
+    FileTransferResponse,
+    IpConfiguration, Ipv4Subnet, SubcomponentInformation, Subcomponent,
 };
 
 pub use packet::state::{
-    SystemStatus, FilterStatus, GnssFixType, SystemState, UnixTime, Status,
+    SystemStatus, FilterStatus, HeadingSource, EventId, GnssFixType, SystemState, UnixTime, EventTimestamp, FormattedTime, Status,
+    ExtensionFields, SystemStateExt, StatusExt,
     PositionStdDev, VelocityStdDev,
     EulerOrientationStdDev, QuaternionOrientationStdDev,
     RawSensors, RawGnss, RawGnssStatus,
-    Satellites,
+    Satellites, DetailedSatellites, SatelliteInfo, SatelliteSystem, SatelliteFrequencies,
     GeodeticPosition, EcefPosition, UtmPosition, NedVelocity, BodyVelocity,
     Acceleration, BodyAcceleration, EulerOrientation, QuaternionOrientation,
     DcmOrientation, AngularVelocity, AngularAcceleration,
     ExternalPositionVelocity, ExternalPosition, ExternalVelocity,
     ExternalBodyVelocity, ExternalHeading,
-    RunningTime, ExternalTime, GeoidHeight, RtcmCorrections,
-    Heave, RawDvlData, DvlStatus,
+    RunningTime, OdometerState, OdometerTracker, ExternalTime, ExternalDepth, GeoidHeight, RtcmCorrections,
+    Wind, Heave, AntennaOffsetEstimation, ExternalAirData, AirDataFlags, RawDvlData, DvlStatus,
     GnssReceiverInformation, GnssManufacturer, GnssReceiverModel,
-    SensorTemperature,
+    NorthSeekingStatus, NorthSeekingFlags, SensorTemperature,
     GnssPositionVelocityTime, GnssOrientation,
     GnssPvtStatus, GnssOrientationStatus, SpoofingStatus, InterferenceStatus,
 };
 
 pub use packet::config::{
-    PacketPeriod, PacketTimerPeriod, PacketsPeriod, OffsetVector,
-    InstallationAlignment, VehicleType, FilterOptions, OdometerConfiguration,
+    PacketPeriod, PacketTimerPeriod, PacketsPeriod, Bandwidth, LinkBudget, BaudRate, BaudRates, OffsetVector,
+    InstallationAlignment, InstallationAlignmentBuilder, VehicleType,
+    FilterOptions, FilterOptionsBuilder, FilterIssue, IssueSeverity, OdometerConfiguration,
     SetZeroOrientationAlignment, ReferencePointOffsets, UserData,
-    IpDataportMode, IpDataport, IpDataportsConfiguration,
+    GpioFunction, GpioOutputRate, GpioOutputRates,
+    GpioConfiguration, GpioOutputConfiguration, GpioInputConfiguration,
+    MagneticCalibrationAction, MagneticCalibrationStatusCode, MagneticCalibrationValues,
+    MagneticCalibrationConfiguration, MagneticCalibrationStatus,
+    IpDataportMode, IpDataport, IpDataportsConfiguration, IpDataportsConfigurationBuilder,
+    HeavePointMask, HeavePointId, VesselMotionConfiguration,
 };
@@ -0,0 +1,130 @@
+//! Vessel motion and heave.
+//!
+//! Groups the packets survey integrators need in one place: the heave
+//! reference points, the heave filter configuration and the Heave state
+//! packet. [`HeaveSetup`] produces the configuration packets for a complete
+//! heave setup in one go.
+
+use std::time::Duration;
+
+use crate::error::{AnError, Result};
+use crate::packet::Packet;
+
+pub use crate::packet::config::{
+    HeavePointId, HeavePointMask, OffsetVector, ReferencePointOffsets, VesselMotionConfiguration,
+};
+pub use crate::packet::state::Heave;
+
+/// Maximum number of heave reference points a device supports
+pub const MAX_HEAVE_POINTS: usize = 4;
+
+/// Complete heave configuration for a vessel
+#[derive(Debug, Clone, PartialEq)]
+pub struct HeaveSetup {
+    /// Reference points in the body frame, in meters from the unit. Points
+    /// not listed are disabled.
+    pub points: Vec<OffsetVector>,
+    /// Heave filter cutoff period
+    pub filter_period: Duration,
+    /// Store the configuration permanently
+    pub permanent: bool,
+}
+
+impl HeaveSetup {
+    /// Configuration packets to write to the device, in order
+    pub fn packets(&self) -> Result<Vec<Packet>> {
+        if self.points.is_empty() || self.points.len() > MAX_HEAVE_POINTS {
+            return Err(AnError::ValidationFailed(format!(
+                "expected 1 to {} heave points, got {}", MAX_HEAVE_POINTS, self.points.len())));
+        }
+        if self.filter_period.is_zero() {
+            return Err(AnError::ValidationFailed("heave filter period must be positive".to_string()));
+        }
+
+        let point = |i: usize| self.points.get(i).cloned().unwrap_or(OffsetVector { x: 0.0, y: 0.0, z: 0.0 });
+        let offsets = ReferencePointOffsets::new(self.permanent, [point(0), point(1), point(2), point(3)]);
+        let motion = VesselMotionConfiguration {
+            permanent: self.permanent,
+            heave_points: HeavePointMask::first(self.points.len()),
+            heave_filter_period: self.filter_period,
+        };
+
+        Ok(vec![
+            Packet::ReferencePointOffsets(offsets),
+            Packet::VesselMotionConfiguration(motion),
+        ])
+    }
+}
+
+/// Heave values for the enabled points only
+pub fn enabled_heave(heave: &Heave, mask: HeavePointMask) -> Vec<f32> {
+    HeavePointId::ALL.into_iter()
+        .filter(|point| mask.contains(*point))
+        .map(|point| heave.at(point).meters() as f32)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn bow() -> OffsetVector {
+        OffsetVector { x: 12.0, y: 0.0, z: -1.5 }
+    }
+
+    #[test]
+    fn test_heave_setup_packets() {
+        let setup = HeaveSetup {
+            points: vec![bow(), OffsetVector { x: -3.0, y: 0.0, z: 0.5 }],
+            filter_period: Duration::from_secs(20),
+            permanent: true,
+        };
+        let packets = setup.packets().unwrap();
+
+        match &packets[0] {
+            Packet::ReferencePointOffsets(offsets) => {
+                assert_eq!(offsets.heave_point_1, bow());
+                assert_eq!(offsets.heave_point_3, OffsetVector { x: 0.0, y: 0.0, z: 0.0 });
+            }
+            other => panic!("unexpected {:?}", other),
+        }
+        match &packets[1] {
+            Packet::VesselMotionConfiguration(motion) => {
+                assert_eq!(motion.heave_points.raw(), 0b0011);
+                assert_eq!(motion.heave_filter_period, Duration::from_secs(20));
+            }
+            other => panic!("unexpected {:?}", other),
+        }
+        for packet in &packets {
+            assert!(packet.to_bytes().is_ok());
+        }
+    }
+
+    #[test]
+    fn test_heave_setup_validation() {
+        let mut setup = HeaveSetup {
+            points: vec![],
+            filter_period: Duration::from_secs(20),
+            permanent: false,
+        };
+        assert!(setup.packets().is_err());
+
+        setup.points = vec![bow(); 5];
+        assert!(setup.packets().is_err());
+
+        setup.points = vec![bow()];
+        setup.filter_period = Duration::ZERO;
+        assert!(setup.packets().is_err());
+    }
+
+    #[test]
+    fn test_enabled_heave() {
+        let heave = Heave {
+            heave_point_1: 0.1,
+            heave_point_2: 0.2,
+            heave_point_3: 0.3,
+            heave_point_4: 0.4,
+        };
+        assert_eq!(enabled_heave(&heave, HeavePointMask::from(0b1010)), vec![0.2, 0.4]);
+    }
+}
@@ -0,0 +1,334 @@
+//! MAVLink common message conversions.
+//!
+//! Builds the `mavlink` crate's common dialect messages that autopilot
+//! stacks consume (`GLOBAL_POSITION_INT`, `ATTITUDE`, `GPS_RAW_INT`,
+//! `HIGHRES_IMU`) from navigation packets, and turns `GPS_INPUT` back into
+//! the external aiding packets. Wrap the messages in [`MavMessage`] and
+//! frame them with the `mavlink` crate, e.g. `mavlink::write_v2_msg`.
+//!
+//! MAVLink altitudes are above mean sea level while ANPP heights are above
+//! the WGS84 ellipsoid, so conversions take the geoid height (the Geoid
+//! Height packet, ID 54) to move between the two.
+
+use ::mavlink::dialects::common::{
+    ATTITUDE_DATA, GLOBAL_POSITION_INT_DATA, GPS_INPUT_DATA, GPS_RAW_INT_DATA, GpsFixType, GpsInputIgnoreFlags,
+    HIGHRES_IMU_DATA, HighresImuUpdatedFlags,
+};
+
+pub use ::mavlink::dialects::common::MavMessage;
+
+use crate::error::AnError;
+use crate::packet::Packet;
+use crate::packet::state::{
+    ExternalPosition, ExternalPositionVelocity, GnssFixType, RawSensors, Satellites, SystemState,
+};
+
+/// `GPS_FIX_TYPE` for a GNSS fix
+pub fn gps_fix_type(fix: GnssFixType) -> GpsFixType {
+    match fix {
+        GnssFixType::NoFix => GpsFixType::GPS_FIX_TYPE_NO_FIX,
+        GnssFixType::Fix2D => GpsFixType::GPS_FIX_TYPE_2D_FIX,
+        GnssFixType::Fix3D => GpsFixType::GPS_FIX_TYPE_3D_FIX,
+        GnssFixType::SbassFix | GnssFixType::DifferentialFix => GpsFixType::GPS_FIX_TYPE_DGPS,
+        GnssFixType::PppFix => GpsFixType::GPS_FIX_TYPE_PPP,
+        GnssFixType::RtkFloat => GpsFixType::GPS_FIX_TYPE_RTK_FLOAT,
+        GnssFixType::RtkFixed => GpsFixType::GPS_FIX_TYPE_RTK_FIXED,
+    }
+}
+
+fn deg_e7(radians: f64) -> i32 {
+    (radians.to_degrees() * 1e7).round() as i32
+}
+
+fn from_deg_e7(value: i32) -> f64 {
+    (value as f64 / 1e7).to_radians()
+}
+
+/// Heading in centidegrees, 0 to 35999
+fn centidegrees(radians: f32) -> u16 {
+    let cdeg = (radians.to_degrees() * 100.0).round().rem_euclid(36000.0);
+    cdeg as u16 % 36000
+}
+
+fn saturate_i16(value: f32) -> i16 {
+    value.round().clamp(i16::MIN as f32, i16::MAX as f32) as i16
+}
+
+fn dop(value: f32) -> u16 {
+    if value.is_finite() && value >= 0.0 {
+        (value * 100.0).round().min(u16::MAX as f32 - 1.0) as u16
+    } else {
+        u16::MAX
+    }
+}
+
+/// `GLOBAL_POSITION_INT` (#33); `home_altitude` is above mean sea level,
+/// in meters
+pub fn global_position_int(state: &SystemState, time_boot_ms: u32, geoid_height: f32, home_altitude: f64) -> GLOBAL_POSITION_INT_DATA {
+    let msl = state.height - geoid_height as f64;
+    GLOBAL_POSITION_INT_DATA {
+        time_boot_ms,
+        lat: deg_e7(state.latitude),
+        lon: deg_e7(state.longitude),
+        alt: (msl * 1000.0).round() as i32,
+        relative_alt: ((msl - home_altitude) * 1000.0).round() as i32,
+        vx: saturate_i16(state.velocity_north * 100.0),
+        vy: saturate_i16(state.velocity_east * 100.0),
+        vz: saturate_i16(state.velocity_down * 100.0),
+        hdg: if state.filter_status.heading_initialised() { centidegrees(state.heading.get()) } else { u16::MAX },
+    }
+}
+
+/// `ATTITUDE` (#30), with yaw wrapped into MAVLink's (-π, π]
+pub fn attitude(state: &SystemState, time_boot_ms: u32) -> ATTITUDE_DATA {
+    ATTITUDE_DATA {
+        time_boot_ms,
+        roll: state.roll.get(),
+        pitch: state.pitch.get(),
+        yaw: crate::angle::normalize_pi(state.heading).get(),
+        rollspeed: state.angular_velocity_x,
+        pitchspeed: state.angular_velocity_y,
+        yawspeed: state.angular_velocity_z,
+    }
+}
+
+/// `GPS_RAW_INT` (#24), timestamped with the solution's Unix time
+pub fn gps_raw_int(state: &SystemState, satellites: &Satellites, geoid_height: f32) -> GPS_RAW_INT_DATA {
+    let speed = state.velocity_north.hypot(state.velocity_east);
+    let time_usec = state.unix_time_seconds as u64 * 1_000_000 + state.microseconds as u64;
+    let visible = [
+        satellites.gps_satellites,
+        satellites.glonass_satellites,
+        satellites.beidou_satellites,
+        satellites.galileo_satellites,
+        satellites.sbas_satellites,
+    ].iter().fold(0u8, |sum, &n| sum.saturating_add(n));
+    GPS_RAW_INT_DATA {
+        time_usec,
+        fix_type: gps_fix_type(state.filter_status.gnss_fix_type()),
+        lat: deg_e7(state.latitude),
+        lon: deg_e7(state.longitude),
+        alt: ((state.height - geoid_height as f64) * 1000.0).round() as i32,
+        eph: dop(satellites.hdop),
+        epv: dop(satellites.vdop),
+        vel: (speed * 100.0).round().min(u16::MAX as f32 - 1.0) as u16,
+        // Course is undefined when standing still
+        cog: if speed > 0.1 { centidegrees(state.velocity_east.atan2(state.velocity_north)) } else { u16::MAX },
+        satellites_visible: visible,
+    }
+}
+
+/// `HIGHRES_IMU` (#105) with acceleration, angular rate, pressure and
+/// temperature
+pub fn highres_imu(raw: &RawSensors, time_usec: u64) -> HIGHRES_IMU_DATA {
+    HIGHRES_IMU_DATA {
+        time_usec,
+        xacc: raw.accelerometer_x,
+        yacc: raw.accelerometer_y,
+        zacc: raw.accelerometer_z,
+        xgyro: raw.gyroscope_x,
+        ygyro: raw.gyroscope_y,
+        zgyro: raw.gyroscope_z,
+        abs_pressure: raw.pressure / 100.0,
+        temperature: raw.imu_temperature,
+        fields_updated: HighresImuUpdatedFlags::HIGHRES_IMU_UPDATED_XACC
+            | HighresImuUpdatedFlags::HIGHRES_IMU_UPDATED_YACC
+            | HighresImuUpdatedFlags::HIGHRES_IMU_UPDATED_ZACC
+            | HighresImuUpdatedFlags::HIGHRES_IMU_UPDATED_XGYRO
+            | HighresImuUpdatedFlags::HIGHRES_IMU_UPDATED_YGYRO
+            | HighresImuUpdatedFlags::HIGHRES_IMU_UPDATED_ZGYRO
+            | HighresImuUpdatedFlags::HIGHRES_IMU_UPDATED_ABS_PRESSURE
+            | HighresImuUpdatedFlags::HIGHRES_IMU_UPDATED_TEMPERATURE,
+        ..HIGHRES_IMU_DATA::DEFAULT
+    }
+}
+
+/// External aiding packet for a `GPS_INPUT` (#232) fix: External Position
+/// and Velocity when velocity and its accuracy are present, External
+/// Position otherwise. Fails without a 3D fix or position accuracies,
+/// which the filter needs to weight the measurement.
+pub fn external_aiding(input: &GPS_INPUT_DATA, geoid_height: f32) -> Result<Packet, AnError> {
+    let has = |flag: GpsInputIgnoreFlags| !input.ignore_flags.contains(flag);
+
+    if (input.fix_type as u32) < GpsFixType::GPS_FIX_TYPE_3D_FIX as u32 || !has(GpsInputIgnoreFlags::GPS_INPUT_IGNORE_FLAG_ALT) {
+        return Err(AnError::ValidationFailed("GPS_INPUT has no 3D fix".to_string()));
+    }
+    if !has(GpsInputIgnoreFlags::GPS_INPUT_IGNORE_FLAG_HORIZONTAL_ACCURACY)
+        || !has(GpsInputIgnoreFlags::GPS_INPUT_IGNORE_FLAG_VERTICAL_ACCURACY)
+    {
+        return Err(AnError::ValidationFailed("GPS_INPUT has no position accuracy".to_string()));
+    }
+
+    let latitude = from_deg_e7(input.lat);
+    let longitude = from_deg_e7(input.lon);
+    let height = input.alt as f64 + geoid_height as f64;
+
+    if has(GpsInputIgnoreFlags::GPS_INPUT_IGNORE_FLAG_VEL_HORIZ)
+        && has(GpsInputIgnoreFlags::GPS_INPUT_IGNORE_FLAG_VEL_VERT)
+        && has(GpsInputIgnoreFlags::GPS_INPUT_IGNORE_FLAG_SPEED_ACCURACY)
+    {
+        Ok(Packet::ExternalPositionVelocity(ExternalPositionVelocity {
+            latitude,
+            longitude,
+            height,
+            velocity_north: input.vn,
+            velocity_east: input.ve,
+            velocity_down: input.vd,
+            latitude_std_dev: input.horiz_accuracy,
+            longitude_std_dev: input.horiz_accuracy,
+            height_std_dev: input.vert_accuracy,
+            velocity_north_std_dev: input.speed_accuracy,
+            velocity_east_std_dev: input.speed_accuracy,
+            velocity_down_std_dev: input.speed_accuracy,
+        }))
+    } else {
+        Ok(Packet::ExternalPosition(ExternalPosition {
+            latitude,
+            longitude,
+            height,
+            latitude_std_dev: input.horiz_accuracy,
+            longitude_std_dev: input.horiz_accuracy,
+            height_std_dev: input.vert_accuracy,
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::packet::state::FilterStatus;
+    use crate::test_support::system_state;
+    use crate::units::Radians;
+
+    fn state() -> SystemState {
+        SystemState {
+            // Orientation and heading initialised, RTK fixed
+            filter_status: FilterStatus::from(0x0001 | 0x0004 | (7 << 4)),
+            unix_time_seconds: 1_700_000_000,
+            microseconds: 250_000,
+            latitude: (-33.8688f64).to_radians(),
+            longitude: 151.2093f64.to_radians(),
+            height: 30.0,
+            velocity_north: 3.0,
+            velocity_east: -3.0,
+            velocity_down: 0.25,
+            roll: Radians(0.1),
+            pitch: Radians(-0.05),
+            heading: Radians(-std::f32::consts::FRAC_PI_2),
+            angular_velocity_x: 0.01,
+            angular_velocity_y: 0.02,
+            angular_velocity_z: 0.03,
+            latitude_std_dev: 0.02,
+            longitude_std_dev: 0.02,
+            height_std_dev: 0.05,
+            ..system_state()
+        }
+    }
+
+    fn gps_input(ignore_flags: GpsInputIgnoreFlags) -> GPS_INPUT_DATA {
+        GPS_INPUT_DATA {
+            ignore_flags,
+            fix_type: GpsFixType::GPS_FIX_TYPE_3D_FIX,
+            lat: -338_688_000,
+            lon: 1_512_093_000,
+            alt: 8.0,
+            vn: 1.0,
+            ve: 2.0,
+            vd: 0.0,
+            speed_accuracy: 0.1,
+            horiz_accuracy: 1.5,
+            vert_accuracy: 3.0,
+            satellites_visible: 12,
+            ..GPS_INPUT_DATA::DEFAULT
+        }
+    }
+
+    #[test]
+    fn test_global_position_int() {
+        let msg = global_position_int(&state(), 1234, 22.0, 5.0);
+        assert_eq!(msg.time_boot_ms, 1234);
+        assert_eq!(msg.lat, -338_688_000);
+        assert_eq!(msg.lon, 1_512_093_000);
+        assert_eq!(msg.alt, 8_000);
+        assert_eq!(msg.relative_alt, 3_000);
+        assert_eq!((msg.vx, msg.vy, msg.vz), (300, -300, 25));
+        assert_eq!(msg.hdg, 27_000);
+
+        let msg = attitude(&state(), 1234);
+        assert!((msg.yaw + std::f32::consts::FRAC_PI_2).abs() < 1e-6);
+        assert_eq!(msg.yawspeed, 0.03);
+
+        // The device reports heading in [0, 2π)
+        let west = SystemState { heading: Radians(3.0 * std::f32::consts::FRAC_PI_2), ..state() };
+        assert!((attitude(&west, 0).yaw + std::f32::consts::FRAC_PI_2).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_gps_raw_int() {
+        let satellites = Satellites {
+            hdop: 0.8, vdop: 1.25, gps_satellites: 10, glonass_satellites: 6,
+            beidou_satellites: 0, galileo_satellites: 5, sbas_satellites: 1,
+        };
+        let msg = gps_raw_int(&state(), &satellites, 22.0);
+        assert_eq!(msg.time_usec, 1_700_000_000_250_000);
+        assert_eq!(msg.fix_type, GpsFixType::GPS_FIX_TYPE_RTK_FIXED);
+        assert_eq!((msg.eph, msg.epv), (80, 125));
+        assert_eq!(msg.vel, 424);
+        assert_eq!(msg.cog, 31_500);
+        assert_eq!(msg.satellites_visible, 22);
+    }
+
+    #[test]
+    fn test_gps_input_to_external_aiding() {
+        let ignore = GpsInputIgnoreFlags::GPS_INPUT_IGNORE_FLAG_HDOP | GpsInputIgnoreFlags::GPS_INPUT_IGNORE_FLAG_VDOP;
+        match external_aiding(&gps_input(ignore), 22.0).unwrap() {
+            Packet::ExternalPositionVelocity(p) => {
+                assert!((p.latitude.to_degrees() + 33.8688).abs() < 1e-7);
+                assert!((p.height - 30.0).abs() < 1e-6);
+                assert_eq!(p.velocity_east, 2.0);
+                assert_eq!(p.latitude_std_dev, 1.5);
+                assert_eq!(p.velocity_down_std_dev, 0.1);
+            }
+            other => panic!("unexpected {:?}", other),
+        }
+
+        let ignore = ignore | GpsInputIgnoreFlags::GPS_INPUT_IGNORE_FLAG_VEL_VERT;
+        assert!(matches!(external_aiding(&gps_input(ignore), 22.0).unwrap(), Packet::ExternalPosition(_)));
+
+        let ignore = ignore | GpsInputIgnoreFlags::GPS_INPUT_IGNORE_FLAG_HORIZONTAL_ACCURACY;
+        assert!(external_aiding(&gps_input(ignore), 22.0).is_err());
+        let input = GPS_INPUT_DATA { fix_type: GpsFixType::GPS_FIX_TYPE_2D_FIX, ..gps_input(GpsInputIgnoreFlags::empty()) };
+        assert!(external_aiding(&input, 22.0).is_err());
+    }
+
+    #[test]
+    fn test_round_trip_through_aiding() {
+        let state = state();
+        let position = global_position_int(&state, 0, 22.0, 0.0);
+        let input = GPS_INPUT_DATA {
+            lat: position.lat, lon: position.lon, alt: position.alt as f32 / 1000.0,
+            vn: state.velocity_north, ve: state.velocity_east, vd: state.velocity_down,
+            horiz_accuracy: 1.0, vert_accuracy: 1.0,
+            ..gps_input(GpsInputIgnoreFlags::empty())
+        };
+        let Packet::ExternalPositionVelocity(aiding) = external_aiding(&input, 22.0).unwrap() else {
+            panic!("expected position and velocity");
+        };
+        // Degrees * 1e7 keeps about a centimetre of precision
+        assert!((aiding.latitude - state.latitude).abs() < 1e-8);
+        assert!((aiding.height - state.height).abs() < 1e-3);
+    }
+
+    #[test]
+    fn test_frames_with_mavlink() {
+        let message = MavMessage::GLOBAL_POSITION_INT(global_position_int(&state(), 1234, 22.0, 5.0));
+        let header = ::mavlink::MavHeader { system_id: 1, component_id: 1, sequence: 7 };
+        let mut frame = Vec::new();
+        ::mavlink::write_v2_msg(&mut frame, header, &message).unwrap();
+
+        let mut reader = ::mavlink::MavlinkReader::new(frame.as_slice());
+        let (read_header, read) = reader.read_message::<MavMessage>(::mavlink::MavlinkVersion::V2).unwrap();
+        assert_eq!(read_header, header);
+        assert_eq!(read, message);
+    }
+}
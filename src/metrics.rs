@@ -0,0 +1,181 @@
+//! Device health metrics.
+//!
+//! The `record_*` functions publish counters and gauges from the parser,
+//! [`TelemetryStore`], [`RateMonitor`] and [`Client`] through the `metrics`
+//! facade, so they reach whichever recorder the application installed,
+//! e.g. `metrics-exporter-prometheus`. Counters are set to their current
+//! totals with `absolute`, so recording is idempotent and can run on every
+//! scrape.
+//!
+//! | Metric | Type | Labels |
+//! |---|---|---|
+//! | `liban_packets_decoded_total` | counter | |
+//! | `liban_header_errors_total`, `liban_crc_errors_total`, `liban_payload_errors_total` | counter | |
+//! | `liban_bytes_discarded_total` | counter | |
+//! | `liban_connected`, `liban_pending_transactions` | gauge | |
+//! | `liban_gnss_fix_type` | gauge, 0 (none) to 7 (RTK fixed) | |
+//! | `liban_navigation_filter_initialised` | gauge, 0 or 1 | |
+//! | `liban_satellites`, `liban_hdop`, `liban_vdop` | gauge | |
+//! | `liban_packet_age_seconds` | gauge | `packet` |
+//! | `liban_packets_total` | counter | `packet` |
+//! | `liban_packet_rate_hz`, `liban_packet_jitter_seconds` | gauge | `packet` |
+
+use std::time::Instant;
+
+use ::metrics::{counter, gauge};
+
+use crate::client::Client;
+use crate::packet::PacketKind;
+use crate::parser::ParserStats;
+use crate::rate::RateMonitor;
+use crate::telemetry::TelemetryStore;
+
+fn packet_label(kind: PacketKind) -> String {
+    format!("{:?}", kind)
+}
+
+/// Decoder counters from [`AnppParser::stats`](crate::AnppParser::stats)
+pub fn record_parser(stats: &ParserStats) {
+    counter!("liban_packets_decoded_total").absolute(stats.packets);
+    counter!("liban_header_errors_total").absolute(stats.header_errors);
+    counter!("liban_crc_errors_total").absolute(stats.crc_errors);
+    counter!("liban_payload_errors_total").absolute(stats.payload_errors);
+    counter!("liban_bytes_discarded_total").absolute(stats.bytes_discarded);
+}
+
+/// Fix, satellite and filter state plus the age of every stored packet
+pub fn record_telemetry(store: &TelemetryStore, now: Instant) {
+    let filter = store.system_state().map(|s| s.filter_status)
+        .or_else(|| store.status().map(|s| s.filter_status));
+    if let Some(filter) = filter {
+        gauge!("liban_gnss_fix_type").set(filter.gnss_fix_type() as u8 as f64);
+        gauge!("liban_navigation_filter_initialised")
+            .set(if filter.navigation_filter_initialised() { 1.0 } else { 0.0 });
+    }
+    if let Some(satellites) = store.satellites() {
+        let count = satellites.gps_satellites as u32 + satellites.glonass_satellites as u32
+            + satellites.beidou_satellites as u32 + satellites.galileo_satellites as u32
+            + satellites.sbas_satellites as u32;
+        gauge!("liban_satellites").set(count as f64);
+        gauge!("liban_hdop").set(satellites.hdop as f64);
+        gauge!("liban_vdop").set(satellites.vdop as f64);
+    }
+    for kind in store.kinds() {
+        if let Some(age) = store.age(kind, now) {
+            gauge!("liban_packet_age_seconds", "packet" => packet_label(kind)).set(age.as_secs_f64());
+        }
+    }
+}
+
+/// Arrival count, rate and jitter per packet kind
+pub fn record_rates(monitor: &RateMonitor) {
+    for (kind, stats) in monitor.all() {
+        let label = packet_label(kind);
+        counter!("liban_packets_total", "packet" => label.clone()).absolute(stats.count);
+        gauge!("liban_packet_rate_hz", "packet" => label.clone()).set(stats.rate_hz);
+        gauge!("liban_packet_jitter_seconds", "packet" => label).set(stats.jitter.as_secs_f64());
+    }
+}
+
+/// Link state, outstanding transactions and the client's parser counters
+pub fn record_client(client: &Client) {
+    gauge!("liban_connected").set(if client.is_connected() { 1.0 } else { 0.0 });
+    gauge!("liban_pending_transactions").set(client.pending() as f64);
+    record_parser(client.parser_stats());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::BTreeMap;
+    use std::time::Duration;
+    use metrics_util::debugging::{DebugValue, DebuggingRecorder};
+    use crate::packet::Packet;
+    use crate::packet::state::{FilterStatus, Satellites, Status, SystemStatus};
+    use crate::packet::system::Request;
+
+    /// Values recorded by `record`, keyed by `name{label="value"}`
+    fn capture(record: impl FnOnce()) -> BTreeMap<String, f64> {
+        let recorder = DebuggingRecorder::new();
+        let snapshotter = recorder.snapshotter();
+        ::metrics::with_local_recorder(&recorder, record);
+        snapshotter.snapshot().into_vec().into_iter()
+            .map(|(key, _, _, value)| {
+                let key = key.key();
+                let labels: Vec<String> = key.labels().map(|l| format!("{}=\"{}\"", l.key(), l.value())).collect();
+                let name = if labels.is_empty() { key.name().to_string() } else { format!("{}{{{}}}", key.name(), labels.join(",")) };
+                let value = match value {
+                    DebugValue::Counter(v) => v as f64,
+                    DebugValue::Gauge(v) => v.into_inner(),
+                    DebugValue::Histogram(_) => unreachable!("no histograms are recorded"),
+                };
+                (name, value)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_parser_counters() {
+        let mut parser = crate::AnppParser::new();
+        let mut frame = Packet::Request(Request::single(PacketKind::Status)).to_bytes().unwrap();
+        assert!(parser.consume(&frame).is_some());
+        frame[5] ^= 0xFF;
+        assert!(parser.consume(&frame).is_none());
+
+        let values = capture(|| record_parser(parser.stats()));
+        assert_eq!(values["liban_packets_decoded_total"], 1.0);
+        assert_eq!(values["liban_crc_errors_total"], 1.0);
+    }
+
+    #[test]
+    fn test_telemetry_and_rates() {
+        let start = Instant::now();
+        let mut store = TelemetryStore::new();
+        let mut rates = RateMonitor::new();
+        let status = Packet::Status(Status {
+            system_status: SystemStatus::default(),
+            filter_status: FilterStatus::from(0x0072),
+            extension: None,
+        });
+        let satellites = Packet::Satellites(Satellites {
+            hdop: 0.9,
+            vdop: 1.2,
+            gps_satellites: 9,
+            glonass_satellites: 6,
+            beidou_satellites: 0,
+            galileo_satellites: 4,
+            sbas_satellites: 1,
+        });
+        for i in 0..3 {
+            let at = start + Duration::from_millis(100 * i);
+            rates.observe(&status, at);
+            store.update(status.clone(), at);
+        }
+        store.update(satellites, start);
+
+        let values = capture(|| {
+            record_telemetry(&store, start + Duration::from_millis(500));
+            record_rates(&rates);
+        });
+
+        assert_eq!(values["liban_gnss_fix_type"], 7.0);
+        assert_eq!(values["liban_navigation_filter_initialised"], 1.0);
+        assert_eq!(values["liban_satellites"], 20.0);
+        assert!((values["liban_packet_age_seconds{packet=\"Status\"}"] - 0.3).abs() < 1e-9);
+        assert!((values["liban_packet_age_seconds{packet=\"Satellites\"}"] - 0.5).abs() < 1e-9);
+        assert_eq!(values["liban_packets_total{packet=\"Status\"}"], 3.0);
+        assert!((values["liban_packet_rate_hz{packet=\"Status\"}"] - 10.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_client_gauges() {
+        let mut client = Client::new();
+        client.connected(Instant::now());
+        client.request(PacketKind::DeviceInformation).unwrap();
+
+        let values = capture(|| record_client(&client));
+        assert_eq!(values["liban_connected"], 1.0);
+        assert_eq!(values["liban_pending_transactions"], 1.0);
+        assert_eq!(values["liban_packets_decoded_total"], 0.0);
+    }
+}
@@ -0,0 +1,1272 @@
+//! Operational procedures built on top of the packet layer.
+//!
+//! Everything in here is sans-io: the caller sends the packets we hand back,
+//! feeds us whatever the device returns and supplies the current time.
+
+use std::collections::{BTreeMap, HashSet};
+use std::ops::Range;
+use std::time::{Duration, Instant};
+
+use crate::packet::{Packet, PacketKind};
+use crate::packet::config::{
+    InstallationAlignment, MagneticCalibrationAction, MagneticCalibrationConfiguration,
+    MagneticCalibrationStatusCode, MagneticCalibrationValues,
+};
+use crate::packet::state::{AntennaOffsetEstimation, GnssFixType, SystemStatus};
+use crate::packet::system::{
+    AcknowledgeResult, DeviceInformation, DeviceModel, DeviceType, FileTransferAcknowledge,
+    FileTransferData, FileTransferRequest, FileTransferResponse, Request,
+};
+use crate::units::Length;
+
+/// Pass/fail thresholds for an acceptance run
+#[derive(Debug, Clone, PartialEq)]
+pub struct AcceptanceThresholds {
+    /// Device type the unit must report, if any
+    pub expected_device_type: Option<DeviceType>,
+    /// Configuration packets that must be read back from the device
+    pub required_configs: Vec<PacketKind>,
+    /// How long to collect streaming statistics for
+    pub stream_duration: Duration,
+    /// Minimum average SystemState rate over the stream window in Hz
+    pub min_system_state_rate_hz: f64,
+    /// Largest allowed gap between consecutive SystemState packets
+    pub max_system_state_gap: Duration,
+    /// Minimum GNSS fix type that must be reached during the stream window
+    pub min_fix_type: GnssFixType,
+    /// System status bits that are tolerated during the run
+    pub allowed_alarm_mask: u16,
+}
+
+impl Default for AcceptanceThresholds {
+    fn default() -> Self {
+        Self {
+            expected_device_type: None,
+            required_configs: vec![
+                PacketKind::FilterOptions,
+                PacketKind::InstallationAlignment,
+                PacketKind::PacketTimerPeriod,
+                PacketKind::IpDataportsConfiguration,
+            ],
+            stream_duration: Duration::from_secs(60),
+            min_system_state_rate_hz: 10.0,
+            max_system_state_gap: Duration::from_millis(500),
+            min_fix_type: GnssFixType::Fix3D,
+            allowed_alarm_mask: 0,
+        }
+    }
+}
+
+impl AcceptanceThresholds {
+    /// Thresholds for a given product line
+    pub fn for_device_type(device_type: DeviceType) -> Self {
+        let mut base = Self {
+            expected_device_type: Some(device_type),
+            ..Self::default()
+        };
+        // Dataports only exist on units configured over Ethernet
+        if !DeviceModel::from(device_type).has_ethernet() {
+            base.required_configs.retain(|kind| *kind != PacketKind::IpDataportsConfiguration);
+        }
+
+        match device_type {
+            DeviceType::BoreasD90 | DeviceType::BoreasD70 | DeviceType::BoreasD50 |
+            DeviceType::BoreasA90 | DeviceType::BoreasA70 | DeviceType::BoreasA50 => Self {
+                min_system_state_rate_hz: 50.0,
+                max_system_state_gap: Duration::from_millis(100),
+                ..base
+            },
+            DeviceType::CertusMiniA | DeviceType::CertusMiniN | DeviceType::CertusMiniD |
+            DeviceType::Certus => Self {
+                min_system_state_rate_hz: 20.0,
+                max_system_state_gap: Duration::from_millis(250),
+                ..base
+            },
+            // Units without an internal receiver can't be held to a fix
+            DeviceType::Orientus | DeviceType::Motus => Self {
+                min_fix_type: GnssFixType::NoFix,
+                ..base
+            },
+            _ => base,
+        }
+    }
+}
+
+/// Individual check outcome in an acceptance report
+#[derive(Debug, Clone, PartialEq)]
+pub struct AcceptanceCheck {
+    pub name: &'static str,
+    pub passed: bool,
+    pub detail: String,
+}
+
+/// Result of a completed acceptance run
+#[derive(Debug, Clone, PartialEq)]
+pub struct AcceptanceReport {
+    pub device_information: Option<DeviceInformation>,
+    pub checks: Vec<AcceptanceCheck>,
+}
+
+impl AcceptanceReport {
+    /// True when every check passed
+    pub fn passed(&self) -> bool {
+        self.checks.iter().all(|c| c.passed)
+    }
+
+    /// Checks that failed
+    pub fn failures(&self) -> impl Iterator<Item = &AcceptanceCheck> {
+        self.checks.iter().filter(|c| !c.passed)
+    }
+}
+
+/// Scripted production acceptance test.
+///
+/// Requests device information and the configuration read-back set, then
+/// collects SystemState statistics for the configured window while watching
+/// the system status for alarms.
+///
+/// ```no_run
+/// use liban::ops::{AcceptanceTest, AcceptanceThresholds};
+/// use std::time::Instant;
+///
+/// let mut test = AcceptanceTest::new(AcceptanceThresholds::default());
+/// let _bytes = test.start(Instant::now()).to_bytes().unwrap(); // write to the device
+/// // for each decoded packet: test.handle(&packet, Instant::now());
+/// if let Some(report) = test.poll(Instant::now()) {
+///     println!("passed: {}", report.passed());
+/// }
+/// ```
+pub struct AcceptanceTest {
+    thresholds: AcceptanceThresholds,
+    started_at: Option<Instant>,
+    device_information: Option<DeviceInformation>,
+    received_configs: HashSet<PacketKind>,
+    system_state_count: u64,
+    last_system_state: Option<Instant>,
+    max_gap: Duration,
+    best_fix: GnssFixType,
+    alarms_seen: u16,
+}
+
+impl AcceptanceTest {
+    pub fn new(thresholds: AcceptanceThresholds) -> Self {
+        Self {
+            thresholds,
+            started_at: None,
+            device_information: None,
+            received_configs: HashSet::new(),
+            system_state_count: 0,
+            last_system_state: None,
+            max_gap: Duration::ZERO,
+            best_fix: GnssFixType::NoFix,
+            alarms_seen: 0,
+        }
+    }
+
+    /// Begin the run, returning the request packet to send to the device
+    pub fn start(&mut self, now: Instant) -> Packet {
+        self.started_at = Some(now);
+
+        Packet::Request(Request::multiple(
+            std::iter::once(PacketKind::DeviceInformation)
+                .chain(self.thresholds.required_configs.iter().copied()),
+        ))
+    }
+
+    /// Feed a decoded packet received from the device
+    pub fn handle(&mut self, packet: &Packet, now: Instant) {
+        let Some(started_at) = self.started_at else {
+            return;
+        };
+
+        let kind = PacketKind::from(packet.packet_id());
+        if self.thresholds.required_configs.contains(&kind) {
+            self.received_configs.insert(kind);
+        }
+
+        match packet {
+            Packet::DeviceInformation(info) => {
+                self.device_information = Some(info.clone());
+            }
+            Packet::SystemState(state) => {
+                if now.duration_since(started_at) > self.thresholds.stream_duration {
+                    return;
+                }
+                self.system_state_count += 1;
+                if let Some(last) = self.last_system_state {
+                    self.max_gap = self.max_gap.max(now.duration_since(last));
+                }
+                self.last_system_state = Some(now);
+                self.record_status(state.system_status);
+
+                let fix = state.filter_status.gnss_fix_type();
+                if fix as u8 > self.best_fix as u8 {
+                    self.best_fix = fix;
+                }
+            }
+            Packet::Status(status) => {
+                self.record_status(status.system_status);
+            }
+            _ => {}
+        }
+    }
+
+    /// Returns the report once the stream window has elapsed
+    pub fn poll(&self, now: Instant) -> Option<AcceptanceReport> {
+        let started_at = self.started_at?;
+        if now.duration_since(started_at) < self.thresholds.stream_duration {
+            return None;
+        }
+        Some(self.report())
+    }
+
+    fn record_status(&mut self, status: SystemStatus) {
+        self.alarms_seen |= status.raw();
+    }
+
+    fn report(&self) -> AcceptanceReport {
+        let t = &self.thresholds;
+        let mut checks = Vec::new();
+
+        checks.push(match (&self.device_information, t.expected_device_type) {
+            (None, _) => AcceptanceCheck {
+                name: "device_information",
+                passed: false,
+                detail: "no DeviceInformation received".to_string(),
+            },
+            (Some(info), Some(expected)) if info.device_type != expected => AcceptanceCheck {
+                name: "device_information",
+                passed: false,
+                detail: format!("expected {:?}, device reported {:?}", expected, info.device_type),
+            },
+            (Some(info), _) => AcceptanceCheck {
+                name: "device_information",
+                passed: true,
+                detail: format!("{:?}", info.device_type),
+            },
+        });
+
+        let missing: Vec<_> = t.required_configs.iter()
+            .filter(|k| !self.received_configs.contains(k))
+            .collect();
+        checks.push(AcceptanceCheck {
+            name: "config_read_back",
+            passed: missing.is_empty(),
+            detail: if missing.is_empty() {
+                format!("{} packets read back", t.required_configs.len())
+            } else {
+                format!("missing {:?}", missing)
+            },
+        });
+
+        let rate = self.system_state_count as f64 / t.stream_duration.as_secs_f64();
+        checks.push(AcceptanceCheck {
+            name: "system_state_rate",
+            passed: rate >= t.min_system_state_rate_hz,
+            detail: format!("{:.1} Hz (min {:.1} Hz)", rate, t.min_system_state_rate_hz),
+        });
+
+        checks.push(AcceptanceCheck {
+            name: "system_state_gap",
+            passed: self.system_state_count > 0 && self.max_gap <= t.max_system_state_gap,
+            detail: format!("max gap {:?} (limit {:?})", self.max_gap, t.max_system_state_gap),
+        });
+
+        checks.push(AcceptanceCheck {
+            name: "gnss_fix",
+            passed: self.best_fix as u8 >= t.min_fix_type as u8,
+            detail: format!("best {:?} (min {:?})", self.best_fix, t.min_fix_type),
+        });
+
+        let unexpected = self.alarms_seen & !t.allowed_alarm_mask;
+        checks.push(AcceptanceCheck {
+            name: "alarms",
+            passed: unexpected == 0,
+            detail: format!("system status bits 0x{:04X}", unexpected),
+        });
+
+        AcceptanceReport {
+            device_information: self.device_information.clone(),
+            checks,
+        }
+    }
+}
+
+/// Magnetic calibration type
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MagCalMode {
+    /// Level rotation only, for vehicles that stay roughly flat
+    TwoD,
+    /// Full rotation through all axes
+    ThreeD,
+}
+
+/// Why a magnetic calibration session ended without saving
+#[derive(Debug, Clone, PartialEq)]
+pub enum MagCalFailure {
+    /// The device refused the start command
+    StartRejected(AcknowledgeResult),
+    /// The device aborted the calibration
+    Calibration(MagneticCalibrationStatusCode),
+    /// The device refused to store the values permanently
+    SaveRejected(AcknowledgeResult),
+    Cancelled,
+}
+
+/// Where a magnetic calibration session is up to
+#[derive(Debug, Clone, PartialEq)]
+pub enum MagCalState {
+    Idle,
+    /// Start command sent, waiting for the acknowledge
+    Starting,
+    /// The device is collecting samples; rotate the vehicle
+    Collecting { progress: u8 },
+    /// Calibration finished, reading the values back to store them permanently
+    Saving,
+    /// Values stored permanently on the device
+    Complete(MagneticCalibrationValues),
+    Failed(MagCalFailure),
+}
+
+/// Sans-io magnetic calibration workflow.
+///
+/// Sequences start, collect, check status and save. The caller sends every
+/// packet returned, feeds back whatever the device answers and sends
+/// [`status_request`](Self::status_request) periodically while collecting.
+///
+/// ```
+/// use liban::ops::{MagCalMode, MagCalSession, MagCalState};
+///
+/// let mut session = MagCalSession::new(MagCalMode::TwoD);
+/// let _start = session.start().to_bytes().unwrap(); // write to the device
+/// // for each decoded packet:
+/// //     if let Some(reply) = session.handle(&packet) { /* write reply */ }
+/// assert_eq!(session.state(), &MagCalState::Starting);
+/// ```
+pub struct MagCalSession {
+    mode: MagCalMode,
+    state: MagCalState,
+    saving: Option<MagneticCalibrationValues>,
+}
+
+impl MagCalSession {
+    pub fn new(mode: MagCalMode) -> Self {
+        Self {
+            mode,
+            state: MagCalState::Idle,
+            saving: None,
+        }
+    }
+
+    pub fn state(&self) -> &MagCalState {
+        &self.state
+    }
+
+    /// True once the session has completed, failed or been cancelled
+    pub fn is_finished(&self) -> bool {
+        matches!(self.state, MagCalState::Complete(_) | MagCalState::Failed(_))
+    }
+
+    /// Begin calibrating, returning the command to send
+    pub fn start(&mut self) -> Packet {
+        self.state = MagCalState::Starting;
+        self.saving = None;
+        let action = match self.mode {
+            MagCalMode::TwoD => MagneticCalibrationAction::Start2D,
+            MagCalMode::ThreeD => MagneticCalibrationAction::Start3D,
+        };
+        Packet::MagneticCalibrationConfiguration(MagneticCalibrationConfiguration { action })
+    }
+
+    /// Abort the calibration, returning the command to send
+    pub fn cancel(&mut self) -> Packet {
+        self.state = MagCalState::Failed(MagCalFailure::Cancelled);
+        Packet::MagneticCalibrationConfiguration(MagneticCalibrationConfiguration {
+            action: MagneticCalibrationAction::Cancel,
+        })
+    }
+
+    /// Status poll to send while the device is collecting
+    pub fn status_request(&self) -> Option<Packet> {
+        match self.state {
+            MagCalState::Starting | MagCalState::Collecting { .. } => Some(Packet::Request(
+                Request::single(PacketKind::MagneticCalibrationStatus),
+            )),
+            _ => None,
+        }
+    }
+
+    /// Feed a decoded packet received from the device, returning the next
+    /// packet to send if the workflow has moved on
+    pub fn handle(&mut self, packet: &Packet) -> Option<Packet> {
+        match (&self.state, packet) {
+            (MagCalState::Starting, Packet::Acknowledge(ack))
+                if ack.acknowledged_packet == PacketKind::MagneticCalibrationConfiguration =>
+            {
+                self.state = if ack.is_success() {
+                    MagCalState::Collecting { progress: 0 }
+                } else {
+                    MagCalState::Failed(MagCalFailure::StartRejected(ack.result))
+                };
+                None
+            }
+            // Until the start is acknowledged only an in-progress status is
+            // trusted; a complete or error left over from an earlier run is not
+            (MagCalState::Starting, Packet::MagneticCalibrationStatus(status)) => {
+                if status.status.is_in_progress() {
+                    self.state = MagCalState::Collecting { progress: status.progress };
+                }
+                None
+            }
+            (MagCalState::Collecting { .. }, Packet::MagneticCalibrationStatus(status)) => {
+                if status.status.is_complete() {
+                    self.state = MagCalState::Saving;
+                    Some(Packet::Request(Request::single(PacketKind::MagneticCalibrationValues)))
+                } else if status.status.is_error() {
+                    self.state = MagCalState::Failed(MagCalFailure::Calibration(status.status));
+                    None
+                } else {
+                    if status.status.is_in_progress() {
+                        self.state = MagCalState::Collecting { progress: status.progress };
+                    }
+                    None
+                }
+            }
+            (MagCalState::Saving, Packet::MagneticCalibrationValues(values)) if self.saving.is_none() => {
+                let values = MagneticCalibrationValues { permanent: true, ..values.clone() };
+                self.saving = Some(values.clone());
+                Some(Packet::MagneticCalibrationValues(values))
+            }
+            (MagCalState::Saving, Packet::Acknowledge(ack))
+                if ack.acknowledged_packet == PacketKind::MagneticCalibrationValues =>
+            {
+                let values = self.saving.take()?;
+                self.state = if ack.is_success() {
+                    MagCalState::Complete(values)
+                } else {
+                    MagCalState::Failed(MagCalFailure::SaveRejected(ack.result))
+                };
+                None
+            }
+            _ => None,
+        }
+    }
+}
+
+/// Why an antenna offset calibration ended without storing an offset
+#[derive(Debug, Clone, PartialEq)]
+pub enum AntennaOffsetFailure {
+    /// The device refused the command starting estimation
+    StartRejected(AcknowledgeResult),
+    /// The converged offset failed [`InstallationAlignment::validate`]
+    InvalidOffset(String),
+    /// The write policy refused the permanent InstallationAlignment write
+    SaveDenied,
+    /// The device refused the InstallationAlignment write
+    SaveRejected(AcknowledgeResult),
+    Cancelled,
+}
+
+/// Where an antenna offset calibration is up to
+#[derive(Debug, Clone, PartialEq)]
+pub enum AntennaOffsetState {
+    Idle,
+    /// Start command sent, waiting for the acknowledge
+    Starting,
+    /// The filter is estimating; drive the vehicle with some turns. Holds
+    /// the latest estimate once one has arrived.
+    Estimating(Option<AntennaOffsetEstimation>),
+    /// The estimate is within the standard deviation limit, waiting for
+    /// [`confirm`](AntennaOffsetCalibration::confirm). Later estimates
+    /// replace it and fall back to `Estimating` if they exceed the limit.
+    Converged(AntennaOffsetEstimation),
+    /// Reading InstallationAlignment back to write the offset into it
+    Saving,
+    /// Offset stored in InstallationAlignment
+    Complete(InstallationAlignment),
+    Failed(AntennaOffsetFailure),
+}
+
+/// Sans-io GNSS antenna offset calibration workflow.
+///
+/// Starts offset estimation, watches the estimate's standard deviation
+/// until it is below the limit, and on [`confirm`](Self::confirm) reads
+/// InstallationAlignment, replaces its GNSS antenna offset with the
+/// estimate and writes it back. Estimation is stopped once the workflow
+/// finishes. Like [`MagCalSession`], the caller sends every packet
+/// returned, feeds back whatever the device answers and sends
+/// [`status_request`](Self::status_request) periodically while estimating;
+/// [`Client::calibrate_antenna_offset`](crate::client::Client::calibrate_antenna_offset)
+/// does all of that.
+///
+/// ```
+/// use liban::ops::{AntennaOffsetCalibration, AntennaOffsetState};
+/// use liban::units::Length;
+///
+/// let mut calibration = AntennaOffsetCalibration::new().with_max_std_dev(Length::from_meters(0.05));
+/// let _start = calibration.start().to_bytes().unwrap(); // write to the device
+/// assert_eq!(calibration.state(), &AntennaOffsetState::Starting);
+/// ```
+#[derive(Debug, Clone)]
+pub struct AntennaOffsetCalibration {
+    max_std_dev: Length,
+    permanent: bool,
+    state: AntennaOffsetState,
+    accepted: Option<AntennaOffsetEstimation>,
+    saving: Option<InstallationAlignment>,
+}
+
+impl Default for AntennaOffsetCalibration {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl AntennaOffsetCalibration {
+    /// Standard deviation every axis must reach by default
+    pub const DEFAULT_MAX_STD_DEV: Length = Length::from_meters(0.02);
+
+    pub fn new() -> Self {
+        Self {
+            max_std_dev: Self::DEFAULT_MAX_STD_DEV,
+            permanent: true,
+            state: AntennaOffsetState::Idle,
+            accepted: None,
+            saving: None,
+        }
+    }
+
+    /// Consider the estimate converged once every axis' standard deviation
+    /// is at most `max_std_dev`
+    pub fn with_max_std_dev(mut self, max_std_dev: Length) -> Self {
+        self.max_std_dev = max_std_dev;
+        self
+    }
+
+    /// Whether the offset is stored permanently; defaults to true
+    pub fn with_permanent(mut self, permanent: bool) -> Self {
+        self.permanent = permanent;
+        self
+    }
+
+    pub fn max_std_dev(&self) -> Length {
+        self.max_std_dev
+    }
+
+    pub fn permanent(&self) -> bool {
+        self.permanent
+    }
+
+    pub fn state(&self) -> &AntennaOffsetState {
+        &self.state
+    }
+
+    /// True once the calibration has completed, failed or been cancelled
+    pub fn is_finished(&self) -> bool {
+        matches!(self.state, AntennaOffsetState::Complete(_) | AntennaOffsetState::Failed(_))
+    }
+
+    /// Begin estimating, returning the command to send
+    pub fn start(&mut self) -> Packet {
+        self.state = AntennaOffsetState::Starting;
+        self.accepted = None;
+        self.saving = None;
+        Packet::AntennaOffsetEstimation(AntennaOffsetEstimation::start())
+    }
+
+    /// Abort the calibration, returning the command that stops estimation
+    pub fn cancel(&mut self) -> Packet {
+        self.state = AntennaOffsetState::Failed(AntennaOffsetFailure::Cancelled);
+        Packet::AntennaOffsetEstimation(AntennaOffsetEstimation::stop())
+    }
+
+    /// Estimate poll to send while the filter is estimating
+    pub fn status_request(&self) -> Option<Packet> {
+        match self.state {
+            AntennaOffsetState::Starting | AntennaOffsetState::Estimating(_) | AntennaOffsetState::Converged(_) => {
+                Some(Packet::Request(Request::single(PacketKind::AntennaOffsetEstimation)))
+            }
+            _ => None,
+        }
+    }
+
+    /// Accept the converged estimate, returning the InstallationAlignment
+    /// request that starts saving it. `None` unless the estimate has converged.
+    pub fn confirm(&mut self) -> Option<Packet> {
+        let AntennaOffsetState::Converged(estimate) = &self.state else {
+            return None;
+        };
+        self.accepted = Some(estimate.clone());
+        self.state = AntennaOffsetState::Saving;
+        Some(Packet::Request(Request::single(PacketKind::InstallationAlignment)))
+    }
+
+    /// The write policy refused the InstallationAlignment write returned
+    /// by [`handle`](Self::handle). Returns the command that stops estimation.
+    pub fn save_denied(&mut self) -> Packet {
+        self.saving = None;
+        self.state = AntennaOffsetState::Failed(AntennaOffsetFailure::SaveDenied);
+        Packet::AntennaOffsetEstimation(AntennaOffsetEstimation::stop())
+    }
+
+    /// Feed a decoded packet received from the device, returning the next
+    /// packet to send if the workflow has moved on
+    pub fn handle(&mut self, packet: &Packet) -> Option<Packet> {
+        match (&self.state, packet) {
+            (AntennaOffsetState::Starting, Packet::Acknowledge(ack))
+                if ack.acknowledged_packet == PacketKind::AntennaOffsetEstimation =>
+            {
+                self.state = if ack.is_success() {
+                    AntennaOffsetState::Estimating(None)
+                } else {
+                    AntennaOffsetState::Failed(AntennaOffsetFailure::StartRejected(ack.result))
+                };
+                None
+            }
+            (
+                AntennaOffsetState::Starting | AntennaOffsetState::Estimating(_) | AntennaOffsetState::Converged(_),
+                Packet::AntennaOffsetEstimation(estimate),
+            ) if estimate.active => {
+                self.state = if estimate.max_std_dev() <= self.max_std_dev {
+                    AntennaOffsetState::Converged(estimate.clone())
+                } else {
+                    AntennaOffsetState::Estimating(Some(estimate.clone()))
+                };
+                None
+            }
+            (AntennaOffsetState::Saving, Packet::InstallationAlignment(alignment)) if self.saving.is_none() => {
+                let estimate = self.accepted.as_ref()?;
+                let alignment = InstallationAlignment {
+                    permanent: self.permanent,
+                    gnss_antenna_offset: estimate.offset.clone(),
+                    ..alignment.clone()
+                };
+                if let Err(error) = alignment.validate() {
+                    self.state = AntennaOffsetState::Failed(AntennaOffsetFailure::InvalidOffset(error.to_string()));
+                    return Some(Packet::AntennaOffsetEstimation(AntennaOffsetEstimation::stop()));
+                }
+                self.saving = Some(alignment.clone());
+                Some(Packet::InstallationAlignment(alignment))
+            }
+            (AntennaOffsetState::Saving, Packet::Acknowledge(ack))
+                if ack.acknowledged_packet == PacketKind::InstallationAlignment =>
+            {
+                let alignment = self.saving.take()?;
+                self.state = if ack.is_success() {
+                    AntennaOffsetState::Complete(alignment)
+                } else {
+                    AntennaOffsetState::Failed(AntennaOffsetFailure::SaveRejected(ack.result))
+                };
+                Some(Packet::AntennaOffsetEstimation(AntennaOffsetEstimation::stop()))
+            }
+            _ => None,
+        }
+    }
+}
+
+/// Why a file transfer ended without the whole file
+#[derive(Debug, Clone, PartialEq)]
+pub enum FileTransferFailure {
+    /// The device answered with a failure code
+    Refused(FileTransferResponse),
+    /// Data was still missing after asking for it again
+    /// [`max_resends`](FileTransfer::with_max_resends) times
+    Incomplete { missing: Vec<Range<u32>> },
+    /// The file runs past [`max_len`](FileTransfer::with_max_len) bytes
+    TooLarge(u64),
+    Cancelled,
+}
+
+/// Where a file transfer is up to
+#[derive(Debug, Clone, PartialEq)]
+pub enum FileTransferState {
+    Idle,
+    /// Request sent, waiting for the first data
+    Requested,
+    /// Data arriving; `received` counts the distinct bytes so far
+    Receiving { received: u64 },
+    /// Every byte arrived; [`FileTransfer::file`] reassembles it
+    Complete { len: u32 },
+    Failed(FileTransferFailure),
+}
+
+/// Sans-io download of a file, such as an onboard log, from the device.
+///
+/// [`start`](Self::start) asks for the file by name. The device sends it in
+/// File Transfer packets, each carrying its byte offset, and finishes with
+/// a File Transfer Acknowledge reporting completion and the file length.
+/// Chunks may arrive out of order or more than once; gaps are tracked and,
+/// once the device reports completion, asked for again from the first
+/// missing byte. The caller sends every packet returned and feeds back
+/// whatever the device answers.
+///
+/// ```
+/// use liban::ops::{FileTransfer, FileTransferState};
+/// use liban::{FileTransferAcknowledge, FileTransferData, FileTransferResponse, Packet};
+///
+/// let mut transfer = FileTransfer::new(7, "log_0001.anpp");
+/// let _request = transfer.start().to_bytes().unwrap(); // write to the device
+/// for (index, data) in [(0, &b"hello "[..]), (6, &b"world"[..])] {
+///     let chunk = FileTransferData { unique_id: 7, data_index: index, response_required: false, data: data.to_vec() };
+///     transfer.handle(&Packet::FileTransferData(chunk));
+/// }
+/// let done = FileTransferAcknowledge { unique_id: 7, data_index: 11, response: FileTransferResponse::Completed };
+/// assert_eq!(transfer.handle(&Packet::FileTransferAcknowledge(done)), None);
+/// assert_eq!(transfer.state(), &FileTransferState::Complete { len: 11 });
+/// assert_eq!(transfer.file().unwrap(), b"hello world");
+/// ```
+pub struct FileTransfer {
+    unique_id: u32,
+    name: String,
+    max_len: u32,
+    max_resends: u32,
+    resends: u32,
+    /// Received data by byte offset
+    chunks: BTreeMap<u32, Vec<u8>>,
+    state: FileTransferState,
+}
+
+impl FileTransfer {
+    /// Default [`with_max_len`](Self::with_max_len) limit, 64 MiB
+    pub const DEFAULT_MAX_LEN: u32 = 64 << 20;
+    /// Default [`with_max_resends`](Self::with_max_resends) limit
+    pub const DEFAULT_MAX_RESENDS: u32 = 3;
+
+    /// Transfer of the file `name`, identified by `unique_id` in every
+    /// packet of the transfer
+    pub fn new(unique_id: u32, name: impl Into<String>) -> Self {
+        Self {
+            unique_id,
+            name: name.into(),
+            max_len: Self::DEFAULT_MAX_LEN,
+            max_resends: Self::DEFAULT_MAX_RESENDS,
+            resends: 0,
+            chunks: BTreeMap::new(),
+            state: FileTransferState::Idle,
+        }
+    }
+
+    /// Fail rather than hold a file longer than `bytes`
+    pub fn with_max_len(mut self, bytes: u32) -> Self {
+        self.max_len = bytes;
+        self
+    }
+
+    /// How many times missing data is asked for again before giving up
+    pub fn with_max_resends(mut self, resends: u32) -> Self {
+        self.max_resends = resends;
+        self
+    }
+
+    pub fn unique_id(&self) -> u32 {
+        self.unique_id
+    }
+
+    pub fn state(&self) -> &FileTransferState {
+        &self.state
+    }
+
+    /// True once the transfer has completed, failed or been cancelled
+    pub fn is_finished(&self) -> bool {
+        matches!(self.state, FileTransferState::Complete { .. } | FileTransferState::Failed(_))
+    }
+
+    /// Begin the download, returning the request to send
+    pub fn start(&mut self) -> Packet {
+        self.state = FileTransferState::Requested;
+        self.resends = 0;
+        self.chunks.clear();
+        Packet::FileTransferRequest(FileTransferRequest::for_file(self.unique_id, &self.name, 0))
+    }
+
+    /// Stop accepting data. The device is not told; it gives up on its own
+    /// once the host stops acknowledging.
+    pub fn cancel(&mut self) {
+        self.state = FileTransferState::Failed(FileTransferFailure::Cancelled);
+    }
+
+    /// Byte ranges not yet received, up to the file length once the device
+    /// has reported it and otherwise up to the furthest byte received
+    pub fn missing(&self) -> Vec<Range<u32>> {
+        let end = match self.state {
+            FileTransferState::Complete { len } => len,
+            _ => self.received_end(),
+        };
+        self.missing_below(end)
+    }
+
+    /// The reassembled file once the transfer is complete
+    pub fn file(&self) -> Option<Vec<u8>> {
+        let FileTransferState::Complete { len } = self.state else {
+            return None;
+        };
+        let mut file = vec![0; len as usize];
+        for (&index, data) in &self.chunks {
+            let start = (index as usize).min(file.len());
+            let end = (start + data.len()).min(file.len());
+            file[start..end].copy_from_slice(&data[..end - start]);
+        }
+        Some(file)
+    }
+
+    /// Feed a decoded packet received from the device, returning the next
+    /// packet to send if there is one
+    pub fn handle(&mut self, packet: &Packet) -> Option<Packet> {
+        if !matches!(self.state, FileTransferState::Requested | FileTransferState::Receiving { .. }) {
+            return None;
+        }
+        match packet {
+            Packet::FileTransferData(chunk) if chunk.unique_id == self.unique_id => self.receive(chunk),
+            Packet::FileTransferAcknowledge(ack) if ack.unique_id == self.unique_id => match ack.response {
+                FileTransferResponse::Completed => self.complete(ack.data_index),
+                response if response.is_error() => {
+                    self.state = FileTransferState::Failed(FileTransferFailure::Refused(response));
+                    None
+                }
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+
+    fn receive(&mut self, chunk: &FileTransferData) -> Option<Packet> {
+        let end = u64::from(chunk.data_index) + chunk.data.len() as u64;
+        if end > u64::from(self.max_len) {
+            self.state = FileTransferState::Failed(FileTransferFailure::TooLarge(end));
+            return None;
+        }
+        let stored = self.chunks.entry(chunk.data_index).or_default();
+        if chunk.data.len() > stored.len() {
+            *stored = chunk.data.clone();
+        }
+        let missing = self.missing_below(self.received_end());
+        let received = u64::from(self.received_end()) - missing.iter().map(|gap| u64::from(gap.end - gap.start)).sum::<u64>();
+        self.state = FileTransferState::Receiving { received };
+        if !chunk.response_required {
+            return None;
+        }
+        // Acknowledge up to the first gap, and ask for it again if this
+        // chunk landed beyond it
+        let (data_index, response) = match missing.first() {
+            Some(gap) if gap.start < chunk.data_index => (gap.start, FileTransferResponse::IndexMismatch),
+            _ => (self.received_end(), FileTransferResponse::Ready),
+        };
+        Some(Packet::FileTransferAcknowledge(FileTransferAcknowledge { unique_id: self.unique_id, data_index, response }))
+    }
+
+    fn complete(&mut self, len: u32) -> Option<Packet> {
+        if len > self.max_len {
+            self.state = FileTransferState::Failed(FileTransferFailure::TooLarge(len.into()));
+            return None;
+        }
+        let missing = self.missing_below(len);
+        let Some(gap) = missing.first() else {
+            self.state = FileTransferState::Complete { len };
+            return None;
+        };
+        if self.resends >= self.max_resends {
+            self.state = FileTransferState::Failed(FileTransferFailure::Incomplete { missing });
+            return None;
+        }
+        self.resends += 1;
+        Some(Packet::FileTransferRequest(FileTransferRequest::for_file(self.unique_id, &self.name, gap.start)))
+    }
+
+    /// One past the furthest byte received
+    fn received_end(&self) -> u32 {
+        self.chunks.iter().map(|(&index, data)| index.saturating_add(data.len() as u32)).max().unwrap_or(0)
+    }
+
+    fn missing_below(&self, end: u32) -> Vec<Range<u32>> {
+        let mut missing = Vec::new();
+        let mut covered = 0;
+        for (&index, data) in &self.chunks {
+            if covered >= end {
+                break;
+            }
+            if index > covered {
+                missing.push(covered..index.min(end));
+            }
+            covered = covered.max(index.saturating_add(data.len() as u32));
+        }
+        if covered < end {
+            missing.push(covered..end);
+        }
+        missing
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::packet::config::{FilterOptions, PacketTimerPeriod, VehicleType};
+    use crate::packet::state::{FilterStatus, SystemState};
+    use crate::test_support;
+
+    fn system_state(system_status: u16, filter_status: u16) -> SystemState {
+        SystemState {
+            system_status: SystemStatus::from(system_status),
+            filter_status: FilterStatus::from(filter_status),
+            ..test_support::system_state()
+        }
+    }
+
+    fn thresholds() -> AcceptanceThresholds {
+        AcceptanceThresholds {
+            expected_device_type: Some(DeviceType::BoreasD90),
+            required_configs: vec![PacketKind::FilterOptions],
+            stream_duration: Duration::from_secs(1),
+            min_system_state_rate_hz: 10.0,
+            max_system_state_gap: Duration::from_millis(150),
+            min_fix_type: GnssFixType::Fix3D,
+            allowed_alarm_mask: 0,
+        }
+    }
+
+    fn device_information() -> Packet {
+        Packet::DeviceInformation(DeviceInformation {
+            software_version: 1,
+            device_type: DeviceType::BoreasD90,
+            hardware_revision: 1,
+            serial_number_1: 1,
+            serial_number_2: 2,
+            serial_number_3: 3,
+        })
+    }
+
+    fn filter_options() -> Packet {
+        Packet::FilterOptions(FilterOptions {
+            permanent: false,
+            vehicle_type: VehicleType::Boat,
+            internal_gnss_enabled: true,
+            atmospheric_altitude_enabled: false,
+            velocity_heading_enabled: false,
+            reversing_detection_enabled: false,
+            motion_analysis_enabled: false,
+        })
+    }
+
+    #[test]
+    fn test_start_requests_device_info_and_configs() {
+        let mut test = AcceptanceTest::new(thresholds());
+        let request = test.start(Instant::now());
+        assert_eq!(request, Packet::Request(Request::multiple([
+            PacketKind::DeviceInformation,
+            PacketKind::FilterOptions,
+        ])));
+    }
+
+    #[test]
+    fn test_passing_run() {
+        let start = Instant::now();
+        let mut test = AcceptanceTest::new(thresholds());
+        test.start(start);
+        test.handle(&device_information(), start);
+        test.handle(&filter_options(), start);
+
+        for i in 0..20 {
+            let now = start + Duration::from_millis(50 * i);
+            test.handle(&Packet::SystemState(system_state(0, 0x0020)), now); // Fix3D
+        }
+
+        assert!(test.poll(start + Duration::from_millis(500)).is_none());
+        let report = test.poll(start + Duration::from_secs(1)).expect("window elapsed");
+        assert!(report.passed(), "{:?}", report.failures().collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn test_failing_run_reports_each_failure() {
+        let start = Instant::now();
+        let mut test = AcceptanceTest::new(thresholds());
+        test.start(start);
+
+        // Slow stream with a GNSS failure and no fix
+        for i in 0..3 {
+            let now = start + Duration::from_millis(300 * i);
+            test.handle(&Packet::SystemState(system_state(1 << 5, 0)), now);
+        }
+
+        let report = test.poll(start + Duration::from_secs(1)).unwrap();
+        assert!(!report.passed());
+        let failed: Vec<_> = report.failures().map(|c| c.name).collect();
+        assert_eq!(failed, vec![
+            "device_information",
+            "config_read_back",
+            "system_state_rate",
+            "system_state_gap",
+            "gnss_fix",
+            "alarms",
+        ]);
+    }
+
+    #[test]
+    fn test_thresholds_per_product_line() {
+        let boreas = AcceptanceThresholds::for_device_type(DeviceType::BoreasD90);
+        assert_eq!(boreas.expected_device_type, Some(DeviceType::BoreasD90));
+        assert!(boreas.min_system_state_rate_hz > AcceptanceThresholds::default().min_system_state_rate_hz);
+
+        let orientus = AcceptanceThresholds::for_device_type(DeviceType::Orientus);
+        assert_eq!(orientus.min_fix_type, GnssFixType::NoFix);
+        assert!(boreas.required_configs.contains(&PacketKind::IpDataportsConfiguration));
+        for device_type in [DeviceType::Orientus, DeviceType::Motus, DeviceType::Spatial] {
+            let thresholds = AcceptanceThresholds::for_device_type(device_type);
+            assert!(!thresholds.required_configs.contains(&PacketKind::IpDataportsConfiguration), "{:?}", device_type);
+        }
+    }
+
+    #[test]
+    fn test_orientus_passes_without_dataports() {
+        let start = Instant::now();
+        let thresholds = AcceptanceThresholds {
+            stream_duration: Duration::from_secs(1),
+            ..AcceptanceThresholds::for_device_type(DeviceType::Orientus)
+        };
+        let mut test = AcceptanceTest::new(thresholds);
+        test.start(start);
+        test.handle(&Packet::DeviceInformation(DeviceInformation {
+            software_version: 1,
+            device_type: DeviceType::Orientus,
+            hardware_revision: 1,
+            serial_number_1: 1,
+            serial_number_2: 2,
+            serial_number_3: 3,
+        }), start);
+        // Everything an Orientus answers; it has no dataports to read back
+        test.handle(&filter_options(), start);
+        test.handle(&Packet::InstallationAlignment(InstallationAlignment::builder().build().unwrap()), start);
+        test.handle(&Packet::PacketTimerPeriod(PacketTimerPeriod::from_rate_hz(1000.0).unwrap()), start);
+
+        for i in 0..20 {
+            let now = start + Duration::from_millis(50 * i);
+            test.handle(&Packet::SystemState(system_state(0, 0)), now); // no fix
+        }
+
+        let report = test.poll(start + Duration::from_secs(1)).expect("window elapsed");
+        assert!(report.passed(), "{:?}", report.failures().collect::<Vec<_>>());
+    }
+
+    fn ack(kind: PacketKind, result: AcknowledgeResult) -> Packet {
+        Packet::Acknowledge(crate::packet::system::Acknowledge {
+            acknowledged_packet: kind,
+            packet_crc: 0,
+            result,
+        })
+    }
+
+    fn mag_status(code: u8, progress: u8) -> Packet {
+        Packet::MagneticCalibrationStatus(crate::packet::config::MagneticCalibrationStatus {
+            status: MagneticCalibrationStatusCode::from(code),
+            progress,
+            local_magnetic_error: 3,
+        })
+    }
+
+    fn mag_values(permanent: bool) -> MagneticCalibrationValues {
+        MagneticCalibrationValues {
+            permanent,
+            hard_iron_bias: crate::packet::config::OffsetVector { x: 12.0, y: -4.0, z: 1.5 },
+            soft_iron_transformation: [[1.0, 0.0, 0.0], [0.0, 1.0, 0.0], [0.0, 0.0, 1.0]],
+        }
+    }
+
+    #[test]
+    fn test_mag_cal_full_workflow() {
+        let mut session = MagCalSession::new(MagCalMode::ThreeD);
+        assert!(session.status_request().is_none());
+
+        match session.start() {
+            Packet::MagneticCalibrationConfiguration(c) => assert_eq!(c.action, MagneticCalibrationAction::Start3D),
+            other => panic!("unexpected {:?}", other),
+        }
+        assert!(session.handle(&ack(PacketKind::MagneticCalibrationConfiguration, AcknowledgeResult::Success)).is_none());
+        assert!(session.status_request().is_some());
+
+        assert!(session.handle(&mag_status(6, 40)).is_none());
+        assert_eq!(session.state(), &MagCalState::Collecting { progress: 40 });
+
+        let read_back = session.handle(&mag_status(2, 100)).unwrap();
+        assert_eq!(read_back, Packet::Request(Request::single(PacketKind::MagneticCalibrationValues)));
+        assert_eq!(session.state(), &MagCalState::Saving);
+
+        let save = session.handle(&Packet::MagneticCalibrationValues(mag_values(false))).unwrap();
+        assert_eq!(save, Packet::MagneticCalibrationValues(mag_values(true)));
+
+        session.handle(&ack(PacketKind::MagneticCalibrationValues, AcknowledgeResult::Success));
+        assert_eq!(session.state(), &MagCalState::Complete(mag_values(true)));
+        assert!(session.is_finished());
+    }
+
+    #[test]
+    fn test_mag_cal_failures() {
+        let mut session = MagCalSession::new(MagCalMode::TwoD);
+        session.start();
+        session.handle(&ack(PacketKind::MagneticCalibrationConfiguration, AcknowledgeResult::SystemNotReady));
+        assert_eq!(session.state(), &MagCalState::Failed(MagCalFailure::StartRejected(AcknowledgeResult::SystemNotReady)));
+
+        session.start();
+        session.handle(&ack(PacketKind::MagneticCalibrationConfiguration, AcknowledgeResult::Success));
+        session.handle(&mag_status(7, 10));
+        assert_eq!(session.state(), &MagCalState::Failed(MagCalFailure::Calibration(MagneticCalibrationStatusCode::ErrorExcessiveRoll)));
+
+        session.start();
+        session.cancel();
+        assert_eq!(session.state(), &MagCalState::Failed(MagCalFailure::Cancelled));
+        assert!(session.status_request().is_none());
+    }
+
+    #[test]
+    fn test_mag_cal_ignores_stale_status_before_ack() {
+        let mut session = MagCalSession::new(MagCalMode::TwoD);
+        session.start();
+        assert!(session.handle(&mag_status(2, 100)).is_none());
+        assert!(session.handle(&mag_status(7, 100)).is_none());
+        assert_eq!(session.state(), &MagCalState::Starting);
+
+        session.handle(&ack(PacketKind::MagneticCalibrationConfiguration, AcknowledgeResult::Success));
+        assert_eq!(session.state(), &MagCalState::Collecting { progress: 0 });
+        assert!(session.handle(&mag_status(2, 100)).is_some());
+        assert_eq!(session.state(), &MagCalState::Saving);
+    }
+
+    fn estimate(offset: [f32; 3], std_dev: f32) -> AntennaOffsetEstimation {
+        use crate::packet::config::OffsetVector;
+        AntennaOffsetEstimation {
+            active: true,
+            converged: false,
+            offset: OffsetVector { x: offset[0], y: offset[1], z: offset[2] },
+            offset_std_dev: OffsetVector { x: std_dev, y: std_dev / 2.0, z: std_dev },
+        }
+    }
+
+    #[test]
+    fn test_antenna_offset_full_workflow() {
+        let mut calibration = AntennaOffsetCalibration::new().with_max_std_dev(Length::from_meters(0.05));
+        assert!(calibration.status_request().is_none());
+        assert_eq!(calibration.start(), Packet::AntennaOffsetEstimation(AntennaOffsetEstimation::start()));
+        assert!(calibration.confirm().is_none());
+        calibration.handle(&ack(PacketKind::AntennaOffsetEstimation, AcknowledgeResult::Success));
+        assert_eq!(calibration.state(), &AntennaOffsetState::Estimating(None));
+        assert!(calibration.status_request().is_some());
+
+        let rough = estimate([0.4, 0.0, -1.0], 0.2);
+        assert!(calibration.handle(&Packet::AntennaOffsetEstimation(rough.clone())).is_none());
+        assert_eq!(calibration.state(), &AntennaOffsetState::Estimating(Some(rough)));
+
+        let good = estimate([0.5, -0.02, -1.2], 0.03);
+        calibration.handle(&Packet::AntennaOffsetEstimation(good.clone()));
+        assert_eq!(calibration.state(), &AntennaOffsetState::Converged(good));
+
+        let settled = estimate([0.51, -0.02, -1.19], 0.01);
+        calibration.handle(&Packet::AntennaOffsetEstimation(settled.clone()));
+        assert_eq!(calibration.confirm(), Some(Packet::Request(Request::single(PacketKind::InstallationAlignment))));
+        assert_eq!(calibration.state(), &AntennaOffsetState::Saving);
+
+        // The rest of the installation alignment is kept
+        let current = InstallationAlignment::builder().odometer_offset(1.0, 0.0, 0.0).build().unwrap();
+        let Some(Packet::InstallationAlignment(written)) = calibration.handle(&Packet::InstallationAlignment(current.clone())) else {
+            panic!("expected an InstallationAlignment write");
+        };
+        assert!(written.permanent);
+        assert_eq!(written.gnss_antenna_offset, settled.offset);
+        assert_eq!(written.odometer_offset, current.odometer_offset);
+
+        let stop = calibration.handle(&ack(PacketKind::InstallationAlignment, AcknowledgeResult::Success));
+        assert_eq!(stop, Some(Packet::AntennaOffsetEstimation(AntennaOffsetEstimation::stop())));
+        assert_eq!(calibration.state(), &AntennaOffsetState::Complete(written));
+        assert!(calibration.is_finished());
+    }
+
+    #[test]
+    fn test_antenna_offset_failures() {
+        let mut calibration = AntennaOffsetCalibration::new();
+        calibration.start();
+        calibration.handle(&ack(PacketKind::AntennaOffsetEstimation, AcknowledgeResult::SystemNotReady));
+        assert_eq!(calibration.state(), &AntennaOffsetState::Failed(AntennaOffsetFailure::StartRejected(AcknowledgeResult::SystemNotReady)));
+
+        // Converged on an implausible lever arm
+        calibration.start();
+        calibration.handle(&ack(PacketKind::AntennaOffsetEstimation, AcknowledgeResult::Success));
+        calibration.handle(&Packet::AntennaOffsetEstimation(estimate([250.0, 0.0, 0.0], 0.01)));
+        calibration.confirm().unwrap();
+        let current = InstallationAlignment::builder().build().unwrap();
+        assert_eq!(
+            calibration.handle(&Packet::InstallationAlignment(current)),
+            Some(Packet::AntennaOffsetEstimation(AntennaOffsetEstimation::stop()))
+        );
+        assert!(matches!(calibration.state(), AntennaOffsetState::Failed(AntennaOffsetFailure::InvalidOffset(_))));
+
+        calibration.start();
+        assert_eq!(calibration.cancel(), Packet::AntennaOffsetEstimation(AntennaOffsetEstimation::stop()));
+        assert_eq!(calibration.state(), &AntennaOffsetState::Failed(AntennaOffsetFailure::Cancelled));
+        assert!(calibration.status_request().is_none());
+    }
+
+    fn chunk(index: u32, data: &[u8], response_required: bool) -> Packet {
+        Packet::FileTransferData(FileTransferData { unique_id: 9, data_index: index, response_required, data: data.to_vec() })
+    }
+
+    fn transfer_ack(index: u32, response: FileTransferResponse) -> Packet {
+        Packet::FileTransferAcknowledge(FileTransferAcknowledge { unique_id: 9, data_index: index, response })
+    }
+
+    #[test]
+    fn test_file_transfer_resends_lost_chunk() {
+        let mut transfer = FileTransfer::new(9, "log_0002.anpp");
+        let Packet::FileTransferRequest(request) = transfer.start() else {
+            panic!("expected a FileTransferRequest");
+        };
+        assert_eq!(request.filename(), Some("log_0002.anpp"));
+        assert_eq!(request.data_index, 0);
+        assert_eq!(transfer.state(), &FileTransferState::Requested);
+
+        assert_eq!(transfer.handle(&chunk(0, b"abcd", true)), Some(transfer_ack(4, FileTransferResponse::Ready)));
+        // Bytes 4..8 are lost
+        assert_eq!(transfer.handle(&chunk(8, b"ijkl", true)), Some(transfer_ack(4, FileTransferResponse::IndexMismatch)));
+        assert_eq!(transfer.state(), &FileTransferState::Receiving { received: 8 });
+        assert_eq!(transfer.missing(), vec![Range { start: 4, end: 8 }]);
+
+        let Some(Packet::FileTransferRequest(resend)) = transfer.handle(&transfer_ack(14, FileTransferResponse::Completed)) else {
+            panic!("expected the missing data to be requested again");
+        };
+        assert_eq!(resend.data_index, 4);
+        assert!(!transfer.is_finished());
+
+        transfer.handle(&chunk(4, b"efgh", false));
+        transfer.handle(&chunk(12, b"mn", false));
+        // Duplicates are harmless
+        transfer.handle(&chunk(0, b"abcd", false));
+        assert_eq!(transfer.handle(&transfer_ack(14, FileTransferResponse::Completed)), None);
+        assert_eq!(transfer.state(), &FileTransferState::Complete { len: 14 });
+        assert_eq!(transfer.file().unwrap(), b"abcdefghijklmn");
+        assert!(transfer.missing().is_empty());
+    }
+
+    #[test]
+    fn test_file_transfer_failures() {
+        let mut transfer = FileTransfer::new(9, "log_0003.anpp").with_max_resends(1);
+        transfer.start();
+        transfer.handle(&chunk(4, b"efgh", false));
+        assert!(transfer.handle(&transfer_ack(8, FileTransferResponse::Completed)).is_some());
+        assert!(transfer.handle(&transfer_ack(8, FileTransferResponse::Completed)).is_none());
+        assert_eq!(transfer.state(), &FileTransferState::Failed(FileTransferFailure::Incomplete { missing: vec![Range { start: 0, end: 4 }] }));
+        assert!(transfer.file().is_none());
+
+        transfer.start();
+        transfer.handle(&transfer_ack(0, FileTransferResponse::Other(5)));
+        assert_eq!(transfer.state(), &FileTransferState::Failed(FileTransferFailure::Refused(FileTransferResponse::Other(5))));
+
+        let mut transfer = FileTransfer::new(9, "log_0004.anpp").with_max_len(6);
+        transfer.start();
+        transfer.handle(&chunk(4, b"efgh", false));
+        assert_eq!(transfer.state(), &FileTransferState::Failed(FileTransferFailure::TooLarge(8)));
+
+        // Other transfers are ignored
+        let mut transfer = FileTransfer::new(10, "log_0005.anpp");
+        transfer.start();
+        assert!(transfer.handle(&chunk(0, b"abcd", true)).is_none());
+        assert_eq!(transfer.state(), &FileTransferState::Requested);
+        transfer.cancel();
+        assert!(transfer.is_finished());
+        assert!(transfer.handle(&transfer_ack(0, FileTransferResponse::Completed)).is_none());
+    }
+}
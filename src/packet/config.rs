@@ -1,11 +1,19 @@
 use binrw::{binrw, BinRead, BinWrite};
+#[cfg(feature = "serde")]
 use serde::{Serialize, Deserialize};
+use std::net::{Ipv4Addr, SocketAddr, SocketAddrV4};
 use std::time::Duration;
 
-use crate::packet::{PacketKind, HasPacketId};
+use crate::error::AnError;
+use crate::units::{Angle, Length};
+use crate::packet::{PacketKind, HasPacketId, ipv4_from_wire, ipv4_to_wire};
+use crate::packet::system::DeviceModel;
+use crate::packet::state::{EventId, ExtensionFields, extension_from_bytes, extension_to_bytes};
 
 /// 3D offset vector for installation alignment
-#[derive(Debug, Clone, PartialEq, BinRead, BinWrite, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, BinRead, BinWrite)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 #[brw(little)]
 pub struct OffsetVector {
     pub x: f32,
@@ -14,7 +22,9 @@ pub struct OffsetVector {
 }
 
 /// Vehicle type enumeration for filter options
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, BinRead, BinWrite, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, BinRead, BinWrite)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 #[brw(repr = u8)]
 pub enum VehicleType {
     Unlimited = 0,
@@ -35,7 +45,9 @@ pub enum VehicleType {
 }
 
 /// Offset type for dual antenna configuration
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, BinRead, BinWrite, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, BinRead, BinWrite)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 #[brw(repr = u16)]
 pub enum OffsetType {
     Manual = 0,
@@ -43,7 +55,9 @@ pub enum OffsetType {
 }
 
 /// Automatic offset orientation for dual antenna configuration
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, BinRead, BinWrite, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, BinRead, BinWrite)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 #[brw(repr = u8)]
 pub enum AutomaticOffsetOrientation {
     PrimaryFrontSecondaryRear = 0,
@@ -52,8 +66,343 @@ pub enum AutomaticOffsetOrientation {
     PrimaryLeftSecondaryRight = 3,
 }
 
+/// Serial port baud rates supported by the device
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum BaudRate {
+    B2400,
+    B4800,
+    B9600,
+    B19200,
+    B38400,
+    B57600,
+    B115200,
+    B230400,
+    B460800,
+    B921600,
+    B1000000,
+    B2000000,
+}
+
+impl BaudRate {
+    pub const ALL: [BaudRate; 12] = [
+        Self::B2400, Self::B4800, Self::B9600, Self::B19200, Self::B38400, Self::B57600,
+        Self::B115200, Self::B230400, Self::B460800, Self::B921600, Self::B1000000, Self::B2000000,
+    ];
+
+    /// Rate in bits per second as sent on the wire
+    pub fn bits_per_second(&self) -> u32 {
+        match self {
+            Self::B2400 => 2400,
+            Self::B4800 => 4800,
+            Self::B9600 => 9600,
+            Self::B19200 => 19200,
+            Self::B38400 => 38400,
+            Self::B57600 => 57600,
+            Self::B115200 => 115200,
+            Self::B230400 => 230400,
+            Self::B460800 => 460800,
+            Self::B921600 => 921600,
+            Self::B1000000 => 1000000,
+            Self::B2000000 => 2000000,
+        }
+    }
+}
+
+impl TryFrom<u32> for BaudRate {
+    type Error = AnError;
+
+    fn try_from(v: u32) -> Result<Self, Self::Error> {
+        Self::ALL.into_iter()
+            .find(|rate| rate.bits_per_second() == v)
+            .ok_or_else(|| AnError::ValidationFailed(format!("unsupported baud rate {}", v)))
+    }
+}
+
+/// Function assigned to a GPIO pin or auxiliary serial line
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum GpioFunction {
+    Inactive,
+    OnePpsOutput,
+    GnssFixOutput,
+    OdometerInput,
+    StationaryInput,
+    PitotTubeInput,
+    NmeaInput,
+    NmeaOutput,
+    NovatelGnssInput,
+    TopconGnssInput,
+    MotecOutput,
+    AnppInput,
+    AnppOutput,
+    DisableMagnetometers,
+    DisableGnss,
+    DisablePressure,
+    SetZeroOrientationAlignment,
+    SystemStateTrigger,
+    RawSensorsTrigger,
+    RtcmCorrectionsInput,
+    TrimbleGnssInput,
+    UbloxGnssInput,
+    HemisphereGnssInput,
+    TeledyneDvlInput,
+    TritechUsblInput,
+    LinkquestDvlInput,
+    PressureDepthSensor,
+    LeftWheelSpeedSensor,
+    RightWheelSpeedSensor,
+    OnePpsInput,
+    WheelSpeedSensor,
+    WheelEncoderPhaseA,
+    WheelEncoderPhaseB,
+    Event1Input,
+    Event2Input,
+    LinkquestUsblInput,
+    /// Function code not known to this library
+    Other(u8),
+}
+
+impl GpioFunction {
+    /// Drives the line
+    pub fn is_output(&self) -> bool {
+        matches!(self,
+            Self::OnePpsOutput | Self::GnssFixOutput | Self::NmeaOutput |
+            Self::MotecOutput | Self::AnppOutput)
+    }
+
+    /// Reads the line
+    pub fn is_input(&self) -> bool {
+        !matches!(self, Self::Inactive | Self::Other(_)) && !self.is_output()
+    }
+
+    /// Event input this function feeds, reported through
+    /// [`FilterStatus::events`](super::state::FilterStatus::events)
+    pub fn event(&self) -> Option<EventId> {
+        match self {
+            Self::Event1Input => Some(EventId::Event1),
+            Self::Event2Input => Some(EventId::Event2),
+            _ => None,
+        }
+    }
+}
+
+impl From<u8> for GpioFunction {
+    fn from(v: u8) -> Self {
+        match v {
+            0 => Self::Inactive,
+            1 => Self::OnePpsOutput,
+            2 => Self::GnssFixOutput,
+            3 => Self::OdometerInput,
+            4 => Self::StationaryInput,
+            5 => Self::PitotTubeInput,
+            6 => Self::NmeaInput,
+            7 => Self::NmeaOutput,
+            8 => Self::NovatelGnssInput,
+            9 => Self::TopconGnssInput,
+            10 => Self::MotecOutput,
+            11 => Self::AnppInput,
+            12 => Self::AnppOutput,
+            13 => Self::DisableMagnetometers,
+            14 => Self::DisableGnss,
+            15 => Self::DisablePressure,
+            16 => Self::SetZeroOrientationAlignment,
+            17 => Self::SystemStateTrigger,
+            18 => Self::RawSensorsTrigger,
+            19 => Self::RtcmCorrectionsInput,
+            20 => Self::TrimbleGnssInput,
+            21 => Self::UbloxGnssInput,
+            22 => Self::HemisphereGnssInput,
+            23 => Self::TeledyneDvlInput,
+            24 => Self::TritechUsblInput,
+            25 => Self::LinkquestDvlInput,
+            26 => Self::PressureDepthSensor,
+            27 => Self::LeftWheelSpeedSensor,
+            28 => Self::RightWheelSpeedSensor,
+            29 => Self::OnePpsInput,
+            30 => Self::WheelSpeedSensor,
+            31 => Self::WheelEncoderPhaseA,
+            32 => Self::WheelEncoderPhaseB,
+            33 => Self::Event1Input,
+            34 => Self::Event2Input,
+            35 => Self::LinkquestUsblInput,
+            other => Self::Other(other),
+        }
+    }
+}
+
+impl From<GpioFunction> for u8 {
+    fn from(function: GpioFunction) -> Self {
+        match function {
+            GpioFunction::Inactive => 0,
+            GpioFunction::OnePpsOutput => 1,
+            GpioFunction::GnssFixOutput => 2,
+            GpioFunction::OdometerInput => 3,
+            GpioFunction::StationaryInput => 4,
+            GpioFunction::PitotTubeInput => 5,
+            GpioFunction::NmeaInput => 6,
+            GpioFunction::NmeaOutput => 7,
+            GpioFunction::NovatelGnssInput => 8,
+            GpioFunction::TopconGnssInput => 9,
+            GpioFunction::MotecOutput => 10,
+            GpioFunction::AnppInput => 11,
+            GpioFunction::AnppOutput => 12,
+            GpioFunction::DisableMagnetometers => 13,
+            GpioFunction::DisableGnss => 14,
+            GpioFunction::DisablePressure => 15,
+            GpioFunction::SetZeroOrientationAlignment => 16,
+            GpioFunction::SystemStateTrigger => 17,
+            GpioFunction::RawSensorsTrigger => 18,
+            GpioFunction::RtcmCorrectionsInput => 19,
+            GpioFunction::TrimbleGnssInput => 20,
+            GpioFunction::UbloxGnssInput => 21,
+            GpioFunction::HemisphereGnssInput => 22,
+            GpioFunction::TeledyneDvlInput => 23,
+            GpioFunction::TritechUsblInput => 24,
+            GpioFunction::LinkquestDvlInput => 25,
+            GpioFunction::PressureDepthSensor => 26,
+            GpioFunction::LeftWheelSpeedSensor => 27,
+            GpioFunction::RightWheelSpeedSensor => 28,
+            GpioFunction::OnePpsInput => 29,
+            GpioFunction::WheelSpeedSensor => 30,
+            GpioFunction::WheelEncoderPhaseA => 31,
+            GpioFunction::WheelEncoderPhaseB => 32,
+            GpioFunction::Event1Input => 33,
+            GpioFunction::Event2Input => 34,
+            GpioFunction::LinkquestUsblInput => 35,
+            GpioFunction::Other(code) => code,
+        }
+    }
+}
+
+/// NMEA output rate for one port, packed as a nibble in [`GpioOutputRates`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum GpioOutputRate {
+    Disabled = 0,
+    Hz0_1 = 1,
+    Hz0_2 = 2,
+    Hz0_5 = 3,
+    Hz1 = 4,
+    Hz2 = 5,
+    Hz5 = 6,
+    Hz10 = 7,
+    Hz25 = 8,
+    Hz50 = 9,
+}
+
+impl From<u8> for GpioOutputRate {
+    fn from(v: u8) -> Self {
+        match v {
+            1 => Self::Hz0_1,
+            2 => Self::Hz0_2,
+            3 => Self::Hz0_5,
+            4 => Self::Hz1,
+            5 => Self::Hz2,
+            6 => Self::Hz5,
+            7 => Self::Hz10,
+            8 => Self::Hz25,
+            9 => Self::Hz50,
+            _ => Self::Disabled,
+        }
+    }
+}
+
+/// Action for the magnetic calibration configuration packet
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, BinRead, BinWrite)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[brw(repr = u8)]
+pub enum MagneticCalibrationAction {
+    Cancel = 0,
+    Start2D = 2,
+    Start3D = 3,
+    ResetToDefaults = 4,
+}
+
+/// Magnetic calibration status reported by the device
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum MagneticCalibrationStatusCode {
+    NotCompleted,
+    Completed2D,
+    Completed3D,
+    CompletedCustom,
+    InProgress2D,
+    InProgress3D,
+    ErrorExcessiveRoll,
+    ErrorExcessivePitch,
+    ErrorSensorOverRange,
+    ErrorTimeout,
+    ErrorSystem,
+    ErrorInterference,
+    Other(u8),
+}
+
+impl MagneticCalibrationStatusCode {
+    pub fn is_complete(&self) -> bool {
+        matches!(self, Self::Completed2D | Self::Completed3D | Self::CompletedCustom)
+    }
+
+    pub fn is_in_progress(&self) -> bool {
+        matches!(self, Self::InProgress2D | Self::InProgress3D)
+    }
+
+    pub fn is_error(&self) -> bool {
+        matches!(self,
+            Self::ErrorExcessiveRoll | Self::ErrorExcessivePitch | Self::ErrorSensorOverRange |
+            Self::ErrorTimeout | Self::ErrorSystem | Self::ErrorInterference)
+    }
+}
+
+impl From<u8> for MagneticCalibrationStatusCode {
+    fn from(v: u8) -> Self {
+        match v {
+            0 => Self::NotCompleted,
+            1 => Self::Completed2D,
+            2 => Self::Completed3D,
+            3 => Self::CompletedCustom,
+            5 => Self::InProgress2D,
+            6 => Self::InProgress3D,
+            7 => Self::ErrorExcessiveRoll,
+            8 => Self::ErrorExcessivePitch,
+            9 => Self::ErrorSensorOverRange,
+            10 => Self::ErrorTimeout,
+            11 => Self::ErrorSystem,
+            12 => Self::ErrorInterference,
+            other => Self::Other(other),
+        }
+    }
+}
+
+impl From<MagneticCalibrationStatusCode> for u8 {
+    fn from(status: MagneticCalibrationStatusCode) -> Self {
+        match status {
+            MagneticCalibrationStatusCode::NotCompleted => 0,
+            MagneticCalibrationStatusCode::Completed2D => 1,
+            MagneticCalibrationStatusCode::Completed3D => 2,
+            MagneticCalibrationStatusCode::CompletedCustom => 3,
+            MagneticCalibrationStatusCode::InProgress2D => 5,
+            MagneticCalibrationStatusCode::InProgress3D => 6,
+            MagneticCalibrationStatusCode::ErrorExcessiveRoll => 7,
+            MagneticCalibrationStatusCode::ErrorExcessivePitch => 8,
+            MagneticCalibrationStatusCode::ErrorSensorOverRange => 9,
+            MagneticCalibrationStatusCode::ErrorTimeout => 10,
+            MagneticCalibrationStatusCode::ErrorSystem => 11,
+            MagneticCalibrationStatusCode::ErrorInterference => 12,
+            MagneticCalibrationStatusCode::Other(code) => code,
+        }
+    }
+}
+
 /// IP dataport mode enumeration
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, BinRead, BinWrite, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, BinRead, BinWrite)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 #[brw(repr = u8)]
 pub enum IpDataportMode {
     Disabled = 0,
@@ -66,35 +415,20 @@ pub enum IpDataportMode {
 // Serde helpers for Duration fields
 // ===========================================================================
 
-mod duration_as_millis {
-    use std::time::Duration;
-    use serde::{Deserialize, Deserializer, Serializer};
-
-    pub fn serialize<S>(duration: &Duration, serializer: S) -> Result<S::Ok, S::Error>
-    where S: Serializer {
-        serializer.serialize_u64(duration.as_millis() as u64)
-    }
-
-    pub fn deserialize<'de, D>(deserializer: D) -> Result<Duration, D::Error>
-    where D: Deserializer<'de> {
-        let millis = u64::deserialize(deserializer)?;
-        Ok(Duration::from_millis(millis))
-    }
-}
-
-mod duration_as_millis_u16 {
+#[cfg(feature = "serde")]
+mod duration_as_micros_u16 {
     use std::time::Duration;
     use serde::{Deserialize, Deserializer, Serializer};
 
     pub fn serialize<S>(duration: &Duration, serializer: S) -> Result<S::Ok, S::Error>
     where S: Serializer {
-        serializer.serialize_u16(duration.as_millis() as u16)
+        serializer.serialize_u16(duration.as_micros() as u16)
     }
 
     pub fn deserialize<'de, D>(deserializer: D) -> Result<Duration, D::Error>
     where D: Deserializer<'de> {
-        let millis = u16::deserialize(deserializer)?;
-        Ok(Duration::from_millis(millis as u64))
+        let micros = u16::deserialize(deserializer)?;
+        Ok(Duration::from_micros(micros as u64))
     }
 }
 
@@ -103,30 +437,40 @@ mod duration_as_millis_u16 {
 // ===========================================================================
 
 /// Packet period entry used within PacketsPeriod
-#[derive(Debug, Clone, PartialEq, BinRead, BinWrite, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, BinRead, BinWrite)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 #[brw(little)]
 pub struct PacketPeriod {
     #[br(map = |x: u8| PacketKind::from(x))]
     #[bw(map = |x: &PacketKind| x.packet_id())]
     pub packet_type: PacketKind,
-    #[br(map = |x: u32| Duration::from_millis(x as u64))]
-    #[bw(map = |x: &Duration| x.as_millis() as u32)]
-    #[serde(with = "duration_as_millis")]
-    pub period: Duration,
+    /// Packet timer periods between outputs of this packet; 0 stops it
+    pub divisor: u32,
 }
 
 impl PacketPeriod {
     /// Create a packet period from a specific packet type
-    pub fn from_packet<P: HasPacketId>(period: Duration) -> Self {
+    pub fn from_packet<P: HasPacketId>(divisor: u32) -> Self {
         Self {
             packet_type: PacketKind::from(P::PACKET_ID.as_u8()),
-            period,
+            divisor,
         }
     }
+
+    /// Interval between packets when the packet timer runs at `timer`
+    pub fn period(&self, timer: &PacketTimerPeriod) -> Duration {
+        timer.packet_timer_period * self.divisor
+    }
 }
 
 /// Packet timer period packet (Packet ID 180, Length 4) - Read/Write
-#[derive(Debug, Clone, PartialEq, BinRead, BinWrite, Serialize, Deserialize)]
+///
+/// The base tick for packet output. On the wire the period is a u16 count
+/// of microseconds, so it round-trips exactly at µs resolution.
+#[derive(Debug, Clone, PartialEq, BinRead, BinWrite)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 #[brw(little)]
 pub struct PacketTimerPeriod {
     #[br(map = |x: u8| x != 0)]
@@ -135,14 +479,64 @@ pub struct PacketTimerPeriod {
     #[br(map = |x: u8| x != 0)]
     #[bw(map = |x: &bool| *x as u8)]
     pub utc_synchronisation: bool,
-    #[br(map = |x: u16| Duration::from_millis(x as u64))]
-    #[bw(map = |x: &Duration| x.as_millis() as u16)]
-    #[serde(with = "duration_as_millis_u16")]
+    #[br(map = |x: u16| Duration::from_micros(x as u64))]
+    #[bw(map = |x: &Duration| x.as_micros() as u16)]
+    #[cfg_attr(feature = "serde", serde(with = "duration_as_micros_u16"))]
     pub packet_timer_period: Duration,
 }
 
+impl PacketTimerPeriod {
+    /// Shortest period the device accepts
+    pub const MIN_PERIOD: Duration = Duration::from_micros(1_000);
+    /// Longest period the device accepts
+    pub const MAX_PERIOD: Duration = Duration::from_micros(65_000);
+
+    /// Non-permanent, UTC synchronised timer with the given period
+    pub fn new(period: Duration) -> Result<Self, AnError> {
+        let timer = Self {
+            permanent: false,
+            utc_synchronisation: true,
+            packet_timer_period: period,
+        };
+        timer.validate()?;
+        Ok(timer)
+    }
+
+    /// Timer ticking at `hz`, rounded to the nearest microsecond
+    pub fn from_rate_hz(hz: f64) -> Result<Self, AnError> {
+        if !(hz.is_finite() && hz > 0.0) {
+            return Err(AnError::ValidationFailed(format!("packet timer rate {} Hz is not positive", hz)));
+        }
+        let micros = (1e6 / hz).round();
+        if micros > u16::MAX as f64 {
+            return Err(AnError::ValidationFailed(format!("packet timer rate {} Hz is too slow", hz)));
+        }
+        Self::new(Duration::from_micros(micros as u64))
+    }
+
+    /// Tick rate in Hz
+    pub fn rate_hz(&self) -> f64 {
+        1.0 / self.packet_timer_period.as_secs_f64()
+    }
+
+    /// Check the period is within 1000-65000 µs and a whole number of µs
+    pub fn validate(&self) -> Result<(), AnError> {
+        let period = self.packet_timer_period;
+        if !(Self::MIN_PERIOD..=Self::MAX_PERIOD).contains(&period) {
+            return Err(AnError::ValidationFailed(format!(
+                "packet timer period {} µs outside 1000-65000 µs", period.as_micros())));
+        }
+        if !period.subsec_nanos().is_multiple_of(1_000) {
+            return Err(AnError::ValidationFailed(format!("packet timer period {:?} is not a whole number of µs", period)));
+        }
+        Ok(())
+    }
+}
+
 /// Packets period packet (Packet ID 181, Variable length) - Read/Write
-#[derive(Debug, Clone, PartialEq, BinRead, BinWrite, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, BinRead, BinWrite)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 #[brw(little)]
 pub struct PacketsPeriod {
     #[br(map = |x: u8| x != 0)]
@@ -167,8 +561,238 @@ pub struct PacketsPeriod {
     pub packet_periods: Vec<PacketPeriod>,
 }
 
+/// Bytes per second the device needs to send, from [`PacketsPeriod::estimated_bandwidth`]
+#[derive(Debug, Clone, PartialEq)]
+pub struct Bandwidth {
+    /// Framed bytes per second, headers included
+    pub bytes_per_second: f64,
+    /// Variable length packets in the table; only their headers are counted
+    pub variable_length: Vec<PacketKind>,
+}
+
+impl Bandwidth {
+    /// Fraction of a link's capacity this would use
+    pub fn utilisation(&self, link: LinkBudget) -> f64 {
+        self.bytes_per_second / link.bytes_per_second()
+    }
+}
+
+/// Capacity of the link packets are streamed over
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum LinkBudget {
+    /// Serial port at 8N1, so ten bits on the line per byte
+    Serial(BaudRate),
+    /// What the device reliably sustains over Ethernet
+    Ethernet,
+}
+
+impl LinkBudget {
+    /// Conservative sustained output over the Ethernet port, 8 Mbit/s
+    pub const ETHERNET_BYTES_PER_SECOND: f64 = 1_000_000.0;
+
+    pub fn bytes_per_second(&self) -> f64 {
+        match self {
+            LinkBudget::Serial(rate) => rate.bits_per_second() as f64 / 10.0,
+            LinkBudget::Ethernet => Self::ETHERNET_BYTES_PER_SECOND,
+        }
+    }
+}
+
+impl PacketsPeriod {
+    /// Estimate the output bandwidth of this period table.
+    ///
+    /// Each entry's interval is its divisor times the packet timer period.
+    /// Frame size is the header plus the packet's fixed length.
+    pub fn estimated_bandwidth(&self, timer: &PacketTimerPeriod) -> Bandwidth {
+        let mut bandwidth = Bandwidth { bytes_per_second: 0.0, variable_length: Vec::new() };
+        for entry in &self.packet_periods {
+            let interval = entry.period(timer).as_secs_f64();
+            if interval <= 0.0 {
+                continue;
+            }
+            let length = entry.packet_type.byte_length().unwrap_or_else(|| {
+                bandwidth.variable_length.push(entry.packet_type);
+                0
+            });
+            bandwidth.bytes_per_second += (crate::wire::HEADER_LEN + length) as f64 / interval;
+        }
+        bandwidth
+    }
+
+    /// Check the table fits on `link`, returning the estimate if it does
+    pub fn validate_bandwidth(&self, timer: &PacketTimerPeriod, link: LinkBudget) -> Result<Bandwidth, AnError> {
+        let bandwidth = self.estimated_bandwidth(timer);
+        if bandwidth.utilisation(link) > 1.0 {
+            return Err(AnError::ValidationFailed(format!(
+                "packet periods need {:.0} bytes/s but {:?} carries {:.0} bytes/s",
+                bandwidth.bytes_per_second, link, link.bytes_per_second())));
+        }
+        #[cfg(feature = "tracing")]
+        if !bandwidth.variable_length.is_empty() {
+            tracing::warn!("Bandwidth estimate excludes payloads of variable length packets {:?}", bandwidth.variable_length);
+        }
+        Ok(bandwidth)
+    }
+}
+
+/// Baud rates packet (Packet ID 182, Length 17) - Read/Write
+///
+/// Decoding fails if the device reports a rate outside [`BaudRate`].
+#[binrw]
+#[brw(little)]
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct BaudRates {
+    #[br(map = |x: u8| x != 0)]
+    #[bw(map = |x: &bool| *x as u8)]
+    pub permanent: bool,
+    #[br(try_map = |x: u32| BaudRate::try_from(x))]
+    #[bw(map = |x: &BaudRate| x.bits_per_second())]
+    pub primary_port: BaudRate,
+    #[br(try_map = |x: u32| BaudRate::try_from(x))]
+    #[bw(map = |x: &BaudRate| x.bits_per_second())]
+    pub gpio_port: BaudRate,
+    #[br(try_map = |x: u32| BaudRate::try_from(x))]
+    #[bw(map = |x: &BaudRate| x.bits_per_second())]
+    pub auxiliary_port: BaudRate,
+    #[br(temp)]
+    #[bw(calc = 0u32)]
+    _reserved: u32,
+}
+
+/// Per-port NMEA output rates bitfield used in GpioOutputConfiguration
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, BinRead, BinWrite)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[brw(little)]
+pub struct GpioOutputRates(u16);
+
+impl GpioOutputRates {
+    pub fn new(gpio1: GpioOutputRate, gpio2: GpioOutputRate, auxiliary: GpioOutputRate) -> Self {
+        Self(gpio1 as u16 | (gpio2 as u16) << 4 | (auxiliary as u16) << 8)
+    }
+
+    pub fn raw(&self) -> u16 { self.0 }
+    pub fn gpio1(&self) -> GpioOutputRate { GpioOutputRate::from((self.0 & 0x0F) as u8) }
+    pub fn gpio2(&self) -> GpioOutputRate { GpioOutputRate::from(((self.0 >> 4) & 0x0F) as u8) }
+    pub fn auxiliary(&self) -> GpioOutputRate { GpioOutputRate::from(((self.0 >> 8) & 0x0F) as u8) }
+}
+
+impl From<u16> for GpioOutputRates {
+    fn from(v: u16) -> Self { Self(v) }
+}
+
+/// GPIO configuration packet (Packet ID 188, Length 13) - Read/Write
+#[binrw]
+#[brw(little)]
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct GpioConfiguration {
+    #[br(map = |x: u8| x != 0)]
+    #[bw(map = |x: &bool| *x as u8)]
+    pub permanent: bool,
+    #[br(map = |x: u8| GpioFunction::from(x))]
+    #[bw(map = |x: &GpioFunction| u8::from(*x))]
+    pub gpio1: GpioFunction,
+    #[br(map = |x: u8| GpioFunction::from(x))]
+    #[bw(map = |x: &GpioFunction| u8::from(*x))]
+    pub gpio2: GpioFunction,
+    #[br(map = |x: u8| GpioFunction::from(x))]
+    #[bw(map = |x: &GpioFunction| u8::from(*x))]
+    pub auxiliary_transmit: GpioFunction,
+    #[br(map = |x: u8| GpioFunction::from(x))]
+    #[bw(map = |x: &GpioFunction| u8::from(*x))]
+    pub auxiliary_receive: GpioFunction,
+    #[br(temp)]
+    #[bw(calc = [0u8; 8])]
+    _reserved: [u8; 8],
+}
+
+impl GpioConfiguration {
+    /// Check the assignment is something the device can honour.
+    ///
+    /// The auxiliary transmit line can only drive outputs and the receive line
+    /// can only read inputs. Codes unknown to this library are rejected so a
+    /// typo can't silently disable a port.
+    pub fn validate(&self) -> Result<(), AnError> {
+        let pins = [
+            ("GPIO 1", self.gpio1),
+            ("GPIO 2", self.gpio2),
+            ("auxiliary transmit", self.auxiliary_transmit),
+            ("auxiliary receive", self.auxiliary_receive),
+        ];
+        for (pin, function) in pins {
+            if let GpioFunction::Other(code) = function {
+                return Err(AnError::ValidationFailed(format!("{} has unknown function code {}", pin, code)));
+            }
+        }
+        if self.auxiliary_transmit.is_input() {
+            return Err(AnError::ValidationFailed(format!(
+                "auxiliary transmit cannot be an input ({:?})", self.auxiliary_transmit)));
+        }
+        if self.auxiliary_receive.is_output() {
+            return Err(AnError::ValidationFailed(format!(
+                "auxiliary receive cannot be an output ({:?})", self.auxiliary_receive)));
+        }
+        Ok(())
+    }
+}
+
+/// GPIO output configuration packet (Packet ID 195, Length 33) - Read/Write
+///
+/// NMEA sentence rates for each port that has been assigned NMEA output.
+#[binrw]
+#[brw(little)]
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct GpioOutputConfiguration {
+    #[br(map = |x: u8| x != 0)]
+    #[bw(map = |x: &bool| *x as u8)]
+    pub permanent: bool,
+    pub nmea_fix_behaviour: u8,
+    pub gpzda: GpioOutputRates,
+    pub gpgga: GpioOutputRates,
+    pub gpvtg: GpioOutputRates,
+    pub gprmc: GpioOutputRates,
+    pub gphdt: GpioOutputRates,
+    pub gpgll: GpioOutputRates,
+    pub pashr: GpioOutputRates,
+    pub tss1: GpioOutputRates,
+    pub simrad: GpioOutputRates,
+    pub gprot: GpioOutputRates,
+    pub gphev: GpioOutputRates,
+    pub gpgsv: GpioOutputRates,
+    pub pfecatt: GpioOutputRates,
+    pub pfechve: GpioOutputRates,
+    #[br(temp)]
+    #[bw(calc = [0u8; 3])]
+    _reserved: [u8; 3],
+}
+
+/// GPIO input configuration packet (Packet ID 199, Length 65) - Read/Write
+#[binrw]
+#[brw(little)]
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct GpioInputConfiguration {
+    #[br(map = |x: u8| x != 0)]
+    #[bw(map = |x: &bool| *x as u8)]
+    pub permanent: bool,
+    /// Gimbal encoder scale in radians per tick
+    pub gimbal_radians_per_encoder_tick: f32,
+    #[br(temp)]
+    #[bw(calc = [0u8; 60])]
+    _reserved: [u8; 60],
+}
+
 /// Installation alignment packet (Packet ID 185, Length 73) - Read/Write
-#[derive(Debug, Clone, PartialEq, BinRead, BinWrite, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, BinRead, BinWrite)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 #[brw(little)]
 pub struct InstallationAlignment {
     #[br(map = |x: u8| x != 0)]
@@ -180,10 +804,168 @@ pub struct InstallationAlignment {
     pub external_data_offset: OffsetVector,
 }
 
+impl InstallationAlignment {
+    /// Largest offset magnitude accepted by [`validate`](Self::validate), in
+    /// meters. Anything further from the unit is almost certainly a units or
+    /// sign mistake rather than a real lever arm.
+    pub const MAX_OFFSET: f32 = 100.0;
+
+    pub fn builder() -> InstallationAlignmentBuilder {
+        InstallationAlignmentBuilder::default()
+    }
+
+    /// Roll, pitch and yaw of the unit relative to the vehicle, the inverse
+    /// of [`set_alignment_from_euler`](Self::set_alignment_from_euler)
+    pub fn alignment_euler(&self) -> (Angle, Angle, Angle) {
+        let m = self.alignment_dcm.map(|row| row.map(f64::from));
+        let roll = m[1][2].atan2(m[2][2]);
+        let pitch = (-m[0][2]).clamp(-1.0, 1.0).asin();
+        let yaw = m[0][1].atan2(m[0][0]);
+        (Angle::from_radians(roll), Angle::from_radians(pitch), Angle::from_radians(yaw))
+    }
+
+    /// Replace the alignment matrix with one built from roll, pitch and yaw
+    pub fn set_alignment_from_euler(&mut self, roll: Angle, pitch: Angle, yaw: Angle) {
+        self.alignment_dcm = dcm_from_euler(roll, pitch, yaw);
+    }
+
+    /// Check the alignment matrix is a proper rotation and every offset is
+    /// finite and within [`MAX_OFFSET`](Self::MAX_OFFSET)
+    pub fn validate(&self) -> Result<(), AnError> {
+        validate_rotation(&self.alignment_dcm)?;
+        for (name, offset) in [
+            ("GNSS antenna", &self.gnss_antenna_offset),
+            ("odometer", &self.odometer_offset),
+            ("external data", &self.external_data_offset),
+        ] {
+            let magnitude = (offset.x * offset.x + offset.y * offset.y + offset.z * offset.z).sqrt();
+            if !magnitude.is_finite() {
+                return Err(AnError::ValidationFailed(format!("{} offset is not finite", name)));
+            }
+            if magnitude > Self::MAX_OFFSET {
+                return Err(AnError::ValidationFailed(format!(
+                    "{} offset of {:.1} m exceeds {} m", name, magnitude, Self::MAX_OFFSET)));
+            }
+        }
+        Ok(())
+    }
+}
+
+fn dcm_from_euler(roll: Angle, pitch: Angle, yaw: Angle) -> [[f32; 3]; 3] {
+    let (sr, cr) = roll.radians().sin_cos();
+    let (sp, cp) = pitch.radians().sin_cos();
+    let (sy, cy) = yaw.radians().sin_cos();
+    let dcm = [
+        [cp * cy, cp * sy, -sp],
+        [sr * sp * cy - cr * sy, sr * sp * sy + cr * cy, sr * cp],
+        [cr * sp * cy + sr * sy, cr * sp * sy - sr * cy, cr * cp],
+    ];
+    dcm.map(|row| row.map(|v| v as f32))
+}
+
+fn validate_rotation(m: &[[f32; 3]; 3]) -> Result<(), AnError> {
+    for i in 0..3 {
+        for j in 0..3 {
+            let dot: f32 = (0..3).map(|k| m[i][k] * m[j][k]).sum();
+            let expected = if i == j { 1.0 } else { 0.0 };
+            if !dot.is_finite() || (dot - expected).abs() > 1e-3 {
+                return Err(AnError::ValidationFailed("alignment DCM is not orthonormal".to_string()));
+            }
+        }
+    }
+    let det = m[0][0] * (m[1][1] * m[2][2] - m[1][2] * m[2][1])
+        - m[0][1] * (m[1][0] * m[2][2] - m[1][2] * m[2][0])
+        + m[0][2] * (m[1][0] * m[2][1] - m[1][1] * m[2][0]);
+    if det < 0.0 {
+        return Err(AnError::ValidationFailed("alignment DCM is a reflection, not a rotation".to_string()));
+    }
+    Ok(())
+}
+
+/// Builder for [`InstallationAlignment`]
+#[derive(Debug, Clone)]
+pub struct InstallationAlignmentBuilder {
+    permanent: bool,
+    alignment_dcm: [[f32; 3]; 3],
+    gnss_antenna_offset: OffsetVector,
+    odometer_offset: OffsetVector,
+    external_data_offset: OffsetVector,
+}
+
+impl Default for InstallationAlignmentBuilder {
+    fn default() -> Self {
+        let zero = OffsetVector { x: 0.0, y: 0.0, z: 0.0 };
+        Self {
+            permanent: false,
+            alignment_dcm: [[1.0, 0.0, 0.0], [0.0, 1.0, 0.0], [0.0, 0.0, 1.0]],
+            gnss_antenna_offset: zero.clone(),
+            odometer_offset: zero.clone(),
+            external_data_offset: zero,
+        }
+    }
+}
+
+impl InstallationAlignmentBuilder {
+    pub fn permanent(mut self, permanent: bool) -> Self {
+        self.permanent = permanent;
+        self
+    }
+
+    /// Alignment rotation matrix; defaults to identity
+    pub fn alignment_dcm(mut self, dcm: [[f32; 3]; 3]) -> Self {
+        self.alignment_dcm = dcm;
+        self
+    }
+
+    /// Alignment from roll, pitch and yaw of the unit relative to the vehicle
+    pub fn alignment_euler(self, roll: Angle, pitch: Angle, yaw: Angle) -> Self {
+        self.alignment_dcm(dcm_from_euler(roll, pitch, yaw))
+    }
+
+    /// GNSS antenna offset in meters
+    pub fn gnss_antenna_offset(mut self, x: f32, y: f32, z: f32) -> Self {
+        self.gnss_antenna_offset = OffsetVector { x, y, z };
+        self
+    }
+
+    /// GNSS antenna offset from typed lengths
+    pub fn gnss_antenna_offset_length(self, x: Length, y: Length, z: Length) -> Self {
+        self.gnss_antenna_offset(x.meters() as f32, y.meters() as f32, z.meters() as f32)
+    }
+
+    /// Odometer offset in meters
+    pub fn odometer_offset(mut self, x: f32, y: f32, z: f32) -> Self {
+        self.odometer_offset = OffsetVector { x, y, z };
+        self
+    }
+
+    /// External data offset in meters
+    pub fn external_data_offset(mut self, x: f32, y: f32, z: f32) -> Self {
+        self.external_data_offset = OffsetVector { x, y, z };
+        self
+    }
+
+    /// Fails unless the alignment matrix is a proper rotation and the
+    /// offsets pass [`InstallationAlignment::validate`]
+    pub fn build(self) -> Result<InstallationAlignment, AnError> {
+        let alignment = InstallationAlignment {
+            permanent: self.permanent,
+            alignment_dcm: self.alignment_dcm,
+            gnss_antenna_offset: self.gnss_antenna_offset,
+            odometer_offset: self.odometer_offset,
+            external_data_offset: self.external_data_offset,
+        };
+        alignment.validate()?;
+        Ok(alignment)
+    }
+}
+
 /// Filter options packet (Packet ID 186, Length 17) - Read/Write
 #[binrw]
 #[brw(little)]
-#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub struct FilterOptions {
     #[br(map = |x: u8| x != 0)]
     #[bw(map = |x: &bool| *x as u8)]
@@ -215,10 +997,262 @@ pub struct FilterOptions {
     _reserved3: [u8; 8],
 }
 
+impl FilterOptions {
+    pub fn builder() -> FilterOptionsBuilder {
+        FilterOptionsBuilder::default()
+    }
+
+    /// Every constraint these options break on `model`, hard errors and
+    /// warnings alike
+    pub fn issues_for_device(&self, model: DeviceModel) -> Vec<FilterIssue> {
+        let mut issues = Vec::new();
+        if !model.has_navigation_filter() {
+            issues.push(FilterIssue::NoNavigationFilter(model));
+            return issues;
+        }
+        if self.velocity_heading_enabled {
+            if self.vehicle_type == VehicleType::Stationary {
+                issues.push(FilterIssue::VelocityHeadingWhileStationary);
+            } else if self.vehicle_type.sideslips() {
+                issues.push(FilterIssue::VelocityHeadingWithSideslip(self.vehicle_type));
+            }
+            if model.has_dual_antenna() {
+                issues.push(FilterIssue::VelocityHeadingWithDualAntenna(model));
+            }
+            if !model.has_internal_gnss() || !self.internal_gnss_enabled {
+                issues.push(FilterIssue::VelocityHeadingWithoutInternalGnss);
+            }
+        }
+        if self.reversing_detection_enabled && !self.velocity_heading_enabled {
+            issues.push(FilterIssue::ReversingWithoutVelocityHeading);
+        }
+        if self.internal_gnss_enabled && !model.has_internal_gnss() && model != DeviceModel::Unknown {
+            issues.push(FilterIssue::NoInternalGnss(model));
+        }
+        issues
+    }
+
+    /// Check the options against the constraint matrix for `model` before
+    /// writing them. Fails on any hard error; otherwise returns the
+    /// warnings for the caller to show.
+    pub fn validate_for_device(&self, model: DeviceModel) -> Result<Vec<FilterIssue>, AnError> {
+        let (errors, warnings): (Vec<_>, Vec<_>) = self.issues_for_device(model)
+            .into_iter()
+            .partition(|issue| issue.severity() == IssueSeverity::Error);
+        if !errors.is_empty() {
+            let messages: Vec<String> = errors.iter().map(ToString::to_string).collect();
+            return Err(AnError::ValidationFailed(messages.join("; ")));
+        }
+        Ok(warnings)
+    }
+}
+
+impl VehicleType {
+    /// Whether the vehicle's heading routinely differs from its direction of
+    /// travel (drift, crabbing or sideslip), so velocity heading misleads
+    pub fn sideslips(&self) -> bool {
+        matches!(self,
+            Self::Hovercraft | Self::Submarine | Self::Underwater3D | Self::FixedWingPlane
+            | Self::Aircraft3D | Self::Boat | Self::LargeShip | Self::StuntPlane)
+    }
+}
+
+/// Whether a [`FilterIssue`] blocks the write or is only advisory
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum IssueSeverity {
+    Warning,
+    Error,
+}
+
+/// A Filter Options setting that does not suit the vehicle or device
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum FilterIssue {
+    /// The device has no navigation filter to configure
+    NoNavigationFilter(DeviceModel),
+    /// A stationary vehicle has no velocity to take heading from
+    VelocityHeadingWhileStationary,
+    /// Reversing detection only works with velocity heading
+    ReversingWithoutVelocityHeading,
+    /// The vehicle's track differs from its heading
+    VelocityHeadingWithSideslip(VehicleType),
+    /// The device already measures heading with two antennas
+    VelocityHeadingWithDualAntenna(DeviceModel),
+    /// Velocity heading needs external GNSS velocity aiding
+    VelocityHeadingWithoutInternalGnss,
+    /// Internal GNSS is enabled on a model without a receiver
+    NoInternalGnss(DeviceModel),
+}
+
+impl FilterIssue {
+    pub fn severity(&self) -> IssueSeverity {
+        match self {
+            Self::NoNavigationFilter(_)
+            | Self::VelocityHeadingWhileStationary
+            | Self::ReversingWithoutVelocityHeading => IssueSeverity::Error,
+            Self::VelocityHeadingWithSideslip(_)
+            | Self::VelocityHeadingWithDualAntenna(_)
+            | Self::VelocityHeadingWithoutInternalGnss
+            | Self::NoInternalGnss(_) => IssueSeverity::Warning,
+        }
+    }
+}
+
+impl std::fmt::Display for FilterIssue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::NoNavigationFilter(model) => write!(f, "{:?} does not accept filter options", model),
+            Self::VelocityHeadingWhileStationary => write!(f, "velocity heading needs a moving vehicle type"),
+            Self::ReversingWithoutVelocityHeading => write!(f, "reversing detection requires velocity heading"),
+            Self::VelocityHeadingWithSideslip(vehicle) => {
+                write!(f, "velocity heading is unreliable for {:?}, whose track differs from its heading", vehicle)
+            }
+            Self::VelocityHeadingWithDualAntenna(model) => {
+                write!(f, "velocity heading is redundant on {:?}, which has dual antenna heading", model)
+            }
+            Self::VelocityHeadingWithoutInternalGnss => {
+                write!(f, "velocity heading needs external GNSS velocity without the internal receiver")
+            }
+            Self::NoInternalGnss(model) => write!(f, "{:?} has no internal GNSS receiver to enable", model),
+        }
+    }
+}
+
+/// Builder for [`FilterOptions`]. The vehicle type is required; everything
+/// else defaults to the factory setting.
+#[derive(Debug, Clone)]
+pub struct FilterOptionsBuilder {
+    permanent: bool,
+    vehicle_type: Option<VehicleType>,
+    internal_gnss_enabled: bool,
+    atmospheric_altitude_enabled: bool,
+    velocity_heading_enabled: bool,
+    reversing_detection_enabled: bool,
+    motion_analysis_enabled: bool,
+}
+
+impl Default for FilterOptionsBuilder {
+    fn default() -> Self {
+        Self {
+            permanent: false,
+            vehicle_type: None,
+            internal_gnss_enabled: true,
+            atmospheric_altitude_enabled: true,
+            velocity_heading_enabled: false,
+            reversing_detection_enabled: false,
+            motion_analysis_enabled: false,
+        }
+    }
+}
+
+impl FilterOptionsBuilder {
+    pub fn permanent(mut self, permanent: bool) -> Self {
+        self.permanent = permanent;
+        self
+    }
+
+    pub fn vehicle_type(mut self, vehicle_type: VehicleType) -> Self {
+        self.vehicle_type = Some(vehicle_type);
+        self
+    }
+
+    pub fn internal_gnss(mut self, enabled: bool) -> Self {
+        self.internal_gnss_enabled = enabled;
+        self
+    }
+
+    pub fn atmospheric_altitude(mut self, enabled: bool) -> Self {
+        self.atmospheric_altitude_enabled = enabled;
+        self
+    }
+
+    pub fn velocity_heading(mut self, enabled: bool) -> Self {
+        self.velocity_heading_enabled = enabled;
+        self
+    }
+
+    pub fn reversing_detection(mut self, enabled: bool) -> Self {
+        self.reversing_detection_enabled = enabled;
+        self
+    }
+
+    pub fn motion_analysis(mut self, enabled: bool) -> Self {
+        self.motion_analysis_enabled = enabled;
+        self
+    }
+
+    pub fn build(self) -> Result<FilterOptions, AnError> {
+        let vehicle_type = self.vehicle_type
+            .ok_or_else(|| AnError::ValidationFailed("vehicle type is required".to_string()))?;
+        if self.velocity_heading_enabled && vehicle_type == VehicleType::Stationary {
+            return Err(AnError::ValidationFailed(
+                "velocity heading needs a moving vehicle type".to_string()));
+        }
+        if self.reversing_detection_enabled && !self.velocity_heading_enabled {
+            return Err(AnError::ValidationFailed(
+                "reversing detection requires velocity heading".to_string()));
+        }
+
+        Ok(FilterOptions {
+            permanent: self.permanent,
+            vehicle_type,
+            internal_gnss_enabled: self.internal_gnss_enabled,
+            atmospheric_altitude_enabled: self.atmospheric_altitude_enabled,
+            velocity_heading_enabled: self.velocity_heading_enabled,
+            reversing_detection_enabled: self.reversing_detection_enabled,
+            motion_analysis_enabled: self.motion_analysis_enabled,
+        })
+    }
+}
+
+/// Magnetic calibration values packet (Packet ID 189, Length 49) - Read/Write
+#[derive(Debug, Clone, PartialEq, BinRead, BinWrite)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[brw(little)]
+pub struct MagneticCalibrationValues {
+    #[br(map = |x: u8| x != 0)]
+    #[bw(map = |x: &bool| *x as u8)]
+    pub permanent: bool,
+    /// Hard iron bias in mG
+    pub hard_iron_bias: OffsetVector,
+    /// Soft iron transformation matrix
+    pub soft_iron_transformation: [[f32; 3]; 3],
+}
+
+/// Magnetic calibration configuration packet (Packet ID 190, Length 1) - Write only
+#[derive(Debug, Clone, PartialEq, BinRead, BinWrite)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[brw(little)]
+pub struct MagneticCalibrationConfiguration {
+    pub action: MagneticCalibrationAction,
+}
+
+/// Magnetic calibration status packet (Packet ID 191, Length 3) - Read only
+#[derive(Debug, Clone, PartialEq, BinRead, BinWrite)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[brw(little)]
+pub struct MagneticCalibrationStatus {
+    #[br(map = |x: u8| MagneticCalibrationStatusCode::from(x))]
+    #[bw(map = |x: &MagneticCalibrationStatusCode| u8::from(*x))]
+    pub status: MagneticCalibrationStatusCode,
+    /// Calibration progress in percent
+    pub progress: u8,
+    /// Local magnetic error in percent
+    pub local_magnetic_error: u8,
+}
+
 /// Odometer configuration packet (Packet ID 192, Length 8) - Read/Write
 #[binrw]
 #[brw(little)]
-#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub struct OdometerConfiguration {
     #[br(map = |x: u8| x != 0)]
     #[bw(map = |x: &bool| *x as u8)]
@@ -233,7 +1267,9 @@ pub struct OdometerConfiguration {
 }
 
 /// Set zero orientation alignment packet (Packet ID 193, Length 1) - Write only
-#[derive(Debug, Clone, PartialEq, BinRead, BinWrite, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, BinRead, BinWrite)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 #[brw(little)]
 pub struct SetZeroOrientationAlignment {
     #[br(map = |x: u8| x != 0)]
@@ -242,35 +1278,226 @@ pub struct SetZeroOrientationAlignment {
 }
 
 /// Reference point offsets packet (Packet ID 194, Length 49) - Read/Write
-#[derive(Debug, Clone, PartialEq, BinRead, BinWrite, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, BinRead, BinWrite)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 #[brw(little)]
 pub struct ReferencePointOffsets {
     #[br(map = |x: u8| x != 0)]
     #[bw(map = |x: &bool| *x as u8)]
     pub permanent: bool,
+    /// Primary reference point, where position and velocity are reported
     pub heave_point_1: OffsetVector,
+    /// Centre of gravity lever arm
     pub heave_point_2: OffsetVector,
     pub heave_point_3: OffsetVector,
     pub heave_point_4: OffsetVector,
 }
 
+impl ReferencePointOffsets {
+    /// Offsets for all four heave points, in order
+    pub fn new(permanent: bool, points: [OffsetVector; 4]) -> Self {
+        let [heave_point_1, heave_point_2, heave_point_3, heave_point_4] = points;
+        Self { permanent, heave_point_1, heave_point_2, heave_point_3, heave_point_4 }
+    }
+
+    pub fn primary(&self) -> &OffsetVector {
+        &self.heave_point_1
+    }
+
+    pub fn cog_lever_arm(&self) -> &OffsetVector {
+        &self.heave_point_2
+    }
+
+    pub fn heave3(&self) -> &OffsetVector {
+        &self.heave_point_3
+    }
+
+    pub fn heave4(&self) -> &OffsetVector {
+        &self.heave_point_4
+    }
+
+    /// Offset of one heave point
+    pub fn offset(&self, point: HeavePointId) -> &OffsetVector {
+        match point {
+            HeavePointId::Primary => &self.heave_point_1,
+            HeavePointId::Cog => &self.heave_point_2,
+            HeavePointId::Point3 => &self.heave_point_3,
+            HeavePointId::Point4 => &self.heave_point_4,
+        }
+    }
+
+    pub fn offset_mut(&mut self, point: HeavePointId) -> &mut OffsetVector {
+        match point {
+            HeavePointId::Primary => &mut self.heave_point_1,
+            HeavePointId::Cog => &mut self.heave_point_2,
+            HeavePointId::Point3 => &mut self.heave_point_3,
+            HeavePointId::Point4 => &mut self.heave_point_4,
+        }
+    }
+
+    /// All four heave points, in order
+    pub fn points(&self) -> [OffsetVector; 4] {
+        [
+            self.heave_point_1.clone(),
+            self.heave_point_2.clone(),
+            self.heave_point_3.clone(),
+            self.heave_point_4.clone(),
+        ]
+    }
+}
+
+impl From<&ReferencePointOffsets> for [OffsetVector; 4] {
+    fn from(offsets: &ReferencePointOffsets) -> Self {
+        offsets.points()
+    }
+}
+
+/// One of the four heave points shared by Reference Point Offsets,
+/// Vessel Motion Configuration and the Heave packet
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum HeavePointId {
+    /// Heave point 1, the primary reference point
+    Primary,
+    /// Heave point 2, the centre of gravity
+    Cog,
+    Point3,
+    Point4,
+}
+
+impl HeavePointId {
+    pub const ALL: [HeavePointId; 4] = [Self::Primary, Self::Cog, Self::Point3, Self::Point4];
+
+    /// Point number as printed in the reference manual, 1 to 4
+    pub fn number(&self) -> u8 {
+        *self as u8 + 1
+    }
+}
+
+impl TryFrom<u8> for HeavePointId {
+    type Error = AnError;
+
+    fn try_from(number: u8) -> Result<Self, Self::Error> {
+        number.checked_sub(1)
+            .and_then(|index| Self::ALL.get(index as usize).copied())
+            .ok_or_else(|| AnError::ValidationFailed(format!("heave point {} outside 1-4", number)))
+    }
+}
+
+/// Heave points enabled in VesselMotionConfiguration bitfield
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, BinRead, BinWrite)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[brw(little)]
+pub struct HeavePointMask(u8);
+
+impl HeavePointMask {
+    /// Enable the first `count` heave points
+    pub fn first(count: usize) -> Self {
+        Self(((1u16 << count.min(4)) - 1) as u8)
+    }
+
+    pub fn raw(&self) -> u8 { self.0 }
+    pub fn heave_point_1(&self) -> bool { self.0 & (1 << 0) != 0 }
+    pub fn heave_point_2(&self) -> bool { self.0 & (1 << 1) != 0 }
+    pub fn heave_point_3(&self) -> bool { self.0 & (1 << 2) != 0 }
+    pub fn heave_point_4(&self) -> bool { self.0 & (1 << 3) != 0 }
+
+    pub fn contains(&self, point: HeavePointId) -> bool {
+        self.0 & (1 << point as u8) != 0
+    }
+
+    pub fn with(self, point: HeavePointId) -> Self {
+        Self(self.0 | (1 << point as u8))
+    }
+}
+
+impl From<u8> for HeavePointMask {
+    fn from(v: u8) -> Self { Self(v) }
+}
+
+/// Vessel motion configuration packet (Packet ID 205, Length 16) - Read/Write
+#[binrw]
+#[brw(little)]
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct VesselMotionConfiguration {
+    #[br(map = |x: u8| x != 0)]
+    #[bw(map = |x: &bool| *x as u8)]
+    pub permanent: bool,
+    pub heave_points: HeavePointMask,
+    #[br(temp)]
+    #[bw(calc = 0u16)]
+    _reserved1: u16,
+    /// Heave high-pass filter cutoff period; longer periods follow slower swell
+    #[br(map = |x: f32| Duration::try_from_secs_f32(x.max(0.0)).unwrap_or(Duration::MAX))]
+    #[bw(map = |x: &Duration| x.as_secs_f32())]
+    pub heave_filter_period: Duration,
+    #[br(temp)]
+    #[bw(calc = [0u8; 8])]
+    _reserved2: [u8; 8],
+}
+
 /// IP dataport configuration entry
-#[derive(Debug, Clone, Copy, PartialEq, BinRead, BinWrite, Serialize, Deserialize)]
+#[binrw]
 #[brw(little)]
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub struct IpDataport {
-    pub ip_address: u32,
-    pub port: u16,
+    #[br(temp)]
+    #[bw(calc = ipv4_to_wire(address.ip()))]
+    ip_address: u32,
+    #[br(temp)]
+    #[bw(calc = address.port())]
+    port: u16,
+    /// Remote address for clients; servers use only the port
+    #[br(calc = SocketAddrV4::new(ipv4_from_wire(ip_address), port))]
+    #[bw(ignore)]
+    #[cfg_attr(feature = "defmt", defmt(Debug2Format))]
+    pub address: SocketAddrV4,
     pub mode: IpDataportMode,
 }
 
+impl IpDataport {
+    pub const DISABLED: Self = Self {
+        address: SocketAddrV4::new(Ipv4Addr::UNSPECIFIED, 0),
+        mode: IpDataportMode::Disabled,
+    };
+
+    pub fn new(mode: IpDataportMode, address: SocketAddrV4) -> Self {
+        Self { address, mode }
+    }
+
+    /// Dataport for any socket address. The packet carries IPv4 only, so
+    /// IPv6 addresses are refused.
+    pub fn try_new(mode: IpDataportMode, address: SocketAddr) -> Result<Self, AnError> {
+        match address {
+            SocketAddr::V4(address) => Ok(Self::new(mode, address)),
+            SocketAddr::V6(address) => Err(AnError::ValidationFailed(format!(
+                "dataport address {} is IPv6, the IP dataports packet only carries IPv4", address))),
+        }
+    }
+
+    pub fn socket_addr(&self) -> SocketAddr {
+        SocketAddr::V4(self.address)
+    }
+}
+
 /// User data packet (Packet ID 198, Length 64) - Read/Write
-#[derive(Debug, Clone, PartialEq, BinRead, BinWrite, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, BinRead, BinWrite)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 #[brw(little)]
 pub struct UserData {
-    #[serde(with = "serde_bytes_64")]
+    #[cfg_attr(feature = "serde", serde(with = "serde_bytes_64"))]
     pub data: [u8; 64],
 }
 
+#[cfg(feature = "serde")]
 mod serde_bytes_64 {
     use serde::{Deserialize, Deserializer, Serializer};
 
@@ -289,7 +1516,9 @@ mod serde_bytes_64 {
 /// Dual antenna configuration packet (Packet ID 196, Length 17) - Read/Write
 #[binrw]
 #[brw(little)]
-#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub struct DualAntennaConfiguration {
     #[br(map = |x: u8| x != 0)]
     #[bw(map = |x: &bool| *x as u8)]
@@ -304,17 +1533,185 @@ pub struct DualAntennaConfiguration {
     pub manual_offset_z: f32,
 }
 
+impl DualAntennaConfiguration {
+    /// Shortest manual baseline accepted by [`validate`](Self::validate), in
+    /// meters. Closer antennas give a heading too noisy to aid the filter.
+    pub const MIN_BASELINE: f32 = 0.3;
+    /// Longest manual baseline accepted by [`validate`](Self::validate), in
+    /// meters, matching [`InstallationAlignment::MAX_OFFSET`]
+    pub const MAX_BASELINE: f32 = InstallationAlignment::MAX_OFFSET;
+
+    /// Let the device measure the baseline, with the antennas laid out as
+    /// `orientation`
+    pub fn automatic(orientation: AutomaticOffsetOrientation) -> Self {
+        Self {
+            permanent: false,
+            offset_type: OffsetType::Automatic,
+            automatic_offset_orientation: orientation,
+            manual_offset_x: 0.0,
+            manual_offset_y: 0.0,
+            manual_offset_z: 0.0,
+        }
+    }
+
+    /// Fixed baseline from the primary to the secondary antenna in the body
+    /// frame
+    pub fn manual(baseline: OffsetVector) -> Self {
+        Self {
+            permanent: false,
+            offset_type: OffsetType::Manual,
+            automatic_offset_orientation: AutomaticOffsetOrientation::PrimaryFrontSecondaryRear,
+            manual_offset_x: baseline.x,
+            manual_offset_y: baseline.y,
+            manual_offset_z: baseline.z,
+        }
+    }
+
+    pub fn is_automatic(&self) -> bool {
+        self.offset_type == OffsetType::Automatic
+    }
+
+    /// Manual baseline vector
+    pub fn manual_offset(&self) -> OffsetVector {
+        OffsetVector { x: self.manual_offset_x, y: self.manual_offset_y, z: self.manual_offset_z }
+    }
+
+    /// Length of the manual baseline
+    pub fn baseline(&self) -> Length {
+        let OffsetVector { x, y, z } = self.manual_offset();
+        Length::from_meters((x * x + y * y + z * z).sqrt() as f64)
+    }
+
+    /// Check a manual baseline is finite and between
+    /// [`MIN_BASELINE`](Self::MIN_BASELINE) and
+    /// [`MAX_BASELINE`](Self::MAX_BASELINE). The device ignores the manual
+    /// offset in automatic mode, so it isn't checked there.
+    pub fn validate(&self) -> Result<(), AnError> {
+        if self.is_automatic() {
+            return Ok(());
+        }
+        let baseline = self.baseline().meters() as f32;
+        if !baseline.is_finite() {
+            return Err(AnError::ValidationFailed("dual antenna baseline is not finite".to_string()));
+        }
+        if !(Self::MIN_BASELINE..=Self::MAX_BASELINE).contains(&baseline) {
+            return Err(AnError::ValidationFailed(format!(
+                "dual antenna baseline of {:.2} m is outside {}-{} m", baseline, Self::MIN_BASELINE, Self::MAX_BASELINE)));
+        }
+        Ok(())
+    }
+}
+
 /// IP dataports configuration packet (Packet ID 202, Length 30) - Read/Write
 #[binrw]
 #[brw(little)]
-#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub struct IpDataportsConfiguration {
     #[br(temp)]
     #[bw(calc = 0u16)]
     _reserved: u16,
     pub dataports: [IpDataport; 4],
+    /// Fields beyond the documented 30 bytes, if any
+    #[br(parse_with = binrw::helpers::until_eof, map = extension_from_bytes)]
+    #[bw(map = extension_to_bytes)]
+    #[cfg_attr(feature = "serde", serde(default, skip_serializing_if = "Option::is_none"))]
+    pub extension: Option<IpDataportsExt>,
+}
+
+/// Fields appended to [`IpDataportsConfiguration`] by newer firmware
+pub type IpDataportsExt = ExtensionFields;
+
+impl IpDataportsConfiguration {
+    pub fn builder() -> IpDataportsConfigurationBuilder {
+        IpDataportsConfigurationBuilder::default()
+    }
+
+    /// Index, mode and address of each enabled dataport
+    pub fn endpoints(&self) -> impl Iterator<Item = (usize, IpDataportMode, SocketAddr)> + '_ {
+        self.dataports.iter().enumerate()
+            .filter(|(_, dataport)| dataport.mode != IpDataportMode::Disabled)
+            .map(|(index, dataport)| (index, dataport.mode, dataport.socket_addr()))
+    }
+}
+
+/// Builder for [`IpDataportsConfiguration`]. Dataports not configured are
+/// disabled.
+#[derive(Debug, Clone, Default)]
+pub struct IpDataportsConfigurationBuilder {
+    dataports: Vec<(usize, IpDataportMode, SocketAddr)>,
+}
+
+impl IpDataportsConfigurationBuilder {
+    /// Accept connections on `port`
+    pub fn tcp_server(self, index: usize, port: u16) -> Self {
+        self.dataport(index, Ipv4Addr::UNSPECIFIED, port, IpDataportMode::TcpServer)
+    }
+
+    /// Connect out to `address:port`
+    pub fn tcp_client(self, index: usize, address: Ipv4Addr, port: u16) -> Self {
+        self.dataport(index, address, port, IpDataportMode::TcpClient)
+    }
+
+    /// Send datagrams to `address:port`
+    pub fn udp_client(self, index: usize, address: Ipv4Addr, port: u16) -> Self {
+        self.dataport(index, address, port, IpDataportMode::UdpClient)
+    }
+
+    pub fn disabled(self, index: usize) -> Self {
+        self.dataport(index, Ipv4Addr::UNSPECIFIED, 0, IpDataportMode::Disabled)
+    }
+
+    /// Configure a dataport from a socket address; [`build`](Self::build)
+    /// fails for IPv6 addresses
+    pub fn endpoint(mut self, index: usize, mode: IpDataportMode, address: SocketAddr) -> Self {
+        self.dataports.push((index, mode, address));
+        self
+    }
+
+    fn dataport(self, index: usize, address: Ipv4Addr, port: u16, mode: IpDataportMode) -> Self {
+        self.endpoint(index, mode, SocketAddr::from((address, port)))
+    }
+
+    pub fn build(self) -> Result<IpDataportsConfiguration, AnError> {
+        let mut dataports = [IpDataport::DISABLED; 4];
+
+        for (index, mode, address) in self.dataports {
+            let slot = dataports.get_mut(index)
+                .ok_or_else(|| AnError::ValidationFailed(format!("dataport index {} out of range 0-3", index)))?;
+            *slot = IpDataport::try_new(mode, address)?;
+        }
+
+        for (index, dataport) in dataports.iter().enumerate() {
+            match dataport.mode {
+                IpDataportMode::Disabled => continue,
+                IpDataportMode::TcpClient | IpDataportMode::UdpClient
+                    if dataport.address.ip().is_unspecified() =>
+                {
+                    return Err(AnError::ValidationFailed(format!("dataport {} needs a remote address", index)));
+                }
+                _ => {}
+            }
+            if dataport.address.port() == 0 {
+                return Err(AnError::ValidationFailed(format!("dataport {} needs a port", index)));
+            }
+            let clash = dataports[..index].iter().any(|other| {
+                other.mode == IpDataportMode::TcpServer
+                    && dataport.mode == IpDataportMode::TcpServer
+                    && other.address.port() == dataport.address.port()
+            });
+            if clash {
+                return Err(AnError::ValidationFailed(format!(
+                    "dataport {} listens on port {} which is already in use", index, dataport.address.port())));
+            }
+        }
+
+        Ok(IpDataportsConfiguration { dataports, extension: None })
+    }
 }
 
 #[cfg(test)]
 #[path = "tests/config.rs"]
+#[allow(clippy::module_inception)]
 mod tests;
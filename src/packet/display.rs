@@ -0,0 +1,757 @@
+//! Human-readable `Display` for every packet: angles in degrees, IP
+//! addresses as dotted quads and periods with their rate in Hz. Debug
+//! still shows the raw fields.
+
+use std::fmt::{self, Display, Formatter};
+use std::time::Duration;
+
+use super::config::*;
+use super::state::*;
+use super::system::*;
+use super::PacketKind;
+
+fn deg(radians: impl Into<f64>) -> f64 {
+    radians.into().to_degrees()
+}
+
+fn hz(period: Duration) -> f64 {
+    if period.is_zero() { 0.0 } else { 1.0 / period.as_secs_f64() }
+}
+
+fn time(f: &mut Formatter<'_>, seconds: u32, microseconds: u32) -> fmt::Result {
+    write!(f, "{}.{:06}", seconds, microseconds)
+}
+
+fn permanent(f: &mut Formatter<'_>, permanent: bool) -> fmt::Result {
+    if permanent { write!(f, " (permanent)") } else { Ok(()) }
+}
+
+fn vector(f: &mut Formatter<'_>, v: &OffsetVector) -> fmt::Result {
+    write!(f, "({:.3}, {:.3}, {:.3}) m", v.x, v.y, v.z)
+}
+
+fn matrix(f: &mut Formatter<'_>, m: &[[f32; 3]; 3]) -> fmt::Result {
+    write!(f, "[{:.4} {:.4} {:.4}; {:.4} {:.4} {:.4}; {:.4} {:.4} {:.4}]",
+        m[0][0], m[0][1], m[0][2], m[1][0], m[1][1], m[1][2], m[2][0], m[2][1], m[2][2])
+}
+
+impl Display for PacketKind {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{:?} ({})", self, self.packet_id())
+    }
+}
+
+impl Display for GnssFixType {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            GnssFixType::NoFix => "no fix",
+            GnssFixType::Fix2D => "2D",
+            GnssFixType::Fix3D => "3D",
+            GnssFixType::SbassFix => "SBAS",
+            GnssFixType::DifferentialFix => "differential",
+            GnssFixType::PppFix => "PPP",
+            GnssFixType::RtkFloat => "RTK float",
+            GnssFixType::RtkFixed => "RTK fixed",
+        };
+        f.write_str(name)
+    }
+}
+
+impl Display for BaudRate {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{} baud", self.bits_per_second())
+    }
+}
+
+impl Display for PacketPeriod {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{:?} every {} timer periods", self.packet_type, self.divisor)
+    }
+}
+
+impl Display for IpDataport {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{:?} {}", self.mode, self.address)
+    }
+}
+
+// System packets
+
+impl Display for Acknowledge {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "Acknowledge {:?} (CRC 0x{:04X}): {:?}", self.acknowledged_packet, self.packet_crc, self.result)
+    }
+}
+
+impl Display for Request {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "Request")?;
+        for (i, kind) in self.requested_packets.iter().enumerate() {
+            write!(f, "{} {:?}", if i == 0 { "" } else { "," }, kind)?;
+        }
+        Ok(())
+    }
+}
+
+impl Display for BootMode {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "Boot mode {}", self.boot_mode)
+    }
+}
+
+impl Display for DeviceInformation {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{:?} serial {:08X}-{:08X}-{:08X}, software {}, hardware {}",
+            self.device_type, self.serial_number_1, self.serial_number_2, self.serial_number_3,
+            self.software(), self.hardware())
+    }
+}
+
+impl Display for Version {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{}.{}.{}", self.major, self.minor, self.patch)
+    }
+}
+
+impl Display for RestoreFactorySettings {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.write_str("Restore factory settings")
+    }
+}
+
+impl Display for Reset {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.write_str("Reset")
+    }
+}
+
+impl Display for FileTransferRequest {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "File transfer {:08X} request from byte {}", self.unique_id, self.data_index)?;
+        match self.filename() {
+            Some(name) => write!(f, " of {:?}", name)?,
+            None => write!(f, ", {:?} metadata {} bytes", self.metadata_type, self.metadata.len())?,
+        }
+        write!(f, ", {} data bytes {:?}", self.data.len(), self.data_encoding)
+    }
+}
+
+impl Display for FileTransferAcknowledge {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "File transfer {:08X} {:?} at byte {}", self.unique_id, self.response, self.data_index)
+    }
+}
+
+impl Display for FileTransferData {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "File transfer {:08X} bytes {}-{}", self.unique_id, self.data_index,
+            u64::from(self.data_index) + self.data.len() as u64)
+    }
+}
+
+impl Display for SerialPortPassthrough {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "Passthrough {:?}: {} bytes", self.route, self.data.len())
+    }
+}
+
+impl Display for IpConfiguration {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "IP {} netmask {} gateway {} DNS {}, DHCP mode {}",
+            self.ip_address, self.ip_netmask, self.ip_gateway, self.dns_server, self.dhcp_mode)?;
+        permanent(f, self.permanent)
+    }
+}
+
+impl Display for SubcomponentInformation {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{} subcomponents", self.subcomponents.len())?;
+        for c in &self.subcomponents {
+            write!(f, "; device {} serial {:08X}-{:08X}-{:08X}, software {}, hardware {}",
+                c.device_id, c.serial_number_1, c.serial_number_2, c.serial_number_3, c.software(), c.hardware())?;
+        }
+        Ok(())
+    }
+}
+
+// State packets
+
+impl Display for SystemState {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "t=")?;
+        time(f, self.unix_time_seconds, self.microseconds)?;
+        write!(f, " pos {:.7}°, {:.7}°, {:.2} m vel N {:.2} E {:.2} D {:.2} m/s \
+                   roll {:.2}° pitch {:.2}° heading {:.2}° fix {}",
+            deg(self.latitude), deg(self.longitude), self.height,
+            self.velocity_north, self.velocity_east, self.velocity_down,
+            deg(self.roll), deg(self.pitch), deg(self.heading),
+            self.filter_status.gnss_fix_type())
+    }
+}
+
+impl Display for UnixTime {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "Unix time ")?;
+        time(f, self.unix_time_seconds, self.microseconds)
+    }
+}
+
+/// ISO 8601, with the month shown from one
+impl Display for FormattedTime {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}.{:06}Z",
+            self.year, u16::from(self.month) + 1, self.month_day, self.hour, self.minute, self.second, self.microseconds)
+    }
+}
+
+impl Display for Status {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "System status 0x{:04X}, filter status 0x{:04X}, fix {}",
+            self.system_status.raw(), self.filter_status.raw(), self.filter_status.gnss_fix_type())
+    }
+}
+
+impl Display for PositionStdDev {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "Position σ N {:.3} E {:.3} D {:.3} m",
+            self.latitude_std_dev, self.longitude_std_dev, self.height_std_dev)
+    }
+}
+
+impl Display for VelocityStdDev {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "Velocity σ N {:.3} E {:.3} D {:.3} m/s",
+            self.velocity_north_std_dev, self.velocity_east_std_dev, self.velocity_down_std_dev)
+    }
+}
+
+impl Display for EulerOrientationStdDev {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "Orientation σ roll {:.3}° pitch {:.3}° heading {:.3}°",
+            deg(self.roll_std_dev), deg(self.pitch_std_dev), deg(self.heading_std_dev))
+    }
+}
+
+impl Display for QuaternionOrientationStdDev {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "Quaternion σ {:.5} {:.5} {:.5} {:.5}",
+            self.q0_std_dev, self.q1_std_dev, self.q2_std_dev, self.q3_std_dev)
+    }
+}
+
+impl Display for RawSensors {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "Accel ({:.3}, {:.3}, {:.3}) m/s² gyro ({:.3}, {:.3}, {:.3})°/s IMU {:.1} °C pressure {:.0} Pa",
+            self.accelerometer_x, self.accelerometer_y, self.accelerometer_z,
+            deg(self.gyroscope_x), deg(self.gyroscope_y), deg(self.gyroscope_z),
+            self.imu_temperature, self.pressure)
+    }
+}
+
+impl Display for RawGnss {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "Raw GNSS t=")?;
+        time(f, self.unix_time_seconds, self.microseconds)?;
+        write!(f, " pos {:.7}°, {:.7}°, {:.2} m vel N {:.2} E {:.2} D {:.2} m/s fix {}",
+            deg(self.latitude), deg(self.longitude), self.height,
+            self.velocity_north, self.velocity_east, self.velocity_down,
+            self.status.gnss_fix_status())
+    }
+}
+
+impl Display for Satellites {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let total = self.gps_satellites as u16 + self.glonass_satellites as u16 + self.beidou_satellites as u16
+            + self.galileo_satellites as u16 + self.sbas_satellites as u16;
+        write!(f, "{} satellites (GPS {}, GLONASS {}, BeiDou {}, Galileo {}, SBAS {}) HDOP {:.1} VDOP {:.1}",
+            total, self.gps_satellites, self.glonass_satellites, self.beidou_satellites,
+            self.galileo_satellites, self.sbas_satellites, self.hdop, self.vdop)
+    }
+}
+
+impl Display for DetailedSatellites {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{} satellites", self.satellites.len())?;
+        for s in &self.satellites {
+            write!(f, "; {:?} {} el {}° az {}° {} dB-Hz", s.system, s.prn, s.elevation, s.azimuth, s.snr)?;
+        }
+        Ok(())
+    }
+}
+
+impl Display for GeodeticPosition {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{:.7}°, {:.7}°, {:.2} m", deg(self.latitude), deg(self.longitude), self.height)
+    }
+}
+
+impl Display for EcefPosition {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "ECEF ({:.2}, {:.2}, {:.2}) m", self.x, self.y, self.z)
+    }
+}
+
+impl Display for UtmPosition {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "UTM {}{} {:.2} mE {:.2} mN, {:.2} m",
+            self.zone_number, self.zone_char as u8 as char, self.easting, self.northing, self.height)
+    }
+}
+
+impl Display for NedVelocity {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "Velocity N {:.2} E {:.2} D {:.2} m/s", self.velocity_north, self.velocity_east, self.velocity_down)
+    }
+}
+
+impl Display for BodyVelocity {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "Body velocity ({:.2}, {:.2}, {:.2}) m/s", self.velocity_x, self.velocity_y, self.velocity_z)
+    }
+}
+
+impl Display for Acceleration {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "Acceleration ({:.3}, {:.3}, {:.3}) m/s²",
+            self.acceleration_x, self.acceleration_y, self.acceleration_z)
+    }
+}
+
+impl Display for BodyAcceleration {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "Body acceleration ({:.3}, {:.3}, {:.3}) m/s², {:.3} g",
+            self.body_acceleration_x, self.body_acceleration_y, self.body_acceleration_z, self.g_force)
+    }
+}
+
+impl Display for EulerOrientation {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "Roll {:.2}° pitch {:.2}° heading {:.2}°", deg(self.roll), deg(self.pitch), deg(self.heading))
+    }
+}
+
+impl Display for QuaternionOrientation {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "Quaternion {:.5} {:.5} {:.5} {:.5}", self.q0, self.q1, self.q2, self.q3)
+    }
+}
+
+impl Display for DcmOrientation {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "DCM ")?;
+        matrix(f, &self.dcm)
+    }
+}
+
+impl Display for AngularVelocity {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "Angular velocity ({:.3}, {:.3}, {:.3})°/s",
+            deg(self.angular_velocity_x), deg(self.angular_velocity_y), deg(self.angular_velocity_z))
+    }
+}
+
+impl Display for AngularAcceleration {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "Angular acceleration ({:.3}, {:.3}, {:.3})°/s²",
+            deg(self.angular_acceleration_x), deg(self.angular_acceleration_y), deg(self.angular_acceleration_z))
+    }
+}
+
+impl Display for ExternalPositionVelocity {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "External pos {:.7}°, {:.7}°, {:.2} m vel N {:.2} E {:.2} D {:.2} m/s",
+            deg(self.latitude), deg(self.longitude), self.height,
+            self.velocity_north, self.velocity_east, self.velocity_down)
+    }
+}
+
+impl Display for ExternalPosition {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "External pos {:.7}°, {:.7}°, {:.2} m σ {:.2}/{:.2}/{:.2} m",
+            deg(self.latitude), deg(self.longitude), self.height,
+            self.latitude_std_dev, self.longitude_std_dev, self.height_std_dev)
+    }
+}
+
+impl Display for ExternalVelocity {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "External velocity N {:.2} E {:.2} D {:.2} m/s", self.velocity_north, self.velocity_east, self.velocity_down)
+    }
+}
+
+impl Display for ExternalBodyVelocity {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "External body velocity ({:.2}, {:.2}, {:.2}) m/s σ {:.2} m/s",
+            self.velocity_x, self.velocity_y, self.velocity_z, self.standard_deviation)
+    }
+}
+
+impl Display for ExternalHeading {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "External heading {:.2}° σ {:.2}°", deg(self.heading), deg(self.standard_deviation))
+    }
+}
+
+impl Display for RunningTime {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "Running ")?;
+        time(f, self.seconds, self.microseconds)?;
+        write!(f, " s")
+    }
+}
+
+impl Display for OdometerState {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "Odometer {} pulses {:.2} m {:.2} m/s slip {:.2} m{}",
+            self.pulse_count, self.distance, self.speed, self.slip,
+            if self.active { "" } else { " (inactive)" })
+    }
+}
+
+impl Display for ExternalTime {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "External time ")?;
+        time(f, self.unix_time_seconds, self.microseconds)
+    }
+}
+
+impl Display for ExternalDepth {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "External depth {:.2} m σ {:.2} m", self.depth, self.standard_deviation)
+    }
+}
+
+impl Display for GeoidHeight {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "Geoid height {:.2} m", self.geoid_height)
+    }
+}
+
+impl Display for RtcmCorrections {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "RTCM corrections: {} bytes", self.data.len())
+    }
+}
+
+impl Display for Wind {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "Wind {:.2} m/s from {:.1}° σ {:.2} m/s",
+            self.speed().meters_per_second(), self.direction_from().degrees(), self.wind_velocity_std_dev)
+    }
+}
+
+impl Display for Heave {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "Heave {:.3} {:.3} {:.3} {:.3} m",
+            self.heave_point_1, self.heave_point_2, self.heave_point_3, self.heave_point_4)
+    }
+}
+
+impl Display for AntennaOffsetEstimation {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        if !self.active {
+            return write!(f, "Antenna offset estimation inactive");
+        }
+        write!(f, "Antenna offset ({:.3}, {:.3}, {:.3}) m σ {:.3} m{}",
+            self.offset.x, self.offset.y, self.offset.z, self.max_std_dev().meters(),
+            if self.converged { ", converged" } else { "" })
+    }
+}
+
+impl Display for ExternalAirData {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "External air data")?;
+        if self.flags.barometric_altitude_valid() {
+            write!(f, " baro {:.2} m σ {:.2} m", self.barometric_altitude, self.barometric_altitude_std_dev)?;
+        }
+        if self.flags.airspeed_valid() {
+            write!(f, " airspeed {:.2} m/s σ {:.2} m/s", self.airspeed, self.airspeed_std_dev)?;
+        }
+        if self.flags.barometric_altitude_reference_reset() {
+            write!(f, " (reference reset)")?;
+        }
+        Ok(())
+    }
+}
+
+impl Display for RawDvlData {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "DVL bottom ({:.3}, {:.3}, {:.3}) m/s depth {:.2} m altitude {:.2} m {:.1} °C",
+            self.bottom_velocity_x, self.bottom_velocity_y, self.bottom_velocity_z,
+            self.depth, self.altitude, self.temperature)
+    }
+}
+
+impl Display for GnssReceiverInformation {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{:?} {:?} serial {}, firmware {}, hardware {}",
+            self.manufacturer, self.receiver_model(), self.serial_number_str(),
+            self.firmware_version, self.hardware_version)
+    }
+}
+
+impl Display for NorthSeekingStatus {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "North seeking {}% {:?}, rotation {:.1}°{}",
+            self.progress(), self.quadrant_progress, deg(self.current_rotation_angle),
+            if self.flags.initialised() { ", initialised" } else { "" })
+    }
+}
+
+impl Display for SensorTemperature {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "Accelerometer {:.1}/{:.1}/{:.1} °C gyroscope {:.1}/{:.1}/{:.1} °C pressure sensor {:.1} °C",
+            self.accelerometer_temp_0, self.accelerometer_temp_1, self.accelerometer_temp_2,
+            self.gyroscope_temp_0, self.gyroscope_temp_1, self.gyroscope_temp_2, self.pressure_sensor_temp)
+    }
+}
+
+impl Display for GnssPositionVelocityTime {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "GNSS {} t=", self.gnss_id)?;
+        time(f, self.posix_time_seconds, self.posix_time_microseconds)?;
+        write!(f, " pos {:.7}°, {:.7}°, {:.2} m vel N {:.2} E {:.2} D {:.2} m/s fix {}",
+            deg(self.latitude), deg(self.longitude), self.altitude,
+            self.velocity_north, self.velocity_east, self.velocity_down,
+            self.status.gnss_fix_status())
+    }
+}
+
+impl Display for GnssOrientation {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "GNSS {} azimuth {:.2}° tilt {:.2}° baseline {:.3} m fix {}",
+            self.gnss_id, deg(self.azimuth), deg(self.tilt), self.baseline_length, self.status.gnss_fix_status())
+    }
+}
+
+// Configuration packets
+
+impl Display for PacketTimerPeriod {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "Packet timer {} µs ({:.1} Hz){}",
+            self.packet_timer_period.as_micros(), hz(self.packet_timer_period),
+            if self.utc_synchronisation { ", UTC synchronised" } else { "" })?;
+        permanent(f, self.permanent)
+    }
+}
+
+impl Display for PacketsPeriod {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "Packet periods")?;
+        for (i, entry) in self.packet_periods.iter().enumerate() {
+            write!(f, "{} {}", if i == 0 { ":" } else { "," }, entry)?;
+        }
+        if self.clear_existing {
+            write!(f, " (clear existing)")?;
+        }
+        permanent(f, self.permanent)
+    }
+}
+
+impl Display for BaudRates {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "Primary {}, GPIO {}, auxiliary {}", self.primary_port, self.gpio_port, self.auxiliary_port)?;
+        permanent(f, self.permanent)
+    }
+}
+
+impl Display for InstallationAlignment {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "Alignment ")?;
+        matrix(f, &self.alignment_dcm)?;
+        write!(f, " GNSS antenna ")?;
+        vector(f, &self.gnss_antenna_offset)?;
+        write!(f, " odometer ")?;
+        vector(f, &self.odometer_offset)?;
+        write!(f, " external data ")?;
+        vector(f, &self.external_data_offset)?;
+        permanent(f, self.permanent)
+    }
+}
+
+impl Display for FilterOptions {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let flags = [
+            ("internal GNSS", self.internal_gnss_enabled),
+            ("atmospheric altitude", self.atmospheric_altitude_enabled),
+            ("velocity heading", self.velocity_heading_enabled),
+            ("reversing detection", self.reversing_detection_enabled),
+            ("motion analysis", self.motion_analysis_enabled),
+        ];
+        write!(f, "{:?}", self.vehicle_type)?;
+        for (name, enabled) in flags {
+            write!(f, ", {} {}", name, if enabled { "on" } else { "off" })?;
+        }
+        permanent(f, self.permanent)
+    }
+}
+
+impl Display for GpioConfiguration {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "GPIO 1 {:?}, GPIO 2 {:?}, auxiliary TX {:?}, auxiliary RX {:?}",
+            self.gpio1, self.gpio2, self.auxiliary_transmit, self.auxiliary_receive)?;
+        permanent(f, self.permanent)
+    }
+}
+
+impl Display for MagneticCalibrationValues {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let b = &self.hard_iron_bias;
+        write!(f, "Hard iron ({:.1}, {:.1}, {:.1}) mG soft iron ", b.x, b.y, b.z)?;
+        matrix(f, &self.soft_iron_transformation)?;
+        permanent(f, self.permanent)
+    }
+}
+
+impl Display for MagneticCalibrationConfiguration {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "Magnetic calibration {:?}", self.action)
+    }
+}
+
+impl Display for MagneticCalibrationStatus {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "Magnetic calibration {:?} {}%, local error {}%", self.status, self.progress, self.local_magnetic_error)
+    }
+}
+
+impl Display for OdometerConfiguration {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "Odometer pulse length {:.4} m{}", self.pulse_length,
+            if self.automatic_pulse_measurement { ", automatic" } else { "" })?;
+        permanent(f, self.permanent)
+    }
+}
+
+impl Display for SetZeroOrientationAlignment {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "Set zero orientation alignment")?;
+        permanent(f, self.permanent)
+    }
+}
+
+impl Display for ReferencePointOffsets {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let points = [&self.heave_point_1, &self.heave_point_2, &self.heave_point_3, &self.heave_point_4];
+        for (i, point) in points.into_iter().enumerate() {
+            write!(f, "{}Heave point {} ", if i == 0 { "" } else { ", " }, i + 1)?;
+            vector(f, point)?;
+        }
+        permanent(f, self.permanent)
+    }
+}
+
+impl Display for GpioOutputConfiguration {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let sentences = [
+            ("GPZDA", self.gpzda), ("GPGGA", self.gpgga), ("GPVTG", self.gpvtg), ("GPRMC", self.gprmc),
+            ("GPHDT", self.gphdt), ("GPGLL", self.gpgll), ("PASHR", self.pashr), ("TSS1", self.tss1),
+            ("Simrad", self.simrad), ("GPROT", self.gprot), ("GPHEV", self.gphev), ("GPGSV", self.gpgsv),
+            ("PFECAtt", self.pfecatt), ("PFECHve", self.pfechve),
+        ];
+        write!(f, "NMEA output")?;
+        let mut any = false;
+        for (name, rates) in sentences.into_iter().filter(|(_, r)| r.raw() != 0) {
+            write!(f, "{} {} {:?}/{:?}/{:?}", if any { "," } else { ":" }, name,
+                rates.gpio1(), rates.gpio2(), rates.auxiliary())?;
+            any = true;
+        }
+        if !any {
+            write!(f, " disabled")?;
+        }
+        permanent(f, self.permanent)
+    }
+}
+
+impl Display for DualAntennaConfiguration {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self.offset_type {
+            OffsetType::Automatic => write!(f, "Dual antenna automatic, {:?}", self.automatic_offset_orientation)?,
+            OffsetType::Manual => write!(f, "Dual antenna manual ({:.3}, {:.3}, {:.3}) m",
+                self.manual_offset_x, self.manual_offset_y, self.manual_offset_z)?,
+        }
+        permanent(f, self.permanent)
+    }
+}
+
+impl Display for UserData {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let used = self.data.iter().rposition(|&b| b != 0).map_or(0, |i| i + 1);
+        write!(f, "User data: {} bytes", used)
+    }
+}
+
+impl Display for GpioInputConfiguration {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "Gimbal encoder {:.6}°/tick", deg(self.gimbal_radians_per_encoder_tick))?;
+        permanent(f, self.permanent)
+    }
+}
+
+impl Display for IpDataportsConfiguration {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let mut any = false;
+        for (i, port) in self.dataports.iter().enumerate().filter(|(_, p)| p.mode != IpDataportMode::Disabled) {
+            write!(f, "{}Dataport {}: {}", if any { ", " } else { "" }, i + 1, port)?;
+            any = true;
+        }
+        if !any {
+            write!(f, "All dataports disabled")?;
+        }
+        Ok(())
+    }
+}
+
+impl Display for VesselMotionConfiguration {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "Heave points 0b{:04b}, filter period {:.1} s", self.heave_points.raw(), self.heave_filter_period.as_secs_f32())?;
+        permanent(f, self.permanent)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Payload;
+    use crate::packet::Packet;
+    use crate::test_support::system_state;
+    use crate::units::Radians;
+    use std::net::Ipv4Addr;
+
+    #[test]
+    fn test_system_state_in_degrees() {
+        let state = SystemState {
+            filter_status: FilterStatus::from(0x20),
+            unix_time_seconds: 1_700_000_000, microseconds: 5_000,
+            latitude: (-33.5f64).to_radians(), longitude: 151.25f64.to_radians(), height: 12.5,
+            velocity_north: 1.0, velocity_east: -2.0, heading: Radians(90f32.to_radians()),
+            ..system_state()
+        };
+        let text = state.to_string();
+        assert!(text.starts_with("t=1700000000.005000 pos -33.5000000°, 151.2500000°, 12.50 m"), "{}", text);
+        assert!(text.contains("heading 90.00°"), "{}", text);
+        assert!(text.ends_with("fix 3D"), "{}", text);
+    }
+
+    #[test]
+    fn test_dataports_dotted_quad() {
+        let config = IpDataportsConfiguration::builder()
+            .udp_client(1, Ipv4Addr::new(192, 168, 1, 50), 16718)
+            .build()
+            .unwrap();
+        assert_eq!(config.to_string(), "Dataport 2: UdpClient 192.168.1.50:16718");
+    }
+
+    #[test]
+    fn test_timer_period_in_hz() {
+        let timer = PacketTimerPeriod {
+            permanent: true,
+            utc_synchronisation: false,
+            packet_timer_period: Duration::from_millis(1),
+        };
+        assert_eq!(timer.to_string(), "Packet timer 1000 µs (1000.0 Hz) (permanent)");
+        let packet = Packet::PacketTimerPeriod(timer.clone());
+        assert_eq!(packet.to_string(), timer.to_string());
+        assert_eq!(Packet::Unsupported { id: 250, payload: Payload::from_slice(&[0; 3]).unwrap() }.to_string(), "Unsupported packet 250 (3 bytes)");
+    }
+}
@@ -1,13 +1,22 @@
 
 use binrw::{BinRead, BinWrite};
+#[cfg(feature = "serde")]
 use serde::{Serialize, Deserialize};
+use std::net::Ipv4Addr;
+
 use crate::{Result, error::AnError};
 pub mod system;
 pub mod state;
 pub mod config;
+mod display;
+mod payload;
+
+pub use payload::Payload;
 
 /// ANPP packet identifier structure
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, BinRead, BinWrite, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, BinRead, BinWrite)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 #[brw(little)]
 pub struct PacketId {
     pub id: u8,
@@ -31,7 +40,9 @@ impl PacketId {
 }
 
 /// ANPP packet header structure
-#[derive(Debug, Clone, PartialEq, BinRead, BinWrite, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, BinRead, BinWrite)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 #[brw(little)]
 pub struct AnppHeader {
     pub header_lrc: u8,
@@ -40,40 +51,135 @@ pub struct AnppHeader {
     pub crc16: u16,
 }
 
+/// IPv4 address from its wire form. The device sends the address's
+/// big-endian value as a little-endian u32, so 192.168.1.1 arrives as bytes
+/// `01 01 A8 C0`. Every IP field is converted here and in [`ipv4_to_wire`].
+pub(crate) fn ipv4_from_wire(raw: u32) -> Ipv4Addr {
+    Ipv4Addr::from(raw)
+}
+
+/// Wire form of an IPv4 address, see [`ipv4_from_wire`]
+pub(crate) fn ipv4_to_wire(address: &Ipv4Addr) -> u32 {
+    u32::from(*address)
+}
+
 pub trait HasPacketId {
     const PACKET_ID: PacketId;
+
+    /// Borrow this type out of a decoded packet of the same kind
+    fn from_packet(packet: &Packet) -> Option<&Self>;
 }
 
 // Import packet types from their respective modules
 use system::{Acknowledge, Request, BootMode, DeviceInformation,
-            RestoreFactorySettings, Reset, IpConfiguration};
-use state::{SystemState, UnixTime, Status, PositionStdDev, VelocityStdDev,
+            RestoreFactorySettings, Reset, FileTransferRequest, FileTransferAcknowledge,
+            FileTransferData, SerialPortPassthrough, IpConfiguration, SubcomponentInformation};
+use state::{SystemState, UnixTime, FormattedTime, Status, PositionStdDev, VelocityStdDev,
             EulerOrientationStdDev, QuaternionOrientationStdDev,
-            RawSensors, RawGnss, Satellites,
+            RawSensors, RawGnss, Satellites, DetailedSatellites,
             GeodeticPosition, EcefPosition, UtmPosition, NedVelocity, BodyVelocity,
             Acceleration, BodyAcceleration, EulerOrientation, QuaternionOrientation,
             DcmOrientation, AngularVelocity, AngularAcceleration,
             ExternalPositionVelocity, ExternalPosition, ExternalVelocity,
             ExternalBodyVelocity, ExternalHeading,
-            RunningTime, ExternalTime, GeoidHeight, RtcmCorrections,
-            Heave, RawDvlData,
-            GnssReceiverInformation, SensorTemperature,
+            RunningTime, OdometerState, ExternalTime, ExternalDepth, GeoidHeight, RtcmCorrections,
+            Wind, Heave, AntennaOffsetEstimation, ExternalAirData, RawDvlData,
+            GnssReceiverInformation, NorthSeekingStatus, SensorTemperature,
             GnssPositionVelocityTime, GnssOrientation};
-use config::{PacketTimerPeriod, PacketsPeriod, InstallationAlignment,
+use config::{PacketTimerPeriod, PacketsPeriod, BaudRates, InstallationAlignment,
             FilterOptions, OdometerConfiguration, SetZeroOrientationAlignment,
             ReferencePointOffsets, DualAntennaConfiguration, UserData,
-            IpDataportsConfiguration};
+            GpioConfiguration, GpioOutputConfiguration, GpioInputConfiguration,
+            MagneticCalibrationValues, MagneticCalibrationConfiguration,
+            MagneticCalibrationStatus, IpDataportsConfiguration, VesselMotionConfiguration};
 
 macro_rules! define_packets {
-    ( $( $variant:ident => $code:expr, $length:expr ),+ $(,)? ) => {
+    ( $( $variant:ident => $code:expr, $fixed:ident $( ($len:literal) )?, $direction:ident $(, $min:literal ..= $max:literal )? ),+ $(,)? ) => {
         $(
             impl HasPacketId for $variant {
                 const PACKET_ID: PacketId = PacketId { id: $code };
+
+                fn from_packet(packet: &Packet) -> Option<&Self> {
+                    match packet {
+                        Packet::$variant(p) => Some(p),
+                        _ => None,
+                    }
+                }
+            }
+
+            $(
+                impl $variant {
+                    /// Payload length in bytes, from the packet table
+                    pub const LEN: usize = $len;
+                }
+            )?
+
+            impl $variant {
+                /// Serialize the payload without ANPP framing
+                pub fn to_payload_bytes(&self) -> Result<Vec<u8>> {
+                    use binrw::BinWrite;
+                    use std::io::Cursor;
+
+                    let mut cursor = Cursor::new(Vec::new());
+                    self.write_le(&mut cursor)
+                        .map_err(|source| AnError::Encode { packet: stringify!($variant), source })?;
+                    Ok(cursor.into_inner())
+                }
+
+                /// Serialize the payload into inline storage
+                pub(crate) fn to_payload(&self) -> Result<Payload> {
+                    use binrw::BinWrite;
+
+                    match Payload::write_with(|cursor| self.write_le(cursor)) {
+                        Ok(payload) => Ok(payload),
+                        // Out of room: report the length a frame would need
+                        Err(binrw::Error::Io(_)) => Err(AnError::PacketTooLong(self.to_payload_bytes()?.len())),
+                        Err(source) => Err(AnError::Encode { packet: stringify!($variant), source }),
+                    }
+                }
+            }
+
+            /// Take this type out of a reply, failing with
+            /// [`AnError::UnexpectedPacket`] when the reply is another kind
+            impl TryFrom<Packet> for $variant {
+                type Error = AnError;
+
+                fn try_from(packet: Packet) -> Result<Self> {
+                    match packet {
+                        Packet::$variant(p) => Ok(p),
+                        other => Err(AnError::UnexpectedPacket {
+                            expected: PacketKind::$variant,
+                            got: PacketKind::from(other.packet_id()),
+                        }),
+                    }
+                }
+            }
+
+            /// Decode an unframed payload. Fixed length packets must be
+            /// exactly their documented length.
+            impl TryFrom<&[u8]> for $variant {
+                type Error = AnError;
+
+                fn try_from(payload: &[u8]) -> Result<Self> {
+                    use binrw::BinRead;
+                    use std::io::Cursor;
+
+                    let expected: Option<usize> = $fixed $( ($len) )?;
+                    if let Some(expected) = expected
+                        && payload.len() != expected
+                    {
+                        return Err(AnError::LengthMismatch { id: $code, expected, got: payload.len() });
+                    }
+                    $variant::read_le(&mut Cursor::new(payload))
+                        .map_err(|source| AnError::Decode { packet: stringify!($variant), source })
+                }
             }
         )+
 
         /// Core enum that represents the packet kind
-        #[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+        #[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+        #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+        #[cfg_attr(feature = "defmt", derive(defmt::Format))]
         pub enum PacketKind {
             $( $variant, )+
             Unsupported,
@@ -83,11 +189,36 @@ macro_rules! define_packets {
             /// Get the expected byte length for this packet kind
             pub fn byte_length(&self) -> Option<usize> {
                 match self {
-                    $( PacketKind::$variant => $length, )+
+                    $( PacketKind::$variant => $fixed $( ($len) )?, )+
                     PacketKind::Unsupported => None,
                 }
             }
 
+            /// Shortest payload that can decode: the table's bound if it
+            /// has one, else the fixed length, else 0
+            pub fn min_length(&self) -> usize {
+                match self {
+                    $( PacketKind::$variant => {
+                        let bounds: Option<(usize, usize)> = None $( .or(Some(($min, $max))) )?;
+                        bounds.map(|(min, _)| min).or(self.byte_length()).unwrap_or(0)
+                    } )+
+                    PacketKind::Unsupported => 0,
+                }
+            }
+
+            /// Longest payload the packet's fields can take, including
+            /// fields newer firmware appends: the table's bound if it has
+            /// one, else the fixed length, else a whole frame
+            pub fn max_length(&self) -> usize {
+                match self {
+                    $( PacketKind::$variant => {
+                        let bounds: Option<(usize, usize)> = None $( .or(Some(($min, $max))) )?;
+                        bounds.map(|(_, max)| max).or(self.byte_length()).unwrap_or(crate::wire::MAX_PAYLOAD_LEN)
+                    } )+
+                    PacketKind::Unsupported => crate::wire::MAX_PAYLOAD_LEN,
+                }
+            }
+
             /// Get the packet ID for this packet kind
             pub fn packet_id(&self) -> u8 {
                 match self {
@@ -95,6 +226,35 @@ macro_rules! define_packets {
                     PacketKind::Unsupported => 0xFF,
                 }
             }
+
+            /// Every supported packet kind, ordered by ID
+            pub const ALL: &'static [PacketKind] = &[ $( PacketKind::$variant, )+ ];
+
+            /// Type name of the packet, e.g. `"SystemState"`
+            pub fn name(&self) -> &'static str {
+                match self {
+                    $( PacketKind::$variant => stringify!($variant), )+
+                    PacketKind::Unsupported => "Unsupported",
+                }
+            }
+
+            /// Whether the device sends this packet, accepts it, or both.
+            /// Unsupported packets are passed through in either direction.
+            pub fn direction(&self) -> PacketDirection {
+                match self {
+                    $( PacketKind::$variant => PacketDirection::$direction, )+
+                    PacketKind::Unsupported => PacketDirection::ReadWrite,
+                }
+            }
+        }
+
+        impl std::fmt::Display for Packet {
+            fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                match self {
+                    $( Packet::$variant(p) => p.fmt(f), )+
+                    Packet::Unsupported { id, payload } => write!(f, "Unsupported packet {} ({} bytes)", id, payload.len()),
+                }
+            }
         }
 
         impl From<u8> for PacketKind {
@@ -107,10 +267,14 @@ macro_rules! define_packets {
         }
 
         /// Packet enum — the single public type for all ANPP packets.
-        #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+        #[derive(Debug, Clone, PartialEq)]
+        #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+        #[cfg_attr(feature = "defmt", derive(defmt::Format))]
         pub enum Packet {
             $( $variant($variant), )+
-            Unsupported(Vec<u8>),
+            /// A packet this library has no type for, kept verbatim so it
+            /// can be forwarded unmodified
+            Unsupported { id: u8, payload: Payload },
         }
 
         impl Packet {
@@ -118,7 +282,7 @@ macro_rules! define_packets {
             pub fn packet_id(&self) -> u8 {
                 match self {
                     $( Packet::$variant(_) => $code, )+
-                    Packet::Unsupported(_) => 0xFF,
+                    Packet::Unsupported { id, .. } => *id,
                 }
             }
 
@@ -132,119 +296,365 @@ macro_rules! define_packets {
                         PacketKind::$variant => {
                             let mut cursor = Cursor::new(data);
                             Packet::$variant($variant::read_le(&mut cursor)
-                                .map_err(|e| AnError::InvalidPacket(format!("Failed to deserialize {}: {}", stringify!($variant), e)))?)
+                                .map_err(|source| AnError::Decode { packet: stringify!($variant), source })?)
                         },
                     )+
-                    PacketKind::Unsupported => Packet::Unsupported(data.to_vec()),
+                    PacketKind::Unsupported => Packet::Unsupported { id: packet_id, payload: Payload::from_slice(data)? },
                 };
                 Ok(packet)
             }
 
             /// Serialize just the payload (no ANPP framing)
             pub(crate) fn payload_bytes(&self) -> crate::Result<Vec<u8>> {
-                use binrw::BinWrite;
-                use std::io::Cursor;
+                self.payload().map(Vec::from)
+            }
 
+            /// Serialize just the payload into inline storage
+            pub(crate) fn payload(&self) -> crate::Result<Payload> {
                 match self {
-                    $(
-                        Packet::$variant(p) => {
-                            let mut cursor = Cursor::new(Vec::new());
-                            p.write_le(&mut cursor)
-                                .map_err(|e| crate::error::AnError::InvalidPacket(format!("Failed to serialize {}: {}", stringify!($variant), e)))?;
-                            Ok(cursor.into_inner())
-                        },
-                    )+
-                    Packet::Unsupported(data) => Ok(data.clone()),
+                    $( Packet::$variant(p) => p.to_payload(), )+
+                    Packet::Unsupported { payload, .. } => Ok(payload.clone()),
                 }
             }
         }
+
+        /// One test per fixed length packet, so a field added or removed
+        /// without updating the packet table fails under its own name
+        #[cfg(test)]
+        #[allow(non_snake_case)]
+        mod fixed_length {
+            use super::*;
+            $( $(
+                #[test]
+                fn $variant() {
+                    check_fixed_length::<$variant>($len);
+                }
+            )? )+
+        }
     };
 }
 
+// Name => ID, documented payload length (None if variable), direction and,
+// where they differ from the documented length, the shortest and longest
+// payloads the fields decode from
 define_packets!(
     // System Packets (0-14)
-    Acknowledge => 0, Some(4),
-    Request => 1, Some(1),
-    BootMode => 2, Some(1),
-    DeviceInformation => 3, Some(24),
-    RestoreFactorySettings => 4, Some(4),
-    Reset => 5, Some(4),
-    IpConfiguration => 11, Some(30),
+    Acknowledge => 0, Some(4), ReadOnly,
+    Request => 1, None, WriteOnly,
+    BootMode => 2, Some(1), ReadWrite,
+    DeviceInformation => 3, Some(24), ReadOnly,
+    RestoreFactorySettings => 4, Some(4), WriteOnly,
+    Reset => 5, Some(4), WriteOnly,
+    FileTransferRequest => 6, None, ReadWrite, 13..=255,
+    FileTransferAcknowledge => 7, Some(9), ReadWrite,
+    FileTransferData => 8, None, ReadWrite, 9..=255,
+    SerialPortPassthrough => 10, None, ReadWrite, 1..=255,
+    IpConfiguration => 11, Some(30), ReadWrite,
+    SubcomponentInformation => 14, None, ReadOnly,
 
     // State Packets (20-93)
-    SystemState => 20, Some(100),
-    UnixTime => 21, Some(8),
-    Status => 23, Some(4),
-    PositionStdDev => 24, Some(12),
-    VelocityStdDev => 25, Some(12),
-    EulerOrientationStdDev => 26, Some(12),
-    QuaternionOrientationStdDev => 27, Some(16),
-    RawSensors => 28, Some(48),
-    RawGnss => 29, Some(74),
-    Satellites => 30, Some(13),
-GeodeticPosition => 32, Some(24),
-    EcefPosition => 33, Some(24),
-    UtmPosition => 34, Some(26),
-    NedVelocity => 35, Some(12),
-    BodyVelocity => 36, Some(12),
-    Acceleration => 37, Some(12),
-    BodyAcceleration => 38, Some(16),
-    EulerOrientation => 39, Some(12),
-    QuaternionOrientation => 40, Some(16),
-    DcmOrientation => 41, Some(36),
-    AngularVelocity => 42, Some(12),
-    AngularAcceleration => 43, Some(12),
-    ExternalPositionVelocity => 44, Some(60),
-    ExternalPosition => 45, Some(36),
-    ExternalVelocity => 46, Some(24),
-    ExternalBodyVelocity => 47, Some(16),
-    ExternalHeading => 48, Some(8),
-    RunningTime => 49, Some(8),
-    ExternalTime => 52, Some(8),
-    GeoidHeight => 54, Some(4),
-    RtcmCorrections => 55, None,
-    Heave => 58, Some(16),
-    GnssReceiverInformation => 69, Some(68),
-    RawDvlData => 70, Some(60),
-    SensorTemperature => 85, Some(32),
-    GnssPositionVelocityTime => 92, Some(76),
-    GnssOrientation => 93, Some(36),
+    SystemState => 20, Some(100), ReadOnly, 100..=255,
+    UnixTime => 21, Some(8), ReadOnly,
+    FormattedTime => 22, Some(14), ReadOnly,
+    Status => 23, Some(4), ReadOnly, 4..=255,
+    PositionStdDev => 24, Some(12), ReadOnly,
+    VelocityStdDev => 25, Some(12), ReadOnly,
+    EulerOrientationStdDev => 26, Some(12), ReadOnly,
+    QuaternionOrientationStdDev => 27, Some(16), ReadOnly,
+    RawSensors => 28, Some(48), ReadOnly,
+    RawGnss => 29, Some(74), ReadOnly,
+    Satellites => 30, Some(13), ReadOnly,
+    DetailedSatellites => 31, None, ReadOnly,
+    GeodeticPosition => 32, Some(24), ReadOnly,
+    EcefPosition => 33, Some(24), ReadOnly,
+    UtmPosition => 34, Some(26), ReadOnly,
+    NedVelocity => 35, Some(12), ReadOnly,
+    BodyVelocity => 36, Some(12), ReadOnly,
+    Acceleration => 37, Some(12), ReadOnly,
+    BodyAcceleration => 38, Some(16), ReadOnly,
+    EulerOrientation => 39, Some(12), ReadOnly,
+    QuaternionOrientation => 40, Some(16), ReadOnly,
+    DcmOrientation => 41, Some(36), ReadOnly,
+    AngularVelocity => 42, Some(12), ReadOnly,
+    AngularAcceleration => 43, Some(12), ReadOnly,
+    ExternalPositionVelocity => 44, Some(60), ReadWrite,
+    ExternalPosition => 45, Some(36), WriteOnly,
+    ExternalVelocity => 46, Some(24), WriteOnly,
+    ExternalBodyVelocity => 47, Some(16), WriteOnly,
+    ExternalHeading => 48, Some(8), WriteOnly,
+    RunningTime => 49, Some(8), ReadOnly,
+    OdometerState => 51, Some(20), ReadOnly,
+    ExternalTime => 52, Some(8), WriteOnly,
+    ExternalDepth => 53, Some(8), WriteOnly,
+    GeoidHeight => 54, Some(4), ReadOnly,
+    RtcmCorrections => 55, None, WriteOnly,
+    Wind => 57, Some(12), ReadWrite,
+    Heave => 58, Some(16), ReadOnly,
+    AntennaOffsetEstimation => 62, Some(28), ReadWrite,
+    ExternalAirData => 68, Some(25), WriteOnly,
+    GnssReceiverInformation => 69, Some(68), ReadOnly,
+    RawDvlData => 70, Some(60), ReadOnly,
+    NorthSeekingStatus => 78, Some(28), ReadOnly,
+    SensorTemperature => 85, Some(32), ReadOnly,
+    GnssPositionVelocityTime => 92, Some(76), ReadOnly,
+    GnssOrientation => 93, Some(36), ReadOnly,
 
     // Configuration Packets (180-203)
-    PacketTimerPeriod => 180, Some(4),
-    PacketsPeriod => 181, None,
-    InstallationAlignment => 185, Some(73),
-    FilterOptions => 186, Some(17),
-    OdometerConfiguration => 192, Some(8),
-    SetZeroOrientationAlignment => 193, Some(1),
-    ReferencePointOffsets => 194, Some(49),
-    DualAntennaConfiguration => 196, Some(17),
-    UserData => 198, Some(64),
-    IpDataportsConfiguration => 202, Some(30),
+    PacketTimerPeriod => 180, Some(4), ReadWrite,
+    PacketsPeriod => 181, None, ReadWrite, 2..=255,
+    BaudRates => 182, Some(17), ReadWrite,
+    InstallationAlignment => 185, Some(73), ReadWrite,
+    FilterOptions => 186, Some(17), ReadWrite,
+    GpioConfiguration => 188, Some(13), ReadWrite,
+    MagneticCalibrationValues => 189, Some(49), ReadWrite,
+    MagneticCalibrationConfiguration => 190, Some(1), WriteOnly,
+    MagneticCalibrationStatus => 191, Some(3), ReadOnly,
+    OdometerConfiguration => 192, Some(8), ReadWrite,
+    SetZeroOrientationAlignment => 193, Some(1), WriteOnly,
+    ReferencePointOffsets => 194, Some(49), ReadWrite,
+    GpioOutputConfiguration => 195, Some(33), ReadWrite,
+    DualAntennaConfiguration => 196, Some(17), ReadWrite,
+    UserData => 198, Some(64), ReadWrite,
+    GpioInputConfiguration => 199, Some(65), ReadWrite,
+    IpDataportsConfiguration => 202, Some(30), ReadWrite, 30..=255,
+    VesselMotionConfiguration => 205, Some(16), ReadWrite,
 );
 
+/// Which way a packet travels, from the host's point of view
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum PacketDirection {
+    /// Sent by the device only
+    ReadOnly,
+    /// Accepted by the device only
+    WriteOnly,
+    /// Sent and accepted by the device
+    ReadWrite,
+}
+
+impl PacketDirection {
+    /// The device sends this packet, on request or as a stream
+    pub fn is_readable(&self) -> bool {
+        matches!(self, PacketDirection::ReadOnly | PacketDirection::ReadWrite)
+    }
+
+    /// The device accepts this packet from the host
+    pub fn is_writable(&self) -> bool {
+        matches!(self, PacketDirection::WriteOnly | PacketDirection::ReadWrite)
+    }
+}
+
+impl std::fmt::Display for PacketDirection {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            PacketDirection::ReadOnly => "Read only",
+            PacketDirection::WriteOnly => "Write only",
+            PacketDirection::ReadWrite => "Read/Write",
+        })
+    }
+}
+
+/// One row of the packet [`registry`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct PacketInfo {
+    pub kind: PacketKind,
+    pub id: u8,
+    pub name: &'static str,
+    /// Payload length, `None` for variable length packets
+    pub length: Option<usize>,
+    pub direction: PacketDirection,
+}
+
+impl From<PacketKind> for PacketInfo {
+    fn from(kind: PacketKind) -> Self {
+        Self {
+            kind,
+            id: kind.id(),
+            name: kind.name(),
+            length: kind.byte_length(),
+            direction: kind.direction(),
+        }
+    }
+}
+
+/// Every supported packet with its ID, name, length and direction, ordered
+/// by ID, for documentation and UI tools
+///
+/// ```
+/// let info = liban::packet::registry().find(|p| p.name == "SystemState").unwrap();
+/// assert_eq!((info.id, info.length), (20, Some(100)));
+/// ```
+pub fn registry() -> impl Iterator<Item = PacketInfo> {
+    PacketKind::all().map(PacketInfo::from)
+}
+
+impl PacketKind {
+    /// Iterate over every supported packet kind, ordered by ID
+    pub fn all() -> impl Iterator<Item = PacketKind> {
+        Self::ALL.iter().copied()
+    }
+
+    /// Packet ID, the same as [`PacketKind::packet_id`]
+    pub fn id(&self) -> u8 {
+        self.packet_id()
+    }
+
+    /// Fixed length packets that newer firmware may extend with trailing
+    /// fields, kept in their extension field. These have a
+    /// [`max_length`](Self::max_length) beyond their documented length.
+    pub fn is_extensible(&self) -> bool {
+        self.byte_length().is_some_and(|len| self.max_length() > len)
+    }
+}
+
 impl Packet {
+    /// Whether this is a write the device stores in flash and keeps across
+    /// power cycles
+    pub fn is_permanent(&self) -> bool {
+        match self {
+            Packet::IpConfiguration(p) => p.permanent,
+            Packet::PacketTimerPeriod(p) => p.permanent,
+            Packet::PacketsPeriod(p) => p.permanent,
+            Packet::BaudRates(p) => p.permanent,
+            Packet::InstallationAlignment(p) => p.permanent,
+            Packet::FilterOptions(p) => p.permanent,
+            Packet::GpioConfiguration(p) => p.permanent,
+            Packet::MagneticCalibrationValues(p) => p.permanent,
+            Packet::OdometerConfiguration(p) => p.permanent,
+            Packet::SetZeroOrientationAlignment(p) => p.permanent,
+            Packet::ReferencePointOffsets(p) => p.permanent,
+            Packet::GpioOutputConfiguration(p) => p.permanent,
+            Packet::DualAntennaConfiguration(p) => p.permanent,
+            Packet::GpioInputConfiguration(p) => p.permanent,
+            Packet::VesselMotionConfiguration(p) => p.permanent,
+            _ => false,
+        }
+    }
+
+    /// Set the `permanent` flag, returning false for packets without one
+    pub fn set_permanent(&mut self, permanent: bool) -> bool {
+        match self {
+            Packet::IpConfiguration(p) => p.permanent = permanent,
+            Packet::PacketTimerPeriod(p) => p.permanent = permanent,
+            Packet::PacketsPeriod(p) => p.permanent = permanent,
+            Packet::BaudRates(p) => p.permanent = permanent,
+            Packet::InstallationAlignment(p) => p.permanent = permanent,
+            Packet::FilterOptions(p) => p.permanent = permanent,
+            Packet::GpioConfiguration(p) => p.permanent = permanent,
+            Packet::MagneticCalibrationValues(p) => p.permanent = permanent,
+            Packet::OdometerConfiguration(p) => p.permanent = permanent,
+            Packet::SetZeroOrientationAlignment(p) => p.permanent = permanent,
+            Packet::ReferencePointOffsets(p) => p.permanent = permanent,
+            Packet::GpioOutputConfiguration(p) => p.permanent = permanent,
+            Packet::DualAntennaConfiguration(p) => p.permanent = permanent,
+            Packet::GpioInputConfiguration(p) => p.permanent = permanent,
+            Packet::VesselMotionConfiguration(p) => p.permanent = permanent,
+            _ => return false,
+        }
+        true
+    }
+
     /// Convert packet to wire format bytes ready to send (with ANPP framing)
     pub fn to_bytes(&self) -> crate::Result<Vec<u8>> {
-        match self {
-            Packet::Request(_) | Packet::BootMode(_) |
-            Packet::RestoreFactorySettings(_) | Packet::Reset(_) |
-            Packet::IpConfiguration(_) |
-            Packet::ExternalPositionVelocity(_) | Packet::ExternalPosition(_) |
-            Packet::ExternalVelocity(_) | Packet::ExternalBodyVelocity(_) |
-            Packet::ExternalHeading(_) | Packet::ExternalTime(_) |
-            Packet::RtcmCorrections(_) |
-            Packet::PacketTimerPeriod(_) | Packet::PacketsPeriod(_) |
-            Packet::InstallationAlignment(_) | Packet::FilterOptions(_) |
-            Packet::OdometerConfiguration(_) | Packet::SetZeroOrientationAlignment(_) |
-            Packet::ReferencePointOffsets(_) | Packet::DualAntennaConfiguration(_) |
-            Packet::UserData(_) |
-            Packet::IpDataportsConfiguration(_) => {
-                let packet_id = PacketId::new(self.packet_id());
-                let data = self.payload_bytes()?;
-                crate::protocol::AnppProtocol::get_packet_bytes(packet_id, &data)
-            }
-            _ => Err(crate::error::AnError::InvalidPacket("Cannot send read-only or unsupported packet types".to_string())),
+        if !PacketKind::from(self.packet_id()).direction().is_writable() {
+            return Err(crate::error::AnError::NotWritable { id: self.packet_id() });
         }
+        crate::wire::EncodedFrame::encode(self).map(|frame| frame.to_vec())
     }
 }
+
+/// Decode random payloads of the table length and check that the packet
+/// reads and writes exactly that many bytes
+#[cfg(test)]
+fn check_fixed_length<T>(len: usize)
+where
+    T: HasPacketId + BinRead + BinWrite + std::fmt::Debug,
+    for<'a> <T as BinRead>::Args<'a>: Default,
+    for<'a> <T as BinWrite>::Args<'a>: Default,
+{
+    use crate::test_support::{Rng, random_payload};
+    use std::io::Cursor;
+
+    let kind = T::PACKET_ID.packet_type();
+    assert_eq!(kind.byte_length(), Some(len));
+    let mut rng = Rng::new(u64::from(kind.packet_id()));
+    for _ in 0..100 {
+        let payload = random_payload(kind, &mut rng);
+        let mut cursor = Cursor::new(&payload[..]);
+        // Some payloads fail validation of enum or flag fields; try another
+        let Ok(value) = T::read_le_args(&mut cursor, Default::default()) else { continue };
+        assert_eq!(cursor.position() as usize, len, "{kind:?} decodes from {} bytes, the table says {len}", cursor.position());
+
+        let mut encoded = Cursor::new(Vec::new());
+        value.write_le_args(&mut encoded, Default::default()).unwrap();
+        assert_eq!(encoded.into_inner().len(), len, "{kind:?} encodes to a different length than the table's {len}: {value:?}");
+        return;
+    }
+    panic!("{kind:?} does not decode from {len} bytes; is the packet table length out of date?");
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_len_consts_match_table() {
+        assert_eq!(SystemState::LEN, 100);
+        assert_eq!(PacketKind::RawSensors.byte_length(), Some(RawSensors::LEN));
+        assert_eq!(PacketKind::FilterOptions.byte_length(), Some(FilterOptions::LEN));
+    }
+
+    #[test]
+    fn test_all_kinds_are_ordered_and_distinct() {
+        let ids: Vec<u8> = PacketKind::all().map(|kind| kind.id()).collect();
+        assert!(ids.windows(2).all(|w| w[0] < w[1]));
+        assert!(!PacketKind::ALL.contains(&PacketKind::Unsupported));
+        // Every ID the decoder recognises is listed, and nothing else
+        let known = (0..=u8::MAX).filter(|id| PacketKind::from(*id) != PacketKind::Unsupported).count();
+        assert_eq!(ids.len(), known);
+        for kind in PacketKind::all() {
+            assert_eq!(PacketKind::from(kind.id()), kind);
+            assert_eq!(kind.name(), format!("{:?}", kind));
+        }
+    }
+
+    #[test]
+    fn test_registry() {
+        let rows: Vec<PacketInfo> = registry().collect();
+        assert_eq!(rows.len(), PacketKind::ALL.len());
+        let heave = rows.iter().find(|row| row.kind == PacketKind::Heave).unwrap();
+        assert_eq!((heave.id, heave.name, heave.length), (58, "Heave", Some(16)));
+        assert_eq!(heave.direction, PacketDirection::ReadOnly);
+        let periods = PacketInfo::from(PacketKind::PacketsPeriod);
+        assert_eq!((periods.length, periods.direction), (None, PacketDirection::ReadWrite));
+        assert_eq!(PacketKind::Reset.direction().to_string(), "Write only");
+    }
+
+    #[test]
+    fn test_to_bytes_follows_direction() {
+        assert!(Packet::Reset(system::Reset {}).to_bytes().is_ok());
+        let err = Packet::RunningTime(RunningTime { seconds: 1, microseconds: 0 }).to_bytes();
+        assert!(matches!(err, Err(AnError::NotWritable { id: 49 })));
+        assert!(Packet::Unsupported { id: 240, payload: Payload::from_slice(&[1, 2]).unwrap() }.to_bytes().is_ok());
+    }
+
+    #[test]
+    fn test_try_from_reply_checks_kind() {
+        let time = RunningTime { seconds: 7, microseconds: 0 };
+        assert_eq!(RunningTime::try_from(Packet::RunningTime(time.clone())).unwrap(), time);
+        let err = system::Reset::try_from(Packet::RunningTime(time)).unwrap_err();
+        assert!(matches!(
+            err,
+            AnError::UnexpectedPacket { expected: PacketKind::Reset, got: PacketKind::RunningTime }
+        ));
+        assert_eq!(err.code(), "unexpected_packet");
+        assert_eq!(err.to_string(), "Unexpected packet: expected Reset, got RunningTime");
+    }
+}
+
+#[cfg(test)]
+#[path = "tests/flags.rs"]
+mod flag_bit_tests;
@@ -0,0 +1,195 @@
+use std::fmt;
+use std::io::Cursor;
+use std::ops::Deref;
+
+use crate::error::{AnError, Result};
+use crate::wire::MAX_PAYLOAD_LEN;
+
+/// Packet payload stored inline, without a heap allocation
+///
+/// An ANPP frame carries at most 255 payload bytes, so every payload fits in
+/// a fixed array. Encoding into a `Payload` and keeping unsupported packets
+/// in one avoids allocating per frame. It dereferences to `[u8]`; use
+/// [`as_slice`](Self::as_slice) or [`to_vec`](Self::to_vec) where a slice or
+/// `Vec` was used before.
+///
+/// ```
+/// use liban::wire::Payload;
+///
+/// let payload = Payload::from_slice(&[1, 2, 3]).unwrap();
+/// assert_eq!(payload.as_slice(), &[1, 2, 3]);
+/// assert_eq!(payload.len(), 3);
+/// assert!(Payload::from_slice(&[0; 256]).is_err());
+/// ```
+#[derive(Clone)]
+pub struct Payload {
+    len: u8,
+    bytes: [u8; MAX_PAYLOAD_LEN],
+}
+
+impl Payload {
+    /// Empty payload
+    pub const fn new() -> Self {
+        Self { len: 0, bytes: [0; MAX_PAYLOAD_LEN] }
+    }
+
+    /// Copy a payload, failing if it is longer than a frame can carry
+    pub fn from_slice(bytes: &[u8]) -> Result<Self> {
+        if bytes.len() > MAX_PAYLOAD_LEN {
+            return Err(AnError::PacketTooLong(bytes.len()));
+        }
+        let mut payload = Self::new();
+        payload.bytes[..bytes.len()].copy_from_slice(bytes);
+        payload.len = bytes.len() as u8;
+        Ok(payload)
+    }
+
+    /// Fill the payload through a cursor over its storage. Writes past the
+    /// end fail with an I/O error, which `write` passes on.
+    pub(crate) fn write_with<E>(write: impl FnOnce(&mut Cursor<&mut [u8]>) -> std::result::Result<(), E>) -> std::result::Result<Self, E> {
+        let mut payload = Self::new();
+        let mut cursor = Cursor::new(&mut payload.bytes[..]);
+        write(&mut cursor)?;
+        payload.len = cursor.position() as u8;
+        Ok(payload)
+    }
+
+    pub fn as_slice(&self) -> &[u8] {
+        &self.bytes[..self.len as usize]
+    }
+
+    pub fn len(&self) -> usize {
+        self.len as usize
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+}
+
+impl Default for Payload {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Deref for Payload {
+    type Target = [u8];
+
+    fn deref(&self) -> &[u8] {
+        self.as_slice()
+    }
+}
+
+impl AsRef<[u8]> for Payload {
+    fn as_ref(&self) -> &[u8] {
+        self.as_slice()
+    }
+}
+
+/// Formats like the byte slice, as `Vec<u8>` did
+impl fmt::Debug for Payload {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.as_slice().fmt(f)
+    }
+}
+
+impl PartialEq for Payload {
+    fn eq(&self, other: &Self) -> bool {
+        self.as_slice() == other.as_slice()
+    }
+}
+
+impl Eq for Payload {}
+
+impl PartialEq<[u8]> for Payload {
+    fn eq(&self, other: &[u8]) -> bool {
+        self.as_slice() == other
+    }
+}
+
+impl PartialEq<Vec<u8>> for Payload {
+    fn eq(&self, other: &Vec<u8>) -> bool {
+        self.as_slice() == other.as_slice()
+    }
+}
+
+impl TryFrom<&[u8]> for Payload {
+    type Error = AnError;
+
+    fn try_from(bytes: &[u8]) -> Result<Self> {
+        Self::from_slice(bytes)
+    }
+}
+
+impl TryFrom<Vec<u8>> for Payload {
+    type Error = AnError;
+
+    fn try_from(bytes: Vec<u8>) -> Result<Self> {
+        Self::from_slice(&bytes)
+    }
+}
+
+impl From<Payload> for Vec<u8> {
+    fn from(payload: Payload) -> Self {
+        payload.to_vec()
+    }
+}
+
+/// Same representation as `Vec<u8>`
+#[cfg(feature = "serde")]
+impl serde::Serialize for Payload {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+        self.as_slice().serialize(serializer)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for Payload {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> std::result::Result<Self, D::Error> {
+        let bytes = Vec::<u8>::deserialize(deserializer)?;
+        Self::from_slice(&bytes).map_err(serde::de::Error::custom)
+    }
+}
+
+#[cfg(feature = "defmt")]
+impl defmt::Format for Payload {
+    fn format(&self, f: defmt::Formatter) {
+        defmt::write!(f, "{=[u8]:02x}", self.as_slice())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+
+    #[test]
+    fn test_write_with_stops_at_capacity() {
+        let payload = Payload::write_with(|cursor| cursor.write_all(&[7; 10])).unwrap();
+        assert_eq!(payload, vec![7; 10]);
+
+        let full = Payload::write_with(|cursor| cursor.write_all(&[1; MAX_PAYLOAD_LEN])).unwrap();
+        assert_eq!(full.len(), MAX_PAYLOAD_LEN);
+        assert!(Payload::write_with(|cursor| cursor.write_all(&[1; MAX_PAYLOAD_LEN + 1])).is_err());
+    }
+
+    #[test]
+    fn test_equality_ignores_unused_storage() {
+        let mut longer = Payload::from_slice(&[1, 2, 3]).unwrap();
+        longer.len = 2;
+        assert_eq!(longer, Payload::from_slice(&[1, 2]).unwrap());
+        assert_eq!(format!("{longer:?}"), "[1, 2]");
+        assert_eq!(Vec::from(longer), vec![1, 2]);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_serde_matches_vec() {
+        let payload = Payload::from_slice(&[1, 2, 3]).unwrap();
+        let json = serde_json::to_string(&payload).unwrap();
+        assert_eq!(json, serde_json::to_string(&vec![1u8, 2, 3]).unwrap());
+        assert_eq!(serde_json::from_str::<Payload>(&json).unwrap(), payload);
+        assert!(serde_json::from_str::<Payload>(&serde_json::to_string(&vec![0u8; 256]).unwrap()).is_err());
+    }
+}
@@ -1,12 +1,24 @@
 use binrw::{binrw, BinRead, BinWrite};
+#[cfg(feature = "serde")]
 use serde::{Serialize, Deserialize};
 
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use crate::alarms::HealthReport;
+use crate::angle::{normalize_2pi, normalize_pi, shortest_angular_distance};
+use crate::error::AnError;
+use crate::packet::config::{HeavePointId, OffsetVector};
+use crate::time_utils::{GpsTime, LeapSecondTable};
+use crate::units::{Angle, Length, Ned, Radians, LinearAcceleration, RotationalAcceleration, Velocity};
+
 // ===========================================================================
 // Enums and Status Types
 // ===========================================================================
 
 /// GNSS fix type enumeration
-#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub enum GnssFixType {
     #[default]
     NoFix = 0,
@@ -36,7 +48,9 @@ impl From<u8> for GnssFixType {
 }
 
 /// Spoofing status for GNSS packets
-#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub enum SpoofingStatus {
     #[default]
     Unknown = 0,
@@ -58,7 +72,9 @@ impl From<u8> for SpoofingStatus {
 }
 
 /// Interference status for GNSS packets
-#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub enum InterferenceStatus {
     #[default]
     Unknown = 0,
@@ -80,7 +96,9 @@ impl From<u8> for InterferenceStatus {
 }
 
 /// System status bitfield
-#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, BinRead, BinWrite, Serialize, Deserialize)]
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, BinRead, BinWrite)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 #[brw(little)]
 pub struct SystemStatus(u16);
 
@@ -109,7 +127,9 @@ impl From<u16> for SystemStatus {
 }
 
 /// Filter status bitfield
-#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, BinRead, BinWrite, Serialize, Deserialize)]
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, BinRead, BinWrite)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 #[brw(little)]
 pub struct FilterStatus(u16);
 
@@ -129,14 +149,67 @@ impl FilterStatus {
     pub fn external_position_active(&self) -> bool { self.0 & (1 << 13) != 0 }
     pub fn external_velocity_active(&self) -> bool { self.0 & (1 << 14) != 0 }
     pub fn external_heading_active(&self) -> bool { self.0 & (1 << 15) != 0 }
+
+    /// Where the filter's heading currently comes from, or `None` until the
+    /// heading is initialised. An aiding source flagged active wins, in the
+    /// order external, dual antenna, velocity; with none flagged the heading
+    /// is the inertial solution's own.
+    pub fn heading_source(&self) -> Option<HeadingSource> {
+        if !self.heading_initialised() {
+            None
+        } else if self.external_heading_active() {
+            Some(HeadingSource::External)
+        } else if self.dual_antenna_heading_active() {
+            Some(HeadingSource::DualAntenna)
+        } else if self.velocity_heading_enabled() {
+            Some(HeadingSource::Velocity)
+        } else {
+            Some(HeadingSource::Gyrocompass)
+        }
+    }
+
+    /// Event inputs flagged as triggered since the previous System State
+    pub fn events(&self) -> impl Iterator<Item = EventId> + use<> {
+        let flags = [(EventId::Event1, self.event1_flag()), (EventId::Event2, self.event2_flag())];
+        flags.into_iter().filter_map(|(event, flagged)| flagged.then_some(event))
+    }
+}
+
+/// GPIO event input, wired through
+/// [`GpioFunction::Event1Input`](super::config::GpioFunction::Event1Input) or
+/// [`GpioFunction::Event2Input`](super::config::GpioFunction::Event2Input)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum EventId {
+    Event1,
+    Event2,
 }
 
 impl From<u16> for FilterStatus {
     fn from(v: u16) -> Self { Self(v) }
 }
 
+/// Source of the filter heading, from [`FilterStatus::heading_source`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum HeadingSource {
+    /// No aiding flagged: gyrocompassing on north-seeking units, otherwise
+    /// the inertial solution held from its last aiding
+    Gyrocompass,
+    /// Dual antenna GNSS heading
+    DualAntenna,
+    /// Heading derived from the velocity vector
+    Velocity,
+    /// External Heading packets
+    External,
+}
+
 /// GNSS PVT status bitfield
-#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, BinRead, BinWrite, Serialize, Deserialize)]
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, BinRead, BinWrite)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 #[brw(little)]
 pub struct GnssPvtStatus(u16);
 
@@ -157,7 +230,9 @@ impl From<u16> for GnssPvtStatus {
 }
 
 /// GNSS Orientation status bitfield
-#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, BinRead, BinWrite, Serialize, Deserialize)]
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, BinRead, BinWrite)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 #[brw(little)]
 pub struct GnssOrientationStatus(u16);
 
@@ -175,12 +250,59 @@ impl From<u16> for GnssOrientationStatus {
     fn from(v: u16) -> Self { Self(v) }
 }
 
+/// Fields appended to a packet by firmware newer than this library.
+///
+/// Only populated when the parser's [`ProtocolProfile`](crate::profile::ProtocolProfile)
+/// tolerates trailing extension bytes. The bytes are kept verbatim so they
+/// survive a round trip and can be decoded once their layout is known.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct ExtensionFields {
+    pub bytes: Vec<u8>,
+}
+
+impl ExtensionFields {
+    /// Decode a little-endian value at `offset` into the extension bytes
+    pub fn read<T>(&self, offset: usize) -> Option<T>
+    where
+        T: for<'a> BinRead<Args<'a> = ()>,
+    {
+        let mut cursor = std::io::Cursor::new(self.bytes.get(offset..)?);
+        T::read_le(&mut cursor).ok()
+    }
+
+    pub fn len(&self) -> usize {
+        self.bytes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.bytes.is_empty()
+    }
+}
+
+/// Fields appended to [`SystemState`] by newer firmware
+pub type SystemStateExt = ExtensionFields;
+
+/// Fields appended to [`Status`] by newer firmware
+pub type StatusExt = ExtensionFields;
+
+pub(crate) fn extension_from_bytes(bytes: Vec<u8>) -> Option<ExtensionFields> {
+    (!bytes.is_empty()).then_some(ExtensionFields { bytes })
+}
+
+pub(crate) fn extension_to_bytes(extension: &Option<ExtensionFields>) -> Vec<u8> {
+    extension.as_ref().map(|e| e.bytes.clone()).unwrap_or_default()
+}
+
 // ===========================================================================
 // Packet Structs
 // ===========================================================================
 
 /// System state packet (Packet ID 20, Length 100) - Read only
-#[derive(Debug, Clone, PartialEq, BinRead, BinWrite, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, BinRead, BinWrite)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 #[brw(little)]
 pub struct SystemState {
     pub system_status: SystemStatus,
@@ -197,35 +319,303 @@ pub struct SystemState {
     pub body_acceleration_y: f32,
     pub body_acceleration_z: f32,
     pub g_force: f32,
-    pub roll: f32,
-    pub pitch: f32,
-    pub heading: f32,
+    pub roll: Radians,
+    pub pitch: Radians,
+    pub heading: Radians,
     pub angular_velocity_x: f32,
     pub angular_velocity_y: f32,
     pub angular_velocity_z: f32,
     pub latitude_std_dev: f32,
     pub longitude_std_dev: f32,
     pub height_std_dev: f32,
+    /// Fields beyond the documented 100 bytes, if any
+    #[br(parse_with = binrw::helpers::until_eof, map = extension_from_bytes)]
+    #[bw(map = extension_to_bytes)]
+    #[cfg_attr(feature = "serde", serde(default, skip_serializing_if = "Option::is_none"))]
+    pub extension: Option<SystemStateExt>,
+}
+
+fn unix_time(seconds: u32, microseconds: u32) -> SystemTime {
+    UNIX_EPOCH + Duration::from_secs(seconds as u64) + Duration::from_micros(microseconds as u64)
+}
+
+impl SystemState {
+    /// Active alarms ranked by severity
+    pub fn health(&self) -> HealthReport {
+        HealthReport::from(self)
+    }
+
+    /// Time of validity of the solution
+    pub fn timestamp(&self) -> SystemTime {
+        unix_time(self.unix_time_seconds, self.microseconds)
+    }
+
+    pub fn latitude_angle(&self) -> Angle {
+        Angle::from_radians(self.latitude)
+    }
+
+    pub fn longitude_angle(&self) -> Angle {
+        Angle::from_radians(self.longitude)
+    }
+
+    /// Height above the ellipsoid
+    pub fn height_length(&self) -> Length {
+        Length::from_meters(self.height)
+    }
+
+    /// Velocity as north, east, down
+    pub fn velocity_ned(&self) -> [Velocity; 3] {
+        self.ned_velocity().into()
+    }
+
+    /// Velocity as a north, east, down vector
+    pub fn ned_velocity(&self) -> Ned<Velocity> {
+        Ned::new(self.velocity_north, self.velocity_east, self.velocity_down)
+            .map(|v| Velocity::from_meters_per_second(v as f64))
+    }
+
+    /// Horizontal speed over ground
+    pub fn ground_speed(&self) -> Velocity {
+        self.ned_velocity().horizontal()
+    }
+
+    /// Direction of travel, clockwise from true north. Meaningless when
+    /// [`ground_speed`](Self::ground_speed) is near zero.
+    pub fn course_over_ground(&self) -> Angle {
+        self.ned_velocity().course()
+    }
+
+    /// Climb rate, positive upward
+    pub fn vertical_speed(&self) -> Velocity {
+        self.ned_velocity().vertical()
+    }
+
+    /// Combined latitude and longitude standard deviation
+    pub fn horizontal_position_std(&self) -> Length {
+        Length::from_meters((self.latitude_std_dev as f64).hypot(self.longitude_std_dev as f64))
+    }
+
+    /// Roll wrapped into (-π, π]
+    pub fn roll_angle(&self) -> Angle {
+        normalize_pi(self.roll).angle()
+    }
+
+    /// Pitch wrapped into (-π, π]
+    pub fn pitch_angle(&self) -> Angle {
+        normalize_pi(self.pitch).angle()
+    }
+
+    /// Heading wrapped into [0, 2π)
+    pub fn heading_angle(&self) -> Angle {
+        normalize_2pi(self.heading).angle()
+    }
+
+    /// Heading in degrees, wrapped into [0, 360)
+    pub fn heading_deg(&self) -> f32 {
+        normalize_2pi(self.heading).to_degrees().get()
+    }
+
+    /// Turn since `previous` along the shorter arc, positive clockwise, so
+    /// crossing north gives a small change rather than nearly a full turn
+    pub fn heading_change(&self, previous: &SystemState) -> Angle {
+        shortest_angular_distance(previous.heading, self.heading).angle()
+    }
+
+    /// Time of the solution as a [`UnixTime`]
+    pub fn unix_time(&self) -> UnixTime {
+        UnixTime { unix_time_seconds: self.unix_time_seconds, microseconds: self.microseconds }
+    }
+
+    /// Events flagged in this state, stamped with its time. The device
+    /// raises the flag in the first System State after the pulse, so the
+    /// event happened at most one System State period before the stamp.
+    pub fn event_timestamps(&self) -> impl Iterator<Item = EventTimestamp> + use<> {
+        let time = self.unix_time();
+        self.filter_status.events().map(move |event| EventTimestamp { event, time: time.clone() })
+    }
+}
+
+/// An external event pulse on a GPIO input, e.g. a camera trigger or a
+/// sensor's 1PPS, placed in navigation time
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct EventTimestamp {
+    pub event: EventId,
+    pub time: UnixTime,
 }
 
 /// Unix time packet (Packet ID 21, Length 8) - Read only
-#[derive(Debug, Clone, PartialEq, BinRead, BinWrite, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, BinRead, BinWrite)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 #[brw(little)]
 pub struct UnixTime {
     pub unix_time_seconds: u32,
     pub microseconds: u32,
 }
 
+impl UnixTime {
+    pub fn to_system_time(&self) -> SystemTime {
+        unix_time(self.unix_time_seconds, self.microseconds)
+    }
+
+    /// GPS week and time of week using the built-in leap second table,
+    /// `None` before the GPS epoch. See
+    /// [`LeapSecondTable::unix_to_gps`] for a custom table.
+    pub fn to_gps_week_tow(&self) -> Option<GpsTime> {
+        LeapSecondTable::builtin().unix_to_gps(self.to_system_time())
+    }
+}
+
+impl From<&UnixTime> for SystemTime {
+    fn from(time: &UnixTime) -> Self {
+        time.to_system_time()
+    }
+}
+
+/// Formatted time packet (Packet ID 22, Length 14) - Read only
+///
+/// The same UTC instant as [`UnixTime`] broken into calendar fields.
+/// Months, year days and week days count from zero (January, 1 January,
+/// Sunday); month days count from one.
+#[derive(Debug, Clone, PartialEq, BinRead, BinWrite)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[brw(little)]
+pub struct FormattedTime {
+    pub microseconds: u32,
+    pub year: u16,
+    /// Day of the year, 0-365
+    pub year_day: u16,
+    /// Month, 0-11
+    pub month: u8,
+    /// Day of the month, 1-31
+    pub month_day: u8,
+    /// Day of the week, 0-6 from Sunday
+    pub week_day: u8,
+    pub hour: u8,
+    pub minute: u8,
+    pub second: u8,
+}
+
+impl FormattedTime {
+    /// The instant as Unix seconds, or `None` if a field is out of range or
+    /// the date is before 1970. Year day and week day are not checked.
+    pub fn unix_seconds(&self) -> Option<u64> {
+        if self.month > 11 || !(1..=days_in_month(self.year, self.month)).contains(&self.month_day)
+            || self.hour > 23 || self.minute > 59 || self.second > 60 || self.microseconds >= 1_000_000
+        {
+            return None;
+        }
+        let days = u64::try_from(days_from_civil(self.year.into(), self.month, self.month_day)).ok()?;
+        Some(days * 86_400 + u64::from(self.hour) * 3600 + u64::from(self.minute) * 60 + u64::from(self.second))
+    }
+
+    pub fn to_system_time(&self) -> Option<SystemTime> {
+        let seconds = self.unix_seconds()?;
+        Some(UNIX_EPOCH + Duration::from_secs(seconds) + Duration::from_micros(self.microseconds.into()))
+    }
+
+    /// Whether this and `time` are within `tolerance` of each other, for
+    /// cross-checking the two time packets. Always false if this does not
+    /// hold a valid date.
+    pub fn approximately_equals(&self, time: &UnixTime, tolerance: Duration) -> bool {
+        let Some(formatted) = self.to_system_time() else {
+            return false;
+        };
+        let unix = time.to_system_time();
+        let difference = formatted.duration_since(unix).or_else(|_| unix.duration_since(formatted));
+        difference.is_ok_and(|difference| difference <= tolerance)
+    }
+}
+
+impl From<&UnixTime> for FormattedTime {
+    fn from(time: &UnixTime) -> Self {
+        let days = time.unix_time_seconds / 86_400;
+        let seconds = time.unix_time_seconds % 86_400;
+        let (year, month, month_day) = civil_from_days(days.into());
+        Self {
+            microseconds: time.microseconds,
+            year,
+            year_day: (i64::from(days) - days_from_civil(year.into(), 0, 1)) as u16,
+            month,
+            month_day,
+            // 1 January 1970 was a Thursday
+            week_day: ((days + 4) % 7) as u8,
+            hour: (seconds / 3600) as u8,
+            minute: (seconds / 60 % 60) as u8,
+            second: (seconds % 60) as u8,
+        }
+    }
+}
+
+fn is_leap_year(year: u16) -> bool {
+    year.is_multiple_of(4) && (!year.is_multiple_of(100) || year.is_multiple_of(400))
+}
+
+/// Days in a zero-based month
+fn days_in_month(year: u16, month: u8) -> u8 {
+    match month {
+        1 if is_leap_year(year) => 29,
+        1 => 28,
+        3 | 5 | 8 | 10 => 30,
+        _ => 31,
+    }
+}
+
+/// Days from 1 January 1970 to a date with a zero-based month, in the
+/// proleptic Gregorian calendar
+fn days_from_civil(year: i64, month: u8, day: u8) -> i64 {
+    let (month, day) = (i64::from(month) + 1, i64::from(day));
+    let y = if month <= 2 { year - 1 } else { year };
+    let era = y.div_euclid(400);
+    let yoe = y - era * 400;
+    let doy = (153 * ((month + 9) % 12) + 2) / 5 + day - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    era * 146_097 + doe - 719_468
+}
+
+/// Year, zero-based month and day of a day count from 1 January 1970
+fn civil_from_days(days: i64) -> (u16, u8, u8) {
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z - era * 146_097;
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 2 } else { mp - 10 };
+    let year = yoe + era * 400 + i64::from(month <= 1);
+    (year as u16, month as u8, day as u8)
+}
+
 /// Status packet (Packet ID 23, Length 4) - Read only
-#[derive(Debug, Clone, PartialEq, BinRead, BinWrite, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, BinRead, BinWrite)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 #[brw(little)]
 pub struct Status {
     pub system_status: SystemStatus,
     pub filter_status: FilterStatus,
+    /// Fields beyond the documented 4 bytes, if any
+    #[br(parse_with = binrw::helpers::until_eof, map = extension_from_bytes)]
+    #[bw(map = extension_to_bytes)]
+    #[cfg_attr(feature = "serde", serde(default, skip_serializing_if = "Option::is_none"))]
+    pub extension: Option<StatusExt>,
+}
+
+impl Status {
+    /// Active alarms ranked by severity
+    pub fn health(&self) -> HealthReport {
+        HealthReport::from(self)
+    }
 }
 
 /// Position standard deviation packet (Packet ID 24, Length 12) - Read only
-#[derive(Debug, Clone, PartialEq, BinRead, BinWrite, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, BinRead, BinWrite)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 #[brw(little)]
 pub struct PositionStdDev {
     /// Latitude standard deviation in meters
@@ -237,7 +627,9 @@ pub struct PositionStdDev {
 }
 
 /// Velocity standard deviation packet (Packet ID 25, Length 12) - Read only
-#[derive(Debug, Clone, PartialEq, BinRead, BinWrite, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, BinRead, BinWrite)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 #[brw(little)]
 pub struct VelocityStdDev {
     /// Velocity north standard deviation in m/s
@@ -248,17 +640,34 @@ pub struct VelocityStdDev {
     pub velocity_down_std_dev: f32,
 }
 
+impl VelocityStdDev {
+    /// Standard deviation as north, east, down
+    pub fn std_dev_ned(&self) -> [Velocity; 3] {
+        [self.velocity_north_std_dev, self.velocity_east_std_dev, self.velocity_down_std_dev]
+            .map(|v| Velocity::from_meters_per_second(v as f64))
+    }
+
+    /// Combined north and east standard deviation
+    pub fn horizontal(&self) -> Velocity {
+        Velocity::from_meters_per_second((self.velocity_north_std_dev as f64).hypot(self.velocity_east_std_dev as f64))
+    }
+}
+
 /// Euler orientation standard deviation packet (Packet ID 26, Length 12) - Read only
-#[derive(Debug, Clone, PartialEq, BinRead, BinWrite, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, BinRead, BinWrite)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 #[brw(little)]
 pub struct EulerOrientationStdDev {
-    pub roll_std_dev: f32,
-    pub pitch_std_dev: f32,
-    pub heading_std_dev: f32,
+    pub roll_std_dev: Radians,
+    pub pitch_std_dev: Radians,
+    pub heading_std_dev: Radians,
 }
 
 /// Quaternion orientation standard deviation packet (Packet ID 27, Length 16) - Read only
-#[derive(Debug, Clone, PartialEq, BinRead, BinWrite, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, BinRead, BinWrite)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 #[brw(little)]
 pub struct QuaternionOrientationStdDev {
     pub q0_std_dev: f32,
@@ -268,7 +677,9 @@ pub struct QuaternionOrientationStdDev {
 }
 
 /// Raw GNSS status bitfield
-#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, BinRead, BinWrite, Serialize, Deserialize)]
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, BinRead, BinWrite)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 #[brw(little)]
 pub struct RawGnssStatus(u16);
 
@@ -296,7 +707,9 @@ impl From<u16> for RawGnssStatus {
 /// Raw sensors packet (Packet ID 28, Length 48) - Read only
 #[binrw]
 #[brw(little)]
-#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub struct RawSensors {
     pub accelerometer_x: f32,
     pub accelerometer_y: f32,
@@ -319,7 +732,9 @@ pub struct RawSensors {
 }
 
 /// Satellites packet (Packet ID 30, Length 13) - Read only
-#[derive(Debug, Clone, PartialEq, BinRead, BinWrite, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, BinRead, BinWrite)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 #[brw(little)]
 pub struct Satellites {
     pub hdop: f32,
@@ -331,8 +746,105 @@ pub struct Satellites {
     pub sbas_satellites: u8,
 }
 
+/// Satellite navigation system
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum SatelliteSystem {
+    #[default]
+    Unknown = 0,
+    Gps = 1,
+    Glonass = 2,
+    Beidou = 3,
+    Galileo = 4,
+    Sbas = 5,
+    Qzss = 6,
+    Starfire = 7,
+    Omnistar = 8,
+    Navic = 9,
+}
+
+impl From<u8> for SatelliteSystem {
+    fn from(v: u8) -> Self {
+        match v {
+            1 => Self::Gps,
+            2 => Self::Glonass,
+            3 => Self::Beidou,
+            4 => Self::Galileo,
+            5 => Self::Sbas,
+            6 => Self::Qzss,
+            7 => Self::Starfire,
+            8 => Self::Omnistar,
+            9 => Self::Navic,
+            _ => Self::Unknown,
+        }
+    }
+}
+
+/// Satellite frequencies bitfield
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, BinRead, BinWrite)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[brw(little)]
+pub struct SatelliteFrequencies(u8);
+
+impl SatelliteFrequencies {
+    pub fn raw(&self) -> u8 { self.0 }
+    pub fn l1_ca(&self) -> bool { self.0 & (1 << 0) != 0 }
+    pub fn l1_c(&self) -> bool { self.0 & (1 << 1) != 0 }
+    pub fn l1_p(&self) -> bool { self.0 & (1 << 2) != 0 }
+    pub fn l1_m(&self) -> bool { self.0 & (1 << 3) != 0 }
+    pub fn l2_c(&self) -> bool { self.0 & (1 << 4) != 0 }
+    pub fn l2_p(&self) -> bool { self.0 & (1 << 5) != 0 }
+    pub fn l2_m(&self) -> bool { self.0 & (1 << 6) != 0 }
+    pub fn l5(&self) -> bool { self.0 & (1 << 7) != 0 }
+}
+
+impl From<u8> for SatelliteFrequencies {
+    fn from(v: u8) -> Self { Self(v) }
+}
+
+/// Per-satellite record within DetailedSatellites (7 bytes)
+#[derive(Debug, Clone, PartialEq, BinRead, BinWrite)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[brw(little)]
+pub struct SatelliteInfo {
+    #[br(map = |x: u8| SatelliteSystem::from(x))]
+    #[bw(map = |x: &SatelliteSystem| *x as u8)]
+    pub system: SatelliteSystem,
+    /// Satellite number (PRN)
+    pub prn: u8,
+    pub frequencies: SatelliteFrequencies,
+    /// Elevation in degrees
+    pub elevation: i8,
+    /// Azimuth in degrees
+    pub azimuth: u16,
+    /// Signal to noise ratio in dB-Hz
+    pub snr: u8,
+}
+
+/// Detailed satellites packet (Packet ID 31, Variable length) - Read only
+#[derive(Debug, Clone, PartialEq, BinRead, BinWrite)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[brw(little)]
+pub struct DetailedSatellites {
+    #[br(parse_with = binrw::helpers::until_eof)]
+    pub satellites: Vec<SatelliteInfo>,
+}
+
+impl DetailedSatellites {
+    /// Satellites belonging to a single constellation
+    pub fn by_system(&self, system: SatelliteSystem) -> impl Iterator<Item = &SatelliteInfo> {
+        self.satellites.iter().filter(move |s| s.system == system)
+    }
+}
+
 /// Raw GNSS packet (Packet ID 29, Length 74) - Read only
-#[derive(Debug, Clone, PartialEq, BinRead, BinWrite, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, BinRead, BinWrite)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 #[brw(little)]
 pub struct RawGnss {
     pub unix_time_seconds: u32,
@@ -356,18 +868,20 @@ pub struct RawGnss {
     /// Height standard deviation in meters
     pub height_std_dev: f32,
     /// Tilt in radians
-    pub tilt: f32,
+    pub tilt: Radians,
     /// Heading in radians
-    pub heading: f32,
+    pub heading: Radians,
     /// Tilt standard deviation in radians
-    pub tilt_std_dev: f32,
+    pub tilt_std_dev: Radians,
     /// Heading standard deviation in radians
-    pub heading_std_dev: f32,
+    pub heading_std_dev: Radians,
     pub status: RawGnssStatus,
 }
 
 /// Geodetic position packet (Packet ID 32, Length 24) - Read only
-#[derive(Debug, Clone, PartialEq, BinRead, BinWrite, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, BinRead, BinWrite)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 #[brw(little)]
 pub struct GeodeticPosition {
     /// Latitude in radians
@@ -379,7 +893,9 @@ pub struct GeodeticPosition {
 }
 
 /// ECEF position packet (Packet ID 33, Length 24) - Read only
-#[derive(Debug, Clone, PartialEq, BinRead, BinWrite, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, BinRead, BinWrite)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 #[brw(little)]
 pub struct EcefPosition {
     /// ECEF X in meters
@@ -391,7 +907,9 @@ pub struct EcefPosition {
 }
 
 /// UTM position packet (Packet ID 34, Length 26) - Read only
-#[derive(Debug, Clone, PartialEq, BinRead, BinWrite, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, BinRead, BinWrite)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 #[brw(little)]
 pub struct UtmPosition {
     /// Northing in meters
@@ -407,7 +925,9 @@ pub struct UtmPosition {
 }
 
 /// NED velocity packet (Packet ID 35, Length 12) - Read only
-#[derive(Debug, Clone, PartialEq, BinRead, BinWrite, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, BinRead, BinWrite)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 #[brw(little)]
 pub struct NedVelocity {
     /// Velocity north in m/s
@@ -418,8 +938,33 @@ pub struct NedVelocity {
     pub velocity_down: f32,
 }
 
+impl NedVelocity {
+    /// Velocity as north, east, down
+    pub fn velocity_ned(&self) -> [Velocity; 3] {
+        self.ned_velocity().into()
+    }
+
+    /// Velocity as a north, east, down vector
+    pub fn ned_velocity(&self) -> Ned<Velocity> {
+        Ned::new(self.velocity_north, self.velocity_east, self.velocity_down)
+            .map(|v| Velocity::from_meters_per_second(v as f64))
+    }
+
+    /// Horizontal speed over ground
+    pub fn ground_speed(&self) -> Velocity {
+        self.ned_velocity().horizontal()
+    }
+
+    /// Direction of travel, clockwise from true north
+    pub fn course_over_ground(&self) -> Angle {
+        self.ned_velocity().course()
+    }
+}
+
 /// Body velocity packet (Packet ID 36, Length 12) - Read only
-#[derive(Debug, Clone, PartialEq, BinRead, BinWrite, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, BinRead, BinWrite)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 #[brw(little)]
 pub struct BodyVelocity {
     /// Velocity X in m/s
@@ -430,8 +975,22 @@ pub struct BodyVelocity {
     pub velocity_z: f32,
 }
 
+impl BodyVelocity {
+    /// Velocity along the body X (forward), Y (right) and Z (down) axes
+    pub fn velocity_body(&self) -> [Velocity; 3] {
+        [self.velocity_x, self.velocity_y, self.velocity_z]
+            .map(|v| Velocity::from_meters_per_second(v as f64))
+    }
+}
+
 /// Acceleration packet (Packet ID 37, Length 12) - Read only
-#[derive(Debug, Clone, PartialEq, BinRead, BinWrite, Serialize, Deserialize)]
+///
+/// Filtered acceleration along the body axes as the accelerometers sense
+/// it, so gravity is included: a unit at rest reads about -9.8 m/s² on Z.
+/// Use [`BodyAcceleration`] for the vehicle's own acceleration.
+#[derive(Debug, Clone, PartialEq, BinRead, BinWrite)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 #[brw(little)]
 pub struct Acceleration {
     /// Acceleration X in m/s²
@@ -442,8 +1001,22 @@ pub struct Acceleration {
     pub acceleration_z: f32,
 }
 
+impl Acceleration {
+    /// Acceleration along the body X, Y and Z axes, gravity included
+    pub fn vector(&self) -> [LinearAcceleration; 3] {
+        [self.acceleration_x, self.acceleration_y, self.acceleration_z]
+            .map(|a| LinearAcceleration::from_meters_per_second_squared(a as f64))
+    }
+}
+
 /// Body acceleration packet (Packet ID 38, Length 16) - Read only
-#[derive(Debug, Clone, PartialEq, BinRead, BinWrite, Serialize, Deserialize)]
+///
+/// Acceleration along the body axes with gravity removed, so a unit at
+/// rest reads about zero. `g_force` is the magnitude of the total
+/// acceleration including gravity, about 1 g at rest.
+#[derive(Debug, Clone, PartialEq, BinRead, BinWrite)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 #[brw(little)]
 pub struct BodyAcceleration {
     /// Body acceleration X in m/s²
@@ -456,20 +1029,64 @@ pub struct BodyAcceleration {
     pub g_force: f32,
 }
 
+impl BodyAcceleration {
+    /// Acceleration along the body X, Y and Z axes, gravity removed
+    pub fn vector(&self) -> [LinearAcceleration; 3] {
+        [self.body_acceleration_x, self.body_acceleration_y, self.body_acceleration_z]
+            .map(|a| LinearAcceleration::from_meters_per_second_squared(a as f64))
+    }
+
+    /// Total acceleration magnitude including gravity
+    pub fn g_force_acceleration(&self) -> LinearAcceleration {
+        LinearAcceleration::from_g(self.g_force as f64)
+    }
+}
+
 /// Euler orientation packet (Packet ID 39, Length 12) - Read only
-#[derive(Debug, Clone, PartialEq, BinRead, BinWrite, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, BinRead, BinWrite)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 #[brw(little)]
 pub struct EulerOrientation {
     /// Roll in radians
-    pub roll: f32,
+    pub roll: Radians,
     /// Pitch in radians
-    pub pitch: f32,
+    pub pitch: Radians,
     /// Heading in radians
-    pub heading: f32,
+    pub heading: Radians,
+}
+
+impl EulerOrientation {
+    /// Roll wrapped into (-π, π]
+    pub fn roll_angle(&self) -> Angle {
+        normalize_pi(self.roll).angle()
+    }
+
+    /// Pitch wrapped into (-π, π]
+    pub fn pitch_angle(&self) -> Angle {
+        normalize_pi(self.pitch).angle()
+    }
+
+    /// Heading wrapped into [0, 2π)
+    pub fn heading_angle(&self) -> Angle {
+        normalize_2pi(self.heading).angle()
+    }
+
+    /// Heading in degrees, wrapped into [0, 360)
+    pub fn heading_deg(&self) -> f32 {
+        normalize_2pi(self.heading).to_degrees().get()
+    }
+
+    /// Turn since `previous` along the shorter arc, positive clockwise
+    pub fn heading_change(&self, previous: &EulerOrientation) -> Angle {
+        shortest_angular_distance(previous.heading, self.heading).angle()
+    }
 }
 
 /// Quaternion orientation packet (Packet ID 40, Length 16) - Read only
-#[derive(Debug, Clone, PartialEq, BinRead, BinWrite, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, BinRead, BinWrite)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 #[brw(little)]
 pub struct QuaternionOrientation {
     /// Scalar component
@@ -483,14 +1100,18 @@ pub struct QuaternionOrientation {
 }
 
 /// DCM orientation packet (Packet ID 41, Length 36) - Read only
-#[derive(Debug, Clone, PartialEq, BinRead, BinWrite, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, BinRead, BinWrite)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 #[brw(little)]
 pub struct DcmOrientation {
     pub dcm: [[f32; 3]; 3],
 }
 
 /// Angular velocity packet (Packet ID 42, Length 12) - Read only
-#[derive(Debug, Clone, PartialEq, BinRead, BinWrite, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, BinRead, BinWrite)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 #[brw(little)]
 pub struct AngularVelocity {
     /// Angular velocity X in rad/s
@@ -502,7 +1123,12 @@ pub struct AngularVelocity {
 }
 
 /// Angular acceleration packet (Packet ID 43, Length 12) - Read only
-#[derive(Debug, Clone, PartialEq, BinRead, BinWrite, Serialize, Deserialize)]
+///
+/// Rate of change of [`AngularVelocity`] about the body axes, derived by
+/// the filter rather than measured, so it is noisier than the rates.
+#[derive(Debug, Clone, PartialEq, BinRead, BinWrite)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 #[brw(little)]
 pub struct AngularAcceleration {
     /// Angular acceleration X in rad/s²
@@ -513,8 +1139,18 @@ pub struct AngularAcceleration {
     pub angular_acceleration_z: f32,
 }
 
+impl AngularAcceleration {
+    /// Angular acceleration about the body X, Y and Z axes
+    pub fn vector(&self) -> [RotationalAcceleration; 3] {
+        [self.angular_acceleration_x, self.angular_acceleration_y, self.angular_acceleration_z]
+            .map(|a| RotationalAcceleration::from_radians_per_second_squared(a as f64))
+    }
+}
+
 /// External position and velocity packet (Packet ID 44, Length 60) - Read/Write
-#[derive(Debug, Clone, PartialEq, BinRead, BinWrite, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, BinRead, BinWrite)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 #[brw(little)]
 pub struct ExternalPositionVelocity {
     /// Latitude in radians
@@ -544,7 +1180,9 @@ pub struct ExternalPositionVelocity {
 }
 
 /// External position packet (Packet ID 45, Length 36) - Write only
-#[derive(Debug, Clone, PartialEq, BinRead, BinWrite, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, BinRead, BinWrite)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 #[brw(little)]
 pub struct ExternalPosition {
     /// Latitude in radians
@@ -562,7 +1200,9 @@ pub struct ExternalPosition {
 }
 
 /// External velocity packet (Packet ID 46, Length 24) - Write only
-#[derive(Debug, Clone, PartialEq, BinRead, BinWrite, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, BinRead, BinWrite)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 #[brw(little)]
 pub struct ExternalVelocity {
     /// Velocity north in m/s
@@ -580,7 +1220,9 @@ pub struct ExternalVelocity {
 }
 
 /// External body velocity packet (Packet ID 47, Length 16) - Write only
-#[derive(Debug, Clone, PartialEq, BinRead, BinWrite, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, BinRead, BinWrite)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 #[brw(little)]
 pub struct ExternalBodyVelocity {
     /// Velocity X in m/s
@@ -594,17 +1236,21 @@ pub struct ExternalBodyVelocity {
 }
 
 /// External heading packet (Packet ID 48, Length 8) - Write only
-#[derive(Debug, Clone, PartialEq, BinRead, BinWrite, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, BinRead, BinWrite)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 #[brw(little)]
 pub struct ExternalHeading {
     /// Heading in radians
-    pub heading: f32,
+    pub heading: Radians,
     /// Standard deviation in radians
-    pub standard_deviation: f32,
+    pub standard_deviation: Radians,
 }
 
 /// Running time packet (Packet ID 49, Length 8) - Read only
-#[derive(Debug, Clone, PartialEq, BinRead, BinWrite, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, BinRead, BinWrite)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 #[brw(little)]
 pub struct RunningTime {
     /// Running time in seconds since power on
@@ -613,16 +1259,195 @@ pub struct RunningTime {
     pub microseconds: u32,
 }
 
+impl RunningTime {
+    /// Time since power on
+    pub fn duration(&self) -> Duration {
+        Duration::from_secs(self.seconds as u64) + Duration::from_micros(self.microseconds as u64)
+    }
+}
+
+/// Odometer state packet (Packet ID 51, Length 20) - Read only
+#[binrw]
+#[brw(little)]
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct OdometerState {
+    /// Raw pulse count, wrapping at the i32 range
+    pub pulse_count: i32,
+    /// Distance travelled in meters
+    pub distance: f32,
+    /// Speed in m/s
+    pub speed: f32,
+    /// Wheel slip in meters
+    pub slip: f32,
+    #[br(map = |x: u8| x != 0)]
+    #[bw(map = |x: &bool| *x as u8)]
+    pub active: bool,
+    #[br(temp)]
+    #[bw(calc = [0u8; 3])]
+    _reserved: [u8; 3],
+}
+
+impl OdometerState {
+    pub fn new(pulse_count: i32, distance: f32, speed: f32, slip: f32, active: bool) -> Self {
+        Self { pulse_count, distance, speed, slip, active }
+    }
+
+    /// Pulses counted since an earlier state, correct across one wrap of
+    /// the counter
+    pub fn pulses_since(&self, earlier: &OdometerState) -> i32 {
+        self.pulse_count.wrapping_sub(earlier.pulse_count)
+    }
+
+    pub fn speed_velocity(&self) -> Velocity {
+        Velocity::from_meters_per_second(self.speed as f64)
+    }
+}
+
+/// Accumulates distance from a stream of [`OdometerState`] packets
+///
+/// The pulse counter wraps, so the total is built from the wrapped
+/// difference between consecutive states multiplied by the configured
+/// pulse length. Reversing counts towards the distance travelled.
+#[derive(Debug, Clone, PartialEq)]
+pub struct OdometerTracker {
+    pulse_length: Length,
+    last: Option<i32>,
+    pulses: u64,
+}
+
+impl OdometerTracker {
+    /// `pulse_length` as configured with
+    /// [`OdometerConfiguration`](super::config::OdometerConfiguration)
+    pub fn new(pulse_length: Length) -> Self {
+        Self { pulse_length, last: None, pulses: 0 }
+    }
+
+    pub fn update(&mut self, state: &OdometerState) {
+        if let Some(last) = self.last {
+            self.pulses += state.pulse_count.wrapping_sub(last).unsigned_abs() as u64;
+        }
+        self.last = Some(state.pulse_count);
+    }
+
+    /// Pulses counted since the first state
+    pub fn pulses(&self) -> u64 {
+        self.pulses
+    }
+
+    pub fn distance_travelled(&self) -> Length {
+        Length::from_meters(self.pulses as f64 * self.pulse_length.meters())
+    }
+
+    /// Start counting again from the next state
+    pub fn reset(&mut self) {
+        self.last = None;
+        self.pulses = 0;
+    }
+}
+
 /// External time packet (Packet ID 52, Length 8) - Write only
-#[derive(Debug, Clone, PartialEq, BinRead, BinWrite, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, BinRead, BinWrite)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 #[brw(little)]
 pub struct ExternalTime {
     pub unix_time_seconds: u32,
     pub microseconds: u32,
 }
 
+impl ExternalTime {
+    /// Build from a host clock reading. Fails for times before 1970 or past
+    /// the 32-bit seconds range.
+    pub fn from_system_time(time: SystemTime) -> Option<Self> {
+        let since_epoch = time.duration_since(UNIX_EPOCH).ok()?;
+        Some(Self {
+            unix_time_seconds: u32::try_from(since_epoch.as_secs()).ok()?,
+            microseconds: since_epoch.subsec_micros(),
+        })
+    }
+
+    /// Current host time
+    pub fn now() -> Self {
+        Self::from_system_time(SystemTime::now()).expect("host clock within the ANPP time range")
+    }
+
+    pub fn to_system_time(&self) -> SystemTime {
+        unix_time(self.unix_time_seconds, self.microseconds)
+    }
+}
+
+/// External depth packet (Packet ID 53, Length 8) - Write only
+///
+/// Depth below the water surface, positive down, from a depth or pressure
+/// sensor on a subsea vehicle.
+#[derive(Debug, Clone, PartialEq, BinRead, BinWrite)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[brw(little)]
+pub struct ExternalDepth {
+    /// Depth in meters, positive down
+    pub depth: f32,
+    /// Depth standard deviation in meters
+    pub standard_deviation: f32,
+}
+
+impl ExternalDepth {
+    /// Sea water density in kg/m³
+    pub const SEAWATER_DENSITY: f64 = 1025.0;
+    /// Fresh water density in kg/m³
+    pub const FRESHWATER_DENSITY: f64 = 1000.0;
+    /// Deeper than the deepest ocean trench, so certainly a unit error
+    pub const MAX_DEPTH: Length = Length::from_meters(11_000.0);
+    /// Allowed height above the surface, for sensors that read slightly
+    /// negative while surfaced
+    pub const MAX_ABOVE_SURFACE: Length = Length::from_meters(10.0);
+
+    const GRAVITY: f64 = 9.80665;
+
+    /// Depth in meters positive down. Fails for depths outside
+    /// [`MAX_ABOVE_SURFACE`](Self::MAX_ABOVE_SURFACE) above to
+    /// [`MAX_DEPTH`](Self::MAX_DEPTH) below the surface, which usually
+    /// means the sensor reports feet, decibar or height positive up.
+    pub fn new(depth: Length, standard_deviation: Length) -> Result<Self, AnError> {
+        let packet = Self { depth: depth.meters() as f32, standard_deviation: standard_deviation.meters() as f32 };
+        packet.validate()?;
+        Ok(packet)
+    }
+
+    /// Depth from absolute pressure in pascals, relative to the pressure at
+    /// the surface, for water of `density` kg/m³
+    pub fn from_pressure(pressure: f64, surface_pressure: f64, density: f64, standard_deviation: Length) -> Result<Self, AnError> {
+        if !(density.is_finite() && density > 0.0) {
+            return Err(AnError::ValidationFailed(format!("water density {} kg/m³ is not positive", density)));
+        }
+        Self::new(Length::from_meters((pressure - surface_pressure) / (density * Self::GRAVITY)), standard_deviation)
+    }
+
+    pub fn depth_length(&self) -> Length {
+        Length::from_meters(self.depth as f64)
+    }
+
+    pub fn validate(&self) -> Result<(), AnError> {
+        let depth = self.depth as f64;
+        if !depth.is_finite() || depth > Self::MAX_DEPTH.meters() || depth < -Self::MAX_ABOVE_SURFACE.meters() {
+            return Err(AnError::ValidationFailed(format!(
+                "depth {} m outside -{}..{} m (depth is in meters, positive down)",
+                self.depth, Self::MAX_ABOVE_SURFACE.meters(), Self::MAX_DEPTH.meters())));
+        }
+        if !(self.standard_deviation.is_finite() && self.standard_deviation > 0.0) {
+            return Err(AnError::ValidationFailed(format!(
+                "depth standard deviation {} m is not positive", self.standard_deviation)));
+        }
+        Ok(())
+    }
+}
+
 /// Geoid height packet (Packet ID 54, Length 4) - Read only
-#[derive(Debug, Clone, PartialEq, BinRead, BinWrite, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, BinRead, BinWrite)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 #[brw(little)]
 pub struct GeoidHeight {
     /// Geoid height in meters (offset between WGS84 ellipsoid and EGM96 geoid)
@@ -630,7 +1455,9 @@ pub struct GeoidHeight {
 }
 
 /// RTCM corrections packet (Packet ID 55, Variable length) - Write only
-#[derive(Debug, Clone, PartialEq, BinRead, BinWrite, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, BinRead, BinWrite)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 #[brw(little)]
 pub struct RtcmCorrections {
     /// Raw RTCM v3 correction data
@@ -638,8 +1465,40 @@ pub struct RtcmCorrections {
     pub data: Vec<u8>,
 }
 
+/// Wind packet (Packet ID 57, Length 12) - Read/Write
+///
+/// The navigation filter's wind estimate, available when airspeed aiding
+/// is active. Writing it sets a fixed wind estimate instead.
+#[derive(Debug, Clone, PartialEq, BinRead, BinWrite)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[brw(little)]
+pub struct Wind {
+    /// Wind velocity north in m/s
+    pub wind_velocity_north: f32,
+    /// Wind velocity east in m/s
+    pub wind_velocity_east: f32,
+    /// Wind velocity standard deviation in m/s
+    pub wind_velocity_std_dev: f32,
+}
+
+impl Wind {
+    /// Horizontal wind speed
+    pub fn speed(&self) -> Velocity {
+        Velocity::from_meters_per_second((self.wind_velocity_north as f64).hypot(self.wind_velocity_east as f64))
+    }
+
+    /// Direction the wind blows from, clockwise from true north
+    pub fn direction_from(&self) -> Angle {
+        let radians = (-self.wind_velocity_east as f64).atan2(-self.wind_velocity_north as f64);
+        normalize_2pi(Angle::from_radians(radians))
+    }
+}
+
 /// Heave packet (Packet ID 58, Length 16) - Read only
-#[derive(Debug, Clone, PartialEq, BinRead, BinWrite, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, BinRead, BinWrite)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 #[brw(little)]
 pub struct Heave {
     pub heave_point_1: f32,
@@ -648,8 +1507,180 @@ pub struct Heave {
     pub heave_point_4: f32,
 }
 
+impl Heave {
+    /// Heave at one of the points configured in Reference Point Offsets
+    pub fn at(&self, point: HeavePointId) -> Length {
+        let meters = match point {
+            HeavePointId::Primary => self.heave_point_1,
+            HeavePointId::Cog => self.heave_point_2,
+            HeavePointId::Point3 => self.heave_point_3,
+            HeavePointId::Point4 => self.heave_point_4,
+        };
+        Length::from_meters(meters as f64)
+    }
+}
+
+/// GNSS antenna offset estimation packet (Packet ID 62, Length 28) - Read/Write
+///
+/// The filter's running estimate of the primary GNSS antenna lever arm in
+/// the body frame, used for automatic offset calibration. Writing the packet
+/// with `active` set starts estimation and clearing it stops estimation; the
+/// other fields are ignored on write. The estimate is not applied by the
+/// device: store it in InstallationAlignment once it has converged, see
+/// [`AntennaOffsetCalibration`](crate::ops::AntennaOffsetCalibration).
+#[binrw]
+#[brw(little)]
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct AntennaOffsetEstimation {
+    #[br(map = |x: u8| x != 0)]
+    #[bw(map = |x: &bool| *x as u8)]
+    pub active: bool,
+    /// Set by the device once the filter considers the estimate converged
+    #[br(map = |x: u8| x != 0)]
+    #[bw(map = |x: &bool| *x as u8)]
+    pub converged: bool,
+    #[br(temp)]
+    #[bw(calc = 0u16)]
+    _reserved: u16,
+    /// Estimated offset from the unit to the antenna in meters
+    pub offset: OffsetVector,
+    /// Standard deviation of each offset axis in meters
+    pub offset_std_dev: OffsetVector,
+}
+
+impl AntennaOffsetEstimation {
+    /// Command that starts estimation
+    pub fn start() -> Self {
+        Self::command(true)
+    }
+
+    /// Command that stops estimation
+    pub fn stop() -> Self {
+        Self::command(false)
+    }
+
+    fn command(active: bool) -> Self {
+        let zero = OffsetVector { x: 0.0, y: 0.0, z: 0.0 };
+        Self { active, converged: false, offset: zero.clone(), offset_std_dev: zero }
+    }
+
+    /// Largest standard deviation of the three axes
+    pub fn max_std_dev(&self) -> Length {
+        let std_dev = &self.offset_std_dev;
+        Length::from_meters(std_dev.x.max(std_dev.y).max(std_dev.z) as f64)
+    }
+}
+
+/// External air data flags bitfield
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, BinRead, BinWrite)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[brw(little)]
+pub struct AirDataFlags(u8);
+
+impl AirDataFlags {
+    pub const BAROMETRIC_ALTITUDE_VALID: u8 = 1 << 0;
+    pub const AIRSPEED_VALID: u8 = 1 << 1;
+    pub const BAROMETRIC_ALTITUDE_REFERENCE_RESET: u8 = 1 << 2;
+
+    pub fn raw(&self) -> u8 { self.0 }
+    pub fn barometric_altitude_valid(&self) -> bool { self.0 & Self::BAROMETRIC_ALTITUDE_VALID != 0 }
+    pub fn airspeed_valid(&self) -> bool { self.0 & Self::AIRSPEED_VALID != 0 }
+    pub fn barometric_altitude_reference_reset(&self) -> bool { self.0 & Self::BAROMETRIC_ALTITUDE_REFERENCE_RESET != 0 }
+}
+
+impl From<u8> for AirDataFlags {
+    fn from(v: u8) -> Self { Self(v) }
+}
+
+/// External air data packet (Packet ID 68, Length 25) - Write only
+///
+/// Barometric altitude and pitot airspeed aiding. Only the measurements
+/// flagged valid are used; the delays are how old each measurement is when
+/// the packet arrives.
+#[derive(Debug, Clone, PartialEq, BinRead, BinWrite)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[brw(little)]
+pub struct ExternalAirData {
+    /// Barometric altitude delay in seconds
+    pub barometric_altitude_delay: f32,
+    /// Airspeed delay in seconds
+    pub airspeed_delay: f32,
+    /// Barometric altitude in meters
+    pub barometric_altitude: f32,
+    /// True airspeed in m/s
+    pub airspeed: f32,
+    /// Barometric altitude standard deviation in meters
+    pub barometric_altitude_std_dev: f32,
+    /// Airspeed standard deviation in m/s
+    pub airspeed_std_dev: f32,
+    pub flags: AirDataFlags,
+}
+
+impl ExternalAirData {
+    /// Airspeed only
+    pub fn airspeed(airspeed: f32, std_dev: f32) -> Self {
+        Self {
+            barometric_altitude_delay: 0.0,
+            airspeed_delay: 0.0,
+            barometric_altitude: 0.0,
+            airspeed,
+            barometric_altitude_std_dev: 0.0,
+            airspeed_std_dev: std_dev,
+            flags: AirDataFlags(AirDataFlags::AIRSPEED_VALID),
+        }
+    }
+
+    /// Barometric altitude only
+    pub fn barometric_altitude(altitude: f32, std_dev: f32) -> Self {
+        Self {
+            barometric_altitude_delay: 0.0,
+            airspeed_delay: 0.0,
+            barometric_altitude: altitude,
+            airspeed: 0.0,
+            barometric_altitude_std_dev: std_dev,
+            airspeed_std_dev: 0.0,
+            flags: AirDataFlags(AirDataFlags::BAROMETRIC_ALTITUDE_VALID),
+        }
+    }
+
+    /// Add barometric altitude to an airspeed measurement or vice versa
+    pub fn with_barometric_altitude(mut self, altitude: f32, std_dev: f32) -> Self {
+        self.barometric_altitude = altitude;
+        self.barometric_altitude_std_dev = std_dev;
+        self.flags.0 |= AirDataFlags::BAROMETRIC_ALTITUDE_VALID;
+        self
+    }
+
+    pub fn with_airspeed(mut self, airspeed: f32, std_dev: f32) -> Self {
+        self.airspeed = airspeed;
+        self.airspeed_std_dev = std_dev;
+        self.flags.0 |= AirDataFlags::AIRSPEED_VALID;
+        self
+    }
+
+    /// Set how old each measurement is, in seconds
+    pub fn with_delays(mut self, barometric_altitude: f32, airspeed: f32) -> Self {
+        self.barometric_altitude_delay = barometric_altitude;
+        self.airspeed_delay = airspeed;
+        self
+    }
+
+    /// Tell the filter the barometric altitude reference has changed, e.g.
+    /// after a QNH update
+    pub fn with_reference_reset(mut self) -> Self {
+        self.flags.0 |= AirDataFlags::BAROMETRIC_ALTITUDE_REFERENCE_RESET;
+        self
+    }
+}
+
 /// DVL status flags bitfield
-#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, BinRead, BinWrite, Serialize, Deserialize)]
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, BinRead, BinWrite)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 #[brw(little)]
 pub struct DvlStatus(u32);
 
@@ -667,7 +1698,9 @@ impl From<u32> for DvlStatus {
 }
 
 /// Raw DVL data packet (Packet ID 70, Length 60) - Read only
-#[derive(Debug, Clone, PartialEq, BinRead, BinWrite, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, BinRead, BinWrite)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 #[brw(little)]
 pub struct RawDvlData {
     pub unix_time_seconds: u32,
@@ -700,7 +1733,9 @@ pub struct RawDvlData {
 }
 
 /// GNSS manufacturer identifier
-#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub enum GnssManufacturer {
     #[default]
     Unknown = 0,
@@ -722,7 +1757,9 @@ impl From<u8> for GnssManufacturer {
 }
 
 /// GNSS receiver model (decoded from manufacturer + model ID)
-#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub enum GnssReceiverModel {
     #[default]
     Unknown,
@@ -754,7 +1791,9 @@ impl From<(u8, u8)> for GnssReceiverModel {
 /// GNSS receiver information packet (Packet ID 69, Length 68) - Read only
 #[binrw]
 #[brw(little)]
-#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub struct GnssReceiverInformation {
     #[br(map = |x: u8| GnssManufacturer::from(x))]
     #[bw(map = |x: &GnssManufacturer| *x as u8)]
@@ -786,10 +1825,79 @@ impl GnssReceiverInformation {
     }
 }
 
+/// North seeking status flags bitfield
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, BinRead, BinWrite)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[brw(little)]
+pub struct NorthSeekingFlags(u16);
+
+impl NorthSeekingFlags {
+    pub fn raw(&self) -> u16 { self.0 }
+    pub fn initialised(&self) -> bool { self.0 & (1 << 0) != 0 }
+    pub fn position_error(&self) -> bool { self.0 & (1 << 1) != 0 }
+    pub fn excessive_roll(&self) -> bool { self.0 & (1 << 2) != 0 }
+    pub fn excessive_pitch(&self) -> bool { self.0 & (1 << 3) != 0 }
+    pub fn excessive_movement(&self) -> bool { self.0 & (1 << 4) != 0 }
+}
+
+impl From<u16> for NorthSeekingFlags {
+    fn from(v: u16) -> Self { Self(v) }
+}
+
+/// North seeking initialisation status packet (Packet ID 78, Length 28) - Read only
+///
+/// Gyrocompass alignment progress. The unit is rotated through four
+/// quadrants and collects data in each before the heading initialises.
+#[binrw]
+#[brw(little)]
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct NorthSeekingStatus {
+    pub flags: NorthSeekingFlags,
+    #[br(temp)]
+    #[bw(calc = 0u16)]
+    _reserved: u16,
+    /// Data collection progress per quadrant in percent
+    pub quadrant_progress: [u8; 4],
+    /// Current rotation angle in radians
+    pub current_rotation_angle: f32,
+    /// Gyroscope bias solution X in rad/s
+    pub gyroscope_bias_x: f32,
+    /// Gyroscope bias solution Y in rad/s
+    pub gyroscope_bias_y: f32,
+    /// Gyroscope bias solution Z in rad/s
+    pub gyroscope_bias_z: f32,
+    /// Gyroscope bias solution error in rad/s
+    pub gyroscope_bias_error: f32,
+}
+
+impl NorthSeekingStatus {
+    /// Overall progress in percent, averaged across the four quadrants
+    pub fn progress(&self) -> u8 {
+        let total: u16 = self.quadrant_progress.iter().map(|p| (*p).min(100) as u16).sum();
+        (total / 4) as u8
+    }
+
+    /// Zero based index of the current quadrant, from the rotation angle
+    pub fn current_quadrant(&self) -> usize {
+        let angle = normalize_2pi(self.current_rotation_angle);
+        ((angle / std::f32::consts::FRAC_PI_2) as usize).min(3)
+    }
+
+    /// First quadrant that still needs data, if any
+    pub fn next_incomplete_quadrant(&self) -> Option<usize> {
+        self.quadrant_progress.iter().position(|p| *p < 100)
+    }
+}
+
 /// Sensor temperature packet (Packet ID 85, Length 32) - Read only
 #[binrw]
 #[brw(little)]
-#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub struct SensorTemperature {
     pub accelerometer_temp_0: f32,
     pub accelerometer_temp_1: f32,
@@ -806,7 +1914,9 @@ pub struct SensorTemperature {
 /// GNSS Position Velocity Time packet (Packet ID 92, Length 76) - Read only
 #[binrw]
 #[brw(little)]
-#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub struct GnssPositionVelocityTime {
     pub gnss_id: u8,
     #[br(temp)]
@@ -833,7 +1943,9 @@ pub struct GnssPositionVelocityTime {
 /// GNSS Orientation packet (Packet ID 93, Length 36) - Read only
 #[binrw]
 #[brw(little)]
-#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub struct GnssOrientation {
     pub gnss_id: u8,
     #[br(temp)]
@@ -842,10 +1954,10 @@ pub struct GnssOrientation {
     pub status: GnssOrientationStatus,
     pub posix_time_seconds: u32,
     pub posix_time_microseconds: u32,
-    pub azimuth: f32,
-    pub azimuth_std_dev: f32,
-    pub tilt: f32,
-    pub tilt_std_dev: f32,
+    pub azimuth: Radians,
+    pub azimuth_std_dev: Radians,
+    pub tilt: Radians,
+    pub tilt_std_dev: Radians,
     pub baseline_length: f32,
     pub latency: u32,
 }
@@ -854,6 +1966,23 @@ pub struct GnssOrientation {
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_north_seeking_status_progress() {
+        let status = NorthSeekingStatus {
+            flags: NorthSeekingFlags::from(0),
+            quadrant_progress: [100, 100, 40, 0],
+            current_rotation_angle: -std::f32::consts::FRAC_PI_4,
+            gyroscope_bias_x: 0.0,
+            gyroscope_bias_y: 0.0,
+            gyroscope_bias_z: 0.0,
+            gyroscope_bias_error: 0.0,
+        };
+        assert_eq!(status.progress(), 60);
+        assert_eq!(status.current_quadrant(), 3);
+        assert_eq!(status.next_incomplete_quadrant(), Some(2));
+        assert!(!status.flags.initialised());
+    }
+
     #[test]
     fn test_system_status_accessors() {
         let status = SystemStatus::from(0b0100_0000_0010_0001u16); // bits 0, 5, 14
@@ -875,6 +2004,16 @@ mod tests {
         assert_eq!(status, FilterStatus::from(status.raw()));
     }
 
+    #[test]
+    fn test_filter_status_heading_source() {
+        const HEADING: u16 = 1 << 2;
+        assert_eq!(FilterStatus::from(0xFFFB).heading_source(), None);
+        assert_eq!(FilterStatus::from(HEADING).heading_source(), Some(HeadingSource::Gyrocompass));
+        assert_eq!(FilterStatus::from(HEADING | 1 << 11).heading_source(), Some(HeadingSource::Velocity));
+        assert_eq!(FilterStatus::from(HEADING | 1 << 10 | 1 << 11).heading_source(), Some(HeadingSource::DualAntenna));
+        assert_eq!(FilterStatus::from(0xFFFF).heading_source(), Some(HeadingSource::External));
+    }
+
     #[test]
     fn test_gnss_pvt_status_accessors() {
         let status = GnssPvtStatus::from(0u16);
@@ -915,15 +2054,16 @@ mod tests {
             body_acceleration_y: -0.01,
             body_acceleration_z: 9.81,
             g_force: 1.0,
-            roll: (PI / 12.0) as f32,
-            pitch: (PI / 18.0) as f32,
-            heading: (PI / 2.0) as f32,
+            roll: Radians((PI / 12.0) as f32),
+            pitch: Radians((PI / 18.0) as f32),
+            heading: Radians((PI / 2.0) as f32),
             angular_velocity_x: 0.001,
             angular_velocity_y: 0.002,
             angular_velocity_z: 0.003,
             latitude_std_dev: 0.5,
             longitude_std_dev: 0.6,
             height_std_dev: 1.0,
+            extension: None,
         };
 
         let mut cursor = std::io::Cursor::new(Vec::new());
@@ -934,9 +2074,38 @@ mod tests {
         let mut cursor = std::io::Cursor::new(&serialized);
         let deserialized = SystemState::read_le(&mut cursor).unwrap();
         assert_eq!(system_state, deserialized);
+
+        assert!((deserialized.latitude_angle().degrees() - 45.0).abs() < 1e-9);
+        assert!((deserialized.heading_angle().degrees() - 90.0).abs() < 1e-4);
+        assert_eq!(deserialized.height_length().meters(), 100.5);
+        assert_eq!(deserialized.velocity_ned()[1].meters_per_second(), 2.5);
+        assert_eq!(deserialized.ground_speed().meters_per_second(), (1.5f64).hypot(2.5));
+        assert!((deserialized.course_over_ground().radians() - 2.5f64.atan2(1.5)).abs() < 1e-9);
+        assert!((deserialized.vertical_speed().meters_per_second() - 0.1).abs() < 1e-6);
+        assert!((deserialized.horizontal_position_std().meters() - 0.5f64.hypot(0.6)).abs() < 1e-6);
+        assert_eq!(deserialized.timestamp(), UNIX_EPOCH + Duration::new(1640995200, 123_456_000));
+    }
+
+    #[test]
+    fn test_time_conversions() {
+        let time = UNIX_EPOCH + Duration::new(1_700_000_000, 250_000_999);
+        let external = ExternalTime::from_system_time(time).unwrap();
+        assert_eq!(external.unix_time_seconds, 1_700_000_000);
+        assert_eq!(external.microseconds, 250_000);
+        assert_eq!(external.to_system_time(), UNIX_EPOCH + Duration::new(1_700_000_000, 250_000_000));
+
+        let unix = UnixTime { unix_time_seconds: 1, microseconds: 5 };
+        assert_eq!(SystemTime::from(&unix), UNIX_EPOCH + Duration::from_micros(1_000_005));
+        assert_eq!(unix.to_gps_week_tow(), None);
+        let gps = UnixTime { unix_time_seconds: 1_704_067_200, microseconds: 5 }.to_gps_week_tow().unwrap();
+        assert_eq!((gps.week(), gps.tow()), (2295, Duration::new(86_418, 5_000)));
+
+        assert!(ExternalTime::from_system_time(UNIX_EPOCH - Duration::from_secs(1)).is_none());
+        assert!(ExternalTime::from_system_time(UNIX_EPOCH + Duration::from_secs(1 << 33)).is_none());
     }
 }
 
 #[cfg(test)]
 #[path = "tests/state.rs"]
+#[allow(clippy::approx_constant, clippy::excessive_precision)]
 mod state_length_tests;
@@ -1,29 +1,86 @@
 use binrw::{binrw, BinRead, BinWrite};
+#[cfg(feature = "serde")]
 use serde::{Serialize, Deserialize};
 
-use super::PacketKind;
+use std::collections::HashMap;
+use std::net::Ipv4Addr;
+
+use crate::error::AnError;
+use crate::protocol::AnppProtocol;
+use super::{HasPacketId, PacketKind, ipv4_from_wire, ipv4_to_wire};
 
 /// Acknowledge result codes
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub enum AcknowledgeResult {
-    Success = 0,
-    Failure = 1,
-    UnknownPacket = 2,
+    Success,
+    /// Failure - CRC error
+    CrcError,
+    /// Failure - packet size incorrect
+    PacketSizeIncorrect,
+    /// Failure - values outside of valid ranges
+    ValuesOutOfRange,
+    /// Failure - system flash memory failure
+    FlashMemoryFailure,
+    /// Failure - system not ready
+    SystemNotReady,
+    /// Failure - unknown packet
+    UnknownPacket,
+    /// Result code not defined by the protocol
+    Other(u8),
+}
+
+impl AcknowledgeResult {
+    /// True for every result other than success
+    pub fn is_failure(&self) -> bool {
+        *self != Self::Success
+    }
+
+    /// True when resending the same packet may succeed.
+    ///
+    /// A CRC error means the packet was damaged in transit and a not-ready
+    /// device will accept it once it has finished starting up. Every other
+    /// failure is a property of the packet itself or of the device.
+    pub fn is_retryable(&self) -> bool {
+        matches!(self, Self::CrcError | Self::SystemNotReady)
+    }
 }
 
 impl From<u8> for AcknowledgeResult {
     fn from(v: u8) -> Self {
         match v {
             0 => Self::Success,
-            1 => Self::Failure,
-            2 => Self::UnknownPacket,
-            _ => Self::Failure,
+            1 => Self::CrcError,
+            2 => Self::PacketSizeIncorrect,
+            3 => Self::ValuesOutOfRange,
+            4 => Self::FlashMemoryFailure,
+            5 => Self::SystemNotReady,
+            6 => Self::UnknownPacket,
+            other => Self::Other(other),
+        }
+    }
+}
+
+impl From<AcknowledgeResult> for u8 {
+    fn from(result: AcknowledgeResult) -> Self {
+        match result {
+            AcknowledgeResult::Success => 0,
+            AcknowledgeResult::CrcError => 1,
+            AcknowledgeResult::PacketSizeIncorrect => 2,
+            AcknowledgeResult::ValuesOutOfRange => 3,
+            AcknowledgeResult::FlashMemoryFailure => 4,
+            AcknowledgeResult::SystemNotReady => 5,
+            AcknowledgeResult::UnknownPacket => 6,
+            AcknowledgeResult::Other(code) => code,
         }
     }
 }
 
 /// Acknowledge packet (Packet ID 0, Length 4) - Read only
-#[derive(Debug, Clone, PartialEq, BinRead, BinWrite, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, BinRead, BinWrite)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 #[brw(little)]
 pub struct Acknowledge {
     #[br(map = |x: u8| PacketKind::from(x))]
@@ -31,28 +88,82 @@ pub struct Acknowledge {
     pub acknowledged_packet: PacketKind,
     pub packet_crc: u16,
     #[br(map = |x: u8| AcknowledgeResult::from(x))]
-    #[bw(map = |x: &AcknowledgeResult| *x as u8)]
+    #[bw(map = |x: &AcknowledgeResult| u8::from(*x))]
     pub result: AcknowledgeResult,
 }
 
-/// Request packet (Packet ID 1, Length 1) - Write only
-#[derive(Debug, Clone, PartialEq, BinRead, BinWrite, Serialize, Deserialize)]
+impl Acknowledge {
+    /// True when the device accepted the packet
+    pub fn is_success(&self) -> bool {
+        self.result == AcknowledgeResult::Success
+    }
+
+    /// True when resending the acknowledged packet may succeed
+    pub fn is_retryable(&self) -> bool {
+        self.result.is_retryable()
+    }
+
+    /// Whether this acknowledges `sent_frame`, one complete ANPP frame as
+    /// written to the device: the packet ID matches and `packet_crc` equals
+    /// the CRC of the frame's payload. Tells apart several writes of the
+    /// same kind in flight at once.
+    pub fn matches(&self, sent_frame: &[u8]) -> bool {
+        AnppProtocol::get_header_from_bytes(sent_frame).is_ok_and(|(header, payload)| {
+            header.packet_id.as_u8() == self.acknowledged_packet.packet_id()
+                && AnppProtocol::calculate_crc16(&payload) == self.packet_crc
+        })
+    }
+}
+
+/// Request packet (Packet ID 1, Variable length) - Write only
+///
+/// One byte per requested packet ID; the device answers each in order.
+#[derive(Debug, Clone, PartialEq, BinRead, BinWrite)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 #[brw(little)]
 pub struct Request {
-    #[br(map = |x: u8| PacketKind::from(x))]
-    #[bw(map = |x: &PacketKind| x.packet_id())]
-    pub requested_packet: PacketKind,
+    #[br(parse_with = binrw::helpers::until_eof, map = |ids: Vec<u8>| ids.into_iter().map(PacketKind::from).collect())]
+    #[bw(map = |kinds: &Vec<PacketKind>| kinds.iter().map(|k| k.packet_id()).collect::<Vec<u8>>())]
+    pub requested_packets: Vec<PacketKind>,
+}
+
+impl Request {
+    /// Request a single packet
+    pub fn single(kind: PacketKind) -> Self {
+        Self { requested_packets: vec![kind] }
+    }
+
+    /// Request several packets in one frame
+    pub fn multiple(kinds: impl IntoIterator<Item = PacketKind>) -> Self {
+        Self { requested_packets: kinds.into_iter().collect() }
+    }
+
+    /// Request a packet by type, e.g. `Request::for_packet::<SystemState>()`
+    pub fn for_packet<T: HasPacketId>() -> Self {
+        Self::single(PacketKind::from(T::PACKET_ID.as_u8()))
+    }
+
+    /// Add another packet type to the request
+    pub fn and<T: HasPacketId>(mut self) -> Self {
+        self.requested_packets.push(PacketKind::from(T::PACKET_ID.as_u8()));
+        self
+    }
 }
 
 /// Boot mode packet (Packet ID 2, Length 1) - Read/Write
-#[derive(Debug, Clone, PartialEq, BinRead, BinWrite, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, BinRead, BinWrite)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 #[brw(little)]
 pub struct BootMode {
     pub boot_mode: u8,
 }
 
 /// Advanced Navigation device type
-#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, BinRead, BinWrite, Serialize, Deserialize)]
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, BinRead, BinWrite)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 #[brw(repr = u32)]
 pub enum DeviceType {
     #[default]
@@ -109,8 +220,164 @@ impl From<u32> for DeviceType {
     }
 }
 
+/// Product line a [`DeviceModel`] belongs to
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum DeviceFamily {
+    Spatial,
+    Certus,
+    Boreas,
+    Orientus,
+    Motus,
+    Subsonus,
+    Other,
+}
+
+/// Advanced Navigation product, with the hardware capabilities that decide
+/// which configuration makes sense for it
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum DeviceModel {
+    #[default]
+    Unknown,
+    Spatial,
+    SpatialFog,
+    SpatialDual,
+    SpatialFogDual,
+    Orientus,
+    Motus,
+    AirDataUnit,
+    Subsonus,
+    SubsonusTag,
+    Poseidon,
+    GnssCompass,
+    Certus,
+    CertusMiniA,
+    CertusMiniN,
+    CertusMiniD,
+    BoreasD90,
+    BoreasD70,
+    BoreasD50,
+    BoreasA90,
+    BoreasA70,
+    BoreasA50,
+}
+
+impl From<DeviceType> for DeviceModel {
+    fn from(device_type: DeviceType) -> Self {
+        match device_type {
+            DeviceType::Unknown => Self::Unknown,
+            DeviceType::Spatial => Self::Spatial,
+            DeviceType::SpatialFog => Self::SpatialFog,
+            DeviceType::SpatialDual => Self::SpatialDual,
+            DeviceType::SpatialFogDual => Self::SpatialFogDual,
+            DeviceType::Orientus => Self::Orientus,
+            DeviceType::Motus => Self::Motus,
+            DeviceType::AirDataUnit => Self::AirDataUnit,
+            DeviceType::Subsonus => Self::Subsonus,
+            DeviceType::SubsonusTag => Self::SubsonusTag,
+            DeviceType::Poseidon => Self::Poseidon,
+            DeviceType::GnssCompass => Self::GnssCompass,
+            DeviceType::Certus => Self::Certus,
+            DeviceType::CertusMiniA => Self::CertusMiniA,
+            DeviceType::CertusMiniN => Self::CertusMiniN,
+            DeviceType::CertusMiniD => Self::CertusMiniD,
+            DeviceType::BoreasD90 => Self::BoreasD90,
+            DeviceType::BoreasD70 => Self::BoreasD70,
+            DeviceType::BoreasD50 => Self::BoreasD50,
+            DeviceType::BoreasA90 => Self::BoreasA90,
+            DeviceType::BoreasA70 => Self::BoreasA70,
+            DeviceType::BoreasA50 => Self::BoreasA50,
+        }
+    }
+}
+
+impl DeviceModel {
+    pub fn family(&self) -> DeviceFamily {
+        match self {
+            Self::Spatial | Self::SpatialFog | Self::SpatialDual | Self::SpatialFogDual => DeviceFamily::Spatial,
+            Self::Certus | Self::CertusMiniA | Self::CertusMiniN | Self::CertusMiniD => DeviceFamily::Certus,
+            Self::BoreasD90 | Self::BoreasD70 | Self::BoreasD50
+            | Self::BoreasA90 | Self::BoreasA70 | Self::BoreasA50 => DeviceFamily::Boreas,
+            Self::Orientus => DeviceFamily::Orientus,
+            Self::Motus => DeviceFamily::Motus,
+            Self::Subsonus | Self::SubsonusTag => DeviceFamily::Subsonus,
+            Self::Unknown | Self::AirDataUnit | Self::Poseidon | Self::GnssCompass => DeviceFamily::Other,
+        }
+    }
+
+    /// Whether the unit has its own GNSS receiver. AHRS-only variants
+    /// (Boreas A, Certus Mini A, Orientus, Motus) rely on external aiding.
+    pub fn has_internal_gnss(&self) -> bool {
+        matches!(self,
+            Self::Spatial | Self::SpatialFog | Self::SpatialDual | Self::SpatialFogDual
+            | Self::GnssCompass | Self::Certus | Self::CertusMiniN | Self::CertusMiniD
+            | Self::BoreasD90 | Self::BoreasD70 | Self::BoreasD50)
+    }
+
+    /// Whether the internal receiver measures heading from two antennas
+    pub fn has_dual_antenna(&self) -> bool {
+        matches!(self,
+            Self::SpatialDual | Self::SpatialFogDual | Self::GnssCompass | Self::Certus
+            | Self::CertusMiniD | Self::BoreasD90 | Self::BoreasD70 | Self::BoreasD50)
+    }
+
+    /// Whether the unit runs a navigation filter that takes Filter Options
+    pub fn has_navigation_filter(&self) -> bool {
+        !matches!(self, Self::AirDataUnit)
+    }
+
+    /// Whether the unit has a fibre-optic gyro that can north seek
+    pub fn has_fog(&self) -> bool {
+        matches!(self.family(), DeviceFamily::Boreas) || matches!(self, Self::SpatialFog | Self::SpatialFogDual)
+    }
+
+    /// Whether the unit is configured over Ethernet
+    pub fn has_ethernet(&self) -> bool {
+        matches!(self.family(), DeviceFamily::Boreas | DeviceFamily::Subsonus)
+            || matches!(self, Self::Certus | Self::SpatialFogDual)
+    }
+
+    /// Whether the unit has a magnetometer to calibrate
+    pub fn has_magnetometer(&self) -> bool {
+        !matches!(self.family(), DeviceFamily::Boreas) && !matches!(self, Self::AirDataUnit | Self::GnssCompass)
+    }
+
+    /// Whether the unit can output or accept packets of `kind`.
+    ///
+    /// Unknown models are assumed to support every packet this library
+    /// decodes, so callers only narrow the packet set when the model is
+    /// recognised.
+    pub fn supports(&self, kind: PacketKind) -> bool {
+        if kind == PacketKind::Unsupported {
+            return false;
+        }
+        if *self == Self::Unknown {
+            return true;
+        }
+        if !self.has_navigation_filter() {
+            return kind.packet_id() < 20;
+        }
+        match kind {
+            PacketKind::RawGnss | PacketKind::Satellites | PacketKind::DetailedSatellites
+            | PacketKind::RtcmCorrections | PacketKind::GnssReceiverInformation
+            | PacketKind::GnssPositionVelocityTime | PacketKind::AntennaOffsetEstimation => self.has_internal_gnss(),
+            PacketKind::GnssOrientation | PacketKind::DualAntennaConfiguration => self.has_dual_antenna(),
+            PacketKind::NorthSeekingStatus => self.has_fog(),
+            PacketKind::IpConfiguration | PacketKind::IpDataportsConfiguration => self.has_ethernet(),
+            PacketKind::MagneticCalibrationValues | PacketKind::MagneticCalibrationConfiguration
+            | PacketKind::MagneticCalibrationStatus => self.has_magnetometer(),
+            _ => true,
+        }
+    }
+}
+
 /// Device information packet (Packet ID 3, Length 24) - Read only
-#[derive(Debug, Clone, PartialEq, BinRead, BinWrite, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, BinRead, BinWrite)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 #[brw(little)]
 pub struct DeviceInformation {
     pub software_version: u32,
@@ -121,10 +388,69 @@ pub struct DeviceInformation {
     pub serial_number_3: u32,
 }
 
+impl DeviceInformation {
+    /// Product model and capabilities for the reported device type
+    pub fn model(&self) -> DeviceModel {
+        DeviceModel::from(self.device_type)
+    }
+
+    pub fn software(&self) -> Version {
+        Version::from_raw(self.software_version)
+    }
+
+    pub fn hardware(&self) -> Version {
+        Version::from_raw(self.hardware_revision)
+    }
+}
+
+/// Firmware or hardware version decoded from Device Information.
+///
+/// Advanced Navigation encodes versions as decimal digits: thousands are the
+/// major version, hundreds the minor and the last two digits the patch, so
+/// 2410 is 2.4.10 and 7000 is 7.0.0. Versions order numerically.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Version {
+    pub major: u32,
+    pub minor: u32,
+    pub patch: u32,
+}
+
+impl Version {
+    pub const fn new(major: u32, minor: u32, patch: u32) -> Self {
+        Self { major, minor, patch }
+    }
+
+    pub const fn from_raw(raw: u32) -> Self {
+        Self { major: raw / 1000, minor: raw / 100 % 10, patch: raw % 100 }
+    }
+
+    /// The value as sent on the wire. Minor and patch saturate at 9 and 99.
+    pub fn raw(&self) -> u32 {
+        self.major.saturating_mul(1000)
+            .saturating_add(self.minor.min(9) * 100)
+            .saturating_add(self.patch.min(99))
+    }
+
+    /// Whether this version is `major.minor.patch` or newer
+    pub fn at_least(&self, major: u32, minor: u32, patch: u32) -> bool {
+        *self >= Self::new(major, minor, patch)
+    }
+}
+
+impl From<u32> for Version {
+    fn from(raw: u32) -> Self {
+        Self::from_raw(raw)
+    }
+}
+
 /// Restore factory settings packet (Packet ID 4, Length 4) - Write only
 #[binrw]
 #[brw(little)]
-#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub struct RestoreFactorySettings {
     #[br(temp)]
     #[bw(calc = 0x85429E1Cu32)]
@@ -134,33 +460,529 @@ pub struct RestoreFactorySettings {
 /// Reset packet (Packet ID 5, Length 4) - Write only
 #[binrw]
 #[brw(little)]
-#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub struct Reset {
     #[br(temp)]
     #[bw(calc = 0x21057A7Eu32)]
     _verification: u32,
 }
 
+/// How the data of a file transfer is encoded
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum FileTransferEncoding {
+    Binary,
+    Aes256,
+    Other(u8),
+}
+
+impl From<u8> for FileTransferEncoding {
+    fn from(v: u8) -> Self {
+        match v {
+            0 => Self::Binary,
+            1 => Self::Aes256,
+            other => Self::Other(other),
+        }
+    }
+}
+
+impl From<FileTransferEncoding> for u8 {
+    fn from(encoding: FileTransferEncoding) -> Self {
+        match encoding {
+            FileTransferEncoding::Binary => 0,
+            FileTransferEncoding::Aes256 => 1,
+            FileTransferEncoding::Other(code) => code,
+        }
+    }
+}
+
+/// What the metadata of a file transfer request holds
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum FileTransferMetadata {
+    None,
+    /// An ANPP packet describing the transfer
+    ExtendedAnpp,
+    /// File name
+    Utf8Filename,
+    /// Advanced Navigation firmware image header
+    AnFirmware,
+    Other(u8),
+}
+
+impl From<u8> for FileTransferMetadata {
+    fn from(v: u8) -> Self {
+        match v {
+            0 => Self::None,
+            1 => Self::ExtendedAnpp,
+            2 => Self::Utf8Filename,
+            3 => Self::AnFirmware,
+            other => Self::Other(other),
+        }
+    }
+}
+
+impl From<FileTransferMetadata> for u8 {
+    fn from(metadata: FileTransferMetadata) -> Self {
+        match metadata {
+            FileTransferMetadata::None => 0,
+            FileTransferMetadata::ExtendedAnpp => 1,
+            FileTransferMetadata::Utf8Filename => 2,
+            FileTransferMetadata::AnFirmware => 3,
+            FileTransferMetadata::Other(code) => code,
+        }
+    }
+}
+
+/// File transfer request packet (Packet ID 6, Variable length) - Read/Write
+///
+/// Opens a transfer, identified by `unique_id` for its lifetime, and may
+/// carry the first data. Sent from `data_index` other than zero it asks for
+/// a transfer to resume from that byte.
+#[binrw]
+#[brw(little)]
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct FileTransferRequest {
+    pub unique_id: u32,
+    /// Byte offset of `data` within the file
+    pub data_index: u32,
+    #[br(map = |x: u8| x != 0)]
+    #[bw(map = |x: &bool| *x as u8)]
+    pub response_required: bool,
+    #[br(map = |x: u8| FileTransferEncoding::from(x))]
+    #[bw(map = |x: &FileTransferEncoding| u8::from(*x))]
+    pub data_encoding: FileTransferEncoding,
+    #[br(map = |x: u8| FileTransferMetadata::from(x))]
+    #[bw(map = |x: &FileTransferMetadata| u8::from(*x))]
+    pub metadata_type: FileTransferMetadata,
+    #[br(temp)]
+    #[bw(calc = metadata.len() as u16)]
+    metadata_length: u16,
+    #[br(count = metadata_length)]
+    pub metadata: Vec<u8>,
+    #[br(parse_with = binrw::helpers::until_eof)]
+    pub data: Vec<u8>,
+}
+
+impl FileTransferRequest {
+    /// Ask for the file `name`, from `data_index` onwards
+    pub fn for_file(unique_id: u32, name: &str, data_index: u32) -> Self {
+        Self {
+            unique_id,
+            data_index,
+            response_required: true,
+            data_encoding: FileTransferEncoding::Binary,
+            metadata_type: FileTransferMetadata::Utf8Filename,
+            metadata: name.as_bytes().to_vec(),
+            data: Vec::new(),
+        }
+    }
+
+    /// The file name, for UTF-8 file name metadata
+    pub fn filename(&self) -> Option<&str> {
+        match self.metadata_type {
+            FileTransferMetadata::Utf8Filename => std::str::from_utf8(&self.metadata).ok(),
+            _ => None,
+        }
+    }
+}
+
+/// File transfer response codes
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum FileTransferResponse {
+    /// Every byte up to `data_index` arrived and the file is complete
+    Completed,
+    /// Data up to `data_index` arrived; send on from there
+    Ready,
+    /// Data did not start at the expected index; resend from `data_index`
+    IndexMismatch,
+    /// Codes for failures not known to this library
+    Other(u8),
+}
+
+impl FileTransferResponse {
+    pub fn is_error(&self) -> bool {
+        matches!(self, Self::Other(_))
+    }
+}
+
+impl From<u8> for FileTransferResponse {
+    fn from(v: u8) -> Self {
+        match v {
+            0 => Self::Completed,
+            1 => Self::Ready,
+            2 => Self::IndexMismatch,
+            other => Self::Other(other),
+        }
+    }
+}
+
+impl From<FileTransferResponse> for u8 {
+    fn from(response: FileTransferResponse) -> Self {
+        match response {
+            FileTransferResponse::Completed => 0,
+            FileTransferResponse::Ready => 1,
+            FileTransferResponse::IndexMismatch => 2,
+            FileTransferResponse::Other(code) => code,
+        }
+    }
+}
+
+/// File transfer acknowledge packet (Packet ID 7, Length 9) - Read/Write
+///
+/// Flow control and completion status for a transfer, sent by whichever
+/// side is receiving the data
+#[derive(Debug, Clone, PartialEq, BinRead, BinWrite)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[brw(little)]
+pub struct FileTransferAcknowledge {
+    pub unique_id: u32,
+    /// Bytes received so far, so the next index expected
+    pub data_index: u32,
+    #[br(map = |x: u8| FileTransferResponse::from(x))]
+    #[bw(map = |x: &FileTransferResponse| u8::from(*x))]
+    pub response: FileTransferResponse,
+}
+
+/// File transfer packet (Packet ID 8, Variable length) - Read/Write
+///
+/// One chunk of a transfer's data. Named for its contents so the
+/// [`FileTransfer`](crate::ops::FileTransfer) workflow can take the packet's
+/// name.
+#[derive(Debug, Clone, PartialEq, BinRead, BinWrite)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[brw(little)]
+pub struct FileTransferData {
+    pub unique_id: u32,
+    /// Byte offset of `data` within the file
+    pub data_index: u32,
+    #[br(map = |x: u8| x != 0)]
+    #[bw(map = |x: &bool| *x as u8)]
+    pub response_required: bool,
+    #[br(parse_with = binrw::helpers::until_eof)]
+    pub data: Vec<u8>,
+}
+
+impl FileTransferData {
+    /// Most data bytes that fit in one packet
+    pub const MAX_DATA_LEN: usize = crate::wire::MAX_PAYLOAD_LEN - 9;
+}
+
+/// Device port that passthrough data is routed to or received from
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum PassthroughRoute {
+    #[default]
+    Unknown = 0,
+    /// GPIO 1 and 2 used as a serial port
+    Gpio = 1,
+    /// Auxiliary RS232 port
+    AuxiliaryRs232 = 2,
+}
+
+impl From<u8> for PassthroughRoute {
+    fn from(v: u8) -> Self {
+        match v {
+            1 => Self::Gpio,
+            2 => Self::AuxiliaryRs232,
+            _ => Self::Unknown,
+        }
+    }
+}
+
+/// Serial port passthrough packet (Packet ID 10, Variable length) - Read/Write
+///
+/// Tunnels raw bytes to and from one of the device's auxiliary serial
+/// ports. Sent to the device, the data is written out of the port; data
+/// arriving on the port comes back in packets of this type.
+#[derive(Debug, Clone, PartialEq, BinRead, BinWrite)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[brw(little)]
+pub struct SerialPortPassthrough {
+    #[br(map = |x: u8| PassthroughRoute::from(x))]
+    #[bw(map = |x: &PassthroughRoute| *x as u8)]
+    pub route: PassthroughRoute,
+    #[br(parse_with = binrw::helpers::until_eof)]
+    pub data: Vec<u8>,
+}
+
+impl SerialPortPassthrough {
+    /// Most data bytes that fit in one packet, after the route byte
+    pub const MAX_DATA_LEN: usize = crate::wire::MAX_PAYLOAD_LEN - 1;
+
+    /// Split a byte stream into as many packets as it takes to send it
+    pub fn chunks(route: PassthroughRoute, data: &[u8]) -> Vec<Self> {
+        data.chunks(Self::MAX_DATA_LEN)
+            .map(|chunk| Self { route, data: chunk.to_vec() })
+            .collect()
+    }
+}
+
+/// Reassembles the byte stream carried by received passthrough packets,
+/// one stream per route.
+///
+/// # Examples
+///
+/// ```
+/// use liban::{PassthroughBuffer, PassthroughRoute, SerialPortPassthrough};
+///
+/// let mut buffer = PassthroughBuffer::new();
+/// for part in [&b"$GPGGA,1"[..], &b"23519*45\r\n$GP"[..]] {
+///     buffer.push(&SerialPortPassthrough { route: PassthroughRoute::AuxiliaryRs232, data: part.to_vec() });
+/// }
+/// let line = buffer.next_line(PassthroughRoute::AuxiliaryRs232).unwrap();
+/// assert_eq!(line, b"$GPGGA,123519*45\r\n");
+/// assert_eq!(buffer.pending(PassthroughRoute::AuxiliaryRs232), b"$GP");
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct PassthroughBuffer {
+    streams: HashMap<PassthroughRoute, Vec<u8>>,
+}
+
+impl PassthroughBuffer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Append the data of a received packet to its route's stream
+    pub fn push(&mut self, packet: &SerialPortPassthrough) {
+        self.streams.entry(packet.route).or_default().extend_from_slice(&packet.data);
+    }
+
+    /// Bytes received on a route and not yet taken
+    pub fn pending(&self, route: PassthroughRoute) -> &[u8] {
+        self.streams.get(&route).map_or(&[], Vec::as_slice)
+    }
+
+    /// Take everything received on a route so far
+    pub fn take(&mut self, route: PassthroughRoute) -> Vec<u8> {
+        self.streams.remove(&route).unwrap_or_default()
+    }
+
+    /// Take the next complete line, including its `\n` terminator, as
+    /// NMEA and most text protocols frame their sentences
+    pub fn next_line(&mut self, route: PassthroughRoute) -> Option<Vec<u8>> {
+        let stream = self.streams.get_mut(&route)?;
+        let end = stream.iter().position(|&b| b == b'\n')? + 1;
+        Some(stream.drain(..end).collect())
+    }
+}
+
 /// IP configuration packet (Packet ID 11, Length 30) - Read/Write
-#[derive(Debug, Clone, PartialEq, BinRead, BinWrite, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, BinRead, BinWrite)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 #[brw(little)]
 pub struct IpConfiguration {
     #[br(map = |x: u8| x != 0)]
     #[bw(map = |x: &bool| *x as u8)]
     pub permanent: bool,
     pub dhcp_mode: u8,
-    pub ip_address: u32,
-    pub ip_netmask: u32,
-    pub ip_gateway: u32,
-    pub dns_server: u32,
+    #[br(map = |x: u32| ipv4_from_wire(x))]
+    #[bw(map = |x: &Ipv4Addr| ipv4_to_wire(x))]
+    #[cfg_attr(feature = "defmt", defmt(Debug2Format))]
+    pub ip_address: Ipv4Addr,
+    #[br(map = |x: u32| ipv4_from_wire(x))]
+    #[bw(map = |x: &Ipv4Addr| ipv4_to_wire(x))]
+    #[cfg_attr(feature = "defmt", defmt(Debug2Format))]
+    pub ip_netmask: Ipv4Addr,
+    #[br(map = |x: u32| ipv4_from_wire(x))]
+    #[bw(map = |x: &Ipv4Addr| ipv4_to_wire(x))]
+    #[cfg_attr(feature = "defmt", defmt(Debug2Format))]
+    pub ip_gateway: Ipv4Addr,
+    #[br(map = |x: u32| ipv4_from_wire(x))]
+    #[bw(map = |x: &Ipv4Addr| ipv4_to_wire(x))]
+    #[cfg_attr(feature = "defmt", defmt(Debug2Format))]
+    pub dns_server: Ipv4Addr,
     pub boreas_serial_number_part_1: u32,
     pub boreas_serial_number_part_2: u32,
     pub boreas_serial_number_part_3: u32,
 }
 
+/// An IPv4 network given by any address in it and its netmask
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Ipv4Subnet {
+    #[cfg_attr(feature = "defmt", defmt(Debug2Format))]
+    pub address: Ipv4Addr,
+    #[cfg_attr(feature = "defmt", defmt(Debug2Format))]
+    pub netmask: Ipv4Addr,
+}
+
+impl Ipv4Subnet {
+    pub fn new(address: Ipv4Addr, netmask: Ipv4Addr) -> Self {
+        Self { address, netmask }
+    }
+
+    /// Network with a prefix length, e.g. `/24`
+    pub fn with_prefix(address: Ipv4Addr, prefix_len: u8) -> Self {
+        let mask = u32::MAX.checked_shl(32 - u32::from(prefix_len.min(32))).unwrap_or(0);
+        Self::new(address, Ipv4Addr::from(mask))
+    }
+
+    /// Number of leading one bits, `None` unless the netmask is contiguous
+    pub fn prefix_len(&self) -> Option<u8> {
+        let mask = u32::from(self.netmask);
+        let ones = mask.leading_ones();
+        (mask.checked_shl(ones).unwrap_or(0) == 0).then_some(ones as u8)
+    }
+
+    /// The all-zeros host address
+    pub fn network(&self) -> Ipv4Addr {
+        Ipv4Addr::from(u32::from(self.address) & u32::from(self.netmask))
+    }
+
+    /// The all-ones host address
+    pub fn broadcast(&self) -> Ipv4Addr {
+        Ipv4Addr::from(u32::from(self.address) | !u32::from(self.netmask))
+    }
+
+    pub fn contains(&self, address: Ipv4Addr) -> bool {
+        u32::from(address) & u32::from(self.netmask) == u32::from(self.network())
+    }
+}
+
+impl std::fmt::Display for Ipv4Subnet {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.prefix_len() {
+            Some(prefix) => write!(f, "{}/{}", self.network(), prefix),
+            None => write!(f, "{} netmask {}", self.network(), self.netmask),
+        }
+    }
+}
+
+impl IpConfiguration {
+    /// The device leases its address from a DHCP server
+    pub fn dhcp_enabled(&self) -> bool {
+        self.dhcp_mode != 0
+    }
+
+    /// The static address and netmask
+    pub fn subnet(&self) -> Ipv4Subnet {
+        Ipv4Subnet::new(self.ip_address, self.ip_netmask)
+    }
+
+    /// Check the static address settings are usable: a contiguous netmask
+    /// of at most /30, a host address that is neither the network nor the
+    /// broadcast address, and a gateway (if set) on the same subnet. With
+    /// DHCP enabled the server assigns the address, so nothing is checked.
+    pub fn validate(&self) -> Result<(), AnError> {
+        if self.dhcp_enabled() {
+            return Ok(());
+        }
+        let subnet = self.subnet();
+        match subnet.prefix_len() {
+            Some(1..=30) => {}
+            _ => return Err(AnError::ValidationFailed(format!(
+                "netmask {} must be contiguous and between /1 and /30", self.ip_netmask))),
+        }
+        let address = self.ip_address;
+        if address.is_unspecified() || address.is_loopback() || address.is_multicast() || address.is_broadcast() {
+            return Err(AnError::ValidationFailed(format!("{} is not a host address", address)));
+        }
+        if address == subnet.network() || address == subnet.broadcast() {
+            return Err(AnError::ValidationFailed(format!(
+                "{} is the network or broadcast address of {}", address, subnet)));
+        }
+        let gateway = self.ip_gateway;
+        if !gateway.is_unspecified()
+            && (!subnet.contains(gateway) || gateway == address
+                || gateway == subnet.network() || gateway == subnet.broadcast())
+        {
+            return Err(AnError::ValidationFailed(format!(
+                "gateway {} is not another host on {}", gateway, subnet)));
+        }
+        Ok(())
+    }
+
+    /// [`validate`](Self::validate), and check the new address is on
+    /// `current`, the subnet the host reaches the device on now, so the
+    /// device stays reachable after the change
+    pub fn validate_reachable_from(&self, current: Ipv4Subnet) -> Result<(), AnError> {
+        self.validate()?;
+        if !self.dhcp_enabled() && !current.contains(self.ip_address) {
+            return Err(AnError::ValidationFailed(format!(
+                "{} is outside {}, the device would be unreachable", self.ip_address, current)));
+        }
+        Ok(())
+    }
+}
+
+/// One subcomponent within SubcomponentInformation (24 bytes), laid out
+/// like Device Information
+#[derive(Debug, Clone, PartialEq, BinRead, BinWrite)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[brw(little)]
+pub struct Subcomponent {
+    pub software_version: u32,
+    /// Subcomponent device ID, not necessarily a [`DeviceType`]
+    pub device_id: u32,
+    pub hardware_revision: u32,
+    pub serial_number_1: u32,
+    pub serial_number_2: u32,
+    pub serial_number_3: u32,
+}
+
+impl Subcomponent {
+    pub const LEN: usize = 24;
+
+    pub fn software(&self) -> Version {
+        Version::from_raw(self.software_version)
+    }
+
+    pub fn hardware(&self) -> Version {
+        Version::from_raw(self.hardware_revision)
+    }
+}
+
+/// Subcomponent information packet (Packet ID 14, Variable length) - Read only
+///
+/// One 24 byte record per internal subcomponent, such as a GNSS receiver
+/// board. Payloads that are not a whole number of records are rejected.
+#[derive(Debug, Clone, PartialEq, BinRead, BinWrite)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[brw(little)]
+pub struct SubcomponentInformation {
+    #[br(parse_with = subcomponent_records)]
+    pub subcomponents: Vec<Subcomponent>,
+}
+
+/// Whole records up to the end of the payload
+#[binrw::parser(reader)]
+fn subcomponent_records() -> binrw::BinResult<Vec<Subcomponent>> {
+    use std::io::SeekFrom;
+
+    let start = reader.stream_position()?;
+    let len = reader.seek(SeekFrom::End(0))? - start;
+    reader.seek(SeekFrom::Start(start))?;
+    if len % Subcomponent::LEN as u64 != 0 {
+        return Err(binrw::Error::AssertFail {
+            pos: start,
+            message: format!("{} bytes is not a whole number of {} byte subcomponent records", len, Subcomponent::LEN),
+        });
+    }
+    (0..len / Subcomponent::LEN as u64).map(|_| Subcomponent::read_le(reader)).collect()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::packet::Packet;
 
     #[test]
     fn test_acknowledge_round_trip() {
@@ -181,10 +1003,33 @@ mod tests {
     }
 
     #[test]
-    fn test_request_round_trip() {
-        let req = Request {
-            requested_packet: PacketKind::SystemState,
+    fn test_acknowledge_result_codes() {
+        for code in 0..=7u8 {
+            assert_eq!(u8::from(AcknowledgeResult::from(code)), code);
+        }
+        assert_eq!(AcknowledgeResult::from(2), AcknowledgeResult::PacketSizeIncorrect);
+        assert_eq!(AcknowledgeResult::from(6), AcknowledgeResult::UnknownPacket);
+        assert_eq!(AcknowledgeResult::from(42), AcknowledgeResult::Other(42));
+    }
+
+    #[test]
+    fn test_acknowledge_is_retryable() {
+        let ack = |result| Acknowledge {
+            acknowledged_packet: PacketKind::FilterOptions,
+            packet_crc: 0,
+            result,
         };
+        assert!(ack(AcknowledgeResult::CrcError).is_retryable());
+        assert!(ack(AcknowledgeResult::SystemNotReady).is_retryable());
+        assert!(!ack(AcknowledgeResult::Success).is_retryable());
+        assert!(!ack(AcknowledgeResult::ValuesOutOfRange).is_retryable());
+        assert!(!ack(AcknowledgeResult::UnknownPacket).is_retryable());
+        assert!(ack(AcknowledgeResult::Success).is_success());
+    }
+
+    #[test]
+    fn test_request_round_trip() {
+        let req = Request::single(PacketKind::SystemState);
 
         let mut cursor = std::io::Cursor::new(Vec::new());
         req.write_le(&mut cursor).unwrap();
@@ -197,6 +1042,20 @@ mod tests {
         assert_eq!(req, deserialized);
     }
 
+    #[test]
+    fn test_request_multiple_round_trip() {
+        let req = Request::multiple([PacketKind::DeviceInformation, PacketKind::Status, PacketKind::FilterOptions]);
+
+        let mut cursor = std::io::Cursor::new(Vec::new());
+        req.write_le(&mut cursor).unwrap();
+        let bytes = cursor.into_inner();
+        assert_eq!(bytes, vec![3, 23, 186]);
+
+        let mut cursor = std::io::Cursor::new(&bytes);
+        let deserialized = Request::read_le(&mut cursor).unwrap();
+        assert_eq!(req, deserialized);
+    }
+
     #[test]
     fn test_restore_factory_settings_write() {
         let packet = RestoreFactorySettings {};
@@ -224,10 +1083,10 @@ mod tests {
         let ip = IpConfiguration {
             permanent: true,
             dhcp_mode: 0,
-            ip_address: u32::from(std::net::Ipv4Addr::new(192, 168, 1, 100)),
-            ip_netmask: u32::from(std::net::Ipv4Addr::new(255, 255, 255, 0)),
-            ip_gateway: u32::from(std::net::Ipv4Addr::new(192, 168, 1, 1)),
-            dns_server: u32::from(std::net::Ipv4Addr::new(8, 8, 8, 8)),
+            ip_address: Ipv4Addr::new(192, 168, 1, 100),
+            ip_netmask: Ipv4Addr::new(255, 255, 255, 0),
+            ip_gateway: Ipv4Addr::new(192, 168, 1, 1),
+            dns_server: Ipv4Addr::new(8, 8, 8, 8),
             boreas_serial_number_part_1: 123456,
             boreas_serial_number_part_2: 789012,
             boreas_serial_number_part_3: 345678,
@@ -242,6 +1101,40 @@ mod tests {
         let deserialized = IpConfiguration::read_le(&mut cursor).unwrap();
         assert_eq!(ip, deserialized);
     }
+
+    #[test]
+    fn test_serial_port_passthrough_round_trip() {
+        let packet = SerialPortPassthrough {
+            route: PassthroughRoute::AuxiliaryRs232,
+            data: b"$GPRMC\r\n".to_vec(),
+        };
+
+        let mut cursor = std::io::Cursor::new(Vec::new());
+        packet.write_le(&mut cursor).unwrap();
+        let bytes = cursor.into_inner();
+        assert_eq!(bytes[0], 2);
+        assert_eq!(&bytes[1..], b"$GPRMC\r\n");
+
+        let mut cursor = std::io::Cursor::new(&bytes);
+        assert_eq!(SerialPortPassthrough::read_le(&mut cursor).unwrap(), packet);
+    }
+
+    #[test]
+    fn test_serial_port_passthrough_chunks() {
+        let data: Vec<u8> = (0..600).map(|i| i as u8).collect();
+        let packets = SerialPortPassthrough::chunks(PassthroughRoute::Gpio, &data);
+        assert_eq!(packets.len(), 3);
+        assert_eq!(packets[0].data.len(), SerialPortPassthrough::MAX_DATA_LEN);
+        for packet in &packets {
+            assert!(Packet::SerialPortPassthrough(packet.clone()).to_bytes().is_ok());
+        }
+
+        let mut buffer = PassthroughBuffer::new();
+        packets.iter().for_each(|p| buffer.push(p));
+        assert_eq!(buffer.next_line(PassthroughRoute::AuxiliaryRs232), None);
+        assert_eq!(buffer.take(PassthroughRoute::Gpio), data);
+        assert!(buffer.pending(PassthroughRoute::Gpio).is_empty());
+    }
 }
 
 #[cfg(test)]
@@ -1,14 +1,23 @@
 #[cfg(test)]
 mod tests {
     use crate::packet::config::{
-        PacketTimerPeriod, PacketsPeriod, PacketPeriod,
+        PacketTimerPeriod, PacketsPeriod, PacketPeriod, BaudRate, BaudRates,
         InstallationAlignment, OffsetVector, FilterOptions, VehicleType,
         OdometerConfiguration, SetZeroOrientationAlignment,
         ReferencePointOffsets, DualAntennaConfiguration,
         OffsetType, AutomaticOffsetOrientation,
         IpDataportsConfiguration, IpDataport, IpDataportMode,
+        GpioFunction, GpioOutputRate, GpioOutputRates,
+        GpioConfiguration, GpioOutputConfiguration, GpioInputConfiguration,
+        MagneticCalibrationValues, MagneticCalibrationConfiguration, MagneticCalibrationAction,
+        MagneticCalibrationStatus, MagneticCalibrationStatusCode,
+        VesselMotionConfiguration, HeavePointMask, LinkBudget, FilterIssue, IssueSeverity,
+        HeavePointId,
     };
+    use crate::packet::system::{DeviceModel, DeviceFamily, DeviceType};
+    use std::net::{Ipv4Addr, SocketAddrV4};
     use crate::packet::PacketKind;
+    use crate::AnError;
     use binrw::{BinRead, BinWrite};
     use std::time::Duration;
 
@@ -17,7 +26,7 @@ mod tests {
         let packet = PacketTimerPeriod {
             permanent: true,
             utc_synchronisation: true,
-            packet_timer_period: Duration::from_millis(10000),
+            packet_timer_period: Duration::from_millis(10),
         };
 
         let mut cursor = std::io::Cursor::new(Vec::new());
@@ -26,6 +35,28 @@ mod tests {
         assert_eq!(bytes.len(), 4, "PacketTimerPeriod should be 4 bytes");
     }
 
+    #[test]
+    fn test_packet_timer_period_microseconds() {
+        let timer = PacketTimerPeriod::new(Duration::from_micros(2_500)).unwrap();
+        let mut cursor = std::io::Cursor::new(Vec::new());
+        timer.write_le(&mut cursor).unwrap();
+        let bytes = cursor.into_inner();
+        assert_eq!(&bytes[2..], &2_500u16.to_le_bytes());
+
+        let mut cursor = std::io::Cursor::new(&bytes);
+        assert_eq!(PacketTimerPeriod::read_le(&mut cursor).unwrap(), timer);
+        assert_eq!(timer.rate_hz(), 400.0);
+
+        let timer = PacketTimerPeriod::from_rate_hz(300.0).unwrap();
+        assert_eq!(timer.packet_timer_period, Duration::from_micros(3_333));
+
+        assert!(PacketTimerPeriod::from_rate_hz(1000.0).is_ok());
+        assert!(PacketTimerPeriod::from_rate_hz(2000.0).is_err());
+        assert!(PacketTimerPeriod::from_rate_hz(10.0).is_err());
+        assert!(PacketTimerPeriod::from_rate_hz(0.0).is_err());
+        assert!(PacketTimerPeriod::new(Duration::from_nanos(1_500_500)).is_err());
+    }
+
     #[test]
     fn test_packets_period_packet_length_empty() {
         let packet = PacketsPeriod {
@@ -46,8 +77,8 @@ mod tests {
             permanent: true,
             clear_existing: false,
             packet_periods: vec![
-                PacketPeriod { packet_type: PacketKind::SystemState, period: Duration::from_millis(1000) },
-                PacketPeriod { packet_type: PacketKind::UnixTime, period: Duration::from_millis(2000) },
+                PacketPeriod { packet_type: PacketKind::SystemState, divisor: 1000 },
+                PacketPeriod { packet_type: PacketKind::UnixTime, divisor: 2000 },
             ],
         };
 
@@ -63,9 +94,9 @@ mod tests {
             permanent: true,
             clear_existing: true,
             packet_periods: vec![
-                PacketPeriod { packet_type: PacketKind::PacketTimerPeriod, period: Duration::from_millis(10000) },
-                PacketPeriod { packet_type: PacketKind::FilterOptions, period: Duration::from_millis(20000) },
-                PacketPeriod { packet_type: PacketKind::IpDataportsConfiguration, period: Duration::from_millis(5000) },
+                PacketPeriod { packet_type: PacketKind::PacketTimerPeriod, divisor: 10000 },
+                PacketPeriod { packet_type: PacketKind::FilterOptions, divisor: 20000 },
+                PacketPeriod { packet_type: PacketKind::IpDataportsConfiguration, divisor: 5000 },
             ],
         };
 
@@ -82,6 +113,79 @@ mod tests {
         assert_eq!(deserialized.packet_periods, original.packet_periods);
     }
 
+    #[test]
+    fn test_packets_period_bandwidth() {
+        let periods = PacketsPeriod {
+            permanent: false,
+            clear_existing: true,
+            packet_periods: vec![
+                PacketPeriod { packet_type: PacketKind::SystemState, divisor: 10 },
+                PacketPeriod { packet_type: PacketKind::UnixTime, divisor: 100 },
+                PacketPeriod { packet_type: PacketKind::DetailedSatellites, divisor: 0 },
+            ],
+        };
+        let timer = |ms| PacketTimerPeriod {
+            permanent: false,
+            utc_synchronisation: true,
+            packet_timer_period: Duration::from_millis(ms),
+        };
+
+        // 105 bytes at 100 Hz plus 13 bytes at 10 Hz; disabled entries cost nothing
+        let bandwidth = periods.estimated_bandwidth(&timer(1));
+        assert!((bandwidth.bytes_per_second - 10_630.0).abs() < 1e-6);
+        assert!(bandwidth.variable_length.is_empty());
+        assert!((periods.estimated_bandwidth(&timer(2)).bytes_per_second - 5_315.0).abs() < 1e-6);
+
+        assert!(periods.validate_bandwidth(&timer(1), LinkBudget::Serial(BaudRate::B115200)).is_ok());
+        assert!(periods.validate_bandwidth(&timer(1), LinkBudget::Serial(BaudRate::B57600)).is_err());
+        assert!(periods.validate_bandwidth(&timer(2), LinkBudget::Serial(BaudRate::B57600)).is_ok());
+        assert!(bandwidth.utilisation(LinkBudget::Ethernet) < 0.02);
+    }
+
+    #[test]
+    fn test_baud_rates_packet_length() {
+        let packet = BaudRates {
+            permanent: true,
+            primary_port: BaudRate::B115200,
+            gpio_port: BaudRate::B9600,
+            auxiliary_port: BaudRate::B921600,
+        };
+
+        let mut cursor = std::io::Cursor::new(Vec::new());
+        packet.write_le(&mut cursor).expect("Failed to serialize");
+        let bytes = cursor.into_inner();
+        assert_eq!(bytes.len(), 17, "BaudRates should be 17 bytes");
+        assert_eq!(u32::from_le_bytes([bytes[1], bytes[2], bytes[3], bytes[4]]), 115200);
+    }
+
+    #[test]
+    fn test_baud_rates_packet_round_trip() {
+        let original = BaudRates {
+            permanent: false,
+            primary_port: BaudRate::B2000000,
+            gpio_port: BaudRate::B38400,
+            auxiliary_port: BaudRate::B2400,
+        };
+
+        let mut cursor = std::io::Cursor::new(Vec::new());
+        original.write_le(&mut cursor).unwrap();
+        let bytes = cursor.into_inner();
+        let mut cursor = std::io::Cursor::new(&bytes);
+        assert_eq!(BaudRates::read_le(&mut cursor).unwrap(), original);
+    }
+
+    #[test]
+    fn test_baud_rates_rejects_unsupported_rate() {
+        let mut bytes = vec![0u8];
+        for rate in [115200u32, 12345, 9600, 0] {
+            bytes.extend_from_slice(&rate.to_le_bytes());
+        }
+        let mut cursor = std::io::Cursor::new(&bytes);
+        assert!(BaudRates::read_le(&mut cursor).is_err());
+        assert!(BaudRate::try_from(12345).is_err());
+        assert_eq!(BaudRate::try_from(460800).unwrap(), BaudRate::B460800);
+    }
+
     #[test]
     fn test_installation_alignment_packet_length() {
         let packet = InstallationAlignment {
@@ -156,22 +260,43 @@ mod tests {
         assert_eq!(bytes.len(), 49, "ReferencePointOffsets should be 49 bytes");
     }
 
+    #[test]
+    fn test_reference_point_offsets_round_trip() {
+        let points = [
+            OffsetVector { x: 1.0, y: 2.0, z: 3.0 },
+            OffsetVector { x: 4.0, y: 5.0, z: 6.0 },
+            OffsetVector { x: 7.0, y: 8.0, z: 9.0 },
+            OffsetVector { x: 10.0, y: 11.0, z: 12.0 },
+        ];
+        let original = ReferencePointOffsets::new(false, points.clone());
+        assert_eq!(original.primary(), &points[0]);
+        assert_eq!(original.cog_lever_arm(), &points[1]);
+        assert_eq!(original.heave3(), &points[2]);
+        assert_eq!(original.heave4(), &points[3]);
+
+        let mut cursor = std::io::Cursor::new(Vec::new());
+        original.write_le(&mut cursor).unwrap();
+        let bytes = cursor.into_inner();
+        assert_eq!(&bytes[37..41], &10.0f32.to_le_bytes());
+
+        let mut cursor = std::io::Cursor::new(&bytes);
+        let decoded = ReferencePointOffsets::read_le(&mut cursor).unwrap();
+        assert_eq!(decoded, original);
+        assert_eq!(<[OffsetVector; 4]>::from(&decoded), points);
+    }
+
     #[test]
     fn test_ip_dataports_configuration_packet_length() {
-        let disabled_entry = IpDataport {
-            ip_address: 0,
-            port: 0,
-            mode: IpDataportMode::Disabled,
-        };
+        let disabled_entry = IpDataport::DISABLED;
 
-        let tcp_server_entry = IpDataport {
-            ip_address: 0,
-            port: 17000,
-            mode: IpDataportMode::TcpServer,
-        };
+        let tcp_server_entry = IpDataport::new(
+            IpDataportMode::TcpServer,
+            SocketAddrV4::new(Ipv4Addr::UNSPECIFIED, 17000),
+        );
 
         let packet = IpDataportsConfiguration {
             dataports: [disabled_entry, tcp_server_entry, disabled_entry, disabled_entry],
+            extension: None,
         };
 
         let mut cursor = std::io::Cursor::new(Vec::new());
@@ -184,17 +309,20 @@ mod tests {
     fn test_ip_dataports_configuration_packet_round_trip() {
         let original = IpDataportsConfiguration {
             dataports: [
-                IpDataport { ip_address: 0, port: 0, mode: IpDataportMode::Disabled },
-                IpDataport { ip_address: 0, port: 8080, mode: IpDataportMode::TcpServer },
-                IpDataport { ip_address: u32::from(std::net::Ipv4Addr::new(192, 168, 1, 1)), port: 9090, mode: IpDataportMode::TcpClient },
-                IpDataport { ip_address: u32::from(std::net::Ipv4Addr::new(172, 16, 0, 1)), port: 5000, mode: IpDataportMode::UdpClient },
+                IpDataport::new(IpDataportMode::Disabled, SocketAddrV4::new(Ipv4Addr::UNSPECIFIED, 0)),
+                IpDataport::new(IpDataportMode::TcpServer, SocketAddrV4::new(Ipv4Addr::UNSPECIFIED, 8080)),
+                IpDataport::new(IpDataportMode::TcpClient, SocketAddrV4::new(Ipv4Addr::new(192, 168, 1, 1), 9090)),
+                IpDataport::new(IpDataportMode::UdpClient, SocketAddrV4::new(Ipv4Addr::new(172, 16, 0, 1), 5000)),
             ],
+            extension: None,
         };
 
         let mut cursor = std::io::Cursor::new(Vec::new());
         original.write_le(&mut cursor).expect("Failed to serialize");
         let bytes = cursor.into_inner();
         assert_eq!(bytes.len(), 30);
+        // Third dataport: address as a little-endian u32, then the port
+        assert_eq!(&bytes[16..22], &[1, 1, 168, 192, 0x82, 0x23]);
 
         let mut cursor = std::io::Cursor::new(&bytes);
         let deserialized = IpDataportsConfiguration::read_le(&mut cursor).expect("Failed to deserialize");
@@ -261,6 +389,24 @@ mod tests {
         assert_eq!(deserialized, original);
     }
 
+    #[test]
+    fn test_dual_antenna_configuration_validate() {
+        let manual = DualAntennaConfiguration::manual(OffsetVector { x: 0.0, y: 1.2, z: 0.0 });
+        assert_eq!(manual.offset_type, OffsetType::Manual);
+        assert!((manual.baseline().meters() - 1.2).abs() < 1e-6);
+        assert!(manual.validate().is_ok());
+
+        for baseline in [0.1, 150.0, f32::NAN] {
+            let config = DualAntennaConfiguration::manual(OffsetVector { x: baseline, y: 0.0, z: 0.0 });
+            assert!(matches!(config.validate(), Err(AnError::ValidationFailed(_))), "{baseline} m accepted");
+        }
+
+        // The manual offset is ignored in automatic mode
+        let automatic = DualAntennaConfiguration::automatic(AutomaticOffsetOrientation::PrimaryLeftSecondaryRight);
+        assert!(automatic.is_automatic());
+        assert!(automatic.validate().is_ok());
+    }
+
     #[test]
     fn test_odometer_configuration_round_trip() {
         let original = OdometerConfiguration {
@@ -278,4 +424,347 @@ mod tests {
         let deserialized = OdometerConfiguration::read_le(&mut cursor).expect("Failed to deserialize");
         assert_eq!(deserialized, original);
     }
+
+    fn gpio_configuration(auxiliary_transmit: GpioFunction, auxiliary_receive: GpioFunction) -> GpioConfiguration {
+        GpioConfiguration {
+            permanent: true,
+            gpio1: GpioFunction::OnePpsOutput,
+            gpio2: GpioFunction::OdometerInput,
+            auxiliary_transmit,
+            auxiliary_receive,
+        }
+    }
+
+    #[test]
+    fn test_gpio_configuration_packet_length() {
+        let packet = gpio_configuration(GpioFunction::NmeaOutput, GpioFunction::RtcmCorrectionsInput);
+
+        let mut cursor = std::io::Cursor::new(Vec::new());
+        packet.write_le(&mut cursor).expect("Failed to serialize");
+        let bytes = cursor.into_inner();
+        assert_eq!(bytes.len(), 13, "GpioConfiguration should be 13 bytes");
+        assert_eq!(&bytes[..5], &[1, 1, 3, 7, 19]);
+    }
+
+    #[test]
+    fn test_gpio_configuration_round_trip() {
+        let original = gpio_configuration(GpioFunction::AnppOutput, GpioFunction::Other(200));
+
+        let mut cursor = std::io::Cursor::new(Vec::new());
+        original.write_le(&mut cursor).unwrap();
+        let bytes = cursor.into_inner();
+        let mut cursor = std::io::Cursor::new(&bytes);
+        assert_eq!(GpioConfiguration::read_le(&mut cursor).unwrap(), original);
+    }
+
+    #[test]
+    fn test_gpio_configuration_validation() {
+        assert!(gpio_configuration(GpioFunction::NmeaOutput, GpioFunction::NmeaInput).validate().is_ok());
+        assert!(gpio_configuration(GpioFunction::Inactive, GpioFunction::Inactive).validate().is_ok());
+        assert!(gpio_configuration(GpioFunction::NmeaInput, GpioFunction::Inactive).validate().is_err());
+        assert!(gpio_configuration(GpioFunction::Inactive, GpioFunction::AnppOutput).validate().is_err());
+        assert!(gpio_configuration(GpioFunction::Other(99), GpioFunction::Inactive).validate().is_err());
+    }
+
+    #[test]
+    fn test_gpio_output_configuration_packet_length() {
+        let rates = GpioOutputRates::new(GpioOutputRate::Hz1, GpioOutputRate::Disabled, GpioOutputRate::Hz10);
+        assert_eq!(rates.raw(), 0x0704);
+        assert_eq!(rates.auxiliary(), GpioOutputRate::Hz10);
+
+        let packet = GpioOutputConfiguration {
+            permanent: false,
+            nmea_fix_behaviour: 0,
+            gpzda: rates,
+            gpgga: rates,
+            gpvtg: GpioOutputRates::default(),
+            gprmc: rates,
+            gphdt: GpioOutputRates::default(),
+            gpgll: GpioOutputRates::default(),
+            pashr: GpioOutputRates::default(),
+            tss1: GpioOutputRates::default(),
+            simrad: GpioOutputRates::default(),
+            gprot: GpioOutputRates::default(),
+            gphev: GpioOutputRates::default(),
+            gpgsv: GpioOutputRates::default(),
+            pfecatt: GpioOutputRates::default(),
+            pfechve: GpioOutputRates::default(),
+        };
+
+        let mut cursor = std::io::Cursor::new(Vec::new());
+        packet.write_le(&mut cursor).expect("Failed to serialize");
+        let bytes = cursor.into_inner();
+        assert_eq!(bytes.len(), 33, "GpioOutputConfiguration should be 33 bytes");
+
+        let mut cursor = std::io::Cursor::new(&bytes);
+        assert_eq!(GpioOutputConfiguration::read_le(&mut cursor).unwrap(), packet);
+    }
+
+    #[test]
+    fn test_gpio_input_configuration_packet_length() {
+        let packet = GpioInputConfiguration {
+            permanent: true,
+            gimbal_radians_per_encoder_tick: 0.001,
+        };
+
+        let mut cursor = std::io::Cursor::new(Vec::new());
+        packet.write_le(&mut cursor).expect("Failed to serialize");
+        let bytes = cursor.into_inner();
+        assert_eq!(bytes.len(), 65, "GpioInputConfiguration should be 65 bytes");
+
+        let mut cursor = std::io::Cursor::new(&bytes);
+        assert_eq!(GpioInputConfiguration::read_le(&mut cursor).unwrap(), packet);
+    }
+
+    #[test]
+    fn test_magnetic_calibration_packet_lengths() {
+        let values = MagneticCalibrationValues {
+            permanent: true,
+            hard_iron_bias: OffsetVector { x: 1.0, y: 2.0, z: 3.0 },
+            soft_iron_transformation: [[1.0, 0.1, 0.0], [0.1, 1.0, 0.0], [0.0, 0.0, 1.0]],
+        };
+        let mut cursor = std::io::Cursor::new(Vec::new());
+        values.write_le(&mut cursor).expect("Failed to serialize");
+        let bytes = cursor.into_inner();
+        assert_eq!(bytes.len(), 49, "MagneticCalibrationValues should be 49 bytes");
+        let mut cursor = std::io::Cursor::new(&bytes);
+        assert_eq!(MagneticCalibrationValues::read_le(&mut cursor).unwrap(), values);
+
+        let config = MagneticCalibrationConfiguration { action: MagneticCalibrationAction::Start2D };
+        let mut cursor = std::io::Cursor::new(Vec::new());
+        config.write_le(&mut cursor).expect("Failed to serialize");
+        assert_eq!(cursor.into_inner(), vec![2]);
+
+        let mut cursor = std::io::Cursor::new(&[6u8, 55, 4]);
+        let status = MagneticCalibrationStatus::read_le(&mut cursor).unwrap();
+        assert_eq!(status.status, MagneticCalibrationStatusCode::InProgress3D);
+        assert_eq!(status.progress, 55);
+        assert_eq!(status.local_magnetic_error, 4);
+    }
+
+    #[test]
+    fn test_vessel_motion_configuration_packet_length() {
+        let packet = VesselMotionConfiguration {
+            permanent: true,
+            heave_points: HeavePointMask::first(3),
+            heave_filter_period: std::time::Duration::from_secs(15),
+        };
+
+        let mut cursor = std::io::Cursor::new(Vec::new());
+        packet.write_le(&mut cursor).expect("Failed to serialize");
+        let bytes = cursor.into_inner();
+        assert_eq!(bytes.len(), 16, "VesselMotionConfiguration should be 16 bytes");
+        assert_eq!(bytes[1], 0b0111);
+
+        let mut cursor = std::io::Cursor::new(&bytes);
+        assert_eq!(VesselMotionConfiguration::read_le(&mut cursor).unwrap(), packet);
+    }
+
+    #[test]
+    fn test_vessel_motion_configuration_out_of_range_period() {
+        let mut bytes = [0u8; 16];
+        for (value, expected) in [(f32::INFINITY, Duration::MAX), (-1.0, Duration::ZERO), (f32::NAN, Duration::ZERO)] {
+            bytes[4..8].copy_from_slice(&value.to_le_bytes());
+            let mut cursor = std::io::Cursor::new(&bytes);
+            let packet = VesselMotionConfiguration::read_le(&mut cursor).unwrap();
+            assert_eq!(packet.heave_filter_period, expected);
+        }
+    }
+
+    #[test]
+    fn test_vessel_motion_configuration_decodes_nan_and_huge_periods() {
+        let mut payload = [0u8; 16];
+        for (value, expected) in [
+            (f32::NAN, Duration::ZERO),
+            (-f32::NAN, Duration::ZERO),
+            (f32::MAX, Duration::MAX),
+            (1.0e20, Duration::MAX),
+        ] {
+            payload[4..8].copy_from_slice(&value.to_le_bytes());
+            match crate::wire::decode_payload(PacketKind::VesselMotionConfiguration.id(), &payload).unwrap() {
+                crate::Packet::VesselMotionConfiguration(packet) => assert_eq!(packet.heave_filter_period, expected),
+                other => panic!("unexpected {:?}", other),
+            }
+        }
+    }
+
+    #[test]
+    fn test_filter_options_builder() {
+        let options = FilterOptions::builder()
+            .vehicle_type(VehicleType::Boat)
+            .velocity_heading(true)
+            .reversing_detection(true)
+            .permanent(true)
+            .build()
+            .unwrap();
+        assert_eq!(options.vehicle_type, VehicleType::Boat);
+        assert!(options.permanent);
+        assert!(options.internal_gnss_enabled);
+        assert!(options.reversing_detection_enabled);
+
+        assert!(FilterOptions::builder().build().is_err());
+        assert!(FilterOptions::builder().vehicle_type(VehicleType::Car).reversing_detection(true).build().is_err());
+        assert!(FilterOptions::builder().vehicle_type(VehicleType::Stationary).velocity_heading(true).build().is_err());
+    }
+
+    #[test]
+    fn test_heave_point_ids_link_offsets_and_heave() {
+        let mut offsets = ReferencePointOffsets::new(false, [
+            OffsetVector { x: 0.0, y: 0.0, z: 0.0 },
+            OffsetVector { x: 1.0, y: 0.0, z: 0.5 },
+            OffsetVector { x: -12.0, y: 2.0, z: 1.0 },
+            OffsetVector { x: 20.0, y: -2.0, z: 1.0 },
+        ]);
+        assert_eq!(offsets.offset(HeavePointId::Cog), offsets.cog_lever_arm());
+        offsets.offset_mut(HeavePointId::Point4).z = 3.0;
+        assert_eq!(offsets.heave4().z, 3.0);
+
+        let heave = crate::packet::state::Heave {
+            heave_point_1: 0.1,
+            heave_point_2: 0.2,
+            heave_point_3: 0.3,
+            heave_point_4: 0.4,
+        };
+        assert!((heave.at(HeavePointId::Point3).meters() - 0.3).abs() < 1e-6);
+
+        let mask = HeavePointMask::first(1).with(HeavePointId::Point4);
+        assert_eq!(mask.raw(), 0b1001);
+        assert!(mask.contains(HeavePointId::Primary) && !mask.contains(HeavePointId::Cog));
+        assert_eq!(HeavePointId::try_from(2).unwrap(), HeavePointId::Cog);
+        assert_eq!(HeavePointId::Point4.number(), 4);
+        assert!(HeavePointId::try_from(0).is_err());
+        assert!(HeavePointId::try_from(5).is_err());
+    }
+
+    #[test]
+    fn test_filter_options_validate_for_device() {
+        let boat = FilterOptions::builder()
+            .vehicle_type(VehicleType::Boat)
+            .velocity_heading(true)
+            .build()
+            .unwrap();
+        let warnings = boat.validate_for_device(DeviceModel::BoreasD90).unwrap();
+        assert_eq!(warnings, vec![
+            FilterIssue::VelocityHeadingWithSideslip(VehicleType::Boat),
+            FilterIssue::VelocityHeadingWithDualAntenna(DeviceModel::BoreasD90),
+        ]);
+        assert!(warnings.iter().all(|w| w.severity() == IssueSeverity::Warning));
+
+        let car = FilterOptions::builder().vehicle_type(VehicleType::Car).velocity_heading(true).build().unwrap();
+        assert_eq!(car.validate_for_device(DeviceModel::Spatial).unwrap(), vec![]);
+        assert_eq!(car.validate_for_device(DeviceModel::CertusMiniA).unwrap(), vec![
+            FilterIssue::VelocityHeadingWithoutInternalGnss,
+            FilterIssue::NoInternalGnss(DeviceModel::CertusMiniA),
+        ]);
+
+        // Options decoded from the device bypass the builder's checks
+        let mut stationary = car.clone();
+        stationary.vehicle_type = VehicleType::Stationary;
+        stationary.reversing_detection_enabled = true;
+        stationary.velocity_heading_enabled = false;
+        let err = stationary.validate_for_device(DeviceModel::Spatial).unwrap_err();
+        assert!(err.to_string().contains("reversing detection requires velocity heading"), "{}", err);
+        stationary.velocity_heading_enabled = true;
+        assert!(stationary.issues_for_device(DeviceModel::Spatial).contains(&FilterIssue::VelocityHeadingWhileStationary));
+        assert!(car.validate_for_device(DeviceModel::AirDataUnit).is_err());
+    }
+
+    #[test]
+    fn test_device_model_capabilities() {
+        let model = DeviceModel::from(DeviceType::BoreasA70);
+        assert_eq!(model, DeviceModel::BoreasA70);
+        assert_eq!(model.family(), DeviceFamily::Boreas);
+        assert!(!model.has_internal_gnss());
+        assert!(DeviceModel::CertusMiniD.has_dual_antenna());
+        assert!(DeviceModel::Spatial.has_internal_gnss() && !DeviceModel::Spatial.has_dual_antenna());
+        assert_eq!(DeviceModel::from(DeviceType::from(999)), DeviceModel::Unknown);
+    }
+
+    #[test]
+    fn test_ip_dataports_configuration_builder() {
+        let config = IpDataportsConfiguration::builder()
+            .tcp_server(0, 16718)
+            .udp_client(2, std::net::Ipv4Addr::new(192, 168, 1, 10), 16719)
+            .build()
+            .unwrap();
+        assert_eq!(config.dataports[0].mode, IpDataportMode::TcpServer);
+        assert_eq!(config.dataports[0].address.port(), 16718);
+        assert_eq!(config.dataports[1].mode, IpDataportMode::Disabled);
+        assert_eq!(config.dataports[2].address, SocketAddrV4::new(Ipv4Addr::new(192, 168, 1, 10), 16719));
+
+        assert!(IpDataportsConfiguration::builder().tcp_server(4, 16718).build().is_err());
+        assert!(IpDataportsConfiguration::builder().tcp_server(0, 0).build().is_err());
+        assert!(IpDataportsConfiguration::builder()
+            .tcp_client(1, std::net::Ipv4Addr::UNSPECIFIED, 16718).build().is_err());
+
+        let endpoint: std::net::SocketAddr = "10.0.0.2:16720".parse().unwrap();
+        let config = IpDataportsConfiguration::builder()
+            .endpoint(3, IpDataportMode::TcpClient, endpoint)
+            .build()
+            .unwrap();
+        assert_eq!(config.endpoints().collect::<Vec<_>>(), vec![(3, IpDataportMode::TcpClient, endpoint)]);
+        assert!(IpDataportsConfiguration::builder()
+            .endpoint(0, IpDataportMode::UdpClient, "[fd00::1]:16718".parse().unwrap())
+            .build().is_err());
+        assert!(IpDataportsConfiguration::builder()
+            .tcp_server(0, 16718).tcp_server(1, 16718).build().is_err());
+    }
+
+    #[test]
+    fn test_installation_alignment_builder() {
+        let alignment = InstallationAlignment::builder()
+            .gnss_antenna_offset(0.5, 0.0, -1.2)
+            .build()
+            .unwrap();
+        assert_eq!(alignment.alignment_dcm[1], [0.0, 1.0, 0.0]);
+        assert_eq!(alignment.gnss_antenna_offset, OffsetVector { x: 0.5, y: 0.0, z: -1.2 });
+
+        // 90 degree yaw is fine, a scaled or mirrored matrix is not
+        assert!(InstallationAlignment::builder()
+            .alignment_dcm([[0.0, -1.0, 0.0], [1.0, 0.0, 0.0], [0.0, 0.0, 1.0]]).build().is_ok());
+        assert!(InstallationAlignment::builder()
+            .alignment_dcm([[2.0, 0.0, 0.0], [0.0, 1.0, 0.0], [0.0, 0.0, 1.0]]).build().is_err());
+        assert!(InstallationAlignment::builder()
+            .alignment_dcm([[-1.0, 0.0, 0.0], [0.0, 1.0, 0.0], [0.0, 0.0, 1.0]]).build().is_err());
+    }
+
+    #[test]
+    fn test_installation_alignment_builder_typed_inputs() {
+        use crate::units::{Angle, Length};
+
+        let alignment = InstallationAlignment::builder()
+            .alignment_euler(Angle::ZERO, Angle::ZERO, Angle::from_degrees(90.0))
+            .gnss_antenna_offset_length(Length::from_feet(1.0), Length::ZERO, Length::from_meters(-0.5))
+            .build()
+            .unwrap();
+        assert!((alignment.alignment_dcm[0][1] - 1.0).abs() < 1e-6);
+        assert!((alignment.alignment_dcm[1][0] + 1.0).abs() < 1e-6);
+        assert!((alignment.gnss_antenna_offset.x - 0.3048).abs() < 1e-6);
+        assert_eq!(alignment.gnss_antenna_offset.z, -0.5);
+    }
+
+    #[test]
+    fn test_installation_alignment_euler_round_trip() {
+        use crate::units::Angle;
+
+        let mut alignment = InstallationAlignment::builder().build().unwrap();
+        alignment.set_alignment_from_euler(Angle::from_degrees(10.0), Angle::from_degrees(-20.0), Angle::from_degrees(135.0));
+        assert!(alignment.validate().is_ok());
+
+        let (roll, pitch, yaw) = alignment.alignment_euler();
+        assert!((roll.degrees() - 10.0).abs() < 1e-4);
+        assert!((pitch.degrees() + 20.0).abs() < 1e-4);
+        assert!((yaw.degrees() - 135.0).abs() < 1e-4);
+    }
+
+    #[test]
+    fn test_installation_alignment_offset_sanity() {
+        assert!(InstallationAlignment::builder().odometer_offset(-60.0, 0.0, 1.5).build().is_ok());
+        assert!(InstallationAlignment::builder().gnss_antenna_offset(150.0, 0.0, 0.0).build().is_err());
+        assert!(InstallationAlignment::builder().external_data_offset(0.0, f32::NAN, 0.0).build().is_err());
+
+        let mut alignment = InstallationAlignment::builder().build().unwrap();
+        alignment.alignment_dcm[2][2] = f32::INFINITY;
+        assert!(alignment.validate().is_err());
+    }
 }
@@ -0,0 +1,183 @@
+//! Bit positions of every status and flag word, checked against the ANPP
+//! reference tables in one place. Each table lists the reference bit for
+//! each flag; setting that bit alone must raise that flag and no other.
+
+use crate::packet::config::{GpioOutputRate, GpioOutputRates, HeavePointMask};
+use crate::packet::state::{
+    AirDataFlags, DvlStatus, FilterStatus, GnssFixType, GnssOrientationStatus, GnssPvtStatus,
+    InterferenceStatus, NorthSeekingFlags, RawGnssStatus, SatelliteFrequencies, SpoofingStatus,
+    SystemStatus,
+};
+
+/// `flags!(Type: u16 { bit => accessor, ... })` checks every listed flag
+/// against every single bit of the word
+macro_rules! flags {
+    ($type:ident : $raw:ty { $( $bit:literal => $flag:ident ),+ $(,)? }) => {{
+        let table: &[(u32, &str, fn(&$type) -> bool)] = &[ $( ($bit, stringify!($flag), $type::$flag), )+ ];
+        for shift in 0..<$raw>::BITS {
+            let word = $type::from((1 as $raw) << shift);
+            assert_eq!(word.raw(), (1 as $raw) << shift);
+            for (bit, name, flag) in table {
+                assert_eq!(flag(&word), *bit == shift, "{}::{} with only bit {} set", stringify!($type), name, shift);
+            }
+        }
+    }};
+}
+
+#[test]
+fn test_system_status_bits() {
+    flags!(SystemStatus: u16 {
+        0 => system_failure,
+        1 => accelerometer_sensor_failure,
+        2 => gyroscope_sensor_failure,
+        3 => magnetometer_sensor_failure,
+        4 => pressure_sensor_failure,
+        5 => gnss_failure,
+        6 => accelerometer_over_range,
+        7 => gyroscope_over_range,
+        8 => magnetometer_over_range,
+        9 => pressure_over_range,
+        10 => minimum_temperature_alarm,
+        11 => maximum_temperature_alarm,
+        12 => internal_data_logging_error,
+        13 => high_voltage_alarm,
+        14 => gnss_antenna_disconnected,
+        15 => data_output_overflow_alarm,
+    });
+}
+
+#[test]
+fn test_filter_status_bits() {
+    flags!(FilterStatus: u16 {
+        0 => orientation_filter_initialised,
+        1 => navigation_filter_initialised,
+        2 => heading_initialised,
+        3 => utc_time_initialised,
+        7 => event1_flag,
+        8 => event2_flag,
+        9 => internal_gnss_enabled,
+        10 => dual_antenna_heading_active,
+        11 => velocity_heading_enabled,
+        12 => atmospheric_altitude_enabled,
+        13 => external_position_active,
+        14 => external_velocity_active,
+        15 => external_heading_active,
+    });
+    // Bits 4-6 hold the GNSS fix type
+    for fix in 0..8u16 {
+        assert_eq!(FilterStatus::from(fix << 4).gnss_fix_type() as u16, fix);
+    }
+    assert_eq!(FilterStatus::from(0xFF8F).gnss_fix_type(), GnssFixType::NoFix);
+}
+
+#[test]
+fn test_gnss_pvt_status_bits() {
+    flags!(GnssPvtStatus: u16 {
+        9 => velocity_valid,
+        10 => time_valid,
+        11 => antenna_disconnected,
+        12 => antenna_short,
+        13 => gnss_failure,
+    });
+    let status = GnssPvtStatus::from(7 | 2 << 3 | 3 << 6);
+    assert_eq!(status.gnss_fix_status(), GnssFixType::RtkFixed);
+    assert_eq!(status.spoofing_status(), SpoofingStatus::DetectedAndMitigated);
+    assert_eq!(status.interference_status(), InterferenceStatus::DetectedAndUnmitigated);
+}
+
+#[test]
+fn test_gnss_orientation_status_bits() {
+    flags!(GnssOrientationStatus: u16 {
+        3 => antenna_disconnected,
+        4 => antenna_short,
+        5 => gnss_failure,
+    });
+    let status = GnssOrientationStatus::from(6 | 1 << 6 | 2 << 9);
+    assert_eq!(status.gnss_fix_status(), GnssFixType::RtkFloat);
+    assert_eq!(status.spoofing_status(), SpoofingStatus::None);
+    assert_eq!(status.interference_status(), InterferenceStatus::DetectedAndMitigated);
+}
+
+#[test]
+fn test_raw_gnss_status_bits() {
+    flags!(RawGnssStatus: u16 {
+        3 => doppler_velocity_valid,
+        4 => time_valid,
+        5 => external_gnss,
+        6 => tilt_valid,
+        7 => heading_valid,
+        8 => floating_ambiguity_heading,
+        10 => antenna_1_disconnected,
+        11 => antenna_2_disconnected,
+        12 => antenna_1_short,
+        13 => antenna_2_short,
+        14 => gnss1_failure,
+        15 => gnss2_failure,
+    });
+    assert_eq!(RawGnssStatus::from(0xFFF8 | 2).gnss_fix_status(), GnssFixType::Fix3D);
+}
+
+#[test]
+fn test_satellite_frequency_bits() {
+    flags!(SatelliteFrequencies: u8 {
+        0 => l1_ca,
+        1 => l1_c,
+        2 => l1_p,
+        3 => l1_m,
+        4 => l2_c,
+        5 => l2_p,
+        6 => l2_m,
+        7 => l5,
+    });
+}
+
+#[test]
+fn test_air_data_flag_bits() {
+    flags!(AirDataFlags: u8 {
+        0 => barometric_altitude_valid,
+        1 => airspeed_valid,
+        2 => barometric_altitude_reference_reset,
+    });
+}
+
+#[test]
+fn test_dvl_status_bits() {
+    flags!(DvlStatus: u32 {
+        0 => bottom_velocity_valid,
+        1 => water_velocity_valid,
+        2 => temperature_valid,
+        3 => depth_valid,
+        4 => altitude_valid,
+    });
+}
+
+#[test]
+fn test_north_seeking_flag_bits() {
+    flags!(NorthSeekingFlags: u16 {
+        0 => initialised,
+        1 => position_error,
+        2 => excessive_roll,
+        3 => excessive_pitch,
+        4 => excessive_movement,
+    });
+}
+
+#[test]
+fn test_heave_point_bits() {
+    flags!(HeavePointMask: u8 {
+        0 => heave_point_1,
+        1 => heave_point_2,
+        2 => heave_point_3,
+        3 => heave_point_4,
+    });
+    assert_eq!(HeavePointMask::first(4).raw(), 0x0F);
+}
+
+#[test]
+fn test_gpio_output_rate_nibbles() {
+    let rates = GpioOutputRates::from(0x0321);
+    assert_eq!(rates.gpio1(), GpioOutputRate::Hz0_1);
+    assert_eq!(rates.gpio2(), GpioOutputRate::Hz0_2);
+    assert_eq!(rates.auxiliary(), GpioOutputRate::Hz0_5);
+    assert_eq!(GpioOutputRates::new(rates.gpio1(), rates.gpio2(), rates.auxiliary()), rates);
+}
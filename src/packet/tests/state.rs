@@ -1,11 +1,16 @@
 #[cfg(test)]
 mod tests {
     use crate::packet::state::{
-        SystemState, UnixTime, Status, PositionStdDev, VelocityStdDev,
+        SystemState, UnixTime, FormattedTime, Status, PositionStdDev, VelocityStdDev,
         EulerOrientationStdDev, RawSensors, SensorTemperature,
+        NorthSeekingStatus, NorthSeekingFlags,
         GnssPositionVelocityTime, GnssOrientation,
         SystemStatus, FilterStatus, GnssPvtStatus, GnssOrientationStatus,
+        DetailedSatellites, SatelliteInfo, SatelliteSystem, SatelliteFrequencies,
+        Wind, ExternalAirData, OdometerState, OdometerTracker, ExternalDepth,
+        Acceleration, BodyAcceleration, AngularAcceleration, EulerOrientation,
     };
+    use crate::units::{Length, Radians};
     use binrw::{BinRead, BinWrite};
 
     #[test]
@@ -25,15 +30,16 @@ mod tests {
             body_acceleration_y: -0.01,
             body_acceleration_z: 9.81,
             g_force: 1.0,
-            roll: 0.26179939,
-            pitch: 0.17453293,
-            heading: 1.5707963,
+            roll: Radians(0.26179939),
+            pitch: Radians(0.17453293),
+            heading: Radians(1.5707963),
             angular_velocity_x: 0.001,
             angular_velocity_y: 0.002,
             angular_velocity_z: 0.003,
             latitude_std_dev: 0.5,
             longitude_std_dev: 0.6,
             height_std_dev: 1.0,
+            extension: None,
         };
 
         let mut cursor = std::io::Cursor::new(Vec::new());
@@ -60,6 +66,7 @@ mod tests {
         let packet = Status {
             system_status: SystemStatus::from(0b0001_0000_0010_0000u16), // gnss_failure + internal_data_logging_error
             filter_status: FilterStatus::from(0b0000_0000_0000_0101u16), // orientation + heading
+            extension: None,
         };
 
         let mut cursor = std::io::Cursor::new(Vec::new());
@@ -99,9 +106,9 @@ mod tests {
     #[test]
     fn test_euler_orientation_std_dev_packet_length() {
         let packet = EulerOrientationStdDev {
-            roll_std_dev: 0.01,
-            pitch_std_dev: 0.015,
-            heading_std_dev: 0.02,
+            roll_std_dev: Radians(0.01),
+            pitch_std_dev: Radians(0.015),
+            heading_std_dev: Radians(0.02),
         };
 
         let mut cursor = std::io::Cursor::new(Vec::new());
@@ -130,6 +137,27 @@ mod tests {
         assert_eq!(bytes.len(), 48, "RawSensors should be 48 bytes");
     }
 
+    #[test]
+    fn test_north_seeking_status_packet_length() {
+        let packet = NorthSeekingStatus {
+            flags: NorthSeekingFlags::from(0x0001u16),
+            quadrant_progress: [100, 100, 100, 100],
+            current_rotation_angle: 1.0,
+            gyroscope_bias_x: 1e-5,
+            gyroscope_bias_y: -2e-5,
+            gyroscope_bias_z: 3e-6,
+            gyroscope_bias_error: 1e-6,
+        };
+
+        let mut cursor = std::io::Cursor::new(Vec::new());
+        packet.write_le(&mut cursor).expect("Failed to serialize");
+        let bytes = cursor.into_inner();
+        assert_eq!(bytes.len(), 28, "NorthSeekingStatus should be 28 bytes");
+
+        let mut cursor = std::io::Cursor::new(&bytes);
+        assert_eq!(NorthSeekingStatus::read_le(&mut cursor).unwrap(), packet);
+    }
+
     #[test]
     fn test_sensor_temperature_packet_length() {
         let packet = SensorTemperature {
@@ -183,10 +211,10 @@ mod tests {
             status: GnssOrientationStatus::from(0x0007u16), // RtkFixed
             posix_time_seconds: 1700000000,
             posix_time_microseconds: 500000,
-            azimuth: 1.5707963,
-            azimuth_std_dev: 0.01,
-            tilt: 0.0,
-            tilt_std_dev: 0.02,
+            azimuth: Radians(1.5707963),
+            azimuth_std_dev: Radians(0.01),
+            tilt: Radians(0.0),
+            tilt_std_dev: Radians(0.02),
             baseline_length: 1.0,
             latency: 500,
         };
@@ -214,15 +242,16 @@ mod tests {
             body_acceleration_y: 0.05,
             body_acceleration_z: 9.75,
             g_force: 0.98,
-            roll: -0.17453293,
-            pitch: 0.08726646,
-            heading: 3.14159265,
+            roll: Radians(-0.17453293),
+            pitch: Radians(0.08726646),
+            heading: Radians(3.14159265),
             angular_velocity_x: -0.01,
             angular_velocity_y: 0.005,
             angular_velocity_z: -0.002,
             latitude_std_dev: 1.2,
             longitude_std_dev: 1.5,
             height_std_dev: 2.0,
+            extension: None,
         };
 
         let mut cursor = std::io::Cursor::new(Vec::new());
@@ -235,6 +264,25 @@ mod tests {
         assert_eq!(deserialized, original);
     }
 
+    #[test]
+    fn test_system_state_extension_round_trip() {
+        let mut bytes = vec![0u8; 100];
+        bytes.extend_from_slice(&1.5f32.to_le_bytes());
+
+        let mut cursor = std::io::Cursor::new(&bytes);
+        let state = SystemState::read_le(&mut cursor).unwrap();
+        let extension = state.extension.as_ref().expect("extension bytes kept");
+        assert_eq!(extension.len(), 4);
+        assert_eq!(extension.read::<f32>(0), Some(1.5));
+
+        let mut cursor = std::io::Cursor::new(Vec::new());
+        state.write_le(&mut cursor).unwrap();
+        assert_eq!(cursor.into_inner(), bytes);
+
+        let mut cursor = std::io::Cursor::new(&bytes[..100]);
+        assert_eq!(SystemState::read_le(&mut cursor).unwrap().extension, None);
+    }
+
     #[test]
     fn test_gnss_pvt_round_trip() {
         let original = GnssPositionVelocityTime {
@@ -274,10 +322,10 @@ mod tests {
             status: GnssOrientationStatus::from(0x0006u16), // RtkFloat
             posix_time_seconds: 1700000000,
             posix_time_microseconds: 654321,
-            azimuth: 1.5,
-            azimuth_std_dev: 0.01,
-            tilt: 0.05,
-            tilt_std_dev: 0.02,
+            azimuth: Radians(1.5),
+            azimuth_std_dev: Radians(0.01),
+            tilt: Radians(0.05),
+            tilt_std_dev: Radians(0.02),
             baseline_length: 1.2,
             latency: 3000,
         };
@@ -292,6 +340,103 @@ mod tests {
         assert_eq!(deserialized, original);
     }
 
+    #[test]
+    fn test_odometer_state_packet_length() {
+        let packet = OdometerState::new(-12345, 100.5, 2.5, 0.1, true);
+
+        let mut cursor = std::io::Cursor::new(Vec::new());
+        packet.write_le(&mut cursor).expect("Failed to serialize");
+        let bytes = cursor.into_inner();
+        assert_eq!(bytes.len(), 20, "OdometerState should be 20 bytes");
+        assert_eq!(bytes[16], 1);
+
+        let mut cursor = std::io::Cursor::new(&bytes);
+        assert_eq!(OdometerState::read_le(&mut cursor).unwrap(), packet);
+    }
+
+    #[test]
+    fn test_odometer_distance_across_wrap() {
+        let state = |pulses| OdometerState::new(pulses, 0.0, 0.0, 0.0, true);
+        let mut tracker = OdometerTracker::new(Length::from_meters(0.01));
+
+        tracker.update(&state(i32::MAX - 50));
+        tracker.update(&state(i32::MAX));
+        tracker.update(&state(i32::MIN + 99));
+        assert_eq!(state(i32::MIN + 99).pulses_since(&state(i32::MAX)), 100);
+        // Reversing still adds to the distance travelled
+        tracker.update(&state(i32::MIN + 49));
+        assert_eq!(tracker.pulses(), 200);
+        assert!((tracker.distance_travelled().meters() - 2.0).abs() < 1e-9);
+
+        tracker.reset();
+        tracker.update(&state(0));
+        assert_eq!(tracker.pulses(), 0);
+    }
+
+    #[test]
+    fn test_external_depth() {
+        let packet = ExternalDepth::new(Length::from_meters(120.5), Length::from_meters(0.1)).unwrap();
+
+        let mut cursor = std::io::Cursor::new(Vec::new());
+        packet.write_le(&mut cursor).expect("Failed to serialize");
+        let bytes = cursor.into_inner();
+        assert_eq!(bytes.len(), 8, "ExternalDepth should be 8 bytes");
+        let mut cursor = std::io::Cursor::new(&bytes);
+        assert_eq!(ExternalDepth::read_le(&mut cursor).unwrap(), packet);
+
+        // 1 bar above surface pressure in sea water is about 10 m
+        let from_pressure = ExternalDepth::from_pressure(201_325.0, 101_325.0, ExternalDepth::SEAWATER_DENSITY,
+            Length::from_meters(0.05)).unwrap();
+        assert!((from_pressure.depth_length().meters() - 9.948).abs() < 0.001);
+
+        // Height positive up or a value in millimetres is rejected
+        assert!(ExternalDepth::new(Length::from_meters(-50.0), Length::from_meters(0.1)).is_err());
+        assert!(ExternalDepth::new(Length::from_meters(120_500.0), Length::from_meters(0.1)).is_err());
+        assert!(ExternalDepth::new(Length::from_meters(10.0), Length::from_meters(0.0)).is_err());
+        assert!(ExternalDepth::from_pressure(2e5, 1e5, 0.0, Length::from_meters(0.1)).is_err());
+    }
+
+    #[test]
+    fn test_wind_round_trip() {
+        let original = Wind { wind_velocity_north: -3.0, wind_velocity_east: -4.0, wind_velocity_std_dev: 0.5 };
+
+        let mut cursor = std::io::Cursor::new(Vec::new());
+        original.write_le(&mut cursor).expect("Failed to serialize");
+        let bytes = cursor.into_inner();
+        assert_eq!(bytes.len(), 12, "Wind should be 12 bytes");
+
+        let mut cursor = std::io::Cursor::new(&bytes);
+        assert_eq!(Wind::read_le(&mut cursor).unwrap(), original);
+
+        // Blowing towards the south west, so from the north east
+        assert!((original.speed().meters_per_second() - 5.0).abs() < 1e-9);
+        assert!((original.direction_from().degrees() - 53.13).abs() < 0.01);
+    }
+
+    #[test]
+    fn test_external_air_data_packet_length() {
+        let packet = ExternalAirData::airspeed(42.0, 1.5)
+            .with_barometric_altitude(1200.0, 3.0)
+            .with_delays(0.05, 0.02)
+            .with_reference_reset();
+        assert!(packet.flags.airspeed_valid());
+        assert!(packet.flags.barometric_altitude_valid());
+        assert!(packet.flags.barometric_altitude_reference_reset());
+
+        let mut cursor = std::io::Cursor::new(Vec::new());
+        packet.write_le(&mut cursor).expect("Failed to serialize");
+        let bytes = cursor.into_inner();
+        assert_eq!(bytes.len(), 25, "ExternalAirData should be 25 bytes");
+        assert_eq!(bytes[24], 0b111);
+
+        let mut cursor = std::io::Cursor::new(&bytes);
+        assert_eq!(ExternalAirData::read_le(&mut cursor).unwrap(), packet);
+
+        let baro_only = ExternalAirData::barometric_altitude(100.0, 2.0);
+        assert!(!baro_only.flags.airspeed_valid());
+        assert!(crate::packet::Packet::ExternalAirData(baro_only).to_bytes().is_ok());
+    }
+
     #[test]
     fn test_position_std_dev_round_trip() {
         let original = PositionStdDev {
@@ -325,4 +470,183 @@ mod tests {
         let deserialized = VelocityStdDev::read_le(&mut cursor).expect("Failed to deserialize");
         assert_eq!(deserialized, original);
     }
+
+    #[test]
+    fn test_acceleration_packets() {
+        let bytes: Vec<u8> = [0.5f32, 0.0, -9.8].iter().flat_map(|v| v.to_le_bytes()).collect();
+        let raw = Acceleration::read_le(&mut std::io::Cursor::new(&bytes)).unwrap();
+        assert!((raw.vector()[2].g() + 0.9993).abs() < 1e-3);
+
+        let body = BodyAcceleration {
+            body_acceleration_x: 0.5,
+            body_acceleration_y: 0.0,
+            body_acceleration_z: 0.0,
+            g_force: 1.0,
+        };
+        assert_eq!(body.vector()[0].meters_per_second_squared(), 0.5);
+        assert!((body.g_force_acceleration().meters_per_second_squared() - 9.80665).abs() < 1e-9);
+
+        let bytes: Vec<u8> = [0.0f32, 0.0, std::f32::consts::PI].iter().flat_map(|v| v.to_le_bytes()).collect();
+        let spin = AngularAcceleration::read_le(&mut std::io::Cursor::new(&bytes)).unwrap();
+        assert!((spin.vector()[2].degrees_per_second_squared() - 180.0).abs() < 1e-4);
+    }
+
+    #[test]
+    fn test_detailed_satellites_packet_length() {
+        let packet = DetailedSatellites {
+            satellites: vec![
+                SatelliteInfo {
+                    system: SatelliteSystem::Gps,
+                    prn: 12,
+                    frequencies: SatelliteFrequencies::from(0b1000_0001), // L1 C/A + L5
+                    elevation: 45,
+                    azimuth: 270,
+                    snr: 42,
+                },
+                SatelliteInfo {
+                    system: SatelliteSystem::Galileo,
+                    prn: 3,
+                    frequencies: SatelliteFrequencies::from(0b0000_0010),
+                    elevation: -2,
+                    azimuth: 15,
+                    snr: 30,
+                },
+            ],
+        };
+
+        let mut cursor = std::io::Cursor::new(Vec::new());
+        packet.write_le(&mut cursor).expect("Failed to serialize");
+        let bytes = cursor.into_inner();
+        assert_eq!(bytes.len(), 14, "DetailedSatellites with 2 records should be 14 bytes");
+        assert_eq!(&bytes[..7], &[1, 12, 0x81, 45, 0x0E, 0x01, 42]);
+
+        let mut cursor = std::io::Cursor::new(&bytes);
+        let deserialized = DetailedSatellites::read_le(&mut cursor).expect("Failed to deserialize");
+        assert_eq!(deserialized, packet);
+        assert!(deserialized.satellites[0].frequencies.l1_ca());
+        assert!(deserialized.satellites[0].frequencies.l5());
+        assert_eq!(deserialized.by_system(SatelliteSystem::Galileo).count(), 1);
+    }
+
+    #[test]
+    fn test_detailed_satellites_empty() {
+        let mut cursor = std::io::Cursor::new(Vec::<u8>::new());
+        let deserialized = DetailedSatellites::read_le(&mut cursor).expect("Failed to deserialize");
+        assert!(deserialized.satellites.is_empty());
+    }
+
+    #[test]
+    fn test_try_from_payload_and_to_payload_bytes() {
+        use crate::error::AnError;
+
+        let packet = UnixTime { unix_time_seconds: 1_700_000_000, microseconds: 42 };
+        let bytes = packet.to_payload_bytes().unwrap();
+        assert_eq!(bytes.len(), 8);
+        assert_eq!(UnixTime::try_from(bytes.as_slice()).unwrap(), packet);
+
+        assert!(matches!(UnixTime::try_from(&bytes[..7]),
+            Err(AnError::LengthMismatch { id: 21, expected: 8, got: 7 })));
+        let mut long = bytes.clone();
+        long.push(0);
+        assert!(UnixTime::try_from(long.as_slice()).is_err());
+
+        // Variable length packets take whatever the payload holds
+        let satellites = DetailedSatellites::try_from(&[1u8, 12, 0x81, 45, 0x0E, 0x01, 42][..]).unwrap();
+        assert_eq!(satellites.satellites.len(), 1);
+        assert_eq!(satellites.to_payload_bytes().unwrap().len(), 7);
+    }
+
+    #[test]
+    fn test_formatted_time_matches_unix_time() {
+        use std::time::Duration;
+
+        // Thursday 29 February 2024 12:34:56.5 UTC
+        let unix = UnixTime { unix_time_seconds: 1_709_210_096, microseconds: 500_000 };
+        let formatted = FormattedTime::from(&unix);
+        assert_eq!(formatted, FormattedTime {
+            microseconds: 500_000,
+            year: 2024,
+            year_day: 59,
+            month: 1,
+            month_day: 29,
+            week_day: 4,
+            hour: 12,
+            minute: 34,
+            second: 56,
+        });
+        assert_eq!(formatted.to_string(), "2024-02-29T12:34:56.500000Z");
+        assert_eq!(formatted.to_payload_bytes().unwrap().len(), FormattedTime::LEN);
+        assert_eq!(formatted.to_system_time(), Some(unix.to_system_time()));
+
+        assert!(formatted.approximately_equals(&unix, Duration::ZERO));
+        let later = UnixTime { unix_time_seconds: unix.unix_time_seconds + 1, ..unix.clone() };
+        assert!(formatted.approximately_equals(&later, Duration::from_secs(1)));
+        assert!(!formatted.approximately_equals(&later, Duration::from_millis(999)));
+
+        // Calendar edges convert both ways
+        for seconds in [0, 951_782_400, 978_307_199, 1_735_689_599, u32::MAX] {
+            let unix = UnixTime { unix_time_seconds: seconds, microseconds: 0 };
+            assert_eq!(FormattedTime::from(&unix).unix_seconds(), Some(u64::from(seconds)), "{seconds}");
+        }
+        assert_eq!(FormattedTime::from(&UnixTime { unix_time_seconds: 978_307_199, microseconds: 0 }).year_day, 365);
+
+        for invalid in [
+            FormattedTime { month: 12, ..formatted.clone() },
+            FormattedTime { month: 1, month_day: 30, ..formatted.clone() },
+            FormattedTime { month_day: 0, ..formatted.clone() },
+            FormattedTime { hour: 24, ..formatted.clone() },
+            FormattedTime { year: 1969, month: 11, ..formatted.clone() },
+        ] {
+            assert_eq!(invalid.to_system_time(), None, "{invalid:?}");
+            assert!(!invalid.approximately_equals(&unix, Duration::MAX));
+        }
+    }
+
+    #[test]
+    fn test_orientation_accessors_wrap() {
+        let euler = |heading: f32| EulerOrientation {
+            roll: Radians(1.5 * std::f32::consts::PI),
+            pitch: Radians(0.1),
+            heading: Radians(heading.to_radians()),
+        };
+        let previous = euler(359.0);
+        let current = euler(-359.0);
+        assert!((current.heading_deg() - 1.0).abs() < 1e-3);
+        assert!((current.heading_change(&previous).degrees() - 2.0).abs() < 1e-3);
+        assert!((previous.heading_change(&current).degrees() + 2.0).abs() < 1e-3);
+        assert!((current.roll_angle().degrees() + 90.0).abs() < 1e-3);
+        assert!((current.pitch_angle().radians() - 0.1).abs() < 1e-6);
+
+        let zeroed = SystemState::read_le(&mut std::io::Cursor::new([0u8; 100])).unwrap();
+        let state = |heading: f32| SystemState { heading: Radians(heading.to_radians()), ..zeroed.clone() };
+        assert!((state(370.0).heading_angle().degrees() - 10.0).abs() < 1e-3);
+        assert!((state(10.0).heading_change(&state(350.0)).degrees() - 20.0).abs() < 1e-3);
+    }
+
+    #[test]
+    fn test_event_timestamps() {
+        use crate::packet::config::GpioFunction;
+        use crate::packet::state::{EventId, EventTimestamp};
+
+        let zeroed = SystemState::read_le(&mut std::io::Cursor::new([0u8; 100])).unwrap();
+        let state = |filter_status: u16| SystemState {
+            filter_status: FilterStatus::from(filter_status),
+            unix_time_seconds: 1_700_000_000,
+            microseconds: 250_000,
+            ..zeroed.clone()
+        };
+        let time = UnixTime { unix_time_seconds: 1_700_000_000, microseconds: 250_000 };
+
+        assert_eq!(state(0x0003).event_timestamps().count(), 0);
+        assert_eq!(state(1 << 8).event_timestamps().collect::<Vec<_>>(), vec![
+            EventTimestamp { event: EventId::Event2, time: time.clone() },
+        ]);
+        let both: Vec<_> = state((1 << 7) | (1 << 8)).filter_status.events().collect();
+        assert_eq!(both, vec![EventId::Event1, EventId::Event2]);
+        assert_eq!(state(0).unix_time().to_system_time(), state(0).timestamp());
+
+        assert_eq!(GpioFunction::Event1Input.event(), Some(EventId::Event1));
+        assert_eq!(GpioFunction::Event2Input.event(), Some(EventId::Event2));
+        assert_eq!(GpioFunction::OnePpsInput.event(), None);
+    }
 }
@@ -3,7 +3,8 @@ mod tests {
     use crate::packet::system::{
         Acknowledge, AcknowledgeResult, Request, BootMode,
         DeviceInformation, RestoreFactorySettings,
-        Reset, IpConfiguration
+        Reset, IpConfiguration, Ipv4Subnet, DeviceModel, DeviceType, Version,
+        Subcomponent, SubcomponentInformation,
     };
     use crate::packet::PacketKind;
     use binrw::{BinRead, BinWrite};
@@ -23,10 +24,29 @@ mod tests {
     }
 
     #[test]
-    fn test_request_packet_length() {
-        let packet = Request {
-            requested_packet: PacketKind::SystemState,
+    fn test_acknowledge_matches_sent_frame() {
+        use crate::packet::Packet;
+        use crate::packet::config::PacketTimerPeriod;
+        use crate::protocol::AnppProtocol;
+
+        let sent = Packet::PacketTimerPeriod(PacketTimerPeriod::from_rate_hz(1000.0).unwrap()).to_bytes().unwrap();
+        let other = Packet::PacketTimerPeriod(PacketTimerPeriod::from_rate_hz(500.0).unwrap()).to_bytes().unwrap();
+        let ack = Acknowledge {
+            acknowledged_packet: PacketKind::PacketTimerPeriod,
+            packet_crc: AnppProtocol::calculate_crc16(&sent[5..]),
+            result: AcknowledgeResult::Success,
         };
+        assert!(ack.matches(&sent));
+        assert!(!ack.matches(&other));
+        assert!(!ack.matches(&sent[..sent.len() - 1]));
+
+        let wrong_kind = Acknowledge { acknowledged_packet: PacketKind::FilterOptions, ..ack };
+        assert!(!wrong_kind.matches(&sent));
+    }
+
+    #[test]
+    fn test_request_packet_length() {
+        let packet = Request::single(PacketKind::SystemState);
 
         let mut cursor = std::io::Cursor::new(Vec::new());
         packet.write_le(&mut cursor).expect("Failed to serialize");
@@ -34,6 +54,21 @@ mod tests {
         assert_eq!(bytes.len(), 1, "Request should be 1 byte");
     }
 
+    #[test]
+    fn test_request_for_packet_type() {
+        use crate::packet::state::{Status, SystemState};
+        use crate::protocol::AnppProtocol;
+
+        assert_eq!(Request::for_packet::<SystemState>(), Request::single(PacketKind::SystemState));
+        assert_eq!(Request::for_packet::<DeviceInformation>().and::<Status>(),
+            Request::multiple([PacketKind::DeviceInformation, PacketKind::Status]));
+
+        let frame = AnppProtocol::encode_request::<SystemState>();
+        let (header, payload) = AnppProtocol::get_header_from_bytes(&frame).unwrap();
+        assert_eq!(header.packet_id.as_u8(), 1);
+        assert_eq!(payload, vec![20]);
+    }
+
     #[test]
     fn test_boot_mode_packet_length() {
         let packet = BootMode { boot_mode: 1 };
@@ -61,6 +96,60 @@ mod tests {
         assert_eq!(bytes.len(), 24, "DeviceInformation should be 24 bytes");
     }
 
+    #[test]
+    fn test_device_information_model_support() {
+        let mut info = DeviceInformation {
+            software_version: 0,
+            device_type: DeviceType::from(28),
+            hardware_revision: 0,
+            serial_number_1: 1,
+            serial_number_2: 0,
+            serial_number_3: 0,
+        };
+        let boreas = info.model();
+        assert_eq!(boreas, DeviceModel::BoreasD90);
+        assert!(boreas.supports(PacketKind::GnssOrientation));
+        assert!(boreas.supports(PacketKind::NorthSeekingStatus));
+        assert!(boreas.supports(PacketKind::IpDataportsConfiguration));
+        assert!(!boreas.supports(PacketKind::MagneticCalibrationStatus));
+
+        info.device_type = DeviceType::CertusMiniA;
+        let ahrs = info.model();
+        assert!(!ahrs.supports(PacketKind::Satellites));
+        assert!(!ahrs.supports(PacketKind::DualAntennaConfiguration));
+        assert!(!ahrs.supports(PacketKind::AntennaOffsetEstimation));
+        assert!(ahrs.supports(PacketKind::SystemState));
+
+        assert!(!DeviceModel::AirDataUnit.supports(PacketKind::SystemState));
+        assert!(DeviceModel::AirDataUnit.supports(PacketKind::DeviceInformation));
+        assert!(DeviceModel::Unknown.supports(PacketKind::NorthSeekingStatus));
+        assert!(!DeviceModel::Unknown.supports(PacketKind::Unsupported));
+    }
+
+    #[test]
+    fn test_version_decoding() {
+        let version = Version::from_raw(2410);
+        assert_eq!(version, Version::new(2, 4, 10));
+        assert_eq!(version.to_string(), "2.4.10");
+        assert_eq!(version.raw(), 2410);
+        assert!(version.at_least(2, 4, 0));
+        assert!(version.at_least(2, 4, 10));
+        assert!(!version.at_least(2, 5, 0));
+        assert!(Version::from_raw(7000) > version);
+        assert_eq!(Version::from_raw(1).to_string(), "0.0.1");
+
+        let info = DeviceInformation {
+            software_version: 7000,
+            device_type: DeviceType::BoreasD90,
+            hardware_revision: 310,
+            serial_number_1: 1,
+            serial_number_2: 0,
+            serial_number_3: 0,
+        };
+        assert_eq!(info.software(), Version::new(7, 0, 0));
+        assert!(info.to_string().ends_with("software 7.0.0, hardware 0.3.10"), "{}", info);
+    }
+
     #[test]
     fn test_restore_factory_settings_packet_length() {
         let packet = RestoreFactorySettings {};
@@ -88,10 +177,10 @@ mod tests {
         let packet = IpConfiguration {
             permanent: true,
             dhcp_mode: 0,
-            ip_address: u32::from(std::net::Ipv4Addr::new(192, 168, 1, 100)),
-            ip_netmask: u32::from(std::net::Ipv4Addr::new(255, 255, 255, 0)),
-            ip_gateway: u32::from(std::net::Ipv4Addr::new(192, 168, 1, 1)),
-            dns_server: u32::from(std::net::Ipv4Addr::new(8, 8, 8, 8)),
+            ip_address: std::net::Ipv4Addr::new(192, 168, 1, 100),
+            ip_netmask: std::net::Ipv4Addr::new(255, 255, 255, 0),
+            ip_gateway: std::net::Ipv4Addr::new(192, 168, 1, 1),
+            dns_server: std::net::Ipv4Addr::new(8, 8, 8, 8),
             boreas_serial_number_part_1: 123456,
             boreas_serial_number_part_2: 789012,
             boreas_serial_number_part_3: 345678,
@@ -138,10 +227,10 @@ mod tests {
         let ip_original = IpConfiguration {
             permanent: false,
             dhcp_mode: 1,
-            ip_address: 0,
-            ip_netmask: 0,
-            ip_gateway: 0,
-            dns_server: 0,
+            ip_address: std::net::Ipv4Addr::UNSPECIFIED,
+            ip_netmask: std::net::Ipv4Addr::UNSPECIFIED,
+            ip_gateway: std::net::Ipv4Addr::UNSPECIFIED,
+            dns_server: std::net::Ipv4Addr::UNSPECIFIED,
             boreas_serial_number_part_1: 111111,
             boreas_serial_number_part_2: 222222,
             boreas_serial_number_part_3: 333333,
@@ -153,4 +242,85 @@ mod tests {
         let ip_deserialized = IpConfiguration::read_le(&mut cursor).unwrap();
         assert_eq!(ip_original, ip_deserialized);
     }
+
+    #[test]
+    fn test_ipv4_subnet() {
+        use std::net::Ipv4Addr;
+        let subnet = Ipv4Subnet::with_prefix(Ipv4Addr::new(10, 1, 2, 3), 20);
+        assert_eq!(subnet.netmask, Ipv4Addr::new(255, 255, 240, 0));
+        assert_eq!(subnet.network(), Ipv4Addr::new(10, 1, 0, 0));
+        assert_eq!(subnet.broadcast(), Ipv4Addr::new(10, 1, 15, 255));
+        assert!(subnet.contains(Ipv4Addr::new(10, 1, 15, 1)));
+        assert!(!subnet.contains(Ipv4Addr::new(10, 1, 16, 1)));
+        assert_eq!(subnet.to_string(), "10.1.0.0/20");
+        assert_eq!(Ipv4Subnet::with_prefix(Ipv4Addr::LOCALHOST, 0).prefix_len(), Some(0));
+        assert_eq!(Ipv4Subnet::with_prefix(Ipv4Addr::LOCALHOST, 32).prefix_len(), Some(32));
+        assert_eq!(Ipv4Subnet::new(Ipv4Addr::LOCALHOST, Ipv4Addr::new(255, 0, 255, 0)).prefix_len(), None);
+    }
+
+    #[test]
+    fn test_ip_configuration_validation() {
+        use std::net::Ipv4Addr;
+        let valid = IpConfiguration {
+            permanent: true,
+            dhcp_mode: 0,
+            ip_address: Ipv4Addr::new(192, 168, 1, 100),
+            ip_netmask: Ipv4Addr::new(255, 255, 255, 0),
+            ip_gateway: Ipv4Addr::new(192, 168, 1, 1),
+            dns_server: Ipv4Addr::new(8, 8, 8, 8),
+            boreas_serial_number_part_1: 0,
+            boreas_serial_number_part_2: 0,
+            boreas_serial_number_part_3: 0,
+        };
+        assert!(valid.validate().is_ok());
+        let current = Ipv4Subnet::with_prefix(Ipv4Addr::new(192, 168, 1, 20), 24);
+        assert!(valid.validate_reachable_from(current).is_ok());
+        assert!(valid.validate_reachable_from(Ipv4Subnet::with_prefix(Ipv4Addr::new(192, 168, 2, 20), 24)).is_err());
+
+        let broken = [
+            IpConfiguration { ip_netmask: Ipv4Addr::UNSPECIFIED, ..valid.clone() },
+            IpConfiguration { ip_netmask: Ipv4Addr::new(255, 0, 255, 0), ..valid.clone() },
+            IpConfiguration { ip_address: Ipv4Addr::new(192, 168, 1, 0), ..valid.clone() },
+            IpConfiguration { ip_address: Ipv4Addr::new(192, 168, 1, 255), ..valid.clone() },
+            IpConfiguration { ip_address: Ipv4Addr::UNSPECIFIED, ..valid.clone() },
+            IpConfiguration { ip_gateway: Ipv4Addr::new(192, 168, 2, 1), ..valid.clone() },
+            IpConfiguration { ip_gateway: Ipv4Addr::new(192, 168, 1, 100), ..valid.clone() },
+        ];
+        for ip in &broken {
+            assert!(ip.validate().is_err(), "{:?}", ip);
+        }
+        // No gateway is allowed, and DHCP leaves the address to the server
+        assert!(IpConfiguration { ip_gateway: Ipv4Addr::UNSPECIFIED, ..valid.clone() }.validate().is_ok());
+        assert!(IpConfiguration { dhcp_mode: 1, ..broken[0].clone() }.validate_reachable_from(current).is_ok());
+    }
+
+    #[test]
+    fn test_subcomponent_information_records() {
+        use crate::AnError;
+
+        let record = |device_id: u32| Subcomponent {
+            software_version: 2105,
+            device_id,
+            hardware_revision: 110,
+            serial_number_1: 1,
+            serial_number_2: 2,
+            serial_number_3: device_id,
+        };
+        let single = SubcomponentInformation { subcomponents: vec![record(1)] };
+        let bytes = single.to_payload_bytes().unwrap();
+        assert_eq!(bytes.len(), Subcomponent::LEN);
+        assert_eq!(SubcomponentInformation::try_from(&bytes[..]).unwrap(), single);
+        assert_eq!(single.subcomponents[0].software(), Version::new(2, 1, 5));
+
+        let several = SubcomponentInformation { subcomponents: (1..=3).map(record).collect() };
+        let bytes = several.to_payload_bytes().unwrap();
+        assert_eq!(bytes.len(), 3 * Subcomponent::LEN);
+        assert_eq!(SubcomponentInformation::try_from(&bytes[..]).unwrap(), several);
+        assert!(SubcomponentInformation::try_from(&[][..]).unwrap().subcomponents.is_empty());
+
+        // A partial record is an error, not silently dropped
+        for len in [1, 23, 25, 47] {
+            assert!(matches!(SubcomponentInformation::try_from(&bytes[..len]), Err(AnError::Decode { .. })), "{len} bytes");
+        }
+    }
 }
@@ -1,9 +1,11 @@
 extern crate alloc;
 use alloc::vec::Vec;
 
-use crate::packet::{Packet, PacketKind};
+use crate::packet::{Packet, PacketKind, Payload};
+use crate::profile::ProtocolProfile;
 use crate::protocol::AnppProtocol;
 
+#[cfg(feature = "tracing")]
 use tracing::debug;
 
 #[derive(Debug)]
@@ -25,9 +27,11 @@ type Result<T> = core::result::Result<(T, usize), ParseError>;
 // Constants for our parser
 const MIN_PACKET_SIZE: usize = 5; // 1 LRC + 1 ID + 1 length + 2 CRC16
 
-fn parse_packet(input: &[u8]) -> Result<Packet> {
-    // Make sure we have enough data for a minimal packet
+/// Validate the header at the start of `input` and return the length of
+/// the whole frame it announces
+fn parse_header(input: &[u8]) -> core::result::Result<usize, ParseError> {
     if input.len() < MIN_PACKET_SIZE {
+        #[cfg(feature = "tracing")]
         debug!("Incomplete data, don't have enough for minimal packet");
         return Err(ParseError::IncompleteData);
     }
@@ -36,32 +40,29 @@ fn parse_packet(input: &[u8]) -> Result<Packet> {
     let header_lrc = input[0];
     let packet_id = input[1];
     let payload_length = input[2];
-
-    let packet_length = payload_length as usize + 5; // length in packet does not include header
-
-    // Ensure we have the complete packet
-    if input.len() < packet_length {
-        debug!("Don't have full packet, need {} bytes but have {}", packet_length, input.len());
-        return Err(ParseError::IncompleteData);
-    }
-
-    // Extract CRC16 (last 2 bytes of header)
     let crc16 = u16::from_le_bytes([input[3], input[4]]);
 
-    // Validate header LRC
     let calculated_lrc = AnppProtocol::calculate_lrc(packet_id, payload_length, crc16);
     if header_lrc != calculated_lrc {
+        #[cfg(feature = "tracing")]
         debug!("Invalid header LRC for packet ID {}: expected {:#02x}, got {:#02x}",
                packet_id, calculated_lrc, header_lrc);
         return Err(ParseError::InvalidHeader);
     }
 
-    // Extract payload (everything between header and CRC16)
-    let payload = &input[5..packet_length];
+    Ok(payload_length as usize + 5) // length in packet does not include header
+}
+
+/// Check the CRC and decode a complete frame whose header is valid. Also
+/// returns the payload bytes the profile set aside as trailing.
+fn parse_frame<'a>(frame: &'a [u8], profile: &ProtocolProfile) -> core::result::Result<(Packet, &'a [u8]), ParseError> {
+    let packet_id = frame[1];
+    let crc16 = u16::from_le_bytes([frame[3], frame[4]]);
+    let payload = &frame[MIN_PACKET_SIZE..];
 
-    // Validate packet CRC16 (calculated over payload)
     let calculated_crc = AnppProtocol::calculate_crc16(payload);
     if crc16 != calculated_crc {
+        #[cfg(feature = "tracing")]
         debug!("Invalid CRC16 for packet ID {}: expected {:#04x}, got {:#04x}",
                packet_id, calculated_crc, crc16);
         return Err(ParseError::InvalidCRC);
@@ -69,22 +70,38 @@ fn parse_packet(input: &[u8]) -> Result<Packet> {
 
     // Validate payload length matches expected length for known packet types
     let packet_kind = PacketKind::from(packet_id);
-    if let Some(expected_length) = packet_kind.byte_length() {
-        if payload_length as usize != expected_length {
-            debug!("Payload length mismatch for packet ID {}: expected {} bytes, got {}",
-                   packet_id, expected_length, payload_length);
-            return Err(ParseError::InvalidPayload);
-        }
+    if !profile.accepts_length(packet_kind, payload.len()) {
+        #[cfg(feature = "tracing")]
+        debug!("Payload length mismatch for packet ID {}: expected {:?} bytes, got {}",
+               packet_id, packet_kind.byte_length(), payload.len());
+        return Err(ParseError::InvalidPayload);
     }
 
-    // Parse the packet payload
-    match Packet::from_bytes(packet_id, payload) {
-        Ok(packet) => Ok((packet, packet_length)),
-        Err(_) => {
-            debug!("Failed to parse payload for packet ID {}", packet_id);
-            Err(ParseError::InvalidPayload)
-        }
+    let (payload, trailing) = payload.split_at(profile.decoded_length(packet_kind, payload.len()));
+    #[cfg(feature = "tracing")]
+    if !trailing.is_empty() {
+        debug!("Ignoring {} trailing bytes after packet ID {}", trailing.len(), packet_id);
+    }
+    let packet = Packet::from_bytes(packet_id, payload).map_err(|_| {
+        #[cfg(feature = "tracing")]
+        debug!("Failed to parse payload for packet ID {}", packet_id);
+        ParseError::InvalidPayload
+    })?;
+    Ok((packet, trailing))
+}
+
+fn parse_packet(input: &[u8], profile: &ProtocolProfile) -> Result<Packet> {
+    let packet_length = parse_header(input)?;
+
+    // Ensure we have the complete packet
+    if input.len() < packet_length {
+        #[cfg(feature = "tracing")]
+        debug!("Don't have full packet, need {} bytes but have {}", packet_length, input.len());
+        return Err(ParseError::IncompleteData);
     }
+
+    let (packet, _trailing) = parse_frame(&input[..packet_length], profile)?;
+    Ok((packet, packet_length))
 }
 
 #[derive(Debug)]
@@ -98,7 +115,15 @@ pub enum DatagramError {
 /// Parse a single ANPP packet from a datagram. Expects the packet to
 /// start at byte 0 — no scanning.
 pub fn parse_datagram(datagram: &[u8]) -> core::result::Result<Packet, DatagramError> {
-    match parse_packet(datagram) {
+    parse_datagram_with_profile(datagram, &ProtocolProfile::strict())
+}
+
+/// Same as [`parse_datagram`] but validates lengths against `profile`
+pub fn parse_datagram_with_profile(
+    datagram: &[u8],
+    profile: &ProtocolProfile,
+) -> core::result::Result<Packet, DatagramError> {
+    match parse_packet(datagram, profile) {
         Ok((packet, _len)) => Ok(packet),
         Err(ParseError::IncompleteData) => Err(DatagramError::IncompleteData),
         Err(ParseError::InvalidHeader) => Err(DatagramError::InvalidHeader),
@@ -107,24 +132,148 @@ pub fn parse_datagram(datagram: &[u8]) -> core::result::Result<Packet, DatagramE
     }
 }
 
+/// A packet tagged with the order in which its parser emitted it
+#[derive(Debug, Clone, PartialEq)]
+pub struct ReceivedPacket {
+    /// Per-parser sequence number, starting at 0 and increasing by one for
+    /// every emitted packet. A gap downstream means a packet was dropped.
+    pub sequence: u64,
+    pub packet: Packet,
+    /// Payload bytes after the documented fields, ignored under
+    /// [`Quirk::TrailingBytes`](crate::profile::Quirk::TrailingBytes);
+    /// empty otherwise
+    pub trailing: Payload,
+}
+
+/// How a stream parser recovers when a frame behind a valid header is
+/// rejected, e.g. because its CRC does not match
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ResyncPolicy {
+    /// Rescan from the byte after the rejected header. A header can match
+    /// by chance inside noise, so this never loses a real frame that starts
+    /// within the rejected one. Suited to noisy serial links.
+    #[default]
+    SkipByte,
+    /// Skip the whole length the header claimed. Faster on links that
+    /// corrupt whole frames rather than bytes, such as TCP, but a header
+    /// that matched by chance swallows whatever follows it.
+    SkipFrame,
+}
+
+/// Running counts of what a stream parser has seen
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ParserStats {
+    /// Packets emitted
+    pub packets: u64,
+    /// Bytes that did not start a header with a valid LRC
+    pub header_errors: u64,
+    /// Frames whose payload CRC did not match the header
+    pub crc_errors: u64,
+    /// Frames with a valid CRC but a length or payload that did not decode
+    pub payload_errors: u64,
+    /// Rejected frames recovered from with [`ResyncPolicy::SkipByte`]
+    pub byte_resyncs: u64,
+    /// Rejected frames recovered from with [`ResyncPolicy::SkipFrame`]
+    pub frame_skips: u64,
+    /// Bytes dropped without being part of an emitted packet
+    pub bytes_discarded: u64,
+    /// Packets emitted with trailing bytes ignored by the profile
+    pub trailing_ignored: u64,
+}
+
+/// Where the stream parser is within a frame
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum State {
+    /// Nothing buffered
+    Idle,
+    /// Fewer bytes than a header buffered, or scanning for a valid header
+    Header,
+    /// A header with a valid LRC starts the buffer; waiting for the rest of
+    /// the `frame_len` byte frame
+    Payload { frame_len: usize },
+}
+
 /// Stateful stream parser for TCP or other byte-stream transports.
 ///
-/// Buffers incoming bytes and scans for valid ANPP packets. When a parse
-/// attempt fails, advances by one byte and retries — necessary because
-/// TCP provides no packet boundaries.
+/// Buffers incoming bytes and scans for valid ANPP packets, moving from
+/// Idle to Header once bytes arrive and to Payload once a header passes its
+/// LRC check. Partial headers and payloads are kept across calls, so reads
+/// may split frames anywhere. When a header turns out to be invalid,
+/// advances by one byte and retries — necessary because TCP provides no
+/// packet boundaries. A rejected frame is skipped as the [`ResyncPolicy`]
+/// says.
 pub struct AnppParser {
     buf: Vec<u8>,
     buf_start: usize, // Start position of valid data in buffer
+    state: State,
+    next_sequence: u64,
+    profile: ProtocolProfile,
+    resync: ResyncPolicy,
+    stats: ParserStats,
+    trailing: Payload,
 }
 
 impl AnppParser {
     pub fn new() -> Self {
+        Self::with_profile(ProtocolProfile::strict())
+    }
+
+    /// Create a parser that validates packet lengths against `profile`
+    pub fn with_profile(profile: ProtocolProfile) -> Self {
         Self {
             buf: Vec::new(),
             buf_start: 0,
+            state: State::Idle,
+            next_sequence: 0,
+            profile,
+            resync: ResyncPolicy::default(),
+            stats: ParserStats::default(),
+            trailing: Payload::new(),
         }
     }
 
+    /// Recovery strategy for rejected frames
+    pub fn with_resync_policy(mut self, resync: ResyncPolicy) -> Self {
+        self.resync = resync;
+        self
+    }
+
+    pub fn resync_policy(&self) -> ResyncPolicy {
+        self.resync
+    }
+
+    /// Counts of packets, errors and recoveries since the parser was created
+    pub fn stats(&self) -> &ParserStats {
+        &self.stats
+    }
+
+    pub fn profile(&self) -> &ProtocolProfile {
+        &self.profile
+    }
+
+    /// Same as [`consume`](Self::consume) but tags the packet with its
+    /// sequence number so consumers fanning out work can restore order.
+    pub fn consume_sequenced(&mut self, input: &[u8]) -> Option<ReceivedPacket> {
+        let packet = self.consume(input)?;
+        Some(ReceivedPacket {
+            sequence: self.next_sequence - 1,
+            packet,
+            trailing: self.trailing.clone(),
+        })
+    }
+
+    /// Payload bytes the profile ignored after the documented fields of
+    /// the packet [`consume`](Self::consume) last returned
+    pub fn trailing(&self) -> &[u8] {
+        &self.trailing
+    }
+
+    /// Sequence number the next emitted packet will carry. This is also
+    /// the total number of packets emitted so far.
+    pub fn next_sequence(&self) -> u64 {
+        self.next_sequence
+    }
+
     /// Consume bytes and attempt to parse a packet. If we can't
     /// find a complete packet we return None. If we get a packet it doesn't
     /// guarantee the whole internal buffer is drained.
@@ -135,50 +284,110 @@ impl AnppParser {
         loop {
             let available_data = &self.buf[self.buf_start..];
 
-            if available_data.is_empty() {
-                return None;
-            }
-
-            match parse_packet(available_data) {
-                Ok((packet, bytes_consumed)) => {
-                    // Advance buffer start position instead of draining
-                    self.buf_start += bytes_consumed;
-
-                    // Compact buffer if it gets too fragmented
-                    if self.buf_start > self.buf.len() / 2 {
-                        self.buf.drain(0..self.buf_start);
-                        self.buf_start = 0;
+            match self.state {
+                State::Idle | State::Header => {
+                    if available_data.is_empty() {
+                        self.state = State::Idle;
+                        return None;
+                    }
+                    self.state = State::Header;
+                    match parse_header(available_data) {
+                        Ok(frame_len) => self.state = State::Payload { frame_len },
+                        Err(ParseError::IncompleteData) => return None,
+                        Err(_) => {
+                            // Advance by 1 byte to find next valid header
+                            self.stats.header_errors += 1;
+                            self.discard(1);
+                        }
                     }
-
-                    return Some(packet);
-                },
-                Err(ParseError::IncompleteData) => {
-                    return None;
                 }
-                Err(ParseError::InvalidCRC | ParseError::InvalidHeader | ParseError::InvalidPayload) => {
-                    // Advance by 1 byte to find next valid packet
-                    self.buf_start += 1;
-
-                    // Ensure we don't go past buffer end
-                    if self.buf_start >= self.buf.len() {
-                        self.buf.clear();
-                        self.buf_start = 0;
+                State::Payload { frame_len } => {
+                    if available_data.len() < frame_len {
+                        #[cfg(feature = "tracing")]
+                        debug!("Don't have full packet, need {} bytes but have {}", frame_len, available_data.len());
                         return None;
                     }
+                    self.state = State::Header;
+                    match parse_frame(&available_data[..frame_len], &self.profile) {
+                        Ok((packet, trailing)) => {
+                            self.trailing = Payload::from_slice(trailing).unwrap_or_default();
+                            if !trailing.is_empty() {
+                                self.stats.trailing_ignored += 1;
+                            }
+                            #[cfg(feature = "tracing")]
+                            tracing::debug!(
+                                kind = ?PacketKind::from(packet.packet_id()),
+                                packet_id = packet.packet_id(),
+                                length = frame_len - MIN_PACKET_SIZE,
+                                sequence = self.next_sequence,
+                                "decoded packet"
+                            );
+                            self.skip(frame_len);
+                            self.next_sequence += 1;
+                            self.stats.packets += 1;
+                            return Some(packet);
+                        }
+                        Err(error) => {
+                            #[cfg(feature = "tracing")]
+                            tracing::warn!(
+                                packet_id = available_data[1],
+                                length = frame_len - MIN_PACKET_SIZE,
+                                crc = matches!(error, ParseError::InvalidCRC),
+                                resync = ?self.resync,
+                                "rejected frame"
+                            );
+                            if matches!(error, ParseError::InvalidCRC) {
+                                self.stats.crc_errors += 1;
+                            } else {
+                                self.stats.payload_errors += 1;
+                            }
+                            match self.resync {
+                                ResyncPolicy::SkipByte => {
+                                    self.stats.byte_resyncs += 1;
+                                    self.discard(1);
+                                }
+                                ResyncPolicy::SkipFrame => {
+                                    self.stats.frame_skips += 1;
+                                    self.discard(frame_len);
+                                }
+                            }
+                        }
+                    }
                 }
             }
         }
     }
 
+    fn discard(&mut self, n: usize) {
+        self.stats.bytes_discarded += n as u64;
+        self.skip(n);
+    }
+
+    fn skip(&mut self, n: usize) {
+        // Advance buffer start position instead of draining
+        self.buf_start += n;
+
+        if self.buf_start >= self.buf.len() {
+            self.buf.clear();
+            self.buf_start = 0;
+        } else if self.buf_start > self.buf.len() / 2 {
+            // Compact buffer if it gets too fragmented
+            self.buf.drain(0..self.buf_start);
+            self.buf_start = 0;
+        }
+    }
+
     /// Get the current buffer length (for debugging/monitoring)
     pub fn buffer_len(&self) -> usize {
         self.buf.len() - self.buf_start
     }
 
-    /// Clear the internal buffer
+    /// Clear the internal buffer. The sequence counter and statistics keep
+    /// running.
     pub fn clear(&mut self) {
         self.buf.clear();
         self.buf_start = 0;
+        self.state = State::Idle;
     }
 }
 
@@ -191,6 +400,7 @@ impl Default for AnppParser {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::Payload;
     use crate::packet::{PacketId, system::Request};
     use binrw::BinWrite;
 
@@ -215,7 +425,8 @@ mod tests {
         let mut parser = AnppParser::new();
 
         // Create a request packet manually
-        let packet_data = Request { requested_packet: PacketKind::SystemState };
+        let packet_data = Request::single(PacketKind::SystemState);
+        #[allow(clippy::let_unit_value)]
         let _packet_bytes = packet_data.write_le(&mut std::io::Cursor::new(Vec::new())).unwrap();
 
         // This would need proper ANPP framing to test fully
@@ -255,7 +466,7 @@ mod tests {
         let packet = parser.consume(&frame).expect("should parse a valid packet");
 
         match packet {
-            Packet::Request(req) => assert_eq!(req.requested_packet, PacketKind::SystemState),
+            Packet::Request(req) => assert_eq!(req.requested_packets, vec![PacketKind::SystemState]),
             other => panic!("expected Request packet, got {:?}", other),
         }
 
@@ -272,8 +483,11 @@ mod tests {
         let mut parser = AnppParser::new();
         let packet = parser.consume(&frame).expect("should parse unsupported packet");
 
-        assert!(matches!(packet, Packet::Unsupported(_)));
+        assert_eq!(packet, Packet::Unsupported { id: 255, payload: Payload::from_slice(&payload).unwrap() });
         assert_eq!(parser.buffer_len(), 0);
+
+        // Forwarded frames are byte-for-byte identical
+        assert_eq!(packet.to_bytes().unwrap(), frame);
     }
 
     #[test]
@@ -287,27 +501,153 @@ mod tests {
         let mut parser = AnppParser::new();
         let p1 = parser.consume(&combined).expect("should parse first packet");
         match p1 {
-            Packet::Request(req) => assert_eq!(req.requested_packet, PacketKind::SystemState),
+            Packet::Request(req) => assert_eq!(req.requested_packets, vec![PacketKind::SystemState]),
             other => panic!("expected Request(20), got {:?}", other),
         }
 
         // Drain second packet without re-appending
         let p2 = parser.consume(&[]).expect("should parse second packet");
         match p2 {
-            Packet::Request(req) => assert_eq!(req.requested_packet, PacketKind::UnixTime),
+            Packet::Request(req) => assert_eq!(req.requested_packets, vec![PacketKind::UnixTime]),
             other => panic!("expected Request(21), got {:?}", other),
         }
 
         assert_eq!(parser.buffer_len(), 0);
     }
 
+    fn feed_bytewise(parser: &mut AnppParser, stream: &[u8]) -> Vec<Packet> {
+        let mut packets = Vec::new();
+        for byte in stream {
+            let mut next = parser.consume(&[*byte]);
+            while let Some(packet) = next {
+                packets.push(packet);
+                next = parser.consume(&[]);
+            }
+        }
+        packets
+    }
+
+    #[test]
+    fn test_one_byte_at_a_time() {
+        let mut stream = vec![0x00, 0x13];
+        for id in [20u8, 21, 23] {
+            stream.extend(AnppProtocol::get_packet_bytes(PacketId::new(1), &[id]).unwrap());
+            stream.push(0xFF);
+        }
+        let status = AnppProtocol::get_packet_bytes(PacketId::new(23), &[0, 0, 1, 0]).unwrap();
+        stream.extend(&status);
+
+        let mut parser = AnppParser::new();
+        let packets = feed_bytewise(&mut parser, &stream);
+        assert_eq!(packets.len(), 4);
+        assert!(matches!(packets[3], Packet::Status(_)));
+        assert_eq!(parser.buffer_len(), 0);
+        assert_eq!(parser.state, State::Idle);
+    }
+
+    #[test]
+    fn test_split_at_every_boundary() {
+        let frame = AnppProtocol::get_packet_bytes(PacketId::new(23), &[0, 0, 1, 0]).unwrap();
+        for split in 1..frame.len() {
+            let mut parser = AnppParser::new();
+            assert!(parser.consume(&frame[..split]).is_none(), "split at {}", split);
+            let expected = if split < MIN_PACKET_SIZE { State::Header } else { State::Payload { frame_len: frame.len() } };
+            assert_eq!(parser.state, expected, "split at {}", split);
+            assert!(matches!(parser.consume(&frame[split..]), Some(Packet::Status(_))), "split at {}", split);
+        }
+    }
+
+    #[test]
+    fn test_false_header_does_not_swallow_real_frame() {
+        // A header with a valid LRC but a payload whose CRC is wrong,
+        // immediately followed by a real frame
+        let mut fake = AnppProtocol::get_packet_bytes(PacketId::new(1), &[20]).unwrap();
+        fake[5] = 21;
+        let real = AnppProtocol::get_packet_bytes(PacketId::new(1), &[22]).unwrap();
+        let mut stream = fake;
+        stream.extend(&real);
+
+        let mut parser = AnppParser::new();
+        let packets = feed_bytewise(&mut parser, &stream);
+        assert_eq!(packets.len(), 1);
+        match &packets[0] {
+            Packet::Request(req) => assert_eq!(req.requested_packets, vec![PacketKind::from(22)]),
+            other => panic!("expected Request, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_resync_policies() {
+        let mut corrupt = AnppProtocol::get_packet_bytes(PacketId::new(23), &[0, 0, 1, 0]).unwrap();
+        corrupt[6] ^= 0x01;
+        let mut stream = corrupt.clone();
+        stream.extend(AnppProtocol::get_packet_bytes(PacketId::new(1), &[20]).unwrap());
+
+        let mut robust = AnppParser::new();
+        assert_eq!(robust.resync_policy(), ResyncPolicy::SkipByte);
+        assert_eq!(feed_bytewise(&mut robust, &stream).len(), 1);
+        let stats = robust.stats();
+        assert_eq!((stats.packets, stats.crc_errors, stats.byte_resyncs, stats.frame_skips), (1, 1, 1, 0));
+        assert_eq!(stats.bytes_discarded, corrupt.len() as u64);
+        assert_eq!(stats.header_errors, corrupt.len() as u64 - 1);
+
+        let mut fast = AnppParser::new().with_resync_policy(ResyncPolicy::SkipFrame);
+        assert_eq!(feed_bytewise(&mut fast, &stream).len(), 1);
+        let stats = fast.stats();
+        assert_eq!((stats.packets, stats.crc_errors, stats.byte_resyncs, stats.frame_skips), (1, 1, 0, 1));
+        assert_eq!(stats.bytes_discarded, corrupt.len() as u64);
+        assert_eq!(stats.header_errors, 0);
+    }
+
+    #[test]
+    fn test_skip_frame_trusts_the_header() {
+        // The fast policy loses a real frame hidden inside a false one
+        let real = AnppProtocol::get_packet_bytes(PacketId::new(1), &[20]).unwrap();
+        let mut false_frame = AnppProtocol::get_packet_bytes(PacketId::new(10), &[0; 8]).unwrap();
+        false_frame.truncate(5);
+        let mut stream = false_frame;
+        stream.extend(&real);
+        stream.extend([0; 2]);
+
+        let mut fast = AnppParser::new().with_resync_policy(ResyncPolicy::SkipFrame);
+        assert!(fast.consume(&stream).is_none());
+        assert_eq!(fast.stats().frame_skips, 1);
+
+        let mut robust = AnppParser::new();
+        assert!(robust.consume(&stream).is_some());
+    }
+
+    #[test]
+    fn test_sequence_numbers_are_monotonic() {
+        let mut stream = Vec::new();
+        for id in [20u8, 21, 22] {
+            stream.extend(AnppProtocol::get_packet_bytes(PacketId::new(1), &[id]).unwrap());
+        }
+
+        let mut parser = AnppParser::new();
+        let first = parser.consume_sequenced(&stream).unwrap();
+        let second = parser.consume(&[]).unwrap();
+        let third = parser.consume_sequenced(&[]).unwrap();
+
+        assert_eq!(first.sequence, 0);
+        assert!(matches!(second, Packet::Request(_)));
+        assert_eq!(third.sequence, 2);
+        assert_eq!(parser.next_sequence(), 3);
+
+        // Garbage and clearing don't consume sequence numbers
+        assert!(parser.consume_sequenced(&[0xFF; 8]).is_none());
+        parser.clear();
+        let frame = AnppProtocol::get_packet_bytes(PacketId::new(1), &[20]).unwrap();
+        assert_eq!(parser.consume_sequenced(&frame).unwrap().sequence, 3);
+    }
+
     #[test]
     fn test_parse_datagram_valid() {
         let frame = AnppProtocol::get_packet_bytes(PacketId::new(1), &[20]).unwrap();
         let packet = parse_datagram(&frame).expect("should parse a valid datagram");
 
         match packet {
-            Packet::Request(req) => assert_eq!(req.requested_packet, PacketKind::SystemState),
+            Packet::Request(req) => assert_eq!(req.requested_packets, vec![PacketKind::SystemState]),
             other => panic!("expected Request packet, got {:?}", other),
         }
     }
@@ -316,7 +656,7 @@ mod tests {
     fn test_parse_datagram_unsupported() {
         let frame = AnppProtocol::get_packet_bytes(PacketId::new(255), &[0xAA]).unwrap();
         let packet = parse_datagram(&frame).expect("should parse unsupported datagram");
-        assert!(matches!(packet, Packet::Unsupported(_)));
+        assert!(matches!(packet, Packet::Unsupported { .. }));
     }
 
     #[test]
@@ -339,4 +679,64 @@ mod tests {
         frame[last] ^= 0xFF;
         assert!(matches!(parse_datagram(&frame), Err(DatagramError::InvalidCrc)));
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_extended_ip_dataports_falls_back_to_documented_fields() {
+        let config = crate::packet::config::IpDataportsConfiguration::builder().tcp_server(0, 16718).build().unwrap();
+        let mut payload = Packet::IpDataportsConfiguration(config.clone()).to_bytes().unwrap()[5..].to_vec();
+        payload.extend([0x01, 0x02, 0x03]);
+        let frame = AnppProtocol::get_packet_bytes(PacketId::new(202), &payload).unwrap();
+
+        let mut parser = AnppParser::with_profile(ProtocolProfile::forward_compatible());
+        match parser.consume(&frame) {
+            Some(Packet::IpDataportsConfiguration(decoded)) => {
+                assert_eq!(decoded.dataports, config.dataports);
+                assert_eq!(decoded.extension.map(|e| e.bytes), Some(vec![0x01, 0x02, 0x03]));
+            }
+            other => panic!("expected IpDataportsConfiguration packet, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_lenient_profile_surfaces_trailing_bytes() {
+        // Unix Time from a firmware variant that appends a 4 byte field
+        let payload = [0x00, 0xF1, 0x53, 0x65, 0x05, 0x00, 0x00, 0x00, 0xAA, 0xBB, 0xCC, 0xDD];
+        let mut frames = AnppProtocol::get_packet_bytes(PacketId::new(21), &payload).unwrap();
+        frames.extend(AnppProtocol::get_packet_bytes(PacketId::new(1), &[20]).unwrap());
+
+        let mut strict = AnppParser::new();
+        assert!(matches!(strict.consume(&frames).unwrap(), Packet::Request(_)));
+
+        let mut parser = AnppParser::with_profile(ProtocolProfile::lenient());
+        let first = parser.consume_sequenced(&frames).unwrap();
+        assert!(matches!(first.packet, Packet::UnixTime(ref time) if time.microseconds == 5));
+        assert_eq!(first.trailing.as_slice(), &[0xAA, 0xBB, 0xCC, 0xDD]);
+        assert_eq!(parser.stats().trailing_ignored, 1);
+
+        let second = parser.consume_sequenced(&[]).unwrap();
+        assert!(matches!(second.packet, Packet::Request(_)));
+        assert!(second.trailing.is_empty());
+        assert!(parser.trailing().is_empty());
+    }
+
+    #[test]
+    fn test_extended_status_requires_profile() {
+        // Status followed by a hypothetical 4 byte extension from newer firmware
+        let payload = [0x00, 0x00, 0x01, 0x00, 0xDE, 0xAD, 0xBE, 0xEF];
+        let frame = AnppProtocol::get_packet_bytes(PacketId::new(23), &payload).unwrap();
+
+        let mut strict = AnppParser::new();
+        assert!(strict.consume(&frame).is_none());
+
+        let mut parser = AnppParser::with_profile(ProtocolProfile::forward_compatible());
+        match parser.consume(&frame) {
+            Some(Packet::Status(status)) => {
+                assert!(status.filter_status.orientation_filter_initialised());
+                let extension = status.extension.expect("extension bytes kept");
+                assert_eq!(extension.read::<u32>(0), Some(0xEFBEADDE));
+                assert_eq!(extension.read::<u32>(2), None);
+            }
+            other => panic!("expected Status packet, got {:?}", other),
+        }
+    }
+}
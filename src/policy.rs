@@ -0,0 +1,195 @@
+#[cfg(feature = "serde")]
+use serde::{Serialize, Deserialize};
+
+use crate::alarms::{AlarmId, Severity};
+
+/// Operating action recommended in response to an alarm
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum Action {
+    ReduceSpeed,
+    SwitchToSecondaryIns,
+    NotifyOperator,
+    HoldPosition,
+    /// Site specific action identified by name
+    Custom(String),
+}
+
+/// Condition that triggers a rule
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum Condition {
+    /// A specific alarm is active
+    Alarm(AlarmId),
+    /// Any active alarm is at least this severe
+    SeverityAtLeast(Severity),
+}
+
+impl Condition {
+    fn matches(&self, alarm: AlarmId) -> bool {
+        match self {
+            Condition::Alarm(id) => *id == alarm,
+            Condition::SeverityAtLeast(severity) => alarm.severity() >= *severity,
+        }
+    }
+}
+
+/// A single `when -> then` mapping
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Rule {
+    pub when: Condition,
+    pub then: Action,
+}
+
+/// An action recommended by a policy, with the alarms that triggered it
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Recommendation {
+    pub action: Action,
+    pub triggered_by: Vec<AlarmId>,
+}
+
+/// Declarative alarm-to-action policy.
+///
+/// Build one in code with [`Policy::builder`] or load it with serde, e.g.
+/// from a fleet-wide JSON file (the derives need the `serde` feature):
+///
+#[cfg_attr(feature = "serde", doc = "```")]
+#[cfg_attr(not(feature = "serde"), doc = "```ignore")]
+/// use liban::policy::{Action, Policy};
+/// use liban::alarms::AlarmId;
+///
+/// let policy: Policy = serde_json::from_str(r#"{
+///     "rules": [
+///         { "when": { "Alarm": "GnssFailure" }, "then": "ReduceSpeed" },
+///         { "when": { "SeverityAtLeast": "Critical" }, "then": "SwitchToSecondaryIns" }
+///     ]
+/// }"#).unwrap();
+///
+/// let actions = policy.evaluate(&[AlarmId::GnssFailure]);
+/// assert_eq!(actions[0].action, Action::ReduceSpeed);
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Policy {
+    pub rules: Vec<Rule>,
+}
+
+impl Policy {
+    pub fn builder() -> PolicyBuilder {
+        PolicyBuilder::default()
+    }
+
+    /// Evaluate the policy against the currently active alarms.
+    ///
+    /// Each action appears once, in the order its first rule was declared.
+    pub fn evaluate(&self, active: &[AlarmId]) -> Vec<Recommendation> {
+        let mut recommendations: Vec<Recommendation> = Vec::new();
+
+        for rule in &self.rules {
+            let triggered: Vec<AlarmId> = active.iter()
+                .copied()
+                .filter(|alarm| rule.when.matches(*alarm))
+                .collect();
+            if triggered.is_empty() {
+                continue;
+            }
+
+            match recommendations.iter_mut().find(|r| r.action == rule.then) {
+                Some(existing) => {
+                    for alarm in triggered {
+                        if !existing.triggered_by.contains(&alarm) {
+                            existing.triggered_by.push(alarm);
+                        }
+                    }
+                }
+                None => recommendations.push(Recommendation {
+                    action: rule.then.clone(),
+                    triggered_by: triggered,
+                }),
+            }
+        }
+
+        recommendations
+    }
+}
+
+/// Builder for [`Policy`]
+#[derive(Debug, Default)]
+pub struct PolicyBuilder {
+    rules: Vec<Rule>,
+}
+
+impl PolicyBuilder {
+    /// Recommend `action` whenever `alarm` is active
+    pub fn on_alarm(mut self, alarm: AlarmId, action: Action) -> Self {
+        self.rules.push(Rule { when: Condition::Alarm(alarm), then: action });
+        self
+    }
+
+    /// Recommend `action` whenever any alarm of at least `severity` is active
+    pub fn on_severity(mut self, severity: Severity, action: Action) -> Self {
+        self.rules.push(Rule { when: Condition::SeverityAtLeast(severity), then: action });
+        self
+    }
+
+    pub fn build(self) -> Policy {
+        Policy { rules: self.rules }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn policy() -> Policy {
+        Policy::builder()
+            .on_alarm(AlarmId::GnssFailure, Action::ReduceSpeed)
+            .on_alarm(AlarmId::GnssAntennaDisconnected, Action::ReduceSpeed)
+            .on_severity(Severity::Critical, Action::SwitchToSecondaryIns)
+            .on_severity(Severity::Warning, Action::NotifyOperator)
+            .build()
+    }
+
+    #[test]
+    fn test_no_alarms_no_actions() {
+        assert!(policy().evaluate(&[]).is_empty());
+    }
+
+    #[test]
+    fn test_actions_are_deduplicated_in_rule_order() {
+        let actions = policy().evaluate(&[AlarmId::GnssAntennaDisconnected, AlarmId::GnssFailure]);
+        assert_eq!(actions, vec![
+            Recommendation {
+                action: Action::ReduceSpeed,
+                triggered_by: vec![AlarmId::GnssFailure, AlarmId::GnssAntennaDisconnected],
+            },
+            Recommendation {
+                action: Action::NotifyOperator,
+                triggered_by: vec![AlarmId::GnssAntennaDisconnected, AlarmId::GnssFailure],
+            },
+        ]);
+    }
+
+    #[test]
+    fn test_severity_rule_covers_more_severe_alarms() {
+        let actions = policy().evaluate(&[AlarmId::SystemFailure]);
+        let names: Vec<_> = actions.iter().map(|r| r.action.clone()).collect();
+        assert_eq!(names, vec![Action::SwitchToSecondaryIns, Action::NotifyOperator]);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_policy_serde_round_trip() {
+        let original = Policy::builder()
+            .on_alarm(AlarmId::HighVoltage, Action::Custom("cut-thrusters".to_string()))
+            .build();
+        let json = serde_json::to_string(&original).unwrap();
+        let loaded: Policy = serde_json::from_str(&json).unwrap();
+        assert_eq!(loaded, original);
+    }
+}
@@ -0,0 +1,131 @@
+//! Protocol profiles describing how strictly to treat packet lengths.
+//!
+//! Advanced Navigation occasionally appends fields to existing packets in a
+//! firmware point release. With the default [`ProtocolProfile::strict`] such
+//! packets fail the length check and are dropped. A profile with the
+//! [`Quirk::TrailingExtensionBytes`] quirk instead decodes the documented
+//! fields and keeps the extra bytes in the packet's extension field.
+//! [`Quirk::TrailingBytes`] goes further for any fixed length packet: the
+//! documented fields are decoded and the rest is set aside as trailing
+//! bytes, which the stream parser hands back with
+//! [`ReceivedPacket::trailing`](crate::parser::ReceivedPacket::trailing).
+
+#[cfg(feature = "serde")]
+use serde::{Serialize, Deserialize};
+
+use crate::packet::PacketKind;
+
+/// Deviation from the documented protocol that a parser should tolerate
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum Quirk {
+    /// Packets longer than documented carry extra fields after the documented
+    /// ones. Only packets with an extension field are affected.
+    TrailingExtensionBytes,
+    /// Packets longer than documented are decoded from their documented
+    /// fields and the remaining bytes are ignored. Applies to every fixed
+    /// length packet; with [`TrailingExtensionBytes`](Self::TrailingExtensionBytes)
+    /// as well, extensible packets still keep their extension field.
+    TrailingBytes,
+}
+
+/// Set of quirks a parser applies when validating packets
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct ProtocolProfile {
+    pub quirks: Vec<Quirk>,
+}
+
+impl ProtocolProfile {
+    /// Packet lengths must match the documentation exactly
+    pub fn strict() -> Self {
+        Self::default()
+    }
+
+    /// Profile for devices whose firmware may be newer than this library
+    pub fn forward_compatible() -> Self {
+        Self::strict().with_quirk(Quirk::TrailingExtensionBytes)
+    }
+
+    /// Profile for firmware variants: any fixed length packet may be longer
+    /// than documented
+    pub fn lenient() -> Self {
+        Self::forward_compatible().with_quirk(Quirk::TrailingBytes)
+    }
+
+    pub fn with_quirk(mut self, quirk: Quirk) -> Self {
+        if !self.has_quirk(quirk) {
+            self.quirks.push(quirk);
+        }
+        self
+    }
+
+    pub fn has_quirk(&self, quirk: Quirk) -> bool {
+        self.quirks.contains(&quirk)
+    }
+
+    /// Whether a payload of `length` bytes is acceptable for `kind`
+    pub fn accepts_length(&self, kind: PacketKind, length: usize) -> bool {
+        if length < kind.min_length() {
+            return false;
+        }
+        match kind.byte_length() {
+            None => true,
+            Some(expected) if length == expected => true,
+            Some(_) if self.has_quirk(Quirk::TrailingBytes) => true,
+            Some(_) => self.has_quirk(Quirk::TrailingExtensionBytes) && length <= kind.max_length(),
+        }
+    }
+
+    /// How many of `length` acceptable payload bytes the packet's fields
+    /// decode; the rest are trailing bytes this profile ignores
+    pub fn decoded_length(&self, kind: PacketKind, length: usize) -> usize {
+        match kind.byte_length() {
+            Some(_) if self.has_quirk(Quirk::TrailingExtensionBytes) => length.min(kind.max_length()),
+            Some(expected) => length.min(expected),
+            None => length,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_strict_profile_requires_exact_length() {
+        let profile = ProtocolProfile::strict();
+        assert!(profile.accepts_length(PacketKind::SystemState, 100));
+        assert!(!profile.accepts_length(PacketKind::SystemState, 108));
+        assert!(profile.accepts_length(PacketKind::PacketsPeriod, 7));
+    }
+
+    #[test]
+    fn test_forward_compatible_profile_allows_extensions() {
+        let profile = ProtocolProfile::forward_compatible();
+        assert!(profile.accepts_length(PacketKind::SystemState, 108));
+        assert!(profile.accepts_length(PacketKind::Status, 6));
+        assert!(!profile.accepts_length(PacketKind::SystemState, 96));
+        // Packets without an extension field stay strict
+        assert!(!profile.accepts_length(PacketKind::UnixTime, 12));
+    }
+
+    #[test]
+    fn test_lenient_profile_ignores_trailing_bytes() {
+        let profile = ProtocolProfile::lenient();
+        assert!(profile.accepts_length(PacketKind::UnixTime, 12));
+        assert_eq!(profile.decoded_length(PacketKind::UnixTime, 12), 8);
+        assert!(!profile.accepts_length(PacketKind::UnixTime, 7));
+        // The extension field still takes the extra bytes
+        assert_eq!(profile.decoded_length(PacketKind::SystemState, 108), 108);
+        assert_eq!(profile.decoded_length(PacketKind::DetailedSatellites, 14), 14);
+
+        let trailing_only = ProtocolProfile::strict().with_quirk(Quirk::TrailingBytes);
+        assert_eq!(trailing_only.decoded_length(PacketKind::SystemState, 108), 100);
+        // Below the table's minimum nothing decodes
+        assert!(!trailing_only.accepts_length(PacketKind::FileTransferData, 8));
+        assert!(ProtocolProfile::strict().accepts_length(PacketKind::FileTransferData, 9));
+    }
+}
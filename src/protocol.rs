@@ -1,5 +1,5 @@
 use crate::error::{AnError, Result};
-use crate::packet::{PacketId, PacketKind, AnppHeader, Packet, system::Request};
+use crate::packet::{HasPacketId, PacketId, PacketKind, AnppHeader, Packet, system::Request};
 use crc::{Crc, Algorithm};
 use binrw::{BinRead, BinWrite};
 use std::io::Cursor;
@@ -66,9 +66,7 @@ impl AnppProtocol {
     /// Parse an ANPP packet and return structured header with data
     pub fn get_header_from_bytes(packet: &[u8]) -> Result<(AnppHeader, Vec<u8>)> {
         if packet.len() < 5 {
-            return Err(AnError::InvalidPacket(
-                "Packet too short (minimum 5 bytes)".to_string(),
-            ));
+            return Err(AnError::Truncated { needed: 5 - packet.len() });
         }
 
         // Extract header
@@ -76,10 +74,14 @@ impl AnppProtocol {
 
         // Validate packet length
         let expected_length = 5 + header.length as usize;
+        if packet.len() < expected_length {
+            return Err(AnError::Truncated { needed: expected_length - packet.len() });
+        }
         if packet.len() != expected_length {
-            return Err(AnError::InvalidLength {
+            return Err(AnError::LengthMismatch {
+                id: header.packet_id.as_u8(),
                 expected: expected_length,
-                actual: packet.len(),
+                got: packet.len(),
             });
         }
 
@@ -100,7 +102,7 @@ impl AnppProtocol {
     pub fn serialize_header(header: &AnppHeader) -> Result<Vec<u8>> {
         let mut cursor = Cursor::new(Vec::new());
         header.write_le(&mut cursor)
-            .map_err(|e| AnError::InvalidPacket(format!("Failed to serialize header: {}", e)))?;
+            .map_err(|source| AnError::Encode { packet: "header", source })?;
         Ok(cursor.into_inner())
     }
 
@@ -108,33 +110,31 @@ impl AnppProtocol {
     pub fn deserialize_header(bytes: &[u8]) -> Result<AnppHeader> {
         let mut cursor = Cursor::new(bytes);
         AnppHeader::read_le(&mut cursor)
-            .map_err(|e| AnError::InvalidPacket(format!("Failed to deserialize header: {}", e)))
+            .map_err(|source| AnError::Decode { packet: "header", source })
     }
 
     /// Validate header against data payload
     pub fn validate_header(header: &AnppHeader, data: &[u8]) -> Result<()> {
         // Validate length
         if header.length as usize != data.len() {
-            return Err(AnError::InvalidLength {
+            return Err(AnError::LengthMismatch {
+                id: header.packet_id.as_u8(),
                 expected: header.length as usize,
-                actual: data.len(),
+                got: data.len(),
             });
         }
 
         // Validate CRC
         let calculated_crc = Self::calculate_crc16(data);
         if header.crc16 != calculated_crc {
-            return Err(AnError::InvalidChecksum);
+            return Err(AnError::CrcMismatch { expected: header.crc16, actual: calculated_crc });
         }
 
         // Validate header LRC
         let expected_lrc = Self::calculate_lrc(header.packet_id.as_u8(), header.length, header.crc16);
 
         if header.header_lrc != expected_lrc {
-            return Err(AnError::InvalidPacket(format!(
-                "Header LRC mismatch: expected 0x{:02X}, got 0x{:02X}",
-                expected_lrc, header.header_lrc
-            )));
+            return Err(AnError::LrcMismatch { expected: expected_lrc, actual: header.header_lrc });
         }
 
         Ok(())
@@ -147,12 +147,17 @@ impl AnppProtocol {
         Packet::from_bytes(header.packet_id.as_u8(), &data)
     }
 
+    /// Framed Request for packet type `T`, ready to send
+    pub fn encode_request<T: HasPacketId>() -> Vec<u8> {
+        Packet::Request(Request::for_packet::<T>())
+            .to_bytes()
+            .expect("a single packet request always fits in a frame")
+    }
+
     /// Create a request packet for the specified packet ID
     #[allow(dead_code)]
     pub(crate) fn create_request(requested_packet_id: PacketId) -> Request {
-        Request {
-            requested_packet: PacketKind::from(requested_packet_id.as_u8())
-        }
+        Request::single(PacketKind::from(requested_packet_id.as_u8()))
     }
 }
 
@@ -200,7 +205,7 @@ mod tests {
         let data = cursor.into_inner();
 
         // Should be a request for packet ID 3
-        assert_eq!(request_packet.requested_packet.packet_id(), 3);
+        assert_eq!(request_packet.requested_packets, vec![PacketKind::DeviceInformation]);
         assert_eq!(data, vec![3]); // Just the requested packet ID
     }
 
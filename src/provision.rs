@@ -0,0 +1,455 @@
+//! Whole-device configuration snapshots for provisioning.
+//!
+//! A [`ConfigSet`] holds at most one packet of each writable configuration
+//! kind. [`Client::read_full_config`] fills one from a device and
+//! [`Client::apply_full_config`] writes one back in stages: the settings
+//! first, then the packets that can cut the link (baud rates, IP dataports
+//! and the IP configuration last). Each stage is only sent once every write
+//! in the previous stage has been acknowledged, and a failed write stops
+//! the later stages. A set holding an IP configuration is only applied
+//! with the [`ConfirmNetworkChange`] token issued for it, as with
+//! [`Client::set_ip_configuration`].
+//!
+//! ```
+//! use liban::provision::{ApplyOptions, ConfigSet};
+//! use liban::client::Client;
+//! use liban::packet::config::PacketTimerPeriod;
+//! use liban::Packet;
+//!
+//! let mut set = ConfigSet::new();
+//! set.insert(Packet::PacketTimerPeriod(PacketTimerPeriod::from_rate_hz(1000.0).unwrap())).unwrap();
+//!
+//! let mut client = Client::new();
+//! // Validates and encodes every packet without queueing anything
+//! let warnings = client.apply_full_config(&set, &ApplyOptions::new().dry_run()).unwrap();
+//! assert!(warnings.is_empty());
+//! assert_eq!(client.pending(), 0);
+//! ```
+//!
+//! [`Client::read_full_config`]: crate::client::Client::read_full_config
+//! [`Client::apply_full_config`]: crate::client::Client::apply_full_config
+//! [`Client::set_ip_configuration`]: crate::client::Client::set_ip_configuration
+
+use std::collections::{BTreeMap, VecDeque};
+
+#[cfg(feature = "serde")]
+use serde::{Serialize, Deserialize};
+
+use crate::client::ConfirmNetworkChange;
+use crate::error::AnError;
+use crate::packet::config::FilterIssue;
+use crate::packet::system::{AcknowledgeResult, DeviceModel};
+use crate::packet::{Packet, PacketDirection, PacketKind};
+
+/// One packet per configuration kind, ordered by packet ID.
+///
+/// Serializes as a list of packets, the profile format of `liban-cli
+/// get-config`.
+#[derive(Debug, Clone, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(try_from = "Vec<Packet>", into = "Vec<Packet>"))]
+pub struct ConfigSet {
+    packets: BTreeMap<u8, Packet>,
+}
+
+impl ConfigSet {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Every kind a set can hold: the read/write configuration packets and
+    /// the IP configuration
+    pub fn kinds() -> impl Iterator<Item = PacketKind> {
+        PacketKind::all().filter(|kind| Self::holds(*kind))
+    }
+
+    /// Whether packets of this kind belong in a set
+    pub fn holds(kind: PacketKind) -> bool {
+        kind.direction() == PacketDirection::ReadWrite
+            && (kind.id() >= PacketKind::PacketTimerPeriod.id() || kind == PacketKind::IpConfiguration)
+    }
+
+    /// Add a packet, returning the one of the same kind it replaces
+    pub fn insert(&mut self, packet: Packet) -> Result<Option<Packet>, AnError> {
+        let kind = PacketKind::from(packet.packet_id());
+        if !Self::holds(kind) {
+            return Err(AnError::ValidationFailed(format!(
+                "{} (packet {}) is not a configuration packet", kind.name(), packet.packet_id())));
+        }
+        Ok(self.packets.insert(packet.packet_id(), packet))
+    }
+
+    pub fn get(&self, kind: PacketKind) -> Option<&Packet> {
+        self.packets.get(&kind.id())
+    }
+
+    pub fn remove(&mut self, kind: PacketKind) -> Option<Packet> {
+        self.packets.remove(&kind.id())
+    }
+
+    pub fn len(&self) -> usize {
+        self.packets.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.packets.is_empty()
+    }
+
+    /// Packets ordered by ID
+    pub fn iter(&self) -> impl Iterator<Item = &Packet> {
+        self.packets.values()
+    }
+
+    /// Kinds from [`ConfigSet::kinds`] with no packet in this set
+    pub fn missing(&self) -> Vec<PacketKind> {
+        Self::kinds().filter(|kind| self.get(*kind).is_none()).collect()
+    }
+
+    /// Packets grouped into the stages they are written in
+    pub fn write_stages(&self) -> Vec<Vec<&Packet>> {
+        let mut stages: Vec<Vec<&Packet>> = Vec::new();
+        let mut packets: Vec<&Packet> = self.iter().collect();
+        packets.sort_by_key(|packet| (write_stage(PacketKind::from(packet.packet_id())), packet.packet_id()));
+        let mut current = None;
+        for packet in packets {
+            let stage = write_stage(PacketKind::from(packet.packet_id()));
+            if current != Some(stage) {
+                stages.push(Vec::new());
+                current = Some(stage);
+            }
+            stages.last_mut().expect("stage pushed above").push(packet);
+        }
+        stages
+    }
+
+    /// Check every packet and encode it. Filter options are also checked
+    /// against `device` when given; their warnings are returned.
+    pub fn validate(&self, device: Option<DeviceModel>) -> Result<Vec<FilterIssue>, AnError> {
+        let mut warnings = Vec::new();
+        for packet in self.iter() {
+            let kind = PacketKind::from(packet.packet_id());
+            let checked = match packet {
+                Packet::IpConfiguration(ip) => ip.validate(),
+                Packet::PacketTimerPeriod(timer) => timer.validate(),
+                Packet::GpioConfiguration(gpio) => gpio.validate(),
+                Packet::InstallationAlignment(alignment) => alignment.validate(),
+                Packet::DualAntennaConfiguration(dual) => dual.validate(),
+                Packet::FilterOptions(options) => match device {
+                    Some(model) => options.validate_for_device(model).map(|issues| warnings.extend(issues)),
+                    None => Ok(()),
+                },
+                _ => Ok(()),
+            };
+            checked.and_then(|_| packet.to_bytes().map(|_| ())).map_err(|error| match error {
+                AnError::ValidationFailed(reason) => AnError::ValidationFailed(format!("{}: {}", kind.name(), reason)),
+                error => error,
+            })?;
+        }
+        Ok(warnings)
+    }
+}
+
+impl TryFrom<Vec<Packet>> for ConfigSet {
+    type Error = AnError;
+
+    /// Fails on a packet that is not configuration or a repeated kind
+    fn try_from(packets: Vec<Packet>) -> Result<Self, AnError> {
+        let mut set = ConfigSet::new();
+        for packet in packets {
+            let id = packet.packet_id();
+            if set.insert(packet)?.is_some() {
+                return Err(AnError::ValidationFailed(format!("packet {} appears more than once", id)));
+            }
+        }
+        Ok(set)
+    }
+}
+
+impl From<ConfigSet> for Vec<Packet> {
+    fn from(set: ConfigSet) -> Self {
+        set.packets.into_values().collect()
+    }
+}
+
+/// Same as the packet list it serializes to
+#[cfg(feature = "defmt")]
+impl defmt::Format for ConfigSet {
+    fn format(&self, f: defmt::Formatter) {
+        defmt::write!(f, "ConfigSet [");
+        for (i, packet) in self.packets.values().enumerate() {
+            if i > 0 {
+                defmt::write!(f, ", ");
+            }
+            defmt::write!(f, "{}", packet);
+        }
+        defmt::write!(f, "]");
+    }
+}
+
+/// Links are only changed once everything else is stored: a new baud rate
+/// or dataport table can cut the host off, and a new address certainly will
+fn write_stage(kind: PacketKind) -> u8 {
+    match kind {
+        PacketKind::BaudRates => 1,
+        PacketKind::IpDataportsConfiguration => 2,
+        PacketKind::IpConfiguration => 3,
+        _ => 0,
+    }
+}
+
+/// How [`Client::apply_full_config`](crate::client::Client::apply_full_config)
+/// writes a set
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ApplyOptions {
+    /// Replace every packet's `permanent` flag
+    pub permanent: Option<bool>,
+    /// Validate and encode only, queue nothing
+    pub dry_run: bool,
+    /// Check filter options against this device
+    pub device: Option<DeviceModel>,
+    /// Token for the set's IP configuration; a set holding one is refused
+    /// without it
+    pub network_change: Option<ConfirmNetworkChange>,
+}
+
+impl ApplyOptions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_permanent(mut self, permanent: bool) -> Self {
+        self.permanent = Some(permanent);
+        self
+    }
+
+    pub fn dry_run(mut self) -> Self {
+        self.dry_run = true;
+        self
+    }
+
+    pub fn with_device(mut self, device: DeviceModel) -> Self {
+        self.device = Some(device);
+        self
+    }
+
+    pub fn with_network_change(mut self, confirm_network_change: ConfirmNetworkChange) -> Self {
+        self.network_change = Some(confirm_network_change);
+        self
+    }
+}
+
+/// What became of one write in an apply
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum WriteOutcome {
+    Acknowledged,
+    /// The device acknowledged the write with a failure
+    Rejected(AcknowledgeResult),
+    /// No acknowledgement after every retry
+    NoAnswer,
+    /// Not sent because an earlier stage failed
+    Skipped,
+    /// Not queued because the kind's flow control queue was full
+    Backpressure,
+}
+
+/// Per-packet result of a finished apply, in write order
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ApplyReport {
+    pub outcomes: Vec<(PacketKind, WriteOutcome)>,
+}
+
+impl ApplyReport {
+    /// True when every write was acknowledged
+    pub fn is_success(&self) -> bool {
+        self.outcomes.iter().all(|(_, outcome)| *outcome == WriteOutcome::Acknowledged)
+    }
+
+    /// Writes that were not acknowledged
+    pub fn failures(&self) -> impl Iterator<Item = &(PacketKind, WriteOutcome)> {
+        self.outcomes.iter().filter(|(_, outcome)| *outcome != WriteOutcome::Acknowledged)
+    }
+}
+
+/// Read of every configuration kind in progress
+#[derive(Debug)]
+pub(crate) struct ConfigRead {
+    set: ConfigSet,
+    outstanding: Vec<PacketKind>,
+}
+
+impl ConfigRead {
+    pub(crate) fn new() -> Self {
+        Self { set: ConfigSet::new(), outstanding: ConfigSet::kinds().collect() }
+    }
+
+    pub(crate) fn outstanding(&self) -> &[PacketKind] {
+        &self.outstanding
+    }
+
+    pub(crate) fn received(&mut self, packet: &Packet) {
+        let kind = PacketKind::from(packet.packet_id());
+        if let Some(index) = self.outstanding.iter().position(|k| *k == kind) {
+            self.outstanding.remove(index);
+            self.set.insert(packet.clone()).expect("outstanding kinds belong in a set");
+        }
+    }
+
+    pub(crate) fn failed(&mut self, kind: PacketKind) {
+        self.outstanding.retain(|k| *k != kind);
+    }
+
+    /// The set once every kind has been answered or given up on
+    pub(crate) fn finished(&mut self) -> Option<ConfigSet> {
+        self.outstanding.is_empty().then(|| std::mem::take(&mut self.set))
+    }
+}
+
+/// Staged write of a set in progress
+#[derive(Debug)]
+pub(crate) struct ConfigApply {
+    stages: VecDeque<Vec<Packet>>,
+    in_flight: Vec<PacketKind>,
+    report: ApplyReport,
+}
+
+impl ConfigApply {
+    pub(crate) fn new(set: &ConfigSet, options: &ApplyOptions) -> Self {
+        let stages = set.write_stages().into_iter()
+            .map(|stage| stage.into_iter()
+                .map(|packet| {
+                    let mut packet = packet.clone();
+                    if let Some(permanent) = options.permanent {
+                        packet.set_permanent(permanent);
+                    }
+                    packet
+                })
+                .collect())
+            .collect();
+        Self { stages, in_flight: Vec::new(), report: ApplyReport::default() }
+    }
+
+    /// Every packet still to be written, after the permanent override
+    pub(crate) fn packets(&self) -> impl Iterator<Item = &Packet> {
+        self.stages.iter().flatten()
+    }
+
+    /// Record the answer to a write, ignoring kinds not in flight
+    pub(crate) fn answered(&mut self, kind: PacketKind, outcome: WriteOutcome) {
+        if let Some(index) = self.in_flight.iter().position(|k| *k == kind) {
+            self.in_flight.remove(index);
+            self.report.outcomes.push((kind, outcome));
+        }
+    }
+
+    /// Packets of the next stage once the current one is answered, empty
+    /// while it is not. After a failure the remaining stages are skipped.
+    pub(crate) fn next_stage(&mut self) -> Vec<Packet> {
+        if !self.in_flight.is_empty() {
+            return Vec::new();
+        }
+        if !self.report.is_success() {
+            for packet in self.stages.drain(..).flatten() {
+                self.report.outcomes.push((PacketKind::from(packet.packet_id()), WriteOutcome::Skipped));
+            }
+        }
+        let stage = self.stages.pop_front().unwrap_or_default();
+        self.in_flight = stage.iter().map(|packet| PacketKind::from(packet.packet_id())).collect();
+        stage
+    }
+
+    /// The report once every stage has been answered
+    pub(crate) fn finished(&mut self) -> Option<ApplyReport> {
+        (self.in_flight.is_empty() && self.stages.is_empty()).then(|| std::mem::take(&mut self.report))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::net::Ipv4Addr;
+    use crate::packet::config::{BaudRate, BaudRates, PacketTimerPeriod, UserData};
+    use crate::packet::state::RunningTime;
+    use crate::packet::system::IpConfiguration;
+
+    fn ip_configuration() -> Packet {
+        Packet::IpConfiguration(IpConfiguration {
+            permanent: false,
+            dhcp_mode: 0,
+            ip_address: Ipv4Addr::new(192, 168, 42, 42),
+            ip_netmask: Ipv4Addr::new(255, 255, 255, 0),
+            ip_gateway: Ipv4Addr::new(192, 168, 42, 1),
+            dns_server: Ipv4Addr::new(192, 168, 42, 1),
+            boreas_serial_number_part_1: 0,
+            boreas_serial_number_part_2: 0,
+            boreas_serial_number_part_3: 0,
+        })
+    }
+
+    fn sample_set() -> ConfigSet {
+        let baud = BaudRates { permanent: false, primary_port: BaudRate::B115200, gpio_port: BaudRate::B115200, auxiliary_port: BaudRate::B115200 };
+        ConfigSet::try_from(vec![
+            ip_configuration(),
+            Packet::BaudRates(baud),
+            Packet::UserData(UserData { data: [0; 64] }),
+            Packet::PacketTimerPeriod(PacketTimerPeriod::from_rate_hz(100.0).unwrap()),
+        ]).unwrap()
+    }
+
+    #[test]
+    fn test_set_holds_only_configuration() {
+        let kinds: Vec<_> = ConfigSet::kinds().collect();
+        assert!(kinds.contains(&PacketKind::IpConfiguration));
+        assert!(kinds.contains(&PacketKind::FilterOptions));
+        assert!(!kinds.contains(&PacketKind::MagneticCalibrationStatus));
+        assert!(!kinds.contains(&PacketKind::SetZeroOrientationAlignment));
+        assert!(!kinds.contains(&PacketKind::BootMode));
+
+        let mut set = ConfigSet::new();
+        assert!(set.insert(Packet::RunningTime(RunningTime { seconds: 0, microseconds: 0 })).is_err());
+        let user = Packet::UserData(UserData { data: [1; 64] });
+        assert_eq!(set.insert(user.clone()).unwrap(), None);
+        assert_eq!(set.insert(user.clone()).unwrap(), Some(user.clone()));
+        assert!(set.missing().contains(&PacketKind::FilterOptions));
+        assert!(ConfigSet::try_from(vec![user.clone(), user]).is_err());
+    }
+
+    #[test]
+    fn test_link_packets_written_last() {
+        let set = sample_set();
+        let stages: Vec<Vec<u8>> = set.write_stages().iter()
+            .map(|stage| stage.iter().map(|packet| packet.packet_id()).collect())
+            .collect();
+        assert_eq!(stages, vec![vec![180, 198], vec![182], vec![11]]);
+        assert!(set.validate(None).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_permanent_override_and_skip_after_failure() {
+        let mut apply = ConfigApply::new(&sample_set(), &ApplyOptions::new().with_permanent(true));
+
+        let first = apply.next_stage();
+        assert!(matches!(&first[0], Packet::PacketTimerPeriod(timer) if timer.permanent));
+        assert_eq!(first.len(), 2);
+        assert!(apply.next_stage().is_empty());
+        assert!(apply.finished().is_none());
+
+        apply.answered(PacketKind::UserData, WriteOutcome::Acknowledged);
+        apply.answered(PacketKind::PacketTimerPeriod, WriteOutcome::Rejected(AcknowledgeResult::ValuesOutOfRange));
+        assert!(apply.next_stage().is_empty());
+        let report = apply.finished().unwrap();
+        assert!(!report.is_success());
+        assert_eq!(report.failures().count(), 3);
+        assert_eq!(report.outcomes[3], (PacketKind::IpConfiguration, WriteOutcome::Skipped));
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_serializes_as_packet_list() {
+        let set = sample_set();
+        let json = serde_json::to_string(&set).unwrap();
+        let packets: Vec<Packet> = serde_json::from_str(&json).unwrap();
+        assert_eq!(packets.len(), set.len());
+        assert_eq!(serde_json::from_str::<ConfigSet>(&json).unwrap(), set);
+        assert!(serde_json::from_str::<ConfigSet>("[]").unwrap().is_empty());
+    }
+}
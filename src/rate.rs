@@ -0,0 +1,326 @@
+//! Arrival rate and jitter per packet kind.
+//!
+//! [`RateMonitor`] is fed each decoded packet with its receive time and
+//! tracks how often each kind arrives, how regular the intervals are and
+//! the longest gap seen. Rate and jitter are exponentially smoothed so a
+//! device that silently drops from 100 Hz to 10 Hz shows up within a few
+//! packets, and [`RateMonitor::check`] compares them against expected rates.
+
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+use crate::clock::Clock;
+use crate::packet::{HasPacketId, Packet, PacketKind};
+
+/// Default weight of each new interval in the smoothed statistics
+pub const DEFAULT_SMOOTHING: f64 = 0.1;
+
+/// Default fraction an expected rate may fall short by before alerting
+pub const DEFAULT_TOLERANCE: f64 = 0.2;
+
+/// Arrival statistics for one packet kind
+#[derive(Debug, Clone, PartialEq)]
+pub struct RateStats {
+    pub count: u64,
+    /// Smoothed arrival rate in Hz, zero until two packets have arrived
+    pub rate_hz: f64,
+    /// Smoothed interval between packets
+    pub mean_interval: Duration,
+    /// Smoothed standard deviation of the interval
+    pub jitter: Duration,
+    /// Longest interval seen
+    pub max_gap: Duration,
+    pub first_seen: Instant,
+    pub last_seen: Instant,
+}
+
+/// A packet kind arriving slower than expected
+#[derive(Debug, Clone, PartialEq)]
+pub struct RateAlert {
+    pub kind: PacketKind,
+    pub expected_hz: f64,
+    /// Current rate, counting the silence since the last packet
+    pub actual_hz: f64,
+}
+
+#[derive(Debug, Clone)]
+struct Tracker {
+    count: u64,
+    mean: f64,
+    variance: f64,
+    max_gap: Duration,
+    first_seen: Instant,
+    last_seen: Instant,
+}
+
+impl Tracker {
+    fn stats(&self) -> RateStats {
+        RateStats {
+            count: self.count,
+            rate_hz: if self.mean > 0.0 { 1.0 / self.mean } else { 0.0 },
+            mean_interval: Duration::from_secs_f64(self.mean),
+            jitter: Duration::from_secs_f64(self.variance.sqrt()),
+            max_gap: self.max_gap,
+            first_seen: self.first_seen,
+            last_seen: self.last_seen,
+        }
+    }
+}
+
+/// Per-kind arrival statistics from caller-supplied timestamps
+///
+/// # Examples
+///
+/// ```
+/// use std::time::{Duration, Instant};
+/// use liban::rate::RateMonitor;
+/// use liban::PacketKind;
+///
+/// let mut monitor = RateMonitor::new().with_expected(PacketKind::SystemState, 100.0);
+/// let start = Instant::now();
+/// for i in 0..50 {
+///     monitor.record(PacketKind::SystemState, start + Duration::from_millis(10 * i));
+/// }
+/// let now = start + Duration::from_millis(495);
+/// assert!((monitor.stats(PacketKind::SystemState).unwrap().rate_hz - 100.0).abs() < 1.0);
+/// assert!(monitor.check(now).is_empty());
+/// ```
+#[derive(Debug, Clone)]
+pub struct RateMonitor {
+    trackers: HashMap<PacketKind, Tracker>,
+    expected: HashMap<PacketKind, f64>,
+    smoothing: f64,
+    tolerance: f64,
+}
+
+impl Default for RateMonitor {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl RateMonitor {
+    pub fn new() -> Self {
+        Self {
+            trackers: HashMap::new(),
+            expected: HashMap::new(),
+            smoothing: DEFAULT_SMOOTHING,
+            tolerance: DEFAULT_TOLERANCE,
+        }
+    }
+
+    /// Weight of each new interval, in (0, 1]; higher reacts faster.
+    /// NaN falls back to [`DEFAULT_SMOOTHING`].
+    pub fn with_smoothing(mut self, smoothing: f64) -> Self {
+        let smoothing = if smoothing.is_nan() { DEFAULT_SMOOTHING } else { smoothing };
+        self.smoothing = smoothing.clamp(f64::MIN_POSITIVE, 1.0);
+        self
+    }
+
+    /// Fraction an expected rate may fall short by before [`check`](Self::check) alerts.
+    /// NaN falls back to [`DEFAULT_TOLERANCE`].
+    pub fn with_tolerance(mut self, tolerance: f64) -> Self {
+        let tolerance = if tolerance.is_nan() { DEFAULT_TOLERANCE } else { tolerance };
+        self.tolerance = tolerance.clamp(0.0, 1.0);
+        self
+    }
+
+    /// Rate a packet kind should arrive at
+    pub fn with_expected(mut self, kind: PacketKind, rate_hz: f64) -> Self {
+        self.expected.insert(kind, rate_hz);
+        self
+    }
+
+    /// Rate a packet type should arrive at
+    pub fn with_expected_for<T: HasPacketId>(self, rate_hz: f64) -> Self {
+        self.with_expected(T::PACKET_ID.packet_type(), rate_hz)
+    }
+
+    /// Record a decoded packet received at `now`
+    pub fn observe(&mut self, packet: &Packet, clock: impl Clock) {
+        let now = clock.now();
+        self.record(PacketKind::from(packet.packet_id()), now);
+    }
+
+    /// Record an arrival of `kind` at `now`
+    pub fn record(&mut self, kind: PacketKind, clock: impl Clock) {
+        let now = clock.now();
+        let smoothing = self.smoothing;
+        let tracker = self.trackers.entry(kind).or_insert(Tracker {
+            count: 0,
+            mean: 0.0,
+            variance: 0.0,
+            max_gap: Duration::ZERO,
+            first_seen: now,
+            last_seen: now,
+        });
+        tracker.count += 1;
+        if tracker.count == 1 {
+            return;
+        }
+
+        let gap = now.saturating_duration_since(tracker.last_seen);
+        tracker.last_seen = tracker.last_seen.max(now);
+        tracker.max_gap = tracker.max_gap.max(gap);
+
+        let interval = gap.as_secs_f64();
+        if tracker.count == 2 {
+            tracker.mean = interval;
+        } else {
+            // Exponentially weighted mean and variance
+            let delta = interval - tracker.mean;
+            tracker.mean += smoothing * delta;
+            tracker.variance = (1.0 - smoothing) * (tracker.variance + smoothing * delta * delta);
+        }
+    }
+
+    /// Statistics for a packet kind, `None` if it has never arrived
+    pub fn stats(&self, kind: PacketKind) -> Option<RateStats> {
+        self.trackers.get(&kind).map(Tracker::stats)
+    }
+
+    /// Statistics for a packet type, `None` if it has never arrived
+    pub fn stats_for<T: HasPacketId>(&self) -> Option<RateStats> {
+        self.stats(T::PACKET_ID.packet_type())
+    }
+
+    /// Statistics for every kind seen, ordered by packet ID
+    pub fn all(&self) -> Vec<(PacketKind, RateStats)> {
+        let mut all: Vec<_> = self.trackers.iter().map(|(&k, t)| (k, t.stats())).collect();
+        all.sort_by_key(|(k, _)| k.packet_id());
+        all
+    }
+
+    /// Expected kinds arriving slower than their rate allows. Silence since
+    /// the last packet counts, so a stream that stops entirely alerts too.
+    pub fn check(&self, clock: impl Clock) -> Vec<RateAlert> {
+        let now = clock.now();
+        let mut alerts: Vec<_> = self.expected.iter()
+            .filter_map(|(&kind, &expected_hz)| {
+                let actual_hz = self.current_rate(kind, now);
+                (actual_hz < expected_hz * (1.0 - self.tolerance))
+                    .then_some(RateAlert { kind, expected_hz, actual_hz })
+            })
+            .collect();
+        alerts.sort_by_key(|a| a.kind.packet_id());
+        alerts
+    }
+
+    /// Forget all statistics, keeping the expected rates
+    pub fn reset(&mut self) {
+        self.trackers.clear();
+    }
+
+    fn current_rate(&self, kind: PacketKind, now: Instant) -> f64 {
+        let Some(tracker) = self.trackers.get(&kind) else {
+            return 0.0;
+        };
+        let rate = tracker.stats().rate_hz;
+        let silence = now.saturating_duration_since(tracker.last_seen).as_secs_f64();
+        if silence > 0.0 && silence > tracker.mean {
+            rate.min(1.0 / silence)
+        } else {
+            rate
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clock::ManualClock;
+
+    fn feed(monitor: &mut RateMonitor, start: Instant, period_ms: u64, n: u64) -> Instant {
+        for i in 0..n {
+            monitor.record(PacketKind::SystemState, start + Duration::from_millis(period_ms * i));
+        }
+        start + Duration::from_millis(period_ms * (n - 1))
+    }
+
+    #[test]
+    fn test_manual_clock() {
+        let clock = ManualClock::new();
+        let mut monitor = RateMonitor::new().with_expected(PacketKind::SystemState, 10.0);
+        for _ in 0..5 {
+            monitor.record(PacketKind::SystemState, &clock);
+            clock.advance(Duration::from_millis(100));
+        }
+        assert!(monitor.check(&clock).is_empty());
+        clock.advance(Duration::from_secs(1));
+        assert_eq!(monitor.check(&clock).len(), 1);
+    }
+
+    #[test]
+    fn test_steady_rate() {
+        let mut monitor = RateMonitor::new();
+        let start = Instant::now();
+        let last = feed(&mut monitor, start, 20, 100);
+
+        let stats = monitor.stats(PacketKind::SystemState).unwrap();
+        assert_eq!(stats.count, 100);
+        assert!((stats.rate_hz - 50.0).abs() < 1e-6);
+        assert_eq!(stats.jitter, Duration::ZERO);
+        assert_eq!(stats.max_gap, Duration::from_millis(20));
+        assert_eq!(stats.last_seen, last);
+        assert!(monitor.stats(PacketKind::Status).is_none());
+    }
+
+    #[test]
+    fn test_detects_rate_drop() {
+        let mut monitor = RateMonitor::new().with_expected(PacketKind::SystemState, 100.0);
+        let start = Instant::now();
+        let last = feed(&mut monitor, start, 10, 100);
+        assert!(monitor.check(last).is_empty());
+
+        // Device falls back to 10 Hz
+        let last = feed(&mut monitor, last + Duration::from_millis(100), 100, 30);
+        let alerts = monitor.check(last);
+        assert_eq!(alerts.len(), 1);
+        assert_eq!(alerts[0].kind, PacketKind::SystemState);
+        assert!(alerts[0].actual_hz < 20.0);
+        assert!(monitor.stats(PacketKind::SystemState).unwrap().max_gap >= Duration::from_millis(100));
+    }
+
+    #[test]
+    fn test_silence_alerts() {
+        let mut monitor = RateMonitor::new()
+            .with_expected(PacketKind::SystemState, 100.0)
+            .with_expected(PacketKind::Status, 1.0);
+        let start = Instant::now();
+        let last = feed(&mut monitor, start, 10, 50);
+
+        let alerts = monitor.check(last + Duration::from_secs(1));
+        let kinds: Vec<_> = alerts.iter().map(|a| a.kind).collect();
+        assert_eq!(kinds, vec![PacketKind::SystemState, PacketKind::Status]);
+        assert!((alerts[0].actual_hz - 1.0).abs() < 0.1);
+        assert_eq!(alerts[1].actual_hz, 0.0);
+    }
+
+    #[test]
+    fn test_jitter() {
+        let mut monitor = RateMonitor::new();
+        let start = Instant::now();
+        let mut t = start;
+        for i in 0..200 {
+            monitor.record(PacketKind::SystemState, t);
+            t += Duration::from_millis(if i % 2 == 0 { 8 } else { 12 });
+        }
+        let stats = monitor.stats(PacketKind::SystemState).unwrap();
+        assert!((stats.jitter.as_secs_f64() - 0.002).abs() < 0.0005);
+        assert!((stats.rate_hz - 100.0).abs() < 5.0);
+    }
+
+    #[test]
+    fn test_nan_settings_use_defaults() {
+        let mut monitor = RateMonitor::new().with_smoothing(f64::NAN).with_tolerance(f64::NAN);
+        assert_eq!(monitor.smoothing, DEFAULT_SMOOTHING);
+        assert_eq!(monitor.tolerance, DEFAULT_TOLERANCE);
+        assert_eq!(RateMonitor::new().with_smoothing(f64::INFINITY).smoothing, 1.0);
+        assert_eq!(RateMonitor::new().with_smoothing(f64::NEG_INFINITY).smoothing, f64::MIN_POSITIVE);
+
+        let start = Instant::now();
+        feed(&mut monitor, start, 10, 20);
+        let stats = monitor.stats(PacketKind::SystemState).unwrap();
+        assert!((stats.rate_hz - 100.0).abs() < 1.0);
+    }
+}
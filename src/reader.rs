@@ -52,6 +52,7 @@ impl<R: Read> Iterator for AnppReader<R> {
     fn next(&mut self) -> Option<Self::Item> {
         let mut buffer = [0u8; BUFFER_SIZE];
         loop {
+            #[cfg(feature = "tracing")]
             tracing::debug!("Trying to read from reader");
             let (bytes_read, is_eof) = {
                 if self.drain_internal {
@@ -59,6 +60,7 @@ impl<R: Read> Iterator for AnppReader<R> {
                 } else {
                     match self.reader.read(&mut buffer) {
                         Ok(br) => {
+                            #[cfg(feature = "tracing")]
                             tracing::debug!("Successfully read {br} bytes from reader");
                             (br, br == 0)
                         }
@@ -220,7 +222,7 @@ mod tests {
         use std::io::Cursor as WriteCursor;
 
         // Create some known ANPP packets
-        let request_packet = Request { requested_packet: crate::packet::PacketKind::SystemState };
+        let request_packet = Request::single(crate::packet::PacketKind::SystemState);
 
         // Serialize the packet payload
         let mut cursor = WriteCursor::new(Vec::new());
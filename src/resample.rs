@@ -0,0 +1,322 @@
+//! Rate reduction for logging.
+//!
+//! [`Decimator`] keeps every Nth sample of a stream. [`Resampler`] puts a
+//! stream on a fixed time grid instead, taking either the nearest sample to
+//! each grid point or interpolating between the samples either side of it.
+//! Interpolation is linear for continuous fields (angles along the shorter
+//! arc) and takes the latest sample at or before the grid point for status
+//! flags. Time is whatever the caller passes in, so the same code serves
+//! live streams and log replay.
+
+use std::time::{Duration, Instant};
+
+use crate::angle::{normalize_2pi, normalize_pi, shortest_angular_distance};
+use crate::packet::{HasPacketId, Packet};
+use crate::packet::state::{RawSensors, SystemState};
+use crate::units::Radians;
+
+/// How a grid point between two samples is filled
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Method {
+    /// The closer sample, the earlier one on a tie
+    Nearest,
+    /// Continuous fields interpolated, flags from the earlier sample
+    #[default]
+    Linear,
+}
+
+/// Packets a [`Resampler`] can interpolate
+pub trait Resample: HasPacketId + Clone {
+    /// Value a fraction `t` (0 to 1) of the way from `self` to `next`
+    fn interpolate(&self, next: &Self, t: f64) -> Self;
+}
+
+fn lerp(a: f32, b: f32, t: f64) -> f32 {
+    (a as f64 + (b as f64 - a as f64) * t) as f32
+}
+
+fn lerp_f64(a: f64, b: f64, t: f64) -> f64 {
+    a + (b - a) * t
+}
+
+/// Interpolate along the shorter arc, in the range (-π, π]
+fn lerp_angle(a: f64, b: f64, t: f64) -> f64 {
+    normalize_pi(a + shortest_angular_distance(a, b) * t)
+}
+
+fn lerp_radians(a: Radians, b: Radians, t: f64) -> Radians {
+    Radians(lerp_angle(a.get() as f64, b.get() as f64, t) as f32)
+}
+
+impl Resample for RawSensors {
+    fn interpolate(&self, next: &Self, t: f64) -> Self {
+        Self {
+            accelerometer_x: lerp(self.accelerometer_x, next.accelerometer_x, t),
+            accelerometer_y: lerp(self.accelerometer_y, next.accelerometer_y, t),
+            accelerometer_z: lerp(self.accelerometer_z, next.accelerometer_z, t),
+            gyroscope_x: lerp(self.gyroscope_x, next.gyroscope_x, t),
+            gyroscope_y: lerp(self.gyroscope_y, next.gyroscope_y, t),
+            gyroscope_z: lerp(self.gyroscope_z, next.gyroscope_z, t),
+            imu_temperature: lerp(self.imu_temperature, next.imu_temperature, t),
+            pressure: lerp(self.pressure, next.pressure, t),
+            pressure_temperature: lerp(self.pressure_temperature, next.pressure_temperature, t),
+        }
+    }
+}
+
+impl Resample for SystemState {
+    fn interpolate(&self, next: &Self, t: f64) -> Self {
+        let micros = |s: &SystemState| s.unix_time_seconds as i64 * 1_000_000 + s.microseconds as i64;
+        let time = micros(self) + ((micros(next) - micros(self)) as f64 * t).round() as i64;
+        Self {
+            system_status: self.system_status,
+            filter_status: self.filter_status,
+            unix_time_seconds: time.div_euclid(1_000_000) as u32,
+            microseconds: time.rem_euclid(1_000_000) as u32,
+            latitude: lerp_f64(self.latitude, next.latitude, t),
+            longitude: lerp_angle(self.longitude, next.longitude, t),
+            height: lerp_f64(self.height, next.height, t),
+            velocity_north: lerp(self.velocity_north, next.velocity_north, t),
+            velocity_east: lerp(self.velocity_east, next.velocity_east, t),
+            velocity_down: lerp(self.velocity_down, next.velocity_down, t),
+            body_acceleration_x: lerp(self.body_acceleration_x, next.body_acceleration_x, t),
+            body_acceleration_y: lerp(self.body_acceleration_y, next.body_acceleration_y, t),
+            body_acceleration_z: lerp(self.body_acceleration_z, next.body_acceleration_z, t),
+            g_force: lerp(self.g_force, next.g_force, t),
+            roll: lerp_radians(self.roll, next.roll, t),
+            pitch: lerp_radians(self.pitch, next.pitch, t),
+            heading: normalize_2pi(lerp_radians(self.heading, next.heading, t)),
+            angular_velocity_x: lerp(self.angular_velocity_x, next.angular_velocity_x, t),
+            angular_velocity_y: lerp(self.angular_velocity_y, next.angular_velocity_y, t),
+            angular_velocity_z: lerp(self.angular_velocity_z, next.angular_velocity_z, t),
+            latitude_std_dev: lerp(self.latitude_std_dev, next.latitude_std_dev, t),
+            longitude_std_dev: lerp(self.longitude_std_dev, next.longitude_std_dev, t),
+            height_std_dev: lerp(self.height_std_dev, next.height_std_dev, t),
+            extension: self.extension.clone(),
+        }
+    }
+}
+
+/// Keeps the first of every `factor` samples
+#[derive(Debug, Clone)]
+pub struct Decimator {
+    factor: u32,
+    count: u32,
+}
+
+impl Decimator {
+    /// A factor of 0 is treated as 1, passing every sample
+    pub fn new(factor: u32) -> Self {
+        Self { factor: factor.max(1), count: 0 }
+    }
+
+    pub fn push<T>(&mut self, sample: T) -> Option<T> {
+        let keep = self.count == 0;
+        self.count = (self.count + 1) % self.factor;
+        keep.then_some(sample)
+    }
+
+    /// Keep the next sample pushed
+    pub fn reset(&mut self) {
+        self.count = 0;
+    }
+}
+
+/// Puts a stream of one packet type on a fixed time grid
+///
+/// The grid starts at the first sample and steps by the period. Each
+/// sample pushed fills the grid points since the previous sample.
+///
+/// # Examples
+///
+/// ```
+/// use std::time::Duration;
+/// use liban::resample::{Method, Resampler};
+/// use liban::RawSensors;
+///
+/// let mut resampler = Resampler::<RawSensors>::new(Duration::from_millis(100), Method::Linear)
+///     .with_max_gap(Duration::from_secs(1));
+/// // for each packet: for (at, sensors) in resampler.handle(&packet, Instant::now()) { ... }
+/// assert_eq!(resampler.period(), Duration::from_millis(100));
+/// ```
+#[derive(Debug, Clone)]
+pub struct Resampler<T> {
+    period: Duration,
+    method: Method,
+    max_gap: Option<Duration>,
+    previous: Option<(Instant, T)>,
+    next_tick: Option<Instant>,
+}
+
+impl<T: Resample> Resampler<T> {
+    /// A zero period is raised to one microsecond
+    pub fn new(period: Duration, method: Method) -> Self {
+        Self { period: period.max(Duration::from_micros(1)), method, max_gap: None, previous: None, next_tick: None }
+    }
+
+    /// Leave grid points empty where consecutive samples are further apart
+    /// than this, rather than filling a dropout
+    pub fn with_max_gap(mut self, max_gap: Duration) -> Self {
+        self.max_gap = Some(max_gap);
+        self
+    }
+
+    pub fn period(&self) -> Duration {
+        self.period
+    }
+
+    /// Feed any packet; packets of other types are ignored
+    pub fn handle(&mut self, packet: &Packet, at: Instant) -> Vec<(Instant, T)> {
+        match T::from_packet(packet) {
+            Some(sample) => self.push(sample.clone(), at),
+            None => Vec::new(),
+        }
+    }
+
+    /// Add a sample taken at `at` and return the grid points it completes.
+    /// Samples older than the previous one are dropped.
+    pub fn push(&mut self, sample: T, at: Instant) -> Vec<(Instant, T)> {
+        let mut output = Vec::new();
+        let Some((previous_at, previous)) = self.previous.take() else {
+            output.push((at, sample.clone()));
+            self.next_tick = Some(at + self.period);
+            self.previous = Some((at, sample));
+            return output;
+        };
+        if at < previous_at {
+            self.previous = Some((previous_at, previous));
+            return output;
+        }
+
+        let span = at - previous_at;
+        let fill = self.max_gap.is_none_or(|max| span <= max);
+        let mut tick = self.next_tick.unwrap_or(at);
+        while tick <= at {
+            if fill {
+                let t = if span.is_zero() { 1.0 } else { (tick - previous_at).as_secs_f64() / span.as_secs_f64() };
+                let value = match self.method {
+                    Method::Nearest if t <= 0.5 => previous.clone(),
+                    Method::Nearest => sample.clone(),
+                    Method::Linear if t < 1.0 => previous.interpolate(&sample, t),
+                    Method::Linear => sample.clone(),
+                };
+                output.push((tick, value));
+            }
+            tick += self.period;
+        }
+        self.next_tick = Some(tick);
+        self.previous = Some((at, sample));
+        output
+    }
+
+    /// Forget the stream; the next sample starts a new grid
+    pub fn reset(&mut self) {
+        self.previous = None;
+        self.next_tick = None;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::f64::consts::PI;
+    use crate::packet::state::FilterStatus;
+    use crate::test_support::system_state;
+
+    fn sensors(value: f32) -> RawSensors {
+        RawSensors {
+            accelerometer_x: value,
+            accelerometer_y: 0.0,
+            accelerometer_z: -9.8,
+            gyroscope_x: 0.0,
+            gyroscope_y: 0.0,
+            gyroscope_z: value / 10.0,
+            imu_temperature: 25.0,
+            pressure: 101_325.0,
+            pressure_temperature: 25.0,
+        }
+    }
+
+    fn state(heading: f32, filter_status: u16, seconds: u32) -> SystemState {
+        SystemState {
+            filter_status: FilterStatus::from(filter_status),
+            unix_time_seconds: seconds,
+            latitude: 0.5,
+            longitude: PI - 0.01,
+            height: 10.0,
+            velocity_north: 1.0,
+            heading: Radians(heading),
+            latitude_std_dev: 1.0,
+            longitude_std_dev: 1.0,
+            height_std_dev: 1.0,
+            ..system_state()
+        }
+    }
+
+    #[test]
+    fn test_decimator() {
+        let mut decimator = Decimator::new(3);
+        let kept: Vec<_> = (0..7).filter_map(|i| decimator.push(i)).collect();
+        assert_eq!(kept, [0, 3, 6]);
+        decimator.reset();
+        assert_eq!(decimator.push(7), Some(7));
+        assert_eq!(Decimator::new(0).push(1), Some(1));
+    }
+
+    #[test]
+    fn test_linear_grid() {
+        let start = Instant::now();
+        let ms = |n| start + Duration::from_millis(n);
+        let mut resampler = Resampler::new(Duration::from_millis(100), Method::Linear);
+
+        assert_eq!(resampler.push(sensors(0.0), ms(0)), [(ms(0), sensors(0.0))]);
+        assert!(resampler.push(sensors(1.0), ms(40)).is_empty());
+        let out = resampler.push(sensors(4.0), ms(240));
+        assert_eq!(out.iter().map(|(at, _)| *at).collect::<Vec<_>>(), [ms(100), ms(200)]);
+        assert!((out[0].1.accelerometer_x - 1.9).abs() < 1e-6);
+        assert!((out[1].1.accelerometer_x - 3.4).abs() < 1e-6);
+        assert_eq!(out[1].1.accelerometer_z, -9.8);
+    }
+
+    #[test]
+    fn test_nearest_and_gaps() {
+        let start = Instant::now();
+        let ms = |n| start + Duration::from_millis(n);
+        let mut resampler = Resampler::new(Duration::from_millis(100), Method::Nearest)
+            .with_max_gap(Duration::from_millis(150));
+
+        resampler.push(sensors(0.0), ms(0));
+        assert_eq!(resampler.push(sensors(1.0), ms(80)), []);
+        assert_eq!(resampler.push(sensors(2.0), ms(130)), [(ms(100), sensors(1.0))]);
+        // Dropout: grid points 200 to 400 are skipped, the grid keeps its phase
+        assert!(resampler.push(sensors(3.0), ms(450)).is_empty());
+        assert_eq!(resampler.push(sensors(4.0), ms(520)), [(ms(500), sensors(4.0))]);
+        // Out of order samples are dropped
+        assert!(resampler.push(sensors(5.0), ms(510)).is_empty());
+
+        resampler.reset();
+        assert_eq!(resampler.push(sensors(6.0), ms(530)), [(ms(530), sensors(6.0))]);
+    }
+
+    #[test]
+    fn test_system_state_interpolation() {
+        let start = Instant::now();
+        let mut resampler = Resampler::<SystemState>::new(Duration::from_millis(500), Method::Linear);
+        assert_eq!(resampler.handle(&Packet::SystemState(state(6.2, 0x0001, 10)), start).len(), 1);
+        let mut next = state(0.2, 0x0003, 11);
+        next.longitude = -PI + 0.01;
+        let out = resampler.handle(&Packet::SystemState(next), start + Duration::from_secs(1));
+        assert_eq!(out.len(), 2);
+
+        let (_, mid) = &out[0];
+        // Heading and longitude cross their wrap points the short way
+        let expected = ((6.2 + 0.2 + std::f32::consts::TAU) / 2.0).rem_euclid(std::f32::consts::TAU);
+        assert!((mid.heading.get() - expected).abs() < 1e-5, "{:?}", mid.heading);
+        assert!((mid.longitude.abs() - PI).abs() < 1e-9);
+        assert_eq!((mid.unix_time_seconds, mid.microseconds), (10, 500_000));
+        // Flags come from the earlier sample
+        assert_eq!(mid.filter_status, FilterStatus::from(0x0001));
+        assert_eq!(out[1].1.filter_status, FilterStatus::from(0x0003));
+        assert!((out[1].1.heading.get() - 0.2).abs() < 1e-5);
+    }
+}
@@ -0,0 +1,209 @@
+//! Packet output rate planning.
+//!
+//! The device streams each packet at a whole multiple of the packet timer
+//! period, and the timer period itself must be a whole number of
+//! microseconds between 1000 and 65000. [`plan`] searches every valid
+//! timer period for the one whose divisors come closest to the requested
+//! rates and builds the Packet Timer Period and Packets Period packets for
+//! it.
+//!
+//! ```
+//! use liban::schedule::plan;
+//! use liban::PacketKind;
+//!
+//! let schedule = plan(&[(PacketKind::SystemState, 50.0), (PacketKind::Satellites, 0.5)]).unwrap();
+//! assert!(schedule.max_error() < 1e-9);
+//! let (timer, periods) = schedule.into_packets();
+//! # let _ = (timer, periods);
+//! ```
+
+use std::time::Duration;
+
+use crate::error::AnError;
+use crate::packet::PacketKind;
+use crate::packet::config::{PacketPeriod, PacketTimerPeriod, PacketsPeriod};
+
+/// Requested and achieved rate for one packet kind
+#[derive(Debug, Clone, PartialEq)]
+pub struct PlannedRate {
+    pub kind: PacketKind,
+    pub requested_hz: f64,
+    pub achieved_hz: f64,
+    /// Multiple of the timer period between packets
+    pub divisor: u32,
+}
+
+impl PlannedRate {
+    /// Relative rate error, positive when faster than requested
+    pub fn error(&self) -> f64 {
+        (self.achieved_hz - self.requested_hz) / self.requested_hz
+    }
+}
+
+/// Timer period and divisors chosen by [`plan`]
+#[derive(Debug, Clone, PartialEq)]
+pub struct Schedule {
+    pub timer: PacketTimerPeriod,
+    pub rates: Vec<PlannedRate>,
+}
+
+impl Schedule {
+    /// Largest relative rate error of any packet
+    pub fn max_error(&self) -> f64 {
+        self.rates.iter().map(|r| r.error().abs()).fold(0.0, f64::max)
+    }
+
+    /// Periods table that replaces any existing one
+    pub fn packets_period(&self) -> PacketsPeriod {
+        PacketsPeriod {
+            permanent: self.timer.permanent,
+            clear_existing: true,
+            packet_periods: self.rates.iter()
+                .map(|rate| PacketPeriod { packet_type: rate.kind, divisor: rate.divisor })
+                .collect(),
+        }
+    }
+
+    /// The two packets to write, timer first
+    pub fn into_packets(self) -> (PacketTimerPeriod, PacketsPeriod) {
+        let periods = self.packets_period();
+        (self.timer, periods)
+    }
+}
+
+/// Divisor closest to the requested rate in period terms
+fn divisor(rate_hz: f64, tick_micros: u32) -> u32 {
+    let ideal = 1e6 / (rate_hz * tick_micros as f64);
+    // Rounding the period can land on the worse neighbour in rate terms
+    let low = ideal.floor().clamp(1.0, u32::MAX as f64) as u32;
+    let high = low.saturating_add(1);
+    let error = |d: u32| (1e6 / (d as f64 * tick_micros as f64) - rate_hz).abs();
+    if error(high) < error(low) { high } else { low }
+}
+
+/// Find the timer period and per-packet divisors that best meet the
+/// requested rates.
+///
+/// Candidates are ranked by their largest relative rate error, then by the
+/// sum of relative errors; ties go to the shortest timer period. Fails for
+/// an empty or duplicated request, or a rate that is not positive or above
+/// the 1000 Hz the shortest timer period allows.
+pub fn plan(requirements: &[(PacketKind, f64)]) -> Result<Schedule, AnError> {
+    if requirements.is_empty() {
+        return Err(AnError::ValidationFailed("no packet rates requested".to_string()));
+    }
+    let max_hz = 1.0 / PacketTimerPeriod::MIN_PERIOD.as_secs_f64();
+    for (i, (kind, hz)) in requirements.iter().enumerate() {
+        if *kind == PacketKind::Unsupported {
+            return Err(AnError::ValidationFailed("cannot schedule an unsupported packet".to_string()));
+        }
+        if !(hz.is_finite() && *hz > 0.0 && *hz <= max_hz) {
+            return Err(AnError::ValidationFailed(format!(
+                "{:?} rate {} Hz outside 0-{} Hz", kind, hz, max_hz)));
+        }
+        if requirements[..i].iter().any(|(other, _)| other == kind) {
+            return Err(AnError::ValidationFailed(format!("{:?} requested more than once", kind)));
+        }
+    }
+
+    let min = PacketTimerPeriod::MIN_PERIOD.as_micros() as u32;
+    let max = PacketTimerPeriod::MAX_PERIOD.as_micros() as u32;
+    let mut best: Option<(f64, f64, u32)> = None;
+    for tick in min..=max {
+        let (mut worst, mut total) = (0.0f64, 0.0);
+        for (_, hz) in requirements {
+            let achieved = 1e6 / (divisor(*hz, tick) as f64 * tick as f64);
+            let error = ((achieved - hz) / hz).abs();
+            worst = worst.max(error);
+            total += error;
+        }
+        if best.is_none_or(|(w, t, _)| (worst, total) < (w, t)) {
+            best = Some((worst, total, tick));
+        }
+    }
+    let (_, _, tick) = best.expect("timer period range is not empty");
+
+    let timer = PacketTimerPeriod::new(Duration::from_micros(tick as u64))?;
+    let rates = requirements.iter()
+        .map(|(kind, hz)| {
+            let divisor = divisor(*hz, tick);
+            PlannedRate {
+                kind: *kind,
+                requested_hz: *hz,
+                achieved_hz: 1e6 / (divisor as f64 * tick as f64),
+                divisor,
+            }
+        })
+        .collect();
+    Ok(Schedule { timer, rates })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_exact_rates_use_shortest_timer() {
+        let schedule = plan(&[(PacketKind::SystemState, 100.0), (PacketKind::Status, 1.0)]).unwrap();
+        assert_eq!(schedule.timer.packet_timer_period, Duration::from_millis(1));
+        assert_eq!(schedule.max_error(), 0.0);
+        assert_eq!(schedule.rates.iter().map(|r| r.divisor).collect::<Vec<_>>(), vec![10, 1000]);
+
+        let (timer, periods) = schedule.into_packets();
+        assert!(timer.validate().is_ok());
+        assert!(periods.clear_existing);
+        assert_eq!(periods.packet_periods[0].divisor, 10);
+        // The bandwidth estimate reads the table the same way
+        let expected = (5.0 + 100.0) * 100.0 + (5.0 + 4.0) * 1.0;
+        assert!((periods.estimated_bandwidth(&timer).bytes_per_second - expected).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_awkward_rates() {
+        // 3 Hz and 7 Hz share no exact millisecond tick, but a 47619 µs
+        // tick gets 7 Hz with divisor 3 and 3 Hz with divisor 7
+        let schedule = plan(&[(PacketKind::SystemState, 7.0), (PacketKind::EulerOrientation, 3.0)]).unwrap();
+        assert!(schedule.max_error() < 1e-5, "{:?}", schedule);
+        assert_eq!(schedule.rates[0].divisor * 7, schedule.rates[1].divisor * 3);
+
+        // Faster than the tick allows for a slow stream in the same table
+        let schedule = plan(&[(PacketKind::RawSensors, 1000.0), (PacketKind::Satellites, 0.01)]).unwrap();
+        assert_eq!(schedule.timer.packet_timer_period, Duration::from_millis(1));
+        assert_eq!(schedule.rates[1].divisor, 100_000);
+        assert!(schedule.rates.iter().all(|r| r.error() == 0.0));
+    }
+
+    #[test]
+    fn test_device_streams_at_planned_rates() {
+        use crate::packet::Packet;
+        use crate::sim::{MockBoreas, Trajectory};
+        use crate::units::{Angle, Length};
+
+        let schedule = plan(&[(PacketKind::SystemState, 7.0), (PacketKind::Status, 3.0)]).unwrap();
+        assert_ne!(schedule.timer.packet_timer_period, Duration::from_millis(1));
+        let (timer, periods) = schedule.into_packets();
+
+        let mut device = MockBoreas::new(Trajectory::Static {
+            latitude: Angle::from_degrees(0.0),
+            longitude: Angle::from_degrees(0.0),
+            height: Length::from_meters(0.0),
+        });
+        device.handle(&Packet::PacketTimerPeriod(timer));
+        device.handle(&Packet::PacketsPeriod(periods));
+
+        let packets = device.tick(Duration::from_secs(3));
+        let count = |kind: PacketKind| packets.iter().filter(|p| PacketKind::from(p.packet_id()) == kind).count();
+        assert_eq!(count(PacketKind::SystemState), 21);
+        assert_eq!(count(PacketKind::Status), 9);
+    }
+
+    #[test]
+    fn test_rejects_invalid_requests() {
+        assert!(plan(&[]).is_err());
+        assert!(plan(&[(PacketKind::SystemState, 0.0)]).is_err());
+        assert!(plan(&[(PacketKind::SystemState, 2000.0)]).is_err());
+        assert!(plan(&[(PacketKind::SystemState, f64::NAN)]).is_err());
+        assert!(plan(&[(PacketKind::Status, 1.0), (PacketKind::Status, 2.0)]).is_err());
+        assert!(plan(&[(PacketKind::Unsupported, 1.0)]).is_err());
+    }
+}
@@ -0,0 +1,632 @@
+//! Simulated device for testing without hardware.
+//!
+//! [`MockBoreas`] is a sans-io responder: feed it the bytes a host would send
+//! to a device and it returns the bytes the device would answer with. It
+//! keeps a configuration store, acknowledges writes the way firmware does
+//! and streams synthetic state packets along a [`Trajectory`] as the caller
+//! advances time. Trajectories can be analytic or recorded tracks loaded
+//! from CSV or GPX, and a [`NoiseModel`] adds measurement noise.
+//!
+//! ```
+//! use std::time::Duration;
+//! use liban::sim::{MockBoreas, Trajectory};
+//! use liban::{AnppParser, Packet, PacketPeriod, PacketsPeriod, SystemState};
+//!
+//! let mut device = MockBoreas::new(Trajectory::Static {
+//!     latitude: liban::units::Angle::from_degrees(-33.87),
+//!     longitude: liban::units::Angle::from_degrees(151.21),
+//!     height: liban::units::Length::from_meters(10.0),
+//! });
+//! let subscribe = Packet::PacketsPeriod(PacketsPeriod {
+//!     permanent: false,
+//!     clear_existing: true,
+//!     packet_periods: vec![PacketPeriod::from_packet::<SystemState>(100)],
+//! });
+//!
+//! let mut parser = AnppParser::new();
+//! assert!(matches!(parser.consume(&device.feed(&subscribe.to_bytes().unwrap())), Some(Packet::Acknowledge(_))));
+//!
+//! let stream = device.advance(Duration::from_secs(1));
+//! let mut count = 0;
+//! let mut next = parser.consume(&stream);
+//! while let Some(Packet::SystemState(_)) = next {
+//!     count += 1;
+//!     next = parser.consume(&[]);
+//! }
+//! assert_eq!(count, 10);
+//! ```
+
+pub mod noise;
+pub mod trajectory;
+
+use std::collections::HashMap;
+use std::time::Duration;
+
+use crate::angle::{normalize_2pi, shortest_angular_distance};
+use crate::packet::config::{BaudRate, BaudRates, FilterOptions, PacketTimerPeriod, VehicleType};
+use crate::packet::state::{
+    FilterStatus, GnssFixType, RawSensors, Satellites, Status, SystemState, SystemStatus, UnixTime,
+};
+use crate::packet::system::{Acknowledge, AcknowledgeResult, DeviceInformation, DeviceType};
+use crate::packet::{Packet, PacketKind};
+use crate::parser::AnppParser;
+use crate::protocol::AnppProtocol;
+use crate::units::Radians;
+use crate::wire::{self, PacketId};
+
+use noise::Gaussian;
+
+pub use noise::NoiseModel;
+pub use trajectory::{Track, TrackPoint, Trajectory, TrajectoryPoint};
+
+/// Unix time the simulated clock starts at
+pub const DEFAULT_START_TIME: u32 = 1_700_000_000;
+
+/// Filter status reported while streaming: orientation, navigation and
+/// heading initialised, UTC time valid, 3D GNSS fix (bits 4-6), internal
+/// GNSS enabled
+const FILTER_STATUS: u16 = 0x000F | ((GnssFixType::Fix3D as u16) << 4) | (1 << 9);
+
+/// Standard gravity in m/s/s
+const GRAVITY: f64 = 9.806_65;
+
+/// Step used to differentiate the trajectory for rates and accelerations
+const DIFFERENTIATION_STEP: Duration = Duration::from_millis(10);
+
+#[derive(Debug, Clone)]
+struct Stream {
+    kind: PacketKind,
+    period: Duration,
+    next_due: Duration,
+}
+
+/// Protocol-level mock of a Boreas
+pub struct MockBoreas {
+    parser: AnppParser,
+    trajectory: Trajectory,
+    device_information: DeviceInformation,
+    start_time: u32,
+    elapsed: Duration,
+    config: HashMap<PacketKind, Packet>,
+    streams: Vec<Stream>,
+    rejections: HashMap<PacketKind, AcknowledgeResult>,
+    noise_model: NoiseModel,
+    noise: Gaussian,
+}
+
+impl MockBoreas {
+    pub fn new(trajectory: Trajectory) -> Self {
+        Self {
+            parser: AnppParser::new(),
+            trajectory,
+            device_information: DeviceInformation {
+                software_version: 7_000,
+                device_type: DeviceType::BoreasD90,
+                hardware_revision: 1,
+                serial_number_1: 1,
+                serial_number_2: 0,
+                serial_number_3: 0,
+            },
+            start_time: DEFAULT_START_TIME,
+            elapsed: Duration::ZERO,
+            config: factory_configuration(),
+            streams: Vec::new(),
+            rejections: HashMap::new(),
+            noise_model: NoiseModel::default(),
+            noise: Gaussian::new(NoiseModel::default().seed),
+        }
+    }
+
+    /// Add measurement noise to the synthesised packets
+    pub fn with_noise(mut self, model: NoiseModel) -> Self {
+        self.noise = Gaussian::new(model.seed);
+        self.noise_model = model;
+        self
+    }
+
+    /// Report this device information instead of the default
+    pub fn with_device_information(mut self, info: DeviceInformation) -> Self {
+        self.device_information = info;
+        self
+    }
+
+    /// Unix time in seconds at the start of the simulation
+    pub fn with_start_time(mut self, unix_time_seconds: u32) -> Self {
+        self.start_time = unix_time_seconds;
+        self
+    }
+
+    /// Acknowledge every write of `kind` with `result` instead of storing it
+    pub fn reject(&mut self, kind: PacketKind, result: AcknowledgeResult) {
+        self.rejections.insert(kind, result);
+    }
+
+    /// Accept writes of `kind` again
+    pub fn accept(&mut self, kind: PacketKind) {
+        self.rejections.remove(&kind);
+    }
+
+    /// Simulated time since the start
+    pub fn elapsed(&self) -> Duration {
+        self.elapsed
+    }
+
+    /// Current value of a configuration packet
+    pub fn configuration(&self, kind: PacketKind) -> Option<&Packet> {
+        self.config.get(&kind)
+    }
+
+    /// Packets currently being streamed and their periods
+    pub fn streams(&self) -> impl Iterator<Item = (PacketKind, Duration)> + '_ {
+        self.streams.iter().map(|s| (s.kind, s.period))
+    }
+
+    /// Feed bytes from the host and return the device's response frames
+    pub fn feed(&mut self, input: &[u8]) -> Vec<u8> {
+        let mut packets = Vec::new();
+        let mut next = self.parser.consume(input);
+        while let Some(packet) = next {
+            packets.extend(self.handle(&packet));
+            next = self.parser.consume(&[]);
+        }
+        frames(&packets)
+    }
+
+    /// Advance the simulated clock and return the stream frames due in the
+    /// interval
+    pub fn advance(&mut self, dt: Duration) -> Vec<u8> {
+        let packets = self.tick(dt);
+        frames(&packets)
+    }
+
+    /// Run the simulation for `duration` in steps of `step`, passing each
+    /// stream frame to `emit` as it becomes due
+    pub fn play(&mut self, duration: Duration, step: Duration, mut emit: impl FnMut(&[u8])) {
+        let step = if step.is_zero() { duration } else { step };
+        let mut remaining = duration;
+        while !remaining.is_zero() {
+            let dt = step.min(remaining);
+            remaining -= dt;
+            for packet in self.tick(dt) {
+                emit(&frames(&[packet]));
+            }
+        }
+    }
+
+    /// Packet-level [`feed`](Self::feed): the responses to one host packet
+    pub fn handle(&mut self, packet: &Packet) -> Vec<Packet> {
+        match packet {
+            Packet::Request(request) => request
+                .requested_packets
+                .iter()
+                .map(|kind| self.read(*kind).unwrap_or_else(|| acknowledge(packet, AcknowledgeResult::UnknownPacket)))
+                .collect(),
+            _ if packet.to_bytes().is_err() => Vec::new(),
+            _ => {
+                let kind = PacketKind::from(packet.packet_id());
+                if let Some(result) = self.rejections.get(&kind) {
+                    return vec![acknowledge(packet, *result)];
+                }
+                self.write(packet);
+                vec![acknowledge(packet, AcknowledgeResult::Success)]
+            }
+        }
+    }
+
+    /// Packet-level [`advance`](Self::advance)
+    pub fn tick(&mut self, dt: Duration) -> Vec<Packet> {
+        let end = self.elapsed + dt;
+        let mut due = Vec::new();
+        for stream in &mut self.streams {
+            while stream.next_due <= end {
+                due.push((stream.next_due, stream.kind));
+                stream.next_due += stream.period;
+            }
+        }
+        due.sort_by_key(|(at, _)| *at);
+        self.elapsed = end;
+
+        due.into_iter().filter_map(|(at, kind)| self.state_packet(kind, at)).collect()
+    }
+
+    fn read(&mut self, kind: PacketKind) -> Option<Packet> {
+        match kind {
+            PacketKind::DeviceInformation => Some(Packet::DeviceInformation(self.device_information.clone())),
+            _ => self.state_packet(kind, self.elapsed).or_else(|| self.config.get(&kind).cloned()),
+        }
+    }
+
+    fn write(&mut self, packet: &Packet) {
+        match packet {
+            Packet::RestoreFactorySettings(_) => {
+                self.config = factory_configuration();
+                self.streams.clear();
+            }
+            Packet::PacketsPeriod(periods) => {
+                if periods.clear_existing {
+                    self.streams.clear();
+                }
+                let timer = match self.config.get(&PacketKind::PacketTimerPeriod) {
+                    Some(Packet::PacketTimerPeriod(timer)) => timer.clone(),
+                    _ => unreachable!("factory configuration has a packet timer period"),
+                };
+                for entry in &periods.packet_periods {
+                    self.streams.retain(|s| s.kind != entry.packet_type);
+                    if entry.divisor != 0 {
+                        let period = entry.period(&timer);
+                        self.streams.push(Stream {
+                            kind: entry.packet_type,
+                            period,
+                            next_due: self.elapsed + period,
+                        });
+                    }
+                }
+            }
+            _ => {
+                self.config.insert(PacketKind::from(packet.packet_id()), packet.clone());
+            }
+        }
+    }
+
+    /// State packets the simulation can synthesise
+    fn state_packet(&mut self, kind: PacketKind, at: Duration) -> Option<Packet> {
+        let time = u64::from(self.start_time) * 1_000_000 + at.as_micros() as u64;
+        let unix_time_seconds = (time / 1_000_000) as u32;
+        let microseconds = (time % 1_000_000) as u32;
+        let filter_status = FilterStatus::from(FILTER_STATUS);
+
+        match kind {
+            PacketKind::SystemState => {
+                let motion = self.motion(at);
+                let point = motion.point;
+                let noise = self.noise_model.clone();
+                let position_std = noise.position.meters() as f32;
+                let r = trajectory::EARTH_RADIUS.meters();
+                let north = self.noise.sample(noise.position.meters());
+                let east = self.noise.sample(noise.position.meters());
+                let down = self.noise.sample(noise.position.meters());
+                let mut velocity = point.velocity.map(|v| v.meters_per_second());
+                for v in &mut velocity {
+                    *v += self.noise.sample(noise.velocity.meters_per_second());
+                }
+                let [roll, pitch, heading] = [point.roll, point.pitch, point.heading]
+                    .map(|a| a.radians() + self.noise.sample(noise.attitude.radians()));
+                let rates = motion.angular_velocity.map(|w| w + self.noise.sample(noise.gyroscope));
+
+                Some(Packet::SystemState(SystemState {
+                    system_status: SystemStatus::default(),
+                    filter_status,
+                    unix_time_seconds,
+                    microseconds,
+                    latitude: point.latitude.radians() + north / r,
+                    longitude: point.longitude.radians() + east / (r * point.latitude.radians().cos()),
+                    height: point.height.meters() - down,
+                    velocity_north: velocity[0] as f32,
+                    velocity_east: velocity[1] as f32,
+                    velocity_down: velocity[2] as f32,
+                    body_acceleration_x: motion.acceleration[0] as f32,
+                    body_acceleration_y: motion.acceleration[1] as f32,
+                    body_acceleration_z: motion.acceleration[2] as f32,
+                    g_force: (motion.specific_force().iter().map(|f| f * f).sum::<f64>().sqrt() / GRAVITY) as f32,
+                    roll: Radians(roll as f32),
+                    pitch: Radians(pitch as f32),
+                    heading: normalize_2pi(Radians(heading as f32)),
+                    angular_velocity_x: rates[0] as f32,
+                    angular_velocity_y: rates[1] as f32,
+                    angular_velocity_z: rates[2] as f32,
+                    latitude_std_dev: 0.5 + position_std,
+                    longitude_std_dev: 0.5 + position_std,
+                    height_std_dev: 1.0 + position_std,
+                    extension: None,
+                }))
+            }
+            PacketKind::RawSensors => {
+                let motion = self.motion(at);
+                let (accel_std, gyro_std) = (self.noise_model.accelerometer, self.noise_model.gyroscope);
+                let force = motion.specific_force().map(|f| f + self.noise.sample(accel_std));
+                let rates = motion.angular_velocity.map(|w| w + self.noise.sample(gyro_std));
+                // International standard atmosphere below 11 km
+                let pressure = 101_325.0 * (1.0 - 2.255_77e-5 * motion.point.height.meters()).powf(5.255_88);
+                Some(Packet::RawSensors(RawSensors {
+                    accelerometer_x: force[0] as f32,
+                    accelerometer_y: force[1] as f32,
+                    accelerometer_z: force[2] as f32,
+                    gyroscope_x: rates[0] as f32,
+                    gyroscope_y: rates[1] as f32,
+                    gyroscope_z: rates[2] as f32,
+                    imu_temperature: 25.0,
+                    pressure: pressure as f32,
+                    pressure_temperature: 25.0,
+                }))
+            }
+            PacketKind::Satellites => Some(Packet::Satellites(Satellites {
+                hdop: 0.8,
+                vdop: 1.2,
+                gps_satellites: 10,
+                glonass_satellites: 6,
+                beidou_satellites: 8,
+                galileo_satellites: 7,
+                sbas_satellites: 2,
+            })),
+            PacketKind::UnixTime => Some(Packet::UnixTime(UnixTime { unix_time_seconds, microseconds })),
+            PacketKind::Status => Some(Packet::Status(Status {
+                system_status: SystemStatus::default(),
+                filter_status,
+                extension: None,
+            })),
+            _ => None,
+        }
+    }
+
+    fn motion(&self, at: Duration) -> Motion {
+        let point = self.trajectory.sample(at);
+        let later = self.trajectory.sample(at + DIFFERENTIATION_STEP);
+        let dt = DIFFERENTIATION_STEP.as_secs_f64();
+
+        let rate = |a: crate::units::Angle, b: crate::units::Angle| shortest_angular_distance(a, b).radians() / dt;
+        let accel = |i: usize| (later.velocity[i] - point.velocity[i]).meters_per_second() / dt;
+        // Kinematic acceleration rotated into the heading frame; roll and
+        // pitch are small enough in the generated paths to ignore here
+        let (sin_h, cos_h) = point.heading.radians().sin_cos();
+        let (a_north, a_east) = (accel(0), accel(1));
+
+        Motion {
+            point,
+            angular_velocity: [
+                rate(point.roll, later.roll),
+                rate(point.pitch, later.pitch),
+                rate(point.heading, later.heading),
+            ],
+            acceleration: [
+                a_north * cos_h + a_east * sin_h,
+                -a_north * sin_h + a_east * cos_h,
+                accel(2),
+            ],
+        }
+    }
+}
+
+/// Trajectory point with its derivatives in the body frame
+struct Motion {
+    point: TrajectoryPoint,
+    /// rad/s about x, y, z
+    angular_velocity: [f64; 3],
+    /// m/s/s along x, y, z, excluding gravity
+    acceleration: [f64; 3],
+}
+
+impl Motion {
+    /// What an accelerometer reads: kinematic acceleration minus gravity
+    fn specific_force(&self) -> [f64; 3] {
+        let (sin_r, cos_r) = self.point.roll.radians().sin_cos();
+        let (sin_p, cos_p) = self.point.pitch.radians().sin_cos();
+        [
+            self.acceleration[0] + GRAVITY * sin_p,
+            self.acceleration[1] - GRAVITY * sin_r * cos_p,
+            self.acceleration[2] - GRAVITY * cos_r * cos_p,
+        ]
+    }
+}
+
+/// Configuration a device ships with
+fn factory_configuration() -> HashMap<PacketKind, Packet> {
+    [
+        Packet::PacketTimerPeriod(PacketTimerPeriod {
+            permanent: true,
+            utc_synchronisation: true,
+            packet_timer_period: Duration::from_millis(1),
+        }),
+        Packet::BaudRates(BaudRates {
+            permanent: true,
+            primary_port: BaudRate::B115200,
+            gpio_port: BaudRate::B115200,
+            auxiliary_port: BaudRate::B115200,
+        }),
+        Packet::FilterOptions(FilterOptions {
+            permanent: true,
+            vehicle_type: VehicleType::Boat,
+            internal_gnss_enabled: true,
+            atmospheric_altitude_enabled: false,
+            velocity_heading_enabled: false,
+            reversing_detection_enabled: false,
+            motion_analysis_enabled: false,
+        }),
+    ]
+    .into_iter()
+    .map(|packet| (PacketKind::from(packet.packet_id()), packet))
+    .collect()
+}
+
+fn acknowledge(packet: &Packet, result: AcknowledgeResult) -> Packet {
+    let payload = wire::encode_payload(packet).unwrap_or_default();
+    Packet::Acknowledge(Acknowledge {
+        acknowledged_packet: PacketKind::from(packet.packet_id()),
+        packet_crc: AnppProtocol::calculate_crc16(&payload),
+        result,
+    })
+}
+
+fn frames(packets: &[Packet]) -> Vec<u8> {
+    let mut bytes = Vec::new();
+    for packet in packets {
+        if let Ok(payload) = wire::encode_payload(packet)
+            && let Ok(frame) = wire::encode_frame(PacketId::new(packet.packet_id()), &payload)
+        {
+            bytes.extend(frame);
+        }
+    }
+    bytes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::packet::config::{PacketPeriod, PacketsPeriod};
+    use crate::packet::system::Request;
+    use crate::units::{Angle, Length, Velocity};
+
+    fn origin() -> (Angle, Angle, Length) {
+        (Angle::from_degrees(-33.87), Angle::from_degrees(151.21), Length::from_meters(10.0))
+    }
+
+    fn static_device() -> MockBoreas {
+        let (latitude, longitude, height) = origin();
+        MockBoreas::new(Trajectory::Static { latitude, longitude, height })
+    }
+
+    fn parse_all(bytes: &[u8]) -> Vec<Packet> {
+        let mut parser = AnppParser::new();
+        let mut packets = Vec::new();
+        let mut next = parser.consume(bytes);
+        while let Some(packet) = next {
+            packets.push(packet);
+            next = parser.consume(&[]);
+        }
+        packets
+    }
+
+    fn subscribe(periods: Vec<PacketPeriod>) -> Packet {
+        Packet::PacketsPeriod(PacketsPeriod { permanent: false, clear_existing: true, packet_periods: periods })
+    }
+
+    #[test]
+    fn test_request_returns_device_information_and_config() {
+        let mut device = static_device();
+        let request = Packet::Request(Request::multiple([
+            PacketKind::DeviceInformation,
+            PacketKind::FilterOptions,
+            PacketKind::GeoidHeight,
+        ]));
+        let replies = parse_all(&device.feed(&request.to_bytes().unwrap()));
+
+        assert!(matches!(&replies[0], Packet::DeviceInformation(info) if info.device_type == DeviceType::BoreasD90));
+        assert!(matches!(&replies[1], Packet::FilterOptions(options) if options.vehicle_type == VehicleType::Boat));
+        match &replies[2] {
+            Packet::Acknowledge(ack) => assert_eq!(ack.result, AcknowledgeResult::UnknownPacket),
+            other => panic!("unexpected {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_write_is_stored_and_acknowledged() {
+        let mut device = static_device();
+        let options = FilterOptions::builder().vehicle_type(VehicleType::Car).build().unwrap();
+        let packet = Packet::FilterOptions(options.clone());
+
+        let replies = device.handle(&packet);
+        match &replies[0] {
+            Packet::Acknowledge(ack) => {
+                assert!(ack.is_success());
+                let payload = wire::encode_payload(&packet).unwrap();
+                assert_eq!(ack.packet_crc, AnppProtocol::calculate_crc16(&payload));
+            }
+            other => panic!("unexpected {:?}", other),
+        }
+        assert_eq!(device.configuration(PacketKind::FilterOptions), Some(&packet));
+
+        device.reject(PacketKind::FilterOptions, AcknowledgeResult::ValuesOutOfRange);
+        let replies = device.handle(&Packet::FilterOptions(FilterOptions { vehicle_type: VehicleType::Boat, ..options }));
+        assert!(matches!(&replies[0], Packet::Acknowledge(ack) if ack.result == AcknowledgeResult::ValuesOutOfRange));
+        assert_eq!(device.configuration(PacketKind::FilterOptions), Some(&packet));
+    }
+
+    #[test]
+    fn test_streams_at_configured_rate() {
+        let mut device = static_device();
+        device.handle(&subscribe(vec![
+            PacketPeriod::from_packet::<SystemState>(100),
+            PacketPeriod::from_packet::<Status>(500),
+        ]));
+
+        let packets = device.tick(Duration::from_secs(1));
+        let states: Vec<_> = packets.iter().filter_map(|p| match p {
+            Packet::SystemState(s) => Some(s.clone()),
+            _ => None,
+        }).collect();
+        assert_eq!(states.len(), 10);
+        assert_eq!(packets.iter().filter(|p| matches!(p, Packet::Status(_))).count(), 2);
+        assert_eq!(states[0].microseconds, 100_000);
+        assert_eq!(states[9].unix_time_seconds, DEFAULT_START_TIME + 1);
+        assert_eq!(states[0].filter_status.gnss_fix_type(), GnssFixType::Fix3D);
+
+        // Clearing the schedule stops the stream
+        device.handle(&subscribe(vec![]));
+        assert!(device.tick(Duration::from_secs(1)).is_empty());
+    }
+
+    #[test]
+    fn test_restore_factory_settings() {
+        let mut device = static_device();
+        device.handle(&Packet::FilterOptions(FilterOptions::builder().vehicle_type(VehicleType::Car).build().unwrap()));
+        device.handle(&subscribe(vec![PacketPeriod::from_packet::<SystemState>(100)]));
+        device.handle(&Packet::RestoreFactorySettings(crate::packet::system::RestoreFactorySettings {}));
+
+        assert!(matches!(device.configuration(PacketKind::FilterOptions),
+            Some(Packet::FilterOptions(options)) if options.vehicle_type == VehicleType::Boat));
+        assert_eq!(device.streams().count(), 0);
+    }
+
+    #[test]
+    fn test_recorded_track_playback() {
+        let track = Track::from_csv("\
+time,latitude,longitude,height,roll,pitch,heading
+0,0,0,0,0,0,90
+10,0,0.001,0,0,0,90
+").unwrap();
+        let mut device = MockBoreas::new(Trajectory::Recorded(track));
+        device.handle(&subscribe(vec![
+            PacketPeriod::from_packet::<SystemState>(1000),
+            PacketPeriod::from_packet::<RawSensors>(1000),
+            PacketPeriod::from_packet::<Satellites>(5000),
+        ]));
+
+        let mut frames = Vec::new();
+        device.play(Duration::from_secs(10), Duration::from_millis(100), |frame| frames.push(frame.to_vec()));
+        assert_eq!(frames.len(), 10 + 10 + 2);
+
+        let packets: Vec<Packet> = frames.iter().flat_map(|f| parse_all(f)).collect();
+        assert_eq!(packets.len(), frames.len());
+        match &packets[8] {
+            Packet::SystemState(state) => {
+                assert!((state.longitude.to_degrees() - 0.0005).abs() < 1e-9);
+                assert!((state.velocity_east - 11.119).abs() < 1e-2);
+                assert!((state.heading.to_degrees().get() - 90.0).abs() < 1e-4);
+            }
+            other => panic!("unexpected {:?}", other),
+        }
+        match &packets[9] {
+            // Level and unaccelerated, so the accelerometer reads gravity only
+            Packet::RawSensors(raw) => {
+                assert!((raw.accelerometer_z + GRAVITY as f32).abs() < 1e-4);
+                assert!(raw.accelerometer_x.abs() < 1e-4);
+                assert!((raw.pressure - 101_325.0).abs() < 1.0);
+            }
+            other => panic!("unexpected {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_noise_is_seeded() {
+        let noise = NoiseModel {
+            position: Length::from_meters(2.0),
+            velocity: Velocity::from_meters_per_second(0.1),
+            ..NoiseModel::default()
+        };
+        let run = |seed| {
+            let mut device = static_device().with_noise(NoiseModel { seed, ..noise.clone() });
+            device.handle(&subscribe(vec![PacketPeriod::from_packet::<SystemState>(100)]));
+            device.tick(Duration::from_secs(1))
+        };
+
+        let (a, b, c) = (run(1), run(1), run(2));
+        assert_eq!(a, b);
+        assert_ne!(a, c);
+        match &a[0] {
+            Packet::SystemState(state) => {
+                assert_ne!(state.latitude, origin().0.radians());
+                assert_eq!(state.latitude_std_dev, 2.5);
+            }
+            other => panic!("unexpected {:?}", other),
+        }
+    }
+}
@@ -0,0 +1,79 @@
+//! Measurement noise for synthesised packets.
+
+use crate::units::{Angle, Length, Velocity};
+
+/// Standard deviations of the white noise added to synthesised values.
+/// The default adds none.
+#[derive(Debug, Clone, PartialEq)]
+pub struct NoiseModel {
+    /// Per axis, north/east/down
+    pub position: Length,
+    /// Per axis, north/east/down
+    pub velocity: Velocity,
+    /// Roll, pitch and heading
+    pub attitude: Angle,
+    /// Accelerometer noise in m/s/s
+    pub accelerometer: f64,
+    /// Gyroscope noise in rad/s
+    pub gyroscope: f64,
+    /// Seed for the generator, so runs reproduce
+    pub seed: u64,
+}
+
+impl Default for NoiseModel {
+    fn default() -> Self {
+        Self {
+            position: Length::ZERO,
+            velocity: Velocity::ZERO,
+            attitude: Angle::ZERO,
+            accelerometer: 0.0,
+            gyroscope: 0.0,
+            seed: 1,
+        }
+    }
+}
+
+/// Gaussian generator: xorshift64* fed through Box-Muller
+#[derive(Debug, Clone)]
+pub(crate) struct Gaussian(u64);
+
+impl Gaussian {
+    pub(crate) fn new(seed: u64) -> Self {
+        // Zero is a fixed point of xorshift
+        Self(seed.max(1))
+    }
+
+    /// Uniform in (0, 1]
+    fn uniform(&mut self) -> f64 {
+        self.0 ^= self.0 >> 12;
+        self.0 ^= self.0 << 25;
+        self.0 ^= self.0 >> 27;
+        let bits = self.0.wrapping_mul(0x2545_f491_4f6c_dd1d) >> 11;
+        (bits as f64 + 1.0) / (1u64 << 53) as f64
+    }
+
+    /// Zero-mean sample with the given standard deviation
+    pub(crate) fn sample(&mut self, std_dev: f64) -> f64 {
+        if std_dev == 0.0 {
+            return 0.0;
+        }
+        let (u1, u2) = (self.uniform(), self.uniform());
+        std_dev * (-2.0 * u1.ln()).sqrt() * (2.0 * std::f64::consts::PI * u2).cos()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_gaussian_moments() {
+        let mut noise = Gaussian::new(7);
+        let samples: Vec<f64> = (0..20_000).map(|_| noise.sample(2.0)).collect();
+        let mean = samples.iter().sum::<f64>() / samples.len() as f64;
+        let variance = samples.iter().map(|x| (x - mean).powi(2)).sum::<f64>() / samples.len() as f64;
+        assert!(mean.abs() < 0.05);
+        assert!((variance.sqrt() - 2.0).abs() < 0.05);
+        assert_eq!(noise.sample(0.0), 0.0);
+    }
+}
@@ -0,0 +1,547 @@
+//! Paths for the simulated vehicle.
+//!
+//! [`Trajectory`] covers a few analytic paths and recorded [`Track`]s. Tracks
+//! load from CSV or GPX and are linearly interpolated between points.
+//!
+//! CSV tracks need a header row naming the columns. `time` (seconds),
+//! `latitude`, `longitude` (degrees) and `height` (meters) are required;
+//! `roll`, `pitch` and `heading` (degrees) are optional and the heading
+//! falls back to the course over ground when absent:
+//!
+//! ```
+//! use std::time::Duration;
+//! use liban::sim::trajectory::Track;
+//!
+//! let track = Track::from_csv("\
+//! time,latitude,longitude,height
+//! 0,-33.8700,151.2100,10
+//! 10,-33.8690,151.2100,10
+//! ").unwrap();
+//! let point = track.sample(Duration::from_secs(5));
+//! assert!((point.latitude.degrees() + 33.8695).abs() < 1e-9);
+//! assert!(point.velocity[0].meters_per_second() > 11.0);
+//! ```
+
+use std::f64::consts::PI;
+use std::time::Duration;
+
+use crate::angle::{normalize_2pi, normalize_pi, shortest_angular_distance};
+use crate::error::{AnError, Result};
+use crate::units::{Angle, Length, Velocity};
+
+/// Mean Earth radius used for the synthetic trajectories
+pub const EARTH_RADIUS: Length = Length::from_meters(6_371_000.0);
+
+/// Path the simulated vehicle follows
+#[derive(Debug, Clone, PartialEq)]
+pub enum Trajectory {
+    /// Stationary at a fixed position
+    Static { latitude: Angle, longitude: Angle, height: Length },
+    /// Clockwise circle (seen from above) starting due north of the centre
+    Circle { latitude: Angle, longitude: Angle, height: Length, radius: Length, speed: Velocity },
+    /// Constant speed along the great circle leaving the start point on
+    /// `heading`
+    GreatCircle { latitude: Angle, longitude: Angle, height: Length, heading: Angle, speed: Velocity },
+    /// Recorded track, held at its last point once it ends
+    Recorded(Track),
+}
+
+/// Position, velocity and attitude at one instant
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TrajectoryPoint {
+    pub latitude: Angle,
+    pub longitude: Angle,
+    pub height: Length,
+    /// North, east, down
+    pub velocity: [Velocity; 3],
+    pub roll: Angle,
+    pub pitch: Angle,
+    pub heading: Angle,
+}
+
+impl Trajectory {
+    /// Point reached `elapsed` after the start
+    pub fn sample(&self, elapsed: Duration) -> TrajectoryPoint {
+        let t = elapsed.as_secs_f64();
+        let r = EARTH_RADIUS.meters();
+
+        match self {
+            Trajectory::Static { latitude, longitude, height } => TrajectoryPoint {
+                latitude: *latitude,
+                longitude: *longitude,
+                height: *height,
+                velocity: [Velocity::ZERO; 3],
+                roll: Angle::ZERO,
+                pitch: Angle::ZERO,
+                heading: Angle::ZERO,
+            },
+            Trajectory::Circle { latitude, longitude, height, radius, speed } => {
+                let omega = speed.meters_per_second() / radius.meters();
+                let phase = omega * t;
+                let north = radius.meters() * phase.cos();
+                let east = radius.meters() * phase.sin();
+                let v_north = -speed.meters_per_second() * phase.sin();
+                let v_east = speed.meters_per_second() * phase.cos();
+                TrajectoryPoint {
+                    latitude: Angle::from_radians(latitude.radians() + north / r),
+                    longitude: Angle::from_radians(longitude.radians() + east / (r * latitude.radians().cos())),
+                    height: *height,
+                    velocity: [
+                        Velocity::from_meters_per_second(v_north),
+                        Velocity::from_meters_per_second(v_east),
+                        Velocity::ZERO,
+                    ],
+                    roll: Angle::ZERO,
+                    pitch: Angle::ZERO,
+                    heading: normalize_2pi(Angle::from_radians(v_east.atan2(v_north))),
+                }
+            }
+            Trajectory::GreatCircle { latitude, longitude, height, heading, speed } => {
+                let (lat1, lon1, bearing) = (latitude.radians(), longitude.radians(), heading.radians());
+                let delta = speed.meters_per_second() * t / r;
+                let lat2 = (lat1.sin() * delta.cos() + lat1.cos() * delta.sin() * bearing.cos()).asin();
+                let lon2 = lon1
+                    + (bearing.sin() * delta.sin() * lat1.cos()).atan2(delta.cos() - lat1.sin() * lat2.sin());
+                // Current heading is the reverse of the bearing back to the start
+                let course = if delta == 0.0 {
+                    bearing
+                } else {
+                    let back = ((lon1 - lon2).sin() * lat1.cos())
+                        .atan2(lat2.cos() * lat1.sin() - lat2.sin() * lat1.cos() * (lon1 - lon2).cos());
+                    back + PI
+                };
+                let course = normalize_2pi(course);
+                TrajectoryPoint {
+                    latitude: Angle::from_radians(lat2),
+                    longitude: normalize_pi(Angle::from_radians(lon2)),
+                    height: *height,
+                    velocity: [
+                        Velocity::from_meters_per_second(speed.meters_per_second() * course.cos()),
+                        Velocity::from_meters_per_second(speed.meters_per_second() * course.sin()),
+                        Velocity::ZERO,
+                    ],
+                    roll: Angle::ZERO,
+                    pitch: Angle::ZERO,
+                    heading: Angle::from_radians(course),
+                }
+            }
+            Trajectory::Recorded(track) => track.sample(elapsed),
+        }
+    }
+}
+
+/// One recorded position, with attitude if the source had it
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TrackPoint {
+    /// Offset from the start of the track
+    pub time: Duration,
+    pub latitude: Angle,
+    pub longitude: Angle,
+    pub height: Length,
+    /// Roll, pitch and heading
+    pub attitude: Option<[Angle; 3]>,
+}
+
+/// Recorded sequence of positions
+#[derive(Debug, Clone, PartialEq)]
+pub struct Track {
+    points: Vec<TrackPoint>,
+}
+
+impl Track {
+    /// Build a track from points in time order
+    pub fn new(points: Vec<TrackPoint>) -> Result<Self> {
+        if points.is_empty() {
+            return Err(AnError::ValidationFailed("track has no points".to_string()));
+        }
+        if let Some(i) = points.windows(2).position(|w| w[1].time <= w[0].time) {
+            return Err(AnError::ValidationFailed(format!(
+                "track point {} is not after the previous point", i + 1)));
+        }
+        Ok(Self { points })
+    }
+
+    pub fn points(&self) -> &[TrackPoint] {
+        &self.points
+    }
+
+    /// Time from the first to the last point
+    pub fn duration(&self) -> Duration {
+        self.points[self.points.len() - 1].time - self.points[0].time
+    }
+
+    /// Parse a CSV track; see the module docs for the columns
+    pub fn from_csv(text: &str) -> Result<Self> {
+        let mut lines = text
+            .lines()
+            .enumerate()
+            .map(|(i, line)| (i + 1, line.trim()))
+            .filter(|(_, line)| !line.is_empty() && !line.starts_with('#'));
+
+        let (_, header) = lines.next().ok_or_else(|| AnError::ValidationFailed("empty CSV track".to_string()))?;
+        let columns: Vec<String> = header.split(',').map(|c| c.trim().to_ascii_lowercase()).collect();
+        let column = |name: &str| columns.iter().position(|c| c == name);
+        let required = |name: &str| {
+            column(name).ok_or_else(|| AnError::ValidationFailed(format!("CSV track has no {} column", name)))
+        };
+        let (time, latitude, longitude, height) =
+            (required("time")?, required("latitude")?, required("longitude")?, required("height")?);
+        let attitude = match (column("roll"), column("pitch"), column("heading")) {
+            (Some(roll), Some(pitch), Some(heading)) => Some([roll, pitch, heading]),
+            (None, None, None) => None,
+            _ => return Err(AnError::ValidationFailed(
+                "CSV track needs all of roll, pitch and heading or none".to_string())),
+        };
+
+        let mut points = Vec::new();
+        let mut start = None;
+        for (number, line) in lines {
+            let fields: Vec<&str> = line.split(',').map(str::trim).collect();
+            let field = |index: usize| -> Result<f64> {
+                fields
+                    .get(index)
+                    .and_then(|f| f.parse().ok())
+                    .ok_or_else(|| AnError::ValidationFailed(format!(
+                        "line {}: bad or missing {}", number, columns[index])))
+            };
+
+            let seconds = field(time)?;
+            let start = *start.get_or_insert(seconds);
+            let offset = Duration::try_from_secs_f64(seconds - start)
+                .map_err(|_| AnError::ValidationFailed(format!("line {}: time goes backwards", number)))?;
+            points.push(TrackPoint {
+                time: offset,
+                latitude: Angle::from_degrees(field(latitude)?),
+                longitude: Angle::from_degrees(field(longitude)?),
+                height: Length::from_meters(field(height)?),
+                attitude: match attitude {
+                    Some(indices) => {
+                        let mut angles = [Angle::ZERO; 3];
+                        for (angle, index) in angles.iter_mut().zip(indices) {
+                            *angle = Angle::from_degrees(field(index)?);
+                        }
+                        Some(angles)
+                    }
+                    None => None,
+                },
+            });
+        }
+        Self::new(points)
+    }
+
+    /// Parse the track points of a GPX file. Every point needs a `<time>`;
+    /// a missing `<ele>` reads as zero height.
+    pub fn from_gpx(text: &str) -> Result<Self> {
+        let mut points = Vec::new();
+        let mut start = None;
+        let mut rest = text;
+
+        while let Some(open) = rest.find("<trkpt") {
+            rest = &rest[open..];
+            let tag_end = rest.find('>').ok_or_else(|| AnError::ValidationFailed("unterminated <trkpt>".to_string()))?;
+            let tag = &rest[..tag_end];
+            let (body, next) = if tag.ends_with('/') {
+                ("", &rest[tag_end + 1..])
+            } else {
+                // Only a closing tag after the opening one ends the point
+                let close = rest[tag_end + 1..].find("</trkpt>")
+                    .ok_or_else(|| AnError::ValidationFailed("unterminated <trkpt>".to_string()))?
+                    + tag_end + 1;
+                (&rest[tag_end + 1..close], &rest[close + "</trkpt>".len()..])
+            };
+            let index = points.len();
+
+            let number = |value: Option<&str>, name: &str| -> Result<f64> {
+                value
+                    .and_then(|v| v.trim().parse().ok())
+                    .ok_or_else(|| AnError::ValidationFailed(format!("track point {}: bad or missing {}", index, name)))
+            };
+            let time = element(body, "time")
+                .ok_or_else(|| AnError::ValidationFailed(format!("track point {}: missing time", index)))
+                .and_then(|t| parse_iso8601(t.trim()).ok_or_else(|| AnError::ValidationFailed(
+                    format!("track point {}: bad time {:?}", index, t))))?;
+            let start = *start.get_or_insert(time);
+            let offset = Duration::try_from_secs_f64(time - start)
+                .map_err(|_| AnError::ValidationFailed(format!("track point {}: time goes backwards", index)))?;
+
+            points.push(TrackPoint {
+                time: offset,
+                latitude: Angle::from_degrees(number(attribute(tag, "lat"), "lat")?),
+                longitude: Angle::from_degrees(number(attribute(tag, "lon"), "lon")?),
+                height: Length::from_meters(match element(body, "ele") {
+                    Some(ele) => number(Some(ele), "ele")?,
+                    None => 0.0,
+                }),
+                attitude: None,
+            });
+            rest = next;
+        }
+        Self::new(points)
+    }
+
+    /// Interpolated point `elapsed` after the first point
+    pub fn sample(&self, elapsed: Duration) -> TrajectoryPoint {
+        let start = self.points[0].time;
+        let t = start + elapsed;
+        let i = self.points.partition_point(|p| p.time <= t);
+
+        if i == 0 || i == self.points.len() {
+            let p = if i == 0 { &self.points[0] } else { &self.points[i - 1] };
+            return at_rest(p);
+        }
+
+        let (a, b) = (&self.points[i - 1], &self.points[i]);
+        let dt = (b.time - a.time).as_secs_f64();
+        let f = (t - a.time).as_secs_f64() / dt;
+        let r = EARTH_RADIUS.meters();
+
+        let v_north = (b.latitude - a.latitude).radians() * r / dt;
+        let v_east = (b.longitude - a.longitude).radians() * r * a.latitude.radians().cos() / dt;
+        let v_down = -(b.height - a.height).meters() / dt;
+        let [roll, pitch, heading] = match (a.attitude, b.attitude) {
+            (Some(x), Some(y)) => [
+                lerp_angle(x[0], y[0], f),
+                lerp_angle(x[1], y[1], f),
+                normalize_2pi(lerp_angle(x[2], y[2], f)),
+            ],
+            _ => [Angle::ZERO, Angle::ZERO, normalize_2pi(Angle::from_radians(v_east.atan2(v_north)))],
+        };
+
+        TrajectoryPoint {
+            latitude: a.latitude + Angle::from_radians((b.latitude - a.latitude).radians() * f),
+            longitude: a.longitude + Angle::from_radians((b.longitude - a.longitude).radians() * f),
+            height: a.height + Length::from_meters((b.height - a.height).meters() * f),
+            velocity: [
+                Velocity::from_meters_per_second(v_north),
+                Velocity::from_meters_per_second(v_east),
+                Velocity::from_meters_per_second(v_down),
+            ],
+            roll,
+            pitch,
+            heading,
+        }
+    }
+}
+
+fn at_rest(p: &TrackPoint) -> TrajectoryPoint {
+    let [roll, pitch, heading] = p.attitude.unwrap_or([Angle::ZERO; 3]);
+    TrajectoryPoint {
+        latitude: p.latitude,
+        longitude: p.longitude,
+        height: p.height,
+        velocity: [Velocity::ZERO; 3],
+        roll,
+        pitch,
+        heading,
+    }
+}
+
+/// Interpolate along the shorter way round
+fn lerp_angle(a: Angle, b: Angle, f: f64) -> Angle {
+    let diff = shortest_angular_distance(a, b);
+    Angle::from_radians(a.radians() + diff.radians() * f)
+}
+
+/// Value of `name="..."` in a tag
+fn attribute<'a>(tag: &'a str, name: &str) -> Option<&'a str> {
+    let key = format!(" {}=", name);
+    let start = tag.find(&key)? + key.len();
+    let quote = tag[start..].chars().next().filter(|quote| matches!(quote, '"' | '\''))?;
+    let value = &tag[start + 1..];
+    Some(&value[..value.find(quote)?])
+}
+
+/// Text of the first `<name>...</name>` element
+fn element<'a>(body: &'a str, name: &str) -> Option<&'a str> {
+    let open = format!("<{}>", name);
+    let start = body.find(&open)? + open.len();
+    let end = body[start..].find(&format!("</{}>", name))?;
+    Some(&body[start..start + end])
+}
+
+/// Seconds since the Unix epoch for an RFC 3339 timestamp such as
+/// `2024-05-01T12:00:00.5Z` or `2024-05-01T22:00:00+10:00`
+fn parse_iso8601(text: &str) -> Option<f64> {
+    let (date, time) = text.split_once(['T', ' '])?;
+    let mut date = date.splitn(3, '-');
+    // Every field is range checked before the calendar arithmetic below
+    let year: i128 = date.next()?.parse().ok()?;
+    let month: i128 = date.next()?.parse().ok()?;
+    let day: i128 = date.next()?.parse().ok()?;
+
+    let (clock, offset) = if let Some(clock) = time.strip_suffix('Z') {
+        (clock, 0)
+    } else {
+        let split = time.rfind(['+', '-'])?;
+        let (clock, zone) = time.split_at(split);
+        let (hours, minutes) = zone[1..].split_once(':')?;
+        let (hours, minutes): (i128, i128) = (hours.parse().ok()?, minutes.parse().ok()?);
+        if !(0..=23).contains(&hours) || !(0..=59).contains(&minutes) {
+            return None;
+        }
+        let offset = hours * 3600 + minutes * 60;
+        (clock, if zone.starts_with('-') { -offset } else { offset })
+    };
+    let mut clock = clock.splitn(3, ':');
+    let hour: i128 = clock.next()?.parse().ok()?;
+    let minute: i128 = clock.next()?.parse().ok()?;
+    let second: f64 = clock.next()?.parse().ok()?;
+
+    if !(0..=9999).contains(&year) || !(1..=12).contains(&month) || !(1..=31).contains(&day) {
+        return None;
+    }
+    // 60 seconds allows for a leap second
+    if !(0..=23).contains(&hour) || !(0..=59).contains(&minute) || !(0.0..61.0).contains(&second) {
+        return None;
+    }
+
+    // Days from 1970-01-01 in the proleptic Gregorian calendar
+    let y = if month <= 2 { year - 1 } else { year };
+    let era = y.div_euclid(400);
+    let yoe = y - era * 400;
+    let doy = (153 * ((month + 9) % 12) + 2) / 5 + day - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    let days = era * 146_097 + doe - 719_468;
+
+    Some((days * 86_400 + hour * 3600 + minute * 60 - offset) as f64 + second)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn origin() -> (Angle, Angle, Length) {
+        (Angle::from_degrees(-33.87), Angle::from_degrees(151.21), Length::from_meters(10.0))
+    }
+
+    #[test]
+    fn test_circle_trajectory() {
+        let (latitude, longitude, height) = origin();
+        let radius = Length::from_meters(100.0);
+        let speed = Velocity::from_meters_per_second(10.0);
+        let trajectory = Trajectory::Circle { latitude, longitude, height, radius, speed };
+        let quarter = Duration::from_secs_f64(PI * 100.0 / 2.0 / 10.0);
+
+        let start = trajectory.sample(Duration::ZERO);
+        assert!(((start.latitude - latitude).radians() * EARTH_RADIUS.meters() - 100.0).abs() < 1e-6);
+        assert!((start.heading.degrees() - 90.0).abs() < 1e-9);
+
+        let east = trajectory.sample(quarter);
+        let east_offset = (east.longitude - longitude).radians() * EARTH_RADIUS.meters() * latitude.radians().cos();
+        assert!((east_offset - 100.0).abs() < 1e-3);
+        assert!((east.heading.degrees() - 180.0).abs() < 1e-6);
+        assert!((east.velocity[0].meters_per_second() + 10.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_great_circle_trajectory() {
+        let trajectory = Trajectory::GreatCircle {
+            latitude: Angle::ZERO,
+            longitude: Angle::ZERO,
+            height: Length::ZERO,
+            heading: Angle::from_degrees(90.0),
+            speed: Velocity::from_meters_per_second(100.0),
+        };
+        // One degree of arc along the equator
+        let degree = EARTH_RADIUS.meters() * PI / 180.0;
+        let point = trajectory.sample(Duration::from_secs_f64(degree / 100.0));
+        assert!(point.latitude.degrees().abs() < 1e-9);
+        assert!((point.longitude.degrees() - 1.0).abs() < 1e-9);
+        assert!((point.heading.degrees() - 90.0).abs() < 1e-6);
+
+        // Heading north from the equator stays on the meridian
+        let north = Trajectory::GreatCircle {
+            latitude: Angle::ZERO,
+            longitude: Angle::from_degrees(10.0),
+            height: Length::ZERO,
+            heading: Angle::ZERO,
+            speed: Velocity::from_meters_per_second(100.0),
+        };
+        let point = north.sample(Duration::from_secs_f64(degree / 100.0));
+        assert!((point.latitude.degrees() - 1.0).abs() < 1e-9);
+        assert!((point.longitude.degrees() - 10.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_csv_track_with_attitude() {
+        let track = Track::from_csv("\
+# recorded on the harbour trial
+Time, Latitude, Longitude, Height, Roll, Pitch, Heading
+100.0, 0.0, 0.0, 5.0, 0, 0, 350
+102.0, 0.0, 0.001, 7.0, 2, -2, 10
+").unwrap();
+
+        assert_eq!(track.duration(), Duration::from_secs(2));
+        let mid = track.sample(Duration::from_secs(1));
+        assert!((mid.longitude.degrees() - 0.0005).abs() < 1e-12);
+        assert!((mid.height.meters() - 6.0).abs() < 1e-9);
+        assert!((mid.velocity[2].meters_per_second() + 1.0).abs() < 1e-9);
+        // Heading interpolates across north rather than through south
+        assert!(mid.heading.radians().cos() > 1.0 - 1e-12);
+        assert!(mid.heading.radians() >= 0.0 && mid.heading.radians() < 2.0 * PI);
+        assert!((mid.roll.degrees() - 1.0).abs() < 1e-9);
+
+        // Held at the last point once the track ends
+        let end = track.sample(Duration::from_secs(10));
+        assert_eq!(end.velocity, [Velocity::ZERO; 3]);
+        assert!((end.heading.degrees() - 10.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_csv_track_errors() {
+        assert!(Track::from_csv("").is_err());
+        assert!(Track::from_csv("time,latitude,longitude\n0,0,0").is_err());
+        assert!(Track::from_csv("time,latitude,longitude,height,roll\n0,0,0,0,0").is_err());
+        assert!(Track::from_csv("time,latitude,longitude,height\n0,0,abc,0").is_err());
+        assert!(Track::from_csv("time,latitude,longitude,height\n1,0,0,0\n0,0,0,0").is_err());
+    }
+
+    #[test]
+    fn test_gpx_track() {
+        let gpx = r#"<?xml version="1.0"?>
+<gpx version="1.1"><trk><trkseg>
+  <trkpt lat="-33.8700" lon="151.2100"><ele>12.0</ele><time>2024-05-01T12:00:00Z</time></trkpt>
+  <trkpt lat="-33.8690" lon='151.2100'><time>2024-05-01T22:00:10+10:00</time></trkpt>
+</trkseg></trk></gpx>"#;
+        let track = Track::from_gpx(gpx).unwrap();
+
+        assert_eq!(track.points().len(), 2);
+        assert_eq!(track.duration(), Duration::from_secs(10));
+        assert_eq!(track.points()[0].height.meters(), 12.0);
+        assert_eq!(track.points()[1].height, Length::ZERO);
+        let point = track.sample(Duration::from_secs(5));
+        assert!(point.heading.degrees().abs() < 1e-9);
+
+        assert!(Track::from_gpx("<gpx></gpx>").is_err());
+        assert!(Track::from_gpx(r#"<trkpt lat="0" lon="0"></trkpt>"#).is_err());
+    }
+
+    #[test]
+    fn test_malformed_gpx_is_an_error() {
+        let time = "<time>2024-05-01T12:00:00Z</time>";
+        for gpx in [
+            // Closing tag inside the opening one
+            "<trkpt</trkpt>".to_string(),
+            format!(r#"<trkpt lat="0" lon="0"{}</trkpt>"#, time),
+            // Year far past any calendar arithmetic
+            r#"<trkpt lat="0" lon="0"><time>999999999999999999-01-01T00:00:00Z</time></trkpt>"#.to_string(),
+            r#"<trkpt lat="0" lon="0"><time>2024-01-01T00:00:00+9223372036854775807:00</time></trkpt>"#.to_string(),
+            // Multibyte quote characters
+            format!("<trkpt lat=é0é lon=\"0\">{}</trkpt>", time),
+            format!("<trkpt lat=\"0\" lon=\u{201C}0\u{201C}>{}</trkpt>", time),
+        ] {
+            assert!(matches!(Track::from_gpx(&gpx), Err(AnError::ValidationFailed(_))), "{}", gpx);
+        }
+    }
+
+    #[test]
+    fn test_parse_iso8601() {
+        assert_eq!(parse_iso8601("1970-01-01T00:00:00Z"), Some(0.0));
+        assert_eq!(parse_iso8601("2023-11-14T22:13:20Z"), Some(1_700_000_000.0));
+        assert_eq!(parse_iso8601("2000-03-01T01:00:00.25+01:00"), Some(951_868_800.25));
+        assert_eq!(parse_iso8601("2024-13-01T00:00:00Z"), None);
+        assert_eq!(parse_iso8601("999999999999999999-01-01T00:00:00Z"), None);
+        assert_eq!(parse_iso8601("2024-01-01T00:00:00+99:00"), None);
+        assert_eq!(parse_iso8601("2024-01-01T170141183460469231731687303715884105727:00:00Z"), None);
+    }
+}
@@ -0,0 +1,452 @@
+//! Latest known state.
+//!
+//! [`TelemetryStore`] keeps the most recent packet of each kind along with
+//! when it arrived, for applications that want "what is the position now"
+//! rather than a callback per packet. Like the rest of the crate it is
+//! sans-io: the caller supplies the receive time and the current time.
+//!
+//! [`DeviceUptime`] follows the Running Time packet to report how long the
+//! device has been up and to detect reboots from the device's own clock.
+
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+use crate::packet::config::FilterOptions;
+use crate::packet::state::{
+    BodyVelocity, EulerOrientation, GeodeticPosition, NedVelocity, RawSensors, RunningTime, Satellites, Status,
+    SystemState, UnixTime, VelocityStdDev,
+};
+use crate::units::Velocity;
+use crate::packet::{HasPacketId, Packet, PacketKind};
+
+/// Default age after which a value counts as stale
+pub const DEFAULT_STALE_AFTER: Duration = Duration::from_secs(1);
+
+/// Default slack between the running time and the host clock before a
+/// shortfall counts as a reboot, covering link latency and clock drift
+pub const DEFAULT_REBOOT_TOLERANCE: Duration = Duration::from_secs(2);
+
+#[derive(Debug, Clone)]
+struct Entry {
+    packet: Packet,
+    received: Instant,
+}
+
+/// Most recent packet of each kind with its receive time
+///
+/// # Examples
+///
+/// ```
+/// use std::time::{Duration, Instant};
+/// use liban::telemetry::TelemetryStore;
+/// use liban::{Packet, Satellites, UnixTime};
+///
+/// let start = Instant::now();
+/// let mut store = TelemetryStore::new();
+/// store.update(Packet::UnixTime(UnixTime { unix_time_seconds: 1, microseconds: 0 }), start);
+///
+/// let now = start + Duration::from_millis(200);
+/// assert_eq!(store.age_of::<UnixTime>(now), Some(Duration::from_millis(200)));
+/// assert_eq!(store.age_of::<Satellites>(now), None);
+/// assert!(store.is_stale::<Satellites>(now));
+/// ```
+#[derive(Debug, Clone)]
+pub struct TelemetryStore {
+    entries: HashMap<PacketKind, Entry>,
+    stale_after: Duration,
+    overrides: HashMap<PacketKind, Duration>,
+    uptime: DeviceUptime,
+}
+
+impl Default for TelemetryStore {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl TelemetryStore {
+    pub fn new() -> Self {
+        Self {
+            entries: HashMap::new(),
+            stale_after: DEFAULT_STALE_AFTER,
+            overrides: HashMap::new(),
+            uptime: DeviceUptime::new(),
+        }
+    }
+
+    /// Age after which values count as stale
+    pub fn with_stale_after(mut self, stale_after: Duration) -> Self {
+        self.stale_after = stale_after;
+        self
+    }
+
+    /// Stale timeout for one packet type, e.g. for a slowly streamed one
+    pub fn with_stale_after_for<T: HasPacketId>(mut self, stale_after: Duration) -> Self {
+        self.overrides.insert(T::PACKET_ID.packet_type(), stale_after);
+        self
+    }
+
+    /// Record a decoded packet received at `received`. Unsupported packets
+    /// are ignored. Returns whether the packet was kept.
+    pub fn update(&mut self, packet: Packet, received: Instant) -> bool {
+        if matches!(packet, Packet::Unsupported { .. }) {
+            return false;
+        }
+        if let Packet::RunningTime(running) = &packet {
+            self.uptime.update(running, received);
+        }
+        let kind = PacketKind::from(packet.packet_id());
+        self.entries.insert(kind, Entry { packet, received });
+        true
+    }
+
+    /// Uptime and reboot history from the Running Time packets seen so far
+    pub fn uptime(&self) -> &DeviceUptime {
+        &self.uptime
+    }
+
+    /// Kinds with a stored packet, ordered by packet ID
+    pub fn kinds(&self) -> Vec<PacketKind> {
+        let mut kinds: Vec<_> = self.entries.keys().copied().collect();
+        kinds.sort_by_key(|k| k.packet_id());
+        kinds
+    }
+
+    /// Latest value of a packet type
+    pub fn get<T: HasPacketId>(&self) -> Option<&T> {
+        self.packet(T::PACKET_ID.packet_type()).and_then(T::from_packet)
+    }
+
+    /// Latest packet of a kind
+    pub fn packet(&self, kind: PacketKind) -> Option<&Packet> {
+        self.entries.get(&kind).map(|e| &e.packet)
+    }
+
+    /// When the latest value of a packet type arrived
+    pub fn received_at<T: HasPacketId>(&self) -> Option<Instant> {
+        self.entries.get(&T::PACKET_ID.packet_type()).map(|e| e.received)
+    }
+
+    /// How old the latest value of a packet type is, `None` if never received
+    pub fn age_of<T: HasPacketId>(&self, now: Instant) -> Option<Duration> {
+        self.age(T::PACKET_ID.packet_type(), now)
+    }
+
+    /// How old the latest packet of a kind is, `None` if never received
+    pub fn age(&self, kind: PacketKind, now: Instant) -> Option<Duration> {
+        self.entries.get(&kind).map(|e| now.saturating_duration_since(e.received))
+    }
+
+    /// Whether a packet type is missing or older than its stale timeout
+    pub fn is_stale<T: HasPacketId>(&self, now: Instant) -> bool {
+        self.is_kind_stale(T::PACKET_ID.packet_type(), now)
+    }
+
+    /// Whether a packet kind is missing or older than its stale timeout
+    pub fn is_kind_stale(&self, kind: PacketKind, now: Instant) -> bool {
+        let limit = self.overrides.get(&kind).copied().unwrap_or(self.stale_after);
+        self.age(kind, now).is_none_or(|age| age > limit)
+    }
+
+    /// Kinds received at some point whose latest value is now stale
+    pub fn stale_kinds(&self, now: Instant) -> Vec<PacketKind> {
+        let mut kinds: Vec<_> = self.entries.keys().copied().filter(|&k| self.is_kind_stale(k, now)).collect();
+        kinds.sort_by_key(PacketKind::packet_id);
+        kinds
+    }
+
+    /// Forget everything, e.g. after reconnecting to a different device
+    pub fn clear(&mut self) {
+        self.entries.clear();
+    }
+
+    pub fn system_state(&self) -> Option<&SystemState> {
+        self.get()
+    }
+
+    pub fn status(&self) -> Option<&Status> {
+        self.get()
+    }
+
+    pub fn unix_time(&self) -> Option<&UnixTime> {
+        self.get()
+    }
+
+    pub fn geodetic_position(&self) -> Option<&GeodeticPosition> {
+        self.get()
+    }
+
+    pub fn ned_velocity(&self) -> Option<&NedVelocity> {
+        self.get()
+    }
+
+    pub fn velocity_std_dev(&self) -> Option<&VelocityStdDev> {
+        self.get()
+    }
+
+    pub fn body_velocity(&self) -> Option<&BodyVelocity> {
+        self.get()
+    }
+
+    pub fn euler_orientation(&self) -> Option<&EulerOrientation> {
+        self.get()
+    }
+
+    pub fn raw_sensors(&self) -> Option<&RawSensors> {
+        self.get()
+    }
+
+    pub fn satellites(&self) -> Option<&Satellites> {
+        self.get()
+    }
+
+    pub fn filter_options(&self) -> Option<&FilterOptions> {
+        self.get()
+    }
+
+    /// NED velocity bundled with its standard deviation, once both packets
+    /// have arrived. Body velocity is included when it is streamed too.
+    pub fn velocity_estimate(&self) -> Option<VelocityEstimate> {
+        let ned = self.entries.get(&PacketKind::NedVelocity)?;
+        let std_dev = self.entries.get(&PacketKind::VelocityStdDev)?;
+        Some(VelocityEstimate {
+            ned: NedVelocity::from_packet(&ned.packet)?.clone(),
+            std_dev: VelocityStdDev::from_packet(&std_dev.packet)?.clone(),
+            body: self.get::<BodyVelocity>().cloned(),
+            received: ned.received.min(std_dev.received),
+        })
+    }
+}
+
+/// Velocity with its uncertainty, from [`TelemetryStore::velocity_estimate`]
+#[derive(Debug, Clone, PartialEq)]
+pub struct VelocityEstimate {
+    pub ned: NedVelocity,
+    pub std_dev: VelocityStdDev,
+    pub body: Option<BodyVelocity>,
+    /// Receive time of the older of the velocity and standard deviation
+    pub received: Instant,
+}
+
+impl VelocityEstimate {
+    pub fn ground_speed(&self) -> Velocity {
+        self.ned.ground_speed()
+    }
+
+    /// Whether the horizontal standard deviation is at most `limit`
+    pub fn is_within(&self, limit: Velocity) -> bool {
+        self.std_dev.horizontal().meters_per_second() <= limit.meters_per_second()
+    }
+}
+
+/// A reboot detected from the Running Time packet
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Reboot {
+    /// When the packet showing the reboot was received
+    pub detected: Instant,
+    /// Last running time seen before the reboot
+    pub previous_uptime: Duration,
+    /// Running time reported after the reboot
+    pub uptime: Duration,
+}
+
+/// Device uptime and reboot detection from Running Time packets.
+///
+/// The device's running time should advance with the host clock between
+/// packets. When it falls short by more than the tolerance, the device has
+/// restarted, even if the link stayed up or the reboot happened during a
+/// gap long enough for the running time to pass its old value.
+///
+/// ```
+/// use std::time::{Duration, Instant};
+/// use liban::telemetry::DeviceUptime;
+/// use liban::RunningTime;
+///
+/// let start = Instant::now();
+/// let mut uptime = DeviceUptime::new();
+/// assert!(uptime.update(&RunningTime { seconds: 600, microseconds: 0 }, start).is_none());
+/// let reboot = uptime.update(&RunningTime { seconds: 3, microseconds: 0 }, start + Duration::from_secs(5));
+/// assert!(reboot.is_some());
+/// assert_eq!(uptime.reboots(), 1);
+/// ```
+#[derive(Debug, Clone)]
+pub struct DeviceUptime {
+    tolerance: Duration,
+    last: Option<(Duration, Instant)>,
+    reboots: u64,
+    last_reboot: Option<Reboot>,
+}
+
+impl Default for DeviceUptime {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl DeviceUptime {
+    pub fn new() -> Self {
+        Self { tolerance: DEFAULT_REBOOT_TOLERANCE, last: None, reboots: 0, last_reboot: None }
+    }
+
+    /// Shortfall allowed before a running time counts as a reboot
+    pub fn with_tolerance(mut self, tolerance: Duration) -> Self {
+        self.tolerance = tolerance;
+        self
+    }
+
+    /// Record a Running Time packet received at `received`. Returns the
+    /// reboot if the device restarted since the previous packet.
+    pub fn update(&mut self, running: &RunningTime, received: Instant) -> Option<Reboot> {
+        let uptime = running.duration();
+        let reboot = self.last.and_then(|(previous, at)| {
+            let expected = previous + received.saturating_duration_since(at);
+            (uptime + self.tolerance < expected).then_some(Reboot { detected: received, previous_uptime: previous, uptime })
+        });
+        if reboot.is_some() {
+            self.reboots += 1;
+            self.last_reboot = reboot;
+        }
+        self.last = Some((uptime, received));
+        reboot
+    }
+
+    /// Device uptime at `now`, extrapolated from the latest packet
+    pub fn uptime(&self, now: Instant) -> Option<Duration> {
+        self.last.map(|(uptime, at)| uptime + now.saturating_duration_since(at))
+    }
+
+    /// Reboots detected since this tracker was created
+    pub fn reboots(&self) -> u64 {
+        self.reboots
+    }
+
+    pub fn last_reboot(&self) -> Option<&Reboot> {
+        self.last_reboot.as_ref()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Payload;
+    use crate::packet::state::ExternalTime;
+    use crate::packet::system::Request;
+
+    fn unix_time(seconds: u32) -> Packet {
+        Packet::UnixTime(UnixTime { unix_time_seconds: seconds, microseconds: 0 })
+    }
+
+    fn running(seconds: u32, microseconds: u32) -> RunningTime {
+        RunningTime { seconds, microseconds }
+    }
+
+    #[test]
+    fn test_uptime_tracks_running_time() {
+        let start = Instant::now();
+        let mut uptime = DeviceUptime::new();
+        assert_eq!(uptime.uptime(start), None);
+
+        assert!(uptime.update(&running(100, 500_000), start).is_none());
+        assert!(uptime.update(&running(101, 400_000), start + Duration::from_secs(1)).is_none());
+        assert_eq!(uptime.uptime(start + Duration::from_secs(3)), Some(Duration::from_millis(103_400)));
+        assert_eq!(uptime.reboots(), 0);
+    }
+
+    #[test]
+    fn test_uptime_detects_reboots() {
+        let start = Instant::now();
+        let mut uptime = DeviceUptime::new();
+        uptime.update(&running(100, 0), start);
+
+        // Backwards
+        let reboot = uptime.update(&running(2, 0), start + Duration::from_secs(1)).unwrap();
+        assert_eq!(reboot.previous_uptime, Duration::from_secs(100));
+        assert_eq!(reboot.uptime, Duration::from_secs(2));
+
+        // Forwards, but by less than the host clock moved during a gap
+        let later = start + Duration::from_secs(120);
+        assert!(uptime.update(&running(30, 0), later).is_some());
+        assert_eq!(uptime.reboots(), 2);
+        assert_eq!(uptime.last_reboot().unwrap().detected, later);
+
+        // Latency within the tolerance is not a reboot
+        assert!(uptime.update(&running(30, 500_000), later + Duration::from_secs(2)).is_none());
+    }
+
+    #[test]
+    fn test_store_feeds_uptime() {
+        let start = Instant::now();
+        let mut store = TelemetryStore::new();
+        store.update(Packet::RunningTime(running(50, 0)), start);
+        store.update(Packet::RunningTime(running(1, 0)), start + Duration::from_secs(1));
+        assert_eq!(store.uptime().reboots(), 1);
+        assert_eq!(store.get::<RunningTime>().unwrap().seconds, 1);
+    }
+
+    #[test]
+    fn test_velocity_estimate_needs_both_packets() {
+        let start = Instant::now();
+        let mut store = TelemetryStore::new();
+        store.update(Packet::NedVelocity(NedVelocity { velocity_north: 3.0, velocity_east: 4.0, velocity_down: 0.1 }), start);
+        assert!(store.velocity_estimate().is_none());
+
+        let later = start + Duration::from_millis(10);
+        store.update(Packet::VelocityStdDev(VelocityStdDev {
+            velocity_north_std_dev: 0.03,
+            velocity_east_std_dev: 0.04,
+            velocity_down_std_dev: 0.1,
+        }), later);
+        let estimate = store.velocity_estimate().unwrap();
+        assert_eq!(estimate.received, start);
+        assert!(estimate.body.is_none());
+        assert!((estimate.ground_speed().meters_per_second() - 5.0).abs() < 1e-6);
+        assert!((estimate.ned.course_over_ground().degrees() - 53.130).abs() < 1e-3);
+        assert!(estimate.is_within(Velocity::from_meters_per_second(0.05)));
+        assert!(!estimate.is_within(Velocity::from_meters_per_second(0.04)));
+
+        store.update(Packet::BodyVelocity(BodyVelocity { velocity_x: 5.0, velocity_y: 0.0, velocity_z: 0.1 }), later);
+        let body = store.velocity_estimate().unwrap().body.unwrap();
+        assert_eq!(body.velocity_body()[0], Velocity::from_meters_per_second(5.0));
+    }
+
+    #[test]
+    fn test_keeps_latest_value() {
+        let start = Instant::now();
+        let mut store = TelemetryStore::new();
+        assert!(store.unix_time().is_none());
+
+        store.update(unix_time(1), start);
+        store.update(unix_time(2), start + Duration::from_millis(100));
+        assert_eq!(store.unix_time().unwrap().unix_time_seconds, 2);
+        assert_eq!(store.received_at::<UnixTime>(), Some(start + Duration::from_millis(100)));
+        assert!(store.get::<ExternalTime>().is_none());
+
+        assert!(store.update(Packet::Request(Request::single(PacketKind::Status)), start));
+        assert!(!store.update(Packet::Unsupported { id: 250, payload: Payload::from_slice(&[1, 2]).unwrap() }, start));
+    }
+
+    #[test]
+    fn test_staleness() {
+        let start = Instant::now();
+        let mut store = TelemetryStore::new()
+            .with_stale_after(Duration::from_millis(500))
+            .with_stale_after_for::<Satellites>(Duration::from_secs(5));
+        store.update(unix_time(1), start);
+        store.update(Packet::Satellites(Satellites {
+            hdop: 1.0, vdop: 1.0, gps_satellites: 8, glonass_satellites: 0,
+            beidou_satellites: 0, galileo_satellites: 0, sbas_satellites: 0,
+        }), start);
+
+        let now = start + Duration::from_secs(1);
+        assert!(store.is_stale::<UnixTime>(now));
+        assert!(!store.is_stale::<Satellites>(now));
+        assert!(store.is_stale::<SystemState>(now));
+        assert_eq!(store.stale_kinds(now), vec![PacketKind::UnixTime]);
+
+        // A receive time after `now` counts as fresh, not as a panic
+        assert_eq!(store.age_of::<UnixTime>(start - Duration::from_millis(1)), Some(Duration::ZERO));
+
+        store.clear();
+        assert!(store.age_of::<UnixTime>(now).is_none());
+    }
+}
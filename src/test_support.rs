@@ -0,0 +1,460 @@
+//! Random packet generators and round-trip asserts for downstream tests.
+//!
+//! Crates that wrap liban types in their own serialization layers can use
+//! these to check that a value survives their encoding the same way it
+//! survives ours:
+//!
+//! ```
+//! use liban::test_support::{assert_roundtrip, random, Rng};
+//! use liban::SystemState;
+//!
+//! assert_roundtrip::<SystemState>();
+//!
+//! let mut rng = Rng::new(7);
+//! let state: SystemState = random(&mut rng);
+//! assert_eq!(state, state.clone());
+//! ```
+//!
+//! Instances are produced by decoding random payloads, so every value is one
+//! a device could send. Values with NaN floats are skipped so instances
+//! compare equal to themselves.
+//!
+//! [`GoldenSet`] checks recorded byte captures against expected decodes:
+//! each capture must parse without errors, match its expected packets and
+//! re-encode to the same bytes. Captures are `<name>.anpp` files with the
+//! expected packets next to them in `<name>.txt`, one `Debug` line per
+//! packet. Register a directory of your own captures, or captures built in
+//! code, and run [`GoldenSet::assert_ok`] from a test:
+//!
+//! ```no_run
+//! use liban::test_support::GoldenSet;
+//!
+//! GoldenSet::new()
+//!     .add_dir(concat!(env!("CARGO_MANIFEST_DIR"), "/tests/data"))
+//!     .unwrap()
+//!     .assert_ok();
+//! ```
+//!
+//! Run with `LIBAN_BLESS=1` to write the `.txt` files for new captures, or
+//! after an intended decoding change, instead of failing.
+//!
+//! [`system_state`] is a baseline [`SystemState`] for tests that only care
+//! about a few of its fields.
+
+use std::fmt::{self, Debug, Display};
+use std::fs;
+use std::io::{self, Cursor};
+use std::path::{Path, PathBuf};
+
+use binrw::{BinRead, BinWrite};
+
+use crate::packet::config::BaudRate;
+use crate::packet::state::{FilterStatus, SystemState, SystemStatus};
+use crate::packet::system::Subcomponent;
+use crate::packet::{HasPacketId, Packet, PacketKind};
+use crate::parser::{AnppParser, ParserStats};
+use crate::units::Radians;
+use crate::wire::{self, PacketId};
+
+/// Largest payload generated for variable-length packets
+pub const MAX_VARIABLE_LEN: usize = 64;
+
+/// Instances checked by each round-trip assert
+pub const ROUNDTRIP_CASES: usize = 256;
+
+/// Attempts at decoding a random payload before giving up on a kind
+const MAX_ATTEMPTS: usize = 10_000;
+
+/// Small deterministic generator (xorshift64*), so failures reproduce from
+/// the seed alone
+#[derive(Debug, Clone)]
+pub struct Rng(u64);
+
+impl Rng {
+    pub fn new(seed: u64) -> Self {
+        // Zero is a fixed point of xorshift
+        Self(seed.max(1))
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.0 ^= self.0 >> 12;
+        self.0 ^= self.0 << 25;
+        self.0 ^= self.0 >> 27;
+        self.0.wrapping_mul(0x2545_f491_4f6c_dd1d)
+    }
+
+    /// Uniform value in `0..bound`
+    pub fn below(&mut self, bound: usize) -> usize {
+        (self.next_u64() % bound as u64) as usize
+    }
+
+    pub fn fill(&mut self, bytes: &mut [u8]) {
+        for byte in bytes {
+            *byte = self.next_u64() as u8;
+        }
+    }
+
+    /// Fill with bytes biased towards zero and small values, so enum codes
+    /// and flags hit their defined values often
+    pub fn fill_biased(&mut self, bytes: &mut [u8]) {
+        for byte in bytes {
+            let r = self.next_u64();
+            *byte = match r % 4 {
+                0 | 1 => 0,
+                2 => (r >> 8) as u8 % 16,
+                _ => (r >> 8) as u8,
+            };
+        }
+    }
+}
+
+/// Random payload of the right length for `kind`. Bytes are biased towards
+/// small values; baud rates, which the decoder validates against a fixed
+/// list, are picked from that list.
+pub fn random_payload(kind: PacketKind, rng: &mut Rng) -> Vec<u8> {
+    let mut len = kind.byte_length().unwrap_or_else(|| rng.below(MAX_VARIABLE_LEN + 1));
+    if kind == PacketKind::SubcomponentInformation {
+        len -= len % Subcomponent::LEN;
+    }
+    let mut payload = vec![0; len];
+    rng.fill_biased(&mut payload);
+
+    if kind == PacketKind::BaudRates {
+        for offset in [1, 5, 9] {
+            let rate = BaudRate::ALL[rng.below(BaudRate::ALL.len())];
+            payload[offset..offset + 4].copy_from_slice(&rate.bits_per_second().to_le_bytes());
+        }
+    }
+    payload
+}
+
+/// Random packet of the given kind
+pub fn random_packet(kind: PacketKind, rng: &mut Rng) -> Packet {
+    for _ in 0..MAX_ATTEMPTS {
+        if let Ok(packet) = wire::decode_payload(kind.packet_id(), &random_payload(kind, rng)) {
+            #[allow(clippy::eq_op)]
+            if packet == packet {
+                return packet;
+            }
+        }
+    }
+    panic!("no valid random {:?} after {} attempts", kind, MAX_ATTEMPTS)
+}
+
+/// Random instance of a packet struct
+pub fn random<T>(rng: &mut Rng) -> T
+where
+    T: HasPacketId + BinRead + PartialEq,
+    for<'a> <T as BinRead>::Args<'a>: Default,
+{
+    let kind = T::PACKET_ID.packet_type();
+    for _ in 0..MAX_ATTEMPTS {
+        let mut cursor = Cursor::new(random_payload(kind, rng));
+        if let Ok(value) = T::read_le_args(&mut cursor, Default::default()) {
+            #[allow(clippy::eq_op)]
+            if value == value {
+                return value;
+            }
+        }
+    }
+    panic!("no valid random {:?} after {} attempts", kind, MAX_ATTEMPTS)
+}
+
+/// Check that random instances of `T` encode to the documented length and
+/// decode back to an equal value
+pub fn assert_roundtrip<T>()
+where
+    T: HasPacketId + BinRead + BinWrite + PartialEq + Debug,
+    for<'a> <T as BinRead>::Args<'a>: Default,
+    for<'a> <T as BinWrite>::Args<'a>: Default,
+{
+    let kind = T::PACKET_ID.packet_type();
+    let mut rng = Rng::new(u64::from(kind.packet_id()) + 1);
+
+    for _ in 0..ROUNDTRIP_CASES {
+        let value: T = random(&mut rng);
+        let mut cursor = Cursor::new(Vec::new());
+        value.write_le_args(&mut cursor, Default::default())
+            .unwrap_or_else(|e| panic!("failed to encode {:?}: {}", value, e));
+        let bytes = cursor.into_inner();
+
+        if let Some(expected) = kind.byte_length() {
+            assert_eq!(bytes.len(), expected, "{:?} encoded to the wrong length", value);
+        }
+        let decoded = T::read_le_args(&mut Cursor::new(&bytes), Default::default())
+            .unwrap_or_else(|e| panic!("failed to decode {:?}: {}", value, e));
+        assert_eq!(decoded, value);
+    }
+}
+
+/// [`assert_roundtrip`] for every packet kind, through [`Packet`]
+pub fn assert_all_roundtrip() {
+    let mut rng = Rng::new(1);
+    for kind in PacketKind::all() {
+        for _ in 0..ROUNDTRIP_CASES {
+            let packet = random_packet(kind, &mut rng);
+            let bytes = wire::encode_payload(&packet).unwrap();
+            let decoded = wire::decode_payload(kind.packet_id(), &bytes)
+                .unwrap_or_else(|e| panic!("failed to decode {:?}: {}", packet, e));
+            assert_eq!(decoded, packet);
+        }
+    }
+}
+
+/// A stationary, level [`SystemState`] at 0°N 0°E at the Unix epoch, with
+/// status flags clear and 1 g on the accelerometers. Tests override the
+/// fields they care about:
+///
+/// ```
+/// use liban::SystemState;
+/// use liban::test_support::system_state;
+///
+/// let state = SystemState { height: 12.5, ..system_state() };
+/// assert_eq!(state.g_force, 1.0);
+/// ```
+pub fn system_state() -> SystemState {
+    SystemState {
+        system_status: SystemStatus::default(),
+        filter_status: FilterStatus::default(),
+        unix_time_seconds: 0,
+        microseconds: 0,
+        latitude: 0.0,
+        longitude: 0.0,
+        height: 0.0,
+        velocity_north: 0.0,
+        velocity_east: 0.0,
+        velocity_down: 0.0,
+        body_acceleration_x: 0.0,
+        body_acceleration_y: 0.0,
+        body_acceleration_z: 0.0,
+        g_force: 1.0,
+        roll: Radians(0.0),
+        pitch: Radians(0.0),
+        heading: Radians(0.0),
+        angular_velocity_x: 0.0,
+        angular_velocity_y: 0.0,
+        angular_velocity_z: 0.0,
+        latitude_std_dev: 0.0,
+        longitude_std_dev: 0.0,
+        height_std_dev: 0.0,
+        extension: None,
+    }
+}
+
+/// Environment variable that makes [`GoldenSet::assert_ok`] rewrite
+/// expected files instead of failing on a mismatch
+pub const BLESS_ENV: &str = "LIBAN_BLESS";
+
+/// File extension of recorded captures
+pub const CAPTURE_EXTENSION: &str = "anpp";
+
+/// File extension of expected decodes
+pub const EXPECTED_EXTENSION: &str = "txt";
+
+/// Expected-file text for a packet sequence, one `Debug` line per packet
+pub fn render(packets: &[Packet]) -> String {
+    packets.iter().map(|packet| format!("{:?}\n", packet)).collect()
+}
+
+/// Why a capture did not match
+#[derive(Debug, Clone, PartialEq)]
+pub enum GoldenFailure {
+    /// The parser dropped bytes or rejected frames
+    Corrupt { discarded: u64, crc_errors: u64, payload_errors: u64 },
+    /// The decoded packets differ from the expected file
+    Mismatch { line: usize, expected: Option<String>, actual: Option<String> },
+    /// Re-encoding the decoded packets gave different bytes
+    Reencode { offset: usize },
+}
+
+impl Display for GoldenFailure {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Corrupt { discarded, crc_errors, payload_errors } => write!(f,
+                "{} bytes discarded, {} CRC errors, {} payload errors", discarded, crc_errors, payload_errors),
+            Self::Mismatch { line, expected, actual } => write!(f,
+                "line {}: expected {}, decoded {}", line,
+                expected.as_deref().unwrap_or("end of file"), actual.as_deref().unwrap_or("nothing")),
+            Self::Reencode { offset } => write!(f, "re-encoded bytes differ from offset {}", offset),
+        }
+    }
+}
+
+/// A byte capture and the packets it should decode to
+#[derive(Debug, Clone, PartialEq)]
+pub struct GoldenCapture {
+    pub name: String,
+    pub bytes: Vec<u8>,
+    /// Expected decode in [`render`] format
+    pub expected: String,
+    /// Where the expected decode is stored, if it came from a file
+    pub expected_path: Option<PathBuf>,
+}
+
+impl GoldenCapture {
+    pub fn new(name: impl Into<String>, bytes: Vec<u8>, expected: &[Packet]) -> Self {
+        Self { name: name.into(), bytes, expected: render(expected), expected_path: None }
+    }
+
+    /// Load a `.anpp` capture and its `.txt` expected decode. A missing
+    /// expected file reads as empty, so new captures fail until blessed.
+    pub fn load(path: impl AsRef<Path>) -> io::Result<Self> {
+        let path = path.as_ref();
+        let expected_path = path.with_extension(EXPECTED_EXTENSION);
+        let expected = match fs::read_to_string(&expected_path) {
+            Ok(text) => text,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => String::new(),
+            Err(e) => return Err(e),
+        };
+        Ok(Self {
+            name: path.file_stem().unwrap_or_default().to_string_lossy().into_owned(),
+            bytes: fs::read(path)?,
+            expected,
+            expected_path: Some(expected_path),
+        })
+    }
+
+    fn parse(&self) -> (Vec<Packet>, ParserStats) {
+        let mut parser = AnppParser::new();
+        let mut packets = Vec::new();
+        let mut next = parser.consume(&self.bytes);
+        while let Some(packet) = next {
+            packets.push(packet);
+            next = parser.consume(&[]);
+        }
+        (packets, parser.stats().clone())
+    }
+
+    /// Packets in the capture
+    pub fn decode(&self) -> Vec<Packet> {
+        self.parse().0
+    }
+
+    pub fn check(&self) -> Result<(), GoldenFailure> {
+        let (packets, stats) = self.parse();
+        if stats.bytes_discarded > 0 || stats.crc_errors > 0 || stats.payload_errors > 0 {
+            return Err(GoldenFailure::Corrupt {
+                discarded: stats.bytes_discarded,
+                crc_errors: stats.crc_errors,
+                payload_errors: stats.payload_errors,
+            });
+        }
+
+        let actual = render(&packets);
+        let (mut expected_lines, mut actual_lines) = (self.expected.lines(), actual.lines());
+        for line in 1.. {
+            match (expected_lines.next(), actual_lines.next()) {
+                (None, None) => break,
+                (e, a) if e == a => {}
+                (e, a) => return Err(GoldenFailure::Mismatch {
+                    line,
+                    expected: e.map(str::to_string),
+                    actual: a.map(str::to_string),
+                }),
+            }
+        }
+
+        let mut encoded = Vec::with_capacity(self.bytes.len());
+        for packet in &packets {
+            let payload = wire::encode_payload(packet).map_err(|_| GoldenFailure::Reencode { offset: encoded.len() })?;
+            let frame = wire::encode_frame(PacketId::new(packet.packet_id()), &payload)
+                .map_err(|_| GoldenFailure::Reencode { offset: encoded.len() })?;
+            encoded.extend_from_slice(&frame);
+        }
+        if encoded != self.bytes {
+            let offset = encoded.iter().zip(&self.bytes).take_while(|(a, b)| a == b).count();
+            return Err(GoldenFailure::Reencode { offset });
+        }
+        Ok(())
+    }
+}
+
+/// Captures checked together
+#[derive(Debug, Clone, Default)]
+pub struct GoldenSet {
+    captures: Vec<GoldenCapture>,
+}
+
+impl GoldenSet {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn add(&mut self, capture: GoldenCapture) -> &mut Self {
+        self.captures.push(capture);
+        self
+    }
+
+    /// Add every `.anpp` capture in `dir`, in file name order
+    pub fn add_dir(&mut self, dir: impl AsRef<Path>) -> io::Result<&mut Self> {
+        let mut paths = Vec::new();
+        for entry in fs::read_dir(dir)? {
+            let path = entry?.path();
+            if path.extension().is_some_and(|ext| ext == CAPTURE_EXTENSION) {
+                paths.push(path);
+            }
+        }
+        paths.sort();
+        for path in paths {
+            self.captures.push(GoldenCapture::load(path)?);
+        }
+        Ok(self)
+    }
+
+    pub fn captures(&self) -> &[GoldenCapture] {
+        &self.captures
+    }
+
+    /// Failing captures by name
+    pub fn check(&self) -> Vec<(String, GoldenFailure)> {
+        self.captures.iter()
+            .filter_map(|capture| capture.check().err().map(|failure| (capture.name.clone(), failure)))
+            .collect()
+    }
+
+    /// Panic listing every failing capture. With [`BLESS_ENV`] set,
+    /// mismatching expected files are rewritten instead.
+    pub fn assert_ok(&self) {
+        assert!(!self.captures.is_empty(), "no golden captures registered");
+        let bless = std::env::var_os(BLESS_ENV).is_some();
+        let mut failures = Vec::new();
+        for capture in &self.captures {
+            match (capture.check(), &capture.expected_path) {
+                (Ok(()), _) => {}
+                (Err(GoldenFailure::Mismatch { .. }), Some(path)) if bless => {
+                    fs::write(path, render(&capture.decode()))
+                        .unwrap_or_else(|e| panic!("failed to bless {}: {}", path.display(), e));
+                }
+                (Err(failure), _) => failures.push(format!("{}: {}", capture.name, failure)),
+            }
+        }
+        assert!(failures.is_empty(), "golden captures failed:\n{}", failures.join("\n"));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{BaudRates, PacketsPeriod, Request, SystemState};
+
+    #[test]
+    fn test_rng_is_deterministic() {
+        let (mut a, mut b) = (Rng::new(42), Rng::new(42));
+        assert_eq!((0..8).map(|_| a.next_u64()).collect::<Vec<_>>(),
+                   (0..8).map(|_| b.next_u64()).collect::<Vec<_>>());
+        assert_ne!(Rng::new(0).next_u64(), 0);
+    }
+
+    #[test]
+    fn test_typed_roundtrips() {
+        assert_roundtrip::<SystemState>();
+        assert_roundtrip::<BaudRates>();
+        assert_roundtrip::<Request>();
+        assert_roundtrip::<PacketsPeriod>();
+    }
+
+    #[test]
+    fn test_all_kinds_roundtrip() {
+        assert_all_roundtrip();
+    }
+}
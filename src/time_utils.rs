@@ -0,0 +1,190 @@
+//! GPS time and leap seconds.
+//!
+//! The device reports UTC as Unix time, while raw GNSS from other
+//! receivers is usually stamped in GPS time: weeks and time of week since
+//! 1980-01-06, without leap seconds. [`LeapSecondTable`] holds the GPS-UTC
+//! offset history and converts between the two; the built-in table covers
+//! every leap second up to 2017-01-01 and can be extended when the IERS
+//! announces a new one.
+
+use std::borrow::Cow;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use crate::error::AnError;
+
+/// Unix time of the GPS epoch, 1980-01-06 00:00:00 UTC
+pub const GPS_EPOCH_UNIX_SECONDS: u64 = 315_964_800;
+
+pub const SECONDS_PER_WEEK: u64 = 604_800;
+
+/// Leap seconds as (Unix time they take effect, GPS-UTC from then on)
+const BUILTIN: &[(u64, i32)] = &[
+    (362_793_600, 1),
+    (394_329_600, 2),
+    (425_865_600, 3),
+    (489_024_000, 4),
+    (567_993_600, 5),
+    (631_152_000, 6),
+    (662_688_000, 7),
+    (709_948_800, 8),
+    (741_484_800, 9),
+    (773_020_800, 10),
+    (820_454_400, 11),
+    (867_715_200, 12),
+    (915_148_800, 13),
+    (1_136_073_600, 14),
+    (1_230_768_000, 15),
+    (1_341_100_800, 16),
+    (1_435_708_800, 17),
+    (1_483_228_800, 18),
+];
+
+/// GPS week and time of week
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct GpsTime {
+    week: u32,
+    tow: Duration,
+}
+
+impl GpsTime {
+    /// `week` is the full week count since the GPS epoch, not the 10-bit
+    /// broadcast value. Time of week must be under one week.
+    pub fn new(week: u32, tow: Duration) -> Result<Self, AnError> {
+        if tow >= Duration::from_secs(SECONDS_PER_WEEK) {
+            return Err(AnError::ValidationFailed(format!("GPS time of week {tow:?} is not under one week")));
+        }
+        Ok(Self { week, tow })
+    }
+
+    /// Time since the GPS epoch
+    pub fn from_elapsed(elapsed: Duration) -> Self {
+        let week = elapsed.as_secs() / SECONDS_PER_WEEK;
+        Self { week: week as u32, tow: elapsed - Duration::from_secs(week * SECONDS_PER_WEEK) }
+    }
+
+    pub fn week(&self) -> u32 {
+        self.week
+    }
+
+    /// Time of week
+    pub fn tow(&self) -> Duration {
+        self.tow
+    }
+
+    /// Time since the GPS epoch
+    pub fn elapsed(&self) -> Duration {
+        Duration::from_secs(self.week as u64 * SECONDS_PER_WEEK) + self.tow
+    }
+}
+
+/// History of the GPS-UTC offset
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LeapSecondTable {
+    entries: Cow<'static, [(u64, i32)]>,
+}
+
+impl Default for LeapSecondTable {
+    fn default() -> Self {
+        Self::builtin()
+    }
+}
+
+impl LeapSecondTable {
+    /// Leap seconds up to 2017-01-01, GPS-UTC 18 s
+    pub fn builtin() -> Self {
+        Self { entries: Cow::Borrowed(BUILTIN) }
+    }
+
+    /// Add or replace the offset taking effect at `unix_seconds`
+    pub fn with_leap_second(mut self, unix_seconds: u64, gps_minus_utc: i32) -> Self {
+        let entries = self.entries.to_mut();
+        match entries.binary_search_by_key(&unix_seconds, |&(at, _)| at) {
+            Ok(i) => entries[i].1 = gps_minus_utc,
+            Err(i) => entries.insert(i, (unix_seconds, gps_minus_utc)),
+        }
+        self
+    }
+
+    /// GPS-UTC in seconds at a Unix time
+    pub fn gps_minus_utc(&self, unix_seconds: u64) -> i32 {
+        let i = self.entries.partition_point(|&(at, _)| at <= unix_seconds);
+        i.checked_sub(1).map_or(0, |i| self.entries[i].1)
+    }
+
+    /// GPS time of a UTC time, `None` before the GPS epoch
+    pub fn unix_to_gps(&self, time: SystemTime) -> Option<GpsTime> {
+        let unix = time.duration_since(UNIX_EPOCH).ok()?;
+        let shifted = shift(unix, self.gps_minus_utc(unix.as_secs()) as i64)?;
+        shifted.checked_sub(Duration::from_secs(GPS_EPOCH_UNIX_SECONDS)).map(GpsTime::from_elapsed)
+    }
+
+    /// UTC time of a GPS time. During an inserted leap second, which Unix
+    /// time cannot represent, this repeats the following second.
+    pub fn gps_to_unix(&self, gps: GpsTime) -> SystemTime {
+        let elapsed = gps.elapsed() + Duration::from_secs(GPS_EPOCH_UNIX_SECONDS);
+        // The offset depends on the UTC time being solved for; the offset at
+        // the GPS time itself is at most one leap second ahead
+        let unix = shift(elapsed, -(self.gps_minus_utc(elapsed.as_secs()) as i64)).unwrap_or(Duration::ZERO);
+        UNIX_EPOCH + shift(elapsed, -(self.gps_minus_utc(unix.as_secs()) as i64)).unwrap_or(Duration::ZERO)
+    }
+}
+
+fn shift(duration: Duration, seconds: i64) -> Option<Duration> {
+    if seconds >= 0 {
+        duration.checked_add(Duration::from_secs(seconds as u64))
+    } else {
+        duration.checked_sub(Duration::from_secs(seconds.unsigned_abs()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn unix(seconds: u64) -> SystemTime {
+        UNIX_EPOCH + Duration::from_secs(seconds)
+    }
+
+    #[test]
+    fn test_gps_epoch_and_offsets() {
+        let table = LeapSecondTable::builtin();
+        assert_eq!(table.unix_to_gps(unix(GPS_EPOCH_UNIX_SECONDS)), Some(GpsTime::new(0, Duration::ZERO).unwrap()));
+        assert_eq!(table.unix_to_gps(unix(GPS_EPOCH_UNIX_SECONDS - 1)), None);
+        assert_eq!(table.gps_minus_utc(1_483_228_799), 17);
+        assert_eq!(table.gps_minus_utc(1_483_228_800), 18);
+        assert_eq!(table.gps_minus_utc(0), 0);
+    }
+
+    #[test]
+    fn test_week_and_tow() {
+        // 2024-01-01 00:00:00 UTC is GPS week 2295, Monday 00:00:18
+        let table = LeapSecondTable::default();
+        let gps = table.unix_to_gps(unix(1_704_067_200) + Duration::from_micros(250)).unwrap();
+        assert_eq!(gps.week(), 2295);
+        assert_eq!(gps.tow(), Duration::from_secs(86_418) + Duration::from_micros(250));
+        assert_eq!(table.gps_to_unix(gps), unix(1_704_067_200) + Duration::from_micros(250));
+        assert_eq!(GpsTime::from_elapsed(gps.elapsed()), gps);
+
+        assert!(GpsTime::new(1, Duration::from_secs(SECONDS_PER_WEEK)).is_err());
+    }
+
+    #[test]
+    fn test_round_trip_across_leap_second() {
+        let table = LeapSecondTable::builtin();
+        for seconds in [1_483_228_798, 1_483_228_799, 1_483_228_800, 1_483_228_801] {
+            let gps = table.unix_to_gps(unix(seconds)).unwrap();
+            assert_eq!(table.gps_to_unix(gps), unix(seconds), "{seconds}");
+        }
+    }
+
+    #[test]
+    fn test_custom_leap_second() {
+        let table = LeapSecondTable::builtin().with_leap_second(2_000_000_000, 19);
+        assert_eq!(table.gps_minus_utc(2_000_000_000), 19);
+        assert_eq!(table.gps_minus_utc(1_999_999_999), 18);
+        let gps = table.unix_to_gps(unix(2_000_000_100)).unwrap();
+        assert_eq!(table.gps_to_unix(gps), unix(2_000_000_100));
+        assert_ne!(table, LeapSecondTable::builtin());
+        assert_eq!(LeapSecondTable::builtin().with_leap_second(1_483_228_800, 18), LeapSecondTable::builtin());
+    }
+}
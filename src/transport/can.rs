@@ -0,0 +1,280 @@
+//! ANPP over CAN, sans-io.
+//!
+//! The framing here is specific to liban, not a scheme from Advanced
+//! Navigation's reference manual, which defines ANPP over serial and
+//! network byte streams and gives no CAN mapping for it. Both ends of the
+//! bus must use this crate's framing, e.g. a host talking to a
+//! serial-to-CAN bridge it controls.
+//!
+//! An encoded ANPP frame is a self-delimiting byte stream: the header
+//! carries the payload length and both header and payload are checked. On
+//! CAN the stream is cut into consecutive CAN data fields under one
+//! identifier, in order, with no extra framing, and the receiver feeds the
+//! data fields of each identifier to its own [`AnppParser`]. A lost CAN
+//! frame fails the ANPP CRC and the parser resynchronises on the next
+//! header, exactly as on a noisy serial line.
+//!
+//! CAN FD data fields are only legal at certain lengths (up to 8, then 12,
+//! 16, 20, 24, 32, 48 or 64 bytes). [`CanEncoder`] always cuts at legal
+//! lengths, so no padding is ever inserted into the stream.
+//!
+//! Reading and writing the bus is left to the caller, e.g. SocketCAN.
+//!
+//! ```
+//! use liban::transport::can::{CanDecoder, CanEncoder, CanMode};
+//! use liban::{Packet, PacketKind, Request};
+//!
+//! let request = Packet::Request(Request::single(PacketKind::SystemState));
+//! let frames = CanEncoder::standard(0x100, CanMode::Classic).unwrap().encode(&request).unwrap();
+//! assert!(frames.iter().all(|frame| frame.data.len() <= 8));
+//!
+//! let mut decoder = CanDecoder::new();
+//! let packets: Vec<_> = frames.iter().flat_map(|frame| decoder.consume(frame)).collect();
+//! assert_eq!(packets[0].packet, request);
+//! assert_eq!(packets[0].id, 0x100);
+//! ```
+
+use std::collections::HashMap;
+
+use crate::error::{AnError, Result};
+use crate::packet::Packet;
+use crate::parser::AnppParser;
+use crate::profile::ProtocolProfile;
+use crate::wire::EncodedFrame;
+
+/// Largest 11 bit standard identifier
+pub const MAX_STANDARD_ID: u32 = 0x7FF;
+
+/// Largest 29 bit extended identifier
+pub const MAX_EXTENDED_ID: u32 = 0x1FFF_FFFF;
+
+/// Data field lengths CAN FD allows above the classic 8 bytes
+const FD_LENGTHS: [usize; 7] = [12, 16, 20, 24, 32, 48, 64];
+
+/// Classic CAN or CAN FD, which sets the largest data field
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum CanMode {
+    /// Up to 8 data bytes per frame
+    Classic,
+    /// Up to 64 data bytes per frame
+    Fd,
+}
+
+impl CanMode {
+    /// Largest data field in bytes
+    pub fn max_data_len(&self) -> usize {
+        match self {
+            CanMode::Classic => 8,
+            CanMode::Fd => 64,
+        }
+    }
+
+    /// Longest legal data field no longer than `len`
+    fn fit(&self, len: usize) -> usize {
+        match self {
+            CanMode::Classic => len.min(8),
+            CanMode::Fd if len <= 8 => len,
+            CanMode::Fd => FD_LENGTHS.iter().copied().take_while(|&legal| legal <= len).last().unwrap_or(8),
+        }
+    }
+}
+
+/// One CAN frame: an 11 or 29 bit identifier and its data field
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CanFrame {
+    pub id: u32,
+    pub data: Vec<u8>,
+}
+
+/// A decoded packet tagged with the CAN identifier it arrived under
+#[derive(Debug, Clone, PartialEq)]
+pub struct CanPacket {
+    pub id: u32,
+    pub packet: Packet,
+}
+
+/// Cuts ANPP frames into CAN frames under one identifier
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CanEncoder {
+    id: u32,
+    mode: CanMode,
+}
+
+impl CanEncoder {
+    /// Encoder for an identifier of up to 29 bits. Fails above
+    /// [`MAX_EXTENDED_ID`]; whether an identifier that also fits 11 bits
+    /// goes out in the standard or extended format is up to the bus driver.
+    pub fn new(id: u32, mode: CanMode) -> Result<Self> {
+        Self::checked(id, MAX_EXTENDED_ID, mode)
+    }
+
+    /// Encoder for an 11 bit standard identifier, failing above
+    /// [`MAX_STANDARD_ID`]
+    pub fn standard(id: u32, mode: CanMode) -> Result<Self> {
+        Self::checked(id, MAX_STANDARD_ID, mode)
+    }
+
+    fn checked(id: u32, max: u32, mode: CanMode) -> Result<Self> {
+        if id > max {
+            return Err(AnError::ValidationFailed(format!("CAN identifier 0x{:X} above 0x{:X}", id, max)));
+        }
+        Ok(Self { id, mode })
+    }
+
+    pub fn id(&self) -> u32 {
+        self.id
+    }
+
+    pub fn mode(&self) -> CanMode {
+        self.mode
+    }
+
+    /// Encode a packet for the device and cut the frame into CAN frames,
+    /// to be sent in order
+    pub fn encode(&self, packet: &Packet) -> Result<Vec<CanFrame>> {
+        Ok(self.segment(&EncodedFrame::encode(packet)?.to_vec()))
+    }
+
+    /// Cut already encoded ANPP bytes, one or more whole frames, into CAN
+    /// frames
+    pub fn segment(&self, mut bytes: &[u8]) -> Vec<CanFrame> {
+        let mut frames = Vec::new();
+        while !bytes.is_empty() {
+            let (data, rest) = bytes.split_at(self.mode.fit(bytes.len()));
+            frames.push(CanFrame { id: self.id, data: data.to_vec() });
+            bytes = rest;
+        }
+        frames
+    }
+}
+
+/// Reassembles ANPP packets from received CAN frames.
+///
+/// Each CAN identifier gets its own parser, so devices or channels sharing
+/// the bus never mix their streams. Classic and FD frames can be fed
+/// interchangeably.
+#[derive(Default)]
+pub struct CanDecoder {
+    profile: ProtocolProfile,
+    ids: Option<Vec<u32>>,
+    parsers: HashMap<u32, AnppParser>,
+}
+
+impl CanDecoder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Validate packet lengths against `profile`
+    pub fn with_profile(mut self, profile: ProtocolProfile) -> Self {
+        self.profile = profile;
+        self
+    }
+
+    /// Only reassemble frames with these identifiers; others, such as
+    /// unrelated traffic on a shared backbone, are ignored
+    pub fn with_ids(mut self, ids: &[u32]) -> Self {
+        self.ids = Some(ids.to_vec());
+        self
+    }
+
+    /// Parser for an identifier seen so far, e.g. for its statistics
+    pub fn parser(&self, id: u32) -> Option<&AnppParser> {
+        self.parsers.get(&id)
+    }
+
+    /// Feed one received CAN frame, returning every packet it completes
+    pub fn consume(&mut self, frame: &CanFrame) -> Vec<CanPacket> {
+        if self.ids.as_ref().is_some_and(|ids| !ids.contains(&frame.id)) {
+            return Vec::new();
+        }
+        let parser = self.parsers.entry(frame.id).or_insert_with(|| AnppParser::with_profile(self.profile.clone()));
+        let mut packets = Vec::new();
+        let mut input = frame.data.as_slice();
+        while let Some(packet) = parser.consume(input) {
+            input = &[];
+            packets.push(CanPacket { id: frame.id, packet });
+        }
+        packets
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::packet::state::UnixTime;
+    use crate::packet::system::Request;
+    use crate::packet::PacketKind;
+    use crate::wire;
+
+    fn unix_time(seconds: u32) -> Packet {
+        Packet::UnixTime(UnixTime { unix_time_seconds: seconds, microseconds: 0 })
+    }
+
+    /// CAN frames of a packet as the device would send it
+    fn from_device(encoder: &CanEncoder, packet: &Packet) -> Vec<CanFrame> {
+        let payload = wire::encode_payload(packet).unwrap();
+        encoder.segment(&wire::encode_frame(wire::PacketId::new(packet.packet_id()), &payload).unwrap())
+    }
+
+    #[test]
+    fn test_fd_segments_at_legal_lengths() {
+        let encoder = CanEncoder::new(0x18FF_0001, CanMode::Fd).unwrap();
+        let request = Packet::Request(Request::multiple(PacketKind::all().take(70).collect::<Vec<_>>()));
+        let frames = encoder.encode(&request).unwrap();
+        // 75 bytes: 64, then 8, then 3
+        assert_eq!(frames.iter().map(|frame| frame.data.len()).collect::<Vec<_>>(), vec![64, 8, 3]);
+        let lengths = [37, 13, 9, 64, 100];
+        for len in lengths {
+            let frames = encoder.segment(&vec![0; len]);
+            assert!(frames.iter().all(|frame| frame.data.len() <= 8 || FD_LENGTHS.contains(&frame.data.len())), "{len}");
+            assert_eq!(frames.iter().map(|frame| frame.data.len()).sum::<usize>(), len);
+        }
+
+        let mut decoder = CanDecoder::new();
+        let packets: Vec<_> = frames.iter().flat_map(|frame| decoder.consume(frame)).collect();
+        assert_eq!(packets, vec![CanPacket { id: 0x18FF_0001, packet: request }]);
+    }
+
+    #[test]
+    fn test_rejects_out_of_range_identifiers() {
+        assert!(CanEncoder::standard(MAX_STANDARD_ID, CanMode::Classic).is_ok());
+        assert!(CanEncoder::standard(0x800, CanMode::Classic).is_err());
+        assert!(CanEncoder::new(0x800, CanMode::Classic).is_ok());
+        assert!(CanEncoder::new(MAX_EXTENDED_ID, CanMode::Fd).is_ok());
+        assert!(CanEncoder::new(0x2000_0000, CanMode::Fd).is_err());
+        assert!(CanEncoder::new(u32::MAX, CanMode::Classic).is_err());
+    }
+
+    #[test]
+    fn test_interleaved_identifiers() {
+        let a = from_device(&CanEncoder::standard(0x100, CanMode::Classic).unwrap(), &unix_time(1));
+        let b = from_device(&CanEncoder::standard(0x200, CanMode::Classic).unwrap(), &unix_time(2));
+        let noise = CanFrame { id: 0x300, data: vec![0xFF; 8] };
+        assert_eq!((a.len(), b.len()), (2, 2));
+
+        let mut decoder = CanDecoder::new().with_ids(&[0x100, 0x200]);
+        let mut packets = Vec::new();
+        for frame in [&a[0], &b[0], &noise, &a[1], &b[1]] {
+            packets.extend(decoder.consume(frame));
+        }
+        assert_eq!(packets, vec![
+            CanPacket { id: 0x100, packet: unix_time(1) },
+            CanPacket { id: 0x200, packet: unix_time(2) },
+        ]);
+        assert!(decoder.parser(0x300).is_none());
+    }
+
+    #[test]
+    fn test_lost_frame_resynchronises() {
+        let encoder = CanEncoder::standard(0x100, CanMode::Classic).unwrap();
+        let mut frames = from_device(&encoder, &unix_time(1));
+        frames.remove(1);
+        frames.extend(from_device(&encoder, &unix_time(2)));
+
+        let mut decoder = CanDecoder::new();
+        let packets: Vec<_> = frames.iter().flat_map(|frame| decoder.consume(frame)).collect();
+        assert_eq!(packets, vec![CanPacket { id: 0x100, packet: unix_time(2) }]);
+        assert!(decoder.parser(0x100).unwrap().stats().bytes_discarded > 0);
+    }
+}
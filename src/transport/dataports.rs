@@ -0,0 +1,375 @@
+use std::io::{self, ErrorKind, Read, Write};
+use std::net::{Ipv4Addr, SocketAddr, SocketAddrV4, TcpListener, TcpStream, UdpSocket};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+use crate::packet::config::{IpDataportMode, IpDataportsConfiguration};
+use crate::packet::Packet;
+use crate::parser::AnppParser;
+
+const READ_SIZE: usize = 65_507;
+
+// How often blocked listeners and UDP sockets check whether the mux has
+// been dropped
+const POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+/// A decoded packet tagged with the dataport it arrived on
+#[derive(Debug, Clone, PartialEq)]
+pub struct DataportPacket {
+    /// Index into [`IpDataportsConfiguration::dataports`]
+    pub index: usize,
+    pub mode: IpDataportMode,
+    pub packet: Packet,
+}
+
+/// What a reader thread sends: a packet, or why its port finished
+type Message = Result<DataportPacket, (usize, io::Error)>;
+
+fn into_result(message: Message) -> io::Result<DataportPacket> {
+    message.map_err(|(index, error)| io::Error::new(error.kind(), format!("dataport {}: {}", index, error)))
+}
+
+struct Port {
+    index: usize,
+    mode: IpDataportMode,
+    local_addr: Option<SocketAddr>,
+    /// Current TCP connection, for writes and for shutting it down on drop
+    stream: Arc<Mutex<Option<TcpStream>>>,
+}
+
+/// Every active IP dataport of one device, merged into a single stream.
+///
+/// Each enabled dataport in an [`IpDataportsConfiguration`] is opened from
+/// the host side of its mode: a TCP server dataport is connected to on the
+/// device address, a TCP client dataport is listened for on its port, and
+/// a UDP client dataport is received on its port. Every port has its own
+/// reader thread and parser, so frames are never mixed between ports, and
+/// the decoded packets come out of [`recv`](Self::recv) in arrival order
+/// tagged with the dataport index. An application that keeps telemetry and
+/// configuration on separate ports sees one coherent stream and still
+/// knows where each packet came from.
+///
+/// A connection to a TCP server dataport that closes is reported once as
+/// an error and the port is finished; a TCP client dataport accepts the
+/// device again when it reconnects.
+///
+/// # Examples
+///
+/// ```no_run
+/// use liban::IpDataportsConfiguration;
+/// use liban::transport::dataports::DataportMux;
+/// use std::net::Ipv4Addr;
+///
+/// let config = IpDataportsConfiguration::builder()
+///     .tcp_server(0, 16718)
+///     .udp_client(1, Ipv4Addr::new(192, 168, 1, 10), 16719)
+///     .build()
+///     .unwrap();
+/// let mux = DataportMux::open(&config, Ipv4Addr::new(192, 168, 1, 50)).unwrap();
+/// for tagged in mux {
+///     let tagged = tagged.unwrap();
+///     println!("dataport {} -> {:?}", tagged.index, tagged.packet);
+/// }
+/// ```
+pub struct DataportMux {
+    ports: Vec<Port>,
+    rx: Receiver<Message>,
+    stop: Arc<AtomicBool>,
+}
+
+impl DataportMux {
+    /// Open every enabled dataport of `config` on the device at `device`
+    pub fn open(config: &IpDataportsConfiguration, device: Ipv4Addr) -> io::Result<Self> {
+        let (tx, rx) = mpsc::channel();
+        let stop = Arc::new(AtomicBool::new(false));
+        let mut mux = Self { ports: Vec::new(), rx, stop };
+        for (index, mode, address) in config.endpoints() {
+            let port = match mode {
+                IpDataportMode::TcpServer => {
+                    let stream = TcpStream::connect(SocketAddrV4::new(device, address.port()))?;
+                    mux.spawn_tcp(index, stream, tx.clone())?
+                }
+                IpDataportMode::TcpClient => {
+                    let listener = TcpListener::bind(SocketAddrV4::new(Ipv4Addr::UNSPECIFIED, address.port()))?;
+                    mux.spawn_listener(index, listener, tx.clone())?
+                }
+                IpDataportMode::UdpClient => {
+                    let socket = UdpSocket::bind(SocketAddrV4::new(Ipv4Addr::UNSPECIFIED, address.port()))?;
+                    mux.spawn_udp(index, socket, tx.clone())?
+                }
+                IpDataportMode::Disabled => continue,
+            };
+            mux.ports.push(port);
+        }
+        Ok(mux)
+    }
+
+    /// Index and mode of each open dataport
+    pub fn ports(&self) -> impl Iterator<Item = (usize, IpDataportMode)> + '_ {
+        self.ports.iter().map(|port| (port.index, port.mode))
+    }
+
+    /// Local address the host listens on for a TCP or UDP client dataport
+    pub fn local_addr(&self, index: usize) -> Option<SocketAddr> {
+        self.port(index)?.local_addr
+    }
+
+    /// Block until the next packet is decoded on any dataport. A port that
+    /// fails is reported once, with its index in the message; once every
+    /// port has finished this returns [`ErrorKind::NotConnected`].
+    pub fn recv(&mut self) -> io::Result<DataportPacket> {
+        match self.rx.recv() {
+            Ok(message) => into_result(message),
+            Err(_) => Err(io::Error::new(ErrorKind::NotConnected, "every dataport has closed")),
+        }
+    }
+
+    /// Send bytes, such as a configuration write, on a TCP dataport. UDP
+    /// client dataports only carry data from the device.
+    pub fn write(&mut self, index: usize, bytes: &[u8]) -> io::Result<()> {
+        let port = self.port(index)
+            .ok_or_else(|| io::Error::new(ErrorKind::NotFound, format!("dataport {} is not open", index)))?;
+        if port.mode == IpDataportMode::UdpClient {
+            return Err(io::Error::new(ErrorKind::Unsupported, format!("dataport {} is a UDP client", index)));
+        }
+        match port.stream.lock().unwrap().as_mut() {
+            Some(stream) => stream.write_all(bytes),
+            None => Err(io::Error::new(ErrorKind::NotConnected, format!("dataport {} has no connection", index))),
+        }
+    }
+
+    fn port(&self, index: usize) -> Option<&Port> {
+        self.ports.iter().find(|port| port.index == index)
+    }
+
+    fn spawn_tcp(&self, index: usize, stream: TcpStream, tx: Sender<Message>) -> io::Result<Port> {
+        let mode = IpDataportMode::TcpServer;
+        let reader = stream.try_clone()?;
+        thread::spawn(move || {
+            let error = read_stream(index, mode, reader, &tx)
+                .unwrap_or_else(|| io::Error::new(ErrorKind::UnexpectedEof, "connection closed by the device"));
+            let _ = tx.send(Err((index, error)));
+        });
+        Ok(Port { index, mode, local_addr: None, stream: Arc::new(Mutex::new(Some(stream))) })
+    }
+
+    fn spawn_listener(&self, index: usize, listener: TcpListener, tx: Sender<Message>) -> io::Result<Port> {
+        let mode = IpDataportMode::TcpClient;
+        let local_addr = listener.local_addr()?;
+        listener.set_nonblocking(true)?;
+        let current = Arc::new(Mutex::new(None));
+        let stream = Arc::clone(&current);
+        let stop = Arc::clone(&self.stop);
+        thread::spawn(move || {
+            while !stop.load(Ordering::Relaxed) {
+                let stream = match listener.accept() {
+                    Ok((stream, _)) => stream,
+                    Err(error) if error.kind() == ErrorKind::WouldBlock => {
+                        thread::sleep(POLL_INTERVAL);
+                        continue;
+                    }
+                    Err(error) => {
+                        let _ = tx.send(Err((index, error)));
+                        return;
+                    }
+                };
+                let reader = stream.set_nonblocking(false).and_then(|_| stream.try_clone());
+                let reader = match reader {
+                    Ok(reader) => reader,
+                    Err(error) => {
+                        let _ = tx.send(Err((index, error)));
+                        return;
+                    }
+                };
+                *current.lock().unwrap() = Some(stream);
+                // The device connects again after a drop, so a closed
+                // connection is not an error here
+                let _ = read_stream(index, mode, reader, &tx);
+                *current.lock().unwrap() = None;
+            }
+        });
+        Ok(Port { index, mode, local_addr: Some(local_addr), stream })
+    }
+
+    fn spawn_udp(&self, index: usize, socket: UdpSocket, tx: Sender<Message>) -> io::Result<Port> {
+        let mode = IpDataportMode::UdpClient;
+        let local_addr = socket.local_addr()?;
+        socket.set_read_timeout(Some(POLL_INTERVAL))?;
+        let stop = Arc::clone(&self.stop);
+        thread::spawn(move || {
+            let mut parser = AnppParser::new();
+            let mut buf = vec![0u8; READ_SIZE];
+            while !stop.load(Ordering::Relaxed) {
+                match socket.recv(&mut buf) {
+                    Ok(n) => {
+                        if !forward(index, mode, &mut parser, &buf[..n], &tx) {
+                            return;
+                        }
+                    }
+                    Err(error) if matches!(error.kind(), ErrorKind::WouldBlock | ErrorKind::TimedOut) => {}
+                    Err(error) => {
+                        let _ = tx.send(Err((index, error)));
+                        return;
+                    }
+                }
+            }
+        });
+        Ok(Port { index, mode, local_addr: Some(local_addr), stream: Arc::new(Mutex::new(None)) })
+    }
+}
+
+impl Drop for DataportMux {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+        for port in &self.ports {
+            if let Some(stream) = port.stream.lock().unwrap().as_ref() {
+                let _ = stream.shutdown(std::net::Shutdown::Both);
+            }
+        }
+    }
+}
+
+/// Ends once every dataport has finished
+impl Iterator for DataportMux {
+    type Item = io::Result<DataportPacket>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.rx.recv().ok().map(into_result)
+    }
+}
+
+/// Read one connection until it closes, returning the read error if there
+/// was one. Also returns once the mux is dropped.
+fn read_stream(index: usize, mode: IpDataportMode, mut stream: TcpStream, tx: &Sender<Message>) -> Option<io::Error> {
+    let mut parser = AnppParser::new();
+    let mut buf = vec![0u8; READ_SIZE];
+    loop {
+        match stream.read(&mut buf) {
+            Ok(0) => return None,
+            Ok(n) => {
+                if !forward(index, mode, &mut parser, &buf[..n], tx) {
+                    return None;
+                }
+            }
+            Err(error) if error.kind() == ErrorKind::Interrupted => {}
+            Err(error) => return Some(error),
+        }
+    }
+}
+
+/// Parse `input` and send every packet; false once the mux is gone
+fn forward(index: usize, mode: IpDataportMode, parser: &mut AnppParser, mut input: &[u8], tx: &Sender<Message>) -> bool {
+    while let Some(packet) = parser.consume(input) {
+        input = &[];
+        if tx.send(Ok(DataportPacket { index, mode, packet })).is_err() {
+            return false;
+        }
+    }
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::packet::config::IpDataport;
+    use crate::packet::{PacketId, PacketKind};
+    use crate::protocol::AnppProtocol;
+
+    fn request_frame(id: u8) -> Vec<u8> {
+        AnppProtocol::get_packet_bytes(PacketId::new(1), &[id]).unwrap()
+    }
+
+    /// Configuration with ports the builder would refuse, so the host
+    /// side can listen on ephemeral ports
+    fn config(dataports: &[(usize, IpDataportMode, u16)]) -> IpDataportsConfiguration {
+        let mut config = IpDataportsConfiguration { dataports: [IpDataport::DISABLED; 4], extension: None };
+        for &(index, mode, port) in dataports {
+            config.dataports[index] = IpDataport::new(mode, SocketAddrV4::new(Ipv4Addr::LOCALHOST, port));
+        }
+        config
+    }
+
+    fn requested(tagged: &DataportPacket) -> Vec<PacketKind> {
+        match &tagged.packet {
+            Packet::Request(request) => request.requested_packets.clone(),
+            other => panic!("expected a Request, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_ports_merged_with_tags() {
+        // The device side: a TCP server dataport on 0 and, once the host
+        // listens, a TCP client on 1 and UDP datagrams to 2
+        let device = TcpListener::bind("127.0.0.1:0").unwrap();
+        let config = config(&[
+            (0, IpDataportMode::TcpServer, device.local_addr().unwrap().port()),
+            (1, IpDataportMode::TcpClient, 0),
+            (2, IpDataportMode::UdpClient, 0),
+        ]);
+        let mut mux = DataportMux::open(&config, Ipv4Addr::LOCALHOST).unwrap();
+        assert_eq!(mux.ports().collect::<Vec<_>>(), vec![
+            (0, IpDataportMode::TcpServer), (1, IpDataportMode::TcpClient), (2, IpDataportMode::UdpClient),
+        ]);
+        assert_eq!(mux.local_addr(0), None);
+        let (mut telemetry, _) = device.accept().unwrap();
+        let mut config_port = TcpStream::connect(("127.0.0.1", mux.local_addr(1).unwrap().port())).unwrap();
+        let udp = UdpSocket::bind("127.0.0.1:0").unwrap();
+
+        // A frame split across writes on one port is not mixed with others
+        let frame = request_frame(20);
+        telemetry.write_all(&frame[..3]).unwrap();
+        config_port.write_all(&request_frame(180)).unwrap();
+        let first = mux.recv().unwrap();
+        assert_eq!((first.index, first.mode), (1, IpDataportMode::TcpClient));
+        assert_eq!(requested(&first), vec![PacketKind::PacketTimerPeriod]);
+
+        telemetry.write_all(&frame[3..]).unwrap();
+        let second = mux.recv().unwrap();
+        assert_eq!(second.index, 0);
+        assert_eq!(requested(&second), vec![PacketKind::SystemState]);
+
+        udp.send_to(&request_frame(21), ("127.0.0.1", mux.local_addr(2).unwrap().port())).unwrap();
+        let third = mux.recv().unwrap();
+        assert_eq!(third.index, 2);
+        assert_eq!(requested(&third), vec![PacketKind::UnixTime]);
+
+        // Writes go out on the chosen TCP port
+        mux.write(1, &request_frame(3)).unwrap();
+        let mut buf = [0u8; 6];
+        config_port.read_exact(&mut buf).unwrap();
+        assert_eq!(buf.to_vec(), request_frame(3));
+        assert_eq!(mux.write(2, &[0]).unwrap_err().kind(), ErrorKind::Unsupported);
+        assert_eq!(mux.write(3, &[0]).unwrap_err().kind(), ErrorKind::NotFound);
+
+        drop(telemetry);
+        let closed = mux.recv().unwrap_err();
+        assert_eq!(closed.kind(), ErrorKind::UnexpectedEof);
+        assert!(closed.to_string().starts_with("dataport 0"));
+    }
+
+    #[test]
+    fn test_client_dataport_reconnects() {
+        let config = config(&[(3, IpDataportMode::TcpClient, 0)]);
+        let mut mux = DataportMux::open(&config, Ipv4Addr::LOCALHOST).unwrap();
+        let port = mux.local_addr(3).unwrap().port();
+        for id in [20, 21] {
+            let mut device = TcpStream::connect(("127.0.0.1", port)).unwrap();
+            device.write_all(&request_frame(id)).unwrap();
+            let tagged = mux.recv().unwrap();
+            assert_eq!(tagged.index, 3);
+            assert_eq!(requested(&tagged), vec![PacketKind::from(id)]);
+        }
+    }
+
+    #[test]
+    fn test_no_dataports() {
+        let config = config(&[]);
+        let mut mux = DataportMux::open(&config, Ipv4Addr::LOCALHOST).unwrap();
+        assert_eq!(mux.ports().count(), 0);
+        assert_eq!(mux.recv().unwrap_err().kind(), ErrorKind::NotConnected);
+        assert!(mux.next().is_none());
+    }
+}
@@ -0,0 +1,11 @@
+//! Transports that feed the sans-io parser: blocking packet sources, and a
+//! sans-io codec for CAN.
+
+#[cfg(feature = "can")]
+pub mod can;
+#[cfg(feature = "dataports")]
+pub mod dataports;
+#[cfg(feature = "serialport")]
+pub mod serial;
+#[cfg(feature = "udp")]
+pub mod udp;
@@ -0,0 +1,293 @@
+use std::io::{self, ErrorKind, Read};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::packet::Packet;
+use crate::parser::AnppParser;
+
+const READ_SIZE: usize = 1024;
+
+/// Port opened by [`open`]
+pub type Port = Box<dyn serialport::SerialPort>;
+
+/// Timing for a [`SerialAnppSource`]
+#[derive(Debug, Clone, PartialEq)]
+pub struct SerialConfig {
+    /// How long one read waits for bytes before coming back empty
+    pub read_timeout: Duration,
+    /// Silence after which a partly received frame is discarded. A frame
+    /// is sent back to back, so a gap this long means the rest was lost.
+    pub inter_byte_timeout: Duration,
+    /// Wait between attempts to reopen a port that has gone away
+    pub reopen_delay: Duration,
+    /// Give up after this many failed reopen attempts in a row; `None`
+    /// keeps trying
+    pub max_reopen_attempts: Option<u32>,
+}
+
+impl Default for SerialConfig {
+    fn default() -> Self {
+        Self {
+            read_timeout: Duration::from_millis(100),
+            inter_byte_timeout: Duration::from_millis(50),
+            reopen_delay: Duration::from_millis(500),
+            max_reopen_attempts: None,
+        }
+    }
+}
+
+/// ANPP packet source over a serial port that survives USB disconnects.
+///
+/// The port is produced by an opener closure so any serial implementation
+/// that implements [`Read`] can be used, and so it can be opened again when
+/// a USB adapter is unplugged and replugged. A read that times out or
+/// returns no bytes just means the line is quiet; on any other read error
+/// the port is dropped, the parser is cleared and the opener is called
+/// again after [`SerialConfig::reopen_delay`].
+///
+/// # Examples
+///
+/// ```no_run
+/// use liban::transport::serial::{self, SerialConfig};
+///
+/// let source = serial::open("/dev/ttyUSB0", 115_200, SerialConfig::default()).unwrap();
+/// for packet in source {
+///     println!("{:?}", packet.unwrap());
+/// }
+/// ```
+pub struct SerialAnppSource<R, F> {
+    open: F,
+    port: Option<R>,
+    parser: AnppParser,
+    config: SerialConfig,
+    last_byte: Option<Instant>,
+    buf: Vec<u8>,
+    reopens: u64,
+    gap_resets: u64,
+}
+
+/// Open a serial port such as `/dev/ttyUSB0` or `COM3` at `baud_rate`,
+/// 8N1 with no flow control
+pub fn open(
+    path: impl Into<String>,
+    baud_rate: u32,
+    config: SerialConfig,
+) -> io::Result<SerialAnppSource<Port, impl FnMut() -> io::Result<Port>>> {
+    let path = path.into();
+    let timeout = config.read_timeout;
+    SerialAnppSource::new(move || Ok(serialport::new(path.as_str(), baud_rate).timeout(timeout).open()?), config)
+}
+
+impl<R: Read, F: FnMut() -> io::Result<R>> SerialAnppSource<R, F> {
+    /// Open the port with `open` and keep it for reopening later
+    pub fn new(mut open: F, config: SerialConfig) -> io::Result<Self> {
+        let port = open()?;
+        Ok(Self {
+            open,
+            port: Some(port),
+            parser: AnppParser::new(),
+            config,
+            last_byte: None,
+            buf: vec![0u8; READ_SIZE],
+            reopens: 0,
+            gap_resets: 0,
+        })
+    }
+
+    /// The open port, e.g. to write to it. `None` while reconnecting.
+    pub fn port_mut(&mut self) -> Option<&mut R> {
+        self.port.as_mut()
+    }
+
+    /// Times the port has been reopened after going away
+    pub fn reopen_count(&self) -> u64 {
+        self.reopens
+    }
+
+    /// Partial frames discarded because of an inter-byte gap
+    pub fn gap_resets(&self) -> u64 {
+        self.gap_resets
+    }
+
+    /// Block until the next packet is decoded
+    pub fn recv(&mut self) -> io::Result<Packet> {
+        loop {
+            if let Some(packet) = self.parser.consume(&[]) {
+                return Ok(packet);
+            }
+
+            let port = match &mut self.port {
+                Some(port) => port,
+                None => {
+                    self.reopen()?;
+                    continue;
+                }
+            };
+
+            match port.read(&mut self.buf) {
+                Ok(0) => {}
+                Ok(n) => {
+                    let now = Instant::now();
+                    let gap = self.last_byte.is_some_and(|t| now - t > self.config.inter_byte_timeout);
+                    if gap && self.parser.buffer_len() > 0 {
+                        #[cfg(feature = "tracing")]
+                        tracing::debug!("Discarding {} bytes after inter-byte gap", self.parser.buffer_len());
+                        self.parser.clear();
+                        self.gap_resets += 1;
+                    }
+                    self.last_byte = Some(now);
+                    if let Some(packet) = self.parser.consume(&self.buf[..n]) {
+                        return Ok(packet);
+                    }
+                }
+                Err(e) if matches!(e.kind(), ErrorKind::Interrupted | ErrorKind::TimedOut | ErrorKind::WouldBlock) => {}
+                Err(_e) => {
+                    #[cfg(feature = "tracing")]
+                    tracing::debug!("Serial read failed, reopening: {}", _e);
+                    self.disconnect();
+                }
+            }
+        }
+    }
+
+    fn disconnect(&mut self) {
+        self.port = None;
+        self.parser.clear();
+        self.last_byte = None;
+    }
+
+    fn reopen(&mut self) -> io::Result<()> {
+        let mut attempts = 0;
+        loop {
+            thread::sleep(self.config.reopen_delay);
+            match (self.open)() {
+                Ok(port) => {
+                    self.port = Some(port);
+                    self.reopens += 1;
+                    return Ok(());
+                }
+                Err(e) => {
+                    attempts += 1;
+                    if self.config.max_reopen_attempts.is_some_and(|max| attempts >= max) {
+                        return Err(e);
+                    }
+                }
+            }
+        }
+    }
+}
+
+impl<R: Read, F: FnMut() -> io::Result<R>> Iterator for SerialAnppSource<R, F> {
+    type Item = io::Result<Packet>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        Some(self.recv())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::packet::{PacketId, PacketKind};
+    use crate::protocol::AnppProtocol;
+    use std::collections::VecDeque;
+
+    fn request_frame(id: u8) -> Vec<u8> {
+        AnppProtocol::get_packet_bytes(PacketId::new(1), &[id]).unwrap()
+    }
+
+    /// Port that replays scripted reads, sleeping where asked
+    enum Step {
+        Data(Vec<u8>),
+        Pause(Duration),
+        Empty,
+        TimedOut,
+        Fail,
+    }
+
+    struct ScriptedPort(VecDeque<Step>);
+
+    impl Read for ScriptedPort {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            loop {
+                match self.0.pop_front() {
+                    Some(Step::Data(data)) => {
+                        buf[..data.len()].copy_from_slice(&data);
+                        return Ok(data.len());
+                    }
+                    Some(Step::Pause(d)) => thread::sleep(d),
+                    Some(Step::Empty) => return Ok(0),
+                    Some(Step::TimedOut) => return Err(ErrorKind::TimedOut.into()),
+                    Some(Step::Fail) => return Err(io::Error::other("device unplugged")),
+                    None => return Err(io::Error::new(ErrorKind::BrokenPipe, "device unplugged")),
+                }
+            }
+        }
+    }
+
+    fn config() -> SerialConfig {
+        SerialConfig {
+            read_timeout: Duration::from_millis(10),
+            inter_byte_timeout: Duration::from_millis(20),
+            reopen_delay: Duration::ZERO,
+            max_reopen_attempts: Some(1),
+        }
+    }
+
+    fn requested(packet: Packet) -> PacketKind {
+        match packet {
+            Packet::Request(request) => request.requested_packets[0],
+            other => panic!("unexpected {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_gap_discards_partial_frame() {
+        let partial = request_frame(20)[..4].to_vec();
+        let script = vec![
+            Step::Data(partial),
+            Step::Pause(Duration::from_millis(60)),
+            Step::Data(request_frame(21)),
+        ];
+        let mut ports = vec![ScriptedPort(script.into())].into_iter();
+        let mut source = SerialAnppSource::new(move || ports.next().ok_or(ErrorKind::NotFound.into()), config()).unwrap();
+
+        assert_eq!(requested(source.recv().unwrap()), PacketKind::UnixTime);
+        assert_eq!(source.gap_resets(), 1);
+    }
+
+    #[test]
+    fn test_reopens_after_disconnect() {
+        let first = request_frame(20);
+        let ports = vec![
+            ScriptedPort(vec![Step::Data(first.clone()), Step::Data(first[..3].to_vec()), Step::Fail].into()),
+            ScriptedPort(vec![Step::Data(request_frame(23))].into()),
+        ];
+        let mut ports = ports.into_iter();
+        let mut source = SerialAnppSource::new(move || ports.next().ok_or(ErrorKind::NotFound.into()), config()).unwrap();
+
+        assert_eq!(requested(source.recv().unwrap()), PacketKind::SystemState);
+        // The partial frame from the first port does not leak into the second
+        assert_eq!(requested(source.recv().unwrap()), PacketKind::Status);
+        assert_eq!(source.reopen_count(), 1);
+
+        // Second port ends and no third port can be opened
+        assert_eq!(source.recv().unwrap_err().kind(), ErrorKind::NotFound);
+    }
+
+    #[test]
+    fn test_quiet_reads_keep_the_port() {
+        let frame = request_frame(20);
+        let script = vec![
+            Step::Data(frame[..4].to_vec()),
+            Step::Empty,
+            Step::TimedOut,
+            Step::Data(frame[4..].to_vec()),
+        ];
+        let mut ports = vec![ScriptedPort(script.into())].into_iter();
+        let mut source = SerialAnppSource::new(move || ports.next().ok_or(ErrorKind::NotFound.into()), config()).unwrap();
+
+        assert_eq!(requested(source.recv().unwrap()), PacketKind::SystemState);
+        assert_eq!(source.reopen_count(), 0);
+    }
+}
@@ -0,0 +1,159 @@
+use std::collections::{HashMap, VecDeque};
+use std::io;
+use std::net::{Ipv4Addr, SocketAddr, SocketAddrV4, UdpSocket};
+
+use crate::packet::Packet;
+use crate::parser::AnppParser;
+
+// Largest possible UDP payload; ANPP frames are much smaller but a device
+// may batch several frames into one datagram.
+const DATAGRAM_SIZE: usize = 65_507;
+
+/// A decoded packet tagged with the address of the device that sent it
+#[derive(Debug, Clone, PartialEq)]
+pub struct TaggedPacket {
+    pub source: SocketAddr,
+    pub packet: Packet,
+}
+
+/// ANPP packet source over UDP, typically a multicast group shared by
+/// several devices.
+///
+/// Each sender gets its own stream parser so frames split across datagrams
+/// are reassembled per device and never mixed between devices.
+///
+/// # Examples
+///
+/// ```no_run
+/// use liban::transport::udp::UdpAnppSource;
+/// use std::net::Ipv4Addr;
+///
+/// let source = UdpAnppSource::join_multicast(
+///     Ipv4Addr::new(239, 0, 0, 1), 16718, Ipv4Addr::UNSPECIFIED,
+/// ).unwrap();
+/// for tagged in source {
+///     let tagged = tagged.unwrap();
+///     println!("{} -> {:?}", tagged.source, tagged.packet);
+/// }
+/// ```
+pub struct UdpAnppSource {
+    socket: UdpSocket,
+    parsers: HashMap<SocketAddr, AnppParser>,
+    pending: VecDeque<TaggedPacket>,
+    buf: Vec<u8>,
+}
+
+impl UdpAnppSource {
+    /// Wrap an already bound socket
+    pub fn from_socket(socket: UdpSocket) -> Self {
+        Self {
+            socket,
+            parsers: HashMap::new(),
+            pending: VecDeque::new(),
+            buf: vec![0u8; DATAGRAM_SIZE],
+        }
+    }
+
+    /// Bind a unicast/broadcast listener
+    pub fn bind(addr: SocketAddrV4) -> io::Result<Self> {
+        Ok(Self::from_socket(UdpSocket::bind(addr)?))
+    }
+
+    /// Bind to `port` on all interfaces and join `group` on `interface`
+    pub fn join_multicast(group: Ipv4Addr, port: u16, interface: Ipv4Addr) -> io::Result<Self> {
+        let socket = UdpSocket::bind(SocketAddrV4::new(Ipv4Addr::UNSPECIFIED, port))?;
+        socket.join_multicast_v4(&group, &interface)?;
+        Ok(Self::from_socket(socket))
+    }
+
+    /// The underlying socket, e.g. to set a read timeout
+    pub fn socket(&self) -> &UdpSocket {
+        &self.socket
+    }
+
+    /// Number of distinct senders seen so far
+    pub fn source_count(&self) -> usize {
+        self.parsers.len()
+    }
+
+    /// Block until the next packet is decoded
+    pub fn recv(&mut self) -> io::Result<TaggedPacket> {
+        loop {
+            if let Some(tagged) = self.pending.pop_front() {
+                return Ok(tagged);
+            }
+
+            let (n, source) = self.socket.recv_from(&mut self.buf)?;
+            let parser = self.parsers.entry(source).or_default();
+
+            let mut input = &self.buf[..n];
+            while let Some(packet) = parser.consume(input) {
+                input = &[];
+                self.pending.push_back(TaggedPacket { source, packet });
+            }
+        }
+    }
+}
+
+impl Iterator for UdpAnppSource {
+    type Item = io::Result<TaggedPacket>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        Some(self.recv())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::packet::{PacketId, PacketKind};
+    use crate::protocol::AnppProtocol;
+    use std::time::Duration;
+
+    fn request_frame(id: u8) -> Vec<u8> {
+        AnppProtocol::get_packet_bytes(PacketId::new(1), &[id]).unwrap()
+    }
+
+    fn source() -> UdpAnppSource {
+        let source = UdpAnppSource::bind(SocketAddrV4::new(Ipv4Addr::LOCALHOST, 0)).unwrap();
+        source.socket().set_read_timeout(Some(Duration::from_secs(5))).unwrap();
+        source
+    }
+
+    #[test]
+    fn test_packets_tagged_with_sender() {
+        let mut source = source();
+        let dest = source.socket().local_addr().unwrap();
+
+        let a = UdpSocket::bind("127.0.0.1:0").unwrap();
+        let b = UdpSocket::bind("127.0.0.1:0").unwrap();
+        a.send_to(&request_frame(20), dest).unwrap();
+        b.send_to(&request_frame(21), dest).unwrap();
+
+        let first = source.recv().unwrap();
+        assert_eq!(first.source, a.local_addr().unwrap());
+        assert!(matches!(first.packet, Packet::Request(ref r) if r.requested_packets == vec![PacketKind::SystemState]));
+
+        let second = source.recv().unwrap();
+        assert_eq!(second.source, b.local_addr().unwrap());
+        assert_eq!(source.source_count(), 2);
+    }
+
+    #[test]
+    fn test_frame_split_across_datagrams() {
+        let mut source = source();
+        let dest = source.socket().local_addr().unwrap();
+        let sender = UdpSocket::bind("127.0.0.1:0").unwrap();
+
+        let mut frames = request_frame(20);
+        frames.extend(request_frame(21));
+        let (head, tail) = frames.split_at(3);
+        sender.send_to(head, dest).unwrap();
+        sender.send_to(tail, dest).unwrap();
+
+        let first = source.recv().unwrap();
+        let second = source.recv().unwrap();
+        assert!(matches!(first.packet, Packet::Request(ref r) if r.requested_packets == vec![PacketKind::SystemState]));
+        assert!(matches!(second.packet, Packet::Request(ref r) if r.requested_packets == vec![PacketKind::UnixTime]));
+    }
+}
@@ -0,0 +1,366 @@
+//! Typed physical quantities.
+//!
+//! The wire structs carry bare floats in the units the device uses (radians,
+//! meters, m/s). These types make the unit part of the type so a value in
+//! degrees can't be passed where radians are expected. Each stores its SI
+//! value and converts on the way in and out.
+//!
+//! ```
+//! use liban::units::{Angle, Velocity};
+//!
+//! let heading = Angle::from_degrees(90.0);
+//! assert!((heading.radians() - std::f64::consts::FRAC_PI_2).abs() < 1e-12);
+//! assert!((Velocity::from_knots(10.0).meters_per_second() - 5.144_444).abs() < 1e-6);
+//! ```
+//!
+//! Orientation fields of the wire structs are the exception: they are
+//! [`Radians`], a single precision newtype with the same wire and serde
+//! form as the bare `f32` it replaced, and [`Degrees`] is its counterpart
+//! for display and input.
+
+use std::ops::{Add, Neg, Sub};
+
+use binrw::{BinRead, BinWrite};
+#[cfg(feature = "serde")]
+use serde::{Serialize, Deserialize};
+
+use crate::angle::normalize_2pi;
+
+macro_rules! quantity {
+    ($(#[$meta:meta])* $name:ident, $si:ident) => {
+        $(#[$meta])*
+        #[derive(Debug, Default, Clone, Copy, PartialEq, PartialOrd)]
+        #[cfg_attr(feature = "defmt", derive(defmt::Format))]
+        pub struct $name(f64);
+
+        impl $name {
+            pub const ZERO: Self = Self(0.0);
+
+            pub const fn $si(&self) -> f64 {
+                self.0
+            }
+        }
+
+        impl Add for $name {
+            type Output = Self;
+            fn add(self, rhs: Self) -> Self { Self(self.0 + rhs.0) }
+        }
+
+        impl Sub for $name {
+            type Output = Self;
+            fn sub(self, rhs: Self) -> Self { Self(self.0 - rhs.0) }
+        }
+
+        impl Neg for $name {
+            type Output = Self;
+            fn neg(self) -> Self { Self(-self.0) }
+        }
+    };
+}
+
+quantity!(
+    /// Plane angle, stored in radians
+    Angle, radians
+);
+
+quantity!(
+    /// Length, stored in meters
+    Length, meters
+);
+
+quantity!(
+    /// Speed along one axis, stored in meters per second
+    Velocity, meters_per_second
+);
+
+quantity!(
+    /// Acceleration along one axis, stored in meters per second squared
+    LinearAcceleration, meters_per_second_squared
+);
+
+quantity!(
+    /// Angular acceleration about one axis, stored in radians per second
+    /// squared
+    RotationalAcceleration, radians_per_second_squared
+);
+
+macro_rules! angle_newtype {
+    ($name:ident) => {
+        impl $name {
+            /// The bare value
+            pub const fn get(&self) -> f32 {
+                self.0
+            }
+        }
+
+        impl From<$name> for f32 {
+            fn from(value: $name) -> f32 { value.0 }
+        }
+
+        impl From<$name> for f64 {
+            fn from(value: $name) -> f64 { value.0 as f64 }
+        }
+
+        impl Add for $name {
+            type Output = Self;
+            fn add(self, rhs: Self) -> Self { Self(self.0 + rhs.0) }
+        }
+
+        impl Sub for $name {
+            type Output = Self;
+            fn sub(self, rhs: Self) -> Self { Self(self.0 - rhs.0) }
+        }
+
+        impl Neg for $name {
+            type Output = Self;
+            fn neg(self) -> Self { Self(-self.0) }
+        }
+    };
+}
+
+/// Single precision angle in radians, the type of the orientation fields
+/// in the wire structs (roll, pitch, heading, tilt, azimuth and their
+/// standard deviations).
+///
+/// # Migrating from 0.7
+///
+/// These fields were bare `f32` radians. Their wire encoding and serde
+/// form (a plain number) are unchanged; only the Rust type differs:
+///
+/// - `state.roll` as an `f32`: `state.roll.get()` or `f32::from(state.roll)`
+/// - in degrees: `state.roll.to_degrees().get()`
+/// - in double precision: `state.roll_angle()` or `Angle::from(state.roll)`
+/// - constructing: `Radians(0.1)`, or `Degrees(5.7).into()`
+///
+/// ```
+/// use liban::units::{Degrees, Radians};
+///
+/// let heading: Radians = Degrees(90.0).into();
+/// assert!((heading.get() - std::f32::consts::FRAC_PI_2).abs() < 1e-6);
+/// assert!((heading.to_degrees().get() - 90.0).abs() < 1e-4);
+/// ```
+#[derive(Debug, Default, Clone, Copy, PartialEq, PartialOrd, BinRead, BinWrite)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(transparent))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[brw(little)]
+pub struct Radians(pub f32);
+
+/// Single precision angle in degrees, for display and operator input. See
+/// [`Radians`].
+#[derive(Debug, Default, Clone, Copy, PartialEq, PartialOrd)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(transparent))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Degrees(pub f32);
+
+angle_newtype!(Radians);
+angle_newtype!(Degrees);
+
+impl Radians {
+    pub fn to_degrees(self) -> Degrees {
+        Degrees(self.0.to_degrees())
+    }
+
+    /// Double precision [`Angle`]
+    pub fn angle(self) -> Angle {
+        Angle::from_radians(self.0 as f64)
+    }
+}
+
+impl Degrees {
+    pub fn to_radians(self) -> Radians {
+        Radians(self.0.to_radians())
+    }
+}
+
+impl From<Degrees> for Radians {
+    fn from(degrees: Degrees) -> Self { degrees.to_radians() }
+}
+
+impl From<Radians> for Degrees {
+    fn from(radians: Radians) -> Self { radians.to_degrees() }
+}
+
+impl From<Radians> for Angle {
+    fn from(radians: Radians) -> Self { radians.angle() }
+}
+
+impl From<Angle> for Radians {
+    fn from(angle: Angle) -> Self { Radians(angle.radians() as f32) }
+}
+
+impl Angle {
+    pub const fn from_radians(radians: f64) -> Self {
+        Self(radians)
+    }
+
+    pub fn from_degrees(degrees: f64) -> Self {
+        Self(degrees.to_radians())
+    }
+
+    pub fn degrees(&self) -> f64 {
+        self.0.to_degrees()
+    }
+}
+
+impl Length {
+    pub const fn from_meters(meters: f64) -> Self {
+        Self(meters)
+    }
+
+    pub fn from_feet(feet: f64) -> Self {
+        Self(feet * 0.3048)
+    }
+
+    pub fn feet(&self) -> f64 {
+        self.0 / 0.3048
+    }
+}
+
+impl Velocity {
+    pub const fn from_meters_per_second(mps: f64) -> Self {
+        Self(mps)
+    }
+
+    pub fn from_knots(knots: f64) -> Self {
+        Self(knots * 1852.0 / 3600.0)
+    }
+
+    pub fn knots(&self) -> f64 {
+        self.0 * 3600.0 / 1852.0
+    }
+
+    pub fn kilometers_per_hour(&self) -> f64 {
+        self.0 * 3.6
+    }
+}
+
+impl LinearAcceleration {
+    /// Standard gravity used to convert to and from g
+    pub const STANDARD_GRAVITY: f64 = 9.806_65;
+
+    pub const fn from_meters_per_second_squared(mps2: f64) -> Self {
+        Self(mps2)
+    }
+
+    pub fn from_g(g: f64) -> Self {
+        Self(g * Self::STANDARD_GRAVITY)
+    }
+
+    pub fn g(&self) -> f64 {
+        self.0 / Self::STANDARD_GRAVITY
+    }
+}
+
+impl RotationalAcceleration {
+    pub const fn from_radians_per_second_squared(radians: f64) -> Self {
+        Self(radians)
+    }
+
+    pub fn degrees_per_second_squared(&self) -> f64 {
+        self.0.to_degrees()
+    }
+}
+
+/// North, east and down components of a quantity
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Ned<T> {
+    pub north: T,
+    pub east: T,
+    pub down: T,
+}
+
+impl<T> Ned<T> {
+    pub const fn new(north: T, east: T, down: T) -> Self {
+        Self { north, east, down }
+    }
+
+    pub fn map<U>(self, mut f: impl FnMut(T) -> U) -> Ned<U> {
+        Ned { north: f(self.north), east: f(self.east), down: f(self.down) }
+    }
+}
+
+impl<T> From<Ned<T>> for [T; 3] {
+    fn from(ned: Ned<T>) -> Self {
+        [ned.north, ned.east, ned.down]
+    }
+}
+
+impl<T> From<[T; 3]> for Ned<T> {
+    fn from([north, east, down]: [T; 3]) -> Self {
+        Self { north, east, down }
+    }
+}
+
+impl Ned<Velocity> {
+    /// Horizontal speed over ground
+    pub fn horizontal(&self) -> Velocity {
+        Velocity(self.north.0.hypot(self.east.0))
+    }
+
+    /// Speed upward, the negated down component
+    pub fn vertical(&self) -> Velocity {
+        -self.down
+    }
+
+    /// Direction of horizontal travel, clockwise from true north in [0, 2π)
+    pub fn course(&self) -> Angle {
+        normalize_2pi(Angle(self.east.0.atan2(self.north.0)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_angle_conversions() {
+        let a = Angle::from_degrees(180.0);
+        assert!((a.radians() - std::f64::consts::PI).abs() < 1e-12);
+        assert!(((a - Angle::from_radians(std::f64::consts::FRAC_PI_2)).degrees() - 90.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_radians_and_degrees() {
+        let half_turn = Radians(std::f32::consts::PI);
+        assert!((half_turn.to_degrees().get() - 180.0).abs() < 1e-4);
+        assert!((Radians::from(Degrees(-45.0)).get() + std::f32::consts::FRAC_PI_4).abs() < 1e-6);
+        assert_eq!(Angle::from(Radians(0.5)).radians(), 0.5);
+        assert_eq!(Radians::from(Angle::from_radians(0.25)), Radians(0.25));
+        assert_eq!(-(Radians(0.5) - Radians(0.25)) + Radians(1.0), Radians(0.75));
+        assert_eq!(f32::from(Degrees(3.0)), 3.0);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_radians_serialize_as_bare_number() {
+        assert_eq!(serde_json::to_string(&Radians(0.5)).unwrap(), "0.5");
+        assert_eq!(serde_json::from_str::<Radians>("0.25").unwrap(), Radians(0.25));
+    }
+
+    #[test]
+    fn test_ned_velocity() {
+        let v = Ned::new(-3.0, -4.0, 1.5).map(Velocity::from_meters_per_second);
+        assert_eq!(v.horizontal().meters_per_second(), 5.0);
+        assert_eq!(v.vertical().meters_per_second(), -1.5);
+        assert!((v.course().degrees() - 233.130).abs() < 1e-3);
+        assert_eq!(<[Velocity; 3]>::from(v)[2], Velocity::from_meters_per_second(1.5));
+        assert_eq!(Ned::from([1, 2, 3]), Ned::new(1, 2, 3));
+    }
+
+    #[test]
+    fn test_length_and_velocity_conversions() {
+        assert!((Length::from_feet(1.0).meters() - 0.3048).abs() < 1e-12);
+        assert!((Length::from_meters(3.048).feet() - 10.0).abs() < 1e-9);
+        assert!((Velocity::from_meters_per_second(10.0).kilometers_per_hour() - 36.0).abs() < 1e-9);
+        assert!((Velocity::from_knots(1.0).knots() - 1.0).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_acceleration_conversions() {
+        assert!((LinearAcceleration::from_g(1.0).meters_per_second_squared() - 9.80665).abs() < 1e-12);
+        assert!((LinearAcceleration::from_meters_per_second_squared(19.6133).g() - 2.0).abs() < 1e-9);
+        let spin = RotationalAcceleration::from_radians_per_second_squared(std::f64::consts::PI);
+        assert!((spin.degrees_per_second_squared() - 180.0).abs() < 1e-9);
+    }
+}
@@ -0,0 +1,328 @@
+//! Wire-level access to ANPP framing for advanced users.
+//!
+//! Most applications only need [`Packet`] together with [`AnppParser`] or
+//! [`AnppReader`]. This module is the supported way to go one level lower:
+//! build and check raw frames, or convert between a payload and a [`Packet`]
+//! without any framing, e.g. when a payload arrives inside another protocol.
+//!
+//! ```
+//! use liban::wire;
+//! use liban::{Packet, PacketKind, Request};
+//!
+//! let packet = Packet::Request(Request::single(PacketKind::Status));
+//! let payload = wire::encode_payload(&packet).unwrap();
+//! let frame = wire::encode_frame(wire::PacketId::new(packet.packet_id()), &payload).unwrap();
+//!
+//! let (header, payload) = wire::decode_frame(&frame).unwrap();
+//! assert_eq!(wire::decode_payload(header.packet_id.as_u8(), &payload).unwrap(), packet);
+//! ```
+//!
+//! # Not public
+//!
+//! The parser's buffering, the per-packet decode helpers behind [`Packet`] and
+//! the serde adapters used by the configuration packets are implementation
+//! details. The examples below document that and fail to compile on purpose.
+//!
+//! ```compile_fail
+//! // Decode through `wire::decode_payload` instead
+//! let _ = liban::Packet::from_bytes(20, &[]);
+//! ```
+//!
+//! ```compile_fail
+//! // Encode through `wire::encode_payload` instead
+//! let packet = liban::Packet::Unsupported { id: 250, payload: liban::wire::Payload::new() };
+//! let _ = packet.payload_bytes();
+//! ```
+//!
+//! ```compile_fail
+//! let parser = liban::AnppParser::new();
+//! let _ = parser.buf;
+//! ```
+//!
+//! ```compile_fail
+//! let _ = liban::protocol::AnppProtocol::parse_bytes(&[]);
+//! ```
+//!
+//! ```compile_fail
+//! use liban::packet::config::duration_as_micros_u16;
+//! ```
+//!
+//! [`AnppParser`]: crate::AnppParser
+//! [`AnppReader`]: crate::reader::AnppReader
+
+use std::io::{IoSlice, Write};
+
+use crate::error::{AnError, Result};
+use crate::packet::{Packet, PacketKind};
+use crate::profile::ProtocolProfile;
+
+pub use crate::packet::{AnppHeader, PacketId, Payload};
+pub use crate::protocol::AnppProtocol;
+
+/// Size of the ANPP header in bytes
+pub const HEADER_LEN: usize = 5;
+
+/// Largest payload a single ANPP frame can carry
+pub const MAX_PAYLOAD_LEN: usize = 255;
+
+/// Frame a payload with an ANPP header
+pub fn encode_frame(packet_id: PacketId, payload: &[u8]) -> Result<Vec<u8>> {
+    AnppProtocol::get_packet_bytes(packet_id, payload)
+}
+
+/// Header for a payload, without copying the payload. Write the header
+/// followed by the payload to send the frame.
+pub fn encode_header(packet_id: PacketId, payload: &[u8]) -> Result<[u8; HEADER_LEN]> {
+    if payload.len() > MAX_PAYLOAD_LEN {
+        return Err(AnError::PacketTooLong(payload.len()));
+    }
+    let length = payload.len() as u8;
+    let crc16 = AnppProtocol::calculate_crc16(payload);
+    let lrc = AnppProtocol::calculate_lrc(packet_id.as_u8(), length, crc16);
+    let [crc_low, crc_high] = crc16.to_le_bytes();
+    Ok([lrc, packet_id.as_u8(), length, crc_low, crc_high])
+}
+
+/// A frame kept as separate header and payload buffers
+///
+/// Neither part is heap allocated, and
+/// [`io_slices`](Self::io_slices) hands both to a vectored write.
+///
+/// ```
+/// use liban::wire::EncodedFrame;
+/// use liban::{Packet, PacketKind, Request};
+///
+/// let packet = Packet::Request(Request::single(PacketKind::Status));
+/// let frame = EncodedFrame::encode(&packet).unwrap();
+/// let mut out = Vec::new();
+/// frame.write_to(&mut out).unwrap();
+/// assert_eq!(out, packet.to_bytes().unwrap());
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EncodedFrame {
+    pub header: [u8; HEADER_LEN],
+    pub payload: Payload,
+}
+
+impl EncodedFrame {
+    /// Encode a packet. Fails for packets that cannot be sent.
+    pub fn encode(packet: &Packet) -> Result<Self> {
+        if !PacketKind::from(packet.packet_id()).direction().is_writable() {
+            return Err(AnError::NotWritable { id: packet.packet_id() });
+        }
+        Ok(Self::new(PacketId::new(packet.packet_id()), packet.payload()?))
+    }
+
+    /// Frame an already serialized payload
+    pub fn new(packet_id: PacketId, payload: Payload) -> Self {
+        let header = encode_header(packet_id, &payload).expect("a Payload always fits in a frame");
+        Self { header, payload }
+    }
+
+    /// Frame length in bytes
+    pub fn len(&self) -> usize {
+        HEADER_LEN + self.payload.len()
+    }
+
+    /// Always false, a frame has at least its header
+    pub fn is_empty(&self) -> bool {
+        false
+    }
+
+    pub fn io_slices(&self) -> [IoSlice<'_>; 2] {
+        [IoSlice::new(&self.header), IoSlice::new(&self.payload)]
+    }
+
+    /// Write the whole frame with vectored writes, retrying short writes
+    pub fn write_to<W: Write + ?Sized>(&self, writer: &mut W) -> Result<()> {
+        let mut slices = self.io_slices();
+        let mut slices = &mut slices[..];
+        while !slices.is_empty() {
+            match writer.write_vectored(slices) {
+                Ok(0) => return Err(std::io::Error::from(std::io::ErrorKind::WriteZero).into()),
+                Ok(n) => IoSlice::advance_slices(&mut slices, n),
+                Err(e) if e.kind() == std::io::ErrorKind::Interrupted => {}
+                Err(e) => return Err(e.into()),
+            }
+        }
+        Ok(())
+    }
+
+    /// The frame as one contiguous buffer
+    pub fn to_vec(&self) -> Vec<u8> {
+        let mut frame = Vec::with_capacity(self.len());
+        frame.extend_from_slice(&self.header);
+        frame.extend_from_slice(&self.payload);
+        frame
+    }
+}
+
+/// Frame several packets back to back in one buffer, e.g. a Request and
+/// some configuration writes to send in a single write. Fails if any
+/// packet cannot be sent.
+pub fn encode_batch(packets: &[Packet]) -> Result<Vec<u8>> {
+    let mut out = Vec::new();
+    for packet in packets {
+        out.extend_from_slice(&packet.to_bytes()?);
+    }
+    Ok(out)
+}
+
+/// Split one complete ANPP frame into its validated header and payload
+pub fn decode_frame(frame: &[u8]) -> Result<(AnppHeader, Vec<u8>)> {
+    AnppProtocol::get_header_from_bytes(frame)
+}
+
+/// Serialize a packet's payload without ANPP framing
+pub fn encode_payload(packet: &Packet) -> Result<Vec<u8>> {
+    packet.payload_bytes()
+}
+
+/// Decode an unframed payload for the given packet ID
+pub fn decode_payload(packet_id: u8, payload: &[u8]) -> Result<Packet> {
+    Packet::from_bytes(packet_id, payload)
+}
+
+/// Decode an unframed payload whose length is checked against `profile`,
+/// returning the packet and any trailing bytes the profile ignores
+///
+/// ```
+/// use liban::profile::ProtocolProfile;
+/// use liban::{wire, Packet};
+///
+/// // Unix Time from a firmware variant with four extra bytes
+/// let payload = [0x00, 0xF1, 0x53, 0x65, 5, 0, 0, 0, 0xAA, 0xBB, 0xCC, 0xDD];
+/// assert!(wire::decode_payload_with_profile(21, &payload, &ProtocolProfile::strict()).is_err());
+/// let (packet, trailing) = wire::decode_payload_with_profile(21, &payload, &ProtocolProfile::lenient()).unwrap();
+/// assert!(matches!(packet, Packet::UnixTime(ref time) if time.microseconds == 5));
+/// assert_eq!(trailing, [0xAA, 0xBB, 0xCC, 0xDD]);
+/// ```
+pub fn decode_payload_with_profile<'a>(packet_id: u8, payload: &'a [u8], profile: &ProtocolProfile) -> Result<(Packet, &'a [u8])> {
+    let kind = PacketKind::from(packet_id);
+    if !profile.accepts_length(kind, payload.len()) {
+        let expected = kind.byte_length().unwrap_or(kind.min_length());
+        return Err(AnError::LengthMismatch { id: packet_id, expected, got: payload.len() });
+    }
+    let (payload, trailing) = payload.split_at(profile.decoded_length(kind, payload.len()));
+    Ok((Packet::from_bytes(packet_id, payload)?, trailing))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::packet::system::Request;
+
+    #[test]
+    fn test_frame_round_trip() {
+        let frame = encode_frame(PacketId::new(1), &[20, 23]).unwrap();
+        assert_eq!(frame.len(), HEADER_LEN + 2);
+
+        let (header, payload) = decode_frame(&frame).unwrap();
+        assert_eq!(header.packet_id, PacketId::new(1));
+        assert_eq!(payload, vec![20, 23]);
+    }
+
+    #[test]
+    fn test_payload_round_trip() {
+        let packet = Packet::Request(Request::multiple([PacketKind::SystemState, PacketKind::Status]));
+        let payload = encode_payload(&packet).unwrap();
+        assert_eq!(decode_payload(1, &payload).unwrap(), packet);
+    }
+
+    #[test]
+    fn test_decode_frame_errors() {
+        let frame = encode_frame(PacketId::new(1), &[20, 23]).unwrap();
+
+        assert!(matches!(decode_frame(&frame[..3]), Err(AnError::Truncated { needed: 2 })));
+        assert!(matches!(decode_frame(&frame[..6]), Err(AnError::Truncated { needed: 1 })));
+        let mut long = frame.clone();
+        long.push(0);
+        assert!(matches!(decode_frame(&long), Err(AnError::LengthMismatch { id: 1, expected: 7, got: 8 })));
+
+        let mut corrupt = frame.clone();
+        corrupt[6] ^= 0xFF;
+        match decode_frame(&corrupt) {
+            Err(AnError::CrcMismatch { expected, actual }) => {
+                assert_eq!(expected, u16::from_le_bytes([frame[3], frame[4]]));
+                assert_ne!(expected, actual);
+            }
+            other => panic!("unexpected {:?}", other),
+        }
+
+        let mut bad_lrc = frame.clone();
+        bad_lrc[0] ^= 0xFF;
+        assert!(matches!(decode_frame(&bad_lrc), Err(AnError::LrcMismatch { .. })));
+    }
+
+    #[test]
+    fn test_payload_errors() {
+        assert!(matches!(decode_payload(20, &[0; 10]), Err(AnError::Decode { packet: "SystemState", .. })));
+        let ack = Packet::Acknowledge(crate::packet::system::Acknowledge {
+            acknowledged_packet: PacketKind::FilterOptions,
+            packet_crc: 0,
+            result: crate::packet::system::AcknowledgeResult::Success,
+        });
+        assert!(matches!(ack.to_bytes(), Err(AnError::NotWritable { id: 0 })));
+    }
+
+    #[test]
+    fn test_oversized_payload_rejected() {
+        assert!(encode_frame(PacketId::new(10), &[0; MAX_PAYLOAD_LEN + 1]).is_err());
+    }
+
+    /// Accepts at most three bytes per call, one slice at a time
+    #[derive(Default)]
+    struct Trickle(Vec<u8>, usize);
+
+    impl Write for Trickle {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            let n = buf.len().min(3);
+            self.0.extend_from_slice(&buf[..n]);
+            self.1 += 1;
+            Ok(n)
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_encoded_frame_matches_contiguous_encoding() {
+        let packet = Packet::Request(Request::multiple([PacketKind::SystemState, PacketKind::Status]));
+        let frame = EncodedFrame::encode(&packet).unwrap();
+        let contiguous = packet.to_bytes().unwrap();
+        assert_eq!(frame.to_vec(), contiguous);
+        assert_eq!(frame.len(), contiguous.len());
+        assert_eq!(frame.header, encode_header(PacketId::new(1), &frame.payload).unwrap());
+        assert_eq!(frame.io_slices().iter().map(|s| s.len()).sum::<usize>(), frame.len());
+
+        let mut out = Trickle::default();
+        frame.write_to(&mut out).unwrap();
+        assert_eq!(out.0, contiguous);
+        assert_eq!(out.1, 3);
+
+        let oversized = Packet::Request(Request::multiple(vec![PacketKind::Status; MAX_PAYLOAD_LEN + 1]));
+        assert!(matches!(EncodedFrame::encode(&oversized), Err(AnError::PacketTooLong(256))));
+        assert!(matches!(oversized.to_bytes(), Err(AnError::PacketTooLong(256))));
+    }
+
+    #[test]
+    fn test_encode_batch() {
+        let first = Packet::Request(Request::single(PacketKind::Status));
+        let second = Packet::Request(Request::single(PacketKind::DeviceInformation));
+        let batch = encode_batch(&[first.clone(), second.clone()]).unwrap();
+
+        let mut parser = crate::AnppParser::new();
+        assert_eq!(parser.consume(&batch).unwrap(), first);
+        assert_eq!(parser.consume(&[]).unwrap(), second);
+
+        let ack = Packet::Acknowledge(crate::packet::system::Acknowledge {
+            acknowledged_packet: PacketKind::FilterOptions,
+            packet_crc: 0,
+            result: crate::packet::system::AcknowledgeResult::Success,
+        });
+        assert!(matches!(encode_batch(&[first, ack]), Err(AnError::NotWritable { id: 0 })));
+        assert!(encode_batch(&[]).unwrap().is_empty());
+    }
+}
@@ -0,0 +1,170 @@
+use crate::error::Result;
+use crate::packet::Packet;
+use crate::wire::EncodedFrame;
+
+use std::io::Write;
+
+/// Payload of a typical Ethernet TCP segment. Batches up to this size go
+/// out in a single segment.
+pub const DEFAULT_CAPACITY: usize = 1460;
+
+/// Coalesces ANPP frames into as few writes as possible.
+///
+/// The device accepts frames back to back, so a Request followed by a
+/// handful of configuration writes can share one buffer and one syscall.
+/// Frames are buffered until the next frame would overflow the capacity or
+/// [`flush`](Self::flush) is called. Dropping the writer discards frames
+/// that have not been flushed.
+///
+/// # Examples
+///
+/// ```no_run
+/// use liban::writer::FrameWriter;
+/// use liban::{Packet, PacketKind, Request};
+/// use std::net::TcpStream;
+///
+/// # fn main() -> liban::error::Result<()> {
+/// let stream = TcpStream::connect("192.168.1.20:16718")?;
+/// let mut writer = FrameWriter::new(stream);
+/// writer.write_packet(&Packet::Request(Request::single(PacketKind::DeviceInformation)))?;
+/// writer.write_packet(&Packet::Request(Request::single(PacketKind::FilterOptions)))?;
+/// writer.flush()?;
+/// # Ok(())
+/// # }
+/// ```
+pub struct FrameWriter<W: Write> {
+    writer: W,
+    buffer: Vec<u8>,
+    capacity: usize,
+}
+
+impl<W: Write> FrameWriter<W> {
+    pub fn new(writer: W) -> Self {
+        Self::with_capacity(writer, DEFAULT_CAPACITY)
+    }
+
+    /// Flush automatically once `capacity` bytes are buffered. A single
+    /// frame larger than the capacity is still written whole.
+    pub fn with_capacity(writer: W, capacity: usize) -> Self {
+        Self { writer, buffer: Vec::with_capacity(capacity), capacity }
+    }
+
+    /// Frame and buffer a packet. Fails without buffering anything for
+    /// packets that cannot be sent.
+    pub fn write_packet(&mut self, packet: &Packet) -> Result<()> {
+        self.write_encoded(&EncodedFrame::encode(packet)?)
+    }
+
+    /// Buffer a frame encoded as separate header and payload
+    pub fn write_encoded(&mut self, frame: &EncodedFrame) -> Result<()> {
+        if !self.buffer.is_empty() && self.buffer.len() + frame.len() > self.capacity {
+            self.flush()?;
+        }
+        self.buffer.extend_from_slice(&frame.header);
+        self.buffer.extend_from_slice(&frame.payload);
+        Ok(())
+    }
+
+    /// Buffer an already framed packet
+    pub fn write_frame(&mut self, frame: &[u8]) -> Result<()> {
+        if !self.buffer.is_empty() && self.buffer.len() + frame.len() > self.capacity {
+            self.flush()?;
+        }
+        self.buffer.extend_from_slice(frame);
+        Ok(())
+    }
+
+    /// Write all buffered frames in one call
+    pub fn flush(&mut self) -> Result<()> {
+        if !self.buffer.is_empty() {
+            #[cfg(feature = "tracing")]
+            tracing::debug!("Writing {} buffered bytes", self.buffer.len());
+            self.writer.write_all(&self.buffer)?;
+            self.buffer.clear();
+        }
+        self.writer.flush()?;
+        Ok(())
+    }
+
+    /// Bytes waiting for the next flush
+    pub fn buffered(&self) -> usize {
+        self.buffer.len()
+    }
+
+    pub fn get_ref(&self) -> &W {
+        &self.writer
+    }
+
+    /// Flush and return the underlying writer
+    pub fn into_inner(mut self) -> Result<W> {
+        self.flush()?;
+        Ok(self.writer)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::AnError;
+    use crate::packet::PacketKind;
+    use crate::packet::config::PacketTimerPeriod;
+    use crate::packet::state::UnixTime;
+    use crate::packet::system::Request;
+    use crate::wire;
+
+    /// Records each write call separately
+    #[derive(Default)]
+    struct Writes(Vec<Vec<u8>>);
+
+    impl Write for Writes {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0.push(buf.to_vec());
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    fn packets() -> Vec<Packet> {
+        vec![
+            Packet::Request(Request::single(PacketKind::DeviceInformation)),
+            Packet::PacketTimerPeriod(PacketTimerPeriod::from_rate_hz(1000.0).unwrap()),
+            Packet::Request(Request::multiple([PacketKind::FilterOptions, PacketKind::PacketTimerPeriod])),
+        ]
+    }
+
+    #[test]
+    fn test_coalesces_into_one_write() {
+        let mut writer = FrameWriter::new(Writes::default());
+        for packet in &packets() {
+            writer.write_packet(packet).unwrap();
+        }
+        assert!(writer.get_ref().0.is_empty());
+        let writes = writer.into_inner().unwrap().0;
+
+        assert_eq!(writes.len(), 1);
+        assert_eq!(writes[0], wire::encode_batch(&packets()).unwrap());
+    }
+
+    #[test]
+    fn test_flushes_at_capacity() {
+        let frame = packets()[0].to_bytes().unwrap();
+        let mut writer = FrameWriter::with_capacity(Writes::default(), frame.len() * 2);
+        for _ in 0..5 {
+            writer.write_frame(&frame).unwrap();
+        }
+        assert_eq!(writer.buffered(), frame.len());
+        let writes = writer.into_inner().unwrap().0;
+        assert_eq!(writes.iter().map(Vec::len).collect::<Vec<_>>(), vec![frame.len() * 2, frame.len() * 2, frame.len()]);
+    }
+
+    #[test]
+    fn test_rejects_read_only_packets() {
+        let mut writer = FrameWriter::new(Writes::default());
+        let read_only = Packet::UnixTime(UnixTime { unix_time_seconds: 0, microseconds: 0 });
+        assert!(matches!(writer.write_packet(&read_only), Err(AnError::NotWritable { id: 21 })));
+        assert_eq!(writer.buffered(), 0);
+    }
+}
@@ -0,0 +1,85 @@
+//! liban-cli against a simulated device on a localhost TCP port.
+
+#![cfg(feature = "cli")]
+
+use std::io::{Read, Write};
+use std::net::TcpListener;
+use std::process::{Command, Output};
+use std::thread;
+use std::time::Duration;
+
+use liban::sim::MockBoreas;
+use liban::sim::trajectory::Trajectory;
+use liban::units::{Angle, Length};
+
+/// Serve a MockBoreas to one connection at a time and return the port
+fn spawn_device(connections: usize) -> u16 {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let port = listener.local_addr().unwrap().port();
+    thread::spawn(move || {
+        let mut device = MockBoreas::new(Trajectory::Static {
+            latitude: Angle::from_degrees(-33.8),
+            longitude: Angle::from_degrees(151.2),
+            height: Length::from_meters(10.0),
+        });
+        for _ in 0..connections {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut buffer = [0u8; 4096];
+            while let Ok(n) = stream.read(&mut buffer) {
+                if n == 0 || stream.write_all(&device.feed(&buffer[..n])).is_err() {
+                    break;
+                }
+            }
+        }
+    });
+    port
+}
+
+fn cli(port: u16, args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_liban-cli"))
+        .args(["--ip", "127.0.0.1", "--port", &port.to_string()])
+        .args(args)
+        .output()
+        .unwrap()
+}
+
+#[test]
+fn test_info() {
+    let port = spawn_device(1);
+    let output = cli(port, &["info"]);
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("Serial number 00000001-00000000-00000000"), "{}", stdout);
+}
+
+#[test]
+fn test_config_round_trip() {
+    let port = spawn_device(2);
+    let profile = std::env::temp_dir().join(format!("liban-cli-profile-{}.json", std::process::id()));
+    let path = profile.to_str().unwrap();
+
+    let output = cli(port, &["get-config", "--output", path]);
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    let text = std::fs::read_to_string(&profile).unwrap();
+    assert!(text.contains("\"FilterOptions\""), "{}", text);
+
+    thread::sleep(Duration::from_millis(50));
+    let output = cli(port, &["set-config", "--file", path]);
+    std::fs::remove_file(&profile).unwrap();
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+}
+
+#[test]
+fn test_replay_and_unknown_packet() {
+    let recording = std::env::temp_dir().join(format!("liban-cli-replay-{}.bin", std::process::id()));
+    let frame = liban::Packet::Request(liban::Request::for_packet::<liban::SystemState>()).to_bytes().unwrap();
+    std::fs::write(&recording, [frame.clone(), vec![0xAA], frame].concat()).unwrap();
+
+    let output = cli(0, &["replay", recording.to_str().unwrap()]);
+    std::fs::remove_file(&recording).unwrap();
+    assert!(output.status.success());
+    assert_eq!(String::from_utf8_lossy(&output.stdout).lines().count(), 2);
+
+    let output = cli(0, &["watch", "NotAPacket"]);
+    assert!(!output.status.success());
+}
@@ -0,0 +1,813 @@
+//! Byte-exact vectors for every packet, hand-encoded from the field tables
+//! of the ANPP reference manual.
+//!
+//! The unit tests round-trip each struct through binrw, which passes even
+//! when two fields are swapped or one has the wrong width. Here every
+//! payload is written out field by field in the manual's order with
+//! `to_le_bytes`, without going through this crate, and must decode to
+//! the expected packet and re-encode to the same bytes. Neighbouring fields
+//! mostly hold distinct values so a swap shows up, and floats are exactly
+//! representable so equality is exact.
+//!
+//! The frame vectors check the header (LRC, ID, length, CRC16-CCITT over
+//! the payload) against values computed independently of this crate.
+
+use std::collections::HashSet;
+use std::net::{Ipv4Addr, SocketAddrV4};
+use std::time::Duration;
+
+use liban::packet::config::*;
+use liban::packet::state::*;
+use liban::packet::system::*;
+use liban::units::Radians;
+use liban::wire::{self, PacketId};
+use liban::{AnppParser, Packet, PacketKind};
+
+/// A payload written field by field, little endian as the manual specifies
+#[derive(Default)]
+struct Fields(Vec<u8>);
+
+impl Fields {
+    fn new() -> Self {
+        Self::default()
+    }
+
+    fn u8(mut self, value: u8) -> Self {
+        self.0.push(value);
+        self
+    }
+
+    fn i8(self, value: i8) -> Self {
+        self.bytes(&value.to_le_bytes())
+    }
+
+    fn u16(self, value: u16) -> Self {
+        self.bytes(&value.to_le_bytes())
+    }
+
+    fn u32(self, value: u32) -> Self {
+        self.bytes(&value.to_le_bytes())
+    }
+
+    fn i32(self, value: i32) -> Self {
+        self.bytes(&value.to_le_bytes())
+    }
+
+    fn f32(self, value: f32) -> Self {
+        self.bytes(&value.to_le_bytes())
+    }
+
+    fn f64(self, value: f64) -> Self {
+        self.bytes(&value.to_le_bytes())
+    }
+
+    fn f32s(self, values: &[f32]) -> Self {
+        values.iter().fold(self, |fields, value| fields.f32(*value))
+    }
+
+    fn bytes(mut self, bytes: &[u8]) -> Self {
+        self.0.extend_from_slice(bytes);
+        self
+    }
+
+    fn reserved(self, len: usize) -> Self {
+        self.bytes(&vec![0; len])
+    }
+}
+
+fn offset(x: f32, y: f32, z: f32) -> OffsetVector {
+    OffsetVector { x, y, z }
+}
+
+fn system_vectors() -> Vec<(Packet, Fields)> {
+    vec![
+        (
+            Packet::Acknowledge(Acknowledge {
+                acknowledged_packet: PacketKind::FilterOptions,
+                packet_crc: 0x1234,
+                result: AcknowledgeResult::ValuesOutOfRange,
+            }),
+            Fields::new().u8(186).u16(0x1234).u8(3),
+        ),
+        (
+            Packet::Request(Request::multiple(vec![PacketKind::SystemState, PacketKind::RawSensors, PacketKind::DeviceInformation])),
+            Fields::new().u8(20).u8(28).u8(3),
+        ),
+        (Packet::BootMode(BootMode { boot_mode: 1 }), Fields::new().u8(1)),
+        (
+            Packet::DeviceInformation(DeviceInformation {
+                software_version: 7_010,
+                device_type: DeviceType::Certus,
+                hardware_revision: 3,
+                serial_number_1: 0x0102_0304,
+                serial_number_2: 0x0506_0708,
+                serial_number_3: 0x090A_0B0C,
+            }),
+            Fields::new().u32(7_010).u32(26).u32(3).u32(0x0102_0304).u32(0x0506_0708).u32(0x090A_0B0C),
+        ),
+        (Packet::RestoreFactorySettings(RestoreFactorySettings {}), Fields::new().u32(0x8542_9E1C)),
+        (Packet::Reset(Reset {}), Fields::new().u32(0x2105_7A7E)),
+        (
+            Packet::FileTransferRequest(FileTransferRequest {
+                unique_id: 0xA1B2_C3D4,
+                data_index: 0,
+                response_required: true,
+                data_encoding: FileTransferEncoding::Binary,
+                metadata_type: FileTransferMetadata::Utf8Filename,
+                metadata: b"log.bin".to_vec(),
+                data: vec![0xDE, 0xAD],
+            }),
+            Fields::new().u32(0xA1B2_C3D4).u32(0).u8(1).u8(0).u8(2).u16(7).bytes(b"log.bin").bytes(&[0xDE, 0xAD]),
+        ),
+        (
+            Packet::FileTransferAcknowledge(FileTransferAcknowledge {
+                unique_id: 0xA1B2_C3D4,
+                data_index: 512,
+                response: FileTransferResponse::IndexMismatch,
+            }),
+            Fields::new().u32(0xA1B2_C3D4).u32(512).u8(2),
+        ),
+        (
+            Packet::FileTransferData(FileTransferData {
+                unique_id: 0xA1B2_C3D4,
+                data_index: 256,
+                response_required: false,
+                data: vec![1, 2, 3, 4],
+            }),
+            Fields::new().u32(0xA1B2_C3D4).u32(256).u8(0).bytes(&[1, 2, 3, 4]),
+        ),
+        (
+            Packet::SerialPortPassthrough(SerialPortPassthrough { route: PassthroughRoute::AuxiliaryRs232, data: b"$GP".to_vec() }),
+            Fields::new().u8(2).bytes(b"$GP"),
+        ),
+        (
+            Packet::IpConfiguration(IpConfiguration {
+                permanent: true,
+                dhcp_mode: 0,
+                ip_address: Ipv4Addr::new(192, 168, 1, 10),
+                ip_netmask: Ipv4Addr::new(255, 255, 255, 0),
+                ip_gateway: Ipv4Addr::new(192, 168, 1, 1),
+                dns_server: Ipv4Addr::new(8, 8, 4, 4),
+                boreas_serial_number_part_1: 11,
+                boreas_serial_number_part_2: 22,
+                boreas_serial_number_part_3: 33,
+            }),
+            Fields::new().u8(1).u8(0)
+                .bytes(&[10, 1, 168, 192]).bytes(&[0, 255, 255, 255]).bytes(&[1, 1, 168, 192]).bytes(&[4, 4, 8, 8])
+                .u32(11).u32(22).u32(33),
+        ),
+        (
+            Packet::SubcomponentInformation(SubcomponentInformation {
+                subcomponents: vec![
+                    Subcomponent {
+                        software_version: 1_200,
+                        device_id: 19,
+                        hardware_revision: 2,
+                        serial_number_1: 4,
+                        serial_number_2: 5,
+                        serial_number_3: 6,
+                    },
+                    Subcomponent {
+                        software_version: 1_300,
+                        device_id: 13,
+                        hardware_revision: 1,
+                        serial_number_1: 7,
+                        serial_number_2: 8,
+                        serial_number_3: 9,
+                    },
+                ],
+            }),
+            Fields::new()
+                .u32(1_200).u32(19).u32(2).u32(4).u32(5).u32(6)
+                .u32(1_300).u32(13).u32(1).u32(7).u32(8).u32(9),
+        ),
+    ]
+}
+
+fn state_vectors() -> Vec<(Packet, Fields)> {
+    vec![
+        (
+            Packet::SystemState(SystemState {
+                system_status: SystemStatus::from(0x0020),
+                filter_status: FilterStatus::from(0x0637),
+                unix_time_seconds: 1_700_000_000,
+                microseconds: 250_000,
+                latitude: -0.5,
+                longitude: 2.5,
+                height: 125.75,
+                velocity_north: 1.5,
+                velocity_east: -2.25,
+                velocity_down: 0.125,
+                body_acceleration_x: 0.5,
+                body_acceleration_y: -0.75,
+                body_acceleration_z: -9.75,
+                g_force: 1.0625,
+                roll: Radians(0.03125),
+                pitch: Radians(-0.0625),
+                heading: Radians(3.0),
+                angular_velocity_x: 0.001953125,
+                angular_velocity_y: -0.00390625,
+                angular_velocity_z: 0.0078125,
+                latitude_std_dev: 0.25,
+                longitude_std_dev: 0.375,
+                height_std_dev: 0.875,
+                extension: None,
+            }),
+            Fields::new().u16(0x0020).u16(0x0637).u32(1_700_000_000).u32(250_000)
+                .f64(-0.5).f64(2.5).f64(125.75)
+                .f32s(&[1.5, -2.25, 0.125, 0.5, -0.75, -9.75, 1.0625, 0.03125, -0.0625, 3.0])
+                .f32s(&[0.001953125, -0.00390625, 0.0078125, 0.25, 0.375, 0.875]),
+        ),
+        (
+            Packet::UnixTime(UnixTime { unix_time_seconds: 1_700_000_000, microseconds: 250_000 }),
+            Fields::new().u32(1_700_000_000).u32(250_000),
+        ),
+        (
+            Packet::FormattedTime(FormattedTime {
+                microseconds: 250_000,
+                year: 2023,
+                year_day: 317,
+                month: 11,
+                month_day: 14,
+                week_day: 2,
+                hour: 22,
+                minute: 13,
+                second: 20,
+            }),
+            Fields::new().u32(250_000).u16(2023).u16(317).u8(11).u8(14).u8(2).u8(22).u8(13).u8(20),
+        ),
+        (
+            Packet::Status(Status { system_status: SystemStatus::from(0x4001), filter_status: FilterStatus::from(0x0180), extension: None }),
+            Fields::new().u16(0x4001).u16(0x0180),
+        ),
+        (
+            Packet::PositionStdDev(PositionStdDev { latitude_std_dev: 0.5, longitude_std_dev: 0.75, height_std_dev: 1.25 }),
+            Fields::new().f32s(&[0.5, 0.75, 1.25]),
+        ),
+        (
+            Packet::VelocityStdDev(VelocityStdDev { velocity_north_std_dev: 0.0625, velocity_east_std_dev: 0.125, velocity_down_std_dev: 0.25 }),
+            Fields::new().f32s(&[0.0625, 0.125, 0.25]),
+        ),
+        (
+            Packet::EulerOrientationStdDev(EulerOrientationStdDev {
+                roll_std_dev: Radians(0.001),
+                pitch_std_dev: Radians(0.002),
+                heading_std_dev: Radians(0.004),
+            }),
+            Fields::new().f32s(&[0.001, 0.002, 0.004]),
+        ),
+        (
+            Packet::QuaternionOrientationStdDev(QuaternionOrientationStdDev { q0_std_dev: 0.1, q1_std_dev: 0.2, q2_std_dev: 0.3, q3_std_dev: 0.4 }),
+            Fields::new().f32s(&[0.1, 0.2, 0.3, 0.4]),
+        ),
+        (
+            Packet::RawSensors(RawSensors {
+                accelerometer_x: 0.25,
+                accelerometer_y: -0.5,
+                accelerometer_z: -9.75,
+                gyroscope_x: 0.01,
+                gyroscope_y: -0.02,
+                gyroscope_z: 0.03,
+                imu_temperature: 35.5,
+                pressure: 101_325.0,
+                pressure_temperature: 30.25,
+            }),
+            // Magnetometer x, y and z are reserved on devices without one
+            Fields::new().f32s(&[0.25, -0.5, -9.75, 0.01, -0.02, 0.03]).reserved(12).f32s(&[35.5, 101_325.0, 30.25]),
+        ),
+        (
+            Packet::RawGnss(RawGnss {
+                unix_time_seconds: 1_700_000_001,
+                microseconds: 500_000,
+                latitude: -0.25,
+                longitude: 1.75,
+                height: 30.5,
+                velocity_north: 2.0,
+                velocity_east: -1.0,
+                velocity_down: 0.5,
+                latitude_std_dev: 1.5,
+                longitude_std_dev: 1.25,
+                height_std_dev: 2.5,
+                tilt: Radians(0.0625),
+                heading: Radians(1.5),
+                tilt_std_dev: Radians(0.125),
+                heading_std_dev: Radians(0.25),
+                status: RawGnssStatus::from(0x0607),
+            }),
+            Fields::new().u32(1_700_000_001).u32(500_000).f64(-0.25).f64(1.75).f64(30.5)
+                .f32s(&[2.0, -1.0, 0.5, 1.5, 1.25, 2.5, 0.0625, 1.5, 0.125, 0.25]).u16(0x0607),
+        ),
+        (
+            Packet::Satellites(Satellites {
+                hdop: 0.75,
+                vdop: 1.25,
+                gps_satellites: 9,
+                glonass_satellites: 7,
+                beidou_satellites: 5,
+                galileo_satellites: 6,
+                sbas_satellites: 2,
+            }),
+            Fields::new().f32(0.75).f32(1.25).u8(9).u8(7).u8(5).u8(6).u8(2),
+        ),
+        (
+            Packet::DetailedSatellites(DetailedSatellites {
+                satellites: vec![
+                    SatelliteInfo {
+                        system: SatelliteSystem::Gps,
+                        prn: 12,
+                        frequencies: SatelliteFrequencies::from(0x81),
+                        elevation: 45,
+                        azimuth: 270,
+                        snr: 42,
+                    },
+                    SatelliteInfo {
+                        system: SatelliteSystem::Galileo,
+                        prn: 3,
+                        frequencies: SatelliteFrequencies::from(0x01),
+                        elevation: -2,
+                        azimuth: 15,
+                        snr: 31,
+                    },
+                ],
+            }),
+            Fields::new()
+                .u8(1).u8(12).u8(0x81).i8(45).u16(270).u8(42)
+                .u8(4).u8(3).u8(0x01).i8(-2).u16(15).u8(31),
+        ),
+        (
+            Packet::GeodeticPosition(GeodeticPosition { latitude: -0.5, longitude: 2.5, height: 125.75 }),
+            Fields::new().f64(-0.5).f64(2.5).f64(125.75),
+        ),
+        (
+            Packet::EcefPosition(EcefPosition { x: -4_646_000.5, y: 2_553_000.25, z: -3_534_000.125 }),
+            Fields::new().f64(-4_646_000.5).f64(2_553_000.25).f64(-3_534_000.125),
+        ),
+        (
+            Packet::UtmPosition(UtmPosition { northing: 6_250_000.5, easting: 330_000.25, height: 40.5, zone_number: 56, zone_char: b'H' as i8 }),
+            Fields::new().f64(6_250_000.5).f64(330_000.25).f64(40.5).u8(56).i8(b'H' as i8),
+        ),
+        (
+            Packet::NedVelocity(NedVelocity { velocity_north: 1.5, velocity_east: -2.25, velocity_down: 0.125 }),
+            Fields::new().f32s(&[1.5, -2.25, 0.125]),
+        ),
+        (
+            Packet::BodyVelocity(BodyVelocity { velocity_x: 3.5, velocity_y: -0.25, velocity_z: 0.0625 }),
+            Fields::new().f32s(&[3.5, -0.25, 0.0625]),
+        ),
+        (
+            Packet::Acceleration(Acceleration { acceleration_x: 0.5, acceleration_y: -1.5, acceleration_z: 2.5 }),
+            Fields::new().f32s(&[0.5, -1.5, 2.5]),
+        ),
+        (
+            Packet::BodyAcceleration(BodyAcceleration {
+                body_acceleration_x: 0.5,
+                body_acceleration_y: -0.75,
+                body_acceleration_z: -9.75,
+                g_force: 1.0625,
+            }),
+            Fields::new().f32s(&[0.5, -0.75, -9.75, 1.0625]),
+        ),
+        (
+            Packet::EulerOrientation(EulerOrientation { roll: Radians(0.03125), pitch: Radians(-0.0625), heading: Radians(3.0) }),
+            Fields::new().f32s(&[0.03125, -0.0625, 3.0]),
+        ),
+        (
+            Packet::QuaternionOrientation(QuaternionOrientation { q0: 0.5, q1: -0.5, q2: 0.25, q3: -0.75 }),
+            Fields::new().f32s(&[0.5, -0.5, 0.25, -0.75]),
+        ),
+        (
+            Packet::DcmOrientation(DcmOrientation { dcm: [[1.0, 2.0, 3.0], [4.0, 5.0, 6.0], [7.0, 8.0, 9.0]] }),
+            // Row major
+            Fields::new().f32s(&[1.0, 2.0, 3.0, 4.0, 5.0, 6.0, 7.0, 8.0, 9.0]),
+        ),
+        (
+            Packet::AngularVelocity(AngularVelocity { angular_velocity_x: 0.001953125, angular_velocity_y: -0.00390625, angular_velocity_z: 0.0078125 }),
+            Fields::new().f32s(&[0.001953125, -0.00390625, 0.0078125]),
+        ),
+        (
+            Packet::AngularAcceleration(AngularAcceleration {
+                angular_acceleration_x: -0.125,
+                angular_acceleration_y: 0.25,
+                angular_acceleration_z: -0.5,
+            }),
+            Fields::new().f32s(&[-0.125, 0.25, -0.5]),
+        ),
+        (
+            Packet::ExternalPositionVelocity(ExternalPositionVelocity {
+                latitude: -0.5,
+                longitude: 2.5,
+                height: 125.75,
+                velocity_north: 1.5,
+                velocity_east: -2.25,
+                velocity_down: 0.125,
+                latitude_std_dev: 0.5,
+                longitude_std_dev: 0.75,
+                height_std_dev: 1.25,
+                velocity_north_std_dev: 0.0625,
+                velocity_east_std_dev: 0.125,
+                velocity_down_std_dev: 0.25,
+            }),
+            Fields::new().f64(-0.5).f64(2.5).f64(125.75).f32s(&[1.5, -2.25, 0.125, 0.5, 0.75, 1.25, 0.0625, 0.125, 0.25]),
+        ),
+        (
+            Packet::ExternalPosition(ExternalPosition {
+                latitude: -0.5,
+                longitude: 2.5,
+                height: 125.75,
+                latitude_std_dev: 0.5,
+                longitude_std_dev: 0.75,
+                height_std_dev: 1.25,
+            }),
+            Fields::new().f64(-0.5).f64(2.5).f64(125.75).f32s(&[0.5, 0.75, 1.25]),
+        ),
+        (
+            Packet::ExternalVelocity(ExternalVelocity {
+                velocity_north: 1.5,
+                velocity_east: -2.25,
+                velocity_down: 0.125,
+                velocity_north_std_dev: 0.0625,
+                velocity_east_std_dev: 0.125,
+                velocity_down_std_dev: 0.25,
+            }),
+            Fields::new().f32s(&[1.5, -2.25, 0.125, 0.0625, 0.125, 0.25]),
+        ),
+        (
+            Packet::ExternalBodyVelocity(ExternalBodyVelocity { velocity_x: 3.5, velocity_y: -0.25, velocity_z: 0.0625, standard_deviation: 0.125 }),
+            Fields::new().f32s(&[3.5, -0.25, 0.0625, 0.125]),
+        ),
+        (
+            Packet::ExternalHeading(ExternalHeading { heading: Radians(1.5), standard_deviation: Radians(0.0625) }),
+            Fields::new().f32s(&[1.5, 0.0625]),
+        ),
+        (
+            Packet::RunningTime(RunningTime { seconds: 3_600, microseconds: 125 }),
+            Fields::new().u32(3_600).u32(125),
+        ),
+        (
+            Packet::OdometerState(OdometerState { pulse_count: -1_234, distance: 56.5, speed: 2.75, slip: 0.125, active: true }),
+            Fields::new().i32(-1_234).f32s(&[56.5, 2.75, 0.125]).u8(1).reserved(3),
+        ),
+        (
+            Packet::ExternalTime(ExternalTime { unix_time_seconds: 1_700_000_002, microseconds: 750_000 }),
+            Fields::new().u32(1_700_000_002).u32(750_000),
+        ),
+        (
+            Packet::ExternalDepth(ExternalDepth { depth: 12.5, standard_deviation: 0.25 }),
+            Fields::new().f32s(&[12.5, 0.25]),
+        ),
+        (Packet::GeoidHeight(GeoidHeight { geoid_height: 22.75 }), Fields::new().f32(22.75)),
+        (
+            Packet::RtcmCorrections(RtcmCorrections { data: vec![0xD3, 0x00, 0x13, 0x3E] }),
+            Fields::new().bytes(&[0xD3, 0x00, 0x13, 0x3E]),
+        ),
+        (
+            Packet::Wind(Wind { wind_velocity_north: 4.5, wind_velocity_east: -3.25, wind_velocity_std_dev: 0.75 }),
+            Fields::new().f32s(&[4.5, -3.25, 0.75]),
+        ),
+        (
+            Packet::Heave(Heave { heave_point_1: 0.5, heave_point_2: -0.25, heave_point_3: 0.125, heave_point_4: -0.0625 }),
+            Fields::new().f32s(&[0.5, -0.25, 0.125, -0.0625]),
+        ),
+        (
+            Packet::AntennaOffsetEstimation(AntennaOffsetEstimation {
+                active: true,
+                converged: false,
+                offset: offset(0.5, -0.25, -1.5),
+                offset_std_dev: offset(0.0625, 0.125, 0.25),
+            }),
+            Fields::new().u8(1).u8(0).reserved(2).f32s(&[0.5, -0.25, -1.5, 0.0625, 0.125, 0.25]),
+        ),
+        (
+            Packet::ExternalAirData(ExternalAirData {
+                barometric_altitude_delay: 0.05,
+                airspeed_delay: 0.1,
+                barometric_altitude: 150.5,
+                airspeed: 42.25,
+                barometric_altitude_std_dev: 1.5,
+                airspeed_std_dev: 0.75,
+                flags: AirDataFlags::from(0x03),
+            }),
+            Fields::new().f32s(&[0.05, 0.1, 150.5, 42.25, 1.5, 0.75]).u8(0x03),
+        ),
+        (
+            Packet::GnssReceiverInformation(GnssReceiverInformation {
+                manufacturer: GnssManufacturer::UBlox,
+                receiver_model_id: 4,
+                serial_number: *b"ABCDEFGHIJKLMNOPQRSTUVWX",
+                firmware_version: 0x0102_0003,
+                hardware_version: 7,
+            }),
+            Fields::new().u8(2).u8(4).bytes(b"ABCDEFGHIJKLMNOPQRSTUVWX").u32(0x0102_0003).u32(7).reserved(34),
+        ),
+        (
+            Packet::RawDvlData(RawDvlData {
+                unix_time_seconds: 1_700_000_003,
+                microseconds: 10,
+                status: DvlStatus::from(0x0000_0105),
+                bottom_velocity_x: 1.5,
+                bottom_velocity_y: -0.5,
+                bottom_velocity_z: 0.25,
+                bottom_velocity_std_dev: 0.0625,
+                water_velocity_x: 1.25,
+                water_velocity_y: -0.75,
+                water_velocity_z: 0.125,
+                water_velocity_std_dev: 0.09375,
+                water_velocity_layer_depth: 4.5,
+                depth: 20.5,
+                altitude: 8.75,
+                temperature: 14.25,
+            }),
+            Fields::new().u32(1_700_000_003).u32(10).u32(0x0000_0105)
+                .f32s(&[1.5, -0.5, 0.25, 0.0625, 1.25, -0.75, 0.125, 0.09375, 4.5, 20.5, 8.75, 14.25]),
+        ),
+        (
+            Packet::NorthSeekingStatus(NorthSeekingStatus {
+                flags: NorthSeekingFlags::from(0x0013),
+                quadrant_progress: [100, 75, 50, 25],
+                current_rotation_angle: 1.5,
+                gyroscope_bias_x: 0.001,
+                gyroscope_bias_y: -0.002,
+                gyroscope_bias_z: 0.003,
+                gyroscope_bias_error: 0.0005,
+            }),
+            Fields::new().u16(0x0013).reserved(2).bytes(&[100, 75, 50, 25]).f32s(&[1.5, 0.001, -0.002, 0.003, 0.0005]),
+        ),
+        (
+            Packet::SensorTemperature(SensorTemperature {
+                accelerometer_temp_0: 30.5,
+                accelerometer_temp_1: 31.5,
+                accelerometer_temp_2: 32.5,
+                gyroscope_temp_0: 33.5,
+                gyroscope_temp_1: 34.5,
+                gyroscope_temp_2: 35.5,
+                pressure_sensor_temp: 28.25,
+            }),
+            // The magnetometer temperature is reserved
+            Fields::new().f32s(&[30.5, 31.5, 32.5, 33.5, 34.5, 35.5]).reserved(4).f32(28.25),
+        ),
+        (
+            Packet::GnssPositionVelocityTime(GnssPositionVelocityTime {
+                gnss_id: 1,
+                status: GnssPvtStatus::from(0x0604),
+                posix_time_seconds: 1_700_000_004,
+                posix_time_microseconds: 200_000,
+                latitude: -0.5,
+                longitude: 2.5,
+                altitude: 125.75,
+                position_std_dev_north: 0.5,
+                position_std_dev_east: 0.75,
+                position_std_dev_down: 1.25,
+                velocity_north: 1.5,
+                velocity_east: -2.25,
+                velocity_down: 0.125,
+                velocity_std_dev_north: 0.0625,
+                velocity_std_dev_east: 0.125,
+                velocity_std_dev_down: 0.25,
+                latency: 35,
+            }),
+            Fields::new().u8(1).reserved(1).u16(0x0604).u32(1_700_000_004).u32(200_000)
+                .f64(-0.5).f64(2.5).f64(125.75)
+                .f32s(&[0.5, 0.75, 1.25, 1.5, -2.25, 0.125, 0.0625, 0.125, 0.25]).u32(35),
+        ),
+        (
+            Packet::GnssOrientation(GnssOrientation {
+                gnss_id: 2,
+                status: GnssOrientationStatus::from(0x0003),
+                posix_time_seconds: 1_700_000_005,
+                posix_time_microseconds: 400_000,
+                azimuth: Radians(1.5),
+                azimuth_std_dev: Radians(0.0625),
+                tilt: Radians(-0.125),
+                tilt_std_dev: Radians(0.25),
+                baseline_length: 1.75,
+                latency: 40,
+            }),
+            Fields::new().u8(2).reserved(1).u16(0x0003).u32(1_700_000_005).u32(400_000)
+                .f32s(&[1.5, 0.0625, -0.125, 0.25, 1.75]).u32(40),
+        ),
+    ]
+}
+
+fn configuration_vectors() -> Vec<(Packet, Fields)> {
+    let rates = |gpio1, gpio2, auxiliary| GpioOutputRates::new(gpio1, gpio2, auxiliary);
+    vec![
+        (
+            Packet::PacketTimerPeriod(PacketTimerPeriod {
+                permanent: true,
+                utc_synchronisation: false,
+                packet_timer_period: Duration::from_micros(1_000),
+            }),
+            Fields::new().u8(1).u8(0).u16(1_000),
+        ),
+        (
+            Packet::PacketsPeriod(PacketsPeriod {
+                permanent: false,
+                clear_existing: true,
+                packet_periods: vec![
+                    PacketPeriod { packet_type: PacketKind::SystemState, divisor: 10 },
+                    PacketPeriod { packet_type: PacketKind::RawSensors, divisor: 100 },
+                ],
+            }),
+            Fields::new().u8(0).u8(1).u8(20).u32(10).u8(28).u32(100),
+        ),
+        (
+            Packet::BaudRates(BaudRates {
+                permanent: true,
+                primary_port: BaudRate::B115200,
+                gpio_port: BaudRate::B9600,
+                auxiliary_port: BaudRate::B921600,
+            }),
+            Fields::new().u8(1).u32(115_200).u32(9_600).u32(921_600).reserved(4),
+        ),
+        (
+            Packet::InstallationAlignment(InstallationAlignment {
+                permanent: true,
+                alignment_dcm: [[1.0, 0.0, 0.0], [0.0, 0.0, -1.0], [0.0, 1.0, 0.0]],
+                gnss_antenna_offset: offset(0.5, -0.25, -1.5),
+                odometer_offset: offset(-1.25, 0.75, 0.375),
+                external_data_offset: offset(2.0, 0.125, -0.5),
+            }),
+            Fields::new().u8(1)
+                .f32s(&[1.0, 0.0, 0.0, 0.0, 0.0, -1.0, 0.0, 1.0, 0.0])
+                .f32s(&[0.5, -0.25, -1.5, -1.25, 0.75, 0.375, 2.0, 0.125, -0.5]),
+        ),
+        (
+            Packet::FilterOptions(FilterOptions {
+                permanent: true,
+                vehicle_type: VehicleType::Boat,
+                internal_gnss_enabled: true,
+                atmospheric_altitude_enabled: false,
+                velocity_heading_enabled: true,
+                reversing_detection_enabled: false,
+                motion_analysis_enabled: true,
+            }),
+            // Field 4 was the magnetometer enable and is now reserved
+            Fields::new().u8(1).u8(9).u8(1).reserved(1).u8(0).u8(1).u8(0).u8(1).reserved(9),
+        ),
+        (
+            Packet::GpioConfiguration(GpioConfiguration {
+                permanent: false,
+                gpio1: GpioFunction::OnePpsOutput,
+                gpio2: GpioFunction::Event1Input,
+                auxiliary_transmit: GpioFunction::NmeaOutput,
+                auxiliary_receive: GpioFunction::RtcmCorrectionsInput,
+            }),
+            Fields::new().u8(0).u8(1).u8(33).u8(7).u8(19).reserved(8),
+        ),
+        (
+            Packet::MagneticCalibrationValues(MagneticCalibrationValues {
+                permanent: true,
+                hard_iron_bias: offset(0.5, -0.25, 0.125),
+                soft_iron_transformation: [[1.0, 0.0625, 0.0], [0.0625, 1.0, -0.125], [0.0, -0.125, 1.0]],
+            }),
+            Fields::new().u8(1).f32s(&[0.5, -0.25, 0.125]).f32s(&[1.0, 0.0625, 0.0, 0.0625, 1.0, -0.125, 0.0, -0.125, 1.0]),
+        ),
+        (
+            Packet::MagneticCalibrationConfiguration(MagneticCalibrationConfiguration { action: MagneticCalibrationAction::Start3D }),
+            Fields::new().u8(3),
+        ),
+        (
+            Packet::MagneticCalibrationStatus(MagneticCalibrationStatus {
+                status: MagneticCalibrationStatusCode::Completed3D,
+                progress: 100,
+                local_magnetic_error: 4,
+            }),
+            Fields::new().u8(2).u8(100).u8(4),
+        ),
+        (
+            Packet::OdometerConfiguration(OdometerConfiguration { permanent: true, automatic_pulse_measurement: false, pulse_length: 0.015625 }),
+            Fields::new().u8(1).u8(0).reserved(2).f32(0.015625),
+        ),
+        (
+            Packet::SetZeroOrientationAlignment(SetZeroOrientationAlignment { permanent: true }),
+            Fields::new().u8(1),
+        ),
+        (
+            Packet::ReferencePointOffsets(ReferencePointOffsets {
+                permanent: false,
+                heave_point_1: offset(0.5, 0.0, -1.0),
+                heave_point_2: offset(-2.0, 0.25, 0.0),
+                heave_point_3: offset(1.5, -0.75, 0.5),
+                heave_point_4: offset(3.0, 1.0, -0.25),
+            }),
+            Fields::new().u8(0).f32s(&[0.5, 0.0, -1.0, -2.0, 0.25, 0.0, 1.5, -0.75, 0.5, 3.0, 1.0, -0.25]),
+        ),
+        (
+            Packet::GpioOutputConfiguration(GpioOutputConfiguration {
+                permanent: true,
+                nmea_fix_behaviour: 1,
+                gpzda: rates(GpioOutputRate::Hz1, GpioOutputRate::Disabled, GpioOutputRate::Hz10),
+                gpgga: rates(GpioOutputRate::Hz5, GpioOutputRate::Hz2, GpioOutputRate::Disabled),
+                gpvtg: rates(GpioOutputRate::Disabled, GpioOutputRate::Hz1, GpioOutputRate::Disabled),
+                gprmc: rates(GpioOutputRate::Hz0_1, GpioOutputRate::Disabled, GpioOutputRate::Disabled),
+                gphdt: rates(GpioOutputRate::Disabled, GpioOutputRate::Disabled, GpioOutputRate::Hz0_2),
+                gpgll: rates(GpioOutputRate::Hz0_5, GpioOutputRate::Disabled, GpioOutputRate::Disabled),
+                pashr: rates(GpioOutputRate::Disabled, GpioOutputRate::Hz5, GpioOutputRate::Disabled),
+                tss1: rates(GpioOutputRate::Disabled, GpioOutputRate::Disabled, GpioOutputRate::Hz2),
+                simrad: rates(GpioOutputRate::Hz2, GpioOutputRate::Hz2, GpioOutputRate::Hz2),
+                gprot: rates(GpioOutputRate::Hz10, GpioOutputRate::Disabled, GpioOutputRate::Disabled),
+                gphev: rates(GpioOutputRate::Disabled, GpioOutputRate::Hz10, GpioOutputRate::Disabled),
+                gpgsv: rates(GpioOutputRate::Disabled, GpioOutputRate::Disabled, GpioOutputRate::Hz1),
+                pfecatt: rates(GpioOutputRate::Hz1, GpioOutputRate::Hz1, GpioOutputRate::Disabled),
+                pfechve: rates(GpioOutputRate::Disabled, GpioOutputRate::Disabled, GpioOutputRate::Disabled),
+            }),
+            // Each rate is a nibble per port: GPIO 1, GPIO 2, auxiliary
+            Fields::new().u8(1).u8(1)
+                .u16(0x0704).u16(0x0056).u16(0x0040).u16(0x0001).u16(0x0200).u16(0x0003).u16(0x0060)
+                .u16(0x0500).u16(0x0555).u16(0x0007).u16(0x0070).u16(0x0400).u16(0x0044).u16(0x0000)
+                .reserved(3),
+        ),
+        (
+            Packet::DualAntennaConfiguration(DualAntennaConfiguration {
+                permanent: true,
+                offset_type: OffsetType::Automatic,
+                automatic_offset_orientation: AutomaticOffsetOrientation::PrimaryRightSecondaryLeft,
+                manual_offset_x: 1.5,
+                manual_offset_y: -0.25,
+                manual_offset_z: 0.125,
+            }),
+            Fields::new().u8(1).u16(1).u8(2).reserved(1).f32s(&[1.5, -0.25, 0.125]),
+        ),
+        (
+            Packet::UserData(UserData { data: std::array::from_fn(|i| i as u8 * 3) }),
+            Fields::new().bytes(&(0..64).map(|i| i * 3).collect::<Vec<u8>>()),
+        ),
+        (
+            Packet::GpioInputConfiguration(GpioInputConfiguration { permanent: true, gimbal_radians_per_encoder_tick: 0.000_976_562_5 }),
+            Fields::new().u8(1).f32(0.000_976_562_5).reserved(60),
+        ),
+        (
+            Packet::IpDataportsConfiguration(IpDataportsConfiguration {
+                dataports: [
+                    IpDataport::new(IpDataportMode::TcpServer, SocketAddrV4::new(Ipv4Addr::UNSPECIFIED, 16_718)),
+                    IpDataport::new(IpDataportMode::UdpClient, SocketAddrV4::new(Ipv4Addr::new(192, 168, 1, 50), 16_719)),
+                    IpDataport::new(IpDataportMode::TcpClient, SocketAddrV4::new(Ipv4Addr::new(10, 0, 0, 2), 5_000)),
+                    IpDataport::DISABLED,
+                ],
+                extension: None,
+            }),
+            Fields::new().reserved(2)
+                .bytes(&[0, 0, 0, 0]).u16(16_718).u8(2)
+                .bytes(&[50, 1, 168, 192]).u16(16_719).u8(4)
+                .bytes(&[2, 0, 0, 10]).u16(5_000).u8(3)
+                .bytes(&[0, 0, 0, 0]).u16(0).u8(0),
+        ),
+        (
+            Packet::VesselMotionConfiguration(VesselMotionConfiguration {
+                permanent: true,
+                heave_points: HeavePointMask::from(0x05),
+                heave_filter_period: Duration::from_secs(15),
+            }),
+            Fields::new().u8(1).u8(0x05).reserved(2).f32(15.0).reserved(8),
+        ),
+    ]
+}
+
+fn vectors() -> Vec<(Packet, Fields)> {
+    system_vectors().into_iter().chain(state_vectors()).chain(configuration_vectors()).collect()
+}
+
+#[test]
+fn test_payloads_match_manual() {
+    for (packet, Fields(payload)) in vectors() {
+        let kind = PacketKind::from(packet.packet_id());
+        if let Some(len) = kind.byte_length() {
+            assert_eq!(payload.len(), len, "{:?} vector length", kind);
+        }
+        let decoded = wire::decode_payload(packet.packet_id(), &payload)
+            .unwrap_or_else(|err| panic!("{:?} vector does not decode: {}", kind, err));
+        assert_eq!(decoded, packet, "{:?} decodes to different fields", kind);
+        assert_eq!(wire::encode_payload(&packet).unwrap(), payload, "{:?} encodes out of order", kind);
+    }
+}
+
+#[test]
+fn test_vectors_cover_every_packet() {
+    let covered: HashSet<PacketKind> = vectors().iter().map(|(packet, _)| PacketKind::from(packet.packet_id())).collect();
+    let missing: Vec<PacketKind> = PacketKind::all().filter(|kind| !covered.contains(kind)).collect();
+    assert!(missing.is_empty(), "no conformance vector for {:?}", missing);
+}
+
+#[test]
+fn test_frames_match_manual() {
+    // LRC, packet ID, length, CRC16-CCITT low then high byte, payload
+    let frames: [(Packet, &[u8]); 3] = [
+        (
+            Packet::Request(Request::single(PacketKind::DeviceInformation)),
+            &[0x9A, 0x01, 0x01, 0x93, 0xD1, 0x03],
+        ),
+        (
+            Packet::Request(Request::multiple(vec![PacketKind::SystemState, PacketKind::RawSensors])),
+            &[0xF7, 0x01, 0x02, 0x05, 0x01, 0x14, 0x1C],
+        ),
+        (
+            Packet::UnixTime(UnixTime { unix_time_seconds: 1_700_000_000, microseconds: 250_000 }),
+            &[0xAF, 0x15, 0x08, 0x60, 0xD4, 0x00, 0xF1, 0x53, 0x65, 0x90, 0xD0, 0x03, 0x00],
+        ),
+    ];
+    for (packet, frame) in frames {
+        let payload = wire::encode_payload(&packet).unwrap();
+        assert_eq!(wire::encode_frame(PacketId::new(packet.packet_id()), &payload).unwrap(), frame, "{:?}", packet);
+        assert_eq!(AnppParser::new().consume(frame), Some(packet));
+    }
+}
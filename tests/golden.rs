@@ -0,0 +1,62 @@
+//! Recorded captures under tests/data decode to their expected packets and
+//! re-encode byte for byte.
+//!
+//! `every_packet` holds two instances of each packet type this crate
+//! decodes plus one unknown packet. `car_circle_odometer` is two seconds of
+//! a car driving a 50 m circle, streaming SystemState, RawSensors and
+//! Status, with an Odometer State after every 100 ms step.
+//!
+//! After adding a capture or changing how a packet decodes, regenerate the
+//! expected files with `LIBAN_BLESS=1 cargo test --features test-utils --test golden`.
+
+use std::collections::HashSet;
+
+use liban::test_support::{GoldenCapture, GoldenFailure, GoldenSet};
+use liban::wire::{self, PacketId};
+use liban::{Packet, PacketKind, UnixTime};
+
+const DATA_DIR: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/data");
+
+fn recorded() -> GoldenSet {
+    let mut set = GoldenSet::new();
+    set.add_dir(DATA_DIR).unwrap();
+    set
+}
+
+#[test]
+fn test_recorded_captures() {
+    recorded().assert_ok();
+}
+
+#[test]
+fn test_captures_cover_every_packet() {
+    let decoded: HashSet<PacketKind> = recorded().captures().iter()
+        .flat_map(GoldenCapture::decode)
+        .map(|packet| PacketKind::from(packet.packet_id()))
+        .collect();
+    let missing: Vec<PacketKind> = (0..=u8::MAX)
+        .map(PacketKind::from)
+        .filter(|kind| !decoded.contains(kind))
+        .collect();
+    assert!(missing.iter().all(|kind| *kind == PacketKind::Unsupported), "no capture has {:?}", missing);
+}
+
+#[test]
+fn test_downstream_capture_registration() {
+    let packets = [Packet::UnixTime(UnixTime { unix_time_seconds: 1_700_000_000, microseconds: 5 })];
+    let payload = wire::encode_payload(&packets[0]).unwrap();
+    let bytes = wire::encode_frame(PacketId::new(packets[0].packet_id()), &payload).unwrap();
+
+    let mut set = GoldenSet::new();
+    set.add(GoldenCapture::new("unix_time", bytes.clone(), &packets));
+    assert!(set.check().is_empty());
+
+    let mut corrupt = bytes.clone();
+    *corrupt.last_mut().unwrap() ^= 0xFF;
+    set.add(GoldenCapture::new("corrupt", corrupt, &packets));
+    set.add(GoldenCapture::new("wrong", bytes, &[]));
+    let failures = set.check();
+    assert_eq!(failures.len(), 2);
+    assert!(matches!(failures[0], (ref name, GoldenFailure::Corrupt { crc_errors: 1, .. }) if name == "corrupt"));
+    assert!(matches!(failures[1].1, GoldenFailure::Mismatch { line: 1, expected: None, .. }));
+}
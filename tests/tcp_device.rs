@@ -0,0 +1,207 @@
+//! End-to-end scenario: the real [`Client`] against [`MockBoreas`] on a
+//! localhost TCP port.
+//!
+//! The device thread feeds whatever arrives on the socket to the simulator
+//! and writes back its answers and due stream frames, so the whole
+//! request/acknowledge/stream stack runs through the public API: a
+//! handshake, a subscription via Packets Period, a SystemState stream, a
+//! rejected configuration write and a reconnect.
+
+use std::io::{ErrorKind, Read, Write};
+use std::net::{SocketAddr, TcpListener, TcpStream};
+use std::sync::mpsc::Receiver;
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+use liban::client::{Backoff, Client, ClientEvent, RetryPolicy};
+use liban::sim::{MockBoreas, Trajectory, DEFAULT_START_TIME};
+use liban::units::{Angle, Length};
+use liban::{
+    AcknowledgeResult, DeviceInformation, DeviceType, FilterOptions, Packet, PacketKind, PacketPeriod,
+    PacketsPeriod, SystemState, VehicleType,
+};
+
+const SERIAL: u32 = 424242;
+const POLL: Duration = Duration::from_millis(5);
+const TIMEOUT: Duration = Duration::from_secs(5);
+
+fn device() -> MockBoreas {
+    let mut device = MockBoreas::new(Trajectory::Static {
+        latitude: Angle::from_degrees(-33.77),
+        longitude: Angle::from_degrees(151.15),
+        height: Length::from_meters(12.0),
+    })
+    .with_device_information(DeviceInformation {
+        software_version: 7_000,
+        device_type: DeviceType::BoreasD90,
+        hardware_revision: 2,
+        serial_number_1: SERIAL,
+        serial_number_2: 0,
+        serial_number_3: 0,
+    });
+    device.reject(PacketKind::FilterOptions, AcknowledgeResult::ValuesOutOfRange);
+    device
+}
+
+/// Run the simulator on one connection until the host hangs up, advancing
+/// its clock in real time
+fn serve(device: &mut MockBoreas, stream: TcpStream) {
+    stream.set_read_timeout(Some(POLL)).unwrap();
+    let mut reader = stream.try_clone().unwrap();
+    let mut writer = stream;
+    let mut last = Instant::now();
+    let mut buf = [0u8; 1024];
+    loop {
+        let reply = match reader.read(&mut buf) {
+            Ok(0) => return,
+            Ok(n) => device.feed(&buf[..n]),
+            Err(e) if matches!(e.kind(), ErrorKind::WouldBlock | ErrorKind::TimedOut) => Vec::new(),
+            Err(_) => return,
+        };
+        let now = Instant::now();
+        let due = device.advance(now - last);
+        last = now;
+        if writer.write_all(&reply).and_then(|_| writer.write_all(&due)).is_err() {
+            return;
+        }
+    }
+}
+
+/// Serve `connections` hosts one after another, handing the simulator back
+/// at the end
+fn spawn_device(mut device: MockBoreas, connections: usize) -> (SocketAddr, JoinHandle<MockBoreas>) {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = listener.local_addr().unwrap();
+    let handle = thread::spawn(move || {
+        for stream in listener.incoming().take(connections) {
+            serve(&mut device, stream.unwrap());
+        }
+        device
+    });
+    (addr, handle)
+}
+
+/// The host side: the sans-io client and the socket it is told to use
+struct Host {
+    client: Client,
+    events: Receiver<ClientEvent>,
+    stream: Option<TcpStream>,
+    seen: Vec<ClientEvent>,
+    packets: Vec<Packet>,
+}
+
+impl Host {
+    fn new() -> Self {
+        let mut client = Client::new()
+            .with_retry(RetryPolicy { max_attempts: 2, timeout: Duration::from_millis(50) })
+            .with_backoff(Backoff { initial: Duration::from_millis(20), max: Duration::from_millis(100), multiplier: 2.0 });
+        let events = client.subscribe();
+        Self { client, events, stream: None, seen: Vec::new(), packets: Vec::new() }
+    }
+
+    /// Open the socket once the client asks for it
+    fn connect(&mut self, addr: SocketAddr) {
+        let deadline = Instant::now() + TIMEOUT;
+        while !self.client.poll_reconnect(Instant::now()) {
+            assert!(Instant::now() < deadline, "client never asked to reconnect");
+            thread::sleep(POLL);
+        }
+        let stream = TcpStream::connect(addr).unwrap();
+        stream.set_read_timeout(Some(POLL)).unwrap();
+        self.stream = Some(stream);
+        self.client.connected(Instant::now());
+    }
+
+    fn disconnect(&mut self) {
+        self.stream = None;
+        self.client.disconnected(Instant::now());
+    }
+
+    /// Pump bytes both ways until `done` holds
+    fn drive_until(&mut self, done: impl Fn(&Host) -> bool) {
+        let deadline = Instant::now() + TIMEOUT;
+        let mut buf = [0u8; 4096];
+        while !done(self) {
+            assert!(Instant::now() < deadline, "timed out; events so far {:?}", self.seen);
+            let stream = self.stream.as_mut().expect("connected");
+            let out = self.client.poll_transmit(Instant::now());
+            stream.write_all(&out).unwrap();
+            match stream.read(&mut buf) {
+                Ok(0) => panic!("device closed the connection"),
+                Ok(n) => {
+                    let packets = self.client.handle(&buf[..n], Instant::now());
+                    self.packets.extend(packets);
+                }
+                Err(e) if matches!(e.kind(), ErrorKind::WouldBlock | ErrorKind::TimedOut) => {}
+                Err(e) => panic!("read failed: {}", e),
+            }
+            self.seen.extend(self.events.try_iter());
+        }
+    }
+
+    fn states(&self) -> Vec<&SystemState> {
+        self.packets.iter().filter_map(|packet| match packet {
+            Packet::SystemState(state) => Some(state),
+            _ => None,
+        }).collect()
+    }
+}
+
+fn filter_options(vehicle_type: VehicleType) -> Packet {
+    Packet::FilterOptions(FilterOptions {
+        permanent: false,
+        vehicle_type,
+        internal_gnss_enabled: true,
+        atmospheric_altitude_enabled: false,
+        velocity_heading_enabled: false,
+        reversing_detection_enabled: false,
+        motion_analysis_enabled: false,
+    })
+}
+
+#[test]
+fn test_subscribe_stream_nack_and_reconnect() {
+    let (addr, device) = spawn_device(device(), 2);
+    let mut host = Host::new();
+    host.connect(addr);
+
+    // Identify the device
+    host.client.handshake();
+    host.drive_until(|host| host.seen.iter().any(|event| matches!(event, ClientEvent::Handshake(_))));
+    let capabilities = host.client.capabilities().unwrap();
+    assert_eq!(capabilities.device.device_type, DeviceType::BoreasD90);
+    assert!(host.packets.iter().any(|packet| matches!(packet, Packet::DeviceInformation(info) if info.serial_number_1 == SERIAL)));
+
+    // Subscribe and receive the stream
+    host.client.send(&Packet::PacketsPeriod(PacketsPeriod {
+        permanent: false,
+        clear_existing: true,
+        packet_periods: vec![PacketPeriod::from_packet::<SystemState>(10)],
+    })).unwrap();
+    host.drive_until(|host| host.states().len() >= 5);
+    let times: Vec<u64> = host.states().iter()
+        .map(|state| u64::from(state.unix_time_seconds) * 1_000_000 + u64::from(state.microseconds))
+        .collect();
+    assert!(times.windows(2).all(|pair| pair[0] < pair[1]), "{:?}", times);
+    assert!(times[0] >= u64::from(DEFAULT_START_TIME) * 1_000_000);
+    assert!(!host.seen.iter().any(|event| matches!(event, ClientEvent::WriteRejected(..))));
+
+    // A rejected configuration write comes back as a non-retryable NACK
+    host.client.send(&filter_options(VehicleType::Car)).unwrap();
+    host.drive_until(|host| host.client.pending() == 0
+        && host.seen.contains(&ClientEvent::WriteRejected(PacketKind::FilterOptions, AcknowledgeResult::ValuesOutOfRange)));
+    assert!(!AcknowledgeResult::ValuesOutOfRange.is_retryable());
+
+    // Drop the connection and come back; the device answers as before
+    host.disconnect();
+    assert!(host.events.try_iter().any(|event| event == ClientEvent::Disconnected));
+    host.connect(addr);
+    host.packets.clear();
+    host.client.request(PacketKind::DeviceInformation).unwrap();
+    host.drive_until(|host| host.packets.iter().any(|packet| matches!(packet, Packet::DeviceInformation(_))));
+    host.drive_until(|host| !host.states().is_empty());
+
+    drop(host);
+    let device = device.join().unwrap();
+    assert_eq!(device.streams().collect::<Vec<_>>(), vec![(PacketKind::SystemState, Duration::from_millis(10))]);
+}